@@ -1,74 +1,203 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::context::Context;
+use crate::{
+    context::Context,
+    page::Page,
+    param::{accept, LedgerVersionParam},
+};
 
-use diem_api_types::{Address, Error, LedgerInfo, MoveModule, MoveResource, Response};
-use diem_types::account_state::AccountState;
+use diem_api_types::{
+    AccountStatesPage, Address, ContentType, Error, LedgerInfo, MoveModule, MoveResource, Response,
+};
+use diem_types::{
+    access_path::AccessPath, account_state::AccountState, account_state_blob::AccountStateBlob,
+};
+use move_core_types::{
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag, TypeTag},
+    parser::parse_type_tag,
+};
 use resource_viewer::MoveValueAnnotator;
 
-use anyhow::Result;
+use anyhow::{format_err, Result};
+use percent_encoding::percent_decode_str;
+use serde_json::json;
 use std::convert::{TryFrom, TryInto};
 use warp::{Filter, Rejection, Reply};
 
 pub fn routes(context: Context) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    get_account_resources(context.clone()).or(get_account_modules(context))
+    get_accounts(context.clone())
+        .or(get_account_resources(context.clone()))
+        .or(get_account_modules(context.clone()))
+        .or(get_account_resource(context.clone()))
+        .or(get_account_module(context))
 }
 
-// GET /accounts/<address>/resources
+// GET /accounts?start=<hash>&limit=<u16>&ledger_version=<u64>
+pub fn get_accounts(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("accounts")
+        .and(warp::get())
+        .and(warp::query::<Page>())
+        .and(warp::query::<LedgerVersionParam>())
+        .and(accept())
+        .and(context.filter())
+        .and_then(handle_get_accounts)
+}
+
+// GET /accounts/<address>/resources?ledger_version=<u64>
 pub fn get_account_resources(
     context: Context,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("accounts" / String / "resources")
         .and(warp::get())
+        .and(warp::query::<LedgerVersionParam>())
+        .and(accept())
         .and(context.filter())
         .and_then(handle_get_account_resources)
 }
 
-// GET /accounts/<address>/modules
+// GET /accounts/<address>/modules?ledger_version=<u64>
 pub fn get_account_modules(
     context: Context,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("accounts" / String / "modules")
         .and(warp::get())
+        .and(warp::query::<LedgerVersionParam>())
+        .and(accept())
         .and(context.filter())
         .and_then(handle_get_account_modules)
 }
 
+// GET /accounts/<address>/resource/<struct_tag>?ledger_version=<u64>
+pub fn get_account_resource(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("accounts" / String / "resource" / String)
+        .and(warp::get())
+        .and(warp::query::<LedgerVersionParam>())
+        .and(accept())
+        .and(context.filter())
+        .and_then(handle_get_account_resource)
+}
+
+// GET /accounts/<address>/module/<module_name>?ledger_version=<u64>
+pub fn get_account_module(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("accounts" / String / "module" / String)
+        .and(warp::get())
+        .and(warp::query::<LedgerVersionParam>())
+        .and(accept())
+        .and(context.filter())
+        .and_then(handle_get_account_module)
+}
+
+async fn handle_get_accounts(
+    page: Page,
+    param: LedgerVersionParam,
+    content_type: ContentType,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    Ok(list_accounts(page, param, content_type, context)?)
+}
+
 async fn handle_get_account_resources(
     address: String,
+    param: LedgerVersionParam,
+    content_type: ContentType,
     context: Context,
 ) -> Result<impl Reply, Rejection> {
-    Ok(AccountResource::new(address, context)?.resources()?)
+    Ok(AccountResource::new(address, param, content_type, context)?.resources()?)
 }
 
 async fn handle_get_account_modules(
     address: String,
+    param: LedgerVersionParam,
+    content_type: ContentType,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    Ok(AccountResource::new(address, param, content_type, context)?.modules()?)
+}
+
+async fn handle_get_account_resource(
+    address: String,
+    struct_tag: String,
+    param: LedgerVersionParam,
+    content_type: ContentType,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    Ok(AccountResource::new(address, param, content_type, context)?.resource(struct_tag)?)
+}
+
+async fn handle_get_account_module(
+    address: String,
+    name: String,
+    param: LedgerVersionParam,
+    content_type: ContentType,
     context: Context,
 ) -> Result<impl Reply, Rejection> {
-    Ok(AccountResource::new(address, context)?.modules()?)
+    Ok(AccountResource::new(address, param, content_type, context)?.module(name)?)
+}
+
+fn list_accounts(
+    page: Page,
+    param: LedgerVersionParam,
+    content_type: ContentType,
+    context: Context,
+) -> Result<impl Reply, Error> {
+    let start = page.start_key()?;
+    let limit = page.limit()?;
+    let ledger_info = context.get_latest_ledger_info()?;
+    let ledger_version = context.get_ledger_version(param.ledger_version()?, &ledger_info)?;
+    let range = context
+        .db()
+        .get_account_states_range(ledger_version, start, limit as u64)?;
+    if content_type == ContentType::Bcs {
+        return Response::new_bcs(ledger_info, &range);
+    }
+    Response::new(ledger_info, &AccountStatesPage::try_from(range)?)
 }
 
 struct AccountResource {
     address: Address,
+    ledger_version: u64,
     ledger_info: LedgerInfo,
+    content_type: ContentType,
     context: Context,
 }
 
 impl AccountResource {
-    pub fn new(address: String, context: Context) -> Result<Self, Error> {
+    pub fn new(
+        address: String,
+        param: LedgerVersionParam,
+        content_type: ContentType,
+        context: Context,
+    ) -> Result<Self, Error> {
+        let address = address.try_into().map_err(Error::bad_request)?;
+        let ledger_info = context.get_latest_ledger_info()?;
+        let ledger_version = context.get_ledger_version(param.ledger_version()?, &ledger_info)?;
         Ok(Self {
-            address: address.try_into().map_err(Error::bad_request)?,
-            ledger_info: context.get_latest_ledger_info()?,
+            address,
+            ledger_version,
+            ledger_info,
+            content_type,
             context,
         })
     }
 
     pub fn resources(self) -> Result<impl Reply, Error> {
+        let blob = self.account_state_blob()?;
+        if self.content_type == ContentType::Bcs {
+            return Response::new_bcs(self.ledger_info, &blob);
+        }
+
         let db = self.context.db();
         let annotator = MoveValueAnnotator::new(&db);
         let mut resources = vec![];
-        for (typ, bytes) in self.account_state()?.get_resources() {
+        for (typ, bytes) in AccountState::try_from(&blob)?.get_resources() {
             let resource = annotator.view_resource(&typ, bytes)?;
             resources.push(MoveResource::from(resource));
         }
@@ -76,24 +205,98 @@ impl AccountResource {
     }
 
     pub fn modules(self) -> Result<impl Reply, Error> {
-        let modules: Vec<MoveModule> = self
-            .account_state()?
+        let blob = self.account_state_blob()?;
+        if self.content_type == ContentType::Bcs {
+            return Response::new_bcs(self.ledger_info, &blob);
+        }
+
+        let modules: Vec<MoveModule> = AccountState::try_from(&blob)?
             .get_modules()
             .map(MoveModule::try_from)
             .collect::<Result<Vec<MoveModule>, Error>>()?;
         Response::new(self.ledger_info, &modules)
     }
 
+    pub fn resource(self, struct_tag: String) -> Result<impl Reply, Error> {
+        let tag = parse_struct_tag(&struct_tag)?;
+        let state = self.account_state()?;
+        let bytes = state
+            .get(&AccessPath::resource_access_vec(tag.clone()))
+            .ok_or_else(|| self.not_found(format!("could not find resource by type: {}", tag)))?;
+        if self.content_type == ContentType::Bcs {
+            return Ok(Response::from_bcs_bytes(self.ledger_info, bytes.clone()));
+        }
+
+        let db = self.context.db();
+        let resource = MoveValueAnnotator::new(&db).view_resource(&tag, bytes)?;
+        Response::new(self.ledger_info, &MoveResource::from(resource))
+    }
+
+    pub fn module(self, name: String) -> Result<impl Reply, Error> {
+        let name = Identifier::new(name.as_str())
+            .map_err(|_| Error::bad_request(format_err!("invalid module name: {}", name)))?;
+        let module_id = ModuleId::new(self.address.into(), name);
+        let state = self.account_state()?;
+        let bytes = state
+            .get(&AccessPath::code_access_path(module_id.clone()).path)
+            .ok_or_else(|| {
+                self.not_found(format!(
+                    "could not find module by name: {}",
+                    module_id.name()
+                ))
+            })?;
+        if self.content_type == ContentType::Bcs {
+            return Ok(Response::from_bcs_bytes(self.ledger_info, bytes.clone()));
+        }
+        Response::new(self.ledger_info, &MoveModule::try_from(bytes)?)
+    }
+
     fn account_state(&self) -> Result<AccountState, Error> {
         self.context
-            .get_account_state(&self.address, self.ledger_info.version())
+            .get_account_state(&self.address, self.ledger_version)
+    }
+
+    fn account_state_blob(&self) -> Result<AccountStateBlob, Error> {
+        self.context
+            .get_existing_account_state_blob(&self.address, self.ledger_version)
+    }
+
+    fn not_found(&self, message: String) -> Error {
+        Error::not_found(
+            message,
+            json!({ "ledger_version": self.ledger_version.to_string() }),
+        )
+    }
+}
+
+pub fn parse_struct_tag(s: &str) -> Result<StructTag, Error> {
+    let decoded = percent_decode_str(s)
+        .decode_utf8()
+        .map_err(|_| Error::bad_request(format_err!("invalid struct tag: {}", s)))?;
+    match parse_type_tag(&decoded) {
+        Ok(TypeTag::Struct(tag)) => Ok(tag),
+        _ => Err(Error::bad_request(format_err!(
+            "invalid struct tag: {}",
+            decoded
+        ))),
     }
 }
 
 #[cfg(any(test))]
 mod tests {
-    use crate::test_utils::{assert_json, find_value, new_test_context, send_request};
+    use crate::{
+        accounts::parse_struct_tag,
+        test_utils::{assert_json, find_value, new_test_context, send_bcs_request, send_request},
+    };
+    use diem_types::{
+        access_path::AccessPath,
+        account_address::AccountAddress,
+        account_state::AccountState,
+        account_state_blob::{AccountStateBlob, AccountStatesRangeWithProof},
+    };
+    use move_core_types::{identifier::Identifier, language_storage::ModuleId};
     use serde_json::json;
+    use std::convert::TryFrom;
 
     #[tokio::test]
     async fn test_get_account_resources_returns_empty_array_for_account_has_no_resources() {
@@ -738,6 +941,225 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_get_account_resources_by_ledger_version() {
+        let context = new_test_context();
+        let latest = send_request(context.clone(), "GET", &account_resources("0xdd"), 200).await;
+        let path = format!("{}?ledger_version=0", account_resources("0xdd"));
+        let resp = send_request(context, "GET", &path, 200).await;
+        assert_json(resp, latest);
+    }
+
+    #[tokio::test]
+    async fn test_get_account_resources_by_ledger_version_is_too_large() {
+        let context = new_test_context();
+        let info = context.get_latest_ledger_info().unwrap();
+        let path = format!("{}?ledger_version=1000000", account_resources("0xdd"));
+        let resp = send_request(context, "GET", &path, 404).await;
+        assert_json(
+            resp,
+            json!({
+                "code": 404,
+                "message": "could not find ledger version: 1000000",
+                "data": {
+                    "ledger_version": info.ledger_version,
+                },
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_account_modules_by_invalid_ledger_version() {
+        let context = new_test_context();
+        let path = format!("{}?ledger_version=-1", account_modules("0x1"));
+        let resp = send_request(context, "GET", &path, 400).await;
+        assert_json(
+            resp,
+            json!({
+                "code": 400,
+                "message": "invalid parameter ledger_version: -1",
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_account_resource() {
+        let context = new_test_context();
+        let resources = send_request(context.clone(), "GET", &account_resources("0xdd"), 200).await;
+        let expected = find_value(&resources, |v| {
+            v["type"]["name"] == "Balance" && v["type"]["generic_type_params"][0]["name"] == "XDX"
+        });
+
+        let path = account_resource("0xdd", "0x1::DiemAccount::Balance%3C0x1::XDX::XDX%3E");
+        let resp = send_request(context, "GET", &path, 200).await;
+        assert_json(resp, expected);
+    }
+
+    #[tokio::test]
+    async fn test_get_account_resource_not_found() {
+        let context = new_test_context();
+        let info = context.get_latest_ledger_info().unwrap();
+        let path = account_resource("0xdd", "0x1::DiemAccount::NotExist");
+        let resp = send_request(context, "GET", &path, 404).await;
+        assert_json(
+            resp,
+            json!({
+                "code": 404,
+                "message": "could not find resource by type: 0x1::DiemAccount::NotExist",
+                "data": {
+                    "ledger_version": info.ledger_version,
+                },
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_account_resource_by_invalid_struct_tag() {
+        let context = new_test_context();
+        let resp = send_request(context, "GET", &account_resource("0xdd", "u64"), 400).await;
+        assert_json(
+            resp,
+            json!({
+                "code": 400,
+                "message": "invalid struct tag: u64",
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_account_module() {
+        let context = new_test_context();
+        let modules = send_request(context.clone(), "GET", &account_modules("0x1"), 200).await;
+        let expected = find_value(&modules, |v| v["name"] == "BCS");
+
+        let resp = send_request(context, "GET", &account_module("0x1", "BCS"), 200).await;
+        assert_json(resp, expected);
+    }
+
+    #[tokio::test]
+    async fn test_get_account_module_not_found() {
+        let context = new_test_context();
+        let resp = send_request(
+            context.clone(),
+            "GET",
+            &account_module("0x1", "NotExist"),
+            404,
+        )
+        .await;
+        assert_eq!("could not find module by name: NotExist", resp["message"]);
+
+        let resp = send_request(context, "GET", &account_module("0x1", "1invalid"), 400).await;
+        assert_eq!("invalid module name: 1invalid", resp["message"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_account_resources_bcs() {
+        let context = new_test_context();
+        let resp = send_bcs_request(context.clone(), &account_resources("0xdd"), 200).await;
+
+        let blob: AccountStateBlob = bcs::from_bytes(&resp).unwrap();
+        let version = context.get_latest_ledger_info().unwrap().version();
+        let expected = context
+            .get_account_state_blob(AccountAddress::from_hex_literal("0xdd").unwrap(), version)
+            .unwrap();
+        assert_eq!(expected, Some(blob));
+
+        let resp = send_bcs_request(context, &account_modules("0x1"), 200).await;
+        let state =
+            AccountState::try_from(&bcs::from_bytes::<AccountStateBlob>(&resp).unwrap()).unwrap();
+        assert!(state.get_modules().next().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_account_resource_and_module_bcs() {
+        let context = new_test_context();
+        let version = context.get_latest_ledger_info().unwrap().version();
+
+        let resp = send_bcs_request(
+            context.clone(),
+            &account_resource("0xdd", "0x1::DiemAccount::DiemAccount"),
+            200,
+        )
+        .await;
+        let state = context
+            .get_account_state(&"0xdd".parse().unwrap(), version)
+            .unwrap();
+        let tag = parse_struct_tag("0x1::DiemAccount::DiemAccount").unwrap();
+        assert_eq!(
+            state.get(&AccessPath::resource_access_vec(tag)),
+            Some(&resp)
+        );
+
+        let resp = send_bcs_request(context.clone(), &account_module("0x1", "BCS"), 200).await;
+        let state = context
+            .get_account_state(&"0x1".parse().unwrap(), version)
+            .unwrap();
+        let module_id = ModuleId::new(
+            AccountAddress::from_hex_literal("0x1").unwrap(),
+            Identifier::new("BCS").unwrap(),
+        );
+        assert_eq!(
+            state.get(&AccessPath::code_access_path(module_id).path),
+            Some(&resp)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_accounts_pages_through_all_accounts() {
+        let context = new_test_context();
+
+        let mut keys = vec![];
+        let mut addresses = vec![];
+        let mut path = "/accounts?limit=3".to_owned();
+        loop {
+            let resp = send_request(context.clone(), "GET", &path, 200).await;
+            let accounts = resp["accounts"].as_array().unwrap();
+            assert!(accounts.len() <= 3);
+            assert!(!accounts.is_empty());
+            assert!(resp["proof"].is_array());
+            for account in accounts {
+                keys.push(account["key"].as_str().unwrap().to_owned());
+                addresses.push(account["address"].as_str().unwrap().to_owned());
+            }
+            match resp["next_start"].as_str() {
+                Some(next) => path = format!("/accounts?limit=3&start={}", next),
+                None => break,
+            }
+        }
+
+        let mut sorted = keys.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(keys, sorted);
+        assert!(addresses.contains(&"0x1".to_owned()));
+        assert!(addresses.contains(&"0xdd".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn test_get_accounts_bcs() {
+        let context = new_test_context();
+
+        let resp = send_bcs_request(context, "/accounts?limit=2", 200).await;
+        let range: AccountStatesRangeWithProof = bcs::from_bytes(&resp).unwrap();
+        assert_eq!(range.account_blobs.len(), 2);
+        assert!(range.proof.is_some());
+        assert!(range.next_key.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_accounts_by_invalid_start() {
+        let context = new_test_context();
+
+        let resp = send_request(context, "GET", "/accounts?start=0xzz", 400).await;
+        assert_eq!(
+            json!({
+                "code": 400,
+                "message": "invalid parameter start: 0xzz",
+            }),
+            resp
+        );
+    }
+
     fn account_resources(address: &str) -> String {
         format!("/accounts/{}/resources", address)
     }
@@ -745,4 +1167,12 @@ mod tests {
     fn account_modules(address: &str) -> String {
         format!("/accounts/{}/modules", address)
     }
+
+    fn account_resource(address: &str, struct_tag: &str) -> String {
+        format!("/accounts/{}/resource/{}", address, struct_tag)
+    }
+
+    fn account_module(address: &str, name: &str) -> String {
+        format!("/accounts/{}/module/{}", address, name)
+    }
 }
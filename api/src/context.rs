@@ -1,14 +1,17 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_api_types::{Address, Error, LedgerInfo};
+use diem_api_types::{Address, Error, LedgerInfo, U64};
+use diem_mempool::{MempoolClientSender, SubmissionStatus};
 use diem_types::{
     account_address::AccountAddress, account_state::AccountState,
-    account_state_blob::AccountStateBlob, chain_id::ChainId, protocol_spec::DpnProto,
+    account_state_blob::AccountStateBlob, chain_id::ChainId, ledger_info::LedgerInfoWithSignatures,
+    protocol_spec::DpnProto, transaction::SignedTransaction,
 };
-use storage_interface::MoveDbReader;
+use storage_interface::{LedgerHistoryPruned, MoveDbReader};
 
 use anyhow::Result;
+use futures::{channel::oneshot, SinkExt};
 use serde_json::json;
 use std::{
     borrow::Borrow,
@@ -22,11 +25,20 @@ use warp::Filter;
 pub struct Context {
     chain_id: ChainId,
     db: Arc<dyn MoveDbReader<DpnProto>>,
+    mp_sender: MempoolClientSender,
 }
 
 impl Context {
-    pub fn new(chain_id: ChainId, db: Arc<dyn MoveDbReader<DpnProto>>) -> Self {
-        Self { chain_id, db }
+    pub fn new(
+        chain_id: ChainId,
+        db: Arc<dyn MoveDbReader<DpnProto>>,
+        mp_sender: MempoolClientSender,
+    ) -> Self {
+        Self {
+            chain_id,
+            db,
+            mp_sender,
+        }
     }
 
     pub fn db(&self) -> &dyn MoveDbReader<DpnProto> {
@@ -41,22 +53,108 @@ impl Context {
         warp::any().map(move || self.clone())
     }
 
+    pub async fn submit_transaction(&self, txn: SignedTransaction) -> Result<SubmissionStatus> {
+        let (req_sender, callback) = oneshot::channel();
+        self.mp_sender.clone().send((txn, req_sender)).await?;
+        callback.await?
+    }
+
     pub fn get_latest_ledger_info(&self) -> Result<LedgerInfo, Error> {
         Ok(LedgerInfo::new(
             self.chain_id(),
-            &self.db.get_latest_ledger_info()?,
+            &self.get_latest_ledger_info_with_signatures()?,
         ))
     }
 
+    pub fn get_latest_ledger_info_with_signatures(
+        &self,
+    ) -> Result<LedgerInfoWithSignatures, Error> {
+        Ok(self.db.get_latest_ledger_info()?)
+    }
+
+    /// Returns the ledger version to read account states at: the requested version if given,
+    /// otherwise the latest version. Versions after the latest version or whose states have been
+    /// pruned are rejected.
+    pub fn get_ledger_version(
+        &self,
+        requested: Option<u64>,
+        latest_ledger_info: &LedgerInfo,
+    ) -> Result<u64, Error> {
+        let version = match requested {
+            Some(version) => version,
+            None => return Ok(latest_ledger_info.version()),
+        };
+        if version > latest_ledger_info.version() {
+            return Err(Error::not_found(
+                format!("could not find ledger version: {}", version),
+                json!({ "ledger_version": latest_ledger_info.ledger_version }),
+            ));
+        }
+        let least_readable_version = self.db.get_least_readable_state_version()?;
+        if version < least_readable_version {
+            return Err(Error::gone(
+                format!("ledger version {} has been pruned", version),
+                json!({
+                    "ledger_version": latest_ledger_info.ledger_version,
+                    "least_readable_version": U64::from(least_readable_version),
+                }),
+            ));
+        }
+        Ok(version)
+    }
+
+    /// Rejects the transaction version if its ledger history has been pruned.
+    pub fn check_transaction_not_pruned(
+        &self,
+        version: u64,
+        latest_ledger_info: &LedgerInfo,
+    ) -> Result<(), Error> {
+        let first_available_version = self.db.get_first_available_ledger_version()?;
+        if version < first_available_version {
+            return Err(Error::gone(
+                format!("transaction version {} has been pruned", version),
+                json!({
+                    "ledger_version": latest_ledger_info.ledger_version,
+                    "first_available_version": U64::from(first_available_version),
+                }),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Converts an error reading the DB, which is a 410 if the ledger history read has been
+    /// pruned.
+    pub fn db_error(&self, error: anyhow::Error, latest_ledger_info: &LedgerInfo) -> Error {
+        match error.downcast_ref::<LedgerHistoryPruned>() {
+            Some(pruned) => Error::gone(
+                pruned.to_string(),
+                json!({
+                    "ledger_version": latest_ledger_info.ledger_version,
+                    "first_available_version": U64::from(pruned.first_available_version),
+                }),
+            ),
+            None => error.into(),
+        }
+    }
+
     pub fn get_account_state(
         &self,
         address: &Address,
         ledger_version: u64,
     ) -> Result<AccountState, Error> {
-        let state = self
-            .get_account_state_blob(address.into(), ledger_version)?
-            .ok_or_else(|| account_not_found(&address.to_string(), ledger_version))?;
-        Ok(AccountState::try_from(&state)?)
+        let blob = self.get_existing_account_state_blob(address, ledger_version)?;
+        Ok(AccountState::try_from(&blob)?)
+    }
+
+    /// Returns the state blob of the account, or a not found error if the account does not
+    /// exist at the ledger version.
+    pub fn get_existing_account_state_blob(
+        &self,
+        address: &Address,
+        ledger_version: u64,
+    ) -> Result<AccountStateBlob, Error> {
+        self.get_account_state_blob(address.into(), ledger_version)?
+            .ok_or_else(|| account_not_found(&address.to_string(), ledger_version))
     }
 
     pub fn get_account_state_blob(
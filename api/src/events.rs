@@ -0,0 +1,278 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{accounts::parse_struct_tag, context::Context, page::Page, param::accept};
+
+use diem_api_types::{Address, ContentType, Error, LedgerInfo, MoveConverter, Response};
+use diem_types::{access_path::AccessPath, event::EventKey};
+use move_core_types::{identifier::Identifier, language_storage::StructTag};
+use resource_viewer::{AnnotatedMoveValue, MoveValueAnnotator};
+use storage_interface::Order;
+
+use anyhow::{format_err, Result};
+use serde_json::json;
+use std::convert::TryInto;
+use warp::{Filter, Rejection, Reply};
+
+pub fn routes(context: Context) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    get_events(context.clone()).or(get_account_events(context))
+}
+
+// GET /events/<event_key>?start=<u64>&limit=<u16>
+pub fn get_events(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("events" / String)
+        .and(warp::get())
+        .and(warp::query::<Page>())
+        .and(accept())
+        .and(context.filter())
+        .and_then(handle_get_events)
+}
+
+// GET /accounts/<address>/events/<struct_tag>/<field_name>?start=<u64>&limit=<u16>
+pub fn get_account_events(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("accounts" / String / "events" / String / String)
+        .and(warp::get())
+        .and(warp::query::<Page>())
+        .and(accept())
+        .and(context.filter())
+        .and_then(handle_get_account_events)
+}
+
+async fn handle_get_events(
+    event_key: String,
+    page: Page,
+    content_type: ContentType,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let key = parse_event_key(&event_key)?;
+    Ok(Events::new(content_type, context)?.list(key, page)?)
+}
+
+async fn handle_get_account_events(
+    address: String,
+    struct_tag: String,
+    field_name: String,
+    page: Page,
+    content_type: ContentType,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let address = address.try_into().map_err(Error::bad_request)?;
+    let struct_tag = parse_struct_tag(&struct_tag)?;
+    let field_name = Identifier::new(field_name.as_str())
+        .map_err(|_| Error::bad_request(format_err!("invalid field name: {}", field_name)))?;
+
+    let events = Events::new(content_type, context)?;
+    let key = events.find_event_key(address, struct_tag, field_name)?;
+    Ok(events.list(key, page)?)
+}
+
+struct Events {
+    ledger_info: LedgerInfo,
+    content_type: ContentType,
+    context: Context,
+}
+
+impl Events {
+    pub fn new(content_type: ContentType, context: Context) -> Result<Self, Error> {
+        Ok(Self {
+            ledger_info: context.get_latest_ledger_info()?,
+            content_type,
+            context,
+        })
+    }
+
+    pub fn list(self, key: EventKey, page: Page) -> Result<impl Reply, Error> {
+        let ledger_version = self.ledger_info.version();
+        let events = self.context.db().get_events(
+            &key,
+            page.start(0)?,
+            Order::Ascending,
+            page.limit()? as u64,
+        )?;
+        // skip the events committed after the ledger info the response is rendered at
+        let events = events
+            .into_iter()
+            .filter(|(version, _event)| *version <= ledger_version)
+            .map(|(_version, event)| event)
+            .collect::<Vec<_>>();
+        if self.content_type == ContentType::Bcs {
+            return Response::new_bcs(self.ledger_info, &events);
+        }
+
+        let db = self.context.db();
+        let events = MoveConverter::new(&db).try_into_events(&events)?;
+        Response::new(self.ledger_info, &events)
+    }
+
+    /// Finds the key of the `EventHandle` stored in the field `field_name` of the resource
+    /// `struct_tag` under the account `address`.
+    pub fn find_event_key(
+        &self,
+        address: Address,
+        struct_tag: StructTag,
+        field_name: Identifier,
+    ) -> Result<EventKey, Error> {
+        let state = self
+            .context
+            .get_account_state(&address, self.ledger_info.version())?;
+        let bytes = state
+            .get(&AccessPath::resource_access_vec(struct_tag.clone()))
+            .ok_or_else(|| {
+                self.not_found(format!("could not find resource by type: {}", struct_tag))
+            })?;
+
+        let db = self.context.db();
+        let resource = MoveValueAnnotator::new(&db).view_resource(&struct_tag, bytes)?;
+        let (_, field) = resource
+            .value
+            .into_iter()
+            .find(|(name, _)| name == &field_name)
+            .ok_or_else(|| {
+                self.not_found(format!(
+                    "could not find field {} in resource {}",
+                    field_name, struct_tag
+                ))
+            })?;
+
+        match field {
+            AnnotatedMoveValue::Struct(handle) if is_event_handle(&handle.type_) => handle
+                .value
+                .into_iter()
+                .find_map(|(name, value)| match value {
+                    AnnotatedMoveValue::Bytes(guid) if name.as_str() == "guid" => Some(guid),
+                    _ => None,
+                })
+                .and_then(|guid| EventKey::from_bytes(guid).ok())
+                .ok_or_else(|| {
+                    Error::internal(format_err!("invalid event handle: {}", handle.type_))
+                }),
+            _ => Err(Error::bad_request(format_err!(
+                "field {} in resource {} is not an event handle",
+                field_name,
+                struct_tag
+            ))),
+        }
+    }
+
+    fn not_found(&self, message: String) -> Error {
+        Error::not_found(
+            message,
+            json!({ "ledger_version": self.ledger_info.ledger_version }),
+        )
+    }
+}
+
+fn is_event_handle(tag: &StructTag) -> bool {
+    tag.address == diem_types::account_config::CORE_CODE_ADDRESS
+        && tag.module.as_str() == "Event"
+        && tag.name.as_str() == "EventHandle"
+}
+
+fn parse_event_key(s: &str) -> Result<EventKey, Error> {
+    let hex = s.strip_prefix("0x").unwrap_or(s);
+    EventKey::from_hex(hex).map_err(|_| Error::bad_request(format_err!("invalid event key: {}", s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{assert_json, new_test_context, send_bcs_request, send_request};
+    use diem_types::contract_event::ContractEvent;
+
+    use serde_json::json;
+
+    const CREATION_EVENTS: &str =
+        "/accounts/0xa550c18/events/0x1::DiemAccount::AccountOperationsCapability/creation_events";
+
+    #[tokio::test]
+    async fn test_get_account_events() {
+        let context = new_test_context();
+        let resp = send_request(context, "GET", CREATION_EVENTS, 200).await;
+
+        let events = resp.as_array().unwrap();
+        assert!(!events.is_empty());
+        let event = &events[0];
+        assert_eq!("0", event["sequence_number"]);
+        assert_json(
+            event["type"].clone(),
+            json!({
+                "type": "struct",
+                "address": "0x1",
+                "module": "DiemAccount",
+                "name": "CreateAccountEvent",
+                "generic_type_params": []
+            }),
+        );
+        assert!(event["data"]["created"].is_string());
+        assert!(event["data"]["role_id"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_get_events_by_event_key() {
+        let context = new_test_context();
+        let path = format!("{}?limit=2", CREATION_EVENTS);
+        let expected = send_request(context.clone(), "GET", &path, 200).await;
+        let key = expected[0]["key"].as_str().unwrap();
+
+        let path = format!("/events/{}?limit=2", key);
+        let resp = send_request(context.clone(), "GET", &path, 200).await;
+        assert_json(resp, expected.clone());
+
+        let path = format!("/events/0x{}?start=1&limit=1", key);
+        let resp = send_request(context, "GET", &path, 200).await;
+        assert_json(resp, json!([expected[1]]));
+    }
+
+    #[tokio::test]
+    async fn test_get_events_bcs() {
+        let context = new_test_context();
+        let path = format!("{}?limit=2", CREATION_EVENTS);
+        let expected = send_request(context.clone(), "GET", &path, 200).await;
+
+        let resp = send_bcs_request(context, &path, 200).await;
+        let events: Vec<ContractEvent> = bcs::from_bytes(&resp).unwrap();
+        assert_eq!(2, events.len());
+        for (event, expected) in events.iter().zip(expected.as_array().unwrap()) {
+            assert_eq!(event.key().to_string(), expected["key"]);
+            assert_eq!(
+                event.sequence_number().to_string(),
+                expected["sequence_number"]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_events_by_invalid_event_key() {
+        let context = new_test_context();
+        let resp = send_request(context, "GET", "/events/0x1", 400).await;
+        assert_json(
+            resp,
+            json!({"code": 400, "message": "invalid event key: 0x1"}),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_account_events_by_field_is_not_event_handle() {
+        let context = new_test_context();
+        let path = "/accounts/0xa550c18/events/0x1::DiemAccount::DiemAccount/sequence_number";
+        let resp = send_request(context, "GET", path, 400).await;
+        assert_eq!(
+            "field sequence_number in resource 0x1::DiemAccount::DiemAccount is not an event handle",
+            resp["message"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_account_events_by_field_not_found() {
+        let context = new_test_context();
+        let path = "/accounts/0xa550c18/events/0x1::DiemAccount::DiemAccount/not_exist";
+        let resp = send_request(context, "GET", path, 404).await;
+        assert_eq!(
+            "could not find field not_exist in resource 0x1::DiemAccount::DiemAccount",
+            resp["message"]
+        );
+    }
+}
@@ -1,15 +1,18 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{accounts, context::Context};
-use diem_api_types::{Error, Response};
+use crate::{accounts, context::Context, events, openapi, param::accept, transactions};
+use diem_api_types::{ContentType, Error, LedgerInfo, Response};
 
 use std::convert::Infallible;
 use warp::{http::StatusCode, reply, Filter, Rejection, Reply};
 
 pub fn routes(context: Context) -> impl Filter<Extract = impl Reply, Error = Infallible> + Clone {
     index(context.clone())
-        .or(accounts::routes(context))
+        .or(accounts::routes(context.clone()))
+        .or(transactions::routes(context.clone()))
+        .or(events::routes(context))
+        .or(openapi::routes())
         .recover(handle_rejection)
 }
 
@@ -17,13 +20,22 @@ pub fn routes(context: Context) -> impl Filter<Extract = impl Reply, Error = Inf
 pub fn index(context: Context) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path::end()
         .and(warp::get())
+        .and(accept())
         .and(context.filter())
         .and_then(handle_index)
 }
 
-pub async fn handle_index(context: Context) -> Result<impl Reply, Rejection> {
-    let info = context.get_latest_ledger_info()?;
-    Ok(Response::new(info.clone(), &info)?)
+pub async fn handle_index(
+    content_type: ContentType,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let info = context.get_latest_ledger_info_with_signatures()?;
+    let ledger_info = LedgerInfo::new(context.chain_id(), &info);
+    let resp = match content_type {
+        ContentType::Json => Response::new(ledger_info.clone(), &ledger_info)?,
+        ContentType::Bcs => Response::new_bcs(ledger_info, &info)?,
+    };
+    Ok(resp)
 }
 
 async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
@@ -44,7 +56,8 @@ async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
 
 #[cfg(test)]
 mod test {
-    use crate::test_utils::{new_test_context, send_request};
+    use crate::test_utils::{new_test_context, send_bcs_request, send_request};
+    use diem_types::ledger_info::LedgerInfoWithSignatures;
     use serde_json::json;
 
     #[tokio::test]
@@ -62,6 +75,15 @@ mod test {
         assert_eq!(expected, resp);
     }
 
+    #[tokio::test]
+    async fn test_get_ledger_info_bcs() {
+        let context = new_test_context();
+        let resp = send_bcs_request(context.clone(), "/", 200).await;
+
+        let ledger_info: LedgerInfoWithSignatures = bcs::from_bytes(&resp).unwrap();
+        assert_eq!(context.db().get_latest_ledger_info().unwrap(), ledger_info);
+    }
+
     #[tokio::test]
     async fn test_returns_not_found_for_the_invalid_path() {
         let context = new_test_context();
@@ -3,8 +3,13 @@
 
 mod accounts;
 mod context;
+mod events;
 mod index;
+mod openapi;
+mod page;
+mod param;
 pub mod runtime;
+mod transactions;
 
 #[cfg(any(test))]
 pub(crate) mod test_utils;
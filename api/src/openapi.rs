@@ -0,0 +1,638 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! OpenAPI 3 document of the REST API.
+//!
+//! Operations are declared next to each other below, one per route; request and response body
+//! schemas come from the `ApiSchema` implementations of the `diem_api_types` rendered by the
+//! routes.
+
+use crate::transactions::BCS_SIGNED_TRANSACTION;
+
+use diem_api_types::{
+    AccountStatesPage, Error, Event, LedgerInfo, MoveModule, MoveResource, Schemas,
+    SubmitTransactionRequest, Transaction, APPLICATION_BCS, APPLICATION_JSON, X_DIEM_CHAIN_ID,
+    X_DIEM_LEDGER_TIMESTAMP, X_DIEM_LEDGER_VERSION,
+};
+
+use anyhow::format_err;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use warp::{
+    http::header::{HeaderValue, CONTENT_TYPE},
+    Filter, Rejection, Reply,
+};
+
+const SPEC_HTML: &str = include_str!("../doc/spec.html");
+
+pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    spec_yaml().or(spec_html())
+}
+
+// GET /spec.yaml
+pub fn spec_yaml() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("spec.yaml")
+        .and(warp::get())
+        .and_then(handle_spec_yaml)
+}
+
+// GET /spec.html
+pub fn spec_html() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("spec.html")
+        .and(warp::get())
+        .and_then(handle_spec_html)
+}
+
+async fn handle_spec_yaml() -> Result<impl Reply, Rejection> {
+    let yaml = serde_yaml::to_string(&document())
+        .map_err(|e| Error::internal(format_err!("failed to render OpenAPI spec: {}", e)))?;
+    let mut resp = warp::reply::Response::new(yaml.into());
+    resp.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/x-yaml"));
+    Ok(resp)
+}
+
+async fn handle_spec_html() -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::html(render_spec_html(&document())))
+}
+
+/// Renders the HTML document of the specification, the specification is embedded in the
+/// document, so that it renders without fetching anything.
+fn render_spec_html(document: &Value) -> String {
+    // escape `</` so that no string of the document can close the embedding `<script>`
+    let spec = document.to_string().replace("</", "<\\/");
+    SPEC_HTML.replace("{{spec}}", &spec)
+}
+
+/// Returns the OpenAPI 3 document of the API.
+pub fn document() -> Value {
+    let mut schemas = Schemas::new();
+    let paths = paths(&mut schemas);
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Diem REST API",
+            "description": "REST API for client applications to query the Diem blockchain. \
+                            Responses are JSON by default, and the BCS of the underlying \
+                            `diem_types` values when requested by `Accept: application/x-bcs`; \
+                            errors are always JSON.",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas.into_inner(),
+            "headers": {
+                X_DIEM_CHAIN_ID: header("Chain id of the current chain."),
+                X_DIEM_LEDGER_VERSION: header("Current ledger version of the chain."),
+                X_DIEM_LEDGER_TIMESTAMP: header(
+                    "Current ledger timestamp of the chain in microseconds."
+                ),
+            },
+        },
+    })
+}
+
+/// Returns the `paths` object of the document, registering the schemas used by the operations.
+fn paths(s: &mut Schemas) -> Value {
+    // every operation responds `Error` by default
+    s.of::<Error>();
+    let mut paths: BTreeMap<&str, Map<String, Value>> = BTreeMap::new();
+    for (method, path, operation) in operations(s) {
+        paths
+            .entry(path)
+            .or_default()
+            .insert(method.to_owned(), operation);
+    }
+    json!(paths)
+}
+
+/// All operations of the API as `(method, path, operation)`.
+fn operations(s: &mut Schemas) -> Vec<(&'static str, &'static str, Value)> {
+    vec![
+        (
+            "get",
+            "/",
+            operation(
+                "get_ledger_info",
+                "Get the latest ledger information",
+                "LedgerInfo",
+                vec![],
+                ok(s.of::<LedgerInfo>()),
+            ),
+        ),
+        (
+            "get",
+            "/accounts",
+            operation(
+                "get_accounts",
+                "Get a page of account states ordered by account key, with a range proof",
+                "Accounts",
+                vec![account_key_start(), limit(), ledger_version()],
+                ok(s.of::<AccountStatesPage>()),
+            ),
+        ),
+        (
+            "get",
+            "/accounts/{address}/resources",
+            operation(
+                "get_account_resources",
+                "Get all resources of an account",
+                "Accounts",
+                vec![address(), ledger_version()],
+                ok(s.array_of::<MoveResource>()),
+            ),
+        ),
+        (
+            "get",
+            "/accounts/{address}/modules",
+            operation(
+                "get_account_modules",
+                "Get all modules published by an account",
+                "Accounts",
+                vec![address(), ledger_version()],
+                ok(s.array_of::<MoveModule>()),
+            ),
+        ),
+        (
+            "get",
+            "/accounts/{address}/resource/{struct_tag}",
+            operation(
+                "get_account_resource",
+                "Get a resource of an account by the resource type",
+                "Accounts",
+                vec![address(), struct_tag(), ledger_version()],
+                ok(s.of::<MoveResource>()),
+            ),
+        ),
+        (
+            "get",
+            "/accounts/{address}/module/{module_name}",
+            operation(
+                "get_account_module",
+                "Get a module published by an account by the module name",
+                "Accounts",
+                vec![
+                    address(),
+                    path_param("module_name", "Name of the module."),
+                    ledger_version(),
+                ],
+                ok(s.of::<MoveModule>()),
+            ),
+        ),
+        (
+            "get",
+            "/accounts/{address}/transactions",
+            operation(
+                "get_account_transactions",
+                "Get transactions sent by an account",
+                "Transactions",
+                vec![address(), start(), limit()],
+                ok(s.array_of::<Transaction>()),
+            ),
+        ),
+        (
+            "get",
+            "/accounts/{address}/events/{struct_tag}/{field_name}",
+            operation(
+                "get_account_events",
+                "Get events by the event handle stored in a resource field of an account",
+                "Events",
+                vec![
+                    address(),
+                    struct_tag(),
+                    path_param(
+                        "field_name",
+                        "Name of the resource field that stores the event handle.",
+                    ),
+                    start(),
+                    limit(),
+                ],
+                ok(s.array_of::<Event>()),
+            ),
+        ),
+        (
+            "get",
+            "/events/{event_key}",
+            operation(
+                "get_events",
+                "Get events by the event key",
+                "Events",
+                vec![
+                    path_param("event_key", "Hex-encoded event key."),
+                    start(),
+                    limit(),
+                ],
+                ok(s.array_of::<Event>()),
+            ),
+        ),
+        (
+            "get",
+            "/transactions",
+            operation(
+                "get_transactions",
+                "Get on-chain transactions",
+                "Transactions",
+                vec![start(), limit()],
+                ok(s.array_of::<Transaction>()),
+            ),
+        ),
+        (
+            "post",
+            "/transactions",
+            with_request_body(
+                operation(
+                    "submit_transaction",
+                    "Submit a signed transaction to mempool",
+                    "Transactions",
+                    vec![],
+                    json!({ "202": response("Accepted", s.of::<Transaction>()) }),
+                ),
+                json!({
+                    "required": true,
+                    "content": {
+                        "application/json": { "schema": s.of::<SubmitTransactionRequest>() },
+                        BCS_SIGNED_TRANSACTION: {
+                            "schema": { "type": "string", "format": "binary" },
+                        },
+                    },
+                }),
+            ),
+        ),
+        (
+            "get",
+            "/transactions/{txn_hash_or_version}",
+            operation(
+                "get_transaction",
+                "Get a transaction by hash or version",
+                "Transactions",
+                vec![path_param(
+                    "txn_hash_or_version",
+                    "Hex-encoded transaction hash with `0x` prefix, or the transaction version.",
+                )],
+                ok(s.of::<Transaction>()),
+            ),
+        ),
+        (
+            "get",
+            "/spec.yaml",
+            json!({
+                "operationId": "get_spec_yaml",
+                "summary": "Get the OpenAPI specification of the API",
+                "tags": ["OpenAPI"],
+                "responses": {
+                    "200": {
+                        "description": "OK",
+                        "content": { "application/x-yaml": { "schema": { "type": "string" } } },
+                    },
+                },
+            }),
+        ),
+        (
+            "get",
+            "/spec.html",
+            json!({
+                "operationId": "get_spec_html",
+                "summary": "Get the HTML document rendering the OpenAPI specification",
+                "tags": ["OpenAPI"],
+                "responses": {
+                    "200": {
+                        "description": "OK",
+                        "content": { "text/html": { "schema": { "type": "string" } } },
+                    },
+                },
+            }),
+        ),
+    ]
+}
+
+/// An operation responding `responses`, and the `Error` for any other status code.
+fn operation(
+    id: &str,
+    summary: &str,
+    tag: &str,
+    parameters: Vec<Value>,
+    mut responses: Value,
+) -> Value {
+    responses["default"] = json!({
+        "description": "Error",
+        "content": {
+            "application/json": {
+                "schema": { "$ref": "#/components/schemas/Error" },
+            },
+        },
+    });
+    json!({
+        "operationId": id,
+        "summary": summary,
+        "tags": [tag],
+        "parameters": parameters,
+        "responses": responses,
+    })
+}
+
+fn with_request_body(mut operation: Value, body: Value) -> Value {
+    operation["requestBody"] = body;
+    operation
+}
+
+fn ok(schema: Value) -> Value {
+    json!({ "200": response("OK", schema) })
+}
+
+fn response(description: &str, schema: Value) -> Value {
+    let headers: Map<String, Value> = [
+        X_DIEM_CHAIN_ID,
+        X_DIEM_LEDGER_VERSION,
+        X_DIEM_LEDGER_TIMESTAMP,
+    ]
+    .iter()
+    .map(|name| {
+        (
+            (*name).to_owned(),
+            json!({ "$ref": format!("#/components/headers/{}", name) }),
+        )
+    })
+    .collect();
+    json!({
+        "description": description,
+        "headers": headers,
+        "content": {
+            APPLICATION_JSON: { "schema": schema },
+            // BCS of the `diem_types` value rendered by the JSON schema
+            APPLICATION_BCS: { "schema": { "type": "string", "format": "binary" } },
+        },
+    })
+}
+
+fn header(description: &str) -> Value {
+    json!({ "description": description, "schema": { "type": "string" } })
+}
+
+fn path_param(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+fn query_param(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": false,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+fn address() -> Value {
+    path_param(
+        "address",
+        "Hex-encoded account address with `0x` prefix and trimmed leading zeros.",
+    )
+}
+
+fn struct_tag() -> Value {
+    path_param(
+        "struct_tag",
+        "Move struct tag of the resource, e.g. `0x1::DiemAccount::DiemAccount`.",
+    )
+}
+
+fn ledger_version() -> Value {
+    query_param(
+        "ledger_version",
+        "Ledger version to read the account state at, defaults to the latest version.",
+    )
+}
+
+fn start() -> Value {
+    query_param(
+        "start",
+        "Version or sequence number to start the page from.",
+    )
+}
+
+fn account_key_start() -> Value {
+    query_param(
+        "start",
+        "Hex-encoded account key (hash of the account address) to start the page from, \
+         defaults to zero.",
+    )
+}
+
+fn limit() -> Value {
+    query_param("limit", "Page size, defaults to 25 and at most 1000.")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        index,
+        openapi::{document, paths},
+        test_utils::{new_test_context, pretty, send_request},
+    };
+    use diem_api_types::Schemas;
+
+    use serde_json::Value;
+    use warp::http::header::CONTENT_TYPE;
+
+    // Route sources scanned for `warp::path!` definitions.
+    const ROUTE_SOURCES: &[&str] = &[
+        include_str!("accounts.rs"),
+        include_str!("events.rs"),
+        include_str!("index.rs"),
+        include_str!("openapi.rs"),
+        include_str!("transactions.rs"),
+    ];
+
+    #[test]
+    fn test_every_route_has_an_operation() {
+        let spec = document();
+        let routes = routes_in_sources();
+        assert!(routes.len() > 10, "found too few routes: {:?}", routes);
+
+        for (method, segments) in routes {
+            let found = spec["paths"]
+                .as_object()
+                .unwrap()
+                .iter()
+                .any(|(path, item)| matches(path, &segments) && item.get(&method).is_some());
+            assert!(
+                found,
+                "route {} /{} has no operation in the OpenAPI spec",
+                method,
+                segments.join("/")
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_reference_resolves() {
+        let spec = document();
+        let mut references = vec![];
+        collect_references(&spec, &mut references);
+        assert!(!references.is_empty());
+
+        for reference in references {
+            let mut node = &spec;
+            for key in reference.trim_start_matches("#/").split('/') {
+                node = &node[key];
+            }
+            assert!(!node.is_null(), "unresolved reference {}", reference);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_responses_match_schemas() {
+        let context = new_test_context();
+        let mut schemas = Schemas::new();
+        let paths = paths(&mut schemas);
+
+        let creation_events =
+            "/accounts/0xa550c18/events/0x1::DiemAccount::AccountOperationsCapability/creation_events";
+        let events = send_request(context.clone(), "GET", creation_events, 200).await;
+        let event_key = events[0]["key"].as_str().unwrap().to_owned();
+
+        let requests = vec![
+            ("/", "/".to_owned()),
+            ("/accounts", "/accounts?limit=10".to_owned()),
+            (
+                "/accounts/{address}/resources",
+                "/accounts/0xa550c18/resources".to_owned(),
+            ),
+            (
+                "/accounts/{address}/modules",
+                "/accounts/0x1/modules".to_owned(),
+            ),
+            (
+                "/accounts/{address}/resource/{struct_tag}",
+                "/accounts/0xa550c18/resource/0x1::DiemAccount::DiemAccount".to_owned(),
+            ),
+            (
+                "/accounts/{address}/module/{module_name}",
+                "/accounts/0x1/module/DiemAccount".to_owned(),
+            ),
+            (
+                "/accounts/{address}/transactions",
+                "/accounts/0xa550c18/transactions".to_owned(),
+            ),
+            (
+                "/accounts/{address}/events/{struct_tag}/{field_name}",
+                creation_events.to_owned(),
+            ),
+            ("/events/{event_key}", format!("/events/{}", event_key)),
+            ("/transactions", "/transactions?limit=10".to_owned()),
+            (
+                "/transactions/{txn_hash_or_version}",
+                "/transactions/0".to_owned(),
+            ),
+        ];
+        for (template, path) in requests {
+            let resp = send_request(context.clone(), "GET", &path, 200).await;
+            let schema = &paths[template]["get"]["responses"]["200"]["content"]["application/json"]
+                ["schema"];
+            assert!(!schema.is_null(), "no response schema for {}", template);
+            if let Err(e) = schemas.validate(schema, &resp) {
+                panic!("GET {}: {}\nresponse: {}", path, e, pretty(&resp));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_spec_yaml() {
+        let context = new_test_context();
+        let resp = warp::test::request()
+            .method("GET")
+            .path("/spec.yaml")
+            .reply(&index::routes(context))
+            .await;
+        assert_eq!(200, resp.status());
+        assert_eq!("application/x-yaml", resp.headers()[CONTENT_TYPE]);
+
+        let spec: Value = serde_yaml::from_slice(resp.body()).unwrap();
+        assert_eq!(document(), spec);
+    }
+
+    #[tokio::test]
+    async fn test_get_spec_html() {
+        let context = new_test_context();
+        let resp = warp::test::request()
+            .method("GET")
+            .path("/spec.html")
+            .reply(&index::routes(context))
+            .await;
+        assert_eq!(200, resp.status());
+        let html = std::str::from_utf8(resp.body()).unwrap();
+        assert!(!html.contains("{{spec}}"));
+        assert!(!html.contains("<script src="));
+        assert!(html.contains("\"operationId\":\"get_spec_html\""));
+    }
+
+    #[test]
+    fn test_render_spec_html_escapes_closing_tags() {
+        let html = render_spec_html(&json!({ "description": "</script><script>" }));
+        assert!(html.contains(r#"{"description":"<\/script><script>"}"#));
+        assert_eq!(html.matches("</script>").count(), 2);
+    }
+
+    /// Finds the `warp::path!` routes and their methods defined in the route sources, a route is
+    /// returned as its method and path segments, and a path parameter segment is `String`.
+    fn routes_in_sources() -> Vec<(String, Vec<String>)> {
+        let mut routes = vec![];
+        for source in ROUTE_SOURCES {
+            // skip the tests module, so that the test code below is not a route
+            let source = source.split("#[cfg(test)]").next().unwrap();
+            for part in source.split("warp::path!(").skip(1) {
+                let (path, rest) = part.split_at(part.find(')').unwrap());
+                let segments = path
+                    .split('/')
+                    .map(|s| s.trim().trim_matches('"').to_owned())
+                    .collect();
+                let method = ["get", "post"]
+                    .iter()
+                    .find(|m| rest.contains(&format!("warp::{}()", m)))
+                    .expect("route method");
+                routes.push((method.to_string(), segments));
+            }
+            if source.contains("warp::path::end()") {
+                routes.push(("get".to_owned(), vec![]));
+            }
+        }
+        routes
+    }
+
+    fn collect_references(value: &Value, references: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    references.push(reference.clone());
+                }
+                map.values().for_each(|v| collect_references(v, references));
+            }
+            Value::Array(values) => values
+                .iter()
+                .for_each(|v| collect_references(v, references)),
+            _ => (),
+        }
+    }
+
+    fn matches(template: &str, segments: &[String]) -> bool {
+        let parts: Vec<&str> = template
+            .trim_start_matches('/')
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        parts.len() == segments.len()
+            && parts.iter().zip(segments).all(|(part, segment)| {
+                if segment == "String" {
+                    part.starts_with('{')
+                } else {
+                    part == segment
+                }
+            })
+    }
+}
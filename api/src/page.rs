@@ -0,0 +1,56 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_api_types::{Error, HashValue};
+
+use anyhow::format_err;
+use serde::Deserialize;
+use std::{num::NonZeroU16, str::FromStr};
+
+const DEFAULT_PAGE_SIZE: u16 = 25;
+const MAX_PAGE_SIZE: u16 = 1000;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Page {
+    start: Option<String>,
+    limit: Option<String>,
+}
+
+impl Page {
+    pub fn start(&self, default: u64) -> Result<u64, Error> {
+        match &self.start {
+            Some(start) => start
+                .parse::<u64>()
+                .map_err(|_| Error::bad_request(format_err!("invalid parameter start: {}", start))),
+            None => Ok(default),
+        }
+    }
+
+    /// Returns the hex-encoded account key to start the page from, defaults to zero.
+    pub fn start_key(&self) -> Result<diem_crypto::HashValue, Error> {
+        match &self.start {
+            Some(start) => HashValue::from_str(start)
+                .map(Into::into)
+                .map_err(|_| Error::bad_request(format_err!("invalid parameter start: {}", start))),
+            None => Ok(diem_crypto::HashValue::zero()),
+        }
+    }
+
+    pub fn limit(&self) -> Result<u16, Error> {
+        let limit = match &self.limit {
+            Some(limit) => limit
+                .parse::<NonZeroU16>()
+                .map_err(|_| Error::bad_request(format_err!("invalid parameter limit: {}", limit)))?
+                .get(),
+            None => DEFAULT_PAGE_SIZE,
+        };
+        if limit > MAX_PAGE_SIZE {
+            return Err(Error::bad_request(format_err!(
+                "invalid parameter limit: {}, exceed limit {}",
+                limit,
+                MAX_PAGE_SIZE
+            )));
+        }
+        Ok(limit)
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_api_types::{ContentType, Error};
+
+use anyhow::format_err;
+use serde::Deserialize;
+use warp::{http::header::ACCEPT, Filter, Rejection};
+
+/// Extracts the `ContentType` of the response body negotiated by the `Accept` header.
+pub fn accept() -> impl Filter<Extract = (ContentType,), Error = Rejection> + Clone {
+    warp::header::optional::<String>(ACCEPT.as_str())
+        .map(|accept: Option<String>| ContentType::from_accept(accept.as_deref()))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LedgerVersionParam {
+    ledger_version: Option<String>,
+}
+
+impl LedgerVersionParam {
+    pub fn ledger_version(&self) -> Result<Option<u64>, Error> {
+        self.ledger_version
+            .as_ref()
+            .map(|version| {
+                version.parse::<u64>().map_err(|_| {
+                    Error::bad_request(format_err!("invalid parameter ledger_version: {}", version))
+                })
+            })
+            .transpose()
+    }
+}
@@ -4,6 +4,7 @@
 use crate::{context::Context, index};
 
 use diem_config::config::ApiConfig;
+use diem_mempool::MempoolClientSender;
 use diem_types::{chain_id::ChainId, protocol_spec::DpnProto};
 use storage_interface::MoveDbReader;
 
@@ -15,6 +16,7 @@ use tokio::runtime::{Builder, Runtime};
 pub fn bootstrap(
     chain_id: ChainId,
     db: Arc<dyn MoveDbReader<DpnProto>>,
+    mp_sender: MempoolClientSender,
     config: &ApiConfig,
 ) -> Runtime {
     let runtime = Builder::new_multi_thread()
@@ -25,7 +27,7 @@ pub fn bootstrap(
 
     let address = config.address;
     runtime.spawn(async move {
-        let service = Context::new(chain_id, db);
+        let service = Context::new(chain_id, db, mp_sender);
         let routes = index::routes(service);
         let server = warp::serve(routes).bind(address);
         server.await
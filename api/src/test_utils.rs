@@ -2,17 +2,30 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{context::Context, index};
-use diem_api_types::{X_DIEM_CHAIN_ID, X_DIEM_LEDGER_TIMESTAMP, X_DIEM_LEDGER_VERSION};
+use diem_api_types::{
+    APPLICATION_BCS, X_DIEM_CHAIN_ID, X_DIEM_LEDGER_TIMESTAMP, X_DIEM_LEDGER_VERSION,
+};
 use diem_genesis_tool::validator_builder::ValidatorBuilder;
 use diem_temppath::TempPath;
-use diem_types::chain_id::ChainId;
+use diem_types::{
+    chain_id::ChainId,
+    mempool_status::{MempoolStatus, MempoolStatusCode},
+};
 use diem_vm::DiemVM;
 use diemdb::DiemDB;
 use executor::db_bootstrapper;
 use storage_interface::DbReaderWriter;
 
+use futures::{channel::mpsc, StreamExt};
 use serde_json::Value;
-use warp::http::header::CONTENT_TYPE;
+use std::sync::Arc;
+use warp::{
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        HeaderMap,
+    },
+    test::RequestBuilder,
+};
 
 pub fn new_test_context() -> Context {
     let tmp_dir = TempPath::new();
@@ -25,20 +38,78 @@ pub fn new_test_context() -> Context {
     );
     let (_root_keys, genesis, genesis_waypoint, _validators) = builder.build(rng).unwrap();
 
-    let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(&tmp_dir));
+    let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
     db_bootstrapper::maybe_bootstrap::<DiemVM>(&db_rw, &genesis, genesis_waypoint).unwrap();
 
-    Context::new(ChainId::test(), db)
+    new_test_context_with_db(db)
+}
+
+/// Creates a context serving `db`, with a mocked shared mempool.
+pub fn new_test_context_with_db(db: Arc<DiemDB>) -> Context {
+    let (mp_sender, mut mp_events) = mpsc::channel(1);
+    // mocks shared mempool: accepts every submitted transaction
+    tokio::spawn(async move {
+        while let Some((_txn, cb)) = mp_events.next().await {
+            let status = (MempoolStatus::new(MempoolStatusCode::Accepted), None);
+            cb.send(Ok(status)).unwrap();
+        }
+    });
+
+    Context::new(ChainId::test(), db, mp_sender)
 }
 
 pub async fn send_request(context: Context, method: &str, path: &str, status_code: u16) -> Value {
-    let routes = index::routes(context.clone());
+    let req = warp::test::request().method(method).path(path);
+    reply(context, req, status_code).await
+}
+
+pub async fn post_json(context: Context, path: &str, body: &Value, status_code: u16) -> Value {
+    let req = warp::test::request().method("POST").path(path).json(body);
+    reply(context, req, status_code).await
+}
 
-    let resp = warp::test::request()
-        .method(method)
+pub async fn post_bcs(
+    context: Context,
+    path: &str,
+    content_type: &str,
+    body: Vec<u8>,
+    status_code: u16,
+) -> Value {
+    let req = warp::test::request()
+        .method("POST")
         .path(path)
-        .reply(&routes)
-        .await;
+        .header(CONTENT_TYPE, content_type)
+        .body(body);
+    reply(context, req, status_code).await
+}
+
+/// Sends a GET request accepting BCS, and returns the BCS response body.
+pub async fn send_bcs_request(context: Context, path: &str, status_code: u16) -> Vec<u8> {
+    let req = warp::test::request().method("GET").path(path);
+    reply_bcs(context, req, status_code).await
+}
+
+/// Replies the request accepting BCS, and returns the BCS response body.
+pub async fn reply_bcs(context: Context, req: RequestBuilder, status_code: u16) -> Vec<u8> {
+    let routes = index::routes(context.clone());
+    let resp = req.header(ACCEPT, APPLICATION_BCS).reply(&routes).await;
+    assert_eq!(
+        status_code,
+        resp.status(),
+        "\nresponse: {}",
+        String::from_utf8_lossy(resp.body())
+    );
+
+    let headers = resp.headers();
+    assert_eq!(headers[CONTENT_TYPE], APPLICATION_BCS);
+    assert_ledger_info_headers(&context, headers);
+
+    resp.body().to_vec()
+}
+
+async fn reply(context: Context, req: RequestBuilder, status_code: u16) -> Value {
+    let routes = index::routes(context.clone());
+    let resp = req.reply(&routes).await;
 
     let headers = resp.headers();
     assert_eq!(headers[CONTENT_TYPE], "application/json");
@@ -47,21 +118,25 @@ pub async fn send_request(context: Context, method: &str, path: &str, status_cod
     assert_eq!(status_code, resp.status(), "\nresponse: {}", pretty(&body));
 
     if status_code < 300 {
-        let ledger_info = context.get_latest_ledger_info().unwrap();
-        assert_eq!(headers[X_DIEM_CHAIN_ID], "4");
-        assert_eq!(
-            headers[X_DIEM_LEDGER_VERSION],
-            ledger_info.version().to_string()
-        );
-        assert_eq!(
-            headers[X_DIEM_LEDGER_TIMESTAMP],
-            ledger_info.timestamp().to_string()
-        );
+        assert_ledger_info_headers(&context, headers);
     }
 
     body
 }
 
+fn assert_ledger_info_headers(context: &Context, headers: &HeaderMap) {
+    let ledger_info = context.get_latest_ledger_info().unwrap();
+    assert_eq!(headers[X_DIEM_CHAIN_ID], "4");
+    assert_eq!(
+        headers[X_DIEM_LEDGER_VERSION],
+        ledger_info.version().to_string()
+    );
+    assert_eq!(
+        headers[X_DIEM_LEDGER_TIMESTAMP],
+        ledger_info.timestamp().to_string()
+    );
+}
+
 pub fn find_value(val: &Value, filter: for<'r> fn(&'r &Value) -> bool) -> Value {
     let resources = val.as_array().expect("array");
     let mut balances = resources.iter().filter(filter);
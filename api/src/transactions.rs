@@ -0,0 +1,658 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{context::Context, page::Page, param::accept};
+
+use diem_api_types::{
+    Address, ContentType, Error, LedgerInfo, MoveConverter, Response, SubmitTransactionRequest,
+    TransactionId,
+};
+use diem_types::{
+    contract_event::ContractEvent,
+    mempool_status::MempoolStatusCode,
+    transaction::{SignedTransaction, Transaction, TransactionInfo, TransactionWithProof},
+};
+
+use anyhow::{format_err, Result};
+use hyper::body::Bytes;
+use std::convert::TryInto;
+use warp::{
+    http::{header::CONTENT_TYPE, StatusCode},
+    Filter, Rejection, Reply,
+};
+
+pub const BCS_SIGNED_TRANSACTION: &str = "application/x.diem.signed_transaction+bcs";
+
+// Upper bound of the request body size of POST /transactions.
+const MAX_SUBMIT_TRANSACTION_BYTES: u64 = 64 * 1024;
+
+pub fn routes(context: Context) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    get_transaction(context.clone())
+        .or(get_transactions(context.clone()))
+        .or(get_account_transactions(context.clone()))
+        .or(post_transactions(context))
+}
+
+// GET /transactions/{txn-hash / version}
+pub fn get_transaction(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("transactions" / String)
+        .and(warp::get())
+        .and(accept())
+        .and(context.filter())
+        .and_then(handle_get_transaction)
+}
+
+// GET /transactions?start={u64}&limit={u16}
+pub fn get_transactions(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("transactions")
+        .and(warp::get())
+        .and(warp::query::<Page>())
+        .and(accept())
+        .and(context.filter())
+        .and_then(handle_get_transactions)
+}
+
+// GET /accounts/{address}/transactions?start={u64}&limit={u16}
+pub fn get_account_transactions(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("accounts" / String / "transactions")
+        .and(warp::get())
+        .and(warp::query::<Page>())
+        .and(accept())
+        .and(context.filter())
+        .and_then(handle_get_account_transactions)
+}
+
+// POST /transactions
+pub fn post_transactions(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("transactions")
+        .and(warp::post())
+        .and(warp::header::optional::<String>(CONTENT_TYPE.as_str()))
+        .and(warp::body::content_length_limit(
+            MAX_SUBMIT_TRANSACTION_BYTES,
+        ))
+        .and(warp::body::bytes())
+        .and(accept())
+        .and(context.filter())
+        .and_then(handle_post_transactions)
+}
+
+async fn handle_get_transaction(
+    id: String,
+    accept: ContentType,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let id = id.parse().map_err(Error::bad_request)?;
+    Ok(Transactions::new(accept, context)?.get(id)?)
+}
+
+async fn handle_get_transactions(
+    page: Page,
+    accept: ContentType,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    Ok(Transactions::new(accept, context)?.list(page)?)
+}
+
+async fn handle_get_account_transactions(
+    address: String,
+    page: Page,
+    accept: ContentType,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let address = address.try_into().map_err(Error::bad_request)?;
+    Ok(Transactions::new(accept, context)?.list_by_account(address, page)?)
+}
+
+async fn handle_post_transactions(
+    content_type: Option<String>,
+    body: Bytes,
+    accept: ContentType,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let txns = Transactions::new(accept, context)?;
+    let txn = match content_type.as_deref() {
+        Some(BCS_SIGNED_TRANSACTION) => bcs::from_bytes(&body)
+            .map_err(|e| Error::bad_request(format_err!("invalid signed transaction: {}", e)))?,
+        _ => {
+            let request = serde_json::from_slice(&body)
+                .map_err(|e| Error::bad_request(format_err!("invalid request body: {}", e)))?;
+            txns.signed_transaction(request)?
+        }
+    };
+    Ok(txns.create(txn).await?)
+}
+
+struct Transactions {
+    ledger_info: LedgerInfo,
+    content_type: ContentType,
+    context: Context,
+}
+
+impl Transactions {
+    pub fn new(content_type: ContentType, context: Context) -> Result<Self, Error> {
+        Ok(Self {
+            ledger_info: context.get_latest_ledger_info()?,
+            content_type,
+            context,
+        })
+    }
+
+    pub fn get(self, id: TransactionId) -> Result<impl Reply, Error> {
+        let ledger_version = self.ledger_info.version();
+        let txn = match &id {
+            TransactionId::Hash(hash) => {
+                self.context
+                    .db()
+                    .get_transaction_by_hash((*hash).into(), ledger_version, true)?
+            }
+            TransactionId::Version(version) if *version <= ledger_version => {
+                self.context
+                    .check_transaction_not_pruned(*version, &self.ledger_info)?;
+                Some(self.context.db().get_transaction_by_version(
+                    *version,
+                    ledger_version,
+                    true,
+                )?)
+            }
+            TransactionId::Version(_) => None,
+        }
+        .ok_or_else(|| self.transaction_not_found(&id))?;
+        if self.content_type == ContentType::Bcs {
+            return Response::new_bcs(self.ledger_info, &txn);
+        }
+
+        let mut txns = self.render_transactions(vec![txn])?;
+        Response::new(self.ledger_info, &txns.remove(0))
+    }
+
+    pub fn list(self, page: Page) -> Result<impl Reply, Error> {
+        let ledger_version = self.ledger_info.version();
+        let start = page.start(0)?;
+        let limit = page.limit()?;
+        if start <= ledger_version {
+            self.context
+                .check_transaction_not_pruned(start, &self.ledger_info)?;
+        }
+        let data = self
+            .context
+            .db()
+            .get_transactions(start, limit as u64, ledger_version, true)?;
+        if self.content_type == ContentType::Bcs {
+            return Response::new_bcs(self.ledger_info, &data);
+        }
+
+        let first_version = data.first_transaction_version.unwrap_or(start);
+        let mut events = data.events.unwrap_or_default().into_iter();
+        let txns = data
+            .transactions
+            .into_iter()
+            .zip(data.proof.transaction_infos)
+            .enumerate()
+            .map(|(i, (txn, info))| {
+                let version = first_version + i as u64;
+                self.render_transaction(version, txn, &info, events.next().unwrap_or_default())
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Response::new(self.ledger_info, &txns)
+    }
+
+    pub fn list_by_account(self, address: Address, page: Page) -> Result<impl Reply, Error> {
+        let ledger_version = self.ledger_info.version();
+        let start = page.start(0)?;
+        let limit = page.limit()?;
+        let data = self
+            .context
+            .db()
+            .get_account_transactions(address.into(), start, limit as u64, true, ledger_version)
+            .map_err(|e| self.context.db_error(e, &self.ledger_info))?;
+        if self.content_type == ContentType::Bcs {
+            return Response::new_bcs(self.ledger_info, &data);
+        }
+        let txns = self.render_transactions(data.into_inner())?;
+        Response::new(self.ledger_info, &txns)
+    }
+
+    pub async fn create(self, txn: SignedTransaction) -> Result<impl Reply, Error> {
+        let (mempool_status, vm_status_opt) = self.context.submit_transaction(txn.clone()).await?;
+        if let Some(vm_status) = vm_status_opt {
+            return Err(Error::bad_request(format_err!(
+                "transaction is rejected by VM validation: {:?}",
+                vm_status
+            )));
+        }
+        if mempool_status.code != MempoolStatusCode::Accepted {
+            return Err(Error::bad_request(format_err!(
+                "transaction is rejected by mempool: {}, {}",
+                mempool_status.code,
+                mempool_status.message
+            )));
+        }
+
+        let resp = match self.content_type {
+            ContentType::Json => {
+                let db = self.context.db();
+                let pending_txn = MoveConverter::new(&db).try_into_pending_transaction(txn)?;
+                Response::new(self.ledger_info, &pending_txn)?
+            }
+            ContentType::Bcs => Response::new_bcs(self.ledger_info, &txn)?,
+        };
+        Ok(warp::reply::with_status(resp, StatusCode::ACCEPTED))
+    }
+
+    fn signed_transaction(
+        &self,
+        request: SubmitTransactionRequest,
+    ) -> Result<SignedTransaction, Error> {
+        let db = self.context.db();
+        MoveConverter::new(&db)
+            .try_into_signed_transaction(request, *self.context.chain_id())
+            .map_err(Error::bad_request)
+    }
+
+    fn render_transactions(
+        &self,
+        data: Vec<TransactionWithProof<TransactionInfo>>,
+    ) -> Result<Vec<diem_api_types::Transaction>, Error> {
+        data.into_iter()
+            .map(|txn| {
+                self.render_transaction(
+                    txn.version,
+                    txn.transaction,
+                    txn.proof.transaction_info(),
+                    txn.events.unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    fn render_transaction(
+        &self,
+        version: u64,
+        txn: Transaction,
+        info: &TransactionInfo,
+        events: Vec<ContractEvent>,
+    ) -> Result<diem_api_types::Transaction, Error> {
+        let db = self.context.db();
+        let timestamp = db.get_block_timestamp(version)?;
+        Ok(MoveConverter::new(&db).try_into_transaction(version, txn, info, events, timestamp)?)
+    }
+
+    fn transaction_not_found(&self, id: &TransactionId) -> Error {
+        Error::not_found(
+            format!("could not find transaction by {}", id),
+            serde_json::json!({ "ledger_version": self.ledger_info.ledger_version }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{
+            assert_json, new_test_context, new_test_context_with_db, post_bcs, post_json,
+            reply_bcs, send_bcs_request, send_request,
+        },
+        transactions::BCS_SIGNED_TRANSACTION,
+    };
+    use diem_api_types::{Address, HashValue};
+    use diem_config::config::SecondaryIndexesConfig;
+    use diem_crypto::{
+        ed25519::Ed25519PrivateKey, hash::CryptoHash, PrivateKey, SigningKey, Uniform,
+    };
+    use diem_proptest_helpers::ValueGenerator;
+    use diem_transaction_builder::stdlib::encode_peer_to_peer_with_metadata_script_function;
+    use diem_types::{
+        account_address::AccountAddress,
+        account_config::xus_tag,
+        chain_id::ChainId,
+        transaction::{
+            authenticator::TransactionAuthenticator, RawTransaction, SignedTransaction,
+            Transaction, TransactionInfo, TransactionInfoTrait, TransactionListWithProof,
+            TransactionWithProof,
+        },
+    };
+
+    use diemdb::{test_helper::arb_blocks_to_commit, DiemDB};
+    use storage_interface::DbWriter;
+
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_get_transactions() {
+        let context = new_test_context();
+        let resp = send_request(context, "GET", "/transactions", 200).await;
+        let txns = resp.as_array().unwrap();
+        assert_eq!(1, txns.len());
+        assert_eq!("genesis_transaction", txns[0]["type"]);
+        assert_eq!("0", txns[0]["version"]);
+        assert!(txns[0]["success"].as_bool().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_transactions_with_start_version_is_too_large() {
+        let context = new_test_context();
+        let resp = send_request(context, "GET", "/transactions?start=1000", 200).await;
+        assert_json(resp, json!([]))
+    }
+
+    #[tokio::test]
+    async fn test_get_transactions_with_invalid_limit() {
+        let context = new_test_context();
+        for limit in &["0", "-1", "1001", "invalid"] {
+            let path = format!("/transactions?limit={}", limit);
+            let resp = send_request(context.clone(), "GET", &path, 400).await;
+            assert!(
+                resp["message"]
+                    .as_str()
+                    .unwrap()
+                    .starts_with(&format!("invalid parameter limit: {}", limit)),
+                "{}",
+                resp
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_by_hash_and_version() {
+        let context = new_test_context();
+        let ledger_version = context.get_latest_ledger_info().unwrap().version();
+        let genesis = context
+            .db()
+            .get_transaction_by_version(0, ledger_version, false)
+            .unwrap();
+        let hash = HashValue::from(genesis.proof.transaction_info().transaction_hash());
+
+        let by_version = send_request(context.clone(), "GET", "/transactions/0", 200).await;
+        let by_hash = send_request(context, "GET", &format!("/transactions/{}", hash), 200).await;
+
+        assert_eq!(hash.to_string(), by_version["hash"]);
+        assert_json(by_hash, by_version);
+    }
+
+    #[tokio::test]
+    async fn test_get_transactions_bcs() {
+        let context = new_test_context();
+        let ledger_version = context.get_latest_ledger_info().unwrap().version();
+
+        let resp = send_bcs_request(context.clone(), "/transactions/0", 200).await;
+        let txn: TransactionWithProof<TransactionInfo> = bcs::from_bytes(&resp).unwrap();
+        let expected = context
+            .db()
+            .get_transaction_by_version(0, ledger_version, true)
+            .unwrap();
+        assert_eq!(expected, txn);
+
+        let resp = send_bcs_request(context.clone(), "/transactions?limit=10", 200).await;
+        let txns: TransactionListWithProof<TransactionInfo> = bcs::from_bytes(&resp).unwrap();
+        let expected = context
+            .db()
+            .get_transactions(0, 10, ledger_version, true)
+            .unwrap();
+        assert_eq!(expected, txns);
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_by_version_not_found() {
+        let context = new_test_context();
+        let info = context.get_latest_ledger_info().unwrap();
+        let resp = send_request(context, "GET", "/transactions/1000", 404).await;
+        assert_json(
+            resp,
+            json!({
+                "code": 404,
+                "message": "could not find transaction by version 1000",
+                "data": {
+                    "ledger_version": info.ledger_version,
+                },
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_by_hash_not_found() {
+        let context = new_test_context();
+        let hash = "0xdadfeddcca7cb6396c735e9bfd8b0e56a3e1d7ea57f5c8b6a3da38c2d6cc8d1b";
+        let resp = send_request(context, "GET", &format!("/transactions/{}", hash), 404).await;
+        assert_eq!(
+            format!("could not find transaction by hash {}", hash),
+            resp["message"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_by_invalid_id() {
+        let context = new_test_context();
+        let resp = send_request(context.clone(), "GET", "/transactions/0xzz", 400).await;
+        assert_json(
+            resp,
+            json!({"code": 400, "message": "invalid transaction hash: 0xzz"}),
+        );
+
+        let resp = send_request(context, "GET", "/transactions/invalid", 400).await;
+        assert_json(
+            resp,
+            json!({"code": 400, "message": "invalid transaction version: invalid"}),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_account_transactions_returns_empty_array_for_account_has_no_transactions() {
+        let context = new_test_context();
+        let resp = send_request(context, "GET", "/accounts/0xdd/transactions", 200).await;
+        assert_json(resp, json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_get_account_transactions_pruned() {
+        let mut gen = ValueGenerator::new();
+        // at least two transactions, so that the first one gets pruned
+        let blocks = loop {
+            let blocks = gen.generate(arb_blocks_to_commit());
+            if blocks.iter().map(|(txns, _)| txns.len()).sum::<usize>() > 1 {
+                break blocks;
+            }
+        };
+        let db = DiemDB::open_in_memory(
+            None,    /* prune_window */
+            Some(0), /* ledger_prune_window */
+            SecondaryIndexesConfig::default(),
+        )
+        .unwrap();
+        let mut next_version = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in &blocks {
+            db.save_transactions(txns_to_commit, next_version, Some(ledger_info_with_sigs))
+                .unwrap();
+            next_version += txns_to_commit.len() as u64;
+        }
+        let sender = blocks[0].0[0]
+            .transaction()
+            .as_signed_user_txn()
+            .unwrap()
+            .sender();
+
+        let context = new_test_context_with_db(Arc::new(db));
+        let path = format!("/accounts/{}/transactions", Address::from(sender));
+        let resp = send_request(context, "GET", &path, 410).await;
+        let latest_version = (next_version - 1).to_string();
+        assert_eq!(resp["data"]["ledger_version"], latest_version);
+        assert_eq!(resp["data"]["first_available_version"], latest_version);
+    }
+
+    #[tokio::test]
+    async fn test_post_bcs_format_transaction() {
+        let context = new_test_context();
+        let txn = test_signed_transaction(*context.chain_id());
+        let body = bcs::to_bytes(&txn).unwrap();
+        let resp = post_bcs(context, "/transactions", BCS_SIGNED_TRANSACTION, body, 202).await;
+
+        assert_eq!("pending_transaction", resp["type"]);
+        assert_eq!(transaction_hash(txn), resp["hash"]);
+        assert_eq!("0", resp["sequence_number"]);
+        assert_eq!(
+            "peer_to_peer_with_metadata",
+            resp["payload"]["function"]["name"]
+        );
+        assert_json(
+            resp["payload"]["arguments"].clone(),
+            json!(["0xdd", "100", "0x", "0x"]),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_post_transaction_accepts_bcs() {
+        let context = new_test_context();
+        let txn = test_signed_transaction(*context.chain_id());
+        let req = warp::test::request()
+            .method("POST")
+            .path("/transactions")
+            .header("content-type", BCS_SIGNED_TRANSACTION)
+            .body(bcs::to_bytes(&txn).unwrap());
+        let resp = reply_bcs(context, req, 202).await;
+
+        let pending: SignedTransaction = bcs::from_bytes(&resp).unwrap();
+        assert_eq!(txn, pending);
+    }
+
+    #[tokio::test]
+    async fn test_post_invalid_bcs_format_transaction() {
+        let context = new_test_context();
+        let resp = post_bcs(
+            context,
+            "/transactions",
+            BCS_SIGNED_TRANSACTION,
+            bcs::to_bytes("invalid data").unwrap(),
+            400,
+        )
+        .await;
+        assert!(resp["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("invalid signed transaction:"));
+    }
+
+    #[tokio::test]
+    async fn test_post_json_format_transaction() {
+        let context = new_test_context();
+        let txn = test_signed_transaction(*context.chain_id());
+        let (public_key, signature) = match txn.authenticator() {
+            TransactionAuthenticator::Ed25519 {
+                public_key,
+                signature,
+            } => (public_key.unvalidated().to_bytes(), signature.to_bytes()),
+            _ => panic!("unexpected authenticator"),
+        };
+        let body = json!({
+            "sender": txn.sender().to_hex_literal(),
+            "sequence_number": "0",
+            "max_gas_amount": "1000000",
+            "gas_unit_price": "0",
+            "gas_currency_code": "XUS",
+            "expiration_timestamp_secs": txn.expiration_timestamp_secs().to_string(),
+            "payload": {
+                "type": "script_function_payload",
+                "function": {
+                    "module": {
+                        "address": "0x1",
+                        "name": "PaymentScripts"
+                    },
+                    "name": "peer_to_peer_with_metadata"
+                },
+                "type_arguments": [
+                    {
+                        "type": "struct",
+                        "address": "0x1",
+                        "module": "XUS",
+                        "name": "XUS",
+                        "generic_type_params": []
+                    }
+                ],
+                "arguments": ["0xdd", "100", "0x", "0x"]
+            },
+            "signature": {
+                "type": "ed25519_signature",
+                "public_key": format!("0x{}", hex::encode(public_key)),
+                "signature": format!("0x{}", hex::encode(signature)),
+            }
+        });
+
+        let resp = post_json(context, "/transactions", &body, 202).await;
+        assert_eq!("pending_transaction", resp["type"]);
+        assert_eq!(transaction_hash(txn), resp["hash"]);
+    }
+
+    #[tokio::test]
+    async fn test_post_json_format_transaction_with_invalid_arguments() {
+        let context = new_test_context();
+        let txn = test_signed_transaction(*context.chain_id());
+        let mut body = json!({
+            "sender": txn.sender().to_hex_literal(),
+            "sequence_number": "0",
+            "max_gas_amount": "1000000",
+            "gas_unit_price": "0",
+            "gas_currency_code": "XUS",
+            "expiration_timestamp_secs": "0",
+            "payload": {
+                "type": "script_function_payload",
+                "function": {
+                    "module": {
+                        "address": "0x1",
+                        "name": "PaymentScripts"
+                    },
+                    "name": "peer_to_peer_with_metadata"
+                },
+                "type_arguments": [],
+                "arguments": ["0xdd", "100"]
+            },
+            "signature": {
+                "type": "ed25519_signature",
+                "public_key": "0x00",
+                "signature": "0x00",
+            }
+        });
+
+        let resp = post_json(context.clone(), "/transactions", &body, 400).await;
+        assert_eq!(
+            "expected 4 arguments for script function peer_to_peer_with_metadata, but got 2",
+            resp["message"]
+        );
+
+        body["payload"]["arguments"] = json!(["0xdd", "invalid", "0x", "0x"]);
+        post_json(context, "/transactions", &body, 400).await;
+    }
+
+    fn test_signed_transaction(chain_id: ChainId) -> SignedTransaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let public_key = private_key.public_key();
+        let raw_txn = RawTransaction::new(
+            AccountAddress::from_hex_literal("0xdd").unwrap(),
+            0,
+            encode_peer_to_peer_with_metadata_script_function(
+                xus_tag(),
+                AccountAddress::from_hex_literal("0xdd").unwrap(),
+                100,
+                vec![],
+                vec![],
+            ),
+            1_000_000,
+            0,
+            "XUS".to_owned(),
+            1_000_000_000,
+            chain_id,
+        );
+        let signature = private_key.sign(&raw_txn);
+        SignedTransaction::new(raw_txn, public_key, signature)
+    }
+
+    fn transaction_hash(txn: SignedTransaction) -> String {
+        HashValue::from(Transaction::UserTransaction(txn).hash()).to_string()
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Address, HashValue, HexEncodedBytes};
+
+use diem_types::{account_state::AccountState, account_state_blob::AccountStatesRangeWithProof};
+
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+/// A page of account states ordered by account key, the hash of the account address.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct AccountStatesPage {
+    pub accounts: Vec<AccountStateEntry>,
+    /// Right siblings of the range proof of the last account in the page, from the bottom of the
+    /// tree up; absent when the page is empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<Vec<HashValue>>,
+    /// Account key to start the next page from; absent on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_start: Option<HashValue>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct AccountStateEntry {
+    pub key: HashValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>,
+    /// BCS of the account state blob.
+    pub state: HexEncodedBytes,
+}
+
+impl TryFrom<AccountStatesRangeWithProof> for AccountStatesPage {
+    type Error = anyhow::Error;
+
+    fn try_from(range: AccountStatesRangeWithProof) -> anyhow::Result<Self> {
+        let accounts = range
+            .account_blobs
+            .into_iter()
+            .map(|(key, blob)| {
+                let address = AccountState::try_from(&blob)?
+                    .get_account_address()?
+                    .map(Address::from);
+                Ok(AccountStateEntry {
+                    key: key.into(),
+                    address,
+                    state: Vec::<u8>::from(blob).into(),
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            accounts,
+            proof: range.proof.map(|proof| {
+                proof
+                    .right_siblings()
+                    .iter()
+                    .map(|hash| (*hash).into())
+                    .collect()
+            }),
+            next_start: range.next_key.map(HashValue::from),
+        })
+    }
+}
@@ -0,0 +1,521 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    transaction::{
+        BlockMetadataTransaction, Event, GenesisTransaction, ModulePayload, PendingTransaction,
+        ScriptFunctionId, ScriptFunctionPayload, ScriptPayload, SubmitTransactionRequest,
+        Transaction, TransactionInfo, TransactionPayload, UserTransaction, UserTransactionRequest,
+        WriteSetChange, WriteSetPayload,
+    },
+    Address, HexEncodedBytes, MoveModule, MoveResource, MoveType, MoveValue, U128, U64,
+};
+
+use diem_crypto::hash::CryptoHash;
+use diem_types::{
+    access_path::{AccessPath, Path},
+    chain_id::ChainId,
+    contract_event::ContractEvent,
+    transaction::{
+        self, authenticator::TransactionAuthenticator, Module, RawTransaction, Script,
+        ScriptFunction, SignedTransaction, TransactionInfoTrait,
+    },
+    write_set::{WriteOp, WriteSet},
+};
+use move_binary_format::{
+    access::{ModuleAccess, ScriptAccess},
+    file_format::{CompiledModule, CompiledScript, SignatureToken},
+};
+use move_core_types::{
+    language_storage::{ModuleId, TypeTag},
+    resolver::MoveResolver,
+    transaction_argument::TransactionArgument,
+    value,
+};
+use resource_viewer::MoveValueAnnotator;
+
+use anyhow::{bail, format_err, Result};
+use serde_json::Value;
+use std::convert::{TryFrom, TryInto};
+
+/// Converts between the `diem_types` / `move_core_types` values and the API views. Move values
+/// are annotated (or encoded) using the module and type information resolved from `db`.
+pub struct MoveConverter<'a, R> {
+    db: &'a R,
+    inner: MoveValueAnnotator<'a, R>,
+}
+
+impl<'a, R: MoveResolver> MoveConverter<'a, R> {
+    pub fn new(db: &'a R) -> Self {
+        Self {
+            db,
+            inner: MoveValueAnnotator::new(db),
+        }
+    }
+
+    pub fn try_into_resource(&self, typ: &TypeTag, bytes: &[u8]) -> Result<MoveResource> {
+        match typ {
+            TypeTag::Struct(tag) => Ok(self.inner.view_resource(tag, bytes)?.into()),
+            _ => bail!("resource type should be struct, but got: {}", typ),
+        }
+    }
+
+    pub fn try_into_pending_transaction(&self, txn: SignedTransaction) -> Result<Transaction> {
+        let hash = transaction::Transaction::UserTransaction(txn.clone()).hash();
+        Ok(Transaction::PendingTransaction(PendingTransaction {
+            hash: hash.into(),
+            signature: txn.authenticator().into(),
+            request: self.try_into_user_transaction_request(txn)?,
+        }))
+    }
+
+    pub fn try_into_transaction<T: TransactionInfoTrait>(
+        &self,
+        version: u64,
+        txn: transaction::Transaction,
+        info: &T,
+        events: Vec<ContractEvent>,
+        timestamp: u64,
+    ) -> Result<Transaction> {
+        let info = TransactionInfo::from((version, info));
+        let events = self.try_into_events(&events)?;
+        Ok(match txn {
+            transaction::Transaction::UserTransaction(txn) => {
+                Transaction::UserTransaction(Box::new(UserTransaction {
+                    info,
+                    signature: txn.authenticator().into(),
+                    request: self.try_into_user_transaction_request(txn)?,
+                    events,
+                    timestamp: timestamp.into(),
+                }))
+            }
+            transaction::Transaction::GenesisTransaction(payload) => {
+                Transaction::GenesisTransaction(GenesisTransaction {
+                    info,
+                    payload: self.try_into_write_set_payload(payload)?,
+                    events,
+                })
+            }
+            transaction::Transaction::BlockMetadata(metadata) => {
+                let id = metadata.id();
+                let (round, timestamp, previous_block_votes, proposer) = metadata.into_inner();
+                Transaction::BlockMetadataTransaction(BlockMetadataTransaction {
+                    info,
+                    id: id.into(),
+                    round: round.into(),
+                    previous_block_votes: previous_block_votes
+                        .into_iter()
+                        .map(Address::from)
+                        .collect(),
+                    proposer: proposer.into(),
+                    timestamp: timestamp.into(),
+                })
+            }
+        })
+    }
+
+    pub fn try_into_user_transaction_request(
+        &self,
+        txn: SignedTransaction,
+    ) -> Result<UserTransactionRequest> {
+        Ok(UserTransactionRequest {
+            sender: txn.sender().into(),
+            sequence_number: txn.sequence_number().into(),
+            max_gas_amount: txn.max_gas_amount().into(),
+            gas_unit_price: txn.gas_unit_price().into(),
+            gas_currency_code: txn.gas_currency_code().to_owned(),
+            expiration_timestamp_secs: txn.expiration_timestamp_secs().into(),
+            payload: self
+                .try_into_transaction_payload(txn.into_raw_transaction().into_payload())?,
+        })
+    }
+
+    pub fn try_into_transaction_payload(
+        &self,
+        payload: transaction::TransactionPayload,
+    ) -> Result<TransactionPayload> {
+        use transaction::TransactionPayload::*;
+        Ok(match payload {
+            ScriptFunction(fun) => {
+                let arg_types = self.script_function_arg_types(&fun)?;
+                let arguments = arg_types
+                    .iter()
+                    .zip(fun.args())
+                    .map(|(typ, arg)| self.try_into_json_value(typ, arg))
+                    .collect::<Result<_>>()?;
+                TransactionPayload::ScriptFunctionPayload(ScriptFunctionPayload {
+                    function: ScriptFunctionId {
+                        module: fun.module().clone().into(),
+                        name: fun.function().to_owned(),
+                    },
+                    type_arguments: fun.ty_args().iter().cloned().map(MoveType::from).collect(),
+                    arguments,
+                })
+            }
+            Script(script) => TransactionPayload::ScriptPayload(script.try_into()?),
+            Module(module) => TransactionPayload::ModulePayload(ModulePayload {
+                code: module.code().to_vec().into(),
+            }),
+            WriteSet(payload) => {
+                TransactionPayload::WriteSetPayload(self.try_into_write_set_payload(payload)?)
+            }
+        })
+    }
+
+    pub fn try_into_write_set_payload(
+        &self,
+        payload: transaction::WriteSetPayload,
+    ) -> Result<WriteSetPayload> {
+        Ok(match payload {
+            transaction::WriteSetPayload::Script { execute_as, script } => {
+                WriteSetPayload::ScriptWriteSet {
+                    execute_as: execute_as.into(),
+                    script: script.try_into()?,
+                }
+            }
+            transaction::WriteSetPayload::Direct(change_set) => {
+                let (write_set, events) = change_set.into_inner();
+                WriteSetPayload::DirectWriteSet {
+                    changes: self.try_into_write_set_changes(&write_set)?,
+                    events: self.try_into_events(&events)?,
+                }
+            }
+        })
+    }
+
+    pub fn try_into_write_set_changes(&self, write_set: &WriteSet) -> Result<Vec<WriteSetChange>> {
+        write_set
+            .iter()
+            .map(|(access_path, op)| self.try_into_write_set_change(access_path, op))
+            .collect()
+    }
+
+    pub fn try_into_write_set_change(
+        &self,
+        access_path: &AccessPath,
+        op: &WriteOp,
+    ) -> Result<WriteSetChange> {
+        let address = access_path.address.into();
+        Ok(match (access_path.get_path(), op) {
+            (Path::Code(module_id), WriteOp::Deletion) => WriteSetChange::DeleteModule {
+                address,
+                module: module_id.into(),
+            },
+            (Path::Resource(tag), WriteOp::Deletion) => WriteSetChange::DeleteResource {
+                address,
+                resource: tag.into(),
+            },
+            (Path::Code(_), WriteOp::Value(bytes)) => WriteSetChange::WriteModule {
+                address,
+                data: MoveModule::try_from(bytes)?,
+            },
+            (Path::Resource(tag), WriteOp::Value(bytes)) => WriteSetChange::WriteResource {
+                address,
+                data: self.inner.view_resource(&tag, bytes)?.into(),
+            },
+        })
+    }
+
+    pub fn try_into_events(&self, events: &[ContractEvent]) -> Result<Vec<Event>> {
+        events
+            .iter()
+            .map(|event| {
+                let data = self
+                    .inner
+                    .view_value(event.type_tag(), event.event_data())?;
+                Ok(Event {
+                    key: *event.key(),
+                    sequence_number: event.sequence_number().into(),
+                    typ: event.type_tag().clone().into(),
+                    data: data.into(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn try_into_signed_transaction(
+        &self,
+        txn: SubmitTransactionRequest,
+        chain_id: ChainId,
+    ) -> Result<SignedTransaction> {
+        let SubmitTransactionRequest {
+            user_transaction_request,
+            signature,
+        } = txn;
+        let UserTransactionRequest {
+            sender,
+            sequence_number,
+            max_gas_amount,
+            gas_unit_price,
+            gas_currency_code,
+            expiration_timestamp_secs,
+            payload,
+        } = user_transaction_request;
+        let raw_txn = RawTransaction::new(
+            sender.into(),
+            sequence_number.into(),
+            self.try_into_diem_transaction_payload(payload)?,
+            max_gas_amount.into(),
+            gas_unit_price.into(),
+            gas_currency_code,
+            expiration_timestamp_secs.into(),
+            chain_id,
+        );
+        Ok(match TransactionAuthenticator::try_from(signature)? {
+            TransactionAuthenticator::Ed25519 {
+                public_key,
+                signature,
+            } => SignedTransaction::new(raw_txn, public_key.validate()?.clone(), signature),
+            TransactionAuthenticator::MultiEd25519 {
+                public_key,
+                signature,
+            } => SignedTransaction::new_multisig(raw_txn, public_key, signature),
+            TransactionAuthenticator::MultiAgent {
+                sender,
+                secondary_signer_addresses,
+                secondary_signers,
+            } => SignedTransaction::new_multi_agent(
+                raw_txn,
+                sender,
+                secondary_signer_addresses,
+                secondary_signers,
+            ),
+        })
+    }
+
+    pub fn try_into_diem_transaction_payload(
+        &self,
+        payload: TransactionPayload,
+    ) -> Result<transaction::TransactionPayload> {
+        Ok(match payload {
+            TransactionPayload::ScriptFunctionPayload(fun) => {
+                let ScriptFunctionPayload {
+                    function,
+                    type_arguments,
+                    arguments,
+                } = fun;
+                let ty_args = type_arguments
+                    .into_iter()
+                    .map(TypeTag::try_from)
+                    .collect::<Result<Vec<_>>>()?;
+                let fun =
+                    ScriptFunction::new(function.module.into(), function.name, ty_args, vec![]);
+                let arg_types = self.script_function_arg_types(&fun)?;
+                if arg_types.len() != arguments.len() {
+                    bail!(
+                        "expected {} arguments for script function {}, but got {}",
+                        arg_types.len(),
+                        fun.function(),
+                        arguments.len(),
+                    );
+                }
+                let args = arg_types
+                    .iter()
+                    .zip(arguments)
+                    .map(|(typ, arg)| {
+                        try_into_move_value(typ, arg)?
+                            .simple_serialize()
+                            .ok_or_else(|| format_err!("failed to serialize argument: {}", typ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                transaction::TransactionPayload::ScriptFunction(ScriptFunction::new(
+                    fun.module().clone(),
+                    fun.function().to_owned(),
+                    fun.ty_args().to_vec(),
+                    args,
+                ))
+            }
+            TransactionPayload::ScriptPayload(script) => {
+                transaction::TransactionPayload::Script(script.try_into()?)
+            }
+            TransactionPayload::ModulePayload(module) => {
+                transaction::TransactionPayload::Module(Module::new(module.code.into()))
+            }
+            TransactionPayload::WriteSetPayload(_) => {
+                bail!("write set payload is not supported for submitting transaction")
+            }
+        })
+    }
+
+    fn try_into_json_value(&self, typ: &TypeTag, bytes: &[u8]) -> Result<Value> {
+        let value: MoveValue = self.inner.view_value(typ, bytes)?.into();
+        Ok(serde_json::to_value(value)?)
+    }
+
+    /// Returns the types of the non-signer parameters of the script function, with the generic
+    /// type parameters substituted by the type arguments of `fun`.
+    fn script_function_arg_types(&self, fun: &ScriptFunction) -> Result<Vec<TypeTag>> {
+        let module = self.get_module(fun.module())?;
+        let def = module
+            .function_defs()
+            .iter()
+            .find(|def| {
+                let handle = module.function_handle_at(def.function);
+                module.identifier_at(handle.name) == fun.function()
+            })
+            .ok_or_else(|| {
+                format_err!(
+                    "could not find script function {}::{}",
+                    fun.module(),
+                    fun.function()
+                )
+            })?;
+        let handle = module.function_handle_at(def.function);
+        arg_types(&module.signature_at(handle.parameters).0, fun.ty_args())
+    }
+
+    fn get_module(&self, module_id: &ModuleId) -> Result<CompiledModule> {
+        let bytes = self
+            .db
+            .get_module(module_id)
+            .map_err(|e| format_err!("failed to load module {}: {:?}", module_id, e))?
+            .ok_or_else(|| format_err!("could not find module {}", module_id))?;
+        CompiledModule::deserialize(&bytes)
+            .map_err(|e| format_err!("failed to deserialize module {}: {:?}", module_id, e))
+    }
+}
+
+impl TryFrom<Script> for ScriptPayload {
+    type Error = anyhow::Error;
+
+    fn try_from(script: Script) -> Result<Self> {
+        Ok(Self {
+            code: script.code().to_vec().into(),
+            type_arguments: script
+                .ty_args()
+                .iter()
+                .cloned()
+                .map(MoveType::from)
+                .collect(),
+            arguments: script
+                .args()
+                .iter()
+                .cloned()
+                .map(|arg| serde_json::to_value(MoveValue::from(arg)))
+                .collect::<serde_json::Result<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<ScriptPayload> for Script {
+    type Error = anyhow::Error;
+
+    fn try_from(script: ScriptPayload) -> Result<Self> {
+        let ScriptPayload {
+            code,
+            type_arguments,
+            arguments,
+        } = script;
+        let ty_args = type_arguments
+            .into_iter()
+            .map(TypeTag::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let compiled = CompiledScript::deserialize(code.inner())
+            .map_err(|e| format_err!("failed to deserialize script: {:?}", e))?;
+        let arg_types = arg_types(&compiled.signature_at(compiled.parameters).0, &ty_args)?;
+        if arg_types.len() != arguments.len() {
+            bail!(
+                "expected {} arguments for script, but got {}",
+                arg_types.len(),
+                arguments.len(),
+            );
+        }
+        let args = arg_types
+            .iter()
+            .zip(arguments)
+            .map(|(typ, arg)| try_into_transaction_argument(try_into_move_value(typ, arg)?))
+            .collect::<Result<_>>()?;
+        Ok(Script::new(code.into(), ty_args, args))
+    }
+}
+
+impl From<TransactionArgument> for MoveValue {
+    fn from(arg: TransactionArgument) -> Self {
+        match arg {
+            TransactionArgument::U8(v) => MoveValue::U8(v),
+            TransactionArgument::U64(v) => MoveValue::U64(v.into()),
+            TransactionArgument::U128(v) => MoveValue::U128(v.into()),
+            TransactionArgument::Address(v) => MoveValue::Address(v.into()),
+            TransactionArgument::U8Vector(v) => MoveValue::Bytes(v.into()),
+            TransactionArgument::Bool(v) => MoveValue::Bool(v),
+        }
+    }
+}
+
+/// Maps the parameter signature of a script or script function to the types of the arguments
+/// carried by the transaction: leading signer parameters are filled in by the VM and skipped.
+fn arg_types(params: &[SignatureToken], ty_args: &[TypeTag]) -> Result<Vec<TypeTag>> {
+    params
+        .iter()
+        .filter(|token| !is_signer(token))
+        .map(|token| type_tag(token, ty_args))
+        .collect()
+}
+
+fn is_signer(token: &SignatureToken) -> bool {
+    match token {
+        SignatureToken::Signer => true,
+        SignatureToken::Reference(inner) => matches!(inner.as_ref(), SignatureToken::Signer),
+        _ => false,
+    }
+}
+
+fn type_tag(token: &SignatureToken, ty_args: &[TypeTag]) -> Result<TypeTag> {
+    Ok(match token {
+        SignatureToken::Bool => TypeTag::Bool,
+        SignatureToken::U8 => TypeTag::U8,
+        SignatureToken::U64 => TypeTag::U64,
+        SignatureToken::U128 => TypeTag::U128,
+        SignatureToken::Address => TypeTag::Address,
+        SignatureToken::Vector(item) => TypeTag::Vector(Box::new(type_tag(item, ty_args)?)),
+        SignatureToken::TypeParameter(i) => ty_args
+            .get(*i as usize)
+            .cloned()
+            .ok_or_else(|| format_err!("missing type argument for type parameter {}", i))?,
+        _ => bail!("unsupported argument type: {:?}", token),
+    })
+}
+
+fn try_into_move_value(typ: &TypeTag, val: Value) -> Result<value::MoveValue> {
+    Ok(match typ {
+        TypeTag::Bool => value::MoveValue::Bool(serde_json::from_value::<bool>(val)?),
+        TypeTag::U8 => value::MoveValue::U8(serde_json::from_value::<u8>(val)?),
+        TypeTag::U64 => value::MoveValue::U64(serde_json::from_value::<U64>(val)?.into()),
+        TypeTag::U128 => value::MoveValue::U128(serde_json::from_value::<U128>(val)?.into()),
+        TypeTag::Address => {
+            value::MoveValue::Address(serde_json::from_value::<Address>(val)?.into())
+        }
+        TypeTag::Vector(item) => match item.as_ref() {
+            TypeTag::U8 => {
+                value::MoveValue::vector_u8(serde_json::from_value::<HexEncodedBytes>(val)?.into())
+            }
+            _ => value::MoveValue::Vector(
+                serde_json::from_value::<Vec<Value>>(val)?
+                    .into_iter()
+                    .map(|v| try_into_move_value(item, v))
+                    .collect::<Result<_>>()?,
+            ),
+        },
+        _ => bail!("unsupported argument type: {}", typ),
+    })
+}
+
+fn try_into_transaction_argument(val: value::MoveValue) -> Result<TransactionArgument> {
+    Ok(match val {
+        value::MoveValue::U8(v) => TransactionArgument::U8(v),
+        value::MoveValue::U64(v) => TransactionArgument::U64(v),
+        value::MoveValue::U128(v) => TransactionArgument::U128(v),
+        value::MoveValue::Bool(v) => TransactionArgument::Bool(v),
+        value::MoveValue::Address(v) => TransactionArgument::Address(v),
+        value::MoveValue::Vector(items) => TransactionArgument::U8Vector(
+            items
+                .into_iter()
+                .map(|item| match item {
+                    value::MoveValue::U8(b) => Ok(b),
+                    _ => Err(format_err!("script argument should be vector<u8>")),
+                })
+                .collect::<Result<_>>()?,
+        ),
+        _ => bail!("unsupported script argument: {:?}", val),
+    })
+}
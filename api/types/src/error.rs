@@ -44,6 +44,10 @@ impl Error {
         Self::new_with_data(StatusCode::NOT_FOUND, message, data)
     }
 
+    pub fn gone(message: String, data: serde_json::Value) -> Self {
+        Self::new_with_data(StatusCode::GONE, message, data)
+    }
+
     pub fn internal(err: anyhow::Error) -> Self {
         Self::from_anyhow_error(StatusCode::INTERNAL_SERVER_ERROR, err)
     }
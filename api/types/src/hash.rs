@@ -0,0 +1,86 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+#[derive(Clone, Debug, PartialEq, Copy)]
+pub struct HashValue(diem_crypto::HashValue);
+
+impl fmt::Display for HashValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{}", self.0.to_hex())
+    }
+}
+
+impl FromStr for HashValue {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self, anyhow::Error> {
+        let hex = s.strip_prefix("0x").unwrap_or(s);
+        Ok(Self(diem_crypto::HashValue::from_hex(hex).map_err(
+            |_| anyhow::format_err!("invalid hash value: {}", s),
+        )?))
+    }
+}
+
+impl From<diem_crypto::HashValue> for HashValue {
+    fn from(hash: diem_crypto::HashValue) -> Self {
+        Self(hash)
+    }
+}
+
+impl From<HashValue> for diem_crypto::HashValue {
+    fn from(hash: HashValue) -> Self {
+        hash.0
+    }
+}
+
+impl Serialize for HashValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for HashValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let hash = <String>::deserialize(deserializer)?;
+        HashValue::from_str(&hash).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hash::HashValue;
+
+    use serde_json::{json, Value};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_from_and_to_string() {
+        let hash = "0xdd8c2b1a7f3b3cf5e4bd9d1db4f2b0c4b3f1c4ee4a0b6c4c5d0b5f1d9e3c2a11";
+        assert_eq!(HashValue::from_str(hash).unwrap().to_string(), hash);
+        assert_eq!(HashValue::from_str(&hash[2..]).unwrap().to_string(), hash);
+
+        let invalid_hashes = vec!["invalid", "0x1", "0xzz"];
+        for hash in invalid_hashes {
+            assert_eq!(
+                format!("invalid hash value: {}", hash),
+                HashValue::from_str(hash).unwrap_err().to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_and_to_json() {
+        let hex = "0x0000000000000000000000000000000000000000000000000000000000000001";
+        let hash: HashValue = serde_json::from_value(json!(hex)).unwrap();
+        assert_eq!(hash, HashValue::from_str(hex).unwrap());
+
+        let val: Value = serde_json::to_value(hash).unwrap();
+        assert_eq!(val, json!(hex));
+    }
+}
@@ -1,17 +1,36 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod account;
 mod address;
+mod convert;
 mod error;
+mod hash;
 mod ledger_info;
 mod move_types;
 mod response;
+mod schema;
+mod transaction;
 
+pub use account::{AccountStateEntry, AccountStatesPage};
 pub use address::Address;
+pub use convert::MoveConverter;
 pub use error::Error;
+pub use hash::HashValue;
 pub use ledger_info::LedgerInfo;
 pub use move_types::{
     HexEncodedBytes, MoveModule, MoveModuleId, MoveResource, MoveStructTag, MoveStructValue,
     MoveType, MoveValue, U128, U64,
 };
-pub use response::{Response, X_DIEM_CHAIN_ID, X_DIEM_LEDGER_TIMESTAMP, X_DIEM_LEDGER_VERSION};
+pub use response::{
+    ContentType, Response, APPLICATION_BCS, APPLICATION_JSON, X_DIEM_CHAIN_ID,
+    X_DIEM_LEDGER_TIMESTAMP, X_DIEM_LEDGER_VERSION,
+};
+pub use schema::{ApiSchema, Schemas};
+pub use transaction::{
+    AccountSignature, BlockMetadataTransaction, Ed25519Signature, Event, GenesisTransaction,
+    ModulePayload, MultiAgentSignature, MultiEd25519Signature, PendingTransaction,
+    ScriptFunctionId, ScriptFunctionPayload, ScriptPayload, SubmitTransactionRequest, Transaction,
+    TransactionId, TransactionInfo, TransactionPayload, TransactionSignature, UserTransaction,
+    UserTransactionRequest, WriteSetChange, WriteSetPayload,
+};
@@ -21,9 +21,10 @@ use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     borrow::Borrow,
     collections::BTreeMap,
-    convert::{From, Into, TryFrom},
+    convert::{From, Into, TryFrom, TryInto},
     fmt,
     result::Result,
+    str::FromStr,
 };
 
 #[derive(Clone, Debug, PartialEq, Serialize)]
@@ -129,12 +130,51 @@ impl<'de> Deserialize<'de> for U128 {
 #[derive(Clone, Debug, PartialEq)]
 pub struct HexEncodedBytes(Vec<u8>);
 
+impl HexEncodedBytes {
+    pub fn inner(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl FromStr for HexEncodedBytes {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self, anyhow::Error> {
+        let hex = s.strip_prefix("0x").unwrap_or(s);
+        Ok(Self(hex::decode(hex).map_err(|_| {
+            anyhow::format_err!("invalid hex encoded bytes: {}", s)
+        })?))
+    }
+}
+
+impl From<Vec<u8>> for HexEncodedBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<HexEncodedBytes> for Vec<u8> {
+    fn from(bytes: HexEncodedBytes) -> Self {
+        bytes.0
+    }
+}
+
 impl Serialize for HexEncodedBytes {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         format!("0x{}", &hex::encode(&self.0)).serialize(serializer)
     }
 }
 
+impl<'de> Deserialize<'de> for HexEncodedBytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <String>::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct MoveStructValue(BTreeMap<Identifier, MoveValue>);
 
@@ -198,7 +238,7 @@ impl Serialize for MoveValue {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MoveStructTag {
     pub address: Address,
     pub module: Identifier,
@@ -232,7 +272,7 @@ impl From<(&CompiledModule, &StructHandleIndex, &Vec<SignatureToken>)> for MoveS
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum MoveType {
     Bool,
@@ -264,6 +304,41 @@ impl From<TypeTag> for MoveType {
     }
 }
 
+impl TryFrom<MoveType> for TypeTag {
+    type Error = anyhow::Error;
+
+    fn try_from(typ: MoveType) -> anyhow::Result<Self> {
+        Ok(match typ {
+            MoveType::Bool => TypeTag::Bool,
+            MoveType::U8 => TypeTag::U8,
+            MoveType::U64 => TypeTag::U64,
+            MoveType::U128 => TypeTag::U128,
+            MoveType::Address => TypeTag::Address,
+            MoveType::Signer => TypeTag::Signer,
+            MoveType::Vector { items } => TypeTag::Vector(Box::new((*items).try_into()?)),
+            MoveType::Struct(v) => TypeTag::Struct(v.try_into()?),
+            _ => anyhow::bail!("invalid move type for converting into `TypeTag`: {:?}", typ),
+        })
+    }
+}
+
+impl TryFrom<MoveStructTag> for StructTag {
+    type Error = anyhow::Error;
+
+    fn try_from(tag: MoveStructTag) -> anyhow::Result<Self> {
+        Ok(Self {
+            address: tag.address.into(),
+            module: tag.module,
+            name: tag.name,
+            type_params: tag
+                .generic_type_params
+                .into_iter()
+                .map(TypeTag::try_from)
+                .collect::<anyhow::Result<Vec<TypeTag>>>()?,
+        })
+    }
+}
+
 impl From<(&CompiledModule, &SignatureToken)> for MoveType {
     fn from((m, token): (&CompiledModule, &SignatureToken)) -> Self {
         match token {
@@ -329,7 +404,7 @@ impl TryFrom<&Vec<u8>> for MoveModule {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MoveModuleId {
     pub address: Address,
     pub name: Identifier,
@@ -345,6 +420,12 @@ impl From<ModuleId> for MoveModuleId {
     }
 }
 
+impl From<MoveModuleId> for ModuleId {
+    fn from(id: MoveModuleId) -> Self {
+        ModuleId::new(id.address.into(), id.name)
+    }
+}
+
 impl fmt::Display for MoveModuleId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}::{}", self.address, self.name)
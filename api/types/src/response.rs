@@ -11,8 +11,45 @@ pub const X_DIEM_CHAIN_ID: &str = "X-Diem-Chain-Id";
 pub const X_DIEM_LEDGER_VERSION: &str = "X-Diem-Ledger-Version";
 pub const X_DIEM_LEDGER_TIMESTAMP: &str = "X-Diem-Ledger-Timestamp";
 
+pub const APPLICATION_JSON: &str = "application/json";
+pub const APPLICATION_BCS: &str = "application/x-bcs";
+
+/// Encoding of a response body, negotiated by the `Accept` request header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContentType {
+    /// JSON of the API types.
+    Json,
+    /// BCS of the underlying `diem_types` values.
+    Bcs,
+}
+
+impl ContentType {
+    /// Returns the first known media type listed in the `Accept` header value, ignoring quality
+    /// values. Defaults to JSON.
+    pub fn from_accept(accept: Option<&str>) -> Self {
+        accept
+            .into_iter()
+            .flat_map(|accept| accept.split(','))
+            .filter_map(|media_range| match media_range.split(';').next()?.trim() {
+                APPLICATION_BCS => Some(ContentType::Bcs),
+                APPLICATION_JSON => Some(ContentType::Json),
+                _ => None,
+            })
+            .next()
+            .unwrap_or(ContentType::Json)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentType::Json => APPLICATION_JSON,
+            ContentType::Bcs => APPLICATION_BCS,
+        }
+    }
+}
+
 pub struct Response {
     pub ledger_info: LedgerInfo,
+    pub content_type: ContentType,
     pub body: Vec<u8>,
 }
 
@@ -20,9 +57,25 @@ impl Response {
     pub fn new<T: Serialize>(ledger_info: LedgerInfo, body: &T) -> Result<Self, Error> {
         Ok(Self {
             ledger_info,
+            content_type: ContentType::Json,
             body: serde_json::to_vec(body)?,
         })
     }
+
+    /// Creates a response of the BCS of the `body`.
+    pub fn new_bcs<T: Serialize>(ledger_info: LedgerInfo, body: &T) -> Result<Self, Error> {
+        let bytes = bcs::to_bytes(body).map_err(|e| Error::internal(e.into()))?;
+        Ok(Self::from_bcs_bytes(ledger_info, bytes))
+    }
+
+    /// Creates a response of the bytes that are already BCS encoded, e.g. Move resources.
+    pub fn from_bcs_bytes(ledger_info: LedgerInfo, body: Vec<u8>) -> Self {
+        Self {
+            ledger_info,
+            content_type: ContentType::Bcs,
+            body,
+        }
+    }
 }
 
 impl warp::Reply for Response {
@@ -30,7 +83,10 @@ impl warp::Reply for Response {
         let mut res = warp::reply::Response::new(self.body.into());
         let headers = res.headers_mut();
 
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static(self.content_type.as_str()),
+        );
         headers.insert(X_DIEM_CHAIN_ID, (self.ledger_info.chain_id as u16).into());
         headers.insert(
             X_DIEM_LEDGER_VERSION,
@@ -44,3 +100,26 @@ impl warp::Reply for Response {
         res
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::response::ContentType;
+
+    #[test]
+    fn test_content_type_from_accept() {
+        assert_eq!(ContentType::Json, ContentType::from_accept(None));
+        assert_eq!(ContentType::Json, ContentType::from_accept(Some("*/*")));
+        assert_eq!(
+            ContentType::Bcs,
+            ContentType::from_accept(Some("application/x-bcs"))
+        );
+        assert_eq!(
+            ContentType::Bcs,
+            ContentType::from_accept(Some("text/html, application/x-bcs;q=0.9, */*"))
+        );
+        assert_eq!(
+            ContentType::Json,
+            ContentType::from_accept(Some("application/json, application/x-bcs"))
+        );
+    }
+}
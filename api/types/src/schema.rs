@@ -0,0 +1,1134 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! OpenAPI (JSON schema) definitions of the API types.
+//!
+//! Every type rendered by the API implements `ApiSchema`. Schemas reference each other by name,
+//! and `Schemas` collects all schemas reachable from the referenced types, so that the
+//! `components/schemas` section of the OpenAPI document is generated from the types used by the
+//! routes.
+
+use crate::{
+    move_types::{
+        MoveFunction, MoveFunctionGenericTypeParam, MoveStruct, MoveStructField,
+        MoveStructGenericTypeParam,
+    },
+    AccountSignature, AccountStateEntry, AccountStatesPage, Address, BlockMetadataTransaction,
+    Ed25519Signature, Error, Event, GenesisTransaction, HashValue, HexEncodedBytes, LedgerInfo,
+    ModulePayload, MoveModule, MoveModuleId, MoveResource, MoveStructTag, MoveType, MoveValue,
+    MultiAgentSignature, MultiEd25519Signature, PendingTransaction, ScriptFunctionId,
+    ScriptFunctionPayload, ScriptPayload, SubmitTransactionRequest, Transaction,
+    TransactionPayload, TransactionSignature, UserTransaction, UserTransactionRequest,
+    WriteSetChange, WriteSetPayload, U128, U64,
+};
+
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// A type that has a named schema in the OpenAPI document.
+pub trait ApiSchema {
+    /// Name of the schema under `#/components/schemas`.
+    fn name() -> &'static str;
+
+    /// JSON schema of the type. Schemas of other types are referenced through `schemas`.
+    fn schema(schemas: &mut Schemas) -> Value;
+}
+
+/// Collects the schemas of types referenced while building an OpenAPI document.
+#[derive(Debug, Default)]
+pub struct Schemas {
+    schemas: BTreeMap<String, Value>,
+}
+
+impl Schemas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a `$ref` to the schema of `T`, registering the schema of `T` (and the schemas it
+    /// references) on first use.
+    pub fn of<T: ApiSchema>(&mut self) -> Value {
+        let name = T::name();
+        if !self.schemas.contains_key(name) {
+            // insert a placeholder first, so that recursive types terminate
+            self.schemas.insert(name.to_owned(), Value::Null);
+            let schema = T::schema(self);
+            self.schemas.insert(name.to_owned(), schema);
+        }
+        json!({ "$ref": format!("#/components/schemas/{}", name) })
+    }
+
+    /// Returns the schema of an array of `T`.
+    pub fn array_of<T: ApiSchema>(&mut self) -> Value {
+        json!({ "type": "array", "items": self.of::<T>() })
+    }
+
+    /// Returns the schema registered under the `$ref`, if any.
+    pub fn resolve(&self, reference: &str) -> Option<&Value> {
+        reference
+            .strip_prefix("#/components/schemas/")
+            .and_then(|name| self.schemas.get(name))
+    }
+
+    /// Validates the JSON `value` against the `schema`, resolving `$ref`s through the registered
+    /// schemas.
+    ///
+    /// Only the subset of JSON schema used by `ApiSchema` is supported: `$ref`, `type`, `enum`,
+    /// `required`, `properties`, `items`, `allOf`, `oneOf` and `anyOf`. Object fields that the
+    /// schema does not declare are rejected, unless the schema declares no properties at all.
+    /// Returns the path to the first value that does not match.
+    pub fn validate(&self, schema: &Value, value: &Value) -> Result<(), String> {
+        validate_at(self, schema, value, "$")?;
+        validate_fields_at(self, schema, value, "$")
+    }
+
+    pub fn into_inner(self) -> BTreeMap<String, Value> {
+        self.schemas
+    }
+}
+
+/// An object schema with the given properties, all of them are required unless listed in
+/// `optional`.
+fn object(properties: Value, optional: &[&str]) -> Value {
+    let required: Vec<&String> = properties
+        .as_object()
+        .expect("properties should be an object")
+        .keys()
+        .filter(|key| !optional.contains(&key.as_str()))
+        .collect();
+    json!({
+        "type": "object",
+        "required": required,
+        "properties": properties,
+    })
+}
+
+/// An object schema of an internally tagged enum variant: the `type` property is fixed to `tag`.
+fn tagged(tag: &str, properties: Value) -> Value {
+    let mut props = Map::new();
+    props.insert(
+        "type".to_owned(),
+        json!({ "type": "string", "enum": [tag] }),
+    );
+    props.extend(
+        properties
+            .as_object()
+            .expect("properties should be an object")
+            .clone(),
+    );
+    object(Value::Object(props), &[])
+}
+
+/// Schema of a newtype variant of an internally tagged enum: the `type` property of the variant
+/// is fixed to `tag`, and the other properties are the ones of the variant type.
+fn variant(tag: &str, schema: Value) -> Value {
+    all_of(vec![tagged(tag, json!({})), schema])
+}
+
+/// Schema composed of all the schemas, used for `#[serde(flatten)]` fields.
+fn all_of(schemas: Vec<Value>) -> Value {
+    json!({ "allOf": schemas })
+}
+
+fn one_of(schemas: Vec<Value>) -> Value {
+    json!({ "oneOf": schemas })
+}
+
+fn string(description: &str) -> Value {
+    json!({ "type": "string", "description": description })
+}
+
+macro_rules! api_schema {
+    ($typ:ty, $name:expr, |$schemas:ident| $body:expr) => {
+        impl ApiSchema for $typ {
+            fn name() -> &'static str {
+                $name
+            }
+
+            #[allow(unused_variables)]
+            fn schema($schemas: &mut Schemas) -> Value {
+                $body
+            }
+        }
+    };
+}
+
+api_schema!(Address, "Address", |s| string(
+    "Hex-encoded account address with `0x` prefix and trimmed leading zeros."
+));
+api_schema!(U64, "U64", |s| string(
+    "Unsigned integer 64, rendered as string because of the JSON number precision limit."
+));
+api_schema!(U128, "U128", |s| string(
+    "Unsigned integer 128, rendered as string because of the JSON number precision limit."
+));
+api_schema!(HashValue, "HashValue", |s| string(
+    "Hex-encoded 32 byte hash value with `0x` prefix."
+));
+api_schema!(HexEncodedBytes, "HexEncodedBytes", |s| string(
+    "Hex-encoded bytes with `0x` prefix."
+));
+
+api_schema!(LedgerInfo, "LedgerInfo", |s| object(
+    json!({
+        "chain_id": { "type": "integer", "format": "uint8" },
+        "ledger_version": s.of::<U64>(),
+        "ledger_timestamp": s.of::<U64>(),
+    }),
+    &[],
+));
+
+api_schema!(Error, "Error", |s| object(
+    json!({
+        "code": { "type": "integer", "format": "uint16" },
+        "message": { "type": "string" },
+        "data": { "type": "object" },
+    }),
+    &["data"],
+));
+
+api_schema!(AccountStatesPage, "AccountStatesPage", |s| object(
+    json!({
+        "accounts": s.array_of::<AccountStateEntry>(),
+        "proof": s.array_of::<HashValue>(),
+        "next_start": s.of::<HashValue>(),
+    }),
+    &["proof", "next_start"],
+));
+
+api_schema!(AccountStateEntry, "AccountStateEntry", |s| object(
+    json!({
+        "key": s.of::<HashValue>(),
+        "address": s.of::<Address>(),
+        "state": s.of::<HexEncodedBytes>(),
+    }),
+    &["address"],
+));
+
+api_schema!(MoveValue, "MoveValue", |s| json!({
+    "description": "Move value, a JSON value typed by the Move type next to it: u8 and bool \
+                    values are JSON number and boolean, u64, u128, address and vector<u8> values \
+                    are strings, other vectors are arrays and structs are objects.",
+    "anyOf": [
+        { "type": "integer" },
+        { "type": "boolean" },
+        { "type": "string" },
+        { "type": "array" },
+        { "type": "object" },
+    ],
+}));
+
+api_schema!(MoveType, "MoveType", |s| one_of(vec![
+    tagged("bool", json!({})),
+    tagged("u8", json!({})),
+    tagged("u64", json!({})),
+    tagged("u128", json!({})),
+    tagged("address", json!({})),
+    tagged("signer", json!({})),
+    tagged("vector", json!({ "items": s.of::<MoveType>() })),
+    variant("struct", s.of::<MoveStructTag>()),
+    tagged(
+        "generic_type_param",
+        json!({ "index": { "type": "integer", "format": "uint16" } })
+    ),
+    tagged(
+        "reference",
+        json!({ "mutable": { "type": "boolean" }, "to": s.of::<MoveType>() })
+    ),
+]));
+
+api_schema!(MoveStructTag, "MoveStructTag", |s| object(
+    json!({
+        "address": s.of::<Address>(),
+        "module": { "type": "string" },
+        "name": { "type": "string" },
+        "generic_type_params": s.array_of::<MoveType>(),
+    }),
+    &[],
+));
+
+api_schema!(MoveResource, "MoveResource", |s| object(
+    json!({
+        "type": all_of(vec![tagged("struct", json!({})), s.of::<MoveStructTag>()]),
+        "value": { "type": "object" },
+    }),
+    &[],
+));
+
+api_schema!(MoveModuleId, "MoveModuleId", |s| object(
+    json!({
+        "address": s.of::<Address>(),
+        "name": { "type": "string" },
+    }),
+    &[],
+));
+
+api_schema!(MoveModule, "MoveModule", |s| object(
+    json!({
+        "address": s.of::<Address>(),
+        "name": { "type": "string" },
+        "friends": s.array_of::<MoveModuleId>(),
+        "exposed_functions": s.array_of::<MoveFunction>(),
+        "structs": s.array_of::<MoveStruct>(),
+    }),
+    &[],
+));
+
+fn abilities() -> Value {
+    json!({
+        "type": "array",
+        "items": { "type": "string", "enum": ["copy", "drop", "store", "key"] },
+    })
+}
+
+api_schema!(MoveStruct, "MoveStruct", |s| object(
+    json!({
+        "name": { "type": "string" },
+        "is_native": { "type": "boolean" },
+        "abilities": abilities(),
+        "generic_type_params": s.array_of::<MoveStructGenericTypeParam>(),
+        "fields": s.array_of::<MoveStructField>(),
+    }),
+    &[],
+));
+
+api_schema!(MoveStructField, "MoveStructField", |s| object(
+    json!({
+        "name": { "type": "string" },
+        "type": s.of::<MoveType>(),
+    }),
+    &[],
+));
+
+api_schema!(
+    MoveStructGenericTypeParam,
+    "MoveStructGenericTypeParam",
+    |s| object(
+        json!({
+            "constraints": abilities(),
+            "is_phantom": { "type": "boolean" },
+        }),
+        &[],
+    )
+);
+
+api_schema!(MoveFunction, "MoveFunction", |s| object(
+    json!({
+        "name": { "type": "string" },
+        "visibility": {
+            "type": "string",
+            "enum": ["private", "public", "script", "friend"],
+        },
+        "generic_type_params": s.array_of::<MoveFunctionGenericTypeParam>(),
+        "params": s.array_of::<MoveType>(),
+        "return": s.array_of::<MoveType>(),
+    }),
+    &[],
+));
+
+api_schema!(
+    MoveFunctionGenericTypeParam,
+    "MoveFunctionGenericTypeParam",
+    |s| object(json!({ "constraints": abilities() }), &[])
+);
+
+fn transaction_info(s: &mut Schemas) -> Value {
+    object(
+        json!({
+            "version": s.of::<U64>(),
+            "hash": s.of::<HashValue>(),
+            "state_root_hash": s.of::<HashValue>(),
+            "event_root_hash": s.of::<HashValue>(),
+            "gas_used": s.of::<U64>(),
+            "success": { "type": "boolean" },
+            "vm_status": { "type": "string" },
+        }),
+        &[],
+    )
+}
+
+api_schema!(Transaction, "Transaction", |s| one_of(vec![
+    variant("pending_transaction", s.of::<PendingTransaction>()),
+    variant("user_transaction", s.of::<UserTransaction>()),
+    variant("genesis_transaction", s.of::<GenesisTransaction>()),
+    variant(
+        "block_metadata_transaction",
+        s.of::<BlockMetadataTransaction>()
+    ),
+]));
+
+api_schema!(PendingTransaction, "PendingTransaction", |s| all_of(vec![
+    object(
+        json!({
+            "hash": s.of::<HashValue>(),
+            "signature": s.of::<TransactionSignature>(),
+        }),
+        &[],
+    ),
+    s.of::<UserTransactionRequest>(),
+]));
+
+api_schema!(UserTransaction, "UserTransaction", |s| all_of(vec![
+    object(
+        json!({
+            "signature": s.of::<TransactionSignature>(),
+            "events": s.array_of::<Event>(),
+            "timestamp": s.of::<U64>(),
+        }),
+        &[],
+    ),
+    transaction_info(s),
+    s.of::<UserTransactionRequest>(),
+]));
+
+api_schema!(GenesisTransaction, "GenesisTransaction", |s| all_of(vec![
+    object(
+        json!({
+            "payload": s.of::<WriteSetPayload>(),
+            "events": s.array_of::<Event>(),
+        }),
+        &[],
+    ),
+    transaction_info(s),
+]));
+
+api_schema!(BlockMetadataTransaction, "BlockMetadataTransaction", |s| {
+    all_of(vec![
+        object(
+            json!({
+                "id": s.of::<HashValue>(),
+                "round": s.of::<U64>(),
+                "previous_block_votes": s.array_of::<Address>(),
+                "proposer": s.of::<Address>(),
+                "timestamp": s.of::<U64>(),
+            }),
+            &[],
+        ),
+        transaction_info(s),
+    ])
+});
+
+api_schema!(
+    UserTransactionRequest,
+    "UserTransactionRequest",
+    |s| object(
+        json!({
+            "sender": s.of::<Address>(),
+            "sequence_number": s.of::<U64>(),
+            "max_gas_amount": s.of::<U64>(),
+            "gas_unit_price": s.of::<U64>(),
+            "gas_currency_code": { "type": "string" },
+            "expiration_timestamp_secs": s.of::<U64>(),
+            "payload": s.of::<TransactionPayload>(),
+        }),
+        &[],
+    )
+);
+
+api_schema!(SubmitTransactionRequest, "SubmitTransactionRequest", |s| {
+    all_of(vec![
+        s.of::<UserTransactionRequest>(),
+        object(json!({ "signature": s.of::<TransactionSignature>() }), &[]),
+    ])
+});
+
+api_schema!(Event, "Event", |s| object(
+    json!({
+        "key": string("Hex-encoded event key."),
+        "sequence_number": s.of::<U64>(),
+        "type": s.of::<MoveType>(),
+        "data": s.of::<MoveValue>(),
+    }),
+    &[],
+));
+
+api_schema!(TransactionPayload, "TransactionPayload", |s| one_of(vec![
+    variant("script_function_payload", s.of::<ScriptFunctionPayload>()),
+    variant("script_payload", s.of::<ScriptPayload>()),
+    variant("module_payload", s.of::<ModulePayload>()),
+    // the `type` of the write set payload overrides the `write_set_payload` tag
+    s.of::<WriteSetPayload>(),
+]));
+
+api_schema!(ScriptFunctionPayload, "ScriptFunctionPayload", |s| object(
+    json!({
+        "function": s.of::<ScriptFunctionId>(),
+        "type_arguments": s.array_of::<MoveType>(),
+        "arguments": s.array_of::<MoveValue>(),
+    }),
+    &[],
+));
+
+api_schema!(ScriptFunctionId, "ScriptFunctionId", |s| object(
+    json!({
+        "module": s.of::<MoveModuleId>(),
+        "name": { "type": "string" },
+    }),
+    &[],
+));
+
+api_schema!(ScriptPayload, "ScriptPayload", |s| object(
+    json!({
+        "code": s.of::<HexEncodedBytes>(),
+        "type_arguments": s.array_of::<MoveType>(),
+        "arguments": s.array_of::<MoveValue>(),
+    }),
+    &[],
+));
+
+api_schema!(ModulePayload, "ModulePayload", |s| object(
+    json!({ "code": s.of::<HexEncodedBytes>() }),
+    &[],
+));
+
+api_schema!(WriteSetPayload, "WriteSetPayload", |s| one_of(vec![
+    tagged(
+        "script_write_set",
+        json!({
+            "execute_as": s.of::<Address>(),
+            "script": s.of::<ScriptPayload>(),
+        })
+    ),
+    tagged(
+        "direct_write_set",
+        json!({
+            "changes": s.array_of::<WriteSetChange>(),
+            "events": s.array_of::<Event>(),
+        })
+    ),
+]));
+
+api_schema!(WriteSetChange, "WriteSetChange", |s| one_of(vec![
+    tagged(
+        "delete_module",
+        json!({ "address": s.of::<Address>(), "module": s.of::<MoveModuleId>() })
+    ),
+    tagged(
+        "delete_resource",
+        json!({ "address": s.of::<Address>(), "resource": s.of::<MoveStructTag>() })
+    ),
+    tagged(
+        "write_module",
+        json!({ "address": s.of::<Address>(), "data": s.of::<MoveModule>() })
+    ),
+    tagged(
+        "write_resource",
+        json!({ "address": s.of::<Address>(), "data": s.of::<MoveResource>() })
+    ),
+]));
+
+api_schema!(TransactionSignature, "TransactionSignature", |s| one_of(
+    vec![
+        variant("ed25519_signature", s.of::<Ed25519Signature>()),
+        variant("multi_ed25519_signature", s.of::<MultiEd25519Signature>()),
+        variant("multi_agent_signature", s.of::<MultiAgentSignature>()),
+    ]
+));
+
+api_schema!(AccountSignature, "AccountSignature", |s| one_of(vec![
+    variant("ed25519_signature", s.of::<Ed25519Signature>()),
+    variant("multi_ed25519_signature", s.of::<MultiEd25519Signature>()),
+]));
+
+api_schema!(Ed25519Signature, "Ed25519Signature", |s| object(
+    json!({
+        "public_key": s.of::<HexEncodedBytes>(),
+        "signature": s.of::<HexEncodedBytes>(),
+    }),
+    &[],
+));
+
+api_schema!(MultiEd25519Signature, "MultiEd25519Signature", |s| object(
+    json!({
+        "public_key": s.of::<HexEncodedBytes>(),
+        "signature": s.of::<HexEncodedBytes>(),
+    }),
+    &[],
+));
+
+api_schema!(MultiAgentSignature, "MultiAgentSignature", |s| object(
+    json!({
+        "sender": s.of::<AccountSignature>(),
+        "secondary_signer_addresses": s.array_of::<Address>(),
+        "secondary_signers": s.array_of::<AccountSignature>(),
+    }),
+    &[],
+));
+
+fn validate_at(schemas: &Schemas, schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let resolved = schemas
+            .resolve(reference)
+            .ok_or_else(|| format!("{}: unknown schema {}", path, reference))?;
+        return validate_at(schemas, resolved, value, path);
+    }
+    if let Some(all) = schema["allOf"].as_array() {
+        for s in all {
+            validate_at(schemas, s, value, path)?;
+        }
+    }
+    for key in &["oneOf", "anyOf"] {
+        if let Some(any) = schema[key].as_array() {
+            if !any
+                .iter()
+                .any(|s| validate_at(schemas, s, value, path).is_ok())
+            {
+                return Err(format!("{}: does not match any schema of {}", path, key));
+            }
+        }
+    }
+    if let Some(typ) = schema["type"].as_str() {
+        let matched = match typ {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_u64() || value.is_i64(),
+            "boolean" => value.is_boolean(),
+            _ => false,
+        };
+        if !matched {
+            return Err(format!("{}: expected {}, but got {}", path, typ, value));
+        }
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        if !values.contains(value) {
+            return Err(format!("{}: {} is not one of {:?}", path, value, values));
+        }
+    }
+    if let Some(required) = schema["required"].as_array() {
+        for key in required.iter().filter_map(Value::as_str) {
+            if value.get(key).is_none() {
+                return Err(format!("{}: missing required field {}", path, key));
+            }
+        }
+    }
+    if let Some(properties) = schema["properties"].as_object() {
+        for (key, property) in properties {
+            if let Some(field) = value.get(key) {
+                validate_at(schemas, property, field, &format!("{}.{}", path, key))?;
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            validate_at(schemas, items, item, &format!("{}[{}]", path, i))?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the fields of the objects in `value` are declared by `schema`, which `value` is
+/// known to match.
+fn validate_fields_at(
+    schemas: &Schemas,
+    schema: &Value,
+    value: &Value,
+    path: &str,
+) -> Result<(), String> {
+    match value {
+        Value::Object(fields) => {
+            let mut properties = Map::new();
+            collect_properties(schemas, schema, value, &mut properties);
+            if properties.is_empty() {
+                // free-form object
+                return Ok(());
+            }
+            for (key, field) in fields {
+                let property = properties
+                    .get(key)
+                    .ok_or_else(|| format!("{}: undeclared field {}", path, key))?;
+                validate_fields_at(schemas, property, field, &format!("{}.{}", path, key))?;
+            }
+        }
+        Value::Array(items) => {
+            if let Some(items_schema) = resolve(schemas, schema).get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_fields_at(schemas, items_schema, item, &format!("{}[{}]", path, i))?;
+                }
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Collects the properties declared for the object `value` by `schema`, through `allOf` and the
+/// `oneOf` and `anyOf` schemas that `value` matches.
+fn collect_properties(
+    schemas: &Schemas,
+    schema: &Value,
+    value: &Value,
+    properties: &mut Map<String, Value>,
+) {
+    let schema = resolve(schemas, schema);
+    for key in &["allOf", "oneOf", "anyOf"] {
+        if let Some(subschemas) = schema[key].as_array() {
+            for subschema in subschemas {
+                if validate_at(schemas, subschema, value, "$").is_ok() {
+                    collect_properties(schemas, subschema, value, properties);
+                }
+            }
+        }
+    }
+    if let Some(declared) = schema["properties"].as_object() {
+        for (key, property) in declared {
+            properties
+                .entry(key.clone())
+                .or_insert_with(|| property.clone());
+        }
+    }
+}
+
+/// Follows the `$ref`s of `schema` to the schema they name, unknown names resolve to null.
+fn resolve<'a>(schemas: &'a Schemas, schema: &'a Value) -> &'a Value {
+    static NULL: Value = Value::Null;
+    match schema["$ref"].as_str() {
+        Some(reference) => schemas
+            .resolve(reference)
+            .map_or(&NULL, |resolved| resolve(schemas, resolved)),
+        None => schema,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        move_types::{
+            MoveFunction, MoveFunctionGenericTypeParam, MoveStruct, MoveStructField,
+            MoveStructGenericTypeParam,
+        },
+        schema::{ApiSchema, Schemas},
+        AccountSignature, AccountStateEntry, AccountStatesPage, Address, BlockMetadataTransaction,
+        Ed25519Signature, Error, Event, GenesisTransaction, HashValue, HexEncodedBytes, LedgerInfo,
+        ModulePayload, MoveModule, MoveModuleId, MoveResource, MoveStructTag, MoveStructValue,
+        MoveType, MoveValue, MultiAgentSignature, MultiEd25519Signature, PendingTransaction,
+        ScriptFunctionId, ScriptFunctionPayload, ScriptPayload, SubmitTransactionRequest,
+        Transaction, TransactionInfo, TransactionPayload, TransactionSignature, UserTransaction,
+        UserTransactionRequest, WriteSetChange, WriteSetPayload, U128, U64,
+    };
+
+    use diem_types::{account_address::AccountAddress, event::EventKey};
+    use move_binary_format::file_format::{basic_test_module, Ability, AbilitySet, Visibility};
+    use move_core_types::{identifier::Identifier, language_storage::StructTag};
+    use resource_viewer::{AnnotatedMoveStruct, AnnotatedMoveValue};
+
+    use serde::Serialize;
+    use serde_json::json;
+    use std::collections::BTreeSet;
+    use warp::http::StatusCode;
+
+    #[test]
+    fn test_schemas_of_recursive_types() {
+        let mut schemas = Schemas::new();
+        let reference = schemas.of::<MoveType>();
+        assert_eq!(reference, json!({"$ref": "#/components/schemas/MoveType"}));
+
+        let value = json!({
+            "type": "vector",
+            "items": {"type": "reference", "mutable": false, "to": {"type": "u8"}}
+        });
+        schemas.validate(&reference, &value).unwrap();
+        assert!(schemas
+            .validate(&reference, &json!({"type": "unknown"}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_schemas_include_referenced_types() {
+        let mut schemas = Schemas::new();
+        schemas.of::<Transaction>();
+        let schemas = schemas.into_inner();
+        for name in &[
+            "Transaction",
+            "UserTransaction",
+            "UserTransactionRequest",
+            "TransactionPayload",
+            "WriteSetChange",
+            "MoveModule",
+            "Event",
+            "U64",
+        ] {
+            assert!(schemas.contains_key(*name), "missing schema {}", name);
+        }
+        assert!(schemas.values().all(|schema| !schema.is_null()));
+    }
+
+    #[test]
+    fn test_validate_missing_field() {
+        let mut schemas = Schemas::new();
+        let reference = schemas.of::<LedgerInfo>();
+        let err = schemas
+            .validate(&reference, &json!({"chain_id": 4, "ledger_version": "1"}))
+            .unwrap_err();
+        assert_eq!(err, "$: missing required field ledger_timestamp");
+    }
+
+    #[test]
+    fn test_serialized_values_match_schemas() {
+        let mut checker = SchemaChecker::default();
+
+        checker.check::<Address>(address());
+        checker.check::<U64>(U64::from(u64::MAX));
+        checker.check::<U128>(U128::from(u128::MAX));
+        checker.check::<HashValue>(hash());
+        checker.check::<HexEncodedBytes>(bytes());
+        checker.check::<LedgerInfo>(LedgerInfo {
+            chain_id: 4,
+            ledger_version: 1.into(),
+            ledger_timestamp: 2.into(),
+        });
+        checker.check::<Error>(Error::new(StatusCode::NOT_FOUND, "not found".to_owned()));
+        checker.check::<Error>(Error::new_with_data(
+            StatusCode::NOT_FOUND,
+            "not found".to_owned(),
+            json!({ "ledger_version": "1" }),
+        ));
+
+        let account_state_entry = AccountStateEntry {
+            key: hash(),
+            address: Some(address()),
+            state: bytes(),
+        };
+        checker.check::<AccountStateEntry>(&account_state_entry);
+        checker.check::<AccountStatesPage>(AccountStatesPage {
+            accounts: vec![account_state_entry],
+            proof: Some(vec![hash()]),
+            next_start: Some(hash()),
+        });
+
+        for value in vec![
+            MoveValue::U8(1),
+            MoveValue::U64(1.into()),
+            MoveValue::U128(1.into()),
+            MoveValue::Bool(true),
+            MoveValue::Address(address()),
+            MoveValue::Vector(vec![MoveValue::U8(1)]),
+            MoveValue::Bytes(bytes()),
+            MoveValue::Struct(MoveStructValue::from(annotated_move_struct())),
+        ] {
+            checker.check::<MoveValue>(value);
+        }
+        for typ in vec![
+            MoveType::Bool,
+            MoveType::U8,
+            MoveType::U64,
+            MoveType::U128,
+            MoveType::Address,
+            MoveType::Signer,
+            MoveType::Vector {
+                items: Box::new(MoveType::U8),
+            },
+            MoveType::Struct(move_struct_tag()),
+            MoveType::GenericTypeParam { index: 0 },
+            MoveType::Reference {
+                mutable: true,
+                to: Box::new(MoveType::Signer),
+            },
+        ] {
+            checker.check::<MoveType>(typ);
+        }
+        checker.check::<MoveStructTag>(move_struct_tag());
+        checker.check::<MoveResource>(MoveResource::from(annotated_move_struct()));
+        checker.check::<MoveModuleId>(move_module_id());
+
+        let move_module = move_module();
+        checker.check::<MoveStruct>(&move_module.structs[0]);
+        checker.check::<MoveStructField>(&move_module.structs[0].fields[0]);
+        checker.check::<MoveStructGenericTypeParam>(&move_module.structs[0].generic_type_params[0]);
+        checker.check::<MoveFunction>(&move_module.exposed_functions[0]);
+        checker.check::<MoveFunctionGenericTypeParam>(
+            &move_module.exposed_functions[0].generic_type_params[0],
+        );
+        checker.check::<MoveModule>(&move_module);
+
+        let ed25519_signature = Ed25519Signature {
+            public_key: bytes(),
+            signature: bytes(),
+        };
+        let multi_ed25519_signature = MultiEd25519Signature {
+            public_key: bytes(),
+            signature: bytes(),
+        };
+        let multi_agent_signature = MultiAgentSignature {
+            sender: AccountSignature::Ed25519Signature(ed25519_signature.clone()),
+            secondary_signer_addresses: vec![address()],
+            secondary_signers: vec![AccountSignature::MultiEd25519Signature(
+                multi_ed25519_signature.clone(),
+            )],
+        };
+        checker.check::<Ed25519Signature>(&ed25519_signature);
+        checker.check::<MultiEd25519Signature>(&multi_ed25519_signature);
+        checker.check::<MultiAgentSignature>(&multi_agent_signature);
+        for signature in vec![
+            AccountSignature::Ed25519Signature(ed25519_signature.clone()),
+            AccountSignature::MultiEd25519Signature(multi_ed25519_signature.clone()),
+        ] {
+            checker.check::<AccountSignature>(signature);
+        }
+        for signature in vec![
+            TransactionSignature::Ed25519Signature(ed25519_signature.clone()),
+            TransactionSignature::MultiEd25519Signature(multi_ed25519_signature),
+            TransactionSignature::MultiAgentSignature(multi_agent_signature),
+        ] {
+            checker.check::<TransactionSignature>(signature);
+        }
+        let signature = TransactionSignature::Ed25519Signature(ed25519_signature);
+
+        let event = Event {
+            key: EventKey::new_from_address(&address().into(), 0),
+            sequence_number: 1.into(),
+            typ: MoveType::Struct(move_struct_tag()),
+            data: MoveValue::Struct(MoveStructValue::from(annotated_move_struct())),
+        };
+        checker.check::<Event>(&event);
+
+        let script_function_payload = ScriptFunctionPayload {
+            function: ScriptFunctionId {
+                module: move_module_id(),
+                name: identifier("foo"),
+            },
+            type_arguments: vec![MoveType::U64],
+            arguments: vec![json!("1")],
+        };
+        let script_payload = ScriptPayload {
+            code: bytes(),
+            type_arguments: vec![MoveType::U64],
+            arguments: vec![json!("1")],
+        };
+        let module_payload = ModulePayload { code: bytes() };
+        checker.check::<ScriptFunctionId>(&script_function_payload.function);
+        checker.check::<ScriptFunctionPayload>(&script_function_payload);
+        checker.check::<ScriptPayload>(&script_payload);
+        checker.check::<ModulePayload>(&module_payload);
+
+        let write_set_changes = vec![
+            WriteSetChange::DeleteModule {
+                address: address(),
+                module: move_module_id(),
+            },
+            WriteSetChange::DeleteResource {
+                address: address(),
+                resource: move_struct_tag(),
+            },
+            WriteSetChange::WriteModule {
+                address: address(),
+                data: move_module,
+            },
+            WriteSetChange::WriteResource {
+                address: address(),
+                data: MoveResource::from(annotated_move_struct()),
+            },
+        ];
+        for change in &write_set_changes {
+            checker.check::<WriteSetChange>(change);
+        }
+        let direct_write_set = WriteSetPayload::DirectWriteSet {
+            changes: write_set_changes,
+            events: vec![event.clone()],
+        };
+        let write_set_payloads = vec![
+            WriteSetPayload::ScriptWriteSet {
+                execute_as: address(),
+                script: script_payload.clone(),
+            },
+            direct_write_set.clone(),
+        ];
+        for payload in &write_set_payloads {
+            checker.check::<WriteSetPayload>(payload);
+        }
+        for payload in vec![
+            TransactionPayload::ScriptFunctionPayload(script_function_payload.clone()),
+            TransactionPayload::ScriptPayload(script_payload),
+            TransactionPayload::ModulePayload(module_payload),
+        ]
+        .into_iter()
+        .chain(
+            write_set_payloads
+                .into_iter()
+                .map(TransactionPayload::WriteSetPayload),
+        ) {
+            checker.check::<TransactionPayload>(payload);
+        }
+
+        let request = UserTransactionRequest {
+            sender: address(),
+            sequence_number: 1.into(),
+            max_gas_amount: 1.into(),
+            gas_unit_price: 1.into(),
+            gas_currency_code: "XUS".to_owned(),
+            expiration_timestamp_secs: 1.into(),
+            payload: TransactionPayload::ScriptFunctionPayload(script_function_payload),
+        };
+        checker.check::<UserTransactionRequest>(&request);
+        checker.check::<SubmitTransactionRequest>(SubmitTransactionRequest {
+            user_transaction_request: request.clone(),
+            signature: signature.clone(),
+        });
+
+        let info = TransactionInfo {
+            version: 1.into(),
+            hash: hash(),
+            state_root_hash: hash(),
+            event_root_hash: hash(),
+            gas_used: 1.into(),
+            success: true,
+            vm_status: "Executed successfully".to_owned(),
+        };
+        let pending_transaction = PendingTransaction {
+            hash: hash(),
+            request: request.clone(),
+            signature: signature.clone(),
+        };
+        let user_transaction = UserTransaction {
+            info: info.clone(),
+            request,
+            signature,
+            events: vec![event.clone()],
+            timestamp: 1.into(),
+        };
+        let genesis_transaction = GenesisTransaction {
+            info: info.clone(),
+            payload: direct_write_set,
+            events: vec![event],
+        };
+        let block_metadata_transaction = BlockMetadataTransaction {
+            info,
+            id: hash(),
+            round: 1.into(),
+            previous_block_votes: vec![address()],
+            proposer: address(),
+            timestamp: 1.into(),
+        };
+        checker.check::<PendingTransaction>(&pending_transaction);
+        checker.check::<UserTransaction>(&user_transaction);
+        checker.check::<GenesisTransaction>(&genesis_transaction);
+        checker.check::<BlockMetadataTransaction>(&block_metadata_transaction);
+        for transaction in vec![
+            Transaction::PendingTransaction(pending_transaction),
+            Transaction::UserTransaction(Box::new(user_transaction)),
+            Transaction::GenesisTransaction(genesis_transaction),
+            Transaction::BlockMetadataTransaction(block_metadata_transaction),
+        ] {
+            checker.check::<Transaction>(transaction);
+        }
+
+        // every schema referenced by the types above is checked against a value of its own
+        let checked = checker.checked;
+        let registered: BTreeSet<String> = checker.schemas.into_inner().into_keys().collect();
+        assert_eq!(
+            registered,
+            checked.into_iter().map(str::to_owned).collect(),
+            "every ApiSchema type needs a value checked against its schema"
+        );
+    }
+
+    #[test]
+    fn test_validate_undeclared_field() {
+        let mut schemas = Schemas::new();
+        let reference = schemas.of::<LedgerInfo>();
+        let err = schemas
+            .validate(
+                &reference,
+                &json!({
+                    "chain_id": 4,
+                    "ledger_version": "1",
+                    "ledger_timestamp": "2",
+                    "epoch": "3",
+                }),
+            )
+            .unwrap_err();
+        assert_eq!(err, "$: undeclared field epoch");
+    }
+
+    /// Validates the JSON of values against the schemas of the API types, and keeps track of the
+    /// schemas checked.
+    #[derive(Default)]
+    struct SchemaChecker {
+        schemas: Schemas,
+        checked: BTreeSet<&'static str>,
+    }
+
+    impl SchemaChecker {
+        fn check<T: ApiSchema>(&mut self, value: impl Serialize) {
+            let reference = self.schemas.of::<T>();
+            let value = serde_json::to_value(value).unwrap();
+            if let Err(e) = self.schemas.validate(&reference, &value) {
+                panic!("{}: {}\nvalue: {}", T::name(), e, value);
+            }
+            self.checked.insert(T::name());
+        }
+    }
+
+    fn address() -> Address {
+        "0x1".parse().unwrap()
+    }
+
+    fn hash() -> HashValue {
+        diem_crypto::HashValue::zero().into()
+    }
+
+    fn bytes() -> HexEncodedBytes {
+        vec![0xca, 0xfe].into()
+    }
+
+    fn identifier(id: &str) -> Identifier {
+        Identifier::new(id).unwrap()
+    }
+
+    fn move_module_id() -> MoveModuleId {
+        MoveModuleId {
+            address: address(),
+            name: identifier("Diem"),
+        }
+    }
+
+    fn move_struct_tag() -> MoveStructTag {
+        MoveStructTag {
+            address: address(),
+            module: identifier("Diem"),
+            name: identifier("Diem"),
+            generic_type_params: vec![MoveType::U64],
+        }
+    }
+
+    fn annotated_move_struct() -> AnnotatedMoveStruct {
+        AnnotatedMoveStruct {
+            abilities: AbilitySet::EMPTY,
+            type_: StructTag {
+                address: AccountAddress::from(address()),
+                module: identifier("Diem"),
+                name: identifier("Diem"),
+                type_params: vec![],
+            },
+            value: vec![(identifier("value"), AnnotatedMoveValue::U64(1))],
+        }
+    }
+
+    /// A module with a public generic function and a generic struct.
+    fn move_module() -> MoveModule {
+        let mut module = basic_test_module();
+        module.function_defs[0].visibility = Visibility::Public;
+        let mut move_struct = MoveStruct::from((&module, &module.struct_defs[0]));
+        move_struct
+            .generic_type_params
+            .push(MoveStructGenericTypeParam {
+                constraints: vec![Ability::Store.into()],
+                is_phantom: false,
+            });
+        let mut move_function = MoveFunction::from((&module, &module.function_defs[0]));
+        move_function
+            .generic_type_params
+            .push(MoveFunctionGenericTypeParam {
+                constraints: vec![Ability::Copy.into(), Ability::Drop.into()],
+            });
+        move_function.params.push(MoveType::Signer);
+        move_function.return_.push(MoveType::U64);
+        MoveModule {
+            address: address(),
+            name: identifier("Diem"),
+            friends: vec![move_module_id()],
+            exposed_functions: vec![move_function],
+            structs: vec![move_struct],
+        }
+    }
+}
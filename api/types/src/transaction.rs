@@ -0,0 +1,462 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    Address, HashValue, HexEncodedBytes, MoveModule, MoveModuleId, MoveResource, MoveStructTag,
+    MoveType, MoveValue, U64,
+};
+
+use diem_crypto::{
+    ed25519::{self, Ed25519PublicKey},
+    multi_ed25519::{self, MultiEd25519PublicKey},
+};
+use diem_types::{
+    account_address::AccountAddress,
+    event::EventKey,
+    transaction::{
+        authenticator::{AccountAuthenticator, TransactionAuthenticator},
+        TransactionInfoTrait,
+    },
+    vm_status::KeptVMStatus,
+};
+use move_core_types::identifier::Identifier;
+
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::{From, TryFrom, TryInto},
+    fmt,
+    str::FromStr,
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transaction {
+    PendingTransaction(PendingTransaction),
+    UserTransaction(Box<UserTransaction>),
+    GenesisTransaction(GenesisTransaction),
+    BlockMetadataTransaction(BlockMetadataTransaction),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TransactionInfo {
+    pub version: U64,
+    pub hash: HashValue,
+    pub state_root_hash: HashValue,
+    pub event_root_hash: HashValue,
+    pub gas_used: U64,
+    pub success: bool,
+    pub vm_status: String,
+}
+
+impl<T: TransactionInfoTrait> From<(u64, &T)> for TransactionInfo {
+    fn from((version, info): (u64, &T)) -> Self {
+        Self {
+            version: version.into(),
+            hash: info.transaction_hash().into(),
+            state_root_hash: info.state_root_hash().into(),
+            event_root_hash: info.event_root_hash().into(),
+            gas_used: info.gas_used().into(),
+            success: info.status() == &KeptVMStatus::Executed,
+            vm_status: format!("{:?}", info.status()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PendingTransaction {
+    pub hash: HashValue,
+    #[serde(flatten)]
+    pub request: UserTransactionRequest,
+    pub signature: TransactionSignature,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct UserTransaction {
+    #[serde(flatten)]
+    pub info: TransactionInfo,
+    #[serde(flatten)]
+    pub request: UserTransactionRequest,
+    pub signature: TransactionSignature,
+    pub events: Vec<Event>,
+    pub timestamp: U64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct GenesisTransaction {
+    #[serde(flatten)]
+    pub info: TransactionInfo,
+    pub payload: WriteSetPayload,
+    pub events: Vec<Event>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BlockMetadataTransaction {
+    #[serde(flatten)]
+    pub info: TransactionInfo,
+    pub id: HashValue,
+    pub round: U64,
+    pub previous_block_votes: Vec<Address>,
+    pub proposer: Address,
+    pub timestamp: U64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UserTransactionRequest {
+    pub sender: Address,
+    pub sequence_number: U64,
+    pub max_gas_amount: U64,
+    pub gas_unit_price: U64,
+    pub gas_currency_code: String,
+    pub expiration_timestamp_secs: U64,
+    pub payload: TransactionPayload,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SubmitTransactionRequest {
+    #[serde(flatten)]
+    pub user_transaction_request: UserTransactionRequest,
+    pub signature: TransactionSignature,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Event {
+    pub key: EventKey,
+    pub sequence_number: U64,
+    #[serde(rename = "type")]
+    pub typ: MoveType,
+    pub data: MoveValue,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransactionPayload {
+    ScriptFunctionPayload(ScriptFunctionPayload),
+    ScriptPayload(ScriptPayload),
+    ModulePayload(ModulePayload),
+    #[serde(skip_deserializing)]
+    WriteSetPayload(WriteSetPayload),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScriptFunctionPayload {
+    pub function: ScriptFunctionId,
+    pub type_arguments: Vec<MoveType>,
+    pub arguments: Vec<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScriptFunctionId {
+    pub module: MoveModuleId,
+    pub name: Identifier,
+}
+
+impl fmt::Display for ScriptFunctionId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}::{}", self.module, self.name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScriptPayload {
+    pub code: HexEncodedBytes,
+    pub type_arguments: Vec<MoveType>,
+    pub arguments: Vec<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModulePayload {
+    pub code: HexEncodedBytes,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WriteSetPayload {
+    ScriptWriteSet {
+        execute_as: Address,
+        script: ScriptPayload,
+    },
+    DirectWriteSet {
+        changes: Vec<WriteSetChange>,
+        events: Vec<Event>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WriteSetChange {
+    DeleteModule {
+        address: Address,
+        module: MoveModuleId,
+    },
+    DeleteResource {
+        address: Address,
+        resource: MoveStructTag,
+    },
+    WriteModule {
+        address: Address,
+        data: MoveModule,
+    },
+    WriteResource {
+        address: Address,
+        data: MoveResource,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransactionSignature {
+    Ed25519Signature(Ed25519Signature),
+    MultiEd25519Signature(MultiEd25519Signature),
+    MultiAgentSignature(MultiAgentSignature),
+}
+
+impl From<TransactionAuthenticator> for TransactionSignature {
+    fn from(auth: TransactionAuthenticator) -> Self {
+        match auth {
+            TransactionAuthenticator::Ed25519 {
+                public_key,
+                signature,
+            } => Self::Ed25519Signature(Ed25519Signature {
+                public_key: public_key.unvalidated().to_bytes().to_vec().into(),
+                signature: signature.to_bytes().to_vec().into(),
+            }),
+            TransactionAuthenticator::MultiEd25519 {
+                public_key,
+                signature,
+            } => Self::MultiEd25519Signature(MultiEd25519Signature {
+                public_key: public_key.to_bytes().into(),
+                signature: signature.to_bytes().into(),
+            }),
+            TransactionAuthenticator::MultiAgent {
+                sender,
+                secondary_signer_addresses,
+                secondary_signers,
+            } => Self::MultiAgentSignature(MultiAgentSignature {
+                sender: sender.into(),
+                secondary_signer_addresses: secondary_signer_addresses
+                    .into_iter()
+                    .map(Address::from)
+                    .collect(),
+                secondary_signers: secondary_signers
+                    .into_iter()
+                    .map(AccountSignature::from)
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<TransactionSignature> for TransactionAuthenticator {
+    type Error = anyhow::Error;
+
+    fn try_from(signature: TransactionSignature) -> anyhow::Result<Self> {
+        Ok(match signature {
+            TransactionSignature::Ed25519Signature(s) => {
+                TransactionAuthenticator::ed25519(s.public_key()?, s.signature()?)
+            }
+            TransactionSignature::MultiEd25519Signature(s) => {
+                TransactionAuthenticator::multi_ed25519(s.public_key()?, s.signature()?)
+            }
+            TransactionSignature::MultiAgentSignature(s) => TransactionAuthenticator::multi_agent(
+                s.sender.try_into()?,
+                s.secondary_signer_addresses
+                    .into_iter()
+                    .map(AccountAddress::from)
+                    .collect(),
+                s.secondary_signers
+                    .into_iter()
+                    .map(AccountAuthenticator::try_from)
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            ),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ed25519Signature {
+    pub public_key: HexEncodedBytes,
+    pub signature: HexEncodedBytes,
+}
+
+impl Ed25519Signature {
+    fn public_key(&self) -> anyhow::Result<Ed25519PublicKey> {
+        Ok(Ed25519PublicKey::try_from(self.public_key.inner())?)
+    }
+
+    fn signature(&self) -> anyhow::Result<ed25519::Ed25519Signature> {
+        Ok(ed25519::Ed25519Signature::try_from(self.signature.inner())?)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MultiEd25519Signature {
+    pub public_key: HexEncodedBytes,
+    pub signature: HexEncodedBytes,
+}
+
+impl MultiEd25519Signature {
+    fn public_key(&self) -> anyhow::Result<MultiEd25519PublicKey> {
+        Ok(MultiEd25519PublicKey::try_from(self.public_key.inner())?)
+    }
+
+    fn signature(&self) -> anyhow::Result<multi_ed25519::MultiEd25519Signature> {
+        Ok(multi_ed25519::MultiEd25519Signature::try_from(
+            self.signature.inner(),
+        )?)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MultiAgentSignature {
+    pub sender: AccountSignature,
+    pub secondary_signer_addresses: Vec<Address>,
+    pub secondary_signers: Vec<AccountSignature>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AccountSignature {
+    Ed25519Signature(Ed25519Signature),
+    MultiEd25519Signature(MultiEd25519Signature),
+}
+
+impl From<AccountAuthenticator> for AccountSignature {
+    fn from(auth: AccountAuthenticator) -> Self {
+        match auth {
+            AccountAuthenticator::Ed25519 {
+                public_key,
+                signature,
+            } => Self::Ed25519Signature(Ed25519Signature {
+                public_key: public_key.unvalidated().to_bytes().to_vec().into(),
+                signature: signature.to_bytes().to_vec().into(),
+            }),
+            AccountAuthenticator::MultiEd25519 {
+                public_key,
+                signature,
+            } => Self::MultiEd25519Signature(MultiEd25519Signature {
+                public_key: public_key.to_bytes().into(),
+                signature: signature.to_bytes().into(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<AccountSignature> for AccountAuthenticator {
+    type Error = anyhow::Error;
+
+    fn try_from(signature: AccountSignature) -> anyhow::Result<Self> {
+        Ok(match signature {
+            AccountSignature::Ed25519Signature(s) => {
+                AccountAuthenticator::ed25519(s.public_key()?, s.signature()?)
+            }
+            AccountSignature::MultiEd25519Signature(s) => {
+                AccountAuthenticator::multi_ed25519(s.public_key()?, s.signature()?)
+            }
+        })
+    }
+}
+
+/// Identifies a committed transaction either by its hash (`0x` prefixed hex string) or by its
+/// version.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransactionId {
+    Hash(HashValue),
+    Version(u64),
+}
+
+impl FromStr for TransactionId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self, anyhow::Error> {
+        if s.starts_with("0x") {
+            Ok(Self::Hash(s.parse().map_err(|_| {
+                anyhow::format_err!("invalid transaction hash: {}", s)
+            })?))
+        } else {
+            Ok(Self::Version(s.parse().map_err(|_| {
+                anyhow::format_err!("invalid transaction version: {}", s)
+            })?))
+        }
+    }
+}
+
+impl fmt::Display for TransactionId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Hash(h) => write!(f, "hash {}", h),
+            Self::Version(v) => write!(f, "version {}", v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transaction::TransactionId, HashValue, SubmitTransactionRequest};
+
+    use serde_json::json;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_transaction_id() {
+        let hash = "0x0000000000000000000000000000000000000000000000000000000000000001";
+        assert_eq!(
+            TransactionId::from_str(hash).unwrap(),
+            TransactionId::Hash(HashValue::from_str(hash).unwrap())
+        );
+        assert_eq!(
+            TransactionId::from_str("12").unwrap(),
+            TransactionId::Version(12)
+        );
+        assert_eq!(
+            TransactionId::from_str("0x1").unwrap_err().to_string(),
+            "invalid transaction hash: 0x1"
+        );
+        assert_eq!(
+            TransactionId::from_str("-1").unwrap_err().to_string(),
+            "invalid transaction version: -1"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_submit_transaction_request() {
+        let req: SubmitTransactionRequest = serde_json::from_value(json!({
+            "sender": "0xdd",
+            "sequence_number": "1",
+            "max_gas_amount": "1000000",
+            "gas_unit_price": "0",
+            "gas_currency_code": "XUS",
+            "expiration_timestamp_secs": "1635447454",
+            "payload": {
+                "type": "script_function_payload",
+                "function": {
+                    "module": {"address": "0x1", "name": "PaymentScripts"},
+                    "name": "peer_to_peer_with_metadata"
+                },
+                "type_arguments": [
+                    {
+                        "type": "struct",
+                        "address": "0x1",
+                        "module": "XUS",
+                        "name": "XUS",
+                        "generic_type_params": []
+                    }
+                ],
+                "arguments": ["0x1668f6be25668c1a17cd8caf6b8d2f25", "100", "0x", "0x"]
+            },
+            "signature": {
+                "type": "ed25519_signature",
+                "public_key": "0x00",
+                "signature": "0x00"
+            }
+        }))
+        .unwrap();
+
+        let value = serde_json::to_value(&req).unwrap();
+        assert_eq!(
+            value["payload"]["function"]["name"],
+            "peer_to_peer_with_metadata"
+        );
+        assert_eq!(value["sequence_number"], "1");
+        assert_eq!(value["signature"]["type"], "ed25519_signature");
+    }
+}
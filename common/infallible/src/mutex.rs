@@ -22,6 +22,13 @@ impl<T> Mutex<T> {
             .lock()
             .expect("diem cannot currently handle a poisoned lock")
     }
+
+    /// return the owned type consuming the lock
+    pub fn into_inner(self) -> T {
+        self.0
+            .into_inner()
+            .expect("diem cannot currently handle a poisoned lock")
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_global_constants::{
     CONSENSUS_KEY, FULLNODE_NETWORK_KEY, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT, OWNER_KEY,
     SAFETY_DATA, VALIDATOR_NETWORK_KEY, WAYPOINT,
@@ -214,8 +214,15 @@ fn compute_genesis(
     genesis_path: &Path,
     db_path: &Path,
 ) -> Result<(DbReaderWriter, Waypoint), Error> {
-    let diemdb = DiemDB::open(db_path, false, None, RocksdbConfig::default())
-        .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+    let diemdb = DiemDB::open(
+        db_path,
+        false,
+        None,
+        None,
+        RocksdbConfig::default(),
+        SecondaryIndexesConfig::default(),
+    )
+    .map_err(|e| Error::UnexpectedError(e.to_string()))?;
     let db_rw = DbReaderWriter::new(diemdb);
 
     let mut file = File::open(genesis_path)
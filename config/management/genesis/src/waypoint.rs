@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_management::{config::ConfigPath, error::Error, secure_backend::SharedBackend};
 use diem_temppath::TempPath;
 use diem_types::{chain_id::ChainId, transaction::Transaction, waypoint::Waypoint};
@@ -40,8 +40,15 @@ impl CreateWaypoint {
 
 pub fn create_genesis_waypoint(genesis: &Transaction) -> Result<Waypoint, Error> {
     let path = TempPath::new();
-    let diemdb = DiemDB::open(&path, false, None, RocksdbConfig::default())
-        .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+    let diemdb = DiemDB::open(
+        &path,
+        false,
+        None,
+        None,
+        RocksdbConfig::default(),
+        SecondaryIndexesConfig::default(),
+    )
+    .map_err(|e| Error::UnexpectedError(e.to_string()))?;
     let db_rw = DbReaderWriter::new(diemdb);
 
     db_bootstrapper::generate_waypoint::<DiemVM>(&db_rw, genesis)
@@ -23,6 +23,9 @@ pub struct ExecutionConfig {
     pub service: ExecutionCorrectnessService,
     pub backend: SecureBackend,
     pub network_timeout_ms: u64,
+    /// The max number of state tree nodes the executor holds in memory after a commit, beyond
+    /// which the least recently accessed ones are evicted (and read from storage again if needed).
+    pub max_in_mem_state_tree_nodes: usize,
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -40,8 +43,9 @@ impl std::fmt::Debug for ExecutionConfig {
         )?;
         write!(
             f,
-            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?} }}",
-            self.sign_vote_proposal, self.service, self.backend
+            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?}, \
+             max_in_mem_state_tree_nodes: {:?} }}",
+            self.sign_vote_proposal, self.service, self.backend, self.max_in_mem_state_tree_nodes
         )?;
         self.service.fmt(f)
     }
@@ -57,6 +61,7 @@ impl Default for ExecutionConfig {
             sign_vote_proposal: true,
             // Default value of 30 seconds for the network timeout.
             network_timeout_ms: 30_000,
+            max_in_mem_state_tree_nodes: 1_000_000,
         }
     }
 }
@@ -3,11 +3,50 @@
 
 use serde::{Deserialize, Serialize};
 
+/// How a node without the latest state gets it from its peers.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrappingMode {
+    /// Fetch and execute every transaction since genesis.
+    ExecuteTransactionsFromGenesis,
+    /// Download the account states at a recent version (without the transactions before it),
+    /// then sync transactions from that version on.
+    DownloadLatestAccountStates,
+}
+
+impl Default for BootstrappingMode {
+    fn default() -> Self {
+        BootstrappingMode::ExecuteTransactionsFromGenesis
+    }
+}
+
+/// How a node syncs the transactions after the state it has.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContinuousSyncingMode {
+    /// Fetch the transactions and execute them.
+    ExecuteTransactions,
+    /// Fetch the transaction outputs and apply their write sets and events, without executing the
+    /// transactions. This is cheaper (in CPU) than execution. Once it has synced to its waypoint,
+    /// the node requests outputs (instead of transactions) from its peers.
+    ApplyTransactionOutputs,
+}
+
+impl Default for ContinuousSyncingMode {
+    fn default() -> Self {
+        ContinuousSyncingMode::ExecuteTransactions
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StateSyncConfig {
+    // How the node syncs to the latest state when it starts far behind its peers
+    pub bootstrapping_mode: BootstrappingMode,
     // Size of chunk to request for state synchronization
     pub chunk_limit: u64,
+    // How the node syncs the transactions after the state it has
+    pub continuous_syncing_mode: ContinuousSyncingMode,
     // The timeout of the state sync client to process a commit notification (in milliseconds)
     pub client_commit_timeout_ms: u64,
     // default timeout used for long polling to remote peer
@@ -25,6 +64,8 @@ pub struct StateSyncConfig {
     // The timeout for ensuring sync requests are making progress (i.e., the maximum time between
     // commits when processing a sync request).
     pub sync_request_timeout_ms: u64,
+    // The configuration of the storage service, which serves data to syncing peers
+    pub storage_service: StorageServiceConfig,
     // interval used for checking state synchronization progress
     pub tick_interval_ms: u64,
 }
@@ -32,7 +73,9 @@ pub struct StateSyncConfig {
 impl Default for StateSyncConfig {
     fn default() -> Self {
         Self {
+            bootstrapping_mode: BootstrappingMode::default(),
             chunk_limit: 1000,
+            continuous_syncing_mode: ContinuousSyncingMode::default(),
             client_commit_timeout_ms: 5_000,
             long_poll_timeout_ms: 10_000,
             max_chunk_limit: 1000,
@@ -40,7 +83,47 @@ impl Default for StateSyncConfig {
             mempool_commit_timeout_ms: 5_000,
             multicast_timeout_ms: 30_000,
             sync_request_timeout_ms: 60_000,
+            storage_service: StorageServiceConfig::default(),
             tick_interval_ms: 100,
         }
     }
 }
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageServiceConfig {
+    // Maximum number of account states per chunk
+    pub max_account_states_chunk_size: u64,
+    // Maximum number of epoch ending ledger infos per chunk
+    pub max_epoch_chunk_size: u64,
+    // Maximum number of pending network messages
+    pub max_network_channel_size: u64,
+    // Maximum number of requests per peer waiting for new data (the rest are rejected)
+    pub max_pending_requests_per_peer: u64,
+    // Maximum number of requests served per peer per second (the rest are rejected)
+    pub max_requests_per_second: u64,
+    // Maximum time a request may wait for new data (in milliseconds)
+    pub max_subscription_wait_ms: u64,
+    // Maximum number of transactions per chunk
+    pub max_transaction_chunk_size: u64,
+    // Maximum number of transaction outputs per chunk
+    pub max_transaction_output_chunk_size: u64,
+    // Interval for expiring the pending requests (they are served as soon as storage commits)
+    pub pending_requests_expiry_interval_ms: u64,
+}
+
+impl Default for StorageServiceConfig {
+    fn default() -> Self {
+        Self {
+            max_account_states_chunk_size: 1000,
+            max_epoch_chunk_size: 1000,
+            max_network_channel_size: 4000,
+            max_pending_requests_per_peer: 10,
+            max_requests_per_second: 100,
+            max_subscription_wait_ms: 30_000,
+            max_transaction_chunk_size: 1000,
+            max_transaction_output_chunk_size: 1000,
+            pending_requests_expiry_interval_ms: 100,
+        }
+    }
+}
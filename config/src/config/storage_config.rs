@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::utils;
+use crate::{
+    config::{invariant, Error},
+    utils,
+};
 use serde::{Deserialize, Serialize};
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
@@ -32,22 +35,61 @@ impl Default for RocksdbConfig {
     }
 }
 
+/// Optional secondary indexes on the transactions, on top of the ones by hash and by sender.
+/// Each one costs an extra write per indexed transaction, and they only cover the transactions
+/// committed while enabled.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecondaryIndexesConfig {
+    /// Index transactions by the types of the events they emitted.
+    pub by_event_type: bool,
+    /// Index user transactions by the script function they called.
+    pub by_script_function: bool,
+}
+
+/// The key-value store DiemDB keeps its data in.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageEngine {
+    /// RocksDB under `dir`.
+    RocksDb,
+    /// Ordered maps in memory, nothing is persisted. Only meant for tests and benchmarks.
+    InMemory,
+}
+
+impl Default for StorageEngine {
+    fn default() -> Self {
+        StorageEngine::RocksDb
+    }
+}
+
+/// The smallest ledger prune window accepted. The ledger history must outlive the state sync and
+/// RPC requests reading it, which are proven against a recent ledger info.
+pub const MIN_LEDGER_PRUNE_WINDOW: u64 = 10_000;
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StorageConfig {
     pub address: SocketAddr,
     pub backup_service_address: SocketAddr,
     pub dir: PathBuf,
+    /// Where DiemDB keeps its data, `dir` is ignored unless it's RocksDB.
+    pub engine: StorageEngine,
     pub grpc_max_receive_len: Option<i32>,
     /// None disables pruning. The windows is in number of versions, consider system tps
     /// (transaction per second) when calculating proper window.
     pub prune_window: Option<u64>,
+    /// None disables pruning of the ledger history, i.e. transactions, transaction infos, events
+    /// and the indexes on them. The window is in number of versions, and can't be smaller than
+    /// `MIN_LEDGER_PRUNE_WINDOW`.
+    pub ledger_prune_window: Option<u64>,
     #[serde(skip)]
     data_dir: PathBuf,
     /// Read, Write, Connect timeout for network operations in milliseconds
     pub timeout_ms: u64,
     /// Rocksdb-specific configurations
     pub rocksdb_config: RocksdbConfig,
+    pub secondary_indexes: SecondaryIndexesConfig,
 }
 
 impl Default for StorageConfig {
@@ -56,6 +98,7 @@ impl Default for StorageConfig {
             address: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 6666),
             backup_service_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 6186),
             dir: PathBuf::from("db"),
+            engine: StorageEngine::default(),
             grpc_max_receive_len: Some(100_000_000),
             // The prune window must at least out live a RPC request because its sub requests are
             // to return a consistent view of the DB at exactly same version. Considering a few
@@ -64,10 +107,13 @@ impl Default for StorageConfig {
             // conservatively safe minimal prune window. It'll take a few Gigabytes of disk space
             // depending on the size of an average account blob.
             prune_window: Some(1_000_000),
+            // Ledger history is kept in full by default, for nodes serving historical queries.
+            ledger_prune_window: None,
             data_dir: PathBuf::from("/opt/diem/data"),
             // Default read/write/connection timeout, in milliseconds
             timeout_ms: 30_000,
             rocksdb_config: RocksdbConfig::default(),
+            secondary_indexes: SecondaryIndexesConfig::default(),
         }
     }
 }
@@ -85,9 +131,42 @@ impl StorageConfig {
         self.data_dir = data_dir;
     }
 
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(ledger_prune_window) = self.ledger_prune_window {
+            invariant(
+                ledger_prune_window >= MIN_LEDGER_PRUNE_WINDOW,
+                format!(
+                    "The ledger prune window ({}) must be at least {} versions",
+                    ledger_prune_window, MIN_LEDGER_PRUNE_WINDOW
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
     pub fn randomize_ports(&mut self) {
         self.address.set_port(utils::get_available_port());
         self.backup_service_address
             .set_port(utils::get_available_port());
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_ledger_prune_window() {
+        let mut config = StorageConfig::default();
+        config.validate().unwrap();
+
+        config.ledger_prune_window = Some(MIN_LEDGER_PRUNE_WINDOW);
+        config.validate().unwrap();
+
+        config.ledger_prune_window = Some(0);
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvariantViolation(_))
+        ));
+    }
+}
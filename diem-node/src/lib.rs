@@ -6,7 +6,11 @@ use consensus::consensus_provider::start_consensus;
 use debug_interface::node_debug_service::NodeDebugService;
 use diem_api::runtime::bootstrap as bootstrap_api;
 use diem_config::{
-    config::{NetworkConfig, NodeConfig, PersistableConfig},
+    config::{
+        BootstrappingMode, NetworkConfig, NodeConfig, PersistableConfig, StorageEngine,
+        StorageServiceConfig,
+    },
+    network_id::NetworkId,
     utils::get_genesis_txn,
 };
 use diem_infallible::RwLock;
@@ -21,19 +25,25 @@ use diem_types::{
     move_resource::MoveStorage,
     on_chain_config::{VMPublishingOption, ON_CHAIN_CONFIG_REGISTRY},
     protocol_spec::DpnProto,
+    trusted_state::TrustedState,
 };
 use diem_vm::DiemVM;
 use diemdb::DiemDB;
 use event_notifications::EventSubscriptionService;
 use executor::{db_bootstrapper::maybe_bootstrap, Executor};
 use executor_types::ChunkExecutor;
-use futures::{channel::mpsc::channel, executor::block_on};
+use futures::{channel::mpsc::channel, executor::block_on, Future};
 use network::application::storage::PeerMetadataStorage;
 use network_builder::builder::NetworkBuilder;
 use state_sync_v1::bootstrapper::StateSyncBootstrapper;
+use state_sync_v2::{
+    bootstrapper::Bootstrapper,
+    storage_client::NetworkStorageServiceClient,
+    streaming_service::{DataStreamingService, DataStreamingServiceConfig},
+};
 use std::{
     boxed::Box,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
     io::Write,
     net::ToSocketAddrs,
@@ -43,22 +53,28 @@ use std::{
         Arc,
     },
     thread,
-    time::Instant,
+    time::{Duration, Instant},
 };
 use storage_interface::default_protocol::DbReaderWriter;
 use storage_service::start_storage_service_with_db;
+use storage_service_server::{
+    network::{StorageServiceNetworkEvents, StorageServiceNetworkSender},
+    notify_commits, CommitListener, StorageReader, StorageServiceServer,
+};
 use tokio::runtime::{Builder, Runtime};
 use tokio_stream::wrappers::IntervalStream;
 
 const AC_SMP_CHANNEL_BUFFER_SIZE: usize = 1_024;
 const INTRA_NODE_CHANNEL_BUFFER_SIZE: usize = 1;
 const MEMPOOL_NETWORK_CHANNEL_BUFFER_SIZE: usize = 1_024;
+const STATE_SYNC_V2_RETRY_INTERVAL: Duration = Duration::from_secs(1);
 
 pub struct DiemHandle {
     _api: Option<Runtime>,
     _rpc: Runtime,
     _mempool: Runtime,
     _state_sync_bootstrapper: StateSyncBootstrapper,
+    _storage_service: Runtime,
     _network_runtimes: Vec<Runtime>,
     _consensus_runtime: Option<Runtime>,
     _debug: NodeDebugService,
@@ -205,6 +221,97 @@ fn setup_chunk_executor(db: DbReaderWriter) -> Box<dyn ChunkExecutor> {
     Box::new(Executor::<DpnProto, DiemVM>::new(db))
 }
 
+/// Starts a storage service server for every network, all sharing the same
+/// storage (and request limits), so that peers can fetch data to sync.
+fn setup_storage_service_servers(
+    config: StorageServiceConfig,
+    network_handles: Vec<StorageServiceNetworkEvents>,
+    db: &DbReaderWriter,
+    commit_listener: CommitListener,
+) -> Runtime {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("storage-service")
+        .enable_all()
+        .build()
+        .expect("Failed to start the storage service runtime!");
+    let storage_reader = StorageReader::new(Arc::new(RwLock::new(db.clone())));
+    let server = Arc::new(StorageServiceServer::new(config, storage_reader));
+    for network_events in network_handles {
+        runtime.spawn(server.clone().start(network_events));
+    }
+    runtime.spawn(server.serve_pending_requests(commit_listener));
+    runtime
+}
+
+/// Returns a future bootstrapping the node through state sync v2 if it's
+/// configured to download the latest account states instead of executing all
+/// transactions. State sync v1 then syncs the transactions after the downloaded
+/// state as usual. Failed attempts (e.g., because no peers are connected yet)
+/// are retried until the node is bootstrapped, so the future runs on the state
+/// sync runtime instead of blocking the node's startup.
+fn bootstrap_state_sync_v2(
+    node_config: &NodeConfig,
+    network_senders: HashMap<NetworkId, StorageServiceNetworkSender>,
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+    db_rw: &DbReaderWriter,
+) -> impl Future<Output = ()> {
+    let bootstrapper = if node_config.state_sync.bootstrapping_mode
+        == BootstrappingMode::DownloadLatestAccountStates
+    {
+        Some(create_state_sync_v2_bootstrapper(
+            node_config,
+            network_senders,
+            peer_metadata_storage,
+            db_rw,
+        ))
+    } else {
+        None
+    };
+
+    async move {
+        let mut bootstrapper = match bootstrapper {
+            Some(bootstrapper) => bootstrapper,
+            None => return,
+        };
+        loop {
+            match bootstrapper.bootstrap().await {
+                Ok(Some(ledger_info)) => {
+                    info!(
+                        "Bootstrapped the node with the account states at version {}",
+                        ledger_info.ledger_info().version()
+                    );
+                    return;
+                }
+                Ok(None) => return,
+                Err(error) => {
+                    warn!("Failed to bootstrap the node, retrying: {}", error);
+                    tokio::time::sleep(STATE_SYNC_V2_RETRY_INTERVAL).await;
+                }
+            }
+        }
+    }
+}
+
+fn create_state_sync_v2_bootstrapper(
+    node_config: &NodeConfig,
+    network_senders: HashMap<NetworkId, StorageServiceNetworkSender>,
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+    db_rw: &DbReaderWriter,
+) -> Bootstrapper {
+    let streaming_service_config = DataStreamingServiceConfig::default();
+    let storage_client = Arc::new(NetworkStorageServiceClient::new(
+        network_senders,
+        peer_metadata_storage,
+        Duration::from_millis(streaming_service_config.response_timeout_ms),
+    ));
+    let streaming_service = DataStreamingService::new(
+        streaming_service_config,
+        storage_client,
+        TrustedState::from_epoch_waypoint(node_config.base.waypoint.waypoint()),
+    );
+    Bootstrapper::new(&node_config.state_sync, streaming_service, db_rw.clone())
+}
+
 fn setup_debug_interface(config: &NodeConfig, logger: Option<Arc<Logger>>) -> NodeDebugService {
     let addr = format!(
         "{}:{}",
@@ -275,15 +382,24 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     });
 
     let mut instant = Instant::now();
-    let (diem_db, db_rw) = DbReaderWriter::wrap(
-        DiemDB::open(
+    let diem_db = match node_config.storage.engine {
+        StorageEngine::RocksDb => DiemDB::open(
             &node_config.storage.dir(),
             false, /* readonly */
             node_config.storage.prune_window,
+            node_config.storage.ledger_prune_window,
             node_config.storage.rocksdb_config,
-        )
-        .expect("DB should open."),
-    );
+            node_config.storage.secondary_indexes,
+        ),
+        StorageEngine::InMemory => DiemDB::open_in_memory(
+            node_config.storage.prune_window,
+            node_config.storage.ledger_prune_window,
+            node_config.storage.secondary_indexes,
+        ),
+    };
+    let (diem_db, db_rw) = DbReaderWriter::wrap(diem_db.expect("DB should open."));
+    // The storage service serves its pending subscriptions as soon as new data is committed.
+    let (db_rw, commit_listener) = notify_commits(db_rw);
     let _simple_storage_service = start_storage_service_with_db(node_config, Arc::clone(&diem_db));
     let backup_service = start_backup_service(
         node_config.storage.backup_service_address,
@@ -304,16 +420,12 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         instant.elapsed().as_millis()
     );
 
-    instant = Instant::now();
-    let chunk_executor = setup_chunk_executor(db_rw.clone());
-    debug!(
-        "ChunkExecutor setup in {} ms",
-        instant.elapsed().as_millis()
-    );
     let chain_id = fetch_chain_id(&db_rw);
     let mut network_runtimes = vec![];
     let mut state_sync_network_handles = vec![];
     let mut mempool_network_handles = vec![];
+    let mut storage_service_network_handles = vec![];
+    let mut storage_service_network_senders = HashMap::new();
     let mut consensus_network_handles = None;
 
     // Create an event subscription service so that components can be notified of events and reconfigs
@@ -391,6 +503,18 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         );
         mempool_network_handles.push((network_id, mempool_sender, mempool_events));
 
+        // Create the endpoints to connect the Network to the storage service.
+        let (storage_service_sender, storage_service_events): (
+            StorageServiceNetworkSender,
+            StorageServiceNetworkEvents,
+        ) = network_builder.add_protocol_handler(
+            storage_service_server::network::network_endpoint_config(
+                node_config.state_sync.storage_service,
+            ),
+        );
+        storage_service_network_handles.push(storage_service_events);
+        storage_service_network_senders.insert(network_id, storage_service_sender);
+
         // Perform steps relevant specifically to Validator networks.
         if network_id.is_validator_network() {
             // A valid config is allowed to have at most one ValidatorNetwork
@@ -415,6 +539,35 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     // TODO set up on-chain discovery network based on UpstreamConfig.fallback_network
     // and pass network handles to mempool/state sync
 
+    // Start the storage service, which serves the data of this node to syncing peers.
+    let storage_service = setup_storage_service_servers(
+        node_config.state_sync.storage_service,
+        storage_service_network_handles,
+        &db_rw,
+        commit_listener,
+    );
+
+    // Bootstrap the node (if it's configured to download the latest account states)
+    let state_sync_v2_bootstrap = bootstrap_state_sync_v2(
+        node_config,
+        storage_service_network_senders,
+        peer_metadata_storage,
+        &db_rw,
+    );
+
+    // The chunk executor reads the latest state from storage, so it's created once the node is
+    // bootstrapped.
+    let chunk_executor_db = db_rw.clone();
+    let create_chunk_executor = move || {
+        let instant = Instant::now();
+        let chunk_executor = setup_chunk_executor(chunk_executor_db);
+        debug!(
+            "ChunkExecutor setup in {} ms",
+            instant.elapsed().as_millis()
+        );
+        chunk_executor
+    };
+
     // For state sync to send notifications to mempool and receive notifications from consensus.
     let (mempool_notifier, mempool_listener) =
         mempool_notifications::new_mempool_notifier_listener_pair();
@@ -423,22 +576,33 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             node_config.state_sync.client_commit_timeout_ms,
         );
 
-    // Create state sync bootstrapper
-    let state_sync_bootstrapper = StateSyncBootstrapper::bootstrap(
+    // Create state sync bootstrapper, which starts syncing once the node is bootstrapped
+    let state_sync_bootstrapper = StateSyncBootstrapper::bootstrap_after(
+        state_sync_v2_bootstrap,
         state_sync_network_handles,
         mempool_notifier,
         consensus_listener,
         Arc::clone(&db_rw.reader),
-        chunk_executor,
+        create_chunk_executor,
         node_config,
         genesis_waypoint,
         event_subscription_service,
     );
     let (mp_client_sender, mp_client_events) = channel(AC_SMP_CHANNEL_BUFFER_SIZE);
 
-    let rpc_runtime = bootstrap_rpc(node_config, chain_id, diem_db.clone(), mp_client_sender);
+    let rpc_runtime = bootstrap_rpc(
+        node_config,
+        chain_id,
+        diem_db.clone(),
+        mp_client_sender.clone(),
+    );
     let api_runtime = match node_config.api.enabled {
-        true => Some(bootstrap_api(chain_id, diem_db.clone(), &node_config.api)),
+        true => Some(bootstrap_api(
+            chain_id,
+            diem_db.clone(),
+            mp_client_sender,
+            &node_config.api,
+        )),
         false => None,
     };
 
@@ -498,6 +662,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         _rpc: rpc_runtime,
         _mempool: mempool,
         _state_sync_bootstrapper: state_sync_bootstrapper,
+        _storage_service: storage_service,
         _consensus_runtime: consensus_runtime,
         _debug: debug_if,
         _backup: backup_service,
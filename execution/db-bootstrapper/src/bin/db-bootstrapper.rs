@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{ensure, format_err, Context, Result};
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_temppath::TempPath;
 use diem_types::{transaction::Transaction, waypoint::Waypoint};
 use diem_vm::DiemVM;
@@ -52,7 +52,9 @@ fn main() -> Result<()> {
             &opt.db_dir,
             false,
             None, /* pruner */
+            None, /* ledger_pruner */
             RocksdbConfig::default(),
+            SecondaryIndexesConfig::default(),
         )
     } else {
         // When not committing, we open the DB as secondary so the tool is usable along side a
@@ -68,16 +68,26 @@ impl ExecutionCorrectnessManager {
         let execution_prikey = extract_execution_prikey(config);
         let storage_address = config.storage.address;
         let timeout_ms = config.storage.timeout_ms;
+        let max_in_mem_state_tree_nodes = config.execution.max_in_mem_state_tree_nodes;
         match &config.execution.service {
-            ExecutionCorrectnessService::Local => {
-                Self::new_local(storage_address, execution_prikey, timeout_ms)
-            }
-            ExecutionCorrectnessService::Serializer => {
-                Self::new_serializer(storage_address, execution_prikey, timeout_ms)
-            }
-            ExecutionCorrectnessService::Thread => {
-                Self::new_thread(storage_address, execution_prikey, timeout_ms)
-            }
+            ExecutionCorrectnessService::Local => Self::new_local(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                max_in_mem_state_tree_nodes,
+            ),
+            ExecutionCorrectnessService::Serializer => Self::new_serializer(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                max_in_mem_state_tree_nodes,
+            ),
+            ExecutionCorrectnessService::Thread => Self::new_thread(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                max_in_mem_state_tree_nodes,
+            ),
             _ => unreachable!(
                 "Unimplemented ExecutionCorrectnessService: {:?}",
                 config.execution.service
@@ -89,10 +99,15 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         timeout: u64,
+        max_in_mem_state_tree_nodes: usize,
     ) -> Self {
-        let block_executor = Box::new(Executor::<DpnProto, DiemVM>::new(DbReaderWriter::new(
-            StorageClient::new(&storage_address, timeout),
-        )));
+        let block_executor = Box::new(
+            Executor::<DpnProto, DiemVM>::new(DbReaderWriter::new(StorageClient::new(
+                &storage_address,
+                timeout,
+            )))
+            .with_max_in_mem_state_tree_nodes(max_in_mem_state_tree_nodes),
+        );
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Local(Arc::new(
                 LocalService::new(block_executor, execution_prikey),
@@ -111,10 +126,15 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         timeout: u64,
+        max_in_mem_state_tree_nodes: usize,
     ) -> Self {
-        let block_executor = Box::new(Executor::<DpnProto, DiemVM>::new(DbReaderWriter::new(
-            StorageClient::new(&storage_address, timeout),
-        )));
+        let block_executor = Box::new(
+            Executor::<DpnProto, DiemVM>::new(DbReaderWriter::new(StorageClient::new(
+                &storage_address,
+                timeout,
+            )))
+            .with_max_in_mem_state_tree_nodes(max_in_mem_state_tree_nodes),
+        );
         let serializer_service = SerializerService::new(block_executor, execution_prikey);
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Serializer(Arc::new(
@@ -127,8 +147,14 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         network_timeout: u64,
+        max_in_mem_state_tree_nodes: usize,
     ) -> Self {
-        let thread = ThreadService::new(storage_address, execution_prikey, network_timeout);
+        let thread = ThreadService::new(
+            storage_address,
+            execution_prikey,
+            network_timeout,
+            max_in_mem_state_tree_nodes,
+        );
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Thread(thread),
         }
@@ -39,6 +39,7 @@ impl Process {
             server_addr,
             self.prikey,
             self.network_timeout_ms,
+            self.config.execution.max_in_mem_state_tree_nodes,
         );
     }
 }
@@ -33,10 +33,15 @@ pub fn execute(
     listen_addr: SocketAddr,
     prikey: Option<Ed25519PrivateKey>,
     network_timeout: u64,
+    max_in_mem_state_tree_nodes: usize,
 ) {
-    let block_executor = Box::new(Executor::<DpnProto, DiemVM>::new(DbReaderWriter::new(
-        StorageClient::new(&storage_addr, network_timeout),
-    )));
+    let block_executor = Box::new(
+        Executor::<DpnProto, DiemVM>::new(DbReaderWriter::new(StorageClient::new(
+            &storage_addr,
+            network_timeout,
+        )))
+        .with_max_in_mem_state_tree_nodes(max_in_mem_state_tree_nodes),
+    );
     let serializer_service = SerializerService::new(block_executor, prikey);
     let mut network_server = NetworkServer::new("execution", listen_addr, network_timeout);
 
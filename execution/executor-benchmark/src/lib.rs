@@ -10,7 +10,7 @@ use crate::{
     transaction_generator::TransactionGenerator,
 };
 use diem_config::{
-    config::{NodeConfig, RocksdbConfig},
+    config::{NodeConfig, RocksdbConfig, SecondaryIndexesConfig},
     utils::get_genesis_txn,
 };
 use diem_logger::prelude::*;
@@ -37,7 +37,9 @@ pub fn create_storage_service_and_executor(
             &config.storage.dir(),
             false, /* readonly */
             None,  /* pruner */
+            None,  /* ledger_pruner */
             RocksdbConfig::default(),
+            SecondaryIndexesConfig::default(),
         )
         .expect("DB should open."),
     );
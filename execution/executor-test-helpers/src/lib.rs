@@ -45,7 +45,7 @@ pub fn start_storage_service() -> (NodeConfig, JoinHandle<()>, Arc<dyn DbReader<
     let (mut config, _genesis_key) = diem_genesis_tool::test_config();
     let server_port = utils::get_available_port();
     config.storage.address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
-    let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(&config.storage.dir()));
+    let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
     bootstrap_genesis::<DiemVM>(&db_rw, utils::get_genesis_txn(&config).unwrap()).unwrap();
     let handle = start_storage_service_with_db(&config, db.clone());
     (config, handle, db as Arc<dyn DbReader<DpnProto>>)
@@ -21,8 +21,8 @@ use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
     proof::{accumulator::InMemoryAccumulator, AccumulatorExtensionProof},
     transaction::{
-        default_protocol::TransactionListWithProof, Transaction, TransactionInfo,
-        TransactionStatus, Version,
+        default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
+        Transaction, TransactionInfo, TransactionStatus, Version,
     },
 };
 use scratchpad::ProofRead;
@@ -46,6 +46,17 @@ pub trait ChunkExecutor: Send + Sync {
         // carrying any epoch change LI.
         epoch_change_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<Vec<ContractEvent>>;
+
+    /// Similar to `execute_and_commit_chunk`, but instead of executing the transactions, applies
+    /// their outputs (i.e., write sets and events). The outputs are verified by checking that the
+    /// resulting transaction infos match the provided proofs. Returns a vector of reconfiguration
+    /// events in the chunk.
+    fn apply_and_commit_chunk(
+        &self,
+        txn_output_list_with_proof: TransactionAndOutputListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>>;
 }
 
 pub trait BlockExecutor: Send + Sync {
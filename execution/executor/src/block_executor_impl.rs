@@ -205,6 +205,7 @@ where
                     txn.clone(),
                     txn_data.account_blobs().clone(),
                     Some(txn_data.jf_node_hashes().clone()),
+                    txn_data.write_set().clone(),
                     txn_data.events().to_vec(),
                     txn_data.gas_used(),
                     recorded_status.clone(),
@@ -281,6 +282,7 @@ where
         self.cache
             .write()
             .prune(ledger_info_with_sigs.ledger_info())?;
+        self.evict_idle_state_subtrees();
 
         // Now that the blocks are persisted successfully, we can reply to consensus
         Ok(())
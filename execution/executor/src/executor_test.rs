@@ -15,7 +15,10 @@ use diem_types::{
     account_address::AccountAddress,
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    transaction::{default_protocol::TransactionListWithProof, Transaction, Version},
+    transaction::{
+        default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
+        Transaction, Version,
+    },
 };
 use diemdb::DiemDB;
 use executor_types::{BlockExecutor, ChunkExecutor, TransactionReplayer};
@@ -42,26 +45,19 @@ fn execute_and_commit_block(
 }
 
 struct TestExecutor {
-    _path: diem_temppath::TempPath,
     db: DbReaderWriter,
     executor: Executor<DpnProto, MockVM>,
 }
 
 impl TestExecutor {
     fn new() -> TestExecutor {
-        let path = diem_temppath::TempPath::new();
-        path.create_as_dir().unwrap();
-        let db = DbReaderWriter::new(DiemDB::new_for_test(path.path()));
+        let db = DbReaderWriter::new(DiemDB::new_for_test());
         let genesis = vm_genesis::test_genesis_transaction();
         let waypoint = generate_waypoint::<MockVM>(&db, &genesis).unwrap();
         maybe_bootstrap::<MockVM>(&db, &genesis, waypoint).unwrap();
         let executor = Executor::new(db.clone());
 
-        TestExecutor {
-            _path: path,
-            db,
-            executor,
-        }
+        TestExecutor { db, executor }
     }
 }
 
@@ -169,6 +165,49 @@ fn test_executor_multiple_blocks() {
     }
 }
 
+#[test]
+fn test_executor_evict_idle_state_subtrees() {
+    let mut executor = TestExecutor::new();
+    executor.executor = Executor::new(executor.db.clone()).with_max_in_mem_state_tree_nodes(0);
+    let unbounded_executor = TestExecutor::new();
+    let mut parent_block_id = executor.committed_block_id();
+
+    for i in 0..10 {
+        // Accounts minted to before are read back from storage once evicted.
+        let txns = (0..=i)
+            .map(|j| encode_mint_transaction(gen_address(j), 100))
+            .collect::<Vec<_>>();
+        let block_id = gen_block_id(i + 1);
+        let output = executor
+            .execute_block((block_id, txns.clone()), parent_block_id)
+            .unwrap();
+        let unbounded_output = unbounded_executor
+            .execute_block((block_id, txns), parent_block_id)
+            .unwrap();
+        assert_eq!(output.root_hash(), unbounded_output.root_hash());
+
+        let ledger_info = gen_ledger_info(output.version(), output.root_hash(), block_id, i + 1);
+        executor
+            .commit_blocks(vec![block_id], ledger_info.clone())
+            .unwrap();
+        unbounded_executor
+            .commit_blocks(vec![block_id], ledger_info)
+            .unwrap();
+        parent_block_id = block_id;
+
+        let committed_trees = executor.cache.read().committed_trees().clone();
+        assert_eq!(committed_trees.state_tree().num_in_mem_nodes(), 0);
+        assert_eq!(
+            committed_trees.state_root(),
+            unbounded_executor
+                .cache
+                .read()
+                .committed_trees()
+                .state_root()
+        );
+    }
+}
+
 #[test]
 fn test_executor_two_blocks_with_failed_txns() {
     let executor = TestExecutor::new();
@@ -260,11 +299,7 @@ fn create_transaction_chunks(
 
     // To obtain the batches of transactions, we first execute and save all these transactions in a
     // separate DB. Then we call get_transactions to retrieve them.
-    let TestExecutor {
-        _path,
-        db: _,
-        executor,
-    } = TestExecutor::new();
+    let TestExecutor { db: _, executor } = TestExecutor::new();
 
     let mut txns = vec![];
     for i in 1..chunk_ranges.last().unwrap().end {
@@ -301,6 +336,46 @@ fn create_transaction_chunks(
     (batches, ledger_info)
 }
 
+/// Generates a `TransactionAndOutputListWithProof` for each of the given chunks, holding the
+/// outputs of executing the transactions in the chunk.
+fn create_transaction_output_chunks(
+    chunks: &[TransactionListWithProof],
+) -> Vec<TransactionAndOutputListWithProof> {
+    // Execute all transactions on top of genesis to obtain their outputs.
+    let TestExecutor { db: _, executor } = TestExecutor::new();
+    let last_version = chunks
+        .iter()
+        .map(|chunk| chunk.first_transaction_version.unwrap() + chunk.transactions.len() as u64)
+        .max()
+        .unwrap();
+    let txns: Vec<_> = (1..last_version)
+        .map(|i| encode_mint_transaction(gen_address(i), 100))
+        .collect();
+    let executed_trees = executor
+        .get_executed_trees(executor.committed_block_id())
+        .unwrap();
+    let state_view = executor.get_executed_state_view(StateViewId::Miscellaneous, &executed_trees);
+    let outputs = MockVM::execute_block(txns, &state_view).unwrap();
+
+    chunks
+        .iter()
+        .map(|chunk| {
+            let first_version = chunk.first_transaction_version.unwrap();
+            let transactions_and_outputs = chunk
+                .transactions
+                .iter()
+                .cloned()
+                .zip(outputs[first_version as usize - 1..].iter().cloned())
+                .collect();
+            TransactionAndOutputListWithProof::new(
+                transactions_and_outputs,
+                Some(first_version),
+                chunk.proof.clone(),
+            )
+        })
+        .collect()
+}
+
 #[test]
 fn test_executor_execute_and_commit_chunk() {
     let first_batch_size = 30;
@@ -320,11 +395,7 @@ fn test_executor_execute_and_commit_chunk() {
     };
 
     // Now we execute these two chunks of transactions.
-    let TestExecutor {
-        _path,
-        db,
-        executor,
-    } = TestExecutor::new();
+    let TestExecutor { db, executor } = TestExecutor::new();
 
     // Execute the first chunk. After that we should still get the genesis ledger info from DB.
     executor
@@ -384,11 +455,7 @@ fn test_executor_execute_and_commit_chunk_restart() {
         ])
     };
 
-    let TestExecutor {
-        _path,
-        db,
-        executor,
-    } = TestExecutor::new();
+    let TestExecutor { db, executor } = TestExecutor::new();
 
     // First we simulate syncing the first chunk of transactions.
     {
@@ -426,11 +493,7 @@ fn test_executor_execute_and_commit_chunk_local_result_mismatch() {
         ])
     };
 
-    let TestExecutor {
-        _path,
-        db: _,
-        executor,
-    } = TestExecutor::new();
+    let TestExecutor { db: _, executor } = TestExecutor::new();
     // commit 5 txns first.
     {
         let parent_block_id = executor.committed_block_id();
@@ -452,6 +515,86 @@ fn test_executor_execute_and_commit_chunk_local_result_mismatch() {
         .is_err());
 }
 
+#[test]
+fn test_executor_apply_and_commit_chunk() {
+    let (chunks, ledger_info) = create_transaction_chunks(vec![1..31, 31..71, 66..86]);
+    let output_chunks = create_transaction_output_chunks(&chunks);
+
+    // Execute the transactions in one DB, and apply their outputs in another.
+    let TestExecutor {
+        db: executed_db,
+        executor,
+    } = TestExecutor::new();
+    for chunk in chunks {
+        executor
+            .execute_and_commit_chunk(chunk, ledger_info.clone(), None)
+            .unwrap();
+    }
+    let TestExecutor {
+        db: applied_db,
+        executor,
+    } = TestExecutor::new();
+    for (i, output_chunk) in output_chunks.into_iter().enumerate() {
+        executor
+            .apply_and_commit_chunk(output_chunk, ledger_info.clone(), None)
+            .unwrap();
+
+        // The ledger info is only committed along with the last chunk.
+        let li = applied_db.reader.get_latest_ledger_info().unwrap();
+        if i < 2 {
+            assert_eq!(li.ledger_info().version(), 0);
+        } else {
+            assert_eq!(li, ledger_info);
+        }
+    }
+
+    // Verify both DBs are identical.
+    let version = ledger_info.ledger_info().version();
+    assert_eq!(
+        applied_db.reader.get_startup_info().unwrap(),
+        executed_db.reader.get_startup_info().unwrap()
+    );
+    assert_eq!(
+        applied_db
+            .reader
+            .get_transactions(0, version + 1, version, true)
+            .unwrap(),
+        executed_db
+            .reader
+            .get_transactions(0, version + 1, version, true)
+            .unwrap()
+    );
+    assert_eq!(
+        applied_db
+            .reader
+            .get_account_states_range(version, HashValue::zero(), 1000)
+            .unwrap(),
+        executed_db
+            .reader
+            .get_account_states_range(version, HashValue::zero(), 1000)
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_executor_apply_and_commit_chunk_wrong_outputs() {
+    let (chunks, ledger_info) = create_transaction_chunks(vec![1..11]);
+    let mut output_chunk = create_transaction_output_chunks(&chunks).remove(0);
+
+    // Swap the outputs of the first two transactions, so their write sets don't match the proof.
+    let first_output = output_chunk.transactions_and_outputs[0].1.clone();
+    let second_output = output_chunk.transactions_and_outputs[1].1.clone();
+    output_chunk.transactions_and_outputs[0].1 = second_output;
+    output_chunk.transactions_and_outputs[1].1 = first_output;
+
+    // Applying the outputs should fail, without committing anything.
+    let TestExecutor { db, executor } = TestExecutor::new();
+    assert!(executor
+        .apply_and_commit_chunk(output_chunk, ledger_info, None)
+        .is_err());
+    assert_eq!(db.reader.get_latest_version().unwrap(), 0);
+}
+
 #[test]
 fn test_noop_block_after_reconfiguration() {
     let executor = TestExecutor::new();
@@ -632,7 +775,7 @@ proptest! {
         let block_a = TestBlock::new(0..a_size, amount, gen_block_id(1));
         let block_b = TestBlock::new(0..b_size, amount, gen_block_id(2));
 
-        let TestExecutor { _path, db, executor } = TestExecutor::new();
+        let TestExecutor { db, executor } = TestExecutor::new();
         let mut parent_block_id;
         let mut root_hash;
 
@@ -681,7 +824,7 @@ proptest! {
                 overlap_start..overlap_end
             ]);
 
-        let TestExecutor { _path, db: _, executor } = TestExecutor::new();
+        let TestExecutor { db: _, executor } = TestExecutor::new();
         let parent_block_id = executor.committed_block_id();
 
         let overlap_txn_list_with_proof = chunks.pop().unwrap();
@@ -19,7 +19,7 @@ use diem_crypto::{
 };
 use diem_infallible::{RwLock, RwLockReadGuard};
 use diem_logger::prelude::*;
-use diem_state_view::StateViewId;
+use diem_state_view::{StateView, StateViewId};
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state::AccountState,
@@ -28,11 +28,12 @@ use diem_types::{
     epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures,
     on_chain_config,
-    proof::accumulator::InMemoryAccumulator,
+    proof::{accumulator::InMemoryAccumulator, TransactionInfoListWithProof},
     protocol_spec::{DpnProto, ProtocolSpec},
     transaction::{
-        Transaction, TransactionInfoTrait, TransactionListWithProof, TransactionOutput,
-        TransactionPayload, TransactionStatus, TransactionToCommit, Version,
+        Transaction, TransactionAndOutputListWithProof, TransactionInfoTrait,
+        TransactionListWithProof, TransactionOutput, TransactionPayload, TransactionStatus,
+        TransactionToCommit, Version,
     },
     write_set::{WriteOp, WriteSet},
 };
@@ -71,6 +72,7 @@ type SparseMerkleProof = diem_types::proof::SparseMerkleProof<AccountStateBlob>;
 pub struct Executor<PS, V> {
     db: DbReaderWriter,
     cache: RwLock<SpeculationCache>,
+    max_in_mem_state_tree_nodes: Option<usize>,
     phantom: PhantomData<(PS, V)>,
 }
 
@@ -94,10 +96,28 @@ where
         Self {
             db,
             cache: RwLock::new(SpeculationCache::new_with_startup_info(startup_info)),
+            max_in_mem_state_tree_nodes: None,
             phantom: PhantomData,
         }
     }
 
+    /// Bounds the number of state tree nodes held in memory after each commit (see
+    /// `ExecutionConfig::max_in_mem_state_tree_nodes`). Unbounded by default.
+    pub fn with_max_in_mem_state_tree_nodes(mut self, max_in_mem_state_tree_nodes: usize) -> Self {
+        self.max_in_mem_state_tree_nodes = Some(max_in_mem_state_tree_nodes);
+        self
+    }
+
+    /// Evicts the least recently accessed subtrees of the committed state tree, if the state
+    /// trees hold more nodes in memory than allowed. Must only be called once the committed state
+    /// is persisted.
+    fn evict_idle_state_subtrees(&self) {
+        if let Some(max_in_mem_state_tree_nodes) = self.max_in_mem_state_tree_nodes {
+            let committed_state_tree = self.cache.read().committed_trees().state_tree().clone();
+            committed_state_tree.evict_idle_subtrees(max_in_mem_state_tree_nodes);
+        }
+    }
+
     fn reset_cache(&self) -> Result<(), Error> {
         let startup_info = self
             .db
@@ -112,6 +132,7 @@ where
         Self {
             db,
             cache: RwLock::new(SpeculationCache::new_for_db_bootstrapping(tree_state)),
+            max_in_mem_state_tree_nodes: None,
             phantom: PhantomData,
         }
     }
@@ -180,17 +201,55 @@ where
             txn_list_with_proof.first_transaction_version,
         )?;
 
+        // 2 and 3.
+        self.skip_persisted_in_chunk(
+            txn_list_with_proof.transactions,
+            txn_list_with_proof.first_transaction_version,
+            txn_list_with_proof.proof,
+        )
+    }
+
+    /// Same as `verify_chunk`, but for a chunk of transaction outputs, which are returned along
+    /// with their transactions.
+    fn verify_output_chunk(
+        &self,
+        txn_output_list_with_proof: TransactionAndOutputListWithProof<PS::TransactionInfo>,
+        verified_target_li: &LedgerInfoWithSignatures,
+    ) -> Result<(
+        Vec<(Transaction, TransactionOutput)>,
+        Vec<PS::TransactionInfo>,
+    )> {
+        // 1. Verify that the outputs belong to the ledger represented by the ledger info.
+        txn_output_list_with_proof.verify(
+            verified_target_li.ledger_info(),
+            txn_output_list_with_proof.first_transaction_version,
+        )?;
+
+        // 2 and 3.
+        self.skip_persisted_in_chunk(
+            txn_output_list_with_proof.transactions_and_outputs,
+            txn_output_list_with_proof.first_transaction_version,
+            txn_output_list_with_proof.proof,
+        )
+    }
+
+    /// Skips the items of a verified chunk (e.g., transactions) that are already persisted, after
+    /// verifying that their transaction infos match what's persisted (no fork). Returns the rest of
+    /// the items, along with their transaction infos.
+    fn skip_persisted_in_chunk<T: std::fmt::Debug>(
+        &self,
+        mut items: Vec<T>,
+        first_version: Option<Version>,
+        proof: TransactionInfoListWithProof<PS::TransactionInfo>,
+    ) -> Result<(Vec<T>, Vec<PS::TransactionInfo>)> {
         // Return empty if there's no work to do.
-        if txn_list_with_proof.transactions.is_empty() {
+        if items.is_empty() {
             return Ok((Vec::new(), Vec::new()));
         }
-        let first_txn_version = match txn_list_with_proof.first_transaction_version {
+        let first_txn_version = match first_version {
             Some(tx) => tx as Version,
             None => {
-                bail!(
-                    "first_transaction_version doesn't exist in {:?}",
-                    txn_list_with_proof
-                );
+                bail!("first_transaction_version doesn't exist in {:?}", items);
             }
         };
         let read_lock = self.cache.read();
@@ -203,12 +262,12 @@ where
             first_txn_version
         );
         let versions_between_first_and_committed = num_committed_txns - first_txn_version;
-        if txn_list_with_proof.transactions.len() <= versions_between_first_and_committed as usize {
+        if items.len() <= versions_between_first_and_committed as usize {
             // All already in DB, nothing to do.
             return Ok((Vec::new(), Vec::new()));
         }
 
-        // 2. Verify that skipped transactions match what's already persisted (no fork):
+        // Verify that skipped transactions match what's already persisted (no fork):
         let num_txns_to_skip = num_committed_txns - first_txn_version;
 
         debug!(
@@ -217,13 +276,11 @@ where
         );
 
         // If the proof is verified, then the length of txn_infos and txns must be the same.
-        let skipped_transaction_infos =
-            &txn_list_with_proof.proof.transaction_infos[..num_txns_to_skip as usize];
+        let skipped_transaction_infos = &proof.transaction_infos[..num_txns_to_skip as usize];
 
         // Left side of the proof happens to be the frozen subtree roots of the accumulator
         // right before the list of txns are applied.
-        let frozen_subtree_roots_from_proof = txn_list_with_proof
-            .proof
+        let frozen_subtree_roots_from_proof = proof
             .ledger_info_to_transaction_infos_proof
             .left_siblings()
             .iter()
@@ -246,13 +303,12 @@ where
             "Fork happens because the current synced_trees doesn't match the txn list provided."
         );
 
-        // 3. Return verified transactions to be applied.
-        let mut txns: Vec<_> = txn_list_with_proof.transactions;
-        txns.drain(0..num_txns_to_skip as usize);
-        let mut txn_infos = txn_list_with_proof.proof.transaction_infos;
+        // Return verified items to be applied.
+        items.drain(0..num_txns_to_skip as usize);
+        let mut txn_infos = proof.transaction_infos;
         txn_infos.drain(0..num_txns_to_skip as usize);
 
-        Ok((txns, txn_infos))
+        Ok((items, txn_infos))
     }
 
     /// Post-processing of what the VM outputs. Returns the entire block's output.
@@ -357,6 +413,7 @@ where
             txn_data.push(TransactionData::new(
                 blobs,
                 new_node_hashes,
+                vm_output.write_set().clone(),
                 vm_output.events().to_vec(),
                 vm_output.status().clone(),
                 state_tree_hash,
@@ -374,6 +431,7 @@ where
                 TransactionData::new(
                     HashMap::new(),
                     HashMap::new(),
+                    WriteSet::default(),
                     vec![],
                     TransactionStatus::Retry,
                     current_state_tree.root_hash(),
@@ -502,6 +560,33 @@ where
             }
         }
 
+        Self::verify_chunk_outputs(
+            first_version,
+            state_view,
+            transactions,
+            vm_outputs,
+            transaction_infos,
+            read_lock.synced_trees(),
+        )
+    }
+
+    /// Processes the outputs of a chunk of transactions on top of `state_view`, and verifies that
+    /// the resulting transaction infos match `transaction_infos`. Returns the transactions to
+    /// commit, along with their events and the transactions to retry (after a reconfiguration).
+    fn verify_chunk_outputs(
+        first_version: u64,
+        state_view: VerifiedStateView<'_, DpnProto>,
+        transactions: Vec<Transaction>,
+        vm_outputs: Vec<TransactionOutput>,
+        transaction_infos: Vec<PS::TransactionInfo>,
+        parent_trees: &ExecutedTrees,
+    ) -> Result<(
+        ProcessedVMOutput,
+        Vec<TransactionToCommit>,
+        Vec<ContractEvent>,
+        Vec<Transaction>,
+        Vec<PS::TransactionInfo>,
+    )> {
         let (account_to_state, account_to_proof) = state_view.into();
 
         let output = Self::process_vm_outputs(
@@ -509,7 +594,7 @@ where
             account_to_proof,
             &transactions,
             vm_outputs,
-            read_lock.synced_trees(),
+            parent_trees,
         )?;
 
         // Since we have verified the proofs, we just need to verify that each PS::TransactionInfo
@@ -556,6 +641,7 @@ where
                 txn,
                 txn_data.account_blobs().clone(),
                 Some(txn_data.jf_node_hashes().clone()),
+                txn_data.write_set().clone(),
                 txn_data.events().to_vec(),
                 txn_data.gas_used(),
                 recorded_status,
@@ -587,6 +673,61 @@ where
         let (processed_vm_output, txns_to_commit, events, txns_to_retry, _txn_infos_to_retry) =
             self.replay_transactions_impl(first_version, transactions, transaction_infos)?;
 
+        Self::ensure_no_retries(first_version, num_txns, &txns_to_retry)?;
+        Ok((processed_vm_output, txns_to_commit, events))
+    }
+
+    /// Applies the outputs of a chunk of transactions instead of executing the transactions.
+    /// The accounts written by the outputs are read first, and the resulting transaction infos are
+    /// verified just like those of executed transactions.
+    fn apply_chunk(
+        &self,
+        first_version: u64,
+        transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
+        transaction_infos: Vec<PS::TransactionInfo>,
+    ) -> Result<(
+        ProcessedVMOutput,
+        Vec<TransactionToCommit>,
+        Vec<ContractEvent>,
+    )> {
+        let num_txns = transactions_and_outputs.len();
+        let (transactions, txn_outputs): (Vec<_>, Vec<_>) =
+            transactions_and_outputs.into_iter().unzip();
+
+        let read_lock = self.cache.read();
+        let state_view = VerifiedStateView::new(
+            StateViewId::ChunkExecution { first_version },
+            Arc::clone(&self.db.reader),
+            read_lock.synced_trees().version(),
+            read_lock.synced_trees().state_root(),
+            read_lock.synced_trees().state_tree(),
+        );
+        // Applying a write set requires the current state (and proof) of each account it writes.
+        for txn_output in &txn_outputs {
+            for (access_path, _) in txn_output.write_set() {
+                state_view.get(access_path)?;
+            }
+        }
+
+        let (processed_vm_output, txns_to_commit, events, txns_to_retry, _txn_infos_to_retry) =
+            Self::verify_chunk_outputs(
+                first_version,
+                state_view,
+                transactions,
+                txn_outputs,
+                transaction_infos,
+                read_lock.synced_trees(),
+            )?;
+
+        Self::ensure_no_retries(first_version, num_txns, &txns_to_retry)?;
+        Ok((processed_vm_output, txns_to_commit, events))
+    }
+
+    fn ensure_no_retries(
+        first_version: u64,
+        num_txns: usize,
+        txns_to_retry: &[Transaction],
+    ) -> Result<()> {
         ensure!(
             txns_to_retry.is_empty(),
             "The transaction at version {} got the status of 'Retry'",
@@ -596,8 +737,7 @@ where
                 .checked_add(first_version as usize)
                 .ok_or_else(|| format_err!("integer overflow occurred"))?,
         );
-
-        Ok((processed_vm_output, txns_to_commit, events))
+        Ok(())
     }
 }
 
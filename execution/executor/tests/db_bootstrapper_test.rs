@@ -5,7 +5,6 @@
 
 use anyhow::Result;
 use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, Uniform};
-use diem_temppath::TempPath;
 use diem_transaction_builder::stdlib::{
     encode_create_parent_vasp_account_script, encode_peer_to_peer_with_metadata_script,
 };
@@ -51,8 +50,7 @@ use storage_interface::{default_protocol::DbReaderWriter, DbReader};
 fn test_empty_db() {
     let genesis = vm_genesis::test_genesis_change_set_and_validators(Some(1));
     let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis.0));
-    let tmp_dir = TempPath::new();
-    let db_rw = DbReaderWriter::new(DiemDB::new_for_test(&tmp_dir));
+    let db_rw = DbReaderWriter::new(DiemDB::new_for_test());
 
     // Executor won't be able to boot on empty db due to lack of StartupInfo.
     assert!(db_rw.reader.get_startup_info().unwrap().is_none());
@@ -256,8 +254,7 @@ fn test_pre_genesis() {
     let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis.0));
 
     // Create bootstrapped DB.
-    let tmp_dir = TempPath::new();
-    let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(&tmp_dir));
+    let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
     let signer = ValidatorSigner::new(genesis.1[0].data.address, genesis.1[0].key.clone());
     let waypoint = bootstrap_genesis::<DiemVM>(&db_rw, &genesis_txn).unwrap();
 
@@ -274,8 +271,7 @@ fn test_pre_genesis() {
     // Get state tree backup.
     let (accounts_backup, proof, root_hash) = get_state_backup(&db);
     // Restore into PRE-GENESIS state of a new empty DB.
-    let tmp_dir = TempPath::new();
-    let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(&tmp_dir));
+    let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
     restore_state_to_db(&db, accounts_backup, proof, root_hash, PRE_GENESIS_VERSION);
 
     // DB is not empty, `maybe_bootstrap()` will try to apply and fail the waypoint check.
@@ -335,8 +331,7 @@ fn test_new_genesis() {
     let genesis_key = &vm_genesis::GENESIS_KEYPAIR.0;
     let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis.0));
     // Create bootstrapped DB.
-    let tmp_dir = TempPath::new();
-    let db = DbReaderWriter::new(DiemDB::new_for_test(&tmp_dir));
+    let db = DbReaderWriter::new(DiemDB::new_for_test());
     let waypoint = bootstrap_genesis::<DiemVM>(&db, &genesis_txn).unwrap();
     let signer = ValidatorSigner::new(genesis.1[0].data.address, genesis.1[0].key.clone());
 
@@ -25,10 +25,8 @@ use std::convert::TryFrom;
 
 #[test]
 fn test_genesis() {
-    let path = diem_temppath::TempPath::new();
-    path.create_as_dir().unwrap();
     let genesis = vm_genesis::test_genesis_transaction();
-    let (_, db, _executor, waypoint) = create_db_and_executor(path.path(), &genesis);
+    let (_, db, _executor, waypoint) = create_db_and_executor(&genesis);
 
     let trusted_state = TrustedState::from_epoch_waypoint(waypoint);
     let initial_accumulator = db
@@ -57,12 +55,10 @@ fn test_reconfiguration() {
     // When executing a transaction emits a validator set change,
     // storage should propagate the new validator set
 
-    let path = diem_temppath::TempPath::new();
-    path.create_as_dir().unwrap();
     let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
     let genesis_key = &vm_genesis::GENESIS_KEYPAIR.0;
     let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
-    let (_, db, executor, _waypoint) = create_db_and_executor(path.path(), &genesis_txn);
+    let (_, db, executor, _waypoint) = create_db_and_executor(&genesis_txn);
     let parent_block_id = executor.committed_block_id();
     let signer = ValidatorSigner::new(validators[0].data.address, validators[0].key.clone());
     let validator_account = signer.author();
@@ -231,13 +227,11 @@ fn test_reconfiguration() {
 
 #[test]
 fn test_change_publishing_option_to_custom() {
-    let path = diem_temppath::TempPath::new();
-    path.create_as_dir().unwrap();
     let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
     let genesis_key = &vm_genesis::GENESIS_KEYPAIR.0;
     let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
 
-    let (_, db, executor, waypoint) = create_db_and_executor(path.path(), &genesis_txn);
+    let (_, db, executor, waypoint) = create_db_and_executor(&genesis_txn);
     let parent_block_id = executor.committed_block_id();
 
     let treasury_compliance_account = treasury_compliance_account_address();
@@ -4,10 +4,10 @@
 use crate::{
     errors::JsonRpcError,
     views::{
-        AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionListView, TransactionView,
-        TransactionsWithProofsView,
+        AccountStateWithProofView, AccountStatesWithMultiproofView,
+        AccountTransactionsWithProofView, AccountView, AccumulatorConsistencyProofView,
+        CurrencyInfoView, EventByVersionWithProofView, EventView, EventWithProofView, MetadataView,
+        StateProofView, TransactionListView, TransactionView, TransactionsWithProofsView,
     },
 };
 use anyhow::Result;
@@ -18,10 +18,10 @@ use diem_types::{
 };
 use resource_viewer::{AnnotatedMoveStruct, MoveValueAnnotator};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashSet},
     convert::{TryFrom, TryInto},
 };
-use storage_interface::{MoveDbReader, Order};
+use storage_interface::{LedgerHistoryPruned, MoveDbReader, Order};
 
 pub fn get_account_state(
     db: &dyn MoveDbReader<DpnProto>,
@@ -88,6 +88,7 @@ pub fn get_transactions(
     if start_version > ledger_version || limit == 0 {
         return Ok(TransactionListView::empty());
     }
+    error_if_pruned(db, start_version)?;
     let txs = db.get_transactions(start_version, limit, ledger_version, include_events)?;
     Ok(TransactionListView::try_from(txs)?)
 }
@@ -103,6 +104,7 @@ pub fn get_transactions_with_proofs(
     if start_version > ledger_version || limit == 0 {
         return Ok(None);
     }
+    error_if_pruned(db, start_version)?;
     let txs = db.get_transactions(start_version, limit, ledger_version, include_events)?;
     Ok(Some(TransactionsWithProofsView::try_from(&txs)?))
 }
@@ -138,13 +140,15 @@ pub fn get_account_transactions(
     include_events: bool,
     ledger_version: u64,
 ) -> Result<Vec<TransactionView>, JsonRpcError> {
-    let acct_txs = db.get_account_transactions(
-        account,
-        start_seq_num,
-        limit,
-        include_events,
-        ledger_version,
-    )?;
+    let acct_txs = db
+        .get_account_transactions(
+            account,
+            start_seq_num,
+            limit,
+            include_events,
+            ledger_version,
+        )
+        .map_err(db_error)?;
     let txs = TransactionListView::try_from(acct_txs)?;
     Ok(txs.0)
 }
@@ -159,8 +163,9 @@ pub fn get_account_transactions_with_proofs(
     include_events: bool,
     ledger_version: u64,
 ) -> Result<AccountTransactionsWithProofView, JsonRpcError> {
-    let acct_txns_with_proof =
-        db.get_account_transactions(account, start, limit, include_events, ledger_version)?;
+    let acct_txns_with_proof = db
+        .get_account_transactions(account, start, limit, include_events, ledger_version)
+        .map_err(db_error)?;
     Ok(AccountTransactionsWithProofView::try_from(
         &acct_txns_with_proof,
     )?)
@@ -296,6 +301,37 @@ pub fn get_account_state_with_proof(
     )?)
 }
 
+/// Returns the states of `account_addresses` to the client, alongside one proof for all of them
+/// relative to the version and ledger_version specified by the client.
+pub fn get_account_states_with_multiproof(
+    db: &dyn MoveDbReader<DpnProto>,
+    ledger_version: u64,
+    account_addresses: Vec<AccountAddress>,
+    version: u64,
+) -> Result<AccountStatesWithMultiproofView, JsonRpcError> {
+    if version > ledger_version {
+        return Err(JsonRpcError::invalid_request_with_msg(format!(
+            "version({}) should <= ledger version({})",
+            version, ledger_version
+        )));
+    }
+    if account_addresses.is_empty() {
+        return Err(JsonRpcError::invalid_request_with_msg(
+            "accounts should not be empty".to_string(),
+        ));
+    }
+    if account_addresses.iter().collect::<HashSet<_>>().len() != account_addresses.len() {
+        return Err(JsonRpcError::invalid_request_with_msg(
+            "accounts should be distinct".to_string(),
+        ));
+    }
+    let account_states_with_multiproof =
+        db.get_account_states_with_multiproof(account_addresses, version, ledger_version)?;
+    Ok(AccountStatesWithMultiproofView::try_from(
+        account_states_with_multiproof,
+    )?)
+}
+
 /// Get all resources stored under `account_address` at `version`
 pub fn get_resources(
     db: &dyn MoveDbReader<DpnProto>,
@@ -317,3 +353,23 @@ pub fn get_resources(
     }
     Ok(resources)
 }
+
+/// Converts an error reading the DB, which is precise if the ledger history read has been pruned.
+fn db_error(error: anyhow::Error) -> JsonRpcError {
+    match error.downcast_ref::<LedgerHistoryPruned>() {
+        Some(pruned) => JsonRpcError::ledger_history_pruned(pruned.to_string()),
+        None => error.into(),
+    }
+}
+
+/// Returns a precise error if the ledger history at `version` has been pruned.
+fn error_if_pruned(db: &dyn MoveDbReader<DpnProto>, version: u64) -> Result<(), JsonRpcError> {
+    let first_available_version = db.get_first_available_ledger_version()?;
+    if version < first_available_version {
+        return Err(JsonRpcError::version_pruned(
+            version,
+            first_available_version,
+        ));
+    }
+    Ok(())
+}
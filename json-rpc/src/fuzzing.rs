@@ -63,6 +63,10 @@ fn test_method_fuzzer() {
         &gen_request_params!(["000000000000000000000000000000dd", 0, 1]),
         "get_account_state_with_proof",
     );
+    method_fuzzer(
+        &gen_request_params!([["000000000000000000000000000000dd"], 0, 1]),
+        "get_account_states_with_multiproof",
+    );
     method_fuzzer(
         &gen_request_params!([0, 1, true]),
         "get_transactions_with_proofs",
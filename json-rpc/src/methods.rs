@@ -6,21 +6,21 @@ use crate::{
     data,
     errors::JsonRpcError,
     views::{
-        AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionListView, TransactionView,
-        TransactionsWithProofsView,
+        AccountStateWithProofView, AccountStatesWithMultiproofView,
+        AccountTransactionsWithProofView, AccountView, AccumulatorConsistencyProofView,
+        CurrencyInfoView, EventByVersionWithProofView, EventView, EventWithProofView, MetadataView,
+        StateProofView, TransactionListView, TransactionView, TransactionsWithProofsView,
     },
 };
 use anyhow::Result;
 use diem_config::config::RoleType;
 use diem_json_rpc_types::request::{
-    GetAccountParams, GetAccountStateWithProofParams, GetAccountTransactionParams,
-    GetAccountTransactionsParams, GetAccountTransactionsWithProofsParams,
-    GetAccumulatorConsistencyProofParams, GetCurrenciesParams, GetEventByVersionWithProof,
-    GetEventsParams, GetEventsWithProofsParams, GetMetadataParams, GetNetworkStatusParams,
-    GetResourcesParams, GetStateProofParams, GetTransactionsParams,
-    GetTransactionsWithProofsParams, MethodRequest, SubmitParams,
+    GetAccountParams, GetAccountStateWithProofParams, GetAccountStatesWithMultiproofParams,
+    GetAccountTransactionParams, GetAccountTransactionsParams,
+    GetAccountTransactionsWithProofsParams, GetAccumulatorConsistencyProofParams,
+    GetCurrenciesParams, GetEventByVersionWithProof, GetEventsParams, GetEventsWithProofsParams,
+    GetMetadataParams, GetNetworkStatusParams, GetResourcesParams, GetStateProofParams,
+    GetTransactionsParams, GetTransactionsWithProofsParams, MethodRequest, SubmitParams,
 };
 use diem_mempool::{MempoolClientSender, SubmissionStatus};
 use diem_types::{
@@ -181,6 +181,9 @@ impl<'a> Handler<'a> {
             MethodRequest::GetAccountStateWithProof(params) => {
                 serde_json::to_value(self.get_account_state_with_proof(params).await?)?
             }
+            MethodRequest::GetAccountStatesWithMultiproof(params) => {
+                serde_json::to_value(self.get_account_states_with_multiproof(params).await?)?
+            }
             MethodRequest::GetTransactionsWithProofs(params) => {
                 serde_json::to_value(self.get_transactions_with_proofs(params).await?)?
             }
@@ -445,4 +448,24 @@ impl<'a> Handler<'a> {
             version,
         )
     }
+
+    /// Returns the states of multiple accounts to the client, alongside one proof for all of them
+    /// relative to the version and ledger_version specified by the client. If version or
+    /// ledger_version are not specified, the latest known versions will be used.
+    async fn get_account_states_with_multiproof(
+        &self,
+        params: GetAccountStatesWithMultiproofParams,
+    ) -> Result<AccountStatesWithMultiproofView, JsonRpcError> {
+        self.service
+            .validate_page_size_limit(params.accounts.len())?;
+        let version = self.version_param(params.version, "version")?;
+        let ledger_version = self.version_param(params.ledger_version, "ledger_version")?;
+
+        data::get_account_states_with_multiproof(
+            self.service.db.borrow(),
+            ledger_version,
+            params.accounts,
+            version,
+        )
+    }
 }
@@ -24,7 +24,10 @@ use diem_types::{
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     mempool_status::{MempoolStatus, MempoolStatusCode},
-    proof::{SparseMerkleProof, TransactionAccumulatorProof, TransactionInfoWithProof},
+    proof::{
+        SparseMerkleMultiProof, SparseMerkleProof, TransactionAccumulatorProof,
+        TransactionInfoWithProof,
+    },
     test_helpers::transaction_test_helpers::get_test_signed_txn,
     transaction::{Transaction, TransactionInfo, TransactionPayload},
     vm_status::StatusCode,
@@ -968,6 +971,70 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "diem_ledger_version": version
             }),
         ),
+        (
+            "get_account_states_with_multiproof: invalid account address",
+            json!({"jsonrpc": "2.0", "method": "get_account_states_with_multiproof", "params": [["invalid"], 1, 1], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32602,
+                    "message": "Invalid params for method 'get_account_states_with_multiproof'",
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
+        (
+            "get_account_states_with_multiproof: no accounts",
+            json!({"jsonrpc": "2.0", "method": "get_account_states_with_multiproof", "params": [[], version, version], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32600,
+                    "message": "Invalid Request: accounts should not be empty",
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
+        (
+            "get_account_states_with_multiproof: duplicate accounts",
+            json!({"jsonrpc": "2.0", "method": "get_account_states_with_multiproof", "params": [["e1b3d22871989e9fd9dc6814b2f4fc41", "e1b3d22871989e9fd9dc6814b2f4fc41"], version, version], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32600,
+                    "message": "Invalid Request: accounts should be distinct",
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
+        (
+            "get_account_states_with_multiproof: version > ledger version",
+            json!({"jsonrpc": "2.0", "method": "get_account_states_with_multiproof", "params": [["e1b3d22871989e9fd9dc6814b2f4fc41"], version, version-1], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32600,
+                    "message": format!("Invalid Request: version({}) should <= ledger version({})",version, version-1),
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
     ];
     for (name, request, expected) in calls {
         let resp = client.post(&url).json(&request).send().unwrap();
@@ -1482,6 +1549,45 @@ fn test_get_account_state_with_proof() {
     assert_eq!(txn_info_with_proof, *expected_txn_info_with_proof);
 }
 
+#[test]
+fn test_get_account_states_with_multiproof() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let account = get_first_account_from_mock_db(&mock_db);
+    let nonexistent_account = AccountAddress::random();
+    let received = client
+        .get_account_states_with_multiproof(vec![nonexistent_account, account], None, None)
+        .unwrap()
+        .into_inner();
+    let expected_proof = get_first_state_proof_from_mock_db(&mock_db);
+
+    // version
+    assert_eq!(received.version, expected_proof.version);
+
+    // blobs, in the order of the requested accounts
+    assert_eq!(received.blobs.len(), 2);
+    assert!(received.blobs[0].is_none());
+    let account_blob: AccountStateBlob =
+        bcs::from_bytes(received.blobs[1].as_ref().unwrap()).unwrap();
+    assert_eq!(
+        Some(account_blob),
+        mock_db.get_latest_account_state(account).unwrap()
+    );
+
+    // proof
+    let txn_info: TransactionInfo = bcs::from_bytes(&received.proof.transaction_info).unwrap();
+    let li_proof: TransactionAccumulatorProof =
+        bcs::from_bytes(&received.proof.ledger_info_to_transaction_info_proof).unwrap();
+    let txn_info_with_proof = TransactionInfoWithProof::new(li_proof, txn_info);
+    assert_eq!(
+        txn_info_with_proof,
+        *expected_proof.proof.transaction_info_with_proof()
+    );
+    let multiproof: SparseMerkleMultiProof<AccountStateBlob> =
+        bcs::from_bytes(&received.proof.transaction_info_to_accounts_multiproof).unwrap();
+    assert_eq!(multiproof, SparseMerkleMultiProof::new(vec![], vec![]));
+}
+
 #[test]
 fn test_get_state_proof() {
     let (mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -16,7 +16,10 @@ use diem_mempool::{MempoolClientSender, SubmissionStatus};
 use diem_types::{
     account_address::AccountAddress,
     account_state::AccountState,
-    account_state_blob::{default_protocol::AccountStateWithProof, AccountStateBlob},
+    account_state_blob::{
+        default_protocol::{AccountStateWithProof, AccountStatesWithMultiProof},
+        AccountStateBlob,
+    },
     block_info::BlockInfo,
     chain_id::ChainId,
     contract_event::{
@@ -27,8 +30,9 @@ use diem_types::{
     event::EventKey,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     proof::default_protocol::{
-        AccumulatorConsistencyProof, AccumulatorRangeProof, SparseMerkleProof,
-        TransactionAccumulatorProof, TransactionInfoListWithProof, TransactionInfoWithProof,
+        AccountStatesMultiProof, AccumulatorConsistencyProof, AccumulatorRangeProof,
+        SparseMerkleMultiProof, SparseMerkleProof, TransactionAccumulatorProof,
+        TransactionInfoListWithProof, TransactionInfoWithProof,
     },
     protocol_spec::DpnProto,
     state_proof::StateProof,
@@ -124,6 +128,10 @@ impl DbReader<DpnProto> for MockDiemDB {
         }
     }
 
+    fn get_first_available_ledger_version(&self) -> Result<Version> {
+        Ok(0)
+    }
+
     fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
         Ok(LedgerInfoWithSignatures::new(
             LedgerInfo::new(
@@ -349,6 +357,33 @@ impl DbReader<DpnProto> for MockDiemDB {
             .clone())
     }
 
+    fn get_account_states_with_multiproof(
+        &self,
+        addresses: Vec<AccountAddress>,
+        _version: Version,
+        _ledger_version: Version,
+    ) -> Result<AccountStatesWithMultiProof> {
+        let account_state_with_proof = self
+            .account_state_with_proof
+            .get(0)
+            .ok_or_else(|| format_err!("could not find account state"))?;
+        let blobs = addresses
+            .iter()
+            .map(|address| self.get_latest_account_state(*address))
+            .collect::<Result<_>>()?;
+        Ok(AccountStatesWithMultiProof::new(
+            account_state_with_proof.version,
+            blobs,
+            AccountStatesMultiProof::new(
+                account_state_with_proof
+                    .proof
+                    .transaction_info_with_proof()
+                    .clone(),
+                SparseMerkleMultiProof::new(vec![], vec![]),
+            ),
+        ))
+    }
+
     fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
         unimplemented!()
     }
@@ -51,6 +51,9 @@ pub enum ServerCode {
     MempoolInvalidUpdate = -32010,
     MempoolVmError = -32011,
     MempoolUnknownError = -32012,
+
+    // Storage errors
+    VersionPruned = -32013,
 }
 
 /// JSON RPC server error codes for invalid request
@@ -188,6 +191,27 @@ impl JsonRpcError {
         }
     }
 
+    pub fn version_pruned(version: u64, first_available_version: u64) -> Self {
+        Self {
+            code: ServerCode::VersionPruned as i16,
+            message: format!(
+                "Server error: transaction version {} has been pruned, first available version is {}",
+                version, first_available_version
+            ),
+            data: None,
+        }
+    }
+
+    /// Like `version_pruned`, for pruned ledger history not requested by version, e.g. the
+    /// transactions of an account.
+    pub fn ledger_history_pruned(message: String) -> Self {
+        Self {
+            code: ServerCode::VersionPruned as i16,
+            message: format!("Server error: {}", message),
+            data: None,
+        }
+    }
+
     pub fn code_as_str(&self) -> &'static str {
         match InvalidRequestCode::from_i16(self.code) {
             Some(code) => code.as_str(),
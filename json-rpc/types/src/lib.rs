@@ -55,6 +55,7 @@ pub enum Method {
     GetStateProof,
     GetAccumulatorConsistencyProof,
     GetAccountStateWithProof,
+    GetAccountStatesWithMultiproof,
     GetTransactionsWithProofs,
     GetAccountTransactionsWithProofs,
     GetEventsWithProofs,
@@ -77,6 +78,7 @@ impl Method {
             Method::GetStateProof => "get_state_proof",
             Method::GetAccumulatorConsistencyProof => "get_accumulator_consistency_proof",
             Method::GetAccountStateWithProof => "get_account_state_with_proof",
+            Method::GetAccountStatesWithMultiproof => "get_account_states_with_multiproof",
             Method::GetTransactionsWithProofs => "get_transactions_with_proofs",
             Method::GetAccountTransactionsWithProofs => "get_account_transactions_with_proofs",
             Method::GetEventsWithProofs => "get_events_with_proofs",
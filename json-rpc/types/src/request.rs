@@ -80,6 +80,7 @@ pub enum MethodRequest {
     GetStateProof(GetStateProofParams),
     GetAccumulatorConsistencyProof(GetAccumulatorConsistencyProofParams),
     GetAccountStateWithProof(GetAccountStateWithProofParams),
+    GetAccountStatesWithMultiproof(GetAccountStatesWithMultiproofParams),
     GetTransactionsWithProofs(GetTransactionsWithProofsParams),
     GetAccountTransactionsWithProofs(GetAccountTransactionsWithProofsParams),
     GetEventsWithProofs(GetEventsWithProofsParams),
@@ -114,6 +115,9 @@ impl MethodRequest {
             Method::GetAccountStateWithProof => {
                 MethodRequest::GetAccountStateWithProof(serde_json::from_value(value)?)
             }
+            Method::GetAccountStatesWithMultiproof => {
+                MethodRequest::GetAccountStatesWithMultiproof(serde_json::from_value(value)?)
+            }
             Method::GetTransactionsWithProofs => {
                 MethodRequest::GetTransactionsWithProofs(serde_json::from_value(value)?)
             }
@@ -148,6 +152,9 @@ impl MethodRequest {
                 Method::GetAccumulatorConsistencyProof
             }
             MethodRequest::GetAccountStateWithProof(_) => Method::GetAccountStateWithProof,
+            MethodRequest::GetAccountStatesWithMultiproof(_) => {
+                Method::GetAccountStatesWithMultiproof
+            }
             MethodRequest::GetTransactionsWithProofs(_) => Method::GetTransactionsWithProofs,
             MethodRequest::GetAccountTransactionsWithProofs(_) => {
                 Method::GetAccountTransactionsWithProofs
@@ -352,6 +359,15 @@ pub struct GetAccountStateWithProofParams {
     pub ledger_version: Option<u64>,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetAccountStatesWithMultiproofParams {
+    pub accounts: Vec<AccountAddress>,
+    #[serde(default)]
+    pub version: Option<u64>,
+    #[serde(default)]
+    pub ledger_version: Option<u64>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GetTransactionsWithProofsParams {
     pub start_version: u64,
@@ -880,6 +896,49 @@ mod test {
         }));
     }
 
+    #[test]
+    fn get_account_states_with_multiproof() {
+        let parse = |value| serde_json::from_value::<GetAccountStatesWithMultiproofParams>(value);
+        let parse_ok = |value| parse(value).unwrap();
+        let parse_err = |value| parse(value).unwrap_err();
+
+        let account1 = "1668f6be25668c1a17cd8caf6b8d2f25";
+        let account2 = "e1b3d22871989e9fd9dc6814b2f4fc41";
+
+        // Array with all params
+        parse_ok(json!([[account1, account2], 11, 12]));
+
+        // Array without optional params
+        parse_ok(json!([[account1]]));
+        parse_ok(json!([[account1, account2], 12]));
+
+        // Array with too many params
+        parse_err(json!([[account1], 11, 12, "foo"]));
+
+        // Array with wrong param
+        parse_err(json!([account1, 11, 12]));
+        parse_err(json!([["foo"], 11, 12]));
+
+        // Empty array without required params should fail
+        parse_err(json!([]));
+
+        // Object without required params should fail
+        parse_err(json!({}));
+
+        // Object params
+        parse_ok(json!({
+            "accounts": [account1, account2],
+            "version": 10,
+            "ledger_version": 10,
+        }));
+
+        // Object without all params
+        parse_ok(json!({
+            "accounts": [account1],
+            "ledger_version": 10,
+        }));
+    }
+
     #[test]
     fn get_transactions_with_proofs() {
         let parse = |value| serde_json::from_value::<GetTransactionsWithProofsParams>(value);
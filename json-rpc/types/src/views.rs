@@ -13,7 +13,10 @@ use diem_types::{
         SentPaymentEvent, ToXDXExchangeRateUpdateEvent, VASPDomainEvent,
     },
     account_state::AccountState,
-    account_state_blob::{default_protocol::AccountStateWithProof, AccountStateBlob},
+    account_state_blob::{
+        default_protocol::{AccountStateWithProof, AccountStatesWithMultiProof},
+        AccountStateBlob,
+    },
     contract_event::{
         default_protocol::{EventByVersionWithProof, EventWithProof},
         ContractEvent,
@@ -21,8 +24,9 @@ use diem_types::{
     diem_id_identifier::DiemIdVaspDomainIdentifier,
     event::EventKey,
     proof::default_protocol::{
-        AccountStateProof, AccumulatorConsistencyProof, SparseMerkleProof,
-        TransactionAccumulatorProof, TransactionInfoListWithProof, TransactionInfoWithProof,
+        AccountStateProof, AccountStatesMultiProof, AccumulatorConsistencyProof,
+        SparseMerkleMultiProof, SparseMerkleProof, TransactionAccumulatorProof,
+        TransactionInfoListWithProof, TransactionInfoWithProof,
     },
     state_proof::StateProof,
     transaction::{
@@ -1544,6 +1548,118 @@ impl TryFrom<&AccountStateProofView> for AccountStateProof {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AccountStatesWithMultiproofView {
+    pub version: u64,
+    /// In the order of the requested accounts, `None` for an account that does not exist.
+    pub blobs: Vec<Option<BytesView>>,
+    pub proof: AccountStatesMultiproofView,
+}
+
+impl TryFrom<AccountStatesWithMultiProof> for AccountStatesWithMultiproofView {
+    type Error = Error;
+
+    fn try_from(
+        account_states_with_multiproof: AccountStatesWithMultiProof,
+    ) -> Result<AccountStatesWithMultiproofView, Error> {
+        let blobs = account_states_with_multiproof
+            .blobs
+            .iter()
+            .map(|blob| {
+                blob.as_ref()
+                    .map(|account_blob| bcs::to_bytes(account_blob).map(BytesView::new))
+                    .transpose()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(AccountStatesWithMultiproofView {
+            version: account_states_with_multiproof.version,
+            blobs,
+            proof: AccountStatesMultiproofView::try_from(account_states_with_multiproof.proof)?,
+        })
+    }
+}
+
+impl TryFrom<&AccountStatesWithMultiproofView> for AccountStatesWithMultiProof {
+    type Error = Error;
+
+    fn try_from(
+        account_states_with_multiproof_view: &AccountStatesWithMultiproofView,
+    ) -> Result<AccountStatesWithMultiProof, Self::Error> {
+        let blobs = account_states_with_multiproof_view
+            .blobs
+            .iter()
+            .map(|blob_view| {
+                blob_view
+                    .as_ref()
+                    .map(|blob_view| bcs::from_bytes(blob_view.as_ref()))
+                    .transpose()
+            })
+            .collect::<Result<_, _>>()?;
+        let version = account_states_with_multiproof_view.version;
+        let proof = AccountStatesMultiProof::try_from(&account_states_with_multiproof_view.proof)?;
+        Ok(AccountStatesWithMultiProof::new(version, blobs, proof))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AccountStatesMultiproofView {
+    pub ledger_info_to_transaction_info_proof: BytesView,
+    pub transaction_info: BytesView,
+    pub transaction_info_to_accounts_multiproof: BytesView,
+}
+
+impl TryFrom<AccountStatesMultiProof> for AccountStatesMultiproofView {
+    type Error = Error;
+
+    fn try_from(
+        account_states_multiproof: AccountStatesMultiProof,
+    ) -> Result<AccountStatesMultiproofView, Error> {
+        Ok(AccountStatesMultiproofView {
+            ledger_info_to_transaction_info_proof: BytesView::new(bcs::to_bytes(
+                account_states_multiproof
+                    .transaction_info_with_proof()
+                    .ledger_info_to_transaction_info_proof(),
+            )?),
+            transaction_info: BytesView::new(bcs::to_bytes(
+                account_states_multiproof
+                    .transaction_info_with_proof()
+                    .transaction_info(),
+            )?),
+            transaction_info_to_accounts_multiproof: BytesView::new(bcs::to_bytes(
+                account_states_multiproof.transaction_info_to_accounts_multiproof(),
+            )?),
+        })
+    }
+}
+
+impl TryFrom<&AccountStatesMultiproofView> for AccountStatesMultiProof {
+    type Error = Error;
+
+    fn try_from(
+        account_states_multiproof_view: &AccountStatesMultiproofView,
+    ) -> Result<AccountStatesMultiProof, Self::Error> {
+        let ledger_info_to_transaction_info_proof: TransactionAccumulatorProof = bcs::from_bytes(
+            account_states_multiproof_view
+                .ledger_info_to_transaction_info_proof
+                .as_ref(),
+        )?;
+        let transaction_info =
+            bcs::from_bytes(account_states_multiproof_view.transaction_info.as_ref())?;
+        let transaction_info_with_proof =
+            TransactionInfoWithProof::new(ledger_info_to_transaction_info_proof, transaction_info);
+        let transaction_info_to_accounts_multiproof: SparseMerkleMultiProof<AccountStateBlob> =
+            bcs::from_bytes(
+                account_states_multiproof_view
+                    .transaction_info_to_accounts_multiproof
+                    .as_ref(),
+            )?;
+        Ok(AccountStatesMultiProof::new(
+            transaction_info_with_proof,
+            transaction_info_to_accounts_multiproof,
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::views::{
@@ -3,7 +3,7 @@
 
 use crate::DiemValidatorInterface;
 use anyhow::{anyhow, Result};
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_types::{
     account_address::AccountAddress,
     account_state::AccountState,
@@ -24,7 +24,9 @@ impl DBDebuggerInterface {
             db_root_path,
             true,
             None,
+            None,
             RocksdbConfig::default(),
+            SecondaryIndexesConfig::default(),
         )?)))
     }
 }
@@ -48,6 +48,7 @@ pub enum ProtocolId {
     HealthCheckerRpc = 5,
     // json provides flexibility for backwards compatible upgrade
     ConsensusDirectSendJSON = 6,
+    StorageServiceRpc = 7,
 }
 
 impl ProtocolId {
@@ -61,6 +62,7 @@ impl ProtocolId {
             DiscoveryDirectSend => "DiscoveryDirectSend",
             HealthCheckerRpc => "HealthCheckerRpc",
             ConsensusDirectSendJSON => "ConsensusDirectSendJson",
+            StorageServiceRpc => "StorageServiceRpc",
         }
     }
 
@@ -73,6 +75,7 @@ impl ProtocolId {
             ProtocolId::DiscoveryDirectSend,
             ProtocolId::HealthCheckerRpc,
             ProtocolId::ConsensusDirectSendJSON,
+            ProtocolId::StorageServiceRpc,
         ]
     }
 
@@ -11,10 +11,10 @@ use crate::{
     error::WaitForTransactionError,
     move_deserialize::{self, Event},
     views::{
-        AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionView,
-        TransactionsWithProofsView,
+        AccountStateWithProofView, AccountStatesWithMultiproofView,
+        AccountTransactionsWithProofView, AccountView, AccumulatorConsistencyProofView,
+        CurrencyInfoView, EventByVersionWithProofView, EventView, EventWithProofView, MetadataView,
+        StateProofView, TransactionView, TransactionsWithProofsView,
     },
     Error, Result, Retry, State,
 };
@@ -267,6 +267,20 @@ impl Client {
         .await
     }
 
+    pub async fn get_account_states_with_multiproof(
+        &self,
+        addresses: Vec<AccountAddress>,
+        version: Option<u64>,
+        ledger_version: Option<u64>,
+    ) -> Result<Response<AccountStatesWithMultiproofView>> {
+        self.send(MethodRequest::get_account_states_with_multiproof(
+            addresses,
+            version,
+            ledger_version,
+        ))
+        .await
+    }
+
     pub async fn get_transactions_with_proofs(
         &self,
         start_version: u64,
@@ -84,6 +84,7 @@ pub enum Method {
     GetStateProof,
     GetAccumulatorConsistencyProof,
     GetAccountStateWithProof,
+    GetAccountStatesWithMultiproof,
     GetTransactionsWithProofs,
     GetAccountTransactionsWithProofs,
     GetEventsWithProofs,
@@ -28,6 +28,7 @@ pub enum MethodRequest {
     GetStateProof((u64,)),
     GetAccumulatorConsistencyProof(Option<u64>, Option<u64>),
     GetAccountStateWithProof(AccountAddress, Option<u64>, Option<u64>),
+    GetAccountStatesWithMultiproof(Vec<AccountAddress>, Option<u64>, Option<u64>),
     GetTransactionsWithProofs(u64, u64, bool),
     GetAccountTransactionsWithProofs(AccountAddress, u64, u64, bool, Option<u64>),
     GetEventsWithProofs(EventKey, u64, u64),
@@ -112,6 +113,14 @@ impl MethodRequest {
         Self::GetAccountStateWithProof(address, version, ledger_version)
     }
 
+    pub fn get_account_states_with_multiproof(
+        addresses: Vec<AccountAddress>,
+        version: Option<u64>,
+        ledger_version: Option<u64>,
+    ) -> Self {
+        Self::GetAccountStatesWithMultiproof(addresses, version, ledger_version)
+    }
+
     pub fn get_transactions_with_proofs(
         start_version: u64,
         limit: u64,
@@ -160,6 +169,9 @@ impl MethodRequest {
                 Method::GetAccumulatorConsistencyProof
             }
             MethodRequest::GetAccountStateWithProof(_, _, _) => Method::GetAccountStateWithProof,
+            MethodRequest::GetAccountStatesWithMultiproof(_, _, _) => {
+                Method::GetAccountStatesWithMultiproof
+            }
             MethodRequest::GetTransactionsWithProofs(_, _, _) => Method::GetTransactionsWithProofs,
             MethodRequest::GetAccountTransactionsWithProofs(_, _, _, _, _) => {
                 Method::GetAccountTransactionsWithProofs
@@ -4,10 +4,10 @@
 use super::Method;
 use crate::{
     views::{
-        AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionView,
-        TransactionsWithProofsView,
+        AccountStateWithProofView, AccountStatesWithMultiproofView,
+        AccountTransactionsWithProofView, AccountView, AccumulatorConsistencyProofView,
+        CurrencyInfoView, EventByVersionWithProofView, EventView, EventWithProofView, MetadataView,
+        StateProofView, TransactionView, TransactionsWithProofsView,
     },
     Error, State,
 };
@@ -72,6 +72,7 @@ pub enum MethodResponse {
     GetStateProof(StateProofView),
     GetAccumulatorConsistencyProof(AccumulatorConsistencyProofView),
     GetAccountStateWithProof(AccountStateWithProofView),
+    GetAccountStatesWithMultiproof(AccountStatesWithMultiproofView),
     GetTransactionsWithProofs(Option<TransactionsWithProofsView>),
     GetAccountTransactionsWithProofs(AccountTransactionsWithProofView),
     GetEventsWithProofs(Vec<EventWithProofView>),
@@ -105,6 +106,9 @@ impl MethodResponse {
             Method::GetAccountStateWithProof => {
                 MethodResponse::GetAccountStateWithProof(serde_json::from_value(json)?)
             }
+            Method::GetAccountStatesWithMultiproof => {
+                MethodResponse::GetAccountStatesWithMultiproof(serde_json::from_value(json)?)
+            }
             Method::GetTransactionsWithProofs => {
                 MethodResponse::GetTransactionsWithProofs(serde_json::from_value(json)?)
             }
@@ -138,6 +142,9 @@ impl MethodResponse {
                 Method::GetAccumulatorConsistencyProof
             }
             MethodResponse::GetAccountStateWithProof(_) => Method::GetAccountStateWithProof,
+            MethodResponse::GetAccountStatesWithMultiproof(_) => {
+                Method::GetAccountStatesWithMultiproof
+            }
             MethodResponse::GetTransactionsWithProofs(_) => Method::GetTransactionsWithProofs,
             MethodResponse::GetAccountTransactionsWithProofs(_) => {
                 Method::GetAccountTransactionsWithProofs
@@ -250,6 +257,18 @@ impl MethodResponse {
         }
     }
 
+    pub fn try_into_get_account_states_with_multiproof(
+        self,
+    ) -> Result<AccountStatesWithMultiproofView, Error> {
+        match self {
+            MethodResponse::GetAccountStatesWithMultiproof(view) => Ok(view),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetAccountStatesWithMultiproof found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
     pub fn try_into_get_events(self) -> Result<Vec<EventView>, Error> {
         match self {
             MethodResponse::GetEvents(events) => Ok(events),
@@ -337,7 +337,7 @@ fn setup_node_using_test_mocks() -> Node<MockDiemInterface> {
 
 // Creates and returns a diem database and database reader/writer pair bootstrapped with genesis.
 fn setup_diem_db(config: &NodeConfig) -> (Arc<DiemDB>, DbReaderWriter) {
-    let (storage, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(&config.storage.dir()));
+    let (storage, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
     executor_test_helpers::bootstrap_genesis::<DiemVM>(&db_rw, get_genesis_txn(config).unwrap())
         .expect("Failed to execute genesis");
 
@@ -546,9 +546,7 @@ fn create_database() -> Arc<RwLock<DbReaderWriter>> {
     let (genesis, _) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
 
     // Create test diem database
-    let db_path = diem_temppath::TempPath::new();
-    assert_ok!(db_path.create_as_dir());
-    let (_, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+    let (_, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
 
     // Bootstrap the genesis transaction
     let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
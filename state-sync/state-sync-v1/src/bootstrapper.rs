@@ -11,7 +11,7 @@ use diem_config::{config::NodeConfig, network_id::NetworkId};
 use diem_types::{protocol_spec::DpnProto, waypoint::Waypoint};
 use event_notifications::EventSubscriptionService;
 use executor_types::ChunkExecutor;
-use futures::channel::mpsc;
+use futures::{channel::mpsc, Future};
 use mempool_notifications::MempoolNotificationSender;
 use std::{boxed::Box, collections::HashMap, sync::Arc};
 use storage_interface::DbReader;
@@ -35,17 +35,9 @@ impl StateSyncBootstrapper {
         waypoint: Waypoint,
         event_subscription_service: EventSubscriptionService,
     ) -> Self {
-        let runtime = Builder::new_multi_thread()
-            .thread_name("state-sync")
-            .enable_all()
-            .build()
-            .expect("[State Sync] Failed to create runtime!");
-
-        // We notify all reconfig subscribers upon executor proxy creation
-        let mut executor_proxy = ExecutorProxy::new(storage, executor, event_subscription_service);
-        executor_proxy
-            .notify_initial_configs()
-            .expect("Failed to notify reconfig subscribers on initialization!");
+        let runtime = Self::create_runtime();
+        let executor_proxy =
+            Self::create_executor_proxy(storage, executor, event_subscription_service);
 
         Self::bootstrap_with_executor_proxy(
             runtime,
@@ -58,6 +50,53 @@ impl StateSyncBootstrapper {
         )
     }
 
+    /// Like `bootstrap`, but the coordinator only starts (on the state sync
+    /// runtime) once `initialization` completes, e.g., once state sync v2 has
+    /// bootstrapped the node. The executor reads the latest state from storage,
+    /// so it's only created by `create_executor` then. Clients can be created
+    /// right away, their requests are handled once the coordinator starts.
+    pub fn bootstrap_after<M, I, F>(
+        initialization: I,
+        network: Vec<(NetworkId, StateSyncSender, StateSyncEvents)>,
+        mempool_notifier: M,
+        consensus_listener: ConsensusNotificationListener,
+        storage: Arc<dyn DbReader<DpnProto>>,
+        create_executor: F,
+        node_config: &NodeConfig,
+        waypoint: Waypoint,
+        event_subscription_service: EventSubscriptionService,
+    ) -> Self
+    where
+        M: MempoolNotificationSender + 'static,
+        I: Future<Output = ()> + Send + 'static,
+        F: FnOnce() -> Box<dyn ChunkExecutor> + Send + 'static,
+    {
+        let runtime = Self::create_runtime();
+        let (coordinator_sender, coordinator_receiver) = mpsc::unbounded();
+        let node_config = node_config.clone();
+        runtime.spawn(async move {
+            initialization.await;
+
+            let executor_proxy =
+                Self::create_executor_proxy(storage, create_executor(), event_subscription_service);
+            let coordinator = Self::create_coordinator(
+                coordinator_receiver,
+                &network,
+                mempool_notifier,
+                consensus_listener,
+                &node_config,
+                waypoint,
+                executor_proxy,
+            );
+            coordinator.start(network).await
+        });
+
+        Self {
+            _runtime: runtime,
+            coordinator_sender,
+        }
+    }
+
     pub fn bootstrap_with_executor_proxy<
         E: ExecutorProxyTrait + 'static,
         M: MempoolNotificationSender + 'static,
@@ -71,6 +110,53 @@ impl StateSyncBootstrapper {
         executor_proxy: E,
     ) -> Self {
         let (coordinator_sender, coordinator_receiver) = mpsc::unbounded();
+        let coordinator = Self::create_coordinator(
+            coordinator_receiver,
+            &network,
+            mempool_notifier,
+            consensus_listener,
+            node_config,
+            waypoint,
+            executor_proxy,
+        );
+        runtime.spawn(coordinator.start(network));
+
+        Self {
+            _runtime: runtime,
+            coordinator_sender,
+        }
+    }
+
+    fn create_runtime() -> Runtime {
+        Builder::new_multi_thread()
+            .thread_name("state-sync")
+            .enable_all()
+            .build()
+            .expect("[State Sync] Failed to create runtime!")
+    }
+
+    fn create_executor_proxy(
+        storage: Arc<dyn DbReader<DpnProto>>,
+        executor: Box<dyn ChunkExecutor>,
+        event_subscription_service: EventSubscriptionService,
+    ) -> ExecutorProxy {
+        // We notify all reconfig subscribers upon executor proxy creation
+        let mut executor_proxy = ExecutorProxy::new(storage, executor, event_subscription_service);
+        executor_proxy
+            .notify_initial_configs()
+            .expect("Failed to notify reconfig subscribers on initialization!");
+        executor_proxy
+    }
+
+    fn create_coordinator<E: ExecutorProxyTrait, M: MempoolNotificationSender>(
+        coordinator_receiver: mpsc::UnboundedReceiver<CoordinatorMessage>,
+        network: &[(NetworkId, StateSyncSender, StateSyncEvents)],
+        mempool_notifier: M,
+        consensus_listener: ConsensusNotificationListener,
+        node_config: &NodeConfig,
+        waypoint: Waypoint,
+        executor_proxy: E,
+    ) -> StateSyncCoordinator<E, M> {
         let initial_state = executor_proxy
             .get_local_storage_state()
             .expect("[State Sync] Starting failure: cannot sync with storage!");
@@ -79,7 +165,7 @@ impl StateSyncBootstrapper {
             .map(|(network_id, sender, _events)| (*network_id, sender.clone()))
             .collect();
 
-        let coordinator = StateSyncCoordinator::new(
+        StateSyncCoordinator::new(
             coordinator_receiver,
             mempool_notifier,
             consensus_listener,
@@ -89,13 +175,7 @@ impl StateSyncBootstrapper {
             executor_proxy,
             initial_state,
         )
-        .expect("[State Sync] Unable to create state sync coordinator!");
-        runtime.spawn(coordinator.start(network));
-
-        Self {
-            _runtime: runtime,
-            coordinator_sender,
-        }
+        .expect("[State Sync] Unable to create state sync coordinator!")
     }
 
     pub fn create_client(&self) -> StateSyncClient {
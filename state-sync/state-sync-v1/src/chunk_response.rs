@@ -3,7 +3,10 @@
 
 use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
-    transaction::{default_protocol::TransactionListWithProof, Version},
+    transaction::{
+        default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
+        TransactionOutput, Version,
+    },
 };
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -75,6 +78,72 @@ impl GetChunkResponse {
     }
 }
 
+/// The response to a chunk request for transaction outputs (see `ContinuousSyncingMode`). The
+/// chunk is bounded and proven in the same way as the one in `GetChunkResponse`, but also carries
+/// the outputs of the transactions, so that the receiver can apply them instead of executing the
+/// transactions.
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GetOutputChunkResponse {
+    /// The proofs are built relative to the LedgerInfo in `response_li`.
+    /// The specifics of ledger info verification depend on its type.
+    pub response_li: ResponseLedgerInfo,
+    /// Chunk of transactions and outputs with proof corresponding to the ledger info carried by
+    /// the response.
+    pub txn_output_list_with_proof: TransactionAndOutputListWithProof,
+}
+
+impl GetOutputChunkResponse {
+    pub fn new(
+        response_li: ResponseLedgerInfo,
+        txn_output_list_with_proof: TransactionAndOutputListWithProof,
+    ) -> Self {
+        Self {
+            response_li,
+            txn_output_list_with_proof,
+        }
+    }
+
+    /// Splits the response into the `GetChunkResponse` carrying the same transactions (along with
+    /// the events of their outputs) and the transaction outputs.
+    pub fn into_chunk_response_and_outputs(self) -> (GetChunkResponse, Vec<TransactionOutput>) {
+        let TransactionAndOutputListWithProof {
+            transactions_and_outputs,
+            first_transaction_version,
+            proof,
+        } = self.txn_output_list_with_proof;
+        let (transactions, outputs): (Vec<_>, Vec<_>) =
+            transactions_and_outputs.into_iter().unzip();
+        let events = outputs
+            .iter()
+            .map(|output| output.events().to_vec())
+            .collect();
+        let txn_list_with_proof = TransactionListWithProof::new(
+            transactions,
+            Some(events),
+            first_transaction_version,
+            proof,
+        );
+        (
+            GetChunkResponse::new(self.response_li, txn_list_with_proof),
+            outputs,
+        )
+    }
+}
+
+impl fmt::Debug for GetOutputChunkResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[OutputChunkResponse: response li version: {}, first version: {:?}, outputs: {}]",
+            self.response_li.version(),
+            self.txn_output_list_with_proof.first_transaction_version,
+            self.txn_output_list_with_proof
+                .transactions_and_outputs
+                .len(),
+        )
+    }
+}
+
 impl fmt::Debug for GetChunkResponse {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self)
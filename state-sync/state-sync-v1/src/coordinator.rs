@@ -3,7 +3,7 @@
 
 use crate::{
     chunk_request::{GetChunkRequest, TargetType},
-    chunk_response::{GetChunkResponse, ResponseLedgerInfo},
+    chunk_response::{GetChunkResponse, GetOutputChunkResponse, ResponseLedgerInfo},
     client::CoordinatorMessage,
     counters,
     error::Error,
@@ -18,14 +18,17 @@ use consensus_notifications::{
     ConsensusSyncNotification,
 };
 use diem_config::{
-    config::{NodeConfig, RoleType, StateSyncConfig},
+    config::{ContinuousSyncingMode, NodeConfig, RoleType, StateSyncConfig},
     network_id::{NetworkId, PeerNetworkId},
 };
 use diem_logger::prelude::*;
 use diem_types::{
     contract_event::ContractEvent,
     ledger_info::LedgerInfoWithSignatures,
-    transaction::{default_protocol::TransactionListWithProof, Transaction, Version},
+    transaction::{
+        default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
+        Transaction, TransactionOutput, Version,
+    },
     waypoint::Waypoint,
     PeerId,
 };
@@ -54,6 +57,7 @@ struct PendingRequestInfo {
     request_epoch: u64,
     target_li: Option<LedgerInfoWithSignatures>,
     chunk_limit: u64,
+    include_outputs: bool,
 }
 
 /// A sync request for a specified target ledger info.
@@ -265,8 +269,10 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         msg: StateSyncMessage,
     ) -> Result<(), Error> {
         let peer = PeerNetworkId::new(network_id, peer_id);
+        let include_outputs = matches!(msg, StateSyncMessage::GetOutputChunkRequest(_));
         match msg {
-            StateSyncMessage::GetChunkRequest(request) => {
+            StateSyncMessage::GetChunkRequest(request)
+            | StateSyncMessage::GetOutputChunkRequest(request) => {
                 // Time request handling
                 let _timer = counters::PROCESS_MSG_LATENCY
                     .with_label_values(&[
@@ -277,7 +283,8 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                     .start_timer();
 
                 // Process chunk request
-                let process_result = self.process_chunk_request(peer, *request.clone());
+                let process_result =
+                    self.process_chunk_request(peer, *request.clone(), include_outputs);
                 if let Err(ref error) = process_result {
                     error!(
                         LogSchema::event_log(LogEntry::ProcessChunkRequest, LogEvent::Fail)
@@ -315,7 +322,22 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                     .start_timer();
 
                 // Process chunk response
-                self.process_chunk_response(&peer, *response).await
+                self.process_chunk_response(&peer, *response, None).await
+            }
+            StateSyncMessage::GetOutputChunkResponse(response) => {
+                // Time response handling
+                let _timer = counters::PROCESS_MSG_LATENCY
+                    .with_label_values(&[
+                        peer.network_id().as_str(),
+                        peer.peer_id().short_str().as_str(),
+                        counters::CHUNK_RESPONSE_MSG_LABEL,
+                    ])
+                    .start_timer();
+
+                // Process chunk response (the outputs are applied along with the transactions)
+                let (response, txn_outputs) = response.into_chunk_response_and_outputs();
+                self.process_chunk_response(&peer, response, Some(txn_outputs))
+                    .await
             }
         }
     }
@@ -647,10 +669,12 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     /// There are two types of ChunkRequests:
     /// 1) Validator chunk requests are for a specific target LI and don't ask for long polling.
     /// 2) FullNode chunk requests don't specify a target LI and can allow long polling.
+    /// If `include_outputs` is set, the chunk carries the transaction outputs.
     fn process_chunk_request(
         &mut self,
         peer: PeerNetworkId,
         request: GetChunkRequest,
+        include_outputs: bool,
     ) -> Result<(), Error> {
         debug!(
             LogSchema::event_log(LogEntry::ProcessChunkRequest, LogEvent::Received)
@@ -673,9 +697,13 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         }
 
         match request.target.clone() {
-            TargetType::TargetLedgerInfo(li) => {
-                self.process_request_for_target_and_highest(peer, request, Some(li), None)
-            }
+            TargetType::TargetLedgerInfo(li) => self.process_request_for_target_and_highest(
+                peer,
+                request,
+                Some(li),
+                None,
+                include_outputs,
+            ),
             TargetType::HighestAvailable {
                 target_li,
                 timeout_ms,
@@ -684,9 +712,10 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 request,
                 target_li,
                 Some(timeout_ms),
+                include_outputs,
             ),
             TargetType::Waypoint(waypoint_version) => {
-                self.process_request_for_waypoint(peer, request, waypoint_version)
+                self.process_request_for_waypoint(peer, request, waypoint_version, include_outputs)
             }
         }
     }
@@ -742,6 +771,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         request: GetChunkRequest,
         target_li: Option<LedgerInfoWithSignatures>,
         timeout_ms: Option<u64>,
+        include_outputs: bool,
     ) -> Result<(), Error> {
         let chunk_limit = std::cmp::min(request.limit, self.config.max_chunk_limit);
         let timeout = if let Some(timeout_ms) = timeout_ms {
@@ -762,6 +792,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                     request_epoch: request.current_epoch,
                     target_li,
                     chunk_limit,
+                    include_outputs,
                 };
                 self.subscriptions.insert(peer, request_info);
             }
@@ -779,6 +810,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 highest_li,
             },
             chunk_limit,
+            include_outputs,
         )
     }
 
@@ -809,6 +841,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         peer: PeerNetworkId,
         request: GetChunkRequest,
         waypoint_version: Version,
+        include_outputs: bool,
     ) -> Result<(), Error> {
         let mut limit = std::cmp::min(request.limit, self.config.max_chunk_limit);
         if self.local_state.committed_version() < waypoint_version {
@@ -857,12 +890,13 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 end_of_epoch_li,
             },
             limit,
+            include_outputs,
         )
     }
 
     /// Generate and send the ChunkResponse to the given peer.
-    /// The chunk response contains transactions from the local storage with the proofs relative to
-    /// the given target ledger info.
+    /// The chunk response contains transactions (and their outputs, if `include_outputs` is set)
+    /// from the local storage with the proofs relative to the given target ledger info.
     /// In case target is None, the ledger info is set to the local highest ledger info.
     fn deliver_chunk(
         &mut self,
@@ -870,15 +904,31 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         known_version: u64,
         response_li: ResponseLedgerInfo,
         limit: u64,
+        include_outputs: bool,
     ) -> Result<(), Error> {
-        let txns = self
-            .executor_proxy
-            .get_chunk(known_version, limit, response_li.version())?;
-        let chunk_response = GetChunkResponse::new(response_li, txns);
-        let log = LogSchema::event_log(LogEntry::ProcessChunkRequest, LogEvent::DeliverChunk)
-            .chunk_response(chunk_response.clone())
-            .peer(&peer);
-        let msg = StateSyncMessage::GetChunkResponse(Box::new(chunk_response));
+        let log =
+            LogSchema::event_log(LogEntry::ProcessChunkRequest, LogEvent::DeliverChunk).peer(&peer);
+        let (log, msg) = if include_outputs {
+            let txn_outputs = self.executor_proxy.get_output_chunk(
+                known_version,
+                limit,
+                response_li.version(),
+            )?;
+            let log = log
+                .version(known_version)
+                .count(txn_outputs.transactions_and_outputs.len());
+            let chunk_response = GetOutputChunkResponse::new(response_li, txn_outputs);
+            let msg = StateSyncMessage::GetOutputChunkResponse(Box::new(chunk_response));
+            (log, msg)
+        } else {
+            let txns =
+                self.executor_proxy
+                    .get_chunk(known_version, limit, response_li.version())?;
+            let chunk_response = GetChunkResponse::new(response_li, txns);
+            let log = log.chunk_response(chunk_response.clone());
+            let msg = StateSyncMessage::GetChunkResponse(Box::new(chunk_response));
+            (log, msg)
+        };
         let send_result = self.request_manager.send_chunk_response(&peer, msg);
         let send_result_label = if send_result.is_err() {
             counters::SEND_FAIL_LABEL
@@ -929,11 +979,13 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         Ok(target_li)
     }
 
-    /// Applies (i.e., executes and stores) the chunk to storage iff `response` is valid.
+    /// Applies (i.e., executes and stores) the chunk to storage iff `response` is valid. If
+    /// `txn_outputs` are given, they are applied instead of executing the transactions.
     fn apply_chunk(
         &mut self,
         peer: &PeerNetworkId,
         response: GetChunkResponse,
+        txn_outputs: Option<Vec<TransactionOutput>>,
     ) -> Result<(), Error> {
         debug!(
             LogSchema::event_log(LogEntry::ProcessChunkResponse, LogEvent::Received)
@@ -951,9 +1003,13 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         let chunk_size = response.txn_list_with_proof.transactions.len() as u64;
         let known_version = self.local_state.synced_version();
         match response.response_li {
-            ResponseLedgerInfo::VerifiableLedgerInfo(li) => {
-                self.process_response_with_target_and_highest(txn_list_with_proof, li, None)
-            }
+            ResponseLedgerInfo::VerifiableLedgerInfo(li) => self
+                .process_response_with_target_and_highest(
+                    txn_list_with_proof,
+                    txn_outputs,
+                    li,
+                    None,
+                ),
             ResponseLedgerInfo::ProgressiveLedgerInfo {
                 target_li,
                 highest_li,
@@ -961,6 +1017,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 let highest_li = highest_li.unwrap_or_else(|| target_li.clone());
                 self.process_response_with_target_and_highest(
                     txn_list_with_proof,
+                    txn_outputs,
                     target_li,
                     Some(highest_li),
                 )
@@ -970,6 +1027,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 end_of_epoch_li,
             } => self.process_response_with_waypoint_li(
                 txn_list_with_proof,
+                txn_outputs,
                 waypoint_li,
                 end_of_epoch_li,
             ),
@@ -1018,12 +1076,14 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         Ok(())
     }
 
-    /// * Verifies, processes and stores the chunk in the given response.
+    /// * Verifies, processes and stores the chunk in the given response (applying `txn_outputs`
+    ///   instead of executing the transactions, if given).
     /// * Triggers post-commit actions based on new local state (after successfully processing a chunk).
     async fn process_chunk_response(
         &mut self,
         peer: &PeerNetworkId,
         response: GetChunkResponse,
+        txn_outputs: Option<Vec<TransactionOutput>>,
     ) -> Result<(), Error> {
         // Ensure consensus isn't running, otherwise we might get a race with storage writes.
         if self.is_consensus_executing() {
@@ -1035,11 +1095,11 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         }
 
         // Verify the chunk response is well formed before trying to process it.
-        self.verify_chunk_response_is_valid(peer, &response)?;
+        self.verify_chunk_response_is_valid(peer, &response, txn_outputs.is_some())?;
 
         // Validate the response and store the chunk if possible.
         // Any errors thrown here should be for detecting bad chunks.
-        match self.apply_chunk(peer, response.clone()) {
+        match self.apply_chunk(peer, response.clone(), txn_outputs) {
             Ok(()) => {
                 counters::APPLY_CHUNK_COUNT
                     .with_label_values(&[
@@ -1089,6 +1149,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         &mut self,
         peer: &PeerNetworkId,
         response: &GetChunkResponse,
+        carries_outputs: bool,
     ) -> Result<(), Error> {
         // Verify response comes from known peer
         if !self.request_manager.is_known_state_sync_peer(peer) {
@@ -1122,6 +1183,15 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             return Err(Error::ReceivedEmptyChunk(peer.to_string()));
         }
 
+        // Verify the chunk only carries outputs if this node applies them
+        if carries_outputs
+            && self.config.continuous_syncing_mode != ContinuousSyncingMode::ApplyTransactionOutputs
+        {
+            return Err(Error::ReceivedWrongChunkType(
+                "Received a chunk of transaction outputs, but we execute transactions!".into(),
+            ));
+        }
+
         // Verify the chunk has the expected type for the current syncing mode
         match &response.response_li {
             ResponseLedgerInfo::LedgerInfoForWaypoint {
@@ -1306,6 +1376,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     fn process_response_with_target_and_highest(
         &mut self,
         txn_list_with_proof: TransactionListWithProof,
+        txn_outputs: Option<Vec<TransactionOutput>>,
         response_li: LedgerInfoWithSignatures,
         new_highest_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error> {
@@ -1360,7 +1431,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
 
         // Validate and store the chunk
         self.log_highest_seen_version(new_highest_li.clone());
-        self.validate_and_store_chunk(txn_list_with_proof, response_li, None)?;
+        self.validate_and_store_chunk(txn_list_with_proof, txn_outputs, response_li, None)?;
 
         // Need to sync with local storage to update synced version
         self.sync_state_with_local_storage()?;
@@ -1390,6 +1461,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     fn process_response_with_waypoint_li(
         &mut self,
         txn_list_with_proof: TransactionListWithProof,
+        txn_outputs: Option<Vec<TransactionOutput>>,
         waypoint_li: LedgerInfoWithSignatures,
         end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error> {
@@ -1442,7 +1514,12 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 Error::UnexpectedError(format!("Waypoint verification failed: {}", error))
             })?;
 
-        self.validate_and_store_chunk(txn_list_with_proof, waypoint_li, end_of_epoch_li_to_commit)?;
+        self.validate_and_store_chunk(
+            txn_list_with_proof,
+            txn_outputs,
+            waypoint_li,
+            end_of_epoch_li_to_commit,
+        )?;
         self.log_highest_seen_version(None);
 
         Ok(())
@@ -1452,6 +1529,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     fn validate_and_store_chunk(
         &mut self,
         txn_list_with_proof: TransactionListWithProof,
+        txn_outputs: Option<Vec<TransactionOutput>>,
         target: LedgerInfoWithSignatures,
         intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error> {
@@ -1470,8 +1548,31 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             return Ok(());
         }
 
-        self.executor_proxy
-            .execute_chunk(txn_list_with_proof, target, intermediate_end_of_epoch_li)
+        match txn_outputs {
+            Some(txn_outputs) => {
+                let TransactionListWithProof {
+                    transactions,
+                    first_transaction_version,
+                    proof,
+                    ..
+                } = txn_list_with_proof;
+                let txn_output_list_with_proof = TransactionAndOutputListWithProof::new(
+                    itertools::zip_eq(transactions, txn_outputs).collect(),
+                    first_transaction_version,
+                    proof,
+                );
+                self.executor_proxy.apply_chunk(
+                    txn_output_list_with_proof,
+                    target,
+                    intermediate_end_of_epoch_li,
+                )
+            }
+            None => self.executor_proxy.execute_chunk(
+                txn_list_with_proof,
+                target,
+                intermediate_end_of_epoch_li,
+            ),
+        }
     }
 
     /// Returns true if consensus is currently executing and state sync should
@@ -1597,8 +1698,14 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             .unwrap_or_else(|| known_version.wrapping_add(1));
         counters::set_version(counters::VersionType::Target, target_version);
 
+        // Once initialized, nodes that apply transaction outputs request them instead of
+        // transactions (the waypoint is always synced by executing transactions).
+        let request_outputs = self.is_initialized()
+            && self.config.continuous_syncing_mode
+                == ContinuousSyncingMode::ApplyTransactionOutputs;
         let req = GetChunkRequest::new(known_version, known_epoch, self.config.chunk_limit, target);
-        self.request_manager.send_chunk_request(req)
+        self.request_manager
+            .send_chunk_request(req, request_outputs)
     }
 
     fn deliver_subscription(
@@ -1621,6 +1728,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 highest_li,
             },
             request_info.chunk_limit,
+            request_info.include_outputs,
         )
     }
 
@@ -1716,7 +1824,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
 mod tests {
     use crate::{
         chunk_request::{GetChunkRequest, TargetType},
-        chunk_response::{GetChunkResponse, ResponseLedgerInfo},
+        chunk_response::{GetChunkResponse, GetOutputChunkResponse, ResponseLedgerInfo},
         coordinator::StateSyncCoordinator,
         error::Error,
         executor_proxy::ExecutorProxy,
@@ -1743,10 +1851,13 @@ mod tests {
         ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
         proof::TransactionInfoListWithProof,
         transaction::{
-            default_protocol::TransactionListWithProof, RawTransaction, Script, SignedTransaction,
-            Transaction, TransactionPayload, Version,
+            default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
+            RawTransaction, Script, SignedTransaction, Transaction, TransactionOutput,
+            TransactionPayload, TransactionStatus, Version,
         },
+        vm_status::KeptVMStatus,
         waypoint::Waypoint,
+        write_set::WriteSet,
         PeerId,
     };
     use futures::{channel::oneshot, executor::block_on};
@@ -2163,6 +2274,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_process_output_chunk_response_execution_mode() {
+        // Create a coordinator for a full node (that executes transactions)
+        let mut full_node_coordinator = test_utils::create_full_node_coordinator();
+
+        // Create a peer for the node and add the peer as a known peer
+        let peer_network_id = PeerNetworkId::random_validator();
+        process_new_peer_event(&mut full_node_coordinator, &peer_network_id);
+
+        // Verify wrong chunk type for a chunk of transaction outputs
+        let output = TransactionOutput::new(
+            WriteSet::default(),
+            vec![],
+            0,
+            TransactionStatus::Keep(KeptVMStatus::Executed),
+        );
+        let txn_output_list_with_proof = TransactionAndOutputListWithProof::new(
+            vec![(create_test_transaction(), output)],
+            Some(1),
+            TransactionInfoListWithProof::new_empty(),
+        );
+        let response_ledger_info = ResponseLedgerInfo::ProgressiveLedgerInfo {
+            target_li: create_ledger_info_at_version(100),
+            highest_li: None,
+        };
+        let output_response =
+            GetOutputChunkResponse::new(response_ledger_info, txn_output_list_with_proof);
+        verify_all_chunk_responses_are_the_wrong_type(
+            &mut full_node_coordinator,
+            &peer_network_id,
+            &[StateSyncMessage::GetOutputChunkResponse(Box::new(
+                output_response,
+            ))],
+        );
+    }
+
     #[test]
     fn test_process_chunk_response_target() {
         // Create a coordinator for a validator
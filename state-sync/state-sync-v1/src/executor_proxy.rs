@@ -9,9 +9,11 @@ use crate::{
 };
 use diem_logger::prelude::*;
 use diem_types::{
-    contract_event::ContractEvent, ledger_info::LedgerInfoWithSignatures,
-    move_resource::MoveStorage, protocol_spec::DpnProto,
-    transaction::default_protocol::TransactionListWithProof,
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    move_resource::MoveStorage,
+    protocol_spec::DpnProto,
+    transaction::default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
 };
 use event_notifications::{EventNotificationSender, EventSubscriptionService};
 use executor_types::{ChunkExecutor, ExecutedTrees};
@@ -39,6 +41,22 @@ pub trait ExecutorProxyTrait: Send {
         target_version: u64,
     ) -> Result<TransactionListWithProof, Error>;
 
+    /// Apply and commit a batch of transaction outputs
+    fn apply_chunk(
+        &mut self,
+        txn_output_list_with_proof: TransactionAndOutputListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error>;
+
+    /// Gets chunk of transaction outputs given the known version, target version and the max limit.
+    fn get_output_chunk(
+        &self,
+        known_version: u64,
+        limit: u64,
+        target_version: u64,
+    ) -> Result<TransactionAndOutputListWithProof, Error>;
+
     /// Get the epoch changing ledger info for the given epoch so that we can move to next epoch.
     fn get_epoch_change_ledger_info(&self, epoch: u64) -> Result<LedgerInfoWithSignatures, Error>;
 
@@ -88,6 +106,22 @@ impl ExecutorProxy {
     }
 }
 
+impl ExecutorProxy {
+    /// Publishes the events of a chunk committed by `operation`, logging any failures (the chunk
+    /// is committed regardless).
+    fn publish_chunk_events(&mut self, events: Vec<ContractEvent>, operation: &str) {
+        if let Err(e) = self.publish_event_notifications(events) {
+            error!(
+                LogSchema::event_log(LogEntry::Reconfig, LogEvent::Fail).error(&e),
+                "Failed to publish reconfig updates in {}", operation
+            );
+            counters::RECONFIG_PUBLISH_COUNT
+                .with_label_values(&[counters::FAIL_LABEL])
+                .inc();
+        }
+    }
+}
+
 impl ExecutorProxyTrait for ExecutorProxy {
     fn get_local_storage_state(&self) -> Result<SyncState, Error> {
         let storage_info = self.storage.get_startup_info().map_err(|error| {
@@ -132,15 +166,7 @@ impl ExecutorProxyTrait for ExecutorProxy {
                 Error::UnexpectedError(format!("Execute and commit chunk failed: {}", error))
             })?;
         timer.stop_and_record();
-        if let Err(e) = self.publish_event_notifications(events) {
-            error!(
-                LogSchema::event_log(LogEntry::Reconfig, LogEvent::Fail).error(&e),
-                "Failed to publish reconfig updates in execute_chunk"
-            );
-            counters::RECONFIG_PUBLISH_COUNT
-                .with_label_values(&[counters::FAIL_LABEL])
-                .inc();
-        }
+        self.publish_chunk_events(events, "execute_chunk");
         Ok(())
     }
 
@@ -160,6 +186,48 @@ impl ExecutorProxyTrait for ExecutorProxy {
             })
     }
 
+    fn apply_chunk(
+        &mut self,
+        txn_output_list_with_proof: TransactionAndOutputListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        // track chunk application time
+        let timer = counters::APPLY_CHUNK_DURATION.start_timer();
+        let events = self
+            .executor
+            .apply_and_commit_chunk(
+                txn_output_list_with_proof,
+                verified_target_li,
+                intermediate_end_of_epoch_li,
+            )
+            .map_err(|error| {
+                Error::UnexpectedError(format!("Apply and commit chunk failed: {}", error))
+            })?;
+        timer.stop_and_record();
+        self.publish_chunk_events(events, "apply_chunk");
+        Ok(())
+    }
+
+    fn get_output_chunk(
+        &self,
+        known_version: u64,
+        limit: u64,
+        target_version: u64,
+    ) -> Result<TransactionAndOutputListWithProof, Error> {
+        let starting_version = known_version
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("Starting version has overflown!".into()))?;
+        self.storage
+            .get_transaction_outputs(starting_version, limit, target_version)
+            .map_err(|error| {
+                Error::UnexpectedError(format!(
+                    "Failed to get transaction outputs from storage {}",
+                    error
+                ))
+            })
+    }
+
     fn get_epoch_change_ledger_info(&self, epoch: u64) -> Result<LedgerInfoWithSignatures, Error> {
         let next_epoch = epoch
             .checked_add(1)
@@ -373,9 +441,7 @@ mod tests {
         let (genesis, _validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
 
         // Create test diem database
-        let db_path = diem_temppath::TempPath::new();
-        assert_ok!(db_path.create_as_dir());
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
 
         // Boostrap the genesis transaction
         let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
@@ -566,9 +632,7 @@ mod tests {
     #[test]
     fn test_missing_on_chain_config() {
         // Create a test diem database
-        let db_path = diem_temppath::TempPath::new();
-        db_path.create_as_dir().unwrap();
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
 
         // Bootstrap the database with regular genesis
         let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
@@ -638,9 +702,7 @@ mod tests {
         let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
 
         // Create test diem database
-        let db_path = diem_temppath::TempPath::new();
-        assert_ok!(db_path.create_as_dir());
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
 
         // Boostrap the genesis transaction
         let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
@@ -55,6 +55,9 @@ pub fn arb_state_sync_msg() -> impl Strategy<Value = StateSyncMessage> {
         (any::<GetChunkRequest>()).prop_map(|chunk_request| {
             StateSyncMessage::GetChunkRequest(Box::new(chunk_request))
         }),
+        (any::<GetChunkRequest>()).prop_map(|chunk_request| {
+            StateSyncMessage::GetOutputChunkRequest(Box::new(chunk_request))
+        }),
         (any::<GetChunkResponse>()).prop_map(|chunk_response| {
             StateSyncMessage::GetChunkResponse(Box::new(chunk_response))
         })
@@ -4,7 +4,10 @@
 //! Interface between State Sync and Network layers.
 
 use crate::{
-    chunk_request::GetChunkRequest, chunk_response::GetChunkResponse, counters, error::Error,
+    chunk_request::GetChunkRequest,
+    chunk_response::{GetChunkResponse, GetOutputChunkResponse},
+    counters,
+    error::Error,
 };
 use channel::message_queues::QueueStyle;
 use diem_metrics::IntCounterVec;
@@ -23,6 +26,10 @@ const STATE_SYNC_MAX_BUFFER_SIZE: usize = 1;
 pub enum StateSyncMessage {
     GetChunkRequest(Box<GetChunkRequest>),
     GetChunkResponse(Box<GetChunkResponse>),
+    /// Requests a chunk of transaction outputs instead of transactions. Sent by nodes that apply
+    /// the outputs instead of executing the transactions (see `ContinuousSyncingMode`).
+    GetOutputChunkRequest(Box<GetChunkRequest>),
+    GetOutputChunkResponse(Box<GetOutputChunkResponse>),
 }
 
 /// The interface from Network to StateSync layer.
@@ -256,7 +256,13 @@ impl RequestManager {
         chosen_peers
     }
 
-    pub fn send_chunk_request(&mut self, req: GetChunkRequest) -> Result<(), Error> {
+    /// Sends the chunk request to the chosen peers, asking for transaction outputs (instead of
+    /// transactions) if `request_outputs` is set.
+    pub fn send_chunk_request(
+        &mut self,
+        req: GetChunkRequest,
+        request_outputs: bool,
+    ) -> Result<(), Error> {
         let log = LogSchema::new(LogEntry::SendChunkRequest).chunk_request(req.clone());
 
         let peers = self.pick_peers();
@@ -273,7 +279,11 @@ impl RequestManager {
             .event(LogEvent::ChunkRequestInfo)
             .chunk_req_info(&req_info));
 
-        let msg = StateSyncMessage::GetChunkRequest(Box::new(req));
+        let msg = if request_outputs {
+            StateSyncMessage::GetOutputChunkRequest(Box::new(req))
+        } else {
+            StateSyncMessage::GetChunkRequest(Box::new(req))
+        };
         let mut failed_peer_sends = vec![];
 
         for peer in peers {
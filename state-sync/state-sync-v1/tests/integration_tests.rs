@@ -831,8 +831,10 @@ fn check_chunk_request(message: Message, known_version: u64, target_version: Opt
             assert_eq!(chunk_request.known_version, known_version);
             assert_eq!(chunk_request.target.version(), target_version);
         }
-        StateSyncMessage::GetChunkResponse(_) => {
-            panic!("Received chunk response but expecting chunk request!");
+        StateSyncMessage::GetChunkResponse(_)
+        | StateSyncMessage::GetOutputChunkRequest(_)
+        | StateSyncMessage::GetOutputChunkResponse(_) => {
+            panic!("Received unexpected message but expecting chunk request!");
         }
     }
 }
@@ -845,8 +847,10 @@ fn check_chunk_response(
 ) {
     let chunk_response: StateSyncMessage = bcs::from_bytes(&message.mdata).unwrap();
     match chunk_response {
-        StateSyncMessage::GetChunkRequest(_) => {
-            panic!("Received chunk response but expecting chunk request!");
+        StateSyncMessage::GetChunkRequest(_)
+        | StateSyncMessage::GetOutputChunkRequest(_)
+        | StateSyncMessage::GetOutputChunkResponse(_) => {
+            panic!("Received unexpected message but expecting chunk response!");
         }
         StateSyncMessage::GetChunkResponse(chunk_response) => {
             assert_eq!(chunk_response.response_li.version(), response_li_version);
@@ -31,14 +31,17 @@ use diem_types::{
     proof::TransactionInfoListWithProof,
     test_helpers::transaction_test_helpers::get_test_signed_txn,
     transaction::{
-        authenticator::AuthenticationKey, default_protocol::TransactionListWithProof,
-        SignedTransaction, Transaction, TransactionPayload,
+        authenticator::AuthenticationKey,
+        default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
+        SignedTransaction, Transaction, TransactionOutput, TransactionPayload, TransactionStatus,
     },
     validator_config::ValidatorConfig,
     validator_info::ValidatorInfo,
     validator_signer::ValidatorSigner,
     validator_verifier::random_validator_verifier,
+    vm_status::KeptVMStatus,
     waypoint::Waypoint,
+    write_set::WriteSet,
     PeerId,
 };
 use executor_types::ExecutedTrees;
@@ -867,6 +870,53 @@ impl ExecutorProxyTrait for MockExecutorProxy {
         (self.handler)(txns_with_proof)
     }
 
+    fn apply_chunk(
+        &mut self,
+        txn_output_list_with_proof: TransactionAndOutputListWithProof,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        let txns = txn_output_list_with_proof
+            .transactions_and_outputs
+            .into_iter()
+            .map(|(txn, _)| txn)
+            .collect();
+        self.storage.write().add_txns_with_li(
+            txns,
+            ledger_info_with_sigs,
+            intermediate_end_of_epoch_li,
+        );
+        Ok(())
+    }
+
+    fn get_output_chunk(
+        &self,
+        known_version: u64,
+        limit: u64,
+        target_version: u64,
+    ) -> Result<TransactionAndOutputListWithProof, Error> {
+        // The mock storage doesn't keep outputs, so every transaction gets an empty one
+        let txns_with_proof = self.get_chunk(known_version, limit, target_version)?;
+        let txns_and_outputs = txns_with_proof
+            .transactions
+            .into_iter()
+            .map(|txn| {
+                let output = TransactionOutput::new(
+                    WriteSet::default(),
+                    vec![],
+                    0,
+                    TransactionStatus::Keep(KeptVMStatus::Executed),
+                );
+                (txn, output)
+            })
+            .collect();
+        Ok(TransactionAndOutputListWithProof::new(
+            txns_and_outputs,
+            txns_with_proof.first_transaction_version,
+            txns_with_proof.proof,
+        ))
+    }
+
     fn get_epoch_change_ledger_info(&self, epoch: u64) -> Result<LedgerInfoWithSignatures, Error> {
         self.storage.read().get_epoch_changes(epoch)
     }
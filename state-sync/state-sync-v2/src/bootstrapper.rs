@@ -0,0 +1,309 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{error::Error, streaming_service::DataStreamingService};
+use diem_config::config::{BootstrappingMode, StateSyncConfig};
+use diem_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
+use diem_infallible::Mutex;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{default_protocol::TransactionListWithProof, Version},
+};
+use storage_interface::{default_protocol::DbReaderWriter, StateSnapshotReceiver};
+use storage_service_types::{
+    AccountStatesChunkWithProofRequest, StorageServerSummary, StorageServiceRequest,
+    StorageServiceResponse, TransactionsWithProofRequest,
+};
+
+/// The bootstrapper brings a node that starts without any state (other than
+/// genesis) close to the latest state of its peers, based on the configured
+/// bootstrapping mode. Once bootstrapped, the node keeps up by syncing (and
+/// executing) chunks of transactions as usual.
+pub struct Bootstrapper {
+    mode: BootstrappingMode,
+    streaming_service: DataStreamingService,
+    storage: DbReaderWriter,
+}
+
+impl Bootstrapper {
+    pub fn new(
+        config: &StateSyncConfig,
+        streaming_service: DataStreamingService,
+        storage: DbReaderWriter,
+    ) -> Self {
+        Self {
+            mode: config.bootstrapping_mode,
+            streaming_service,
+            storage,
+        }
+    }
+
+    /// Returns the streaming service, e.g., to keep syncing with it once the
+    /// node is bootstrapped.
+    pub fn into_streaming_service(self) -> DataStreamingService {
+        self.streaming_service
+    }
+
+    /// Bootstraps the node and returns the ledger info it has synced to, or
+    /// `None` if the node is left to sync the transactions from its latest
+    /// version (e.g., because it already holds more than genesis).
+    pub async fn bootstrap(&mut self) -> Result<Option<LedgerInfoWithSignatures>, Error> {
+        match self.mode {
+            BootstrappingMode::ExecuteTransactionsFromGenesis => Ok(None),
+            BootstrappingMode::DownloadLatestAccountStates => {
+                let startup_info = self
+                    .storage
+                    .reader
+                    .get_startup_info()
+                    .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
+                match startup_info {
+                    Some(startup_info)
+                        if startup_info.latest_ledger_info.ledger_info().version() > 0 =>
+                    {
+                        Ok(None)
+                    }
+                    _ => self.download_latest_account_states().await.map(Some),
+                }
+            }
+        }
+    }
+
+    /// Downloads the account states at the latest version advertised by the
+    /// peers and makes the db start from them, without the transactions
+    /// before that version.
+    async fn download_latest_account_states(&mut self) -> Result<LedgerInfoWithSignatures, Error> {
+        // Choose the highest ledger info whose account states can be fetched
+        let data_client = self.streaming_service.data_client().clone();
+        data_client.refresh_peer_summaries().await;
+        let summaries = data_client.peer_summaries();
+        let target = summaries
+            .iter()
+            .map(|summary| summary.data_summary.synced_ledger_info.clone())
+            .filter(|ledger_info| {
+                let version = ledger_info.ledger_info().version();
+                summaries
+                    .iter()
+                    .any(|summary| can_serve_snapshot(summary, version))
+            })
+            .max_by_key(|ledger_info| ledger_info.ledger_info().version())
+            .ok_or_else(|| {
+                Error::DataIsUnavailable("No connected peer advertises its account states".into())
+            })?;
+        self.streaming_service.verify_target(&target).await?;
+        let ledger_infos = self.ledger_infos_to_save(&target)?;
+
+        // Fetch the transaction at the target version, which proves the state root
+        let version = target.ledger_info().version();
+        let max_request_attempts = self.streaming_service.config().max_request_attempts;
+        let transaction_list = data_client
+            .send_request(
+                |summary| {
+                    if !can_serve_snapshot(summary, version) {
+                        return None;
+                    }
+                    Some(StorageServiceRequest::GetTransactionsWithProof(
+                        TransactionsWithProofRequest {
+                            proof_version: version,
+                            start_version: version,
+                            expected_num_transactions: 1,
+                            include_events: true,
+                        },
+                    ))
+                },
+                |response| validate_transaction(response, &target),
+                max_request_attempts,
+            )
+            .await?;
+        let state_root_hash = transaction_list.proof.transaction_infos[0].state_root_hash();
+
+        // Fetch the account states chunk by chunk. The receiver can't be used
+        // after a bad chunk, so it's recreated (keeping the restored accounts).
+        let receiver = Mutex::new(self.create_receiver(version, state_root_hash)?);
+        loop {
+            let start_account_key = match receiver.lock().previous_key() {
+                Some(previous_key) => plus_one(previous_key),
+                None => HashValue::zero(),
+            };
+            let is_last_chunk = data_client
+                .send_request(
+                    |summary| {
+                        if !summary.data_summary.account_states.contains(version) {
+                            return None;
+                        }
+                        Some(StorageServiceRequest::GetAccountStatesChunkWithProof(
+                            AccountStatesChunkWithProofRequest {
+                                version,
+                                start_account_key,
+                                expected_num_account_states: summary
+                                    .protocol_metadata
+                                    .max_account_states_chunk_size,
+                            },
+                        ))
+                    },
+                    |response| {
+                        let mut receiver = receiver.lock();
+                        let result = add_account_states_chunk(
+                            receiver.as_mut(),
+                            response,
+                            start_account_key,
+                        );
+                        if result.is_err() {
+                            *receiver = self.create_receiver(version, state_root_hash)?;
+                        }
+                        result
+                    },
+                    max_request_attempts,
+                )
+                .await?;
+            if is_last_chunk {
+                break;
+            }
+        }
+        receiver
+            .into_inner()
+            .finish_box()
+            .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
+
+        self.storage
+            .writer
+            .finalize_state_snapshot(version, transaction_list, &ledger_infos)
+            .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
+        Ok(target)
+    }
+
+    /// Returns the ledger infos the db needs to start at `target`: the
+    /// verified epoch ending ledger infos, followed by `target` itself.
+    fn ledger_infos_to_save(
+        &self,
+        target: &LedgerInfoWithSignatures,
+    ) -> Result<Vec<LedgerInfoWithSignatures>, Error> {
+        let mut ledger_infos: Vec<_> = self
+            .streaming_service
+            .verified_epoch_ending_ledger_infos()
+            .iter()
+            .filter(|ledger_info| {
+                ledger_info.ledger_info().version() < target.ledger_info().version()
+            })
+            .cloned()
+            .collect();
+
+        // The epoch state of the target comes from the previous epoch ending
+        // ledger info, unless the target ends an epoch itself.
+        let target_epoch = target.ledger_info().epoch();
+        if !target.ledger_info().ends_epoch()
+            && target_epoch > 0
+            && ledger_infos
+                .last()
+                .map(|ledger_info| ledger_info.ledger_info().epoch())
+                != Some(target_epoch - 1)
+        {
+            return Err(Error::UnexpectedErrorEncountered(format!(
+                "The ledger info ending epoch {} wasn't verified, so the epoch state of the \
+                 target is unknown",
+                target_epoch - 1
+            )));
+        }
+        ledger_infos.push(target.clone());
+        Ok(ledger_infos)
+    }
+
+    fn create_receiver(
+        &self,
+        version: Version,
+        state_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>, Error> {
+        self.storage
+            .writer
+            .get_state_snapshot_receiver(version, state_root_hash)
+            .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))
+    }
+}
+
+/// Returns true iff the peer with `summary` can serve both the account states
+/// and the transaction at `version`.
+fn can_serve_snapshot(summary: &StorageServerSummary, version: Version) -> bool {
+    let data_summary = &summary.data_summary;
+    data_summary.synced_ledger_info.ledger_info().version() >= version
+        && data_summary.account_states.contains(version)
+        && data_summary.transactions.contains(version)
+}
+
+/// Verifies that `response` holds the transaction (and events) at the version
+/// of `target`, proven by `target`.
+fn validate_transaction(
+    response: StorageServiceResponse,
+    target: &LedgerInfoWithSignatures,
+) -> Result<TransactionListWithProof, Error> {
+    let version = target.ledger_info().version();
+    match response {
+        StorageServiceResponse::TransactionsWithProof(transaction_list) => {
+            if transaction_list.transactions.len() != 1 || transaction_list.events.is_none() {
+                return Err(Error::InvalidResponse(format!(
+                    "Expected the transaction and events at version {}",
+                    version
+                )));
+            }
+            transaction_list
+                .verify(target.ledger_info(), Some(version))
+                .map_err(|error| Error::VerificationError(error.to_string()))?;
+            Ok(transaction_list)
+        }
+        _ => Err(Error::InvalidResponse(
+            "Expected a transaction list with proof".into(),
+        )),
+    }
+}
+
+/// Adds the account states chunk in `response` to `receiver` and returns true
+/// iff the chunk holds the last account.
+fn add_account_states_chunk(
+    receiver: &mut dyn StateSnapshotReceiver,
+    response: StorageServiceResponse,
+    start_account_key: HashValue,
+) -> Result<bool, Error> {
+    let chunk = match response {
+        StorageServiceResponse::AccountStatesChunkWithProof(chunk) => chunk,
+        _ => {
+            return Err(Error::InvalidResponse(
+                "Expected an account states chunk with proof".into(),
+            ))
+        }
+    };
+    let proof = match (chunk.account_blobs.first(), chunk.proof) {
+        (Some((first_key, _)), Some(proof))
+            if chunk.start_key == start_account_key && *first_key >= start_account_key =>
+        {
+            proof
+        }
+        _ => {
+            return Err(Error::InvalidResponse(format!(
+                "Expected account states starting at key {}",
+                start_account_key
+            )))
+        }
+    };
+
+    // The last account is the rightmost one iff it has no siblings on the right
+    let is_last_chunk = proof
+        .right_siblings()
+        .iter()
+        .all(|sibling| *sibling == *SPARSE_MERKLE_PLACEHOLDER_HASH);
+    receiver
+        .add_chunk(chunk.account_blobs, proof)
+        .map_err(|error| Error::VerificationError(error.to_string()))?;
+    Ok(is_last_chunk)
+}
+
+/// Returns the account key right after `key`.
+fn plus_one(key: HashValue) -> HashValue {
+    let mut bytes = key.to_vec();
+    for byte in bytes.iter_mut().rev() {
+        if *byte == u8::MAX {
+            *byte = 0;
+        } else {
+            *byte += 1;
+            break;
+        }
+    }
+    HashValue::from_slice(&bytes).expect("The key has the right length")
+}
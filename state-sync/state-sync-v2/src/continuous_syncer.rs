@@ -0,0 +1,127 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    data_stream::{DataPayload, DataStream},
+    error::Error,
+    streaming_service::DataStreamingService,
+};
+use diem_config::config::{ContinuousSyncingMode, StateSyncConfig};
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::Version};
+use executor_types::ChunkExecutor;
+use futures::StreamExt;
+use std::sync::Arc;
+use storage_interface::default_protocol::DbReaderWriter;
+
+/// The continuous syncer keeps a (bootstrapped) node up to date by syncing to
+/// new target ledger infos chunk by chunk. Based on the configured syncing
+/// mode, it either executes the transactions of each chunk, or applies their
+/// outputs (which is cheaper, as the transactions aren't executed).
+pub struct ContinuousSyncer {
+    mode: ContinuousSyncingMode,
+    streaming_service: DataStreamingService,
+    storage: DbReaderWriter,
+    chunk_executor: Arc<dyn ChunkExecutor>,
+}
+
+impl ContinuousSyncer {
+    pub fn new(
+        config: &StateSyncConfig,
+        streaming_service: DataStreamingService,
+        storage: DbReaderWriter,
+        chunk_executor: Arc<dyn ChunkExecutor>,
+    ) -> Self {
+        Self {
+            mode: config.continuous_syncing_mode,
+            streaming_service,
+            storage,
+            chunk_executor,
+        }
+    }
+
+    /// Syncs the node to `target`, which is verified first.
+    pub async fn sync_to_target(&mut self, target: LedgerInfoWithSignatures) -> Result<(), Error> {
+        self.streaming_service.verify_target(&target).await?;
+
+        // Chunks can't span multiple epochs, so we sync to the end of each
+        // epoch before the target first.
+        let mut next_version = self.next_version_to_sync()?;
+        let epoch_ending_ledger_infos: Vec<_> = self
+            .streaming_service
+            .verified_epoch_ending_ledger_infos()
+            .iter()
+            .filter(|ledger_info| {
+                let version = ledger_info.ledger_info().version();
+                version >= next_version && version < target.ledger_info().version()
+            })
+            .cloned()
+            .collect();
+        for target in epoch_ending_ledger_infos
+            .into_iter()
+            .chain(std::iter::once(target))
+        {
+            if next_version > target.ledger_info().version() {
+                continue;
+            }
+            let mut data_stream = self.create_data_stream(next_version, target).await?;
+            while let Some(payload) = data_stream.next().await {
+                let payload = payload?;
+                next_version += payload.num_items();
+                self.execute_or_apply_chunk(payload, data_stream.target())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the first version the node hasn't synced yet.
+    fn next_version_to_sync(&self) -> Result<Version, Error> {
+        let startup_info = self
+            .storage
+            .reader
+            .get_startup_info()
+            .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?
+            .ok_or_else(|| Error::UnexpectedErrorEncountered("The db isn't bootstrapped".into()))?;
+        let tree_state = startup_info
+            .synced_tree_state
+            .unwrap_or(startup_info.committed_tree_state);
+        Ok(tree_state.num_transactions)
+    }
+
+    async fn create_data_stream(
+        &mut self,
+        start_version: Version,
+        target: LedgerInfoWithSignatures,
+    ) -> Result<DataStream, Error> {
+        match self.mode {
+            ContinuousSyncingMode::ExecuteTransactions => {
+                self.streaming_service
+                    .stream_transactions(start_version, target, false)
+                    .await
+            }
+            ContinuousSyncingMode::ApplyTransactionOutputs => {
+                self.streaming_service
+                    .stream_transaction_outputs(start_version, target)
+                    .await
+            }
+        }
+    }
+
+    /// Executes (or applies) and commits a chunk proven by `target`. The
+    /// target is committed along with the chunk if the chunk ends at it.
+    fn execute_or_apply_chunk(
+        &self,
+        payload: DataPayload,
+        target: &LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        match payload {
+            DataPayload::TransactionsWithProof(transaction_list) => self
+                .chunk_executor
+                .execute_and_commit_chunk(transaction_list, target.clone(), None),
+            DataPayload::TransactionOutputsWithProof(output_list) => self
+                .chunk_executor
+                .apply_and_commit_chunk(output_list, target.clone(), None),
+        }
+        .map(|_| ())
+        .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))
+    }
+}
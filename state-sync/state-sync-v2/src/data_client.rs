@@ -0,0 +1,223 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{error::Error, storage_client::StorageServiceClient};
+use diem_infallible::RwLock;
+use diem_types::PeerId;
+use futures::future::join_all;
+use rand::seq::SliceRandom;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use storage_service_types::{StorageServerSummary, StorageServiceRequest, StorageServiceResponse};
+
+/// Scores are used to prefer reliable peers when choosing where to send a
+/// request. Successful responses slowly increase a peer's score, while failed
+/// or invalid responses quickly decrease it.
+const STARTING_SCORE: f64 = 50.0;
+const MAX_SCORE: f64 = 100.0;
+const MIN_SCORE: f64 = 1.0;
+const SUCCESSFUL_RESPONSE_DELTA: f64 = 1.0;
+const ERROR_MULTIPLIER: f64 = 0.95;
+const MALICIOUS_MULTIPLIER: f64 = 0.8;
+/// Peers scoring below this are only chosen if no other peer can serve a request.
+const IGNORE_PEER_THRESHOLD: f64 = 25.0;
+
+/// The state we keep for each peer that we can send requests to.
+#[derive(Clone, Debug)]
+struct PeerState {
+    score: f64,
+    /// The latest storage server summary advertised by the peer (if any).
+    summary: Option<StorageServerSummary>,
+}
+
+impl PeerState {
+    fn new() -> Self {
+        Self {
+            score: STARTING_SCORE,
+            summary: None,
+        }
+    }
+
+    fn update_score_success(&mut self) {
+        self.score = f64::min(self.score + SUCCESSFUL_RESPONSE_DELTA, MAX_SCORE);
+    }
+
+    fn update_score_error(&mut self, error: &Error) {
+        let multiplier = match error {
+            Error::InvalidResponse(_) | Error::VerificationError(_) => MALICIOUS_MULTIPLIER,
+            _ => ERROR_MULTIPLIER,
+        };
+        self.score = f64::max(self.score * multiplier, MIN_SCORE);
+    }
+}
+
+/// The data client sends storage service requests to peers. It keeps track of
+/// what data each peer advertises and how reliable each peer has been, and uses
+/// both to choose where to send (and retry) each request.
+pub struct DataClient {
+    storage_client: Arc<dyn StorageServiceClient>,
+    peer_states: RwLock<HashMap<PeerId, PeerState>>,
+    response_timeout: Duration,
+}
+
+impl DataClient {
+    pub fn new(storage_client: Arc<dyn StorageServiceClient>, response_timeout: Duration) -> Self {
+        Self {
+            storage_client,
+            peer_states: RwLock::new(HashMap::new()),
+            response_timeout,
+        }
+    }
+
+    /// Returns the current score of `peer`, if the peer is known.
+    pub fn peer_score(&self, peer: &PeerId) -> Option<f64> {
+        self.peer_states.read().get(peer).map(|state| state.score)
+    }
+
+    /// Returns the latest storage server summaries advertised by the peers.
+    pub fn peer_summaries(&self) -> Vec<StorageServerSummary> {
+        self.peer_states
+            .read()
+            .values()
+            .filter_map(|state| state.summary.clone())
+            .collect()
+    }
+
+    /// Fetches the storage server summary of every connected peer. Peers that
+    /// are no longer connected are forgotten.
+    pub async fn refresh_peer_summaries(&self) {
+        let peers = self.storage_client.connected_peers();
+        self.peer_states
+            .write()
+            .retain(|peer, _| peers.contains(peer));
+
+        let responses = join_all(peers.iter().map(|peer| {
+            self.send_request_to_peer(*peer, StorageServiceRequest::GetStorageServerSummary)
+        }))
+        .await;
+
+        let mut peer_states = self.peer_states.write();
+        for (peer, response) in peers.into_iter().zip(responses) {
+            let peer_state = peer_states.entry(peer).or_insert_with(PeerState::new);
+            match response {
+                Ok(StorageServiceResponse::StorageServerSummary(summary)) => {
+                    peer_state.summary = Some(summary);
+                }
+                Ok(_) => peer_state.update_score_error(&Error::InvalidResponse(
+                    "Expected a storage server summary".into(),
+                )),
+                Err(error) => peer_state.update_score_error(&error),
+            }
+        }
+    }
+
+    /// Sends a request to a peer and validates the response, retrying on
+    /// failure up to `max_attempts` times in total.
+    ///
+    /// `create_request` is called with the summary of each peer, and returns
+    /// the request to send to that peer, or `None` if the peer can't serve
+    /// it. `validate_response` checks the response and turns it into the
+    /// result. The score of the chosen peer is updated based on the outcome.
+    pub async fn send_request<T, CreateRequest, ValidateResponse>(
+        &self,
+        create_request: CreateRequest,
+        validate_response: ValidateResponse,
+        max_attempts: u64,
+    ) -> Result<T, Error>
+    where
+        CreateRequest: Fn(&StorageServerSummary) -> Option<StorageServiceRequest>,
+        ValidateResponse: Fn(StorageServiceResponse) -> Result<T, Error>,
+    {
+        let mut last_error = Error::DataIsUnavailable("No request was attempted".into());
+        for _ in 0..max_attempts {
+            let (peer, request) = match self.choose_peer(&create_request) {
+                Some(choice) => choice,
+                None => {
+                    // Our view of the peers might be stale, so refresh it once
+                    // before giving up.
+                    self.refresh_peer_summaries().await;
+                    self.choose_peer(&create_request).ok_or_else(|| {
+                        Error::DataIsUnavailable(
+                            "No connected peer advertises the requested data".into(),
+                        )
+                    })?
+                }
+            };
+
+            let result = self
+                .send_request_to_peer(peer, request)
+                .await
+                .and_then(&validate_response);
+            let mut peer_states = self.peer_states.write();
+            let peer_state = peer_states.entry(peer).or_insert_with(PeerState::new);
+            match result {
+                Ok(value) => {
+                    peer_state.update_score_success();
+                    return Ok(value);
+                }
+                Err(error) => {
+                    peer_state.update_score_error(&error);
+                    last_error = error;
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Chooses a peer that can serve the request built by `create_request`,
+    /// at random weighted by peer score. Peers with a score below the ignore
+    /// threshold are only chosen if no other peer can serve the request.
+    fn choose_peer<CreateRequest>(
+        &self,
+        create_request: &CreateRequest,
+    ) -> Option<(PeerId, StorageServiceRequest)>
+    where
+        CreateRequest: Fn(&StorageServerSummary) -> Option<StorageServiceRequest>,
+    {
+        let candidates: Vec<_> = self
+            .peer_states
+            .read()
+            .iter()
+            .filter_map(|(peer, state)| {
+                let request = create_request(state.summary.as_ref()?)?;
+                Some((*peer, state.score, request))
+            })
+            .collect();
+        let (preferred, ignored): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|(_, score, _)| *score >= IGNORE_PEER_THRESHOLD);
+        let candidates = if preferred.is_empty() {
+            ignored
+        } else {
+            preferred
+        };
+
+        candidates
+            .choose_weighted(&mut rand::thread_rng(), |(_, score, _)| *score)
+            .ok()
+            .map(|(peer, _, request)| (*peer, request.clone()))
+    }
+
+    async fn send_request_to_peer(
+        &self,
+        peer: PeerId,
+        request: StorageServiceRequest,
+    ) -> Result<StorageServiceResponse, Error> {
+        let response = tokio::time::timeout(
+            self.response_timeout,
+            self.storage_client.send_request(peer, request),
+        )
+        .await
+        .map_err(|_| {
+            Error::TimeoutWaitingForResponse(format!(
+                "No response from peer {} after {:?}",
+                peer, self.response_timeout
+            ))
+        })??;
+        match response {
+            StorageServiceResponse::StorageServiceError(error) => Err(Error::RequestFailed(
+                format!("Peer {} returned an error: {:?}", peer, error),
+            )),
+            response => Ok(response),
+        }
+    }
+}
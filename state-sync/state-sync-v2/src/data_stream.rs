@@ -0,0 +1,273 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{data_client::DataClient, error::Error, streaming_service::DataStreamingServiceConfig};
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{
+        default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
+        Version,
+    },
+};
+use futures::{
+    future::BoxFuture,
+    stream::{FuturesOrdered, Stream, StreamExt},
+    task::{Context, Poll},
+    FutureExt,
+};
+use std::{collections::VecDeque, pin::Pin, sync::Arc};
+use storage_service_types::{
+    StorageServerSummary, StorageServiceRequest, StorageServiceResponse,
+    TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
+};
+
+/// A chunk of verified data returned by a data stream.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataPayload {
+    TransactionsWithProof(TransactionListWithProof),
+    TransactionOutputsWithProof(TransactionAndOutputListWithProof),
+}
+
+impl DataPayload {
+    /// Returns the number of items (e.g., transactions) held by the payload.
+    pub fn num_items(&self) -> u64 {
+        match self {
+            DataPayload::TransactionsWithProof(transactions) => {
+                transactions.transactions.len() as u64
+            }
+            DataPayload::TransactionOutputsWithProof(outputs) => {
+                outputs.transactions_and_outputs.len() as u64
+            }
+        }
+    }
+}
+
+/// The type of data fetched by a data stream.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum StreamKind {
+    Transactions { include_events: bool },
+    TransactionOutputs,
+}
+
+impl StreamKind {
+    /// Returns the request fetching versions `start_version` to `end_version`
+    /// (inclusive) with a proof relative to `target`, capped to the chunk size
+    /// of the peer, or `None` if the peer can't serve it.
+    fn create_request(
+        &self,
+        summary: &StorageServerSummary,
+        start_version: Version,
+        end_version: Version,
+        target: &LedgerInfoWithSignatures,
+    ) -> Option<StorageServiceRequest> {
+        let proof_version = target.ledger_info().version();
+        let data_summary = &summary.data_summary;
+        if data_summary.synced_ledger_info.ledger_info().version() < proof_version {
+            return None;
+        }
+
+        let num_versions = end_version - start_version + 1;
+        match self {
+            StreamKind::Transactions { include_events } => {
+                if !data_summary.transactions.contains(start_version) {
+                    return None;
+                }
+                let max_chunk_size = summary.protocol_metadata.max_transaction_chunk_size;
+                Some(StorageServiceRequest::GetTransactionsWithProof(
+                    TransactionsWithProofRequest {
+                        proof_version,
+                        start_version,
+                        expected_num_transactions: num_versions.min(max_chunk_size),
+                        include_events: *include_events,
+                    },
+                ))
+            }
+            StreamKind::TransactionOutputs => {
+                if !data_summary.transaction_outputs.contains(start_version) {
+                    return None;
+                }
+                let max_chunk_size = summary.protocol_metadata.max_transaction_output_chunk_size;
+                Some(StorageServiceRequest::GetTransactionOutputsWithProof(
+                    TransactionOutputsWithProofRequest {
+                        proof_version,
+                        start_version,
+                        expected_num_outputs: num_versions.min(max_chunk_size),
+                    },
+                ))
+            }
+        }
+    }
+
+    /// Verifies that `response` holds a non-empty list of at most the versions
+    /// `start_version` to `end_version` (inclusive), proven by `target`.
+    fn validate_response(
+        &self,
+        response: StorageServiceResponse,
+        start_version: Version,
+        end_version: Version,
+        target: &LedgerInfoWithSignatures,
+    ) -> Result<DataPayload, Error> {
+        let payload = match (self, response) {
+            (
+                StreamKind::Transactions { include_events },
+                StorageServiceResponse::TransactionsWithProof(transactions),
+            ) => {
+                if *include_events && transactions.events.is_none() {
+                    return Err(Error::InvalidResponse(
+                        "Requested transaction events are missing".into(),
+                    ));
+                }
+                transactions
+                    .verify(target.ledger_info(), Some(start_version))
+                    .map_err(|error| Error::VerificationError(error.to_string()))?;
+                DataPayload::TransactionsWithProof(transactions)
+            }
+            (
+                StreamKind::TransactionOutputs,
+                StorageServiceResponse::TransactionOutputsWithProof(outputs),
+            ) => {
+                outputs
+                    .verify(target.ledger_info(), Some(start_version))
+                    .map_err(|error| Error::VerificationError(error.to_string()))?;
+                DataPayload::TransactionOutputsWithProof(outputs)
+            }
+            (kind, _) => {
+                return Err(Error::InvalidResponse(format!(
+                    "Received a response of the wrong type for a {:?} request",
+                    kind
+                )))
+            }
+        };
+
+        let num_items = payload.num_items();
+        if num_items == 0 || num_items > end_version - start_version + 1 {
+            return Err(Error::InvalidResponse(format!(
+                "Expected between 1 and {} items, received {}",
+                end_version - start_version + 1,
+                num_items
+            )));
+        }
+        Ok(payload)
+    }
+}
+
+/// A stream of verified data chunks covering all versions from a start version
+/// to the version of a verified target ledger info.
+///
+/// The versions are split into ranges that are fetched concurrently (from
+/// different peers), but the chunks are always returned in version order. The
+/// stream ends after returning all versions or after returning an error.
+pub struct DataStream {
+    data_client: Arc<DataClient>,
+    config: DataStreamingServiceConfig,
+    kind: StreamKind,
+    target: Arc<LedgerInfoWithSignatures>,
+    /// The first version not yet covered by a request.
+    next_request_version: Version,
+    pending_requests: FuturesOrdered<BoxFuture<'static, Result<Vec<DataPayload>, Error>>>,
+    ready_payloads: VecDeque<DataPayload>,
+    failed: bool,
+}
+
+impl DataStream {
+    pub(crate) fn new(
+        data_client: Arc<DataClient>,
+        config: DataStreamingServiceConfig,
+        kind: StreamKind,
+        start_version: Version,
+        target: LedgerInfoWithSignatures,
+    ) -> Self {
+        Self {
+            data_client,
+            config,
+            kind,
+            target: Arc::new(target),
+            next_request_version: start_version,
+            pending_requests: FuturesOrdered::new(),
+            ready_payloads: VecDeque::new(),
+            failed: false,
+        }
+    }
+
+    /// Returns the ledger info that all data in the stream is proven against.
+    pub fn target(&self) -> &LedgerInfoWithSignatures {
+        &self.target
+    }
+
+    /// Starts fetching new ranges until the concurrency limit is reached or
+    /// all versions are covered.
+    fn send_pending_requests(&mut self) {
+        let target_version = self.target.ledger_info().version();
+        while self.pending_requests.len() < self.config.max_concurrent_requests as usize
+            && self.next_request_version <= target_version
+        {
+            let start_version = self.next_request_version;
+            let end_version = target_version.min(start_version + self.config.max_chunk_size - 1);
+            self.next_request_version = end_version + 1;
+            self.pending_requests.push(
+                fetch_range(
+                    self.data_client.clone(),
+                    self.kind,
+                    start_version,
+                    end_version,
+                    self.target.clone(),
+                    self.config.max_request_attempts,
+                )
+                .boxed(),
+            );
+        }
+    }
+}
+
+/// Fetches all versions from `start_version` to `end_version` (inclusive).
+/// Responses may hold fewer versions than requested, so this keeps requesting
+/// the rest of the range until it is complete.
+async fn fetch_range(
+    data_client: Arc<DataClient>,
+    kind: StreamKind,
+    start_version: Version,
+    end_version: Version,
+    target: Arc<LedgerInfoWithSignatures>,
+    max_request_attempts: u64,
+) -> Result<Vec<DataPayload>, Error> {
+    let mut payloads = vec![];
+    let mut next_version = start_version;
+    while next_version <= end_version {
+        let payload = data_client
+            .send_request(
+                |summary| kind.create_request(summary, next_version, end_version, &target),
+                |response| kind.validate_response(response, next_version, end_version, &target),
+                max_request_attempts,
+            )
+            .await?;
+        next_version += payload.num_items();
+        payloads.push(payload);
+    }
+    Ok(payloads)
+}
+
+impl Stream for DataStream {
+    type Item = Result<DataPayload, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(payload) = this.ready_payloads.pop_front() {
+                return Poll::Ready(Some(Ok(payload)));
+            }
+            if this.failed {
+                return Poll::Ready(None);
+            }
+
+            this.send_pending_requests();
+            match futures::ready!(this.pending_requests.poll_next_unpin(cx)) {
+                Some(Ok(payloads)) => this.ready_payloads.extend(payloads),
+                Some(Err(error)) => {
+                    this.failed = true;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("The requested data is unavailable: {0}")]
+    DataIsUnavailable(String),
+    #[error("Invalid response received: {0}")]
+    InvalidResponse(String),
+    #[error("The storage service request failed: {0}")]
+    RequestFailed(String),
+    #[error("Timed out waiting for a response: {0}")]
+    TimeoutWaitingForResponse(String),
+    #[error("Unexpected error encountered: {0}")]
+    UnexpectedErrorEncountered(String),
+    #[error("Verification error: {0}")]
+    VerificationError(String),
+}
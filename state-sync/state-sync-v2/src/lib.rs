@@ -1,7 +1,29 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-//! This is an empty skeleton library for state sync v2.
+//! State sync v2 -- the next iteration of state sync.
 //!
-//! TODO(joshlind): update this description when we start implementing state sync v2.
+//! The data streaming service turns requests like "sync all transactions from
+//! version X to the target ledger info Y" into streams of verified data chunks.
+//! Chunks are fetched from the storage services of connected peers: requests
+//! are spread across peers based on the data they advertise and a per-peer
+//! score, and failed or unverifiable responses are retried with other peers.
+//! All data is verified against the node's `TrustedState` before it is returned.
+//!
+//! The bootstrapper uses the streaming service to bring a new node up to date,
+//! e.g., by downloading the latest account states instead of executing all
+//! transactions since genesis. Once bootstrapped, the continuous syncer keeps
+//! the node up to date by either executing the synced transactions, or applying
+//! their outputs, based on the configured continuous syncing mode.
 #![forbid(unsafe_code)]
+
+pub mod bootstrapper;
+pub mod continuous_syncer;
+pub mod data_client;
+pub mod data_stream;
+pub mod error;
+pub mod storage_client;
+pub mod streaming_service;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,170 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::Error;
+use async_trait::async_trait;
+use diem_config::network_id::{NetworkId, PeerNetworkId};
+use diem_infallible::RwLock;
+use diem_types::PeerId;
+use network::{
+    application::{
+        storage::PeerMetadataStorage,
+        types::{PeerInfo, PeerState},
+    },
+    ProtocolId,
+};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use storage_service_server::{
+    network::StorageServiceNetworkSender, StorageReaderInterface, StorageServiceServer,
+};
+use storage_service_types::{StorageServiceRequest, StorageServiceResponse};
+
+/// The interface used by state sync to send requests to the storage services
+/// of connected peers.
+#[async_trait]
+pub trait StorageServiceClient: Send + Sync {
+    /// Returns the peers that storage service requests can currently be sent to.
+    fn connected_peers(&self) -> Vec<PeerId>;
+
+    /// Sends the given request to the storage service of `peer` and returns the
+    /// (unverified) response.
+    async fn send_request(
+        &self,
+        peer: PeerId,
+        request: StorageServiceRequest,
+    ) -> Result<StorageServiceResponse, Error>;
+}
+
+/// A storage service client that hands requests directly to storage service
+/// servers running in the same process. This is useful for testing state sync
+/// without a network.
+pub struct InProcessStorageServiceClient<T> {
+    servers: RwLock<HashMap<PeerId, Arc<StorageServiceServer<T>>>>,
+}
+
+impl<T: StorageReaderInterface> InProcessStorageServiceClient<T> {
+    pub fn new() -> Self {
+        Self {
+            servers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Makes `server` reachable as `peer`, replacing any server already
+    /// registered for that peer.
+    pub fn add_server(&self, peer: PeerId, server: StorageServiceServer<T>) {
+        self.servers.write().insert(peer, Arc::new(server));
+    }
+
+    /// Makes `peer` unreachable.
+    pub fn remove_server(&self, peer: &PeerId) {
+        self.servers.write().remove(peer);
+    }
+}
+
+impl<T: StorageReaderInterface> Default for InProcessStorageServiceClient<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<T: StorageReaderInterface + Send + Sync> StorageServiceClient
+    for InProcessStorageServiceClient<T>
+{
+    fn connected_peers(&self) -> Vec<PeerId> {
+        self.servers.read().keys().copied().collect()
+    }
+
+    async fn send_request(
+        &self,
+        peer: PeerId,
+        request: StorageServiceRequest,
+    ) -> Result<StorageServiceResponse, Error> {
+        let server = self
+            .servers
+            .read()
+            .get(&peer)
+            .cloned()
+            .ok_or_else(|| Error::RequestFailed(format!("Peer {} is not connected", peer)))?;
+        server
+            .handle_request(request)
+            .map_err(|error| Error::RequestFailed(error.to_string()))
+    }
+}
+
+/// A storage service client that sends requests to the storage services of
+/// peers over the network. A peer can be reached while it's connected on any
+/// of the networks and supports the storage service protocol.
+pub struct NetworkStorageServiceClient {
+    network_senders: HashMap<NetworkId, StorageServiceNetworkSender>,
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+    request_timeout: Duration,
+}
+
+impl NetworkStorageServiceClient {
+    pub fn new(
+        network_senders: HashMap<NetworkId, StorageServiceNetworkSender>,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
+        request_timeout: Duration,
+    ) -> Self {
+        Self {
+            network_senders,
+            peer_metadata_storage,
+            request_timeout,
+        }
+    }
+
+    /// Returns the network to reach `peer` on, if the peer is connected.
+    fn find_network(&self, peer: PeerId) -> Option<NetworkId> {
+        self.network_senders.keys().copied().find(|network_id| {
+            self.peer_metadata_storage
+                .read(PeerNetworkId::new(*network_id, peer))
+                .map_or(false, |peer_info| supports_storage_service(&peer_info))
+        })
+    }
+}
+
+#[async_trait]
+impl StorageServiceClient for NetworkStorageServiceClient {
+    fn connected_peers(&self) -> Vec<PeerId> {
+        let mut peers: Vec<_> = self
+            .network_senders
+            .keys()
+            .flat_map(|network_id| {
+                self.peer_metadata_storage
+                    .read_filtered(*network_id, |(_, peer_info)| {
+                        supports_storage_service(peer_info)
+                    })
+                    .into_iter()
+                    .map(|(peer_network_id, _)| peer_network_id.peer_id())
+            })
+            .collect();
+        peers.sort();
+        peers.dedup();
+        peers
+    }
+
+    async fn send_request(
+        &self,
+        peer: PeerId,
+        request: StorageServiceRequest,
+    ) -> Result<StorageServiceResponse, Error> {
+        let network_id = self
+            .find_network(peer)
+            .ok_or_else(|| Error::RequestFailed(format!("Peer {} is not connected", peer)))?;
+        let mut network_sender = self.network_senders[&network_id].clone();
+        network_sender
+            .send_request(peer, request, self.request_timeout)
+            .await
+            .map_err(|error| Error::RequestFailed(error.to_string()))
+    }
+}
+
+/// Returns true iff the peer is connected and supports the storage service.
+fn supports_storage_service(peer_info: &PeerInfo) -> bool {
+    peer_info.status == PeerState::Connected
+        && peer_info
+            .active_connection
+            .application_protocols
+            .contains(ProtocolId::StorageServiceRpc)
+}
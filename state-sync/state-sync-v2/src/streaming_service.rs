@@ -0,0 +1,264 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    data_client::DataClient,
+    data_stream::{DataStream, StreamKind},
+    error::Error,
+    storage_client::StorageServiceClient,
+};
+use diem_types::{
+    epoch_change::Verifier, epoch_state::EpochState, ledger_info::LedgerInfoWithSignatures,
+    transaction::Version, trusted_state::TrustedState,
+};
+use std::{sync::Arc, time::Duration};
+use storage_service_types::{
+    EpochEndingLedgerInfoRequest, StorageServiceRequest, StorageServiceResponse,
+};
+
+/// The configuration of the data streaming service.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DataStreamingServiceConfig {
+    /// The max number of versions a data stream requests at once. Peers may
+    /// cap this further based on their own chunk sizes.
+    pub max_chunk_size: u64,
+    /// The max number of requests each data stream has in flight at once.
+    pub max_concurrent_requests: u64,
+    /// The max number of times a request is sent (to possibly different peers)
+    /// before the data stream fails.
+    pub max_request_attempts: u64,
+    /// The time to wait for a single response from a peer.
+    pub response_timeout_ms: u64,
+}
+
+impl Default for DataStreamingServiceConfig {
+    fn default() -> Self {
+        Self {
+            max_chunk_size: 1000,
+            max_concurrent_requests: 3,
+            max_request_attempts: 3,
+            response_timeout_ms: 10_000,
+        }
+    }
+}
+
+/// The data streaming service turns requests like "sync all transactions from
+/// version X to the target ledger info Y" into streams of verified chunks,
+/// fetched from the storage services of connected peers.
+///
+/// Before a data stream is created, the target ledger info is verified against
+/// the trusted state, which fetches and verifies the epoch ending ledger infos
+/// of any epochs in between. Every chunk in the stream is then verified against
+/// the target ledger info.
+pub struct DataStreamingService {
+    config: DataStreamingServiceConfig,
+    data_client: Arc<DataClient>,
+    trusted_state: TrustedState,
+    /// The latest epoch state verified from epoch ending ledger infos (if any).
+    latest_epoch_state: Option<EpochState>,
+    /// The epoch ending ledger infos verified so far, in epoch order. Ledger
+    /// infos older than the trusted state are skipped.
+    verified_epoch_ending_ledger_infos: Vec<LedgerInfoWithSignatures>,
+    /// The first epoch whose epoch ending ledger info hasn't been fetched.
+    next_epoch_to_fetch: u64,
+}
+
+impl DataStreamingService {
+    pub fn new(
+        config: DataStreamingServiceConfig,
+        storage_client: Arc<dyn StorageServiceClient>,
+        trusted_state: TrustedState,
+    ) -> Self {
+        let data_client = Arc::new(DataClient::new(
+            storage_client,
+            Duration::from_millis(config.response_timeout_ms),
+        ));
+        // We don't know the epoch of a waypoint, so we fetch the epoch ending
+        // ledger infos from genesis and skip the stale ones.
+        let next_epoch_to_fetch = match &trusted_state {
+            TrustedState::EpochWaypoint(_) => 0,
+            TrustedState::EpochState { epoch_state, .. } => epoch_state.epoch,
+        };
+        Self {
+            config,
+            data_client,
+            trusted_state,
+            latest_epoch_state: None,
+            verified_epoch_ending_ledger_infos: vec![],
+            next_epoch_to_fetch,
+        }
+    }
+
+    pub fn config(&self) -> &DataStreamingServiceConfig {
+        &self.config
+    }
+
+    pub fn data_client(&self) -> &Arc<DataClient> {
+        &self.data_client
+    }
+
+    /// Returns the latest verified epoch state, if the trusted state has been
+    /// moved past its initial epoch.
+    pub fn latest_epoch_state(&self) -> Option<&EpochState> {
+        self.latest_epoch_state.as_ref()
+    }
+
+    /// Returns the epoch ending ledger infos verified so far, in epoch order.
+    pub fn verified_epoch_ending_ledger_infos(&self) -> &[LedgerInfoWithSignatures] {
+        &self.verified_epoch_ending_ledger_infos
+    }
+
+    /// Verifies `target` and returns a stream of all transactions from
+    /// `start_version` to the version of `target` (inclusive).
+    pub async fn stream_transactions(
+        &mut self,
+        start_version: Version,
+        target: LedgerInfoWithSignatures,
+        include_events: bool,
+    ) -> Result<DataStream, Error> {
+        self.create_data_stream(
+            StreamKind::Transactions { include_events },
+            start_version,
+            target,
+        )
+        .await
+    }
+
+    /// Verifies `target` and returns a stream of all transaction outputs from
+    /// `start_version` to the version of `target` (inclusive).
+    pub async fn stream_transaction_outputs(
+        &mut self,
+        start_version: Version,
+        target: LedgerInfoWithSignatures,
+    ) -> Result<DataStream, Error> {
+        self.create_data_stream(StreamKind::TransactionOutputs, start_version, target)
+            .await
+    }
+
+    async fn create_data_stream(
+        &mut self,
+        kind: StreamKind,
+        start_version: Version,
+        target: LedgerInfoWithSignatures,
+    ) -> Result<DataStream, Error> {
+        let target_version = target.ledger_info().version();
+        if start_version > target_version {
+            return Err(Error::UnexpectedErrorEncountered(format!(
+                "The start version ({}) is higher than the target version ({})",
+                start_version, target_version
+            )));
+        }
+
+        // Refresh our view of the peers, so new peers and data are considered
+        self.data_client.refresh_peer_summaries().await;
+        self.verify_target(&target).await?;
+
+        Ok(DataStream::new(
+            self.data_client.clone(),
+            self.config,
+            kind,
+            start_version,
+            target,
+        ))
+    }
+
+    /// Verifies `target` against the trusted state, fetching and verifying the
+    /// epoch ending ledger infos up to the epoch of `target` first.
+    pub async fn verify_target(&mut self, target: &LedgerInfoWithSignatures) -> Result<(), Error> {
+        // Epoch ending ledger infos that were verified already need no more work
+        if self.verified_epoch_ending_ledger_infos.contains(target) {
+            return Ok(());
+        }
+
+        let target_epoch = target.ledger_info().epoch();
+        while self
+            .verifier()
+            .epoch_change_verification_required(target_epoch)
+            && self.next_epoch_to_fetch < target_epoch
+        {
+            let start_epoch = self.next_epoch_to_fetch;
+            let (last_epoch, ledger_infos) = self
+                .data_client
+                .send_request(
+                    |summary| {
+                        let epoch_range = &summary.data_summary.epoch_ending_ledger_infos;
+                        if !epoch_range.contains(start_epoch) {
+                            return None;
+                        }
+                        // The end epoch is exclusive
+                        let end_epoch = target_epoch
+                            .min(epoch_range.highest + 1)
+                            .min(start_epoch + summary.protocol_metadata.max_epoch_chunk_size);
+                        Some(StorageServiceRequest::GetEpochEndingLedgerInfos(
+                            EpochEndingLedgerInfoRequest {
+                                start_epoch,
+                                expected_end_epoch: end_epoch,
+                            },
+                        ))
+                    },
+                    |response| self.verify_epoch_ending_ledger_infos(response, start_epoch),
+                    self.config.max_request_attempts,
+                )
+                .await?;
+            self.next_epoch_to_fetch = last_epoch + 1;
+            if let Some(ledger_info) = ledger_infos.last() {
+                self.latest_epoch_state = ledger_info.ledger_info().next_epoch_state().cloned();
+            }
+            self.verified_epoch_ending_ledger_infos.extend(ledger_infos);
+        }
+
+        self.verifier()
+            .verify(target)
+            .map_err(|error| Error::VerificationError(error.to_string()))
+    }
+
+    /// Verifies a chunk of epoch ending ledger infos starting at `start_epoch`
+    /// and returns the last epoch in the chunk, along with the ledger infos in
+    /// the chunk that aren't older than the trusted state.
+    fn verify_epoch_ending_ledger_infos(
+        &self,
+        response: StorageServiceResponse,
+        start_epoch: u64,
+    ) -> Result<(u64, Vec<LedgerInfoWithSignatures>), Error> {
+        let epoch_change_proof = match response {
+            StorageServiceResponse::EpochEndingLedgerInfos(epoch_change_proof) => {
+                epoch_change_proof
+            }
+            _ => {
+                return Err(Error::InvalidResponse(
+                    "Expected a list of epoch ending ledger infos".into(),
+                ))
+            }
+        };
+        let ledger_infos = &epoch_change_proof.ledger_info_with_sigs;
+        match (ledger_infos.first(), ledger_infos.last()) {
+            (Some(first), Some(last)) if first.ledger_info().epoch() == start_epoch => {
+                let last_epoch = last.ledger_info().epoch();
+                if self.verifier().is_ledger_info_stale(last.ledger_info()) {
+                    return Ok((last_epoch, vec![]));
+                }
+                epoch_change_proof
+                    .verify(self.verifier())
+                    .map_err(|error| Error::VerificationError(error.to_string()))?;
+                let verifier = self.verifier();
+                let ledger_infos = epoch_change_proof
+                    .ledger_info_with_sigs
+                    .into_iter()
+                    .filter(|ledger_info| !verifier.is_ledger_info_stale(ledger_info.ledger_info()))
+                    .collect();
+                Ok((last_epoch, ledger_infos))
+            }
+            _ => Err(Error::InvalidResponse(format!(
+                "Expected epoch ending ledger infos starting at epoch {}",
+                start_epoch
+            ))),
+        }
+    }
+
+    fn verifier(&self) -> &dyn Verifier {
+        match &self.latest_epoch_state {
+            Some(epoch_state) => epoch_state,
+            None => &self.trusted_state,
+        }
+    }
+}
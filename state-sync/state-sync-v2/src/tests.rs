@@ -0,0 +1,644 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bootstrapper::Bootstrapper,
+    continuous_syncer::ContinuousSyncer,
+    data_stream::DataPayload,
+    error::Error,
+    storage_client::InProcessStorageServiceClient,
+    streaming_service::{DataStreamingService, DataStreamingServiceConfig},
+};
+use claim::{assert_err, assert_matches, assert_none, assert_ok};
+use diem_config::config::{
+    BootstrappingMode, ContinuousSyncingMode, StateSyncConfig, StorageServiceConfig,
+};
+use diem_crypto::HashValue;
+use diem_infallible::{Mutex, RwLock};
+use diem_proptest_helpers::ValueGenerator;
+use diem_types::{
+    account_state_blob::{AccountStateBlob, AccountStatesChunkWithProof},
+    contract_event::ContractEvent,
+    epoch_change::EpochChangeProof,
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{
+        default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
+        Transaction, TransactionOutput, TransactionStatus, TransactionToCommit, Version,
+    },
+    trusted_state::TrustedState,
+    waypoint::Waypoint,
+    write_set::WriteSet,
+    PeerId,
+};
+use diemdb::{test_helper::arb_blocks_to_commit_impl, DiemDB};
+use executor_types::ChunkExecutor;
+use futures::StreamExt;
+use std::{str::FromStr, sync::Arc};
+use storage_interface::{default_protocol::DbReaderWriter, DbReader, DbWriter};
+use storage_service_server::{
+    Error as StorageError, StorageReader, StorageReaderInterface, StorageServiceServer,
+};
+use storage_service_types::DataSummary;
+
+type Block = (Vec<TransactionToCommit>, LedgerInfoWithSignatures);
+
+#[tokio::test]
+async fn test_stream_transactions() {
+    // Create a storage service server for each peer, all on top of the same db
+    let (db, blocks) = create_db_with_blocks();
+    let storage_client = Arc::new(InProcessStorageServiceClient::new());
+    for _ in 0..3 {
+        storage_client.add_server(PeerId::random(), create_storage_server(&db));
+    }
+
+    // Stream all transactions up to the latest ledger info
+    let mut streaming_service = create_streaming_service(storage_client, &blocks);
+    let target = blocks.last().unwrap().1.clone();
+    let data_stream = streaming_service
+        .stream_transactions(0, target, true)
+        .await
+        .unwrap();
+    let payloads: Vec<_> = data_stream.collect().await;
+
+    // Verify the stream holds every transaction, in order
+    let mut transactions = vec![];
+    for payload in payloads {
+        match payload.unwrap() {
+            DataPayload::TransactionsWithProof(transaction_list) => {
+                assert_eq!(
+                    transaction_list.first_transaction_version,
+                    Some(transactions.len() as u64)
+                );
+                transactions.extend(transaction_list.transactions);
+            }
+            payload => panic!("Unexpected payload: {:?}", payload),
+        }
+    }
+    assert_eq!(transactions, get_transactions(&blocks));
+}
+
+#[tokio::test]
+async fn test_stream_transactions_from_middle() {
+    // Create a storage service server
+    let (db, blocks) = create_db_with_blocks();
+    let storage_client = Arc::new(InProcessStorageServiceClient::new());
+    storage_client.add_server(PeerId::random(), create_storage_server(&db));
+
+    // Stream the transactions after the first block (without events)
+    let mut streaming_service = create_streaming_service(storage_client, &blocks);
+    let start_version = blocks[0].0.len() as u64;
+    let target = blocks.last().unwrap().1.clone();
+    let data_stream = streaming_service
+        .stream_transactions(start_version, target, false)
+        .await
+        .unwrap();
+    let payloads: Vec<_> = data_stream.collect().await;
+
+    // Verify the stream holds every transaction from the start version
+    let mut transactions = vec![];
+    for payload in payloads {
+        match payload.unwrap() {
+            DataPayload::TransactionsWithProof(transaction_list) => {
+                assert_eq!(transaction_list.events, None);
+                transactions.extend(transaction_list.transactions);
+            }
+            payload => panic!("Unexpected payload: {:?}", payload),
+        }
+    }
+    assert_eq!(
+        transactions,
+        get_transactions(&blocks)[start_version as usize..].to_vec()
+    );
+}
+
+#[tokio::test]
+async fn test_stream_transactions_with_bad_peer() {
+    // Create a storage service server that corrupts all transaction lists
+    let (db, blocks) = create_db_with_blocks();
+    let bad_peer = PeerId::random();
+    let storage_client = Arc::new(InProcessStorageServiceClient::new());
+    storage_client.add_server(
+        bad_peer,
+        StorageServiceServer::new(
+            StorageServiceConfig::default(),
+            CorruptingStorageReader::new(&db, true, false),
+        ),
+    );
+
+    // Verify the stream fails once all attempts are used up, and then ends
+    let config = DataStreamingServiceConfig {
+        max_request_attempts: 10,
+        ..create_streaming_config()
+    };
+    let trusted_state = create_trusted_state(&blocks);
+    let mut streaming_service =
+        DataStreamingService::new(config, storage_client.clone(), trusted_state);
+    let target = blocks.last().unwrap().1.clone();
+    let data_stream = streaming_service
+        .stream_transactions(0, target.clone(), true)
+        .await
+        .unwrap();
+    let payloads: Vec<_> = data_stream.collect().await;
+    assert_eq!(payloads.len(), 1);
+    assert_matches!(payloads[0], Err(Error::VerificationError(_)));
+
+    // Add a good storage service server
+    let good_peer = PeerId::random();
+    storage_client.add_server(
+        good_peer,
+        StorageServiceServer::new(
+            StorageServiceConfig::default(),
+            CorruptingStorageReader::new(&db, false, false),
+        ),
+    );
+
+    // Verify all data is now received, and the bad peer is scored lower
+    let data_stream = streaming_service
+        .stream_transactions(0, target, true)
+        .await
+        .unwrap();
+    let payloads: Vec<_> = data_stream.collect().await;
+    let num_transactions: u64 = payloads
+        .into_iter()
+        .map(|payload| payload.unwrap().num_items())
+        .sum();
+    assert_eq!(num_transactions, get_transactions(&blocks).len() as u64);
+    let data_client = streaming_service.data_client();
+    assert!(data_client.peer_score(&bad_peer) < data_client.peer_score(&good_peer));
+}
+
+#[tokio::test]
+async fn test_unverifiable_target() {
+    // Create a storage service server
+    let (db, blocks) = create_db_with_blocks();
+    let storage_client = Arc::new(InProcessStorageServiceClient::new());
+    storage_client.add_server(PeerId::random(), create_storage_server(&db));
+
+    // Create a waypoint that doesn't match the first epoch ending ledger info
+    let waypoint_version = blocks[0].1.ledger_info().version();
+    let waypoint = Waypoint::from_str(&format!(
+        "{}:{}",
+        waypoint_version,
+        HashValue::random().to_hex()
+    ))
+    .unwrap();
+    let mut streaming_service = DataStreamingService::new(
+        create_streaming_config(),
+        storage_client,
+        TrustedState::from_epoch_waypoint(waypoint),
+    );
+
+    // Verify the target can't be verified, so no stream is created
+    let target = blocks.last().unwrap().1.clone();
+    let result = streaming_service.stream_transactions(0, target, true).await;
+    assert_matches!(result.err(), Some(Error::VerificationError(_)));
+}
+
+#[tokio::test]
+async fn test_no_peers() {
+    let (_, blocks) = create_db_with_blocks();
+    let storage_client = Arc::new(InProcessStorageServiceClient::<StorageReader>::new());
+    let mut streaming_service = create_streaming_service(storage_client, &blocks);
+
+    // Verify the target can't be verified without any peers
+    let target = blocks.last().unwrap().1.clone();
+    assert_err!(streaming_service.verify_target(&target).await);
+
+    // Verify the target of the trusted state itself needs no peers
+    assert_ok!(streaming_service.verify_target(&blocks[0].1).await);
+}
+
+#[tokio::test]
+async fn test_bootstrap_from_latest_account_states() {
+    // Create a storage service server holding all but the last block
+    let blocks = generate_blocks();
+    let (synced_blocks, new_blocks) = blocks.split_at(blocks.len() - 1);
+    let source_db = create_db(synced_blocks);
+    let storage_client = Arc::new(InProcessStorageServiceClient::new());
+    storage_client.add_server(
+        PeerId::random(),
+        StorageServiceServer::new(
+            StorageServiceConfig::default(),
+            CorruptingStorageReader::new(&source_db, false, false),
+        ),
+    );
+
+    // Bootstrap an empty db from the latest account states
+    let db = Arc::new(DiemDB::new_for_test());
+    let mut bootstrapper = create_bootstrapper(storage_client, &blocks, &db);
+    let target = bootstrapper.bootstrap().await.unwrap().unwrap();
+    assert_eq!(&target, &synced_blocks.last().unwrap().1);
+
+    // Verify the db starts at the target, without the transactions before it
+    let version = target.ledger_info().version();
+    assert_eq!(
+        db.get_startup_info().unwrap(),
+        source_db.get_startup_info().unwrap()
+    );
+    assert_eq!(
+        db.get_account_states_range(version, HashValue::zero(), 1000)
+            .unwrap(),
+        source_db
+            .get_account_states_range(version, HashValue::zero(), 1000)
+            .unwrap()
+    );
+    assert_ok!(db.get_transactions(version, 1, version, true));
+    assert_err!(db.get_transactions(0, 1, version, false));
+
+    // Verify the db keeps syncing from the target as usual
+    let full_db = create_db(&blocks);
+    let (txns_to_commit, ledger_info) = &new_blocks[0];
+    db.save_transactions(txns_to_commit, version + 1, Some(ledger_info))
+        .unwrap();
+    assert_eq!(
+        db.get_startup_info().unwrap(),
+        full_db.get_startup_info().unwrap()
+    );
+
+    // Verify a node that is already bootstrapped isn't bootstrapped again
+    assert_none!(bootstrapper.bootstrap().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_bootstrap_with_bad_peer() {
+    // Create a storage service server that corrupts account states
+    let (source_db, blocks) = create_db_with_blocks();
+    let bad_peer = PeerId::random();
+    let storage_client = Arc::new(InProcessStorageServiceClient::new());
+    storage_client.add_server(
+        bad_peer,
+        StorageServiceServer::new(
+            StorageServiceConfig::default(),
+            CorruptingStorageReader::new(&source_db, false, true),
+        ),
+    );
+
+    // Verify bootstrapping fails, leaving the db without a ledger info
+    let db = Arc::new(DiemDB::new_for_test());
+    let mut bootstrapper = create_bootstrapper(storage_client.clone(), &blocks, &db);
+    assert_matches!(
+        bootstrapper.bootstrap().await,
+        Err(Error::VerificationError(_))
+    );
+    assert_none!(db.get_startup_info().unwrap());
+
+    // Replace the bad storage service server with a good one
+    storage_client.remove_server(&bad_peer);
+    storage_client.add_server(
+        PeerId::random(),
+        StorageServiceServer::new(
+            StorageServiceConfig::default(),
+            CorruptingStorageReader::new(&source_db, false, false),
+        ),
+    );
+
+    // Verify bootstrapping now resumes and completes
+    let target = bootstrapper.bootstrap().await.unwrap().unwrap();
+    assert_eq!(&target, &blocks.last().unwrap().1);
+    assert_eq!(
+        db.get_startup_info().unwrap(),
+        source_db.get_startup_info().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_bootstrap_by_executing_transactions() {
+    // Create a storage service server
+    let (source_db, blocks) = create_db_with_blocks();
+    let storage_client = Arc::new(InProcessStorageServiceClient::new());
+    storage_client.add_server(PeerId::random(), create_storage_server(&source_db));
+
+    // Verify the bootstrapper leaves the transactions to be synced as usual
+    let db = Arc::new(DiemDB::new_for_test());
+    let config = StateSyncConfig {
+        bootstrapping_mode: BootstrappingMode::ExecuteTransactionsFromGenesis,
+        ..StateSyncConfig::default()
+    };
+    let mut bootstrapper = Bootstrapper::new(
+        &config,
+        create_streaming_service(storage_client, &blocks),
+        DbReaderWriter::from_arc(db.clone()),
+    );
+    assert_none!(bootstrapper.bootstrap().await.unwrap());
+    assert_none!(db.get_startup_info().unwrap());
+}
+
+#[tokio::test]
+async fn test_continuous_sync_by_executing_transactions() {
+    test_continuous_sync(ContinuousSyncingMode::ExecuteTransactions).await;
+}
+
+#[tokio::test]
+async fn test_continuous_sync_by_applying_transaction_outputs() {
+    test_continuous_sync(ContinuousSyncingMode::ApplyTransactionOutputs).await;
+}
+
+/// Syncs a node holding the first block to the latest ledger info and
+/// verifies every chunk is handed to the chunk executor as `mode` requires.
+async fn test_continuous_sync(mode: ContinuousSyncingMode) {
+    // Create a storage service server
+    let (source_db, blocks) = create_db_with_blocks();
+    let storage_client = Arc::new(InProcessStorageServiceClient::new());
+    storage_client.add_server(
+        PeerId::random(),
+        StorageServiceServer::new(
+            StorageServiceConfig::default(),
+            CorruptingStorageReader::new(&source_db, false, false),
+        ),
+    );
+
+    // Sync a db holding the first block to the latest ledger info
+    let db = create_db(&blocks[..1]);
+    let chunk_executor = Arc::new(MockChunkExecutor::default());
+    let config = StateSyncConfig {
+        continuous_syncing_mode: mode,
+        ..StateSyncConfig::default()
+    };
+    let mut continuous_syncer = ContinuousSyncer::new(
+        &config,
+        create_streaming_service(storage_client, &blocks),
+        DbReaderWriter::from_arc(db),
+        chunk_executor.clone(),
+    );
+    let target = blocks.last().unwrap().1.clone();
+    continuous_syncer
+        .sync_to_target(target.clone())
+        .await
+        .unwrap();
+
+    // Verify the chunks cover all new versions, in order, with the method of
+    // the syncing mode, and that no chunk crosses an epoch ending ledger info
+    let chunks = chunk_executor.chunks.lock();
+    let mut next_version = blocks[0].0.len() as u64;
+    for chunk in chunks.iter() {
+        assert_eq!(
+            chunk.applied,
+            mode == ContinuousSyncingMode::ApplyTransactionOutputs
+        );
+        assert_eq!(chunk.first_version, next_version);
+        next_version += chunk.num_transactions;
+        assert!(next_version - 1 <= chunk.target.ledger_info().version());
+    }
+    assert_eq!(next_version, target.ledger_info().version() + 1);
+    for (_, ledger_info) in blocks.iter().skip(1) {
+        if ledger_info.ledger_info().ends_epoch() {
+            assert!(chunks.iter().any(|chunk| {
+                &chunk.target == ledger_info
+                    && chunk.first_version + chunk.num_transactions - 1
+                        == ledger_info.ledger_info().version()
+            }));
+        }
+    }
+}
+
+/// Creates a db holding a few blocks of random transactions, spanning at
+/// least two epochs.
+fn create_db_with_blocks() -> (Arc<DiemDB>, Vec<Block>) {
+    let blocks = generate_blocks();
+    (create_db(&blocks), blocks)
+}
+
+/// Generates at least three blocks of random transactions.
+fn generate_blocks() -> Vec<Block> {
+    let mut generator = ValueGenerator::new_deterministic();
+    loop {
+        let blocks = generator.generate(arb_blocks_to_commit_impl(
+            5,  /* num_accounts */
+            10, /* max_txn_per_block */
+            20, /* max_blocks */
+        ));
+        if blocks.len() >= 3 {
+            return blocks;
+        }
+    }
+}
+
+fn create_db(blocks: &[Block]) -> Arc<DiemDB> {
+    let db = Arc::new(DiemDB::new_for_test());
+    let mut version = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in blocks {
+        db.save_transactions(txns_to_commit, version, Some(ledger_info_with_sigs))
+            .unwrap();
+        version += txns_to_commit.len() as u64;
+    }
+    db
+}
+
+fn create_bootstrapper<T: StorageReaderInterface + Send + Sync + 'static>(
+    storage_client: Arc<InProcessStorageServiceClient<T>>,
+    blocks: &[Block],
+    db: &Arc<DiemDB>,
+) -> Bootstrapper {
+    let config = StateSyncConfig {
+        bootstrapping_mode: BootstrappingMode::DownloadLatestAccountStates,
+        ..StateSyncConfig::default()
+    };
+    Bootstrapper::new(
+        &config,
+        create_streaming_service(storage_client, blocks),
+        DbReaderWriter::from_arc(db.clone()),
+    )
+}
+
+fn create_storage_server(db: &Arc<DiemDB>) -> StorageServiceServer<StorageReader> {
+    StorageServiceServer::new(StorageServiceConfig::default(), create_storage_reader(db))
+}
+
+fn create_storage_reader(db: &Arc<DiemDB>) -> StorageReader {
+    StorageReader::new(Arc::new(RwLock::new(DbReaderWriter::from_arc(db.clone()))))
+}
+
+/// Uses small chunks so that every stream is split into multiple requests.
+fn create_streaming_config() -> DataStreamingServiceConfig {
+    DataStreamingServiceConfig {
+        max_chunk_size: 7,
+        ..DataStreamingServiceConfig::default()
+    }
+}
+
+/// Trusts the first epoch ending ledger info.
+fn create_trusted_state(blocks: &[Block]) -> TrustedState {
+    let waypoint = Waypoint::new_epoch_boundary(blocks[0].1.ledger_info()).unwrap();
+    TrustedState::from_epoch_waypoint(waypoint)
+}
+
+fn create_streaming_service<T: StorageReaderInterface + Send + Sync + 'static>(
+    storage_client: Arc<InProcessStorageServiceClient<T>>,
+    blocks: &[Block],
+) -> DataStreamingService {
+    DataStreamingService::new(
+        create_streaming_config(),
+        storage_client,
+        create_trusted_state(blocks),
+    )
+}
+
+fn get_transactions(blocks: &[Block]) -> Vec<Transaction> {
+    blocks
+        .iter()
+        .flat_map(|(txns_to_commit, _)| txns_to_commit.iter())
+        .map(|txn_to_commit| txn_to_commit.transaction().clone())
+        .collect()
+}
+
+/// A storage reader that (optionally) corrupts the data it returns, which makes
+/// the proofs fail to verify. Transaction lists are corrupted by dropping their
+/// last transaction. Account states are returned one per chunk, so that they
+/// span many chunks, and all chunks but the first are corrupted by replacing
+/// the account state blob.
+struct CorruptingStorageReader {
+    storage_reader: StorageReader,
+    corrupt_transactions: bool,
+    corrupt_account_states: bool,
+}
+
+impl CorruptingStorageReader {
+    fn new(db: &Arc<DiemDB>, corrupt_transactions: bool, corrupt_account_states: bool) -> Self {
+        Self {
+            storage_reader: create_storage_reader(db),
+            corrupt_transactions,
+            corrupt_account_states,
+        }
+    }
+}
+
+impl StorageReaderInterface for CorruptingStorageReader {
+    fn get_data_summary(&self) -> Result<DataSummary, StorageError> {
+        self.storage_reader.get_data_summary()
+    }
+
+    fn get_transactions_with_proof(
+        &self,
+        proof_version: u64,
+        start_version: u64,
+        expected_num_transactions: u64,
+        include_events: bool,
+    ) -> Result<TransactionListWithProof, StorageError> {
+        let mut transaction_list = self.storage_reader.get_transactions_with_proof(
+            proof_version,
+            start_version,
+            expected_num_transactions,
+            include_events,
+        )?;
+        if self.corrupt_transactions {
+            transaction_list.transactions.pop();
+        }
+        Ok(transaction_list)
+    }
+
+    fn get_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        expected_end_epoch: u64,
+    ) -> Result<EpochChangeProof, StorageError> {
+        self.storage_reader
+            .get_epoch_ending_ledger_infos(start_epoch, expected_end_epoch)
+    }
+
+    /// The db doesn't persist write sets, so the outputs are made up of the
+    /// transaction events and empty write sets. This still passes verification,
+    /// as the proofs can't tell write sets apart.
+    fn get_transaction_outputs_with_proof(
+        &self,
+        proof_version: u64,
+        start_version: u64,
+        expected_num_transaction_outputs: u64,
+    ) -> Result<TransactionAndOutputListWithProof, StorageError> {
+        let transaction_list = self.get_transactions_with_proof(
+            proof_version,
+            start_version,
+            expected_num_transaction_outputs,
+            true,
+        )?;
+        let transactions_and_outputs = transaction_list
+            .transactions
+            .into_iter()
+            .zip(transaction_list.events.unwrap_or_default())
+            .zip(transaction_list.proof.transaction_infos.iter())
+            .map(|((transaction, events), transaction_info)| {
+                let output = TransactionOutput::new(
+                    WriteSet::default(),
+                    events,
+                    transaction_info.gas_used(),
+                    TransactionStatus::Keep(transaction_info.status().clone()),
+                );
+                (transaction, output)
+            })
+            .collect();
+        Ok(TransactionAndOutputListWithProof::new(
+            transactions_and_outputs,
+            transaction_list.first_transaction_version,
+            transaction_list.proof,
+        ))
+    }
+
+    fn get_number_of_accounts(&self, version: u64) -> Result<u64, StorageError> {
+        self.storage_reader.get_number_of_accounts(version)
+    }
+
+    fn get_account_states_chunk_with_proof(
+        &self,
+        version: u64,
+        start_account_key: HashValue,
+        expected_num_account_states: u64,
+    ) -> Result<AccountStatesChunkWithProof, StorageError> {
+        let mut account_states_chunk = self.storage_reader.get_account_states_chunk_with_proof(
+            version,
+            start_account_key,
+            expected_num_account_states.min(1),
+        )?;
+        if self.corrupt_account_states && start_account_key != HashValue::zero() {
+            account_states_chunk.account_blobs[0].1 = AccountStateBlob::from(vec![]);
+        }
+        Ok(account_states_chunk)
+    }
+}
+
+/// A chunk handed to the `MockChunkExecutor`.
+struct Chunk {
+    applied: bool,
+    first_version: Version,
+    num_transactions: u64,
+    target: LedgerInfoWithSignatures,
+}
+
+/// A chunk executor that records the chunks it's given, without executing
+/// (or committing) them.
+#[derive(Default)]
+struct MockChunkExecutor {
+    chunks: Mutex<Vec<Chunk>>,
+}
+
+impl ChunkExecutor for MockChunkExecutor {
+    fn execute_and_commit_chunk(
+        &self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        _epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> anyhow::Result<Vec<ContractEvent>> {
+        self.chunks.lock().push(Chunk {
+            applied: false,
+            first_version: txn_list_with_proof.first_transaction_version.unwrap(),
+            num_transactions: txn_list_with_proof.transactions.len() as u64,
+            target: verified_target_li,
+        });
+        Ok(vec![])
+    }
+
+    fn apply_and_commit_chunk(
+        &self,
+        txn_output_list_with_proof: TransactionAndOutputListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        _epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> anyhow::Result<Vec<ContractEvent>> {
+        self.chunks.lock().push(Chunk {
+            applied: true,
+            first_version: txn_output_list_with_proof
+                .first_transaction_version
+                .unwrap(),
+            num_transactions: txn_output_list_with_proof.transactions_and_outputs.len() as u64,
+            target: verified_target_li,
+        });
+        Ok(vec![])
+    }
+}
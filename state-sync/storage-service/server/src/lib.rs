@@ -3,36 +3,45 @@
 
 #![forbid(unsafe_code)]
 
+use diem_config::config::StorageServiceConfig;
 use diem_crypto::HashValue;
-use diem_infallible::RwLock;
+use diem_infallible::{Mutex, RwLock};
 use diem_types::{
     account_state_blob::AccountStatesChunkWithProof,
     epoch_change::EpochChangeProof,
+    ledger_info::LedgerInfoWithSignatures,
     transaction::{
-        default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
+        default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
         Version,
     },
+    PeerId,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use storage_interface::default_protocol::DbReaderWriter;
 use storage_service_types::{
     AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary,
-    EpochEndingLedgerInfoRequest, ProtocolMetadata, ServerProtocolVersion, StorageServerSummary,
-    StorageServiceError, StorageServiceRequest, StorageServiceResponse,
-    TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
+    EpochEndingLedgerInfoRequest, NewTransactionsWithProofRequest, ProtocolMetadata,
+    ServerProtocolVersion, StorageServerSummary, StorageServiceError, StorageServiceRequest,
+    StorageServiceResponse, TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
 };
+use subscription::PendingRequests;
 use thiserror::Error;
 
+mod metrics;
+pub mod network;
+mod subscription;
+
+pub use subscription::{notify_commits, CommitListener, ResponseSender};
+
 #[cfg(test)]
 mod tests;
 
-// TODO(joshlind): make these configurable.
 /// Storage server constants.
-pub const MAX_EPOCH_CHUNK_SIZE: u64 = 1000;
-pub const MAX_TRANSACTION_CHUNK_SIZE: u64 = 1000;
-pub const MAX_TRANSACTION_OUTPUT_CHUNK_SIZE: u64 = 1000;
-pub const MAX_ACCOUNT_STATES_CHUNK_SIZE: u64 = 1000;
 pub const STORAGE_SERVER_VERSION: u64 = 1;
 
 #[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
@@ -46,18 +55,53 @@ pub enum Error {
 /// The server-side implementation of the storage service. This provides all the
 /// functionality required to handle storage service requests (i.e., from clients).
 pub struct StorageServiceServer<T> {
+    config: StorageServiceConfig,
     storage: T,
+    rate_limiter: Mutex<RequestRateLimiter>,
+    pending_requests: Mutex<PendingRequests>,
 }
 
 impl<T: StorageReaderInterface> StorageServiceServer<T> {
-    pub fn new(storage: T) -> Self {
-        Self { storage }
+    pub fn new(config: StorageServiceConfig, storage: T) -> Self {
+        Self {
+            config,
+            storage,
+            rate_limiter: Mutex::new(RequestRateLimiter::new(config.max_requests_per_second)),
+            pending_requests: Mutex::new(PendingRequests::new(
+                config.max_pending_requests_per_peer,
+            )),
+        }
     }
 
+    /// Handles a request sent by `peer` over the network. Requests beyond the
+    /// rate limit of the peer are rejected. Subscription requests are served
+    /// right away (see `handle_subscription_request` to hold them instead).
+    pub fn handle_peer_request(
+        &self,
+        peer: PeerId,
+        request: StorageServiceRequest,
+    ) -> Result<StorageServiceResponse, Error> {
+        if !self.rate_limiter.lock().try_acquire(peer, Instant::now()) {
+            metrics::increment_counter(&metrics::STORAGE_REQUESTS_REJECTED, request.get_label());
+            return Ok(StorageServiceResponse::StorageServiceError(
+                StorageServiceError::TooManyRequests,
+            ));
+        }
+        self.handle_request(request)
+    }
+
+    /// Handles a request. Subscription requests are served right away, i.e.,
+    /// the response holds no transactions if storage has no new ones.
     pub fn handle_request(
         &self,
         request: StorageServiceRequest,
     ) -> Result<StorageServiceResponse, Error> {
+        let request_label = request.get_label();
+        metrics::increment_counter(&metrics::STORAGE_REQUESTS_RECEIVED, request_label);
+        let _timer = metrics::STORAGE_REQUEST_PROCESSING_LATENCY
+            .with_label_values(&[request_label])
+            .start_timer();
+
         let response = match request {
             StorageServiceRequest::GetAccountStatesChunkWithProof(request) => {
                 self.get_account_states_chunk_with_proof(request)
@@ -65,6 +109,11 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
             StorageServiceRequest::GetEpochEndingLedgerInfos(request) => {
                 self.get_epoch_ending_ledger_infos(request)
             }
+            StorageServiceRequest::GetNewTransactionsWithProof(request) => {
+                self.storage.get_data_summary().and_then(|data_summary| {
+                    self.get_new_transactions_with_proof(&request, data_summary.synced_ledger_info)
+                })
+            }
             StorageServiceRequest::GetNumberOfAccountsAtVersion(version) => {
                 self.get_number_of_accounts_at_version(version)
             }
@@ -77,16 +126,24 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
                 self.get_transactions_with_proof(request)
             }
         };
+        Ok(self.to_service_response(response, request_label))
+    }
 
+    /// Converts the result of serving a request into the response for the client.
+    fn to_service_response(
+        &self,
+        response: Result<StorageServiceResponse, Error>,
+        request_label: &str,
+    ) -> StorageServiceResponse {
         // If any requests resulted in an unexpected error, return an InternalStorageError to the
         // client and log the actual error.
-        if let Err(_error) = response {
-            // TODO(joshlind): add logging support to this library so we can log _error
-            Ok(StorageServiceResponse::StorageServiceError(
-                StorageServiceError::InternalError,
-            ))
-        } else {
-            response
+        match response {
+            Ok(response) => response,
+            Err(_error) => {
+                // TODO(joshlind): add logging support to this library so we can log _error
+                metrics::increment_counter(&metrics::STORAGE_ERRORS_ENCOUNTERED, request_label);
+                StorageServiceResponse::StorageServiceError(StorageServiceError::InternalError)
+            }
         }
     }
 
@@ -97,7 +154,9 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         let account_states_chunk_with_proof = self.storage.get_account_states_chunk_with_proof(
             request.version,
             request.start_account_key,
-            request.expected_num_account_states,
+            request
+                .expected_num_account_states
+                .min(self.config.max_account_states_chunk_size),
         )?;
 
         Ok(StorageServiceResponse::AccountStatesChunkWithProof(
@@ -109,15 +168,63 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         &self,
         request: EpochEndingLedgerInfoRequest,
     ) -> Result<StorageServiceResponse, Error> {
-        let epoch_change_proof = self
-            .storage
-            .get_epoch_ending_ledger_infos(request.start_epoch, request.expected_end_epoch)?;
+        let max_end_epoch = request
+            .start_epoch
+            .saturating_add(self.config.max_epoch_chunk_size.saturating_sub(1));
+        let epoch_change_proof = self.storage.get_epoch_ending_ledger_infos(
+            request.start_epoch,
+            request.expected_end_epoch.min(max_end_epoch),
+        )?;
 
         Ok(StorageServiceResponse::EpochEndingLedgerInfos(
             epoch_change_proof,
         ))
     }
 
+    /// Returns the transactions after the known version of the client, proven
+    /// by `synced_ledger_info`. Transactions can't be proven across epochs, so
+    /// clients in older epochs get the transactions up to the end of their
+    /// epoch instead.
+    fn get_new_transactions_with_proof(
+        &self,
+        request: &NewTransactionsWithProofRequest,
+        synced_ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<StorageServiceResponse, Error> {
+        let target_ledger_info = if request.known_epoch < synced_ledger_info.ledger_info().epoch() {
+            self.storage
+                .get_epoch_ending_ledger_infos(request.known_epoch, request.known_epoch + 1)?
+                .ledger_info_with_sigs
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    Error::UnexpectedErrorEncountered(format!(
+                        "No epoch ending ledger info found for epoch {}",
+                        request.known_epoch
+                    ))
+                })?
+        } else {
+            synced_ledger_info
+        };
+
+        let target_version = target_ledger_info.ledger_info().version();
+        let transactions_with_proof = if target_version > request.known_version {
+            self.storage.get_transactions_with_proof(
+                target_version,
+                request.known_version + 1,
+                (target_version - request.known_version)
+                    .min(self.config.max_transaction_chunk_size),
+                request.include_events,
+            )?
+        } else {
+            TransactionListWithProof::new_empty()
+        };
+
+        Ok(StorageServiceResponse::NewTransactionsWithProof((
+            transactions_with_proof,
+            target_ledger_info,
+        )))
+    }
+
     fn get_number_of_accounts_at_version(
         &self,
         version: Version,
@@ -141,10 +248,10 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
     fn get_storage_server_summary(&self) -> Result<StorageServiceResponse, Error> {
         let storage_server_summary = StorageServerSummary {
             protocol_metadata: ProtocolMetadata {
-                max_epoch_chunk_size: MAX_EPOCH_CHUNK_SIZE,
-                max_transaction_chunk_size: MAX_TRANSACTION_CHUNK_SIZE,
-                max_transaction_output_chunk_size: MAX_TRANSACTION_OUTPUT_CHUNK_SIZE,
-                max_account_states_chunk_size: MAX_ACCOUNT_STATES_CHUNK_SIZE,
+                max_epoch_chunk_size: self.config.max_epoch_chunk_size,
+                max_transaction_chunk_size: self.config.max_transaction_chunk_size,
+                max_transaction_output_chunk_size: self.config.max_transaction_output_chunk_size,
+                max_account_states_chunk_size: self.config.max_account_states_chunk_size,
             },
             data_summary: self.storage.get_data_summary()?,
         };
@@ -161,7 +268,9 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         let transaction_output_list_with_proof = self.storage.get_transaction_outputs_with_proof(
             request.proof_version,
             request.start_version,
-            request.expected_num_outputs,
+            request
+                .expected_num_outputs
+                .min(self.config.max_transaction_output_chunk_size),
         )?;
 
         Ok(StorageServiceResponse::TransactionOutputsWithProof(
@@ -176,7 +285,9 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         let transactions_with_proof = self.storage.get_transactions_with_proof(
             request.proof_version,
             request.start_version,
-            request.expected_num_transactions,
+            request
+                .expected_num_transactions
+                .min(self.config.max_transaction_chunk_size),
             request.include_events,
         )?;
 
@@ -186,6 +297,36 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
     }
 }
 
+/// Limits the number of requests served per peer to `max_requests_per_second`,
+/// counting requests in fixed one second windows.
+struct RequestRateLimiter {
+    max_requests_per_second: u64,
+    windows: HashMap<PeerId, (Instant, u64)>,
+}
+
+impl RequestRateLimiter {
+    fn new(max_requests_per_second: u64) -> Self {
+        Self {
+            max_requests_per_second,
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Returns true iff `peer` may send another request at time `now`.
+    fn try_acquire(&mut self, peer: PeerId, now: Instant) -> bool {
+        let (window_start, num_requests) = self.windows.entry(peer).or_insert((now, 0));
+        if now.duration_since(*window_start) >= Duration::from_secs(1) {
+            *window_start = now;
+            *num_requests = 0;
+        }
+        if *num_requests >= self.max_requests_per_second {
+            return false;
+        }
+        *num_requests += 1;
+        true
+    }
+}
+
 /// The interface into local storage (e.g., the Diem DB) used by the storage
 /// server to handle client requests.
 pub trait StorageReaderInterface {
@@ -220,7 +361,7 @@ pub trait StorageReaderInterface {
         proof_version: u64,
         start_version: u64,
         expected_num_transaction_outputs: u64,
-    ) -> Result<TransactionOutputListWithProof, Error>;
+    ) -> Result<TransactionAndOutputListWithProof, Error>;
 
     /// Returns the number of accounts in the account state tree at the
     /// specified version.
@@ -228,6 +369,7 @@ pub trait StorageReaderInterface {
 
     /// Returns a chunk holding a list of account states starting at the
     /// specified account key with *at most* `expected_num_account_states`.
+    /// The chunk is empty if there are no account states from the key on.
     fn get_account_states_chunk_with_proof(
         &self,
         version: u64,
@@ -312,26 +454,42 @@ impl StorageReaderInterface for StorageReader {
 
     fn get_transaction_outputs_with_proof(
         &self,
-        _proof_version: u64,
-        _start_version: u64,
-        _expected_num_transaction_outputs: u64,
-    ) -> Result<TransactionOutputListWithProof, Error> {
-        // TODO(joshlind): implement this once the transaction outputs are persisted in the DB.
-        Err(Error::UnexpectedErrorEncountered(
-            "Unimplemented! This API call needs to be implemented!".into(),
-        ))
+        proof_version: u64,
+        start_version: u64,
+        expected_num_transaction_outputs: u64,
+    ) -> Result<TransactionAndOutputListWithProof, Error> {
+        let transaction_output_list_with_proof = self
+            .storage
+            .read()
+            .reader
+            .get_transaction_outputs(
+                start_version,
+                expected_num_transaction_outputs,
+                proof_version,
+            )
+            .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+        Ok(transaction_output_list_with_proof)
     }
 
     fn get_account_states_chunk_with_proof(
         &self,
-        _version: u64,
-        _start_account_key: HashValue,
-        _expected_num_account_states: u64,
+        version: u64,
+        start_account_key: HashValue,
+        expected_num_account_states: u64,
     ) -> Result<AccountStatesChunkWithProof, Error> {
-        // TODO(joshlind): implement this once DbReaderWriter supports these calls.
-        Err(Error::UnexpectedErrorEncountered(
-            "Unimplemented! This API call needs to be implemented!".into(),
-        ))
+        let account_states_range_with_proof = self
+            .storage
+            .read()
+            .reader
+            .get_account_states_range(version, start_account_key, expected_num_account_states)
+            .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+
+        // An empty range (e.g., past the last account) is returned as an empty chunk
+        Ok(AccountStatesChunkWithProof {
+            start_key: start_account_key,
+            account_blobs: account_states_range_with_proof.account_blobs,
+            proof: account_states_range_with_proof.proof,
+        })
     }
 
     fn get_number_of_accounts(&self, _version: u64) -> Result<u64, Error> {
@@ -0,0 +1,72 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_metrics::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, HistogramVec,
+    IntCounterVec, IntGauge,
+};
+use once_cell::sync::Lazy;
+
+/// Counter of pending network events to the storage service (server-side)
+pub static PENDING_STORAGE_SERVER_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_storage_service_server_pending_network_events",
+        "Counters for pending network events for the storage server",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// Counter of storage requests received by the server
+pub static STORAGE_REQUESTS_RECEIVED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_storage_service_server_requests_received",
+        "Counters related to the storage server requests received",
+        &["request_type"]
+    )
+    .unwrap()
+});
+
+/// Counter of storage requests rejected because of the per-peer rate limit
+pub static STORAGE_REQUESTS_REJECTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_storage_service_server_requests_rejected",
+        "Counters related to the storage server requests rejected by the rate limit",
+        &["request_type"]
+    )
+    .unwrap()
+});
+
+/// Counter of storage requests that failed to be served
+pub static STORAGE_ERRORS_ENCOUNTERED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_storage_service_server_errors",
+        "Counters related to the storage server errors encountered",
+        &["request_type"]
+    )
+    .unwrap()
+});
+
+/// Number of subscription requests waiting for new data
+pub static PENDING_STORAGE_SUBSCRIPTIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_service_server_pending_subscriptions",
+        "Number of subscription requests held by the storage server until new data is synced"
+    )
+    .unwrap()
+});
+
+/// Time it takes to process a storage request
+pub static STORAGE_REQUEST_PROCESSING_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "diem_storage_service_server_request_latency",
+        "Time it takes to process a storage service request",
+        &["request_type"]
+    )
+    .unwrap()
+});
+
+/// Increments the given counter with the provided label.
+pub fn increment_counter(counter: &Lazy<IntCounterVec>, label: &str) {
+    counter.with_label_values(&[label]).inc();
+}
@@ -0,0 +1,170 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interface between the storage service and the network layer.
+
+use crate::{metrics, CommitListener, StorageReaderInterface, StorageServiceServer};
+use bytes::Bytes;
+use channel::message_queues::QueueStyle;
+use diem_config::config::StorageServiceConfig;
+use diem_metrics::IntCounterVec;
+use diem_types::PeerId;
+use futures::{channel::oneshot, StreamExt};
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::{
+        network::{Event, NetworkEvents, NetworkSender, NewNetworkSender},
+        rpc::error::RpcError,
+    },
+    ProtocolId,
+};
+use std::{sync::Arc, time::Duration};
+use storage_service_types::{StorageServiceMessage, StorageServiceRequest, StorageServiceResponse};
+use tokio::time::interval;
+
+/// The network events received by the storage service server. Requests arrive
+/// as RPCs, carrying a `StorageServiceMessage::Request`.
+pub type StorageServiceNetworkEvents = NetworkEvents<StorageServiceMessage>;
+
+/// The interface used by clients to send requests to the storage services of
+/// remote peers.
+#[derive(Clone)]
+pub struct StorageServiceNetworkSender {
+    inner: NetworkSender<StorageServiceMessage>,
+}
+
+impl NewNetworkSender for StorageServiceNetworkSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+impl StorageServiceNetworkSender {
+    /// Sends `request` to the storage service of `recipient` and returns the
+    /// (unverified) response.
+    pub async fn send_request(
+        &mut self,
+        recipient: PeerId,
+        request: StorageServiceRequest,
+        timeout: Duration,
+    ) -> Result<StorageServiceResponse, RpcError> {
+        let message = self
+            .inner
+            .send_rpc(
+                recipient,
+                ProtocolId::StorageServiceRpc,
+                StorageServiceMessage::Request(request),
+                timeout,
+            )
+            .await?;
+        match message {
+            StorageServiceMessage::Response(response) => Ok(response),
+            StorageServiceMessage::Request(_) => Err(RpcError::InvalidRpcResponse),
+        }
+    }
+}
+
+/// Configuration for the network endpoints to support the storage service.
+pub fn network_endpoint_config(
+    config: StorageServiceConfig,
+) -> (
+    Vec<ProtocolId>,
+    Vec<ProtocolId>,
+    QueueStyle,
+    usize,
+    Option<&'static IntCounterVec>,
+) {
+    (
+        vec![ProtocolId::StorageServiceRpc],
+        vec![],
+        QueueStyle::FIFO,
+        config.max_network_channel_size as usize,
+        Some(&metrics::PENDING_STORAGE_SERVER_NETWORK_EVENTS),
+    )
+}
+
+impl<T: StorageReaderInterface + Send + Sync + 'static> StorageServiceServer<T> {
+    /// Serves the requests arriving on `network_events` until the network
+    /// shuts down. Requests are handled on blocking threads, as they read from
+    /// storage. Subscription requests may be held by the server, in which case
+    /// they are served by `serve_pending_requests`.
+    pub async fn start(self: Arc<Self>, mut network_events: StorageServiceNetworkEvents) {
+        while let Some(event) = network_events.next().await {
+            let (peer, request, response_sender) = match event {
+                Event::RpcRequest(peer, StorageServiceMessage::Request(request), sender) => {
+                    (peer, request, sender)
+                }
+                _ => continue, // Only requests are expected, and there's no peer state
+            };
+
+            let server = self.clone();
+            match request {
+                StorageServiceRequest::GetNewTransactionsWithProof(request) => {
+                    // The server may hold the request, so the response is awaited separately
+                    let (subscription_sender, subscription_receiver) = oneshot::channel();
+                    tokio::task::spawn_blocking(move || {
+                        server.handle_subscription_request(peer, request, subscription_sender)
+                    });
+                    let mut response_sender = response_sender;
+                    tokio::spawn(async move {
+                        // If the peer gives up on the request, the server drops it
+                        let response = tokio::select! {
+                            response = subscription_receiver => response.ok(),
+                            _ = response_sender.cancellation() => None,
+                        };
+                        if let Some(response) = response {
+                            send_response(Ok(response), response_sender);
+                        }
+                    });
+                }
+                request => {
+                    tokio::task::spawn_blocking(move || {
+                        let response = server
+                            .handle_peer_request(peer, request)
+                            .map_err(|error| RpcError::ApplicationError(error.into()));
+                        send_response(response, response_sender);
+                    });
+                }
+            }
+        }
+    }
+
+    /// Serves the pending subscription requests (see `check_pending_requests`)
+    /// whenever `commit_listener` is notified of a commit. They are also
+    /// checked every `pending_requests_expiry_interval_ms`, so that expired
+    /// requests are answered. Only one of these should run per server,
+    /// regardless of the number of networks it serves.
+    pub async fn serve_pending_requests(self: Arc<Self>, commit_listener: CommitListener) {
+        let mut ticker = interval(Duration::from_millis(
+            self.config.pending_requests_expiry_interval_ms,
+        ));
+        loop {
+            tokio::select! {
+                _ = commit_listener.wait_for_commit() => {}
+                _ = ticker.tick() => {}
+            }
+            let server = self.clone();
+            let _ = tokio::task::spawn_blocking(move || server.check_pending_requests()).await;
+        }
+    }
+}
+
+/// Serializes the response and sends it to the peer.
+fn send_response(
+    response: Result<StorageServiceResponse, RpcError>,
+    response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
+) {
+    let response = response.and_then(|response| {
+        ProtocolId::StorageServiceRpc
+            .to_bytes(&StorageServiceMessage::Response(response))
+            .map(Into::into)
+            .map_err(RpcError::Error)
+    });
+    // The peer may have given up on the request already
+    let _ = response_sender.send(response);
+}
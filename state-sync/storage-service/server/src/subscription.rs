@@ -0,0 +1,263 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Long-poll (subscription) requests: requests for new data that the server
+//! holds until storage advances past the version known by the client.
+
+use crate::{metrics, StorageReaderInterface, StorageServiceServer};
+use anyhow::Result;
+use diem_crypto::HashValue;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    protocol_spec::DpnProto,
+    transaction::{default_protocol::TransactionListWithProof, TransactionToCommit, Version},
+    PeerId,
+};
+use futures::channel::oneshot;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use storage_interface::{default_protocol::DbReaderWriter, DbWriter, StateSnapshotReceiver};
+use storage_service_types::{
+    NewTransactionsWithProofRequest, StorageServiceError, StorageServiceRequest,
+    StorageServiceResponse,
+};
+use tokio::sync::Notify;
+
+/// The channel used to respond to a subscription request once it is served.
+pub type ResponseSender = oneshot::Sender<StorageServiceResponse>;
+
+/// Wraps the writer of `db` so that the returned listener is notified whenever
+/// a new ledger info is committed, i.e., whenever storage advances.
+pub fn notify_commits(db: DbReaderWriter) -> (DbReaderWriter, CommitListener) {
+    let notify = Arc::new(Notify::new());
+    let writer = Arc::new(CommitNotifier {
+        writer: db.writer,
+        notify: notify.clone(),
+    });
+    let db = DbReaderWriter {
+        reader: db.reader,
+        writer,
+    };
+    (db, CommitListener { notify })
+}
+
+/// Listens to the commits to storage, see `notify_commits`.
+#[derive(Clone)]
+pub struct CommitListener {
+    notify: Arc<Notify>,
+}
+
+impl CommitListener {
+    /// Waits for a commit. Returns right away if there was one since the last
+    /// wait returned.
+    pub async fn wait_for_commit(&self) {
+        self.notify.notified().await
+    }
+}
+
+/// A writer notifying the commit listener after every new ledger info.
+struct CommitNotifier {
+    writer: Arc<dyn DbWriter<DpnProto>>,
+    notify: Arc<Notify>,
+}
+
+impl DbWriter<DpnProto> for CommitNotifier {
+    fn save_transactions(
+        &self,
+        txns_to_commit: &[TransactionToCommit],
+        first_version: Version,
+        ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+    ) -> Result<()> {
+        self.writer
+            .save_transactions(txns_to_commit, first_version, ledger_info_with_sigs)?;
+        if ledger_info_with_sigs.is_some() {
+            self.notify.notify_one();
+        }
+        Ok(())
+    }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        self.writer
+            .get_state_snapshot_receiver(version, expected_root_hash)
+    }
+
+    fn finalize_state_snapshot(
+        &self,
+        version: Version,
+        transaction_list_with_proof: TransactionListWithProof,
+        ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        self.writer
+            .finalize_state_snapshot(version, transaction_list_with_proof, ledger_infos)?;
+        self.notify.notify_one();
+        Ok(())
+    }
+}
+
+/// A subscription request waiting for new data.
+pub(crate) struct PendingRequest {
+    request: NewTransactionsWithProofRequest,
+    expiry_time: Instant,
+    response_sender: ResponseSender,
+}
+
+/// The subscription requests waiting for new data, held per peer.
+pub(crate) struct PendingRequests {
+    max_pending_requests_per_peer: u64,
+    requests: HashMap<PeerId, Vec<PendingRequest>>,
+}
+
+impl PendingRequests {
+    pub(crate) fn new(max_pending_requests_per_peer: u64) -> Self {
+        Self {
+            max_pending_requests_per_peer,
+            requests: HashMap::new(),
+        }
+    }
+
+    /// Holds the request of `peer` until `expiry_time`. If the peer already
+    /// has too many pending requests, the response sender is handed back.
+    pub(crate) fn add(
+        &mut self,
+        peer: PeerId,
+        request: NewTransactionsWithProofRequest,
+        expiry_time: Instant,
+        response_sender: ResponseSender,
+    ) -> Result<(), ResponseSender> {
+        let peer_requests = self.requests.entry(peer).or_insert_with(Vec::new);
+        if peer_requests.len() as u64 >= self.max_pending_requests_per_peer {
+            return Err(response_sender);
+        }
+        peer_requests.push(PendingRequest {
+            request,
+            expiry_time,
+            response_sender,
+        });
+        Ok(())
+    }
+
+    /// Removes and returns the requests that can be served at time `now`, i.e.,
+    /// the requests for versions below `synced_version` and the expired ones.
+    pub(crate) fn remove_ready(
+        &mut self,
+        synced_version: u64,
+        now: Instant,
+    ) -> Vec<(NewTransactionsWithProofRequest, ResponseSender)> {
+        let mut ready = vec![];
+        for peer_requests in self.requests.values_mut() {
+            let mut index = 0;
+            while index < peer_requests.len() {
+                let pending_request = &peer_requests[index];
+                if pending_request.request.known_version < synced_version
+                    || pending_request.expiry_time <= now
+                    || pending_request.response_sender.is_canceled()
+                {
+                    let pending_request = peer_requests.swap_remove(index);
+                    ready.push((pending_request.request, pending_request.response_sender));
+                } else {
+                    index += 1;
+                }
+            }
+        }
+        self.requests
+            .retain(|_, peer_requests| !peer_requests.is_empty());
+        ready
+    }
+
+    /// Returns the number of requests held for all peers.
+    pub(crate) fn len(&self) -> usize {
+        self.requests.values().map(Vec::len).sum()
+    }
+}
+
+impl<T: StorageReaderInterface> StorageServiceServer<T> {
+    /// Handles a subscription request sent by `peer`. The response is sent on
+    /// `response_sender` right away if storage holds new transactions (or the
+    /// request is rejected). Otherwise, the request is held until storage
+    /// advances or the request expires, see `check_pending_requests`.
+    pub fn handle_subscription_request(
+        &self,
+        peer: PeerId,
+        request: NewTransactionsWithProofRequest,
+        response_sender: ResponseSender,
+    ) {
+        let request_label =
+            StorageServiceRequest::GetNewTransactionsWithProof(request.clone()).get_label();
+        let now = Instant::now();
+        if !self.rate_limiter.lock().try_acquire(peer, now) {
+            metrics::increment_counter(&metrics::STORAGE_REQUESTS_REJECTED, request_label);
+            let _ = response_sender.send(StorageServiceResponse::StorageServiceError(
+                StorageServiceError::TooManyRequests,
+            ));
+            return;
+        }
+        metrics::increment_counter(&metrics::STORAGE_REQUESTS_RECEIVED, request_label);
+
+        // Serve the request now if storage already holds new transactions
+        let synced_ledger_info = match self.storage.get_data_summary() {
+            Ok(data_summary) => data_summary.synced_ledger_info,
+            Err(error) => {
+                let response = self.to_service_response(Err(error), request_label);
+                let _ = response_sender.send(response);
+                return;
+            }
+        };
+        if synced_ledger_info.ledger_info().version() > request.known_version {
+            let response = self.get_new_transactions_with_proof(&request, synced_ledger_info);
+            let _ = response_sender.send(self.to_service_response(response, request_label));
+            return;
+        }
+
+        // Otherwise, hold the request until new transactions are synced
+        let wait_time = request
+            .max_wait_ms
+            .min(self.config.max_subscription_wait_ms);
+        let expiry_time = now + Duration::from_millis(wait_time);
+        let mut pending_requests = self.pending_requests.lock();
+        if let Err(response_sender) =
+            pending_requests.add(peer, request, expiry_time, response_sender)
+        {
+            metrics::increment_counter(&metrics::STORAGE_REQUESTS_REJECTED, request_label);
+            let _ = response_sender.send(StorageServiceResponse::StorageServiceError(
+                StorageServiceError::TooManyPendingRequests,
+            ));
+        }
+        metrics::PENDING_STORAGE_SUBSCRIPTIONS.set(pending_requests.len() as i64);
+    }
+
+    /// Serves the pending subscription requests for which storage now holds
+    /// new transactions, as well as the expired ones (with no transactions).
+    /// This is expected to be called after every commit to storage (see
+    /// `notify_commits`) and periodically, for the requests to expire.
+    pub fn check_pending_requests(&self) {
+        let synced_ledger_info = match self.storage.get_data_summary() {
+            Ok(data_summary) => data_summary.synced_ledger_info,
+            Err(_error) => return, // The requests are checked again next time
+        };
+        let synced_version = synced_ledger_info.ledger_info().version();
+        let ready_requests = {
+            let mut pending_requests = self.pending_requests.lock();
+            let ready_requests = pending_requests.remove_ready(synced_version, Instant::now());
+            metrics::PENDING_STORAGE_SUBSCRIPTIONS.set(pending_requests.len() as i64);
+            ready_requests
+        };
+
+        for (request, response_sender) in ready_requests {
+            if response_sender.is_canceled() {
+                continue; // The peer has given up on the request
+            }
+            let request_label =
+                StorageServiceRequest::GetNewTransactionsWithProof(request.clone()).get_label();
+            let response =
+                self.get_new_transactions_with_proof(&request, synced_ledger_info.clone());
+            let _ = response_sender.send(self.to_service_response(response, request_label));
+        }
+    }
+}
@@ -3,14 +3,20 @@
 
 #![forbid(unsafe_code)]
 
-use crate::{StorageReader, StorageServiceServer};
+use crate::{
+    notify_commits, subscription::PendingRequests, RequestRateLimiter, StorageReader,
+    StorageServiceServer,
+};
 use anyhow::Result;
 use claim::{assert_matches, assert_none, assert_some};
+use diem_config::config::StorageServiceConfig;
 use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
 use diem_infallible::RwLock;
 use diem_types::{
     account_address::AccountAddress,
-    account_state_blob::{default_protocol::AccountStateWithProof, AccountStateBlob},
+    account_state_blob::{
+        default_protocol::AccountStateWithProof, AccountStateBlob, AccountStatesRangeWithProof,
+    },
     block_info::BlockInfo,
     chain_id::ChainId,
     contract_event::{
@@ -20,25 +26,34 @@ use diem_types::{
     epoch_change::EpochChangeProof,
     event::EventKey,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    proof::{SparseMerkleProof, TransactionInfoListWithProof},
+    proof::{SparseMerkleProof, SparseMerkleRangeProof, TransactionInfoListWithProof},
     protocol_spec::DpnProto,
     state_proof::StateProof,
     transaction::{
         default_protocol::{
-            AccountTransactionsWithProof, TransactionListWithProof, TransactionWithProof,
+            AccountTransactionsWithProof, TransactionAndOutputListWithProof,
+            TransactionListWithProof, TransactionWithProof,
         },
-        RawTransaction, Script, SignedTransaction, Transaction, TransactionPayload,
-        TransactionToCommit, Version,
+        RawTransaction, Script, SignedTransaction, Transaction, TransactionOutput,
+        TransactionPayload, TransactionStatus, TransactionToCommit, Version,
     },
+    vm_status::KeptVMStatus,
+    write_set::WriteSet,
+    PeerId,
 };
+use futures::{channel::oneshot, FutureExt};
 use move_core_types::language_storage::TypeTag;
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use storage_interface::{DbReader, DbReaderWriter, DbWriter, Order, StartupInfo, TreeState};
 use storage_service_types::{
     AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary,
-    EpochEndingLedgerInfoRequest, ProtocolMetadata, ServerProtocolVersion, StorageServerSummary,
-    StorageServiceError, StorageServiceRequest, StorageServiceResponse,
-    TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
+    EpochEndingLedgerInfoRequest, NewTransactionsWithProofRequest, ProtocolMetadata,
+    ServerProtocolVersion, StorageServerSummary, StorageServiceError, StorageServiceRequest,
+    StorageServiceResponse, TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
 };
 
 // TODO(joshlind): Expand these test cases to better test storage interaction
@@ -50,10 +65,51 @@ fn test_get_account_states_chunk_with_proof() {
     let storage_server = create_storage_server();
 
     // Create a request to fetch an account states chunk with a proof
+    let start_account_key = HashValue::random();
+    let expected_num_account_states = 10;
     let account_states_chunk_request =
         StorageServiceRequest::GetAccountStatesChunkWithProof(AccountStatesChunkWithProofRequest {
             version: 0,
-            start_account_key: HashValue::random(),
+            start_account_key,
+            expected_num_account_states,
+        });
+
+    // Process the request
+    let account_states_chunk_response = storage_server
+        .handle_request(account_states_chunk_request)
+        .unwrap();
+
+    // Verify the response is correct
+    match account_states_chunk_response {
+        StorageServiceResponse::AccountStatesChunkWithProof(account_states_chunk) => {
+            assert_eq!(
+                account_states_chunk.account_blobs.len(),
+                expected_num_account_states as usize
+            );
+            assert_eq!(account_states_chunk.start_key, start_account_key);
+            assert_eq!(
+                account_states_chunk.account_blobs.first().unwrap().0,
+                start_account_key
+            );
+            assert_some!(account_states_chunk.proof);
+        }
+        result => {
+            panic!("Expected an account states chunk but got: {:?}", result);
+        }
+    };
+}
+
+#[test]
+fn test_get_account_states_chunk_with_proof_empty() {
+    // Create a storage service server
+    let storage_server = create_storage_server();
+
+    // Create a request to fetch an empty account states chunk
+    let start_account_key = HashValue::random();
+    let account_states_chunk_request =
+        StorageServiceRequest::GetAccountStatesChunkWithProof(AccountStatesChunkWithProofRequest {
+            version: 0,
+            start_account_key,
             expected_num_account_states: 0,
         });
 
@@ -62,11 +118,17 @@ fn test_get_account_states_chunk_with_proof() {
         .handle_request(account_states_chunk_request)
         .unwrap();
 
-    // Verify the response is correct (the API call is currently unsupported)
-    assert_matches!(
-        account_states_chunk_response,
-        StorageServiceResponse::StorageServiceError(StorageServiceError::InternalError)
-    );
+    // Verify the response is an empty chunk (there are no account states to prove)
+    match account_states_chunk_response {
+        StorageServiceResponse::AccountStatesChunkWithProof(account_states_chunk) => {
+            assert_eq!(account_states_chunk.start_key, start_account_key);
+            assert!(account_states_chunk.account_blobs.is_empty());
+            assert_none!(account_states_chunk.proof);
+        }
+        result => {
+            panic!("Expected an account states chunk but got: {:?}", result);
+        }
+    };
 }
 
 #[test]
@@ -187,23 +249,39 @@ fn test_get_transaction_outputs_with_proof() {
     let storage_server = create_storage_server();
 
     // Create a request to fetch transaction outputs with a proof
+    let start_version = 0;
+    let expected_num_outputs = 10;
     let transaction_outputs_proof_request =
         StorageServiceRequest::GetTransactionOutputsWithProof(TransactionOutputsWithProofRequest {
             proof_version: 1000,
-            start_version: 0,
-            expected_num_outputs: 10,
+            start_version,
+            expected_num_outputs,
         });
 
     // Process the request
-    let transactions_proof_response = storage_server
+    let transaction_outputs_proof_response = storage_server
         .handle_request(transaction_outputs_proof_request)
         .unwrap();
 
-    // Verify the response is correct (the API call is currently unsupported)
-    assert_matches!(
-        transactions_proof_response,
-        StorageServiceResponse::StorageServiceError(StorageServiceError::InternalError)
-    );
+    // Verify the response is correct
+    match transaction_outputs_proof_response {
+        StorageServiceResponse::TransactionOutputsWithProof(outputs_with_proof) => {
+            assert_eq!(
+                outputs_with_proof.transactions_and_outputs.len(),
+                expected_num_outputs as usize
+            );
+            assert_eq!(
+                outputs_with_proof.first_transaction_version,
+                Some(start_version)
+            );
+        }
+        result => {
+            panic!(
+                "Expected transaction outputs with proof but got: {:?}",
+                result
+            );
+        }
+    };
 }
 
 #[test]
@@ -288,10 +366,309 @@ fn test_get_epoch_ending_ledger_infos() {
     };
 }
 
+#[test]
+fn test_request_size_limits() {
+    // Create a storage service server with small chunk limits
+    let config = StorageServiceConfig {
+        max_epoch_chunk_size: 3,
+        max_transaction_chunk_size: 5,
+        ..StorageServiceConfig::default()
+    };
+    let storage_server = create_storage_server_with_config(config);
+
+    // Verify transaction requests are capped at the limit
+    let transactions_proof_request =
+        StorageServiceRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+            proof_version: 100,
+            start_version: 0,
+            expected_num_transactions: 20,
+            include_events: false,
+        });
+    match storage_server
+        .handle_request(transactions_proof_request)
+        .unwrap()
+    {
+        StorageServiceResponse::TransactionsWithProof(transactions_with_proof) => {
+            assert_eq!(transactions_with_proof.transactions.len(), 5);
+        }
+        result => {
+            panic!("Expected transactions with proof but got: {:?}", result);
+        }
+    };
+
+    // Verify epoch ending ledger info requests are capped at the limit
+    let epoch_ending_li_request =
+        StorageServiceRequest::GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest {
+            start_epoch: 2,
+            expected_end_epoch: 9,
+        });
+    match storage_server
+        .handle_request(epoch_ending_li_request)
+        .unwrap()
+    {
+        StorageServiceResponse::EpochEndingLedgerInfos(epoch_change_proof) => {
+            assert_eq!(epoch_change_proof.ledger_info_with_sigs.len(), 3);
+        }
+        result => {
+            panic!("Expected epoch ending ledger infos but got: {:?}", result);
+        }
+    };
+}
+
+#[test]
+fn test_request_rate_limit() {
+    // Create a storage service server that serves two requests per peer per second
+    let config = StorageServiceConfig {
+        max_requests_per_second: 2,
+        ..StorageServiceConfig::default()
+    };
+    let storage_server = create_storage_server_with_config(config);
+
+    // Verify requests beyond the limit are rejected
+    let peer = PeerId::random();
+    for _ in 0..2 {
+        let response = storage_server
+            .handle_peer_request(peer, StorageServiceRequest::GetServerProtocolVersion)
+            .unwrap();
+        assert_matches!(response, StorageServiceResponse::ServerProtocolVersion(_));
+    }
+    let response = storage_server
+        .handle_peer_request(peer, StorageServiceRequest::GetServerProtocolVersion)
+        .unwrap();
+    assert_eq!(
+        response,
+        StorageServiceResponse::StorageServiceError(StorageServiceError::TooManyRequests)
+    );
+
+    // Verify other peers aren't affected
+    let response = storage_server
+        .handle_peer_request(
+            PeerId::random(),
+            StorageServiceRequest::GetServerProtocolVersion,
+        )
+        .unwrap();
+    assert_matches!(response, StorageServiceResponse::ServerProtocolVersion(_));
+}
+
+#[test]
+fn test_request_rate_limiter_window() {
+    let mut rate_limiter = RequestRateLimiter::new(1);
+    let peer = PeerId::random();
+    let now = Instant::now();
+
+    // Verify the limit holds within a window, and is reset by the next one
+    assert!(rate_limiter.try_acquire(peer, now));
+    assert!(!rate_limiter.try_acquire(peer, now + Duration::from_millis(999)));
+    assert!(rate_limiter.try_acquire(peer, now + Duration::from_secs(1)));
+    assert!(!rate_limiter.try_acquire(peer, now + Duration::from_millis(1500)));
+}
+
+#[test]
+fn test_get_new_transactions_with_proof() {
+    // Create a storage service server
+    let storage_server = create_storage_server();
+
+    // Verify the transactions after the known version are returned right away
+    let new_transactions_request =
+        StorageServiceRequest::GetNewTransactionsWithProof(create_new_transactions_request(95, 0));
+    match storage_server
+        .handle_request(new_transactions_request)
+        .unwrap()
+    {
+        StorageServiceResponse::NewTransactionsWithProof((
+            transactions_with_proof,
+            ledger_info,
+        )) => {
+            assert_eq!(transactions_with_proof.transactions.len(), 5);
+            assert_eq!(transactions_with_proof.first_transaction_version, Some(96));
+            assert_eq!(ledger_info.ledger_info().version(), 100);
+        }
+        result => {
+            panic!("Expected new transactions with proof but got: {:?}", result);
+        }
+    };
+
+    // Verify a subscription is served right away if storage holds new transactions
+    let (response_sender, mut response_receiver) = oneshot::channel();
+    storage_server.handle_subscription_request(
+        PeerId::random(),
+        create_new_transactions_request(99, 10_000),
+        response_sender,
+    );
+    match response_receiver.try_recv().unwrap().unwrap() {
+        StorageServiceResponse::NewTransactionsWithProof((transactions_with_proof, _)) => {
+            assert_eq!(transactions_with_proof.transactions.len(), 1);
+            assert_eq!(transactions_with_proof.first_transaction_version, Some(100));
+        }
+        result => {
+            panic!("Expected new transactions with proof but got: {:?}", result);
+        }
+    };
+}
+
+#[test]
+fn test_subscription_expiry() {
+    // Create a storage service server
+    let storage_server = create_storage_server();
+
+    // Subscribe to the transactions after the highest synced version
+    let (response_sender, mut response_receiver) = oneshot::channel();
+    storage_server.handle_subscription_request(
+        PeerId::random(),
+        create_new_transactions_request(100, 0),
+        response_sender,
+    );
+    assert_none!(response_receiver.try_recv().unwrap());
+
+    // Verify the expired subscription is served without transactions
+    storage_server.check_pending_requests();
+    match response_receiver.try_recv().unwrap().unwrap() {
+        StorageServiceResponse::NewTransactionsWithProof((
+            transactions_with_proof,
+            ledger_info,
+        )) => {
+            assert!(transactions_with_proof.transactions.is_empty());
+            assert_eq!(ledger_info.ledger_info().version(), 100);
+        }
+        result => {
+            panic!("Expected new transactions with proof but got: {:?}", result);
+        }
+    };
+}
+
+#[test]
+fn test_subscription_limit() {
+    // Create a storage service server that holds two subscriptions per peer
+    let config = StorageServiceConfig {
+        max_pending_requests_per_peer: 2,
+        ..StorageServiceConfig::default()
+    };
+    let storage_server = create_storage_server_with_config(config);
+
+    // Verify subscriptions beyond the limit are rejected
+    let peer = PeerId::random();
+    let mut response_receivers = vec![];
+    for _ in 0..2 {
+        let (response_sender, mut response_receiver) = oneshot::channel();
+        storage_server.handle_subscription_request(
+            peer,
+            create_new_transactions_request(100, 10_000),
+            response_sender,
+        );
+        assert_none!(response_receiver.try_recv().unwrap());
+        response_receivers.push(response_receiver);
+    }
+    let (response_sender, mut response_receiver) = oneshot::channel();
+    storage_server.handle_subscription_request(
+        peer,
+        create_new_transactions_request(100, 10_000),
+        response_sender,
+    );
+    assert_eq!(
+        response_receiver.try_recv().unwrap(),
+        Some(StorageServiceResponse::StorageServiceError(
+            StorageServiceError::TooManyPendingRequests
+        ))
+    );
+
+    // Verify other peers aren't affected
+    let (response_sender, mut response_receiver) = oneshot::channel();
+    storage_server.handle_subscription_request(
+        PeerId::random(),
+        create_new_transactions_request(100, 10_000),
+        response_sender,
+    );
+    assert_none!(response_receiver.try_recv().unwrap());
+}
+
+#[test]
+fn test_pending_requests_ready() {
+    let mut pending_requests = PendingRequests::new(10);
+    let peer = PeerId::random();
+    let now = Instant::now();
+    let expiry_time = now + Duration::from_secs(10);
+
+    // Verify requests are held until storage advances past the known version
+    let (response_sender, _response_receiver) = oneshot::channel();
+    pending_requests
+        .add(
+            peer,
+            create_new_transactions_request(100, 0),
+            expiry_time,
+            response_sender,
+        )
+        .unwrap();
+    assert!(pending_requests.remove_ready(100, now).is_empty());
+    assert_eq!(pending_requests.remove_ready(101, now).len(), 1);
+    assert_eq!(pending_requests.len(), 0);
+
+    // Verify requests are removed once they expire
+    let (response_sender, _response_receiver) = oneshot::channel();
+    pending_requests
+        .add(
+            peer,
+            create_new_transactions_request(100, 0),
+            expiry_time,
+            response_sender,
+        )
+        .unwrap();
+    assert!(pending_requests.remove_ready(100, now).is_empty());
+    assert_eq!(pending_requests.remove_ready(100, expiry_time).len(), 1);
+
+    // Verify requests are removed once the peer gives up on them
+    let (response_sender, response_receiver) = oneshot::channel();
+    pending_requests
+        .add(
+            peer,
+            create_new_transactions_request(100, 0),
+            expiry_time,
+            response_sender,
+        )
+        .unwrap();
+    drop(response_receiver);
+    assert_eq!(pending_requests.remove_ready(100, now).len(), 1);
+    assert_eq!(pending_requests.len(), 0);
+}
+
+#[test]
+fn test_commit_notifications() {
+    let (db, commit_listener) = notify_commits(DbReaderWriter::new(MockDbReaderWriter));
+
+    // Verify the listener isn't notified of transactions without a ledger info
+    db.writer.save_transactions(&[], 0, None).unwrap();
+    assert_none!(commit_listener.wait_for_commit().now_or_never());
+
+    // Verify the listener is notified once a ledger info is committed
+    let ledger_info = create_test_ledger_info_with_sigs(10, 100);
+    db.writer
+        .save_transactions(&[], 0, Some(&ledger_info))
+        .unwrap();
+    assert_some!(commit_listener.wait_for_commit().now_or_never());
+    assert_none!(commit_listener.wait_for_commit().now_or_never());
+}
+
+fn create_new_transactions_request(
+    known_version: u64,
+    max_wait_ms: u64,
+) -> NewTransactionsWithProofRequest {
+    NewTransactionsWithProofRequest {
+        known_version,
+        known_epoch: 10,
+        include_events: false,
+        max_wait_ms,
+    }
+}
+
 fn create_storage_server() -> StorageServiceServer<StorageReader> {
+    create_storage_server_with_config(StorageServiceConfig::default())
+}
+
+fn create_storage_server_with_config(
+    config: StorageServiceConfig,
+) -> StorageServiceServer<StorageReader> {
     let storage = Arc::new(RwLock::new(DbReaderWriter::new(MockDbReaderWriter)));
     let storage_reader = StorageReader::new(storage);
-    StorageServiceServer::new(storage_reader)
+    StorageServiceServer::new(config, storage_reader)
 }
 
 fn create_test_event(sequence_number: u64) -> ContractEvent {
@@ -396,6 +773,31 @@ impl DbReader<DpnProto> for MockDbReaderWriter {
         })
     }
 
+    fn get_transaction_outputs(
+        &self,
+        start_version: Version,
+        batch_size: u64,
+        _ledger_version: Version,
+    ) -> Result<TransactionAndOutputListWithProof> {
+        // Create mock transactions and outputs
+        let mut transactions_and_outputs = vec![];
+        for i in 0..batch_size {
+            let output = TransactionOutput::new(
+                WriteSet::default(),
+                vec![create_test_event(i)],
+                0,
+                TransactionStatus::Keep(KeptVMStatus::Executed),
+            );
+            transactions_and_outputs.push((create_test_transaction(i), output));
+        }
+
+        Ok(TransactionAndOutputListWithProof::new(
+            transactions_and_outputs,
+            Some(start_version),
+            TransactionInfoListWithProof::new_empty(),
+        ))
+    }
+
     /// Returns events by given event key
     fn get_events(
         &self,
@@ -509,6 +911,32 @@ impl DbReader<DpnProto> for MockDbReaderWriter {
         unimplemented!()
     }
 
+    fn get_account_states_range(
+        &self,
+        _version: Version,
+        start_key: HashValue,
+        limit: u64,
+    ) -> Result<AccountStatesRangeWithProof> {
+        // Create mock account states, starting at the start key
+        let mut account_blobs = vec![];
+        let mut key = start_key;
+        for i in 0..limit {
+            account_blobs.push((key, AccountStateBlob::from(bcs::to_bytes(&i).unwrap())));
+            key = HashValue::sha3_256_of(key.as_ref());
+        }
+        let proof = if account_blobs.is_empty() {
+            None
+        } else {
+            Some(SparseMerkleRangeProof::new(vec![]))
+        };
+
+        Ok(AccountStatesRangeWithProof {
+            account_blobs,
+            proof,
+            next_key: None,
+        })
+    }
+
     fn get_epoch_ending_ledger_info(
         &self,
         _known_version: u64,
@@ -524,6 +952,6 @@ impl DbWriter<DpnProto> for MockDbReaderWriter {
         _first_version: Version,
         _ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
     ) -> Result<()> {
-        unimplemented!()
+        Ok(())
     }
 }
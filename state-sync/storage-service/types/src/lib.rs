@@ -9,16 +9,26 @@ use diem_types::{
     epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
     transaction::{
-        default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
+        default_protocol::{TransactionAndOutputListWithProof, TransactionListWithProof},
         Version,
     },
 };
+use serde::{Deserialize, Serialize};
+
+/// A storage service message, sent over the network as an RPC: either a
+/// request, or the response to one.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StorageServiceMessage {
+    Request(StorageServiceRequest),
+    Response(StorageServiceResponse),
+}
 
 /// A storage service request.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum StorageServiceRequest {
     GetAccountStatesChunkWithProof(AccountStatesChunkWithProofRequest), // Fetches a list of account states with a proof
     GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest), // Fetches a list of epoch ending ledger infos
+    GetNewTransactionsWithProof(NewTransactionsWithProofRequest), // Waits for (and fetches) transactions after a known version
     GetNumberOfAccountsAtVersion(Version), // Fetches the number of accounts at the specified version
     GetServerProtocolVersion,              // Fetches the protocol version run by the server
     GetStorageServerSummary,               // Fetches a summary of the storage server state
@@ -26,22 +36,47 @@ pub enum StorageServiceRequest {
     GetTransactionsWithProof(TransactionsWithProofRequest), // Fetches a list of transactions with a proof
 }
 
+impl StorageServiceRequest {
+    /// Returns a summary label for the request (e.g., for metrics).
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            StorageServiceRequest::GetAccountStatesChunkWithProof(_) => {
+                "get_account_states_chunk_with_proof"
+            }
+            StorageServiceRequest::GetEpochEndingLedgerInfos(_) => "get_epoch_ending_ledger_infos",
+            StorageServiceRequest::GetNewTransactionsWithProof(_) => {
+                "get_new_transactions_with_proof"
+            }
+            StorageServiceRequest::GetNumberOfAccountsAtVersion(_) => {
+                "get_number_of_accounts_at_version"
+            }
+            StorageServiceRequest::GetServerProtocolVersion => "get_server_protocol_version",
+            StorageServiceRequest::GetStorageServerSummary => "get_storage_server_summary",
+            StorageServiceRequest::GetTransactionOutputsWithProof(_) => {
+                "get_transaction_outputs_with_proof"
+            }
+            StorageServiceRequest::GetTransactionsWithProof(_) => "get_transactions_with_proof",
+        }
+    }
+}
+
 /// A storage service response.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum StorageServiceResponse {
     AccountStatesChunkWithProof(AccountStatesChunkWithProof),
     EpochEndingLedgerInfos(EpochChangeProof),
+    NewTransactionsWithProof((TransactionListWithProof, LedgerInfoWithSignatures)),
     NumberOfAccountsAtVersion(u64),
     ServerProtocolVersion(ServerProtocolVersion),
     StorageServiceError(StorageServiceError),
     StorageServerSummary(StorageServerSummary),
-    TransactionOutputsWithProof(TransactionOutputListWithProof),
+    TransactionOutputsWithProof(TransactionAndOutputListWithProof),
     TransactionsWithProof(TransactionListWithProof),
 }
 
 /// A storage service request for fetching a list of account states at a
 /// specified version.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct AccountStatesChunkWithProofRequest {
     pub version: u64,                     // The version to fetch the account states at
     pub start_account_key: HashValue,     // The account key to start fetching account states
@@ -50,7 +85,7 @@ pub struct AccountStatesChunkWithProofRequest {
 
 /// A storage service request for fetching a transaction output list with a
 /// corresponding proof.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TransactionOutputsWithProofRequest {
     pub proof_version: u64,        // The version the proof should be relative to
     pub start_version: u64,        // The starting version of the transaction output list
@@ -59,7 +94,7 @@ pub struct TransactionOutputsWithProofRequest {
 
 /// A storage service request for fetching a transaction list with a
 /// corresponding proof.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TransactionsWithProofRequest {
     pub proof_version: u64, // The version the proof should be relative to
     pub start_version: u64, // The starting version of the transaction list
@@ -67,8 +102,20 @@ pub struct TransactionsWithProofRequest {
     pub include_events: bool, // Whether or not to include events in the response
 }
 
+/// A storage service request for fetching the transactions after a known
+/// version, along with the ledger info they are proven by. If the server has
+/// no new transactions yet, it holds the request (for at most `max_wait_ms`)
+/// until it does. If none arrive in time, the response holds no transactions.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct NewTransactionsWithProofRequest {
+    pub known_version: u64,   // The highest version known by the client
+    pub known_epoch: u64,     // The highest epoch known by the client
+    pub include_events: bool, // Whether or not to include events in the response
+    pub max_wait_ms: u64,     // The maximum time to wait for new transactions
+}
+
 /// A storage service request for fetching a list of epoch ending ledger infos.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct EpochEndingLedgerInfoRequest {
     pub start_epoch: u64,
     pub expected_end_epoch: u64,
@@ -76,14 +123,16 @@ pub struct EpochEndingLedgerInfoRequest {
 
 /// A storage service error that can be returned to the client on a failure
 /// to process a service request.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum StorageServiceError {
     InternalError,
+    TooManyPendingRequests, // The peer has too many requests waiting for new data
+    TooManyRequests,        // The peer exceeded the rate limit of the server
 }
 
 /// The protocol version run by this server. Clients request this first to
 /// identify what API calls and data requests the server supports.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ServerProtocolVersion {
     pub protocol_version: u64, // The storage server version run by this instance.
 }
@@ -91,7 +140,7 @@ pub struct ServerProtocolVersion {
 /// A storage server summary, containing a summary of the information held
 /// by the corresponding server instance. This is useful for identifying the
 /// data that a server instance can provide, as well as relevant metadata.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct StorageServerSummary {
     pub protocol_metadata: ProtocolMetadata,
     pub data_summary: DataSummary,
@@ -99,7 +148,7 @@ pub struct StorageServerSummary {
 
 /// A summary of the protocol metadata for the storage service instance, such as
 /// the maximum chunk sizes supported for different requests.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ProtocolMetadata {
     pub max_epoch_chunk_size: u64, // The max number of epochs the server can return in a single chunk
     pub max_transaction_chunk_size: u64, // The max number of transactions the server can return in a single chunk
@@ -111,7 +160,7 @@ pub struct ProtocolMetadata {
 pub type Epoch = u64;
 
 /// A summary of the data actually held by the storage service instance.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct DataSummary {
     /// The ledger info corresponding to the highest synced version in storage.
     /// This indicates the highest version and epoch that storage can prove.
@@ -137,7 +186,7 @@ pub struct DataSummary {
 /// is complete (i.e. there are no missing pieces of data).
 /// This is used to provide a summary of the data currently held in storage, e.g.
 /// a CompleteDataRange<Version> of (A,B) means all versions A->B (inclusive).
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CompleteDataRange<T> {
     pub lowest: T,
     pub highest: T,
@@ -148,3 +197,10 @@ impl<T> CompleteDataRange<T> {
         Self { lowest, highest }
     }
 }
+
+impl<T: PartialOrd> CompleteDataRange<T> {
+    /// Returns true iff the given item is within this range (inclusive).
+    pub fn contains(&self, item: T) -> bool {
+        self.lowest <= item && item <= self.highest
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
+use diemdb::DiemDB;
+use rand::Rng;
+use rayon::prelude::*;
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use storage_interface::DbReader;
+
+/// Measures the latency of transaction accumulator range proofs served by a DB populated by, e.g.,
+/// `diemdb-benchmark`, with `concurrency` readers issuing overlapping requests.
+///
+/// Frozen accumulator nodes are cached in memory once read, so rounds after the first one show the
+/// latency with warm caches.
+pub fn run_benchmark(
+    num_requests: usize,
+    num_rounds: usize,
+    chunk_size: u64,
+    window: u64,
+    concurrency: usize,
+    db_dir: PathBuf,
+) {
+    assert!(chunk_size > 0, "chunk_size must be positive.");
+    let db = DiemDB::open(
+        &db_dir,
+        true, /* readonly */
+        None, /* pruner */
+        None, /* ledger_pruner */
+        RocksdbConfig::default(),
+        SecondaryIndexesConfig::default(),
+    )
+    .expect("DB should open.");
+    let backup_handler = db.get_backup_handler();
+
+    let latest_version = db.get_latest_version().expect("DB should not be empty.");
+    assert!(
+        latest_version + 1 >= chunk_size,
+        "DB has only {} transactions, fewer than chunk_size {}.",
+        latest_version + 1,
+        chunk_size,
+    );
+    let min_first_version = (latest_version + 1).saturating_sub(window);
+    let max_first_version = latest_version + 1 - chunk_size;
+    let min_first_version = std::cmp::min(min_first_version, max_first_version);
+    println!(
+        "Requesting range proofs of {} transactions in versions [{}, {}].",
+        chunk_size, min_first_version, latest_version,
+    );
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(concurrency)
+        .thread_name(|index| format!("accumulator-bench-{}", index))
+        .build()
+        .expect("Failed to build rayon thread pool.");
+
+    for round in 0..num_rounds {
+        let start = Instant::now();
+        let mut latencies = pool.install(|| {
+            (0..num_requests)
+                .into_par_iter()
+                .map_init(rand::thread_rng, |rng, _| {
+                    let first_version = rng.gen_range(min_first_version..=max_first_version);
+                    let last_version = first_version + chunk_size - 1;
+                    let request_start = Instant::now();
+                    backup_handler
+                        .get_transaction_range_proof(first_version, last_version)
+                        .expect("Range proof should be available.");
+                    request_start.elapsed()
+                })
+                .collect::<Vec<_>>()
+        });
+        let elapsed = start.elapsed();
+        latencies.sort_unstable();
+
+        println!(
+            "Round {}: {} requests in {}ms ({:.0} req/s), latency mean {}us, p50 {}us, p99 {}us, max {}us",
+            round,
+            num_requests,
+            elapsed.as_millis(),
+            num_requests as f64 / elapsed.as_secs_f64(),
+            mean(&latencies).as_micros(),
+            percentile(&latencies, 50).as_micros(),
+            percentile(&latencies, 99).as_micros(),
+            latencies.last().copied().unwrap_or_default().as_micros(),
+        );
+    }
+}
+
+fn mean(sorted_latencies: &[Duration]) -> Duration {
+    if sorted_latencies.is_empty() {
+        return Duration::default();
+    }
+    sorted_latencies.iter().sum::<Duration>() / sorted_latencies.len() as u32
+}
+
+fn percentile(sorted_latencies: &[Duration], p: usize) -> Duration {
+    if sorted_latencies.is_empty() {
+        return Duration::default();
+    }
+    let idx = (sorted_latencies.len() - 1) * p / 100;
+    sorted_latencies[idx]
+}
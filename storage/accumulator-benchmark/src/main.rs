@@ -0,0 +1,43 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+struct Opt {
+    /// Number of proof requests in each round.
+    #[structopt(long, default_value = "10000")]
+    num_requests: usize,
+
+    /// Number of rounds. The first round starts with cold caches.
+    #[structopt(long, default_value = "3")]
+    num_rounds: usize,
+
+    /// Number of transactions covered by each range proof.
+    #[structopt(long, default_value = "100")]
+    chunk_size: u64,
+
+    /// Requests are spread over the latest `window` transactions, so they overlap like requests
+    /// from peers syncing to the head of the chain do.
+    #[structopt(long, default_value = "100000")]
+    window: u64,
+
+    #[structopt(long, default_value = "8")]
+    concurrency: usize,
+
+    #[structopt(long, parse(from_os_str))]
+    db_dir: PathBuf,
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    accumulator_benchmark::run_benchmark(
+        opt.num_requests,
+        opt.num_rounds,
+        opt.chunk_size,
+        opt.window,
+        opt.concurrency,
+        opt.db_dir,
+    );
+}
@@ -0,0 +1,90 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides `CachedHashReader`, a `HashReader` that keeps recently read nodes of a
+//! Merkle Accumulator in memory.
+//!
+//! Since the physical representation of the accumulator is append-only, a frozen node never
+//! changes once it is persisted, and neither does the list of frozen subtree roots (the
+//! "summary") of the accumulator at a given leaf count. Both can be cached without ever being
+//! invalidated, which saves reading the same nodes from the underlying storage over and over when
+//! serving many overlapping proof requests.
+
+use crate::HashReader;
+use anyhow::Result;
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_types::proof::{
+    definition::LeafCount,
+    position::{FrozenSubTreeIterator, Position},
+};
+use lru::LruCache;
+use std::fmt;
+
+/// A `HashReader` that fronts another `HashReader` with LRU caches of frozen nodes and of frozen
+/// subtree root hashes keyed by leaf count.
+pub struct CachedHashReader<R> {
+    reader: R,
+    frozen_nodes: Mutex<LruCache<Position, HashValue>>,
+    frozen_subtrees: Mutex<LruCache<LeafCount, Vec<HashValue>>>,
+}
+
+impl<R> CachedHashReader<R>
+where
+    R: HashReader,
+{
+    /// Wraps `reader`, caching at most `node_cache_size` frozen nodes and the frozen subtree root
+    /// hashes of at most `summary_cache_size` distinct leaf counts.
+    pub fn new(reader: R, node_cache_size: usize, summary_cache_size: usize) -> Self {
+        Self {
+            reader,
+            frozen_nodes: Mutex::new(LruCache::new(node_cache_size)),
+            frozen_subtrees: Mutex::new(LruCache::new(summary_cache_size)),
+        }
+    }
+
+    /// Returns the underlying reader, bypassing the caches.
+    pub fn inner(&self) -> &R {
+        &self.reader
+    }
+}
+
+impl<R> HashReader for CachedHashReader<R>
+where
+    R: HashReader,
+{
+    fn get(&self, position: Position) -> Result<HashValue> {
+        if let Some(hash) = self.frozen_nodes.lock().get(&position) {
+            return Ok(*hash);
+        }
+        // Read without holding the lock so concurrent readers missing on different positions
+        // don't serialize on storage reads.
+        let hash = self.reader.get(position)?;
+        self.frozen_nodes.lock().put(position, hash);
+        Ok(hash)
+    }
+
+    fn get_frozen_subtree_hashes(&self, num_leaves: LeafCount) -> Result<Vec<HashValue>> {
+        if let Some(hashes) = self.frozen_subtrees.lock().get(&num_leaves) {
+            return Ok(hashes.clone());
+        }
+        let hashes = FrozenSubTreeIterator::new(num_leaves)
+            .map(|p| self.get(p))
+            .collect::<Result<Vec<_>>>()?;
+        self.frozen_subtrees.lock().put(num_leaves, hashes.clone());
+        Ok(hashes)
+    }
+}
+
+impl<R> fmt::Debug for CachedHashReader<R>
+where
+    R: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CachedHashReader")
+            .field("reader", &self.reader)
+            .field("num_cached_nodes", &self.frozen_nodes.lock().len())
+            .field("num_cached_summaries", &self.frozen_subtrees.lock().len())
+            .finish()
+    }
+}
@@ -103,9 +103,12 @@
 //! |  ...  |   ...     |
 //! ```
 
+mod cache;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod test_helpers;
 
+pub use cache::CachedHashReader;
+
 use anyhow::{ensure, format_err, Result};
 use diem_crypto::hash::{CryptoHash, CryptoHasher, HashValue, ACCUMULATOR_PLACEHOLDER_HASH};
 use diem_types::proof::{
@@ -120,6 +123,15 @@ use std::marker::PhantomData;
 pub trait HashReader {
     /// Return `HashValue` carried by the node at `Position`.
     fn get(&self, position: Position) -> Result<HashValue>;
+
+    /// Return, from left to right, the root hashes of the frozen subtrees of an accumulator with
+    /// `num_leaves` leaves. Readers that keep these around can override this to avoid reading
+    /// every subtree root individually.
+    fn get_frozen_subtree_hashes(&self, num_leaves: LeafCount) -> Result<Vec<HashValue>> {
+        FrozenSubTreeIterator::new(num_leaves)
+            .map(|p| self.get(p))
+            .collect::<Result<Vec<_>>>()
+    }
 }
 
 /// A `Node` in a `MerkleAccumulator` tree is a `HashValue` at a `Position`
@@ -460,9 +472,7 @@ where
 
     /// Implementation for public interface `MerkleAccumulator::get_frozen_subtree_hashes`.
     fn get_frozen_subtree_hashes(&self) -> Result<Vec<HashValue>> {
-        FrozenSubTreeIterator::new(self.num_leaves)
-            .map(|p| self.reader.get(p))
-            .collect::<Result<Vec<_>>>()
+        self.reader.get_frozen_subtree_hashes(self.num_leaves)
     }
 }
 
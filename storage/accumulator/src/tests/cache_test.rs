@@ -0,0 +1,114 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::test_helpers::{arb_three_hash_batches, MockHashStore, TestAccumulator};
+use diem_crypto::hash::TestOnlyHasher;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+type CachedTestAccumulator = MerkleAccumulator<CachedHashReader<CountingHashStore>, TestOnlyHasher>;
+
+struct CountingHashStore {
+    store: MockHashStore,
+    num_reads: AtomicUsize,
+}
+
+impl CountingHashStore {
+    fn new(store: MockHashStore) -> Self {
+        Self {
+            store,
+            num_reads: AtomicUsize::new(0),
+        }
+    }
+
+    fn num_reads(&self) -> usize {
+        self.num_reads.load(Ordering::SeqCst)
+    }
+}
+
+impl HashReader for CountingHashStore {
+    fn get(&self, position: Position) -> Result<HashValue> {
+        self.num_reads.fetch_add(1, Ordering::SeqCst);
+        self.store.get(position)
+    }
+}
+
+fn cached_store(leaves: &[HashValue]) -> (HashValue, CachedHashReader<CountingHashStore>) {
+    let mut store = MockHashStore::new();
+    let (root_hash, writes) = TestAccumulator::append(&store, 0, leaves).unwrap();
+    store.put_many(&writes);
+    (
+        root_hash,
+        CachedHashReader::new(CountingHashStore::new(store), 1024, 16),
+    )
+}
+
+#[test]
+fn test_cached_reads_not_repeated() {
+    let leaves: Vec<_> = (0..100).map(|_| HashValue::random()).collect();
+    let (root_hash, reader) = cached_store(&leaves);
+    let num_leaves = leaves.len() as LeafCount;
+
+    let proof = CachedTestAccumulator::get_range_proof(&reader, num_leaves, Some(10), 20).unwrap();
+    proof.verify(root_hash, Some(10), &leaves[10..30]).unwrap();
+    let num_reads = reader.inner().num_reads();
+    assert!(num_reads > 0);
+
+    // Same request again is served entirely from the cache.
+    let proof = CachedTestAccumulator::get_range_proof(&reader, num_leaves, Some(10), 20).unwrap();
+    proof.verify(root_hash, Some(10), &leaves[10..30]).unwrap();
+    assert_eq!(reader.inner().num_reads(), num_reads);
+
+    // So is the summary, once computed.
+    let frozen_subtree_hashes =
+        CachedTestAccumulator::get_frozen_subtree_hashes(&reader, num_leaves).unwrap();
+    let num_reads = reader.inner().num_reads();
+    assert_eq!(
+        CachedTestAccumulator::get_frozen_subtree_hashes(&reader, num_leaves).unwrap(),
+        frozen_subtree_hashes,
+    );
+    assert_eq!(reader.inner().num_reads(), num_reads);
+}
+
+#[test]
+fn test_missing_node_not_cached() {
+    let reader = CachedHashReader::new(CountingHashStore::new(MockHashStore::new()), 16, 16);
+    assert!(reader.get(Position::from_inorder_index(0)).is_err());
+    assert!(reader.get(Position::from_inorder_index(0)).is_err());
+    assert_eq!(reader.inner().num_reads(), 2);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_cached_range_proof((batch1, batch2, batch3) in arb_three_hash_batches(100)) {
+        let mut leaves = batch1.clone();
+        leaves.extend_from_slice(&batch2);
+        leaves.extend_from_slice(&batch3);
+        let (root_hash, reader) = cached_store(&leaves);
+        let num_leaves = leaves.len() as LeafCount;
+
+        let first_leaf_index = if batch2.is_empty() {
+            None
+        } else {
+            Some(batch1.len() as u64)
+        };
+        // Ask twice so the second proof is built from cached nodes.
+        for _ in 0..2 {
+            let proof = CachedTestAccumulator::get_range_proof(
+                &reader,
+                num_leaves,
+                first_leaf_index,
+                batch2.len() as LeafCount,
+            )
+            .unwrap();
+            proof.verify(root_hash, first_leaf_index, &batch2).unwrap();
+            prop_assert_eq!(
+                CachedTestAccumulator::get_frozen_subtree_hashes(&reader, num_leaves).unwrap(),
+                TestAccumulator::get_frozen_subtree_hashes(&reader.inner().store, num_leaves)
+                    .unwrap()
+            );
+        }
+    }
+}
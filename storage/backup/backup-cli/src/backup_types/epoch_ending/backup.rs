@@ -6,8 +6,12 @@ use crate::{
     metadata::Metadata,
     storage::{BackupHandleRef, BackupStorage, FileHandle, ShellSafeName},
     utils::{
-        backup_service_client::BackupServiceClient, read_record_bytes::ReadRecordBytes,
-        should_cut_chunk, storage_ext::BackupStorageExt, GlobalBackupOpt,
+        backup_service_client::BackupServiceClient,
+        encryption::{EncryptionInfo, EncryptionKey, EncryptionOpt},
+        read_record_bytes::ReadRecordBytes,
+        should_cut_chunk,
+        storage_ext::BackupStorageExt,
+        GlobalBackupOpt,
     },
 };
 use anyhow::{anyhow, ensure, Result};
@@ -34,6 +38,7 @@ pub struct EpochEndingBackupController {
     start_epoch: u64,
     end_epoch: u64,
     max_chunk_size: usize,
+    encryption: EncryptionOpt,
     client: Arc<BackupServiceClient>,
     storage: Arc<dyn BackupStorage>,
 }
@@ -49,6 +54,7 @@ impl EpochEndingBackupController {
             start_epoch: opt.start_epoch,
             end_epoch: opt.end_epoch,
             max_chunk_size: global_opt.max_chunk_size,
+            encryption: global_opt.encryption,
             client,
             storage,
         }
@@ -71,6 +77,7 @@ impl EpochEndingBackupController {
 
 impl EpochEndingBackupController {
     async fn run_impl(self) -> Result<FileHandle> {
+        let encryption_key = self.encryption.load_key()?;
         let backup_handle = self
             .storage
             .create_backup_with_random_suffix(&self.backup_name())
@@ -95,6 +102,7 @@ impl EpochEndingBackupController {
                         &chunk_bytes,
                         chunk_first_epoch,
                         current_epoch - 1,
+                        encryption_key.as_ref(),
                     )
                     .await?;
                 chunks.push(chunk);
@@ -116,11 +124,13 @@ impl EpochEndingBackupController {
                 &chunk_bytes,
                 chunk_first_epoch,
                 current_epoch - 1,
+                encryption_key.as_ref(),
             )
             .await?;
         chunks.push(chunk);
 
-        self.write_manifest(&backup_handle, waypoints, chunks).await
+        self.write_manifest(&backup_handle, waypoints, chunks, encryption_key.as_ref())
+            .await
     }
 
     fn backup_name(&self) -> String {
@@ -154,10 +164,15 @@ impl EpochEndingBackupController {
         chunk_bytes: &[u8],
         first_epoch: u64,
         last_epoch: u64,
+        encryption_key: Option<&Arc<EncryptionKey>>,
     ) -> Result<EpochEndingChunk> {
         let (chunk_handle, mut chunk_file) = self
             .storage
-            .create_for_write(backup_handle, &Self::chunk_name(first_epoch))
+            .create_for_write_sealed(
+                backup_handle,
+                &Self::chunk_name(first_epoch),
+                encryption_key,
+            )
             .await?;
         chunk_file.write_all(chunk_bytes).await?;
         chunk_file.shutdown().await?;
@@ -173,6 +188,7 @@ impl EpochEndingBackupController {
         backup_handle: &BackupHandleRef,
         waypoints: Vec<Waypoint>,
         chunks: Vec<EpochEndingChunk>,
+        encryption_key: Option<&Arc<EncryptionKey>>,
     ) -> Result<FileHandle> {
         let first_epoch = self.start_epoch;
         let last_epoch = self.end_epoch - 1;
@@ -182,6 +198,7 @@ impl EpochEndingBackupController {
             last_epoch,
             waypoints,
             chunks,
+            encryption: encryption_key.map(|key| EncryptionInfo::new(key)),
         };
         let (manifest_handle, mut manifest_file) = self
             .storage
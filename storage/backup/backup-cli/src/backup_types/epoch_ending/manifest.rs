@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::storage::FileHandle;
+use crate::{storage::FileHandle, utils::encryption::EncryptionInfo};
 use anyhow::{ensure, Result};
 use diem_types::waypoint::Waypoint;
 use serde::{Deserialize, Serialize};
@@ -23,6 +23,9 @@ pub struct EpochEndingBackup {
     pub last_epoch: u64,
     pub waypoints: Vec<Waypoint>,
     pub chunks: Vec<EpochEndingChunk>,
+    /// Present if the data and proof files are sealed, see `utils::encryption`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionInfo>,
 }
 
 impl EpochEndingBackup {
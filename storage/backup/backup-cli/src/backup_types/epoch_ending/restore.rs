@@ -9,7 +9,10 @@ use crate::{
     },
     storage::{BackupStorage, FileHandle, FileHandleRef},
     utils::{
-        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, stream::StreamX,
+        encryption::{EncryptionInfo, EncryptionKey},
+        read_record_bytes::ReadRecordBytes,
+        storage_ext::BackupStorageExt,
+        stream::StreamX,
         GlobalRestoreOptions, RestoreRunMode,
     },
 };
@@ -37,6 +40,8 @@ pub struct EpochEndingRestoreController {
     manifest_handle: FileHandle,
     target_version: Version,
     trusted_waypoints: Arc<HashMap<Version, Waypoint>>,
+    encryption_key: Option<Arc<EncryptionKey>>,
+    allow_plaintext: bool,
 }
 
 impl EpochEndingRestoreController {
@@ -51,6 +56,8 @@ impl EpochEndingRestoreController {
             manifest_handle: opt.manifest_handle,
             target_version: global_opt.target_version,
             trusted_waypoints: global_opt.trusted_waypoints,
+            encryption_key: global_opt.encryption_key,
+            allow_plaintext: global_opt.allow_plaintext,
         }
     }
 
@@ -81,6 +88,11 @@ impl EpochEndingRestoreController {
         let manifest: EpochEndingBackup =
             self.storage.load_json_file(&self.manifest_handle).await?;
         manifest.verify()?;
+        let encryption_key = EncryptionInfo::key_for(
+            manifest.encryption.as_ref(),
+            self.encryption_key.as_ref(),
+            self.allow_plaintext,
+        )?;
 
         let mut next_epoch = manifest.first_epoch;
         let mut waypoint_iter = manifest.waypoints.iter();
@@ -94,7 +106,9 @@ impl EpochEndingRestoreController {
                 break;
             }
 
-            let lis = self.read_chunk(&chunk.ledger_infos).await?;
+            let lis = self
+                .read_chunk(&chunk.ledger_infos, encryption_key.as_ref())
+                .await?;
             ensure!(
                 chunk.first_epoch + lis.len() as u64 == chunk.last_epoch + 1,
                 "Number of items in chunks doesn't match that in manifest. \
@@ -160,8 +174,12 @@ impl EpochEndingRestoreController {
     async fn read_chunk(
         &self,
         file_handle: &FileHandleRef,
+        encryption_key: Option<&Arc<EncryptionKey>>,
     ) -> Result<Vec<LedgerInfoWithSignatures>> {
-        let mut file = self.storage.open_for_read(file_handle).await?;
+        let mut file = self
+            .storage
+            .open_for_read_sealed(file_handle, encryption_key)
+            .await?;
         let mut chunk = vec![];
 
         while let Some(record_bytes) = file.read_record_bytes().await? {
@@ -10,12 +10,16 @@ use crate::{
     },
     storage::{local_fs::LocalFs, BackupStorage},
     utils::{
-        backup_service_client::BackupServiceClient, test_utils::tmp_db_with_random_content,
-        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, RocksdbOpt, TrustedWaypointOpt,
+        backup_service_client::BackupServiceClient, encryption::EncryptionOpt,
+        test_utils::tmp_db_with_random_content, ConcurrentDownloadsOpt, GlobalBackupOpt,
+        GlobalRestoreOpt, MaxChunksInFlightOpt, RocksdbOpt, TrustedWaypointOpt,
     },
 };
 use backup_service::start_backup_service;
-use diem_config::{config::RocksdbConfig, utils::get_available_port};
+use diem_config::{
+    config::{RocksdbConfig, SecondaryIndexesConfig},
+    utils::get_available_port,
+};
 use diem_temppath::TempPath;
 use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
@@ -36,7 +40,7 @@ use warp::Filter;
 
 #[test]
 fn end_to_end() {
-    let (_src_db_dir, src_db, blocks) = tmp_db_with_random_content();
+    let (src_db, blocks) = tmp_db_with_random_content();
     let tgt_db_dir = TempPath::new();
     tgt_db_dir.create_as_dir().unwrap();
 
@@ -65,6 +69,7 @@ fn end_to_end() {
                 },
                 GlobalBackupOpt {
                     max_chunk_size: 1024,
+                    encryption: EncryptionOpt::default(),
                 },
                 client,
                 Arc::clone(&store),
@@ -83,6 +88,8 @@ fn end_to_end() {
                 trusted_waypoints: TrustedWaypointOpt::default(),
                 rocksdb_opt: RocksdbOpt::default(),
                 concurernt_downloads: ConcurrentDownloadsOpt::default(),
+                max_chunks_in_flight: MaxChunksInFlightOpt::default(),
+                encryption: EncryptionOpt::default(),
             }
             .try_into()
             .unwrap(),
@@ -106,7 +113,9 @@ fn end_to_end() {
         &tgt_db_dir,
         true, /* read_only */
         None, /* pruner */
+        None, /* ledger_pruner */
         RocksdbConfig::default(),
+        SecondaryIndexesConfig::default(),
     )
     .unwrap();
     assert_eq!(
@@ -200,6 +209,7 @@ async fn test_trusted_waypoints_impl(
             },
             GlobalBackupOpt {
                 max_chunk_size: 1024,
+                encryption: EncryptionOpt::default(),
             },
             client.clone(),
             Arc::clone(&store),
@@ -220,6 +230,8 @@ async fn test_trusted_waypoints_impl(
             trusted_waypoints: TrustedWaypointOpt::default(),
             rocksdb_opt: RocksdbOpt::default(),
             concurernt_downloads: ConcurrentDownloadsOpt::default(),
+            max_chunks_in_flight: MaxChunksInFlightOpt::default(),
+            encryption: EncryptionOpt::default(),
         }
         .try_into()
         .unwrap(),
@@ -240,6 +252,8 @@ async fn test_trusted_waypoints_impl(
             },
             rocksdb_opt: RocksdbOpt::default(),
             concurernt_downloads: ConcurrentDownloadsOpt::default(),
+            max_chunks_in_flight: MaxChunksInFlightOpt::default(),
+            encryption: EncryptionOpt::default(),
         }
         .try_into()
         .unwrap(),
@@ -2,15 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    backup_types::state_snapshot::manifest::{StateSnapshotBackup, StateSnapshotChunk},
+    backup_types::state_snapshot::manifest::{
+        IncrementalStateSnapshotBackup, IncrementalStateSnapshotChunk, StateSnapshotBackup,
+        StateSnapshotChunk,
+    },
     metadata::Metadata,
     storage::{BackupHandleRef, BackupStorage, FileHandle, ShellSafeName},
     utils::{
-        backup_service_client::BackupServiceClient, read_record_bytes::ReadRecordBytes,
-        should_cut_chunk, storage_ext::BackupStorageExt, GlobalBackupOpt,
+        backup_service_client::BackupServiceClient,
+        encryption::{EncryptionInfo, EncryptionKey, EncryptionOpt},
+        read_record_bytes::ReadRecordBytes,
+        should_cut_chunk,
+        storage_ext::BackupStorageExt,
+        GlobalBackupOpt,
     },
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, ensure, Result};
 use bytes::Bytes;
 use diem_crypto::HashValue;
 use diem_logger::prelude::*;
@@ -37,6 +44,7 @@ pub struct StateSnapshotBackupOpt {
 pub struct StateSnapshotBackupController {
     version: Version,
     max_chunk_size: usize,
+    encryption: EncryptionOpt,
     client: Arc<BackupServiceClient>,
     storage: Arc<dyn BackupStorage>,
 }
@@ -51,6 +59,7 @@ impl StateSnapshotBackupController {
         Self {
             version: opt.version,
             max_chunk_size: global_opt.max_chunk_size,
+            encryption: global_opt.encryption,
             client,
             storage,
         }
@@ -70,6 +79,7 @@ impl StateSnapshotBackupController {
     }
 
     async fn run_impl(self) -> Result<FileHandle> {
+        let encryption_key = self.encryption.load_key()?;
         let backup_handle = self
             .storage
             .create_backup_with_random_suffix(&self.backup_name())
@@ -98,6 +108,7 @@ impl StateSnapshotBackupController {
                         current_idx,
                         chunk_first_key,
                         Self::parse_key(&prev_record_bytes)?,
+                        encryption_key.as_ref(),
                     )
                     .await?;
                 chunks.push(chunk);
@@ -121,11 +132,13 @@ impl StateSnapshotBackupController {
                 current_idx,
                 chunk_first_key,
                 Self::parse_key(&prev_record_bytes)?,
+                encryption_key.as_ref(),
             )
             .await?;
         chunks.push(chunk);
 
-        self.write_manifest(&backup_handle, chunks).await
+        self.write_manifest(&backup_handle, chunks, encryption_key.as_ref())
+            .await
     }
 }
 
@@ -169,16 +182,21 @@ impl StateSnapshotBackupController {
         last_idx: usize,
         first_key: HashValue,
         last_key: HashValue,
+        encryption_key: Option<&Arc<EncryptionKey>>,
     ) -> Result<StateSnapshotChunk> {
         let (chunk_handle, mut chunk_file) = self
             .storage
-            .create_for_write(backup_handle, &Self::chunk_name(first_idx))
+            .create_for_write_sealed(backup_handle, &Self::chunk_name(first_idx), encryption_key)
             .await?;
         chunk_file.write_all(chunk_bytes).await?;
         chunk_file.shutdown().await?;
         let (proof_handle, mut proof_file) = self
             .storage
-            .create_for_write(backup_handle, &Self::chunk_proof_name(first_idx, last_idx))
+            .create_for_write_sealed(
+                backup_handle,
+                &Self::chunk_proof_name(first_idx, last_idx),
+                encryption_key,
+            )
             .await?;
         tokio::io::copy(
             &mut self
@@ -204,6 +222,7 @@ impl StateSnapshotBackupController {
         &self,
         backup_handle: &BackupHandleRef,
         chunks: Vec<StateSnapshotChunk>,
+        encryption_key: Option<&Arc<EncryptionKey>>,
     ) -> Result<FileHandle> {
         let proof_bytes = self.client.get_state_root_proof(self.version).await?;
         let (txn_info, _): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
@@ -211,7 +230,7 @@ impl StateSnapshotBackupController {
 
         let (proof_handle, mut proof_file) = self
             .storage
-            .create_for_write(backup_handle, Self::proof_name())
+            .create_for_write_sealed(backup_handle, Self::proof_name(), encryption_key)
             .await?;
         proof_file.write_all(&proof_bytes).await?;
         proof_file.shutdown().await?;
@@ -221,6 +240,7 @@ impl StateSnapshotBackupController {
             root_hash: txn_info.transaction_info().state_root_hash(),
             chunks,
             proof: proof_handle,
+            encryption: encryption_key.map(|key| EncryptionInfo::new(key)),
         };
 
         let (manifest_handle, mut manifest_file) = self
@@ -240,3 +260,226 @@ impl StateSnapshotBackupController {
         Ok(manifest_handle)
     }
 }
+
+#[derive(StructOpt)]
+pub struct IncrementalStateSnapshotBackupOpt {
+    #[structopt(
+        long = "base-state-version",
+        help = "Version of an existing state snapshot (full or incremental) to base on."
+    )]
+    pub base_version: Version,
+    #[structopt(
+        long = "state-version",
+        help = "Version at which a state snapshot to be taken."
+    )]
+    pub version: Version,
+}
+
+/// Backs up the accounts changed between two versions, see `IncrementalStateSnapshotBackup`.
+pub struct IncrementalStateSnapshotBackupController {
+    base_version: Version,
+    version: Version,
+    max_chunk_size: usize,
+    encryption: EncryptionOpt,
+    client: Arc<BackupServiceClient>,
+    storage: Arc<dyn BackupStorage>,
+}
+
+impl IncrementalStateSnapshotBackupController {
+    pub fn new(
+        opt: IncrementalStateSnapshotBackupOpt,
+        global_opt: GlobalBackupOpt,
+        client: Arc<BackupServiceClient>,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Self {
+        Self {
+            base_version: opt.base_version,
+            version: opt.version,
+            max_chunk_size: global_opt.max_chunk_size,
+            encryption: global_opt.encryption,
+            client,
+            storage,
+        }
+    }
+
+    pub async fn run(self) -> Result<FileHandle> {
+        info!(
+            "Incremental state snapshot backup started, for version {} based on version {}.",
+            self.version, self.base_version,
+        );
+        let ret = self
+            .run_impl()
+            .await
+            .map_err(|e| anyhow!("Incremental state snapshot backup failed: {}", e))?;
+        info!(
+            "Incremental state snapshot backup succeeded. Manifest: {}",
+            ret
+        );
+        Ok(ret)
+    }
+
+    async fn run_impl(self) -> Result<FileHandle> {
+        ensure!(
+            self.base_version < self.version,
+            "Base version {} must be older than version {}.",
+            self.base_version,
+            self.version,
+        );
+        let encryption_key = self.encryption.load_key()?;
+        let backup_handle = self
+            .storage
+            .create_backup_with_random_suffix(&self.backup_name())
+            .await?;
+
+        let mut chunks = vec![];
+
+        let mut changes_file = self
+            .client
+            .get_state_snapshot_changes(self.base_version, self.version)
+            .await?;
+        let mut prev_record_bytes = changes_file
+            .read_record_bytes()
+            .await?
+            .ok_or_else(|| anyhow!("No state changes."))?;
+        let mut chunk_bytes = (prev_record_bytes.len() as u32).to_be_bytes().to_vec();
+        chunk_bytes.extend(&prev_record_bytes);
+        let mut chunk_first_key = StateSnapshotBackupController::parse_key(&prev_record_bytes)?;
+        let mut current_idx: usize = 0;
+        let mut chunk_first_idx: usize = 0;
+
+        while let Some(record_bytes) = changes_file.read_record_bytes().await? {
+            if should_cut_chunk(&chunk_bytes, &record_bytes, self.max_chunk_size) {
+                let chunk = self
+                    .write_chunk(
+                        &backup_handle,
+                        &chunk_bytes,
+                        chunk_first_idx,
+                        current_idx,
+                        chunk_first_key,
+                        StateSnapshotBackupController::parse_key(&prev_record_bytes)?,
+                        encryption_key.as_ref(),
+                    )
+                    .await?;
+                chunks.push(chunk);
+                chunk_bytes = vec![];
+                chunk_first_idx = current_idx + 1;
+                chunk_first_key = StateSnapshotBackupController::parse_key(&record_bytes)?;
+            }
+
+            current_idx += 1;
+            chunk_bytes.extend(&(record_bytes.len() as u32).to_be_bytes());
+            chunk_bytes.extend(&record_bytes);
+            prev_record_bytes = record_bytes;
+        }
+
+        assert!(!chunk_bytes.is_empty());
+        let chunk = self
+            .write_chunk(
+                &backup_handle,
+                &chunk_bytes,
+                chunk_first_idx,
+                current_idx,
+                chunk_first_key,
+                StateSnapshotBackupController::parse_key(&prev_record_bytes)?,
+                encryption_key.as_ref(),
+            )
+            .await?;
+        chunks.push(chunk);
+
+        self.write_manifest(&backup_handle, chunks, encryption_key.as_ref())
+            .await
+    }
+}
+
+impl IncrementalStateSnapshotBackupController {
+    fn backup_name(&self) -> String {
+        format!(
+            "incremental_state_ver_{}-{}",
+            self.base_version, self.version
+        )
+    }
+
+    async fn write_chunk(
+        &self,
+        backup_handle: &BackupHandleRef,
+        chunk_bytes: &[u8],
+        first_idx: usize,
+        last_idx: usize,
+        first_key: HashValue,
+        last_key: HashValue,
+        encryption_key: Option<&Arc<EncryptionKey>>,
+    ) -> Result<IncrementalStateSnapshotChunk> {
+        let (chunk_handle, mut chunk_file) = self
+            .storage
+            .create_for_write_sealed(
+                backup_handle,
+                &StateSnapshotBackupController::chunk_name(first_idx),
+                encryption_key,
+            )
+            .await?;
+        chunk_file.write_all(chunk_bytes).await?;
+        chunk_file.shutdown().await?;
+
+        Ok(IncrementalStateSnapshotChunk {
+            first_idx,
+            last_idx,
+            first_key,
+            last_key,
+            blobs: chunk_handle,
+        })
+    }
+
+    async fn write_manifest(
+        &self,
+        backup_handle: &BackupHandleRef,
+        chunks: Vec<IncrementalStateSnapshotChunk>,
+        encryption_key: Option<&Arc<EncryptionKey>>,
+    ) -> Result<FileHandle> {
+        let proof_bytes = self.client.get_state_root_proof(self.version).await?;
+        let (txn_info, _): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
+            bcs::from_bytes(&proof_bytes)?;
+
+        let (proof_handle, mut proof_file) = self
+            .storage
+            .create_for_write_sealed(
+                backup_handle,
+                StateSnapshotBackupController::proof_name(),
+                encryption_key,
+            )
+            .await?;
+        proof_file.write_all(&proof_bytes).await?;
+        proof_file.shutdown().await?;
+
+        let manifest = IncrementalStateSnapshotBackup {
+            base_version: self.base_version,
+            version: self.version,
+            root_hash: txn_info.transaction_info().state_root_hash(),
+            chunks,
+            proof: proof_handle,
+            encryption: encryption_key.map(|key| EncryptionInfo::new(key)),
+        };
+
+        let (manifest_handle, mut manifest_file) = self
+            .storage
+            .create_for_write(
+                backup_handle,
+                StateSnapshotBackupController::manifest_name(),
+            )
+            .await?;
+        manifest_file
+            .write_all(&serde_json::to_vec(&manifest)?)
+            .await?;
+        manifest_file.shutdown().await?;
+
+        let metadata = Metadata::new_incremental_state_snapshot_backup(
+            self.base_version,
+            self.version,
+            manifest_handle.clone(),
+        );
+        self.storage
+            .save_metadata_line(&metadata.name(), &metadata.to_text_line()?)
+            .await?;
+
+        Ok(manifest_handle)
+    }
+}
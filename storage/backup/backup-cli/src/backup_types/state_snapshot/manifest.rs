@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::storage::FileHandle;
+use crate::{storage::FileHandle, utils::encryption::EncryptionInfo};
 use diem_crypto::HashValue;
 use diem_types::transaction::Version;
 use serde::{Deserialize, Serialize};
@@ -46,4 +46,49 @@ pub struct StateSnapshotBackup {
     /// `EpochStateBackup` recovered prior to this to the DB; Requiring it to be in the same epoch
     /// limits the requirement on such `EpochStateBackup` to no older than the same epoch.
     pub proof: FileHandle,
+    /// Present if the data and proof files are sealed, see `utils::encryption`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionInfo>,
+}
+
+/// A chunk of an incremental state snapshot manifest, representing the changed accounts in the
+/// key range [`first_key`, `last_key`] (right side inclusive).
+#[derive(Deserialize, Serialize)]
+pub struct IncrementalStateSnapshotChunk {
+    /// index of the first account in this chunk over all changed accounts.
+    pub first_idx: usize,
+    /// index of the last account in this chunk over all changed accounts.
+    pub last_idx: usize,
+    /// key of the first account in this chunk.
+    pub first_key: HashValue,
+    /// key of the last account in this chunk.
+    pub last_key: HashValue,
+    /// Repeated `len(record) + record` where `record` is BCS serialized tuple
+    /// `(key, account_state_blob)`
+    pub blobs: FileHandle,
+}
+
+/// Incremental state snapshot backup manifest, representing the accounts changed between two
+/// versions. Applied to the state tree at `base_version` (restored from a full state snapshot or
+/// another incremental one), it makes the state tree at `version`.
+///
+/// There are no proofs for individual chunks, since the changed accounts are not contiguous in the
+/// tree. Instead, the root hash of the resulting tree is checked against `root_hash`, which is
+/// proven by `proof`.
+#[derive(Deserialize, Serialize)]
+pub struct IncrementalStateSnapshotBackup {
+    /// Version of the state snapshot this is based on.
+    pub base_version: Version,
+    /// Version at which this state snapshot is taken.
+    pub version: Version,
+    /// Hash of the state tree root at `version`.
+    pub root_hash: HashValue,
+    /// All changed account blobs in chunks.
+    pub chunks: Vec<IncrementalStateSnapshotChunk>,
+    /// BCS serialized `Tuple(TransactionInfoWithProof, LedgerInfoWithSignatures)`, same as
+    /// `StateSnapshotBackup::proof`.
+    pub proof: FileHandle,
+    /// Present if the data and proof files are sealed, see `utils::encryption`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionInfo>,
 }
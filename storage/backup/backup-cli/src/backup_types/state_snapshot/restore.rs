@@ -3,7 +3,8 @@
 
 use crate::{
     backup_types::{
-        epoch_ending::restore::EpochHistory, state_snapshot::manifest::StateSnapshotBackup,
+        epoch_ending::restore::EpochHistory,
+        state_snapshot::manifest::{IncrementalStateSnapshotBackup, StateSnapshotBackup},
     },
     metrics::{
         restore::{
@@ -14,21 +15,27 @@ use crate::{
             VERIFY_STATE_SNAPSHOT_VERSION,
         },
     },
-    storage::{BackupStorage, FileHandle},
+    storage::{BackupStorage, FileHandle, FileHandleRef},
     utils::{
-        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, GlobalRestoreOptions,
-        RestoreRunMode,
+        encryption::{EncryptionInfo, EncryptionKey},
+        read_record_bytes::ReadRecordBytes,
+        restore_journal::RestoreJournal,
+        storage_ext::BackupStorageExt,
+        stream::StreamX,
+        GlobalRestoreOptions, RestoreRunMode,
     },
 };
 use anyhow::{anyhow, ensure, Result};
 use diem_crypto::HashValue;
+use diem_jellyfish_merkle::restore::JellyfishMerkleRestore;
 use diem_logger::prelude::*;
 use diem_types::{
     account_state_blob::AccountStateBlob,
     ledger_info::LedgerInfoWithSignatures,
-    proof::default_protocol::TransactionInfoWithProof,
+    proof::{default_protocol::TransactionInfoWithProof, SparseMerkleRangeProof},
     transaction::{TransactionInfoTrait, Version},
 };
+use futures::StreamExt;
 use std::sync::Arc;
 use structopt::StructOpt;
 
@@ -50,6 +57,11 @@ pub struct StateSnapshotRestoreController {
     /// nothing will be done, otherwise, this has no effect.
     target_version: Version,
     epoch_history: Option<Arc<EpochHistory>>,
+    encryption_key: Option<Arc<EncryptionKey>>,
+    allow_plaintext: bool,
+    concurrent_downloads: usize,
+    max_chunks_in_flight: usize,
+    journal: Arc<RestoreJournal>,
 }
 
 impl StateSnapshotRestoreController {
@@ -66,6 +78,11 @@ impl StateSnapshotRestoreController {
             manifest_handle: opt.manifest_handle,
             target_version: global_opt.target_version,
             epoch_history,
+            encryption_key: global_opt.encryption_key,
+            allow_plaintext: global_opt.allow_plaintext,
+            concurrent_downloads: global_opt.concurrent_downloads,
+            max_chunks_in_flight: global_opt.max_chunks_in_flight,
+            journal: global_opt.journal,
         }
     }
 
@@ -95,24 +112,56 @@ impl StateSnapshotRestoreController {
             return Ok(());
         }
 
+        let progress = self.journal.get(&self.manifest_handle);
+        if progress.done {
+            info!(
+                manifest = %self.manifest_handle,
+                "State snapshot already restored according to the journal, skipping.",
+            );
+            return Ok(());
+        }
+
         let manifest: StateSnapshotBackup =
             self.storage.load_json_file(&self.manifest_handle).await?;
-        let (txn_info_with_proof, li): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
-            self.storage.load_bcs_file(&manifest.proof).await?;
-        txn_info_with_proof.verify(li.ledger_info(), manifest.version)?;
-        ensure!(
-            txn_info_with_proof.transaction_info().state_root_hash() == manifest.root_hash,
-            "Root hash mismatch with that in proof. root hash: {}, expected: {}",
+        let encryption_key = EncryptionInfo::key_for(
+            manifest.encryption.as_ref(),
+            self.encryption_key.as_ref(),
+            self.allow_plaintext,
+        )?;
+        verify_state_root_proof(
+            &self.storage,
+            &manifest.proof,
+            manifest.version,
             manifest.root_hash,
-            txn_info_with_proof.transaction_info().state_root_hash(),
-        );
-        if let Some(epoch_history) = self.epoch_history.as_ref() {
-            epoch_history.verify_ledger_info(&li)?;
-        }
+            encryption_key.as_ref(),
+            self.epoch_history.as_ref(),
+        )
+        .await?;
 
-        let mut receiver = self
-            .run_mode
-            .get_state_restore_receiver(self.version, manifest.root_hash)?;
+        // The journal tells whether a previous attempt has started writing to the DB, but accounts
+        // are persisted by `JellyfishMerkleRestore` with a lag, so it's the rightmost account in
+        // the DB that tells where to resume from.
+        let resume = progress.num_chunks_applied > 0;
+        let mut receiver =
+            self.run_mode
+                .get_state_restore_receiver(self.version, manifest.root_hash, resume)?;
+        let resume_after_key = receiver.previous_key();
+        let first_chunk_idx = match resume_after_key {
+            Some(key) => manifest
+                .chunks
+                .iter()
+                .position(|chunk| chunk.last_key > key)
+                .unwrap_or_else(|| manifest.chunks.len()),
+            None => 0,
+        };
+        if resume {
+            info!(
+                manifest = %self.manifest_handle,
+                num_chunks_applied = progress.num_chunks_applied,
+                first_chunk_idx = first_chunk_idx,
+                "Resuming state snapshot restore.",
+            );
+        }
 
         let (ver_gauge, tgt_leaf_idx, leaf_idx) = if self.run_mode.is_verify() {
             (
@@ -131,30 +180,215 @@ impl StateSnapshotRestoreController {
         // FIXME update counters
         ver_gauge.set(self.version as i64);
         tgt_leaf_idx.set(manifest.chunks.last().map_or(0, |c| c.last_idx as i64));
-        for chunk in manifest.chunks {
-            let blobs = self.read_account_state_chunk(chunk.blobs).await?;
-            let proof = self.storage.load_bcs_file(&chunk.proof).await?;
 
-            receiver.add_chunk(blobs, proof)?;
-            leaf_idx.set(chunk.last_idx as i64);
+        // Chunks are downloaded and prepared (their account states decoded and hashed)
+        // concurrently, up to `max_chunks_in_flight` ahead of the one being added to the tree.
+        // The range proof of a chunk is verified against all the accounts before it, so chunks
+        // are added to the tree in order.
+        let futs_iter = manifest
+            .chunks
+            .into_iter()
+            .enumerate()
+            .skip(first_chunk_idx)
+            .map(|(idx, chunk)| {
+                let storage = self.storage.clone();
+                let encryption_key = encryption_key.clone();
+                async move {
+                    let mut blobs =
+                        read_account_state_chunk(&storage, chunk.blobs, encryption_key.as_ref())
+                            .await?;
+                    if let Some(key) = resume_after_key {
+                        blobs.retain(|(k, _blob)| *k > key);
+                    }
+                    let proof = storage
+                        .load_bcs_file::<SparseMerkleRangeProof>(
+                            &chunk.proof,
+                            encryption_key.as_ref(),
+                        )
+                        .await?;
+                    let prepared_chunk = tokio::task::spawn_blocking(move || {
+                        JellyfishMerkleRestore::prepare_chunk(blobs)
+                    })
+                    .await??;
+                    Result::<_>::Ok((idx, chunk.last_idx, prepared_chunk, proof))
+                }
+            });
+        let concurrent_downloads = self.concurrent_downloads.min(self.max_chunks_in_flight);
+        let mut futs_stream = futures::stream::iter(futs_iter).buffered_x(
+            self.max_chunks_in_flight, /* buffer size */
+            concurrent_downloads,      /* concurrency */
+        );
+        while let Some(res) = futs_stream.next().await {
+            let (idx, last_idx, prepared_chunk, proof) = res?;
+            receiver.add_prepared_chunk(prepared_chunk, proof)?;
+            self.journal
+                .record_chunks_applied(&self.manifest_handle, idx + 1)?;
+            leaf_idx.set(last_idx as i64);
         }
 
         receiver.finish()?;
+        self.journal.record_done(&self.manifest_handle)?;
+        Ok(())
+    }
+}
+
+#[derive(StructOpt)]
+pub struct IncrementalStateSnapshotRestoreOpt {
+    #[structopt(long = "incremental-state-manifest")]
+    pub manifest_handle: FileHandle,
+}
+
+/// Applies an incremental state snapshot to the state tree at its base version, which must have
+/// been restored already.
+pub struct IncrementalStateSnapshotRestoreController {
+    storage: Arc<dyn BackupStorage>,
+    run_mode: Arc<RestoreRunMode>,
+    manifest_handle: FileHandle,
+    /// Global "target_version" for the entire restore process, if the version of the incremental
+    /// snapshot is newer than this, nothing will be done, otherwise, this has no effect.
+    target_version: Version,
+    epoch_history: Option<Arc<EpochHistory>>,
+    encryption_key: Option<Arc<EncryptionKey>>,
+    allow_plaintext: bool,
+    journal: Arc<RestoreJournal>,
+}
+
+impl IncrementalStateSnapshotRestoreController {
+    pub fn new(
+        opt: IncrementalStateSnapshotRestoreOpt,
+        global_opt: GlobalRestoreOptions,
+        storage: Arc<dyn BackupStorage>,
+        epoch_history: Option<Arc<EpochHistory>>,
+    ) -> Self {
+        Self {
+            storage,
+            run_mode: global_opt.run_mode,
+            manifest_handle: opt.manifest_handle,
+            target_version: global_opt.target_version,
+            epoch_history,
+            encryption_key: global_opt.encryption_key,
+            allow_plaintext: global_opt.allow_plaintext,
+            journal: global_opt.journal,
+        }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        let name = self.name();
+        info!("{} started. Manifest: {}", name, self.manifest_handle);
+        self.run_impl()
+            .await
+            .map_err(|e| anyhow!("{} failed: {}", name, e))?;
+        info!("{} succeeded.", name);
         Ok(())
     }
+}
 
-    async fn read_account_state_chunk(
-        &self,
-        file_handle: FileHandle,
-    ) -> Result<Vec<(HashValue, AccountStateBlob)>> {
-        let mut file = self.storage.open_for_read(&file_handle).await?;
+impl IncrementalStateSnapshotRestoreController {
+    fn name(&self) -> String {
+        format!("incremental state snapshot {}", self.run_mode.name())
+    }
 
-        let mut chunk = vec![];
+    async fn run_impl(self) -> Result<()> {
+        if self.journal.get(&self.manifest_handle).done {
+            info!(
+                manifest = %self.manifest_handle,
+                "Incremental state snapshot already restored according to the journal, skipping.",
+            );
+            return Ok(());
+        }
 
-        while let Some(record_bytes) = file.read_record_bytes().await? {
-            chunk.push(bcs::from_bytes(&record_bytes)?);
+        let manifest: IncrementalStateSnapshotBackup =
+            self.storage.load_json_file(&self.manifest_handle).await?;
+        if manifest.version > self.target_version {
+            warn!(
+                "Trying to restore incremental state snapshot to version {}, which is newer than the target version {}, skipping.",
+                manifest.version,
+                self.target_version,
+            );
+            return Ok(());
+        }
+        let encryption_key = EncryptionInfo::key_for(
+            manifest.encryption.as_ref(),
+            self.encryption_key.as_ref(),
+            self.allow_plaintext,
+        )?;
+        verify_state_root_proof(
+            &self.storage,
+            &manifest.proof,
+            manifest.version,
+            manifest.root_hash,
+            encryption_key.as_ref(),
+            self.epoch_history.as_ref(),
+        )
+        .await?;
+
+        let mut blobs = Vec::new();
+        for chunk in manifest.chunks {
+            let chunk_blobs =
+                read_account_state_chunk(&self.storage, chunk.blobs, encryption_key.as_ref())
+                    .await?;
+            ensure!(
+                chunk_blobs.len() == chunk.last_idx - chunk.first_idx + 1,
+                "Number of accounts in chunk doesn't match manifest. Actual: {}, expected: {}",
+                chunk_blobs.len(),
+                chunk.last_idx - chunk.first_idx + 1,
+            );
+            blobs.extend(chunk_blobs);
         }
+        ensure!(
+            blobs.windows(2).all(|w| w[0].0 < w[1].0),
+            "Account keys not strictly increasing.",
+        );
 
-        Ok(chunk)
+        self.run_mode.save_account_state_changes(
+            manifest.base_version,
+            manifest.version,
+            blobs,
+            manifest.root_hash,
+        )?;
+        self.journal.record_done(&self.manifest_handle)
     }
 }
+
+/// Verifies `root_hash` is the state root hash at `version`, by the proof in `proof_handle`, a
+/// BCS serialized `Tuple(TransactionInfoWithProof, LedgerInfoWithSignatures)`.
+async fn verify_state_root_proof(
+    storage: &Arc<dyn BackupStorage>,
+    proof_handle: &FileHandleRef,
+    version: Version,
+    root_hash: HashValue,
+    encryption_key: Option<&Arc<EncryptionKey>>,
+    epoch_history: Option<&Arc<EpochHistory>>,
+) -> Result<()> {
+    let (txn_info_with_proof, li): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
+        storage.load_bcs_file(proof_handle, encryption_key).await?;
+    txn_info_with_proof.verify(li.ledger_info(), version)?;
+    ensure!(
+        txn_info_with_proof.transaction_info().state_root_hash() == root_hash,
+        "Root hash mismatch with that in proof. root hash: {}, expected: {}",
+        root_hash,
+        txn_info_with_proof.transaction_info().state_root_hash(),
+    );
+    if let Some(epoch_history) = epoch_history {
+        epoch_history.verify_ledger_info(&li)?;
+    }
+    Ok(())
+}
+
+pub(crate) async fn read_account_state_chunk(
+    storage: &Arc<dyn BackupStorage>,
+    file_handle: FileHandle,
+    encryption_key: Option<&Arc<EncryptionKey>>,
+) -> Result<Vec<(HashValue, AccountStateBlob)>> {
+    let mut file = storage
+        .open_for_read_sealed(&file_handle, encryption_key)
+        .await?;
+
+    let mut chunk = vec![];
+
+    while let Some(record_bytes) = file.read_record_bytes().await? {
+        chunk.push(bcs::from_bytes(&record_bytes)?);
+    }
+
+    Ok(chunk)
+}
@@ -3,17 +3,28 @@
 
 use crate::{
     backup_types::state_snapshot::{
-        backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
-        restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        backup::{
+            IncrementalStateSnapshotBackupController, IncrementalStateSnapshotBackupOpt,
+            StateSnapshotBackupController, StateSnapshotBackupOpt,
+        },
+        manifest::StateSnapshotBackup,
+        restore::{
+            read_account_state_chunk, IncrementalStateSnapshotRestoreController,
+            IncrementalStateSnapshotRestoreOpt, StateSnapshotRestoreController,
+            StateSnapshotRestoreOpt,
+        },
     },
     storage::{local_fs::LocalFs, BackupStorage},
     utils::{
         backup_service_client::BackupServiceClient,
+        encryption::EncryptionOpt,
+        storage_ext::BackupStorageExt,
         test_utils::{start_local_backup_service, tmp_db_with_random_content},
-        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, RocksdbOpt, TrustedWaypointOpt,
+        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, GlobalRestoreOptions,
+        MaxChunksInFlightOpt, RestoreRunMode, RocksdbOpt, TrustedWaypointOpt,
     },
 };
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_temppath::TempPath;
 use diem_types::transaction::PRE_GENESIS_VERSION;
 use diemdb::DiemDB;
@@ -23,7 +34,7 @@ use tokio::time::Duration;
 
 #[test]
 fn end_to_end() {
-    let (_src_db_dir, src_db, _blocks) = tmp_db_with_random_content();
+    let (src_db, _blocks) = tmp_db_with_random_content();
     let tgt_db_dir = TempPath::new();
     tgt_db_dir.create_as_dir().unwrap();
     let backup_dir = TempPath::new();
@@ -46,6 +57,7 @@ fn end_to_end() {
                 StateSnapshotBackupOpt { version },
                 GlobalBackupOpt {
                     max_chunk_size: 500,
+                    encryption: EncryptionOpt::default(),
                 },
                 client,
                 Arc::clone(&store),
@@ -67,6 +79,8 @@ fn end_to_end() {
                 trusted_waypoints: TrustedWaypointOpt::default(),
                 rocksdb_opt: RocksdbOpt::default(),
                 concurernt_downloads: ConcurrentDownloadsOpt::default(),
+                max_chunks_in_flight: MaxChunksInFlightOpt::default(),
+                encryption: EncryptionOpt::default(),
             }
             .try_into()
             .unwrap(),
@@ -81,7 +95,9 @@ fn end_to_end() {
         &tgt_db_dir,
         true, /* read_only */
         None, /* pruner */
+        None, /* ledger_pruner */
         RocksdbConfig::default(),
+        SecondaryIndexesConfig::default(),
     )
     .unwrap();
     assert_eq!(
@@ -94,3 +110,233 @@ fn end_to_end() {
 
     rt.shutdown_timeout(Duration::from_secs(1));
 }
+
+#[test]
+fn incremental_end_to_end() {
+    let (src_db, _blocks) = tmp_db_with_random_content();
+    let tgt_db_dir = TempPath::new();
+    tgt_db_dir.create_as_dir().unwrap();
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+
+    let latest_tree_state = src_db.get_latest_tree_state().unwrap();
+    let version = latest_tree_state.num_transactions - 1;
+    // A full snapshot at the first version and incremental ones for the rest.
+    let mut versions = vec![0, version / 2, version];
+    versions.dedup();
+    let base_version = versions[0];
+    let expected_accounts = src_db
+        .get_backup_handler()
+        .get_account_iter(version)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let (rt, port) = start_local_backup_service(src_db);
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+    let global_backup_opt = GlobalBackupOpt {
+        max_chunk_size: 500,
+        encryption: EncryptionOpt::default(),
+    };
+
+    let base_manifest = rt
+        .block_on(
+            StateSnapshotBackupController::new(
+                StateSnapshotBackupOpt {
+                    version: base_version,
+                },
+                global_backup_opt.clone(),
+                Arc::clone(&client),
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+    let incremental_manifests = versions
+        .windows(2)
+        .map(|w| {
+            rt.block_on(
+                IncrementalStateSnapshotBackupController::new(
+                    IncrementalStateSnapshotBackupOpt {
+                        base_version: w[0],
+                        version: w[1],
+                    },
+                    global_backup_opt.clone(),
+                    Arc::clone(&client),
+                    Arc::clone(&store),
+                )
+                .run(),
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let global_restore_opt: GlobalRestoreOptions = GlobalRestoreOpt {
+        dry_run: false,
+        db_dir: Some(tgt_db_dir.path().to_path_buf()),
+        target_version: None, // max
+        trusted_waypoints: TrustedWaypointOpt::default(),
+        rocksdb_opt: RocksdbOpt::default(),
+        concurernt_downloads: ConcurrentDownloadsOpt::default(),
+        max_chunks_in_flight: MaxChunksInFlightOpt::default(),
+        encryption: EncryptionOpt::default(),
+    }
+    .try_into()
+    .unwrap();
+    rt.block_on(
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle: base_manifest,
+                version: base_version,
+            },
+            global_restore_opt.clone(),
+            Arc::clone(&store),
+            None, /* epoch_history */
+        )
+        .run(),
+    )
+    .unwrap();
+    // The root hash is checked against the proof after each incremental snapshot is applied.
+    for manifest_handle in incremental_manifests {
+        rt.block_on(
+            IncrementalStateSnapshotRestoreController::new(
+                IncrementalStateSnapshotRestoreOpt { manifest_handle },
+                global_restore_opt.clone(),
+                Arc::clone(&store),
+                None, /* epoch_history */
+            )
+            .run(),
+        )
+        .unwrap();
+    }
+
+    let restore_handler = match global_restore_opt.run_mode.as_ref() {
+        RestoreRunMode::Restore { restore_handler } => restore_handler.clone(),
+        RestoreRunMode::Verify => unreachable!(),
+    };
+    assert_eq!(
+        restore_handler
+            .diemdb
+            .get_backup_handler()
+            .get_account_iter(version)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        expected_accounts,
+    );
+
+    rt.shutdown_timeout(Duration::from_secs(1));
+}
+
+#[test]
+fn resume_end_to_end() {
+    let (src_db, _blocks) = tmp_db_with_random_content();
+    let tgt_db_dir = TempPath::new();
+    tgt_db_dir.create_as_dir().unwrap();
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+
+    let latest_tree_state = src_db.get_latest_tree_state().unwrap();
+    let version = latest_tree_state.num_transactions - 1;
+    let expected_accounts = src_db
+        .get_backup_handler()
+        .get_account_iter(version)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let (rt, port) = start_local_backup_service(src_db);
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+
+    // One account per chunk.
+    let manifest_handle = rt
+        .block_on(
+            StateSnapshotBackupController::new(
+                StateSnapshotBackupOpt { version },
+                GlobalBackupOpt {
+                    max_chunk_size: 1,
+                    encryption: EncryptionOpt::default(),
+                },
+                client,
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+    let manifest: StateSnapshotBackup =
+        rt.block_on(store.load_json_file(&manifest_handle)).unwrap();
+    assert!(manifest.chunks.len() > 1);
+
+    let global_restore_opt: GlobalRestoreOptions = GlobalRestoreOpt {
+        dry_run: false,
+        db_dir: Some(tgt_db_dir.path().to_path_buf()),
+        target_version: None, // max
+        trusted_waypoints: TrustedWaypointOpt::default(),
+        rocksdb_opt: RocksdbOpt::default(),
+        concurernt_downloads: ConcurrentDownloadsOpt::default(),
+        max_chunks_in_flight: MaxChunksInFlightOpt::default(),
+        encryption: EncryptionOpt::default(),
+    }
+    .try_into()
+    .unwrap();
+    let restore_handler = match global_restore_opt.run_mode.as_ref() {
+        RestoreRunMode::Restore { restore_handler } => restore_handler.clone(),
+        RestoreRunMode::Verify => unreachable!(),
+    };
+
+    // Apply the first half of the chunks, as if the restore was interrupted.
+    let num_chunks_applied = manifest.chunks.len() / 2;
+    let mut receiver = restore_handler
+        .get_state_restore_receiver(version, manifest.root_hash)
+        .unwrap();
+    for chunk in &manifest.chunks[..num_chunks_applied] {
+        let blobs = rt
+            .block_on(read_account_state_chunk(&store, chunk.blobs.clone(), None))
+            .unwrap();
+        let proof = rt
+            .block_on(store.load_bcs_file(&chunk.proof, None))
+            .unwrap();
+        receiver.add_chunk(blobs, proof).unwrap();
+    }
+    drop(receiver);
+    global_restore_opt
+        .journal
+        .record_chunks_applied(&manifest_handle, num_chunks_applied)
+        .unwrap();
+
+    rt.block_on(
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle: manifest_handle.clone(),
+                version,
+            },
+            global_restore_opt.clone(),
+            store,
+            None, /* epoch_history */
+        )
+        .run(),
+    )
+    .unwrap();
+
+    assert!(global_restore_opt.journal.get(&manifest_handle).done);
+    assert_eq!(
+        restore_handler
+            .diemdb
+            .get_backup_handler()
+            .get_account_iter(version)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        expected_accounts,
+    );
+
+    rt.shutdown_timeout(Duration::from_secs(1));
+}
@@ -14,12 +14,13 @@ use crate::{
     },
     storage::{local_fs::LocalFs, BackupStorage},
     utils::{
-        backup_service_client::BackupServiceClient, test_utils::start_local_backup_service,
-        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, GlobalRestoreOptions,
-        RocksdbOpt, TrustedWaypointOpt,
+        backup_service_client::BackupServiceClient, encryption::EncryptionOpt,
+        test_utils::start_local_backup_service, ConcurrentDownloadsOpt, GlobalBackupOpt,
+        GlobalRestoreOpt, GlobalRestoreOptions, MaxChunksInFlightOpt, RocksdbOpt,
+        TrustedWaypointOpt,
     },
 };
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_temppath::TempPath;
 use diem_types::transaction::Version;
 use diemdb::DiemDB;
@@ -75,9 +76,17 @@ fn test_end_to_end_impl(d: TestData) {
     )));
     let num_txns_to_backup = d.target_ver - d.txn_start_ver + 1;
 
+    let encryption_key_file = TempPath::new();
+    std::fs::write(encryption_key_file.path(), hex::encode([7u8; 32])).unwrap();
+    let encryption = EncryptionOpt {
+        encryption_key_file: Some(encryption_key_file.path().to_path_buf()),
+        ..Default::default()
+    };
+
     // Backup
     let global_backup_opt = GlobalBackupOpt {
         max_chunk_size: 2048,
+        encryption: encryption.clone(),
     };
     let state_snapshot_manifest = d.state_snapshot_ver.map(|version| {
         rt.block_on(
@@ -114,6 +123,8 @@ fn test_end_to_end_impl(d: TestData) {
         trusted_waypoints: TrustedWaypointOpt::default(),
         rocksdb_opt: RocksdbOpt::default(),
         concurernt_downloads: ConcurrentDownloadsOpt::default(),
+        max_chunks_in_flight: MaxChunksInFlightOpt::default(),
+        encryption,
     }
     .try_into()
     .unwrap();
@@ -153,7 +164,9 @@ fn test_end_to_end_impl(d: TestData) {
         &tgt_db_dir,
         false, /* read_only */
         None,  /* pruner */
+        None,  /* ledger_pruner */
         RocksdbConfig::default(),
+        SecondaryIndexesConfig::default(),
     )
     .unwrap();
     assert_eq!(
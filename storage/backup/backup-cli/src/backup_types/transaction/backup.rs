@@ -6,8 +6,12 @@ use crate::{
     metadata::Metadata,
     storage::{BackupHandleRef, BackupStorage, FileHandle, ShellSafeName},
     utils::{
-        backup_service_client::BackupServiceClient, read_record_bytes::ReadRecordBytes,
-        should_cut_chunk, storage_ext::BackupStorageExt, GlobalBackupOpt,
+        backup_service_client::BackupServiceClient,
+        encryption::{EncryptionInfo, EncryptionKey, EncryptionOpt},
+        read_record_bytes::ReadRecordBytes,
+        should_cut_chunk,
+        storage_ext::BackupStorageExt,
+        GlobalBackupOpt,
     },
 };
 use anyhow::{anyhow, Result};
@@ -31,6 +35,7 @@ pub struct TransactionBackupController {
     start_version: u64,
     num_transactions: usize,
     max_chunk_size: usize,
+    encryption: EncryptionOpt,
     client: Arc<BackupServiceClient>,
     storage: Arc<dyn BackupStorage>,
 }
@@ -46,6 +51,7 @@ impl TransactionBackupController {
             start_version: opt.start_version,
             num_transactions: opt.num_transactions,
             max_chunk_size: global_opt.max_chunk_size,
+            encryption: global_opt.encryption,
             client,
             storage,
         }
@@ -67,6 +73,7 @@ impl TransactionBackupController {
 
 impl TransactionBackupController {
     async fn run_impl(self) -> Result<FileHandle> {
+        let encryption_key = self.encryption.load_key()?;
         let backup_handle = self
             .storage
             .create_backup_with_random_suffix(&self.backup_name())
@@ -90,6 +97,7 @@ impl TransactionBackupController {
                         &chunk_bytes,
                         chunk_first_ver,
                         current_ver - 1,
+                        encryption_key.as_ref(),
                     )
                     .await?;
                 chunks.push(chunk);
@@ -113,12 +121,19 @@ impl TransactionBackupController {
                 &chunk_bytes,
                 chunk_first_ver,
                 current_ver - 1,
+                encryption_key.as_ref(),
             )
             .await?;
         chunks.push(chunk);
 
-        self.write_manifest(&backup_handle, self.start_version, current_ver - 1, chunks)
-            .await
+        self.write_manifest(
+            &backup_handle,
+            self.start_version,
+            current_ver - 1,
+            chunks,
+            encryption_key.as_ref(),
+        )
+        .await
     }
 
     fn backup_name(&self) -> String {
@@ -147,12 +162,14 @@ impl TransactionBackupController {
         chunk_bytes: &[u8],
         first_version: u64,
         last_version: u64,
+        encryption_key: Option<&Arc<EncryptionKey>>,
     ) -> Result<TransactionChunk> {
         let (proof_handle, mut proof_file) = self
             .storage
-            .create_for_write(
+            .create_for_write_sealed(
                 backup_handle,
                 &Self::chunk_proof_name(first_version, last_version),
+                encryption_key,
             )
             .await?;
         tokio::io::copy(
@@ -167,7 +184,11 @@ impl TransactionBackupController {
 
         let (chunk_handle, mut chunk_file) = self
             .storage
-            .create_for_write(backup_handle, &Self::chunk_name(first_version))
+            .create_for_write_sealed(
+                backup_handle,
+                &Self::chunk_name(first_version),
+                encryption_key,
+            )
             .await?;
         chunk_file.write_all(chunk_bytes).await?;
         chunk_file.shutdown().await?;
@@ -186,11 +207,13 @@ impl TransactionBackupController {
         first_version: Version,
         last_version: Version,
         chunks: Vec<TransactionChunk>,
+        encryption_key: Option<&Arc<EncryptionKey>>,
     ) -> Result<FileHandle> {
         let manifest = TransactionBackup {
             first_version,
             last_version,
             chunks,
+            encryption: encryption_key.map(|key| EncryptionInfo::new(key)),
         };
         let (manifest_handle, mut manifest_file) = self
             .storage
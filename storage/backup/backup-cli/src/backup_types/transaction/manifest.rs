@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::storage::FileHandle;
+use crate::{storage::FileHandle, utils::encryption::EncryptionInfo};
 use anyhow::{ensure, Result};
 use diem_types::transaction::Version;
 use serde::{Deserialize, Serialize};
@@ -30,6 +30,9 @@ pub struct TransactionBackup {
     pub first_version: Version,
     pub last_version: Version,
     pub chunks: Vec<TransactionChunk>,
+    /// Present if the data and proof files are sealed, see `utils::encryption`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionInfo>,
 }
 
 impl TransactionBackup {
@@ -12,7 +12,11 @@ use crate::{
     },
     storage::{BackupStorage, FileHandle},
     utils::{
-        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, stream::StreamX,
+        encryption::{EncryptionInfo, EncryptionKey},
+        read_record_bytes::ReadRecordBytes,
+        restore_journal::RestoreJournal,
+        storage_ext::BackupStorageExt,
+        stream::StreamX,
         GlobalRestoreOptions, RestoreRunMode,
     },
 };
@@ -65,6 +69,9 @@ pub struct TransactionRestoreController {
     target_version: Version,
     replay_from_version: Version,
     epoch_history: Option<Arc<EpochHistory>>,
+    encryption_key: Option<Arc<EncryptionKey>>,
+    allow_plaintext: bool,
+    journal: Arc<RestoreJournal>,
     state: State,
 }
 
@@ -83,8 +90,13 @@ impl LoadedChunk {
         manifest: TransactionChunk,
         storage: &Arc<dyn BackupStorage>,
         epoch_history: Option<&Arc<EpochHistory>>,
+        encryption_key: Option<&Arc<EncryptionKey>>,
     ) -> Result<Self> {
-        let mut file = BufReader::new(storage.open_for_read(&manifest.transactions).await?);
+        let mut file = BufReader::new(
+            storage
+                .open_for_read_sealed(&manifest.transactions, encryption_key)
+                .await?,
+        );
         let mut txns = Vec::new();
         let mut txn_infos = Vec::new();
         let mut event_vecs = Vec::new();
@@ -107,6 +119,7 @@ impl LoadedChunk {
         let (range_proof, ledger_info) = storage
             .load_bcs_file::<(TransactionAccumulatorRangeProof, LedgerInfoWithSignatures)>(
                 &manifest.proof,
+                encryption_key,
             )
             .await?;
         if let Some(epoch_history) = epoch_history {
@@ -154,6 +167,9 @@ impl TransactionRestoreController {
             manifest_handle: opt.manifest_handle,
             target_version: global_opt.target_version,
             epoch_history,
+            encryption_key: global_opt.encryption_key,
+            allow_plaintext: global_opt.allow_plaintext,
+            journal: global_opt.journal,
             state: State::default(),
         }
     }
@@ -179,9 +195,22 @@ impl TransactionRestoreController {
         let manifest: TransactionBackup =
             self.storage.load_json_file(&self.manifest_handle).await?;
         manifest.verify()?;
+        let encryption_key = EncryptionInfo::key_for(
+            manifest.encryption.as_ref(),
+            self.encryption_key.as_ref(),
+            self.allow_plaintext,
+        )?;
+
+        // Don't bother downloading chunks that have been applied according to the journal.
+        let progress = self.journal.get(&self.manifest_handle);
+        let first_chunk_idx = if progress.done {
+            manifest.chunks.len()
+        } else {
+            progress.num_chunks_applied
+        };
 
         let mut loaded_chunks = Vec::new();
-        for chunk_manifest in &manifest.chunks {
+        for chunk_manifest in manifest.chunks.iter().skip(first_chunk_idx) {
             if chunk_manifest.first_version > self.target_version {
                 break;
             }
@@ -191,6 +220,7 @@ impl TransactionRestoreController {
                     chunk_manifest.clone(),
                     &self.storage,
                     self.epoch_history.as_ref(),
+                    encryption_key.as_ref(),
                 )
                 .await?,
             )
@@ -198,6 +228,7 @@ impl TransactionRestoreController {
 
         Ok(TransactionRestorePreheatData {
             manifest,
+            first_chunk_idx,
             loaded_chunks,
         })
     }
@@ -205,10 +236,20 @@ impl TransactionRestoreController {
     fn maybe_restore_transactions(
         &mut self,
         mut chunk: LoadedChunk,
+        first_to_apply: u64,
         last: u64,
         first_to_replay: u64,
     ) -> Result<()> {
         if let RestoreRunMode::Restore { restore_handler } = self.run_mode.as_ref() {
+            // Transactions already in the DB, applied before the previous restore attempt was
+            // interrupted in the middle of this chunk:
+            if first_to_apply > chunk.manifest.first_version {
+                let num_applied = (first_to_apply - chunk.manifest.first_version) as usize;
+                chunk.txns.drain(0..num_applied);
+                chunk.txn_infos.drain(0..num_applied);
+                chunk.event_vecs.drain(0..num_applied);
+                chunk.manifest.first_version = first_to_apply;
+            }
             // Transactions to save without replaying:
             if first_to_replay > chunk.manifest.first_version {
                 let last_to_save = min(last, first_to_replay - 1);
@@ -311,6 +352,8 @@ struct State {
 
 struct TransactionRestorePreheatData {
     manifest: TransactionBackup,
+    /// Index of `loaded_chunks[0]` in the manifest, chunks before which have been applied.
+    first_chunk_idx: usize,
     loaded_chunks: Vec<LoadedChunk>,
 }
 
@@ -351,18 +394,44 @@ impl PreheatedTransactionRestore {
         let preheat_data = self
             .preheat_result
             .map_err(|e| anyhow!("Preheat failed: {}", e))?;
-
-        for chunk in preheat_data.loaded_chunks {
-            self.controller.maybe_save_frozen_subtrees(&chunk)?;
-
-            let last = min(self.controller.target_version, chunk.manifest.last_version);
-            let first_to_replay = max(
-                chunk.manifest.first_version,
-                self.controller.replay_from_version,
+        let journal = Arc::clone(&self.controller.journal);
+        let manifest_handle = self.controller.manifest_handle.clone();
+        if journal.get(&manifest_handle).done {
+            info!(
+                manifest = %manifest_handle,
+                "Transactions already restored according to the journal, skipping.",
             );
+            return Ok(());
+        }
 
-            self.controller
-                .maybe_restore_transactions(chunk, last, first_to_replay)?;
+        let next_expected_version = match self.controller.run_mode.as_ref() {
+            RestoreRunMode::Restore { restore_handler } => {
+                restore_handler.get_next_expected_transaction_version()?
+            }
+            RestoreRunMode::Verify => 0,
+        };
+
+        for (idx, chunk) in
+            (preheat_data.first_chunk_idx..).zip(preheat_data.loaded_chunks.into_iter())
+        {
+            let chunk_last_version = chunk.manifest.last_version;
+            let last = min(self.controller.target_version, chunk_last_version);
+            let first_to_apply = max(chunk.manifest.first_version, next_expected_version);
+            if first_to_apply <= last {
+                self.controller.maybe_save_frozen_subtrees(&chunk)?;
+
+                let first_to_replay = max(first_to_apply, self.controller.replay_from_version);
+                self.controller.maybe_restore_transactions(
+                    chunk,
+                    first_to_apply,
+                    last,
+                    first_to_replay,
+                )?;
+            }
+
+            if chunk_last_version <= self.controller.target_version {
+                journal.record_chunks_applied(&manifest_handle, idx + 1)?;
+            }
         }
 
         if self.controller.target_version < preheat_data.manifest.last_version {
@@ -370,6 +439,8 @@ impl PreheatedTransactionRestore {
                 "Transactions newer than target version {} ignored.",
                 self.controller.target_version,
             )
+        } else {
+            journal.record_done(&manifest_handle)?;
         }
 
         Ok(())
@@ -9,11 +9,13 @@ use crate::{
     storage::{local_fs::LocalFs, BackupStorage},
     utils::{
         backup_service_client::BackupServiceClient,
+        encryption::EncryptionOpt,
         test_utils::{start_local_backup_service, tmp_db_with_random_content},
-        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, RocksdbOpt, TrustedWaypointOpt,
+        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, MaxChunksInFlightOpt,
+        RocksdbOpt, TrustedWaypointOpt,
     },
 };
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_temppath::TempPath;
 use diem_types::transaction::Version;
 use diemdb::DiemDB;
@@ -23,7 +25,7 @@ use tokio::time::Duration;
 
 #[test]
 fn end_to_end() {
-    let (_src_db_dir, src_db, blocks) = tmp_db_with_random_content();
+    let (src_db, blocks) = tmp_db_with_random_content();
     let tgt_db_dir = TempPath::new();
     tgt_db_dir.create_as_dir().unwrap();
     let backup_dir = TempPath::new();
@@ -64,7 +66,10 @@ fn end_to_end() {
                     start_version: first_ver_to_backup,
                     num_transactions: num_txns_to_backup,
                 },
-                GlobalBackupOpt { max_chunk_size },
+                GlobalBackupOpt {
+                    max_chunk_size,
+                    encryption: EncryptionOpt::default(),
+                },
                 client,
                 Arc::clone(&store),
             )
@@ -85,6 +90,8 @@ fn end_to_end() {
                 trusted_waypoints: TrustedWaypointOpt::default(),
                 rocksdb_opt: RocksdbOpt::default(),
                 concurernt_downloads: ConcurrentDownloadsOpt::default(),
+                max_chunks_in_flight: MaxChunksInFlightOpt::default(),
+                encryption: EncryptionOpt::default(),
             }
             .try_into()
             .unwrap(),
@@ -102,7 +109,9 @@ fn end_to_end() {
         &tgt_db_dir,
         true, /* read_only */
         None, /* pruner */
+        None, /* ledger_pruner */
         RocksdbConfig::default(),
+        SecondaryIndexesConfig::default(),
     )
     .unwrap();
     assert_eq!(
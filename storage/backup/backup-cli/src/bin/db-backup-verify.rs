@@ -6,7 +6,9 @@ use backup_cli::{
     coordinators::verify::VerifyCoordinator,
     metadata::cache::MetadataCacheOpt,
     storage::StorageOpt,
-    utils::{ConcurrentDownloadsOpt, TrustedWaypointOpt},
+    utils::{
+        encryption::EncryptionOpt, ConcurrentDownloadsOpt, MaxChunksInFlightOpt, TrustedWaypointOpt,
+    },
 };
 use diem_logger::{prelude::*, Level, Logger};
 use diem_secure_push_metrics::MetricsPusher;
@@ -18,10 +20,14 @@ struct Opt {
     metadata_cache_opt: MetadataCacheOpt,
     #[structopt(flatten)]
     trusted_waypoints_opt: TrustedWaypointOpt,
+    #[structopt(flatten)]
+    encryption_opt: EncryptionOpt,
     #[structopt(subcommand)]
     storage: StorageOpt,
     #[structopt(flatten)]
     concurrent_downloads: ConcurrentDownloadsOpt,
+    #[structopt(flatten)]
+    max_chunks_in_flight: MaxChunksInFlightOpt,
 }
 
 #[tokio::main]
@@ -37,11 +43,14 @@ async fn main_impl() -> Result<()> {
     let _mp = MetricsPusher::start();
 
     let opt = Opt::from_args();
+    let concurrent_downloads = opt.concurrent_downloads.get();
     VerifyCoordinator::new(
         opt.storage.init_storage().await?,
         opt.metadata_cache_opt,
         opt.trusted_waypoints_opt,
-        opt.concurrent_downloads.get(),
+        opt.encryption_opt,
+        concurrent_downloads,
+        opt.max_chunks_in_flight.get(concurrent_downloads),
     )?
     .run()
     .await
@@ -9,10 +9,16 @@ use structopt::StructOpt;
 use backup_cli::{
     backup_types::{
         epoch_ending::backup::{EpochEndingBackupController, EpochEndingBackupOpt},
-        state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+        state_snapshot::backup::{
+            IncrementalStateSnapshotBackupController, IncrementalStateSnapshotBackupOpt,
+            StateSnapshotBackupController, StateSnapshotBackupOpt,
+        },
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
-    coordinators::backup::{BackupCoordinator, BackupCoordinatorOpt},
+    coordinators::{
+        backup::{BackupCoordinator, BackupCoordinatorOpt},
+        gc::{GcCoordinator, GcCoordinatorOpt},
+    },
     metadata::{cache, cache::MetadataCacheOpt},
     storage::StorageOpt,
     utils::{
@@ -30,6 +36,10 @@ enum Command {
     OneShot(OneShotCommand),
     #[structopt(about = "Long running process backing up the chain continuously.")]
     Coordinator(CoordinatorCommand),
+    #[structopt(
+        about = "Delete backups not needed under a retention policy, and compact the metadata."
+    )]
+    Gc(GcOpt),
 }
 
 #[derive(StructOpt)]
@@ -95,6 +105,12 @@ enum BackupType {
         #[structopt(subcommand)]
         storage: StorageOpt,
     },
+    IncrementalStateSnapshot {
+        #[structopt(flatten)]
+        opt: IncrementalStateSnapshotBackupOpt,
+        #[structopt(subcommand)]
+        storage: StorageOpt,
+    },
     Transaction {
         #[structopt(flatten)]
         opt: TransactionBackupOpt,
@@ -124,6 +140,15 @@ struct CoordinatorRunOpt {
     storage: StorageOpt,
 }
 
+#[derive(StructOpt)]
+struct GcOpt {
+    #[structopt(flatten)]
+    gc: GcCoordinatorOpt,
+
+    #[structopt(subcommand)]
+    storage: StorageOpt,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     main_impl().await.map_err(|e| {
@@ -183,6 +208,16 @@ async fn main_impl() -> Result<()> {
                         .run()
                         .await?;
                     }
+                    BackupType::IncrementalStateSnapshot { opt, storage } => {
+                        IncrementalStateSnapshotBackupController::new(
+                            opt,
+                            global_opt,
+                            client,
+                            storage.init_storage().await?,
+                        )
+                        .run()
+                        .await?;
+                    }
                     BackupType::Transaction { opt, storage } => {
                         TransactionBackupController::new(
                             opt,
@@ -208,6 +243,11 @@ async fn main_impl() -> Result<()> {
                 .await?;
             }
         },
+        Command::Gc(opt) => {
+            GcCoordinator::new(opt.gc, opt.storage.init_storage().await?)?
+                .run()
+                .await?;
+        }
     }
     Ok(())
 }
@@ -5,10 +5,15 @@ use anyhow::Result;
 use backup_cli::{
     backup_types::{
         epoch_ending::restore::{EpochEndingRestoreController, EpochEndingRestoreOpt},
-        state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        state_snapshot::restore::{
+            IncrementalStateSnapshotRestoreController, IncrementalStateSnapshotRestoreOpt,
+            StateSnapshotRestoreController, StateSnapshotRestoreOpt,
+        },
         transaction::restore::{TransactionRestoreController, TransactionRestoreOpt},
     },
-    coordinators::restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+    coordinators::restore::{
+        RestoreCoordinator, RestoreCoordinatorOpt, RestorePlanOpt, RestorePlanner,
+    },
     storage::StorageOpt,
     utils::{GlobalRestoreOpt, GlobalRestoreOptions},
 };
@@ -40,6 +45,12 @@ enum RestoreType {
         #[structopt(subcommand)]
         storage: StorageOpt,
     },
+    IncrementalStateSnapshot {
+        #[structopt(flatten)]
+        opt: IncrementalStateSnapshotRestoreOpt,
+        #[structopt(subcommand)]
+        storage: StorageOpt,
+    },
     Transaction {
         #[structopt(flatten)]
         opt: TransactionRestoreOpt,
@@ -52,6 +63,16 @@ enum RestoreType {
         #[structopt(subcommand)]
         storage: StorageOpt,
     },
+    #[structopt(
+        about = "Print the plan of an `auto` restore as JSON, with the backups to restore from \
+        and anything missing, without restoring anything."
+    )]
+    Plan {
+        #[structopt(flatten)]
+        opt: RestorePlanOpt,
+        #[structopt(subcommand)]
+        storage: StorageOpt,
+    },
 }
 
 #[tokio::main]
@@ -85,6 +106,16 @@ async fn main_impl() -> Result<()> {
             .run()
             .await?;
         }
+        RestoreType::IncrementalStateSnapshot { opt, storage } => {
+            IncrementalStateSnapshotRestoreController::new(
+                opt,
+                global_opt,
+                storage.init_storage().await?,
+                None, /* epoch_history */
+            )
+            .run()
+            .await?;
+        }
         RestoreType::Transaction { opt, storage } => {
             TransactionRestoreController::new(
                 opt,
@@ -100,6 +131,12 @@ async fn main_impl() -> Result<()> {
                 .run()
                 .await?;
         }
+        RestoreType::Plan { opt, storage } => {
+            let plan = RestorePlanner::new(opt, global_opt, storage.init_storage().await?)
+                .run()
+                .await?;
+            println!("{}", serde_json::to_string_pretty(&plan)?);
+        }
     }
 
     Ok(())
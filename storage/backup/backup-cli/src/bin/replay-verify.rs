@@ -6,8 +6,12 @@ use backup_cli::{
     coordinators::replay_verify::ReplayVerifyCoordinator,
     metadata::cache::MetadataCacheOpt,
     storage::StorageOpt,
-    utils::{ConcurrentDownloadsOpt, RocksdbOpt, TrustedWaypointOpt},
+    utils::{
+        encryption::EncryptionOpt, ConcurrentDownloadsOpt, MaxChunksInFlightOpt, RocksdbOpt,
+        TrustedWaypointOpt,
+    },
 };
+use diem_config::config::SecondaryIndexesConfig;
 use diem_logger::{prelude::*, Level, Logger};
 use diem_types::transaction::Version;
 use diemdb::{DiemDB, GetRestoreHandler};
@@ -20,10 +24,14 @@ struct Opt {
     metadata_cache_opt: MetadataCacheOpt,
     #[structopt(flatten)]
     trusted_waypoints_opt: TrustedWaypointOpt,
+    #[structopt(flatten)]
+    encryption_opt: EncryptionOpt,
     #[structopt(subcommand)]
     storage: StorageOpt,
     #[structopt(flatten)]
     concurrent_downloads: ConcurrentDownloadsOpt,
+    #[structopt(flatten)]
+    max_chunks_in_flight: MaxChunksInFlightOpt,
     #[structopt(long = "target-db-dir", parse(from_os_str))]
     pub db_dir: PathBuf,
     #[structopt(flatten)]
@@ -53,18 +61,23 @@ async fn main_impl() -> Result<()> {
     Logger::new().level(Level::Info).read_env().init();
 
     let opt = Opt::from_args();
+    let concurrent_downloads = opt.concurrent_downloads.get();
     let restore_handler = Arc::new(DiemDB::open(
         opt.db_dir,
         false, /* read_only */
         None,  /* pruner */
+        None,  /* ledger_pruner */
         opt.rocksdb_opt.into(),
+        SecondaryIndexesConfig::default(),
     )?)
     .get_restore_handler();
     ReplayVerifyCoordinator::new(
         opt.storage.init_storage().await?,
         opt.metadata_cache_opt,
         opt.trusted_waypoints_opt,
-        opt.concurrent_downloads.get(),
+        opt.encryption_opt,
+        concurrent_downloads,
+        opt.max_chunks_in_flight.get(concurrent_downloads),
         restore_handler,
         opt.start_version.unwrap_or(0),
         opt.end_version.unwrap_or(Version::MAX),
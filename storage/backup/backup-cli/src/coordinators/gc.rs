@@ -0,0 +1,238 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        epoch_ending::manifest::EpochEndingBackup,
+        state_snapshot::manifest::{IncrementalStateSnapshotBackup, StateSnapshotBackup},
+        transaction::manifest::TransactionBackup,
+    },
+    metadata,
+    metadata::{cache::MetadataCacheOpt, view::MetadataView, Metadata},
+    storage::{BackupStorage, FileHandle, ShellSafeName},
+    utils::{
+        storage_ext::BackupStorageExt, stream::StreamX, unix_timestamp_sec, ConcurrentDownloadsOpt,
+    },
+};
+use anyhow::{anyhow, ensure, Result};
+use diem_logger::prelude::*;
+use diem_types::transaction::Version;
+use futures::StreamExt;
+use std::{convert::TryInto, sync::Arc};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+pub struct GcCoordinatorOpt {
+    #[structopt(flatten)]
+    pub metadata_cache_opt: MetadataCacheOpt,
+    #[structopt(
+        long,
+        help = "Number of the latest state snapshots to keep, at least 1."
+    )]
+    pub keep_latest_state_snapshots: usize,
+    #[structopt(
+        long,
+        help = "[Defaults to not set] On top of the latest ones, also keep the first state \
+        snapshot in every this many epochs. Epochs are learned from the epoch ending backups."
+    )]
+    pub keep_state_snapshot_every_n_epochs: Option<u64>,
+    #[structopt(
+        long,
+        help = "Only log what would be deleted, without deleting anything."
+    )]
+    pub dry_run: bool,
+    #[structopt(flatten)]
+    pub concurernt_downloads: ConcurrentDownloadsOpt,
+}
+
+impl GcCoordinatorOpt {
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.keep_latest_state_snapshots > 0,
+            "At least one state snapshot must be kept."
+        );
+        ensure!(
+            self.keep_state_snapshot_every_n_epochs != Some(0),
+            "Epoch interval to keep state snapshots must be greater than 0."
+        );
+        Ok(())
+    }
+}
+
+/// Deletes backups not needed under a retention policy (see `MetadataView::plan_retention()`)
+/// and compacts the metadata of the remaining ones into a single file.
+///
+/// The compacted metadata is saved before anything is deleted, and the old metadata files are
+/// deleted before the files of the dropped backups, so an interruption at any point never leaves
+/// metadata referring to deleted files. The worst case is the files of some dropped backups
+/// being leaked.
+pub struct GcCoordinator {
+    storage: Arc<dyn BackupStorage>,
+    metadata_cache_opt: MetadataCacheOpt,
+    keep_latest_state_snapshots: usize,
+    keep_state_snapshot_every_n_epochs: Option<u64>,
+    dry_run: bool,
+    concurrent_downloads: usize,
+}
+
+impl GcCoordinator {
+    pub fn new(opt: GcCoordinatorOpt, storage: Arc<dyn BackupStorage>) -> Result<Self> {
+        opt.validate()?;
+        Ok(Self {
+            storage,
+            metadata_cache_opt: opt.metadata_cache_opt,
+            keep_latest_state_snapshots: opt.keep_latest_state_snapshots,
+            keep_state_snapshot_every_n_epochs: opt.keep_state_snapshot_every_n_epochs,
+            dry_run: opt.dry_run,
+            concurrent_downloads: opt.concurernt_downloads.get(),
+        })
+    }
+
+    pub async fn run(self) -> Result<()> {
+        info!("Gc coordinator started.");
+        self.run_impl()
+            .await
+            .map_err(|e| anyhow!("Gc coordinator failed: {}", e))?;
+        info!("Gc coordinator exiting with success.");
+        Ok(())
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        // Listed before loading the metadata, so that metadata files saved by backups happening in
+        // the meantime are not deleted without being compacted.
+        let metadata_files = self.storage.list_metadata_files().await?;
+        let metadata_view = metadata::cache::sync_and_load(
+            &self.metadata_cache_opt,
+            Arc::clone(&self.storage),
+            self.concurrent_downloads,
+        )
+        .await?;
+        let epoch_ending_versions = self.load_epoch_ending_versions(&metadata_view).await?;
+        let plan = metadata_view.plan_retention(
+            self.keep_latest_state_snapshots,
+            self.keep_state_snapshot_every_n_epochs,
+            &epoch_ending_versions,
+        );
+        info!(
+            num_metadata_files = metadata_files.len(),
+            num_kept_backups = plan.keep.len(),
+            num_dropped_state_snapshots = plan.drop_state_snapshots.len(),
+            num_dropped_incremental_state_snapshots = plan.drop_incremental_state_snapshots.len(),
+            num_dropped_transaction_backups = plan.drop_transactions.len(),
+            "Retention planned."
+        );
+        for s in &plan.drop_state_snapshots {
+            info!(version = s.version, manifest = %s.manifest, "Dropping state snapshot.");
+        }
+        for s in &plan.drop_incremental_state_snapshots {
+            info!(
+                base_version = s.base_version,
+                version = s.version,
+                manifest = %s.manifest,
+                "Dropping incremental state snapshot."
+            );
+        }
+        for t in &plan.drop_transactions {
+            info!(
+                first_version = t.first_version,
+                last_version = t.last_version,
+                manifest = %t.manifest,
+                "Dropping transaction backup."
+            );
+        }
+        if self.dry_run {
+            info!("Dry run, nothing deleted.");
+            return Ok(());
+        }
+        if plan.drop_state_snapshots.is_empty()
+            && plan.drop_incremental_state_snapshots.is_empty()
+            && plan.drop_transactions.is_empty()
+            && metadata_files.len() <= 1
+        {
+            info!("Nothing to collect.");
+            return Ok(());
+        }
+
+        let lines = plan
+            .keep
+            .iter()
+            .map(Metadata::to_text_line)
+            .collect::<Result<Vec<_>>>()?;
+        self.storage
+            .save_metadata_lines(&Self::compacted_metadata_name(), &lines)
+            .await?;
+        self.delete_files(metadata_files).await?;
+        info!("Metadata compacted.");
+
+        for s in plan.drop_state_snapshots {
+            let manifest: StateSnapshotBackup = self.storage.load_json_file(&s.manifest).await?;
+            let mut files = manifest
+                .chunks
+                .into_iter()
+                .flat_map(|c| vec![c.blobs, c.proof])
+                .collect::<Vec<_>>();
+            files.push(manifest.proof);
+            // Manifest goes the last, so a retry after interruption still finds everything.
+            self.delete_files(files).await?;
+            self.storage.delete_file(&s.manifest).await?;
+        }
+        for s in plan.drop_incremental_state_snapshots {
+            let manifest: IncrementalStateSnapshotBackup =
+                self.storage.load_json_file(&s.manifest).await?;
+            let mut files = manifest
+                .chunks
+                .into_iter()
+                .map(|c| c.blobs)
+                .collect::<Vec<_>>();
+            files.push(manifest.proof);
+            self.delete_files(files).await?;
+            self.storage.delete_file(&s.manifest).await?;
+        }
+        for t in plan.drop_transactions {
+            let manifest: TransactionBackup = self.storage.load_json_file(&t.manifest).await?;
+            let files = manifest
+                .chunks
+                .into_iter()
+                .flat_map(|c| vec![c.transactions, c.proof])
+                .collect();
+            self.delete_files(files).await?;
+            self.storage.delete_file(&t.manifest).await?;
+        }
+
+        Ok(())
+    }
+
+    fn compacted_metadata_name() -> ShellSafeName {
+        format!("compacted_{}.meta", unix_timestamp_sec())
+            .try_into()
+            .unwrap()
+    }
+
+    async fn load_epoch_ending_versions(&self, view: &MetadataView) -> Result<Vec<Version>> {
+        if self.keep_state_snapshot_every_n_epochs.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let mut versions = Vec::new();
+        for backup in view.select_epoch_ending_backups(Version::max_value())? {
+            let manifest: EpochEndingBackup = self.storage.load_json_file(&backup.manifest).await?;
+            versions.extend(manifest.waypoints.iter().map(|w| w.version()));
+        }
+        Ok(versions)
+    }
+
+    async fn delete_files(&self, file_handles: Vec<FileHandle>) -> Result<()> {
+        let futs = file_handles.into_iter().map(|file_handle| {
+            let storage = Arc::clone(&self.storage);
+            async move { storage.delete_file(&file_handle).await }
+        });
+        futures::stream::iter(futs)
+            .buffered_x(
+                self.concurrent_downloads * 2, /* buffer size */
+                self.concurrent_downloads,     /* concurrency */
+            )
+            .collect::<Result<Vec<_>>>()
+            .await?;
+        Ok(())
+    }
+}
@@ -9,7 +9,10 @@ use crate::{
     metadata,
     metadata::cache::MetadataCacheOpt,
     storage::BackupStorage,
-    utils::{GlobalRestoreOptions, RestoreRunMode, TrustedWaypointOpt},
+    utils::{
+        encryption::EncryptionOpt, restore_journal::RestoreJournal, GlobalRestoreOptions,
+        RestoreRunMode, TrustedWaypointOpt,
+    },
 };
 use anyhow::{ensure, Result};
 use diem_logger::prelude::*;
@@ -21,7 +24,9 @@ pub struct ReplayVerifyCoordinator {
     storage: Arc<dyn BackupStorage>,
     metadata_cache_opt: MetadataCacheOpt,
     trusted_waypoints_opt: TrustedWaypointOpt,
+    encryption_opt: EncryptionOpt,
     concurrent_downloads: usize,
+    max_chunks_in_flight: usize,
     restore_handler: RestoreHandler,
     start_version: Version,
     end_version: Version,
@@ -32,7 +37,9 @@ impl ReplayVerifyCoordinator {
         storage: Arc<dyn BackupStorage>,
         metadata_cache_opt: MetadataCacheOpt,
         trusted_waypoints_opt: TrustedWaypointOpt,
+        encryption_opt: EncryptionOpt,
         concurrent_downloads: usize,
+        max_chunks_in_flight: usize,
         restore_handler: RestoreHandler,
         start_version: Version,
         end_version: Version,
@@ -41,7 +48,9 @@ impl ReplayVerifyCoordinator {
             storage,
             metadata_cache_opt,
             trusted_waypoints_opt,
+            encryption_opt,
             concurrent_downloads,
+            max_chunks_in_flight,
             restore_handler,
             start_version,
             end_version,
@@ -96,6 +105,10 @@ impl ReplayVerifyCoordinator {
                 restore_handler: self.restore_handler,
             }),
             concurrent_downloads: self.concurrent_downloads,
+            max_chunks_in_flight: self.max_chunks_in_flight,
+            encryption_key: self.encryption_opt.load_key()?,
+            allow_plaintext: self.encryption_opt.allow_plaintext,
+            journal: Arc::new(RestoreJournal::new_in_memory()),
         };
 
         if let Some(backup) = state_snapshot {
@@ -3,22 +3,33 @@
 
 use crate::{
     backup_types::{
-        epoch_ending::restore::EpochHistoryRestoreController,
-        state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
-        transaction::restore::TransactionRestoreBatchController,
+        epoch_ending::{manifest::EpochEndingBackup, restore::EpochHistoryRestoreController},
+        state_snapshot::{
+            manifest::{IncrementalStateSnapshotBackup, StateSnapshotBackup},
+            restore::{
+                IncrementalStateSnapshotRestoreController, IncrementalStateSnapshotRestoreOpt,
+                StateSnapshotRestoreController, StateSnapshotRestoreOpt,
+            },
+        },
+        transaction::{manifest::TransactionBackup, restore::TransactionRestoreBatchController},
     },
     metadata,
-    metadata::{cache::MetadataCacheOpt, TransactionBackupMeta},
+    metadata::{cache::MetadataCacheOpt, view::RestorePlan},
     metrics::restore::{
         COORDINATOR_FAIL_TS, COORDINATOR_START_TS, COORDINATOR_SUCC_TS, COORDINATOR_TARGET_VERSION,
     },
-    storage::BackupStorage,
-    utils::{unix_timestamp_sec, GlobalRestoreOptions, RestoreRunMode},
+    storage::{BackupStorage, FileHandle, FileHandleRef},
+    utils::{
+        storage_ext::BackupStorageExt, stream::StreamX, unix_timestamp_sec, GlobalRestoreOptions,
+        RestoreRunMode,
+    },
 };
-use anyhow::{bail, Result};
+use anyhow::{ensure, Result};
 use diem_logger::prelude::*;
 use diem_types::transaction::Version;
-use std::sync::Arc;
+use futures::{future, FutureExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use std::{path::PathBuf, sync::Arc};
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -30,6 +41,13 @@ pub struct RestoreCoordinatorOpt {
         help = "Replay all transactions, don't try to use a state snapshot."
     )]
     pub replay_all: bool,
+    #[structopt(
+        long = "plan",
+        parse(from_os_str),
+        help = "Restore exactly according to the plan in this file, printed by `db-restore plan`, \
+        instead of planning according to the metadata in the storage."
+    )]
+    pub plan_file: Option<PathBuf>,
 }
 
 pub struct RestoreCoordinator {
@@ -37,6 +55,7 @@ pub struct RestoreCoordinator {
     global_opt: GlobalRestoreOptions,
     metadata_cache_opt: MetadataCacheOpt,
     replay_all: bool,
+    plan_file: Option<PathBuf>,
 }
 
 impl RestoreCoordinator {
@@ -50,6 +69,7 @@ impl RestoreCoordinator {
             global_opt,
             metadata_cache_opt: opt.metadata_cache_opt,
             replay_all: opt.replay_all,
+            plan_file: opt.plan_file,
         }
     }
 
@@ -73,28 +93,50 @@ impl RestoreCoordinator {
         ret
     }
 
-    async fn run_impl(self) -> Result<()> {
-        let metadata_view = metadata::cache::sync_and_load(
-            &self.metadata_cache_opt,
-            Arc::clone(&self.storage),
-            self.global_opt.concurrent_downloads,
-        )
-        .await?;
-
-        let transactions = metadata_view.select_transaction_backups(0, self.target_version())?;
-        let actual_target_version = self.get_actual_target_version(&transactions)?;
-        let epoch_endings = metadata_view.select_epoch_ending_backups(actual_target_version)?;
-        let state_snapshot = if self.replay_all {
-            None
-        } else {
-            metadata_view.select_state_snapshot(actual_target_version)?
-        };
-        let replay_transactions_from_version = match &state_snapshot {
-            Some(b) => b.version + 1,
-            None => 0,
+    async fn run_impl(mut self) -> Result<()> {
+        let plan = match &self.plan_file {
+            Some(path) => {
+                let plan: RestorePlan = serde_json::from_slice(&tokio::fs::read(path).await?)?;
+                ensure!(
+                    plan.target_version <= self.target_version(),
+                    "The plan restores to version {}, newer than the target version {}.",
+                    plan.target_version,
+                    self.target_version(),
+                );
+                plan
+            }
+            None => {
+                let metadata_view = metadata::cache::sync_and_load(
+                    &self.metadata_cache_opt,
+                    Arc::clone(&self.storage),
+                    self.global_opt.concurrent_downloads,
+                )
+                .await?;
+                metadata_view.plan_restore(self.target_version(), self.replay_all)?
+            }
         };
-        COORDINATOR_TARGET_VERSION.set(actual_target_version as i64);
-        info!("Planned to restore to version {}.", actual_target_version);
+        info!("Restore plan: {}", serde_json::to_string(&plan)?);
+        ensure!(
+            plan.transaction_gaps.is_empty(),
+            "Transaction backups missing for versions {:?}.",
+            plan.transaction_gaps,
+        );
+        ensure!(
+            plan.missing_epochs.is_empty(),
+            "Epoch ending backups missing for epochs {:?}.",
+            plan.missing_epochs,
+        );
+        if !plan.reachable {
+            warn!(
+                "Can't find transaction backup containing the target version, \
+                will restore as much as possible"
+            );
+        }
+        // Controllers don't go beyond the planned version either.
+        self.global_opt.target_version = plan.target_version;
+
+        COORDINATOR_TARGET_VERSION.set(plan.target_version as i64);
+        info!("Planned to restore to version {}.", plan.target_version);
         let txn_resume_point = match self.global_opt.run_mode.as_ref() {
             RestoreRunMode::Restore { restore_handler } => {
                 restore_handler.get_next_expected_transaction_version()?
@@ -113,7 +155,7 @@ impl RestoreCoordinator {
 
         let epoch_history = Arc::new(
             EpochHistoryRestoreController::new(
-                epoch_endings
+                plan.epoch_ending_backups
                     .into_iter()
                     .map(|backup| backup.manifest)
                     .collect(),
@@ -124,11 +166,11 @@ impl RestoreCoordinator {
             .await?,
         );
 
-        if let Some(backup) = state_snapshot {
+        if let Some(snapshot) = plan.state_snapshot_backup {
             StateSnapshotRestoreController::new(
                 StateSnapshotRestoreOpt {
-                    manifest_handle: backup.manifest,
-                    version: backup.version,
+                    manifest_handle: snapshot.manifest,
+                    version: snapshot.version,
                 },
                 self.global_opt.clone(),
                 Arc::clone(&self.storage),
@@ -136,9 +178,22 @@ impl RestoreCoordinator {
             )
             .run()
             .await?;
+            for backup in plan.incremental_state_snapshot_backups {
+                IncrementalStateSnapshotRestoreController::new(
+                    IncrementalStateSnapshotRestoreOpt {
+                        manifest_handle: backup.manifest,
+                    },
+                    self.global_opt.clone(),
+                    Arc::clone(&self.storage),
+                    Some(Arc::clone(&epoch_history)),
+                )
+                .run()
+                .await?;
+            }
         }
 
-        let txn_manifests = transactions
+        let txn_manifests = plan
+            .transaction_backups
             .into_iter()
             .skip_while(|b| b.last_version < txn_resume_point)
             .map(|b| b.manifest)
@@ -147,7 +202,7 @@ impl RestoreCoordinator {
             self.global_opt,
             self.storage,
             txn_manifests,
-            Some(replay_transactions_from_version),
+            Some(plan.replay_transactions_from_version),
             Some(epoch_history),
         )
         .run()
@@ -161,23 +216,129 @@ impl RestoreCoordinator {
     fn target_version(&self) -> Version {
         self.global_opt.target_version
     }
+}
 
-    fn get_actual_target_version(
-        &self,
-        transaction_backups: &[TransactionBackupMeta],
-    ) -> Result<Version> {
-        if let Some(b) = transaction_backups.last() {
-            if b.last_version > self.target_version() {
-                Ok(self.target_version())
-            } else {
-                warn!(
-                    "Can't find transaction backup containing the target version, \
-                    will restore as much as possible"
-                );
-                Ok(b.last_version)
-            }
-        } else {
-            bail!("No transaction backup found.")
+#[derive(StructOpt)]
+pub struct RestorePlanOpt {
+    #[structopt(flatten)]
+    pub metadata_cache_opt: MetadataCacheOpt,
+    #[structopt(
+        long,
+        help = "Replay all transactions, don't try to use a state snapshot."
+    )]
+    pub replay_all: bool,
+    #[structopt(
+        long,
+        help = "Estimate the number of bytes to download, by loading all the manifests in the plan \
+        and querying the storage for the size of each file."
+    )]
+    pub estimate_download_size: bool,
+}
+
+/// Makes the plan `RestoreCoordinator` would follow, without restoring anything.
+pub struct RestorePlanner {
+    storage: Arc<dyn BackupStorage>,
+    global_opt: GlobalRestoreOptions,
+    metadata_cache_opt: MetadataCacheOpt,
+    replay_all: bool,
+    estimate_download_size: bool,
+}
+
+impl RestorePlanner {
+    pub fn new(
+        opt: RestorePlanOpt,
+        global_opt: GlobalRestoreOptions,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Self {
+        Self {
+            storage,
+            global_opt,
+            metadata_cache_opt: opt.metadata_cache_opt,
+            replay_all: opt.replay_all,
+            estimate_download_size: opt.estimate_download_size,
+        }
+    }
+
+    pub async fn run(self) -> Result<RestorePlan> {
+        let metadata_view = metadata::cache::sync_and_load(
+            &self.metadata_cache_opt,
+            Arc::clone(&self.storage),
+            self.global_opt.concurrent_downloads,
+        )
+        .await?;
+        let mut plan =
+            metadata_view.plan_restore(self.global_opt.target_version, self.replay_all)?;
+        if self.estimate_download_size {
+            plan.download_size = Some(self.get_download_size(&plan).await?);
+        }
+        Ok(plan)
+    }
+
+    async fn get_download_size(&self, plan: &RestorePlan) -> Result<u64> {
+        let storage = &self.storage;
+        let manifest_sizes = plan
+            .epoch_ending_backups
+            .iter()
+            .map(|b| {
+                Self::get_files_size::<EpochEndingBackup, _>(storage, &b.manifest, |m| {
+                    m.chunks.into_iter().map(|c| c.ledger_infos).collect()
+                })
+                .boxed()
+            })
+            .chain(plan.state_snapshot_backup.iter().map(|b| {
+                Self::get_files_size::<StateSnapshotBackup, _>(storage, &b.manifest, |m| {
+                    m.chunks
+                        .into_iter()
+                        .flat_map(|c| vec![c.blobs, c.proof])
+                        .chain(std::iter::once(m.proof))
+                        .collect()
+                })
+                .boxed()
+            }))
+            .chain(plan.incremental_state_snapshot_backups.iter().map(|b| {
+                Self::get_files_size::<IncrementalStateSnapshotBackup, _>(
+                    storage,
+                    &b.manifest,
+                    |m| {
+                        m.chunks
+                            .into_iter()
+                            .map(|c| c.blobs)
+                            .chain(std::iter::once(m.proof))
+                            .collect()
+                    },
+                )
+                .boxed()
+            }))
+            .chain(plan.transaction_backups.iter().map(|b| {
+                Self::get_files_size::<TransactionBackup, _>(storage, &b.manifest, |m| {
+                    m.chunks
+                        .into_iter()
+                        .flat_map(|c| vec![c.transactions, c.proof])
+                        .collect()
+                })
+                .boxed()
+            }));
+
+        futures::stream::iter(manifest_sizes)
+            .buffered_x(
+                self.global_opt.concurrent_downloads * 2, /* buffer size */
+                self.global_opt.concurrent_downloads,     /* concurrency */
+            )
+            .try_fold(0, |total, size| future::ok(total + size))
+            .await
+    }
+
+    /// Total size of a manifest and the files listed in it by `files`.
+    async fn get_files_size<M: DeserializeOwned, F: FnOnce(M) -> Vec<FileHandle>>(
+        storage: &Arc<dyn BackupStorage>,
+        manifest_handle: &FileHandleRef,
+        files: F,
+    ) -> Result<u64> {
+        let manifest: M = storage.load_json_file(manifest_handle).await?;
+        let mut size = storage.get_file_size(manifest_handle).await?;
+        for file_handle in files(manifest) {
+            size += storage.get_file_size(&file_handle).await?;
         }
+        Ok(size)
     }
 }
@@ -13,7 +13,10 @@ use crate::{
         VERIFY_COORDINATOR_FAIL_TS, VERIFY_COORDINATOR_START_TS, VERIFY_COORDINATOR_SUCC_TS,
     },
     storage::BackupStorage,
-    utils::{unix_timestamp_sec, GlobalRestoreOptions, RestoreRunMode, TrustedWaypointOpt},
+    utils::{
+        encryption::EncryptionOpt, restore_journal::RestoreJournal, unix_timestamp_sec,
+        GlobalRestoreOptions, RestoreRunMode, TrustedWaypointOpt,
+    },
 };
 use anyhow::Result;
 use diem_logger::prelude::*;
@@ -24,7 +27,9 @@ pub struct VerifyCoordinator {
     storage: Arc<dyn BackupStorage>,
     metadata_cache_opt: MetadataCacheOpt,
     trusted_waypoints_opt: TrustedWaypointOpt,
+    encryption_opt: EncryptionOpt,
     concurrent_downloads: usize,
+    max_chunks_in_flight: usize,
 }
 
 impl VerifyCoordinator {
@@ -32,13 +37,17 @@ impl VerifyCoordinator {
         storage: Arc<dyn BackupStorage>,
         metadata_cache_opt: MetadataCacheOpt,
         trusted_waypoints_opt: TrustedWaypointOpt,
+        encryption_opt: EncryptionOpt,
         concurrent_downloads: usize,
+        max_chunks_in_flight: usize,
     ) -> Result<Self> {
         Ok(Self {
             storage,
             metadata_cache_opt,
             trusted_waypoints_opt,
+            encryption_opt,
             concurrent_downloads,
+            max_chunks_in_flight,
         })
     }
 
@@ -79,6 +88,10 @@ impl VerifyCoordinator {
             trusted_waypoints: Arc::new(self.trusted_waypoints_opt.verify()?),
             run_mode: Arc::new(RestoreRunMode::Verify),
             concurrent_downloads: self.concurrent_downloads,
+            max_chunks_in_flight: self.max_chunks_in_flight,
+            encryption_key: self.encryption_opt.load_key()?,
+            allow_plaintext: self.encryption_opt.allow_plaintext,
+            journal: Arc::new(RestoreJournal::new_in_memory()),
         };
 
         let epoch_history = Arc::new(
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod cache;
+#[cfg(test)]
+mod tests;
 pub mod view;
 
 use crate::storage::{FileHandle, ShellSafeName, TextLine};
@@ -16,6 +18,7 @@ pub(crate) enum Metadata {
     EpochEndingBackup(EpochEndingBackupMeta),
     StateSnapshotBackup(StateSnapshotBackupMeta),
     TransactionBackup(TransactionBackupMeta),
+    IncrementalStateSnapshotBackup(IncrementalStateSnapshotBackupMeta),
 }
 
 impl Metadata {
@@ -39,6 +42,18 @@ impl Metadata {
         Self::StateSnapshotBackup(StateSnapshotBackupMeta { version, manifest })
     }
 
+    pub fn new_incremental_state_snapshot_backup(
+        base_version: Version,
+        version: Version,
+        manifest: FileHandle,
+    ) -> Self {
+        Self::IncrementalStateSnapshotBackup(IncrementalStateSnapshotBackupMeta {
+            version,
+            base_version,
+            manifest,
+        })
+    }
+
     pub fn new_transaction_backup(
         first_version: Version,
         last_version: Version,
@@ -60,6 +75,10 @@ impl Metadata {
             Self::TransactionBackup(t) => {
                 format!("transaction_{}-{}.meta", t.first_version, t.last_version,)
             }
+            Self::IncrementalStateSnapshotBackup(s) => format!(
+                "incremental_state_snapshot_ver_{}-{}.meta",
+                s.base_version, s.version
+            ),
         }
         .try_into()
         .unwrap()
@@ -91,3 +110,10 @@ pub struct TransactionBackupMeta {
     pub last_version: Version,
     pub manifest: FileHandle,
 }
+
+#[derive(Clone, Deserialize, Serialize, Eq, PartialEq, Ord, PartialOrd)]
+pub struct IncrementalStateSnapshotBackupMeta {
+    pub version: Version,
+    pub base_version: Version,
+    pub manifest: FileHandle,
+}
@@ -0,0 +1,253 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::metadata::{view::MetadataView, Metadata};
+use diem_types::transaction::Version;
+
+fn snapshot(version: Version) -> Metadata {
+    Metadata::new_state_snapshot_backup(version, format!("snapshot_{}", version))
+}
+
+fn transaction(first_version: Version, last_version: Version) -> Metadata {
+    Metadata::new_transaction_backup(
+        first_version,
+        last_version,
+        format!("transaction_{}", first_version),
+    )
+}
+
+fn epoch_ending(epoch: u64, version: Version) -> Metadata {
+    Metadata::new_epoch_ending_backup(epoch, epoch, version, version, format!("epoch_{}", epoch))
+}
+
+fn test_view() -> MetadataView {
+    vec![
+        epoch_ending(0, 0),
+        epoch_ending(1, 150),
+        epoch_ending(2, 350),
+        snapshot(100),
+        snapshot(200),
+        snapshot(300),
+        snapshot(400),
+        transaction(0, 99),
+        transaction(100, 199),
+        transaction(200, 299),
+        transaction(300, 399),
+        transaction(400, 499),
+        // Duplicated, as if loaded from both the compacted and an old metadata file.
+        transaction(400, 499),
+    ]
+    .into()
+}
+
+#[test]
+fn test_plan_retention_keep_latest() {
+    let plan = test_view().plan_retention(2, None, &[]);
+
+    assert_eq!(
+        plan.drop_state_snapshots
+            .iter()
+            .map(|s| s.version)
+            .collect::<Vec<_>>(),
+        vec![100, 200],
+    );
+    assert_eq!(
+        plan.drop_transactions
+            .iter()
+            .map(|t| t.first_version)
+            .collect::<Vec<_>>(),
+        vec![0, 100, 200],
+    );
+    // 3 epoch endings, 2 snapshots and 2 transaction backups.
+    assert_eq!(plan.keep.len(), 7);
+
+    let kept: MetadataView = plan.keep.into();
+    assert_eq!(
+        kept.select_transaction_backups(0, Version::max_value())
+            .unwrap()
+            .iter()
+            .map(|t| t.first_version)
+            .collect::<Vec<_>>(),
+        vec![300, 400],
+    );
+    assert_eq!(
+        kept.select_state_snapshot(350).unwrap().unwrap().version,
+        300
+    );
+    assert!(kept.select_state_snapshot(299).unwrap().is_none());
+}
+
+#[test]
+fn test_plan_retention_epoch_interval() {
+    // Snapshots 100, 200, 300, 400 are in epochs 1, 2, 2, 3 respectively.
+    let plan = test_view().plan_retention(1, Some(2), &[0, 150, 350]);
+
+    assert_eq!(
+        plan.drop_state_snapshots
+            .iter()
+            .map(|s| s.version)
+            .collect::<Vec<_>>(),
+        vec![300],
+    );
+    assert_eq!(
+        plan.drop_transactions
+            .iter()
+            .map(|t| t.first_version)
+            .collect::<Vec<_>>(),
+        vec![0],
+    );
+}
+
+#[test]
+fn test_plan_retention_keeps_latest_transactions() {
+    let view: MetadataView = vec![snapshot(100), transaction(0, 49)].into();
+    let plan = view.plan_retention(1, None, &[]);
+
+    assert!(plan.drop_state_snapshots.is_empty());
+    assert!(plan.drop_transactions.is_empty());
+}
+
+fn incremental(base_version: Version, version: Version) -> Metadata {
+    Metadata::new_incremental_state_snapshot_backup(
+        base_version,
+        version,
+        format!("incremental_{}-{}", base_version, version),
+    )
+}
+
+fn test_view_with_incrementals() -> MetadataView {
+    vec![
+        snapshot(100),
+        snapshot(300),
+        incremental(100, 150),
+        incremental(150, 250),
+        incremental(100, 250),
+        incremental(250, 280),
+        // Based on a state snapshot not existing.
+        incremental(200, 290),
+        incremental(300, 320),
+        transaction(0, 499),
+    ]
+    .into()
+}
+
+#[test]
+fn test_select_state_snapshot_chain() {
+    let view = test_view_with_incrementals();
+    let chain_of = |target_version| {
+        view.select_state_snapshot_chain(target_version)
+            .unwrap()
+            .map(|chain| {
+                (
+                    chain.snapshot.version,
+                    chain
+                        .incrementals
+                        .iter()
+                        .map(|s| (s.base_version, s.version))
+                        .collect::<Vec<_>>(),
+                )
+            })
+    };
+
+    assert_eq!(chain_of(99), None);
+    assert_eq!(chain_of(149), Some((100, vec![])));
+    assert_eq!(chain_of(150), Some((100, vec![(100, 150)])));
+    // The shorter chain is preferred.
+    assert_eq!(chain_of(299), Some((100, vec![(100, 250), (250, 280)])));
+    assert_eq!(chain_of(300), Some((300, vec![])));
+    assert_eq!(
+        chain_of(Version::max_value()),
+        Some((300, vec![(300, 320)]))
+    );
+}
+
+#[test]
+fn test_plan_retention_incrementals() {
+    let plan = test_view_with_incrementals().plan_retention(1, None, &[]);
+
+    assert_eq!(
+        plan.drop_incremental_state_snapshots
+            .iter()
+            .map(|s| (s.base_version, s.version))
+            .collect::<Vec<_>>(),
+        vec![(100, 150), (100, 250), (150, 250), (250, 280), (200, 290)],
+    );
+    let kept: MetadataView = plan.keep.into();
+    assert_eq!(
+        kept.select_state_snapshot_chain(Version::max_value())
+            .unwrap()
+            .unwrap()
+            .incrementals
+            .len(),
+        1,
+    );
+}
+
+#[test]
+fn test_plan_restore() {
+    let view = test_view();
+
+    let plan = view.plan_restore(Version::max_value(), false).unwrap();
+    assert_eq!(plan.target_version, 499);
+    assert!(plan.reachable);
+    assert_eq!(plan.state_snapshot_backup.unwrap().version, 400);
+    assert_eq!(plan.replay_transactions_from_version, 401);
+    assert_eq!(plan.transaction_backups.len(), 5);
+    assert_eq!(plan.epoch_ending_backups.len(), 3);
+
+    let plan = view.plan_restore(250, false).unwrap();
+    assert_eq!(plan.target_version, 250);
+    assert!(plan.reachable);
+    assert_eq!(plan.state_snapshot_backup.unwrap().version, 200);
+    assert_eq!(plan.replay_transactions_from_version, 201);
+    assert_eq!(plan.transaction_backups.len(), 3);
+    assert_eq!(plan.epoch_ending_backups.len(), 2);
+
+    let plan = view.plan_restore(250, true /* replay_all */).unwrap();
+    assert!(plan.state_snapshot_backup.is_none());
+    assert_eq!(plan.replay_transactions_from_version, 0);
+
+    // Beyond what's backed up.
+    let plan = view.plan_restore(600, false).unwrap();
+    assert_eq!(plan.target_version, 499);
+    assert!(!plan.reachable);
+}
+
+#[test]
+fn test_plan_restore_with_gaps() {
+    let view: MetadataView = vec![
+        epoch_ending(0, 0),
+        epoch_ending(2, 350),
+        snapshot(300),
+        transaction(0, 99),
+        transaction(200, 299),
+        transaction(300, 399),
+    ]
+    .into();
+    let plan = view.plan_restore(Version::max_value(), false).unwrap();
+    assert_eq!(plan.target_version, 399);
+    assert!(!plan.reachable);
+    assert_eq!(plan.transaction_gaps, vec![(100, 199)]);
+    assert_eq!(plan.missing_epochs, vec![(1, 1)]);
+    assert!(view
+        .select_transaction_backups(0, Version::max_value())
+        .is_err());
+    assert!(view
+        .select_epoch_ending_backups(Version::max_value())
+        .is_err());
+
+    // Transactions before the state snapshot are fine to be garbage collected, unless replayed.
+    let view: MetadataView = vec![
+        epoch_ending(0, 0),
+        snapshot(300),
+        transaction(300, 399),
+        transaction(400, 499),
+    ]
+    .into();
+    let plan = view.plan_restore(Version::max_value(), false).unwrap();
+    assert!(plan.reachable);
+    assert!(plan.transaction_gaps.is_empty());
+    let plan = view.plan_restore(Version::max_value(), true).unwrap();
+    assert!(!plan.reachable);
+    assert_eq!(plan.transaction_gaps, vec![(0, 299)]);
+}
@@ -2,16 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::metadata::{
-    EpochEndingBackupMeta, Metadata, StateSnapshotBackupMeta, TransactionBackupMeta,
+    EpochEndingBackupMeta, IncrementalStateSnapshotBackupMeta, Metadata, StateSnapshotBackupMeta,
+    TransactionBackupMeta,
 };
-use anyhow::{anyhow, ensure, Result};
+use anyhow::{anyhow, bail, ensure, Result};
 use diem_types::transaction::Version;
 use itertools::Itertools;
-use std::{fmt, str::FromStr};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::min,
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
 
 pub struct MetadataView {
     epoch_ending_backups: Vec<EpochEndingBackupMeta>,
     state_snapshot_backups: Vec<StateSnapshotBackupMeta>,
+    incremental_state_snapshot_backups: Vec<IncrementalStateSnapshotBackupMeta>,
     transaction_backups: Vec<TransactionBackupMeta>,
 }
 
@@ -47,68 +55,365 @@ impl MetadataView {
             .map(Clone::clone))
     }
 
+    /// Selects a full state snapshot and a chain of incremental ones based on it, that together
+    /// restore the state at the latest possible version no newer than `target_version`. Among
+    /// chains reaching the same version, the shortest one is preferred.
+    pub fn select_state_snapshot_chain(
+        &self,
+        target_version: Version,
+    ) -> Result<Option<StateSnapshotChain>> {
+        // version => (full snapshot, incremental snapshots) restoring the state at the version.
+        let mut chains = HashMap::new();
+        for snapshot in &self.state_snapshot_backups {
+            if snapshot.version <= target_version {
+                chains.insert(snapshot.version, (snapshot, Vec::new()));
+            }
+        }
+        // Sorted by version, so the base of each incremental snapshot is visited before it.
+        for incremental in &self.incremental_state_snapshot_backups {
+            if incremental.version > target_version {
+                break;
+            }
+            let base = chains
+                .get(&incremental.base_version)
+                .map(|(snapshot, chain)| (*snapshot, chain.clone()));
+            if let Some((snapshot, mut chain)) = base {
+                chain.push(incremental);
+                let is_shorter = chains
+                    .get(&incremental.version)
+                    .map_or(true, |(_, existing)| existing.len() > chain.len());
+                if is_shorter {
+                    chains.insert(incremental.version, (snapshot, chain));
+                }
+            }
+        }
+
+        Ok(chains.into_iter().max_by_key(|(version, _)| *version).map(
+            |(_version, (snapshot, chain))| StateSnapshotChain {
+                snapshot: snapshot.clone(),
+                incrementals: chain.into_iter().cloned().collect(),
+            },
+        ))
+    }
+
     pub fn select_transaction_backups(
         &self,
         start_version: Version,
         target_version: Version,
     ) -> Result<Vec<TransactionBackupMeta>> {
-        // This can be more flexible, but for now we assume and check backups are continuous in
-        // range (which is always true when we backup from a single backup coordinator)
-        let mut next_ver = 0;
+        let (res, gaps) =
+            self.select_transaction_backups_with_gaps(start_version, target_version)?;
+        if let Some((first_missing, last_missing)) = gaps.first() {
+            bail!(
+                "Transactioon backup ranges not continuous, expecting version {}, got {}.",
+                first_missing,
+                last_missing + 1,
+            );
+        }
+        Ok(res)
+    }
+
+    /// Like `select_transaction_backups()`, but tolerates gaps in between the backups, returning
+    /// the missing version ranges (both ends inclusive) as well.
+    pub fn select_transaction_backups_with_gaps(
+        &self,
+        start_version: Version,
+        target_version: Version,
+    ) -> Result<(Vec<TransactionBackupMeta>, Vec<(Version, Version)>)> {
+        // This can be more flexible, but for now we assume and check backups don't overlap (which
+        // is always true when we backup from a single backup coordinator). They don't necessarily
+        // start from version 0 though, since old ones can be garbage collected.
+        let mut next_ver = None;
         let mut res = Vec::new();
+        let mut gaps = Vec::new();
         for backup in self.transaction_backups.iter().sorted() {
             if backup.first_version > target_version {
                 break;
             }
-            ensure!(
-                backup.first_version == next_ver,
-                "Transactioon backup ranges not continuous, expecting version {}, got {}.",
-                next_ver,
-                backup.first_version,
-            );
+            if let Some(next_ver) = next_ver {
+                ensure!(
+                    backup.first_version >= next_ver,
+                    "Transaction backup ranges overlap, expecting version {}, got {}.",
+                    next_ver,
+                    backup.first_version,
+                );
+                if backup.first_version > next_ver {
+                    gaps.push((next_ver, backup.first_version - 1));
+                }
+            }
 
             if backup.last_version >= start_version {
                 res.push(backup.clone());
             }
 
-            next_ver = backup.last_version + 1;
+            next_ver = Some(backup.last_version + 1);
         }
 
-        Ok(res)
+        Ok((res, gaps))
     }
 
     pub fn select_epoch_ending_backups(
         &self,
         target_version: Version,
     ) -> Result<Vec<EpochEndingBackupMeta>> {
-        // This can be more flexible, but for now we assume and check backups are continuous in
-        // range (which is always true when we backup from a single backup coordinator)
+        let (res, missing_epochs) = self.select_epoch_ending_backups_with_gaps(target_version)?;
+        if let Some((first_missing, last_missing)) = missing_epochs.first() {
+            bail!(
+                "Epoch ending backup ranges not continuous, expecting epoch {}, got {}.",
+                first_missing,
+                last_missing + 1,
+            );
+        }
+        Ok(res)
+    }
+
+    /// Like `select_epoch_ending_backups()`, but tolerates missing epochs, returning the missing
+    /// epoch ranges (both ends inclusive) as well. Epochs after the last backup selected can't be
+    /// told missing here, since which epoch `target_version` is in is unknown.
+    pub fn select_epoch_ending_backups_with_gaps(
+        &self,
+        target_version: Version,
+    ) -> Result<(Vec<EpochEndingBackupMeta>, Vec<(u64, u64)>)> {
+        // This can be more flexible, but for now we assume and check backups don't overlap (which
+        // is always true when we backup from a single backup coordinator)
         let mut next_epoch = 0;
         let mut res = Vec::new();
+        let mut missing_epochs = Vec::new();
         for backup in self.epoch_ending_backups.iter().sorted() {
             if backup.first_version > target_version {
                 break;
             }
 
             ensure!(
-                backup.first_epoch == next_epoch,
-                "Epoch ending backup ranges not continuous, expecting epoch {}, got {}.",
+                backup.first_epoch >= next_epoch,
+                "Epoch ending backup ranges overlap, expecting epoch {}, got {}.",
                 next_epoch,
                 backup.first_epoch,
             );
+            if backup.first_epoch > next_epoch {
+                missing_epochs.push((next_epoch, backup.first_epoch - 1));
+            }
             res.push(backup.clone());
 
             next_epoch = backup.last_epoch + 1;
         }
 
-        Ok(res)
+        Ok((res, missing_epochs))
+    }
+
+    /// Decides which backups to keep under a retention policy:
+    ///   1. The latest `keep_latest_state_snapshots` state snapshots are kept.
+    ///   2. If `state_snapshot_epoch_interval` is set, the first state snapshot in every window of
+    /// that many epochs is kept as well. `epoch_ending_versions` are the sorted versions of the
+    /// known epoch ending LedgerInfos, to tell which epoch a snapshot is in.
+    ///   3. Incremental state snapshots are kept if they can still be restored, i.e. the full
+    /// state snapshot at the start of their chain is kept.
+    ///   4. Transaction backups are kept back to the oldest kept state snapshot, and the latest one
+    /// is always kept so the backup coordinator carries on from where it was.
+    ///   5. Epoch ending backups are all kept, they are small and needed to verify everything else.
+    pub(crate) fn plan_retention(
+        &self,
+        keep_latest_state_snapshots: usize,
+        state_snapshot_epoch_interval: Option<u64>,
+        epoch_ending_versions: &[Version],
+    ) -> RetentionPlan {
+        let snapshots = &self.state_snapshot_backups;
+        let mut keep_snapshot = vec![false; snapshots.len()];
+        keep_snapshot
+            .iter_mut()
+            .rev()
+            .take(keep_latest_state_snapshots)
+            .for_each(|keep| *keep = true);
+        if let Some(interval) = state_snapshot_epoch_interval {
+            let mut last_window = None;
+            for (snapshot, keep) in snapshots.iter().zip(keep_snapshot.iter_mut()) {
+                let epoch = epoch_ending_versions.partition_point(|v| *v < snapshot.version) as u64;
+                let window = Some(epoch / interval);
+                if window != last_window {
+                    *keep = true;
+                    last_window = window;
+                }
+            }
+        }
+        let (kept_snapshots, dropped_snapshots): (Vec<_>, Vec<_>) = snapshots
+            .iter()
+            .zip(keep_snapshot)
+            .partition(|(_snapshot, keep)| *keep);
+        let oldest_kept_version = kept_snapshots.first().map(|(s, _keep)| s.version);
+
+        let mut restorable_versions = kept_snapshots
+            .iter()
+            .map(|(s, _keep)| s.version)
+            .collect::<HashSet<_>>();
+        let (kept_incrementals, dropped_incrementals): (Vec<_>, Vec<_>) = self
+            .incremental_state_snapshot_backups
+            .iter()
+            .partition(|s| {
+                // Sorted by version, so the base is decided before any incremental one on it.
+                if restorable_versions.contains(&s.base_version) {
+                    restorable_versions.insert(s.version);
+                    true
+                } else {
+                    false
+                }
+            });
+
+        let latest_transaction_version = self.get_storage_state().latest_transaction_version;
+        let (kept_transactions, dropped_transactions): (Vec<_>, Vec<_>) =
+            self.transaction_backups.iter().partition(|t| {
+                oldest_kept_version.map_or(true, |v| t.last_version >= v)
+                    || Some(t.last_version) == latest_transaction_version
+            });
+
+        let keep = self
+            .epoch_ending_backups
+            .iter()
+            .cloned()
+            .map(Metadata::EpochEndingBackup)
+            .chain(
+                kept_snapshots
+                    .into_iter()
+                    .map(|(s, _keep)| Metadata::StateSnapshotBackup(s.clone())),
+            )
+            .chain(
+                kept_incrementals
+                    .into_iter()
+                    .cloned()
+                    .map(Metadata::IncrementalStateSnapshotBackup),
+            )
+            .chain(
+                kept_transactions
+                    .into_iter()
+                    .cloned()
+                    .map(Metadata::TransactionBackup),
+            )
+            .collect();
+
+        RetentionPlan {
+            keep,
+            drop_state_snapshots: dropped_snapshots
+                .into_iter()
+                .map(|(s, _keep)| s.clone())
+                .collect(),
+            drop_incremental_state_snapshots: dropped_incrementals.into_iter().cloned().collect(),
+            drop_transactions: dropped_transactions.into_iter().cloned().collect(),
+        }
     }
+
+    /// Decides which backups to restore from to get the DB to `target_version`, see
+    /// `RestorePlan`. Unless `replay_all` is set, the state is restored from the state snapshot
+    /// chain reaching the latest version possible, and transactions after it are replayed.
+    pub fn plan_restore(&self, target_version: Version, replay_all: bool) -> Result<RestorePlan> {
+        let (transaction_backups, mut transaction_gaps) =
+            self.select_transaction_backups_with_gaps(0, target_version)?;
+        let last_backup = transaction_backups
+            .last()
+            .ok_or_else(|| anyhow!("No transaction backup found."))?;
+        let actual_target_version = min(last_backup.last_version, target_version);
+
+        let (epoch_ending_backups, missing_epochs) =
+            self.select_epoch_ending_backups_with_gaps(actual_target_version)?;
+        let state_snapshot_chain = if replay_all {
+            None
+        } else {
+            self.select_state_snapshot_chain(actual_target_version)?
+        };
+        let replay_transactions_from_version = match &state_snapshot_chain {
+            Some(chain) => {
+                chain
+                    .incrementals
+                    .last()
+                    .map_or(chain.snapshot.version, |b| b.version)
+                    + 1
+            }
+            None => 0,
+        };
+        // Transactions before the first backup are fine to be missing (garbage collected), as
+        // long as they don't need to be replayed.
+        let first_backed_up_version = transaction_backups[0].first_version;
+        if replay_transactions_from_version < first_backed_up_version
+            && replay_transactions_from_version <= actual_target_version
+        {
+            transaction_gaps.insert(
+                0,
+                (
+                    replay_transactions_from_version,
+                    first_backed_up_version - 1,
+                ),
+            );
+        }
+
+        let reachable = (target_version == Version::max_value()
+            || actual_target_version == target_version)
+            && transaction_gaps.is_empty()
+            && missing_epochs.is_empty();
+        let (state_snapshot_backup, incremental_state_snapshot_backups) = match state_snapshot_chain
+        {
+            Some(chain) => (Some(chain.snapshot), chain.incrementals),
+            None => (None, Vec::new()),
+        };
+
+        Ok(RestorePlan {
+            target_version: actual_target_version,
+            reachable,
+            epoch_ending_backups,
+            state_snapshot_backup,
+            incremental_state_snapshot_backups,
+            transaction_backups,
+            replay_transactions_from_version,
+            transaction_gaps,
+            missing_epochs,
+            download_size: None,
+        })
+    }
+}
+
+/// Result of `MetadataView::plan_restore()`, printed as JSON by `db-restore plan` and executed
+/// by `RestoreCoordinator`.
+#[derive(Deserialize, Serialize)]
+pub struct RestorePlan {
+    /// Version the DB is gonna be restored to, which is older than the requested target version
+    /// if the transaction backups don't reach that far.
+    pub target_version: Version,
+    /// Whether the requested target version, or the latest version in the backups if not
+    /// specified, can be restored to, i.e. it's reached and nothing is missing on the way.
+    pub reachable: bool,
+    pub epoch_ending_backups: Vec<EpochEndingBackupMeta>,
+    pub state_snapshot_backup: Option<StateSnapshotBackupMeta>,
+    /// To be applied in order on top of `state_snapshot_backup`.
+    pub incremental_state_snapshot_backups: Vec<IncrementalStateSnapshotBackupMeta>,
+    pub transaction_backups: Vec<TransactionBackupMeta>,
+    /// Transactions from this version on are replayed, the ones before are saved as they are.
+    pub replay_transactions_from_version: Version,
+    /// Version ranges (both ends inclusive) needed but not in any transaction backup.
+    pub transaction_gaps: Vec<(Version, Version)>,
+    /// Epoch ranges (both ends inclusive) not in any epoch ending backup.
+    pub missing_epochs: Vec<(u64, u64)>,
+    /// Total number of bytes to download, if estimated.
+    pub download_size: Option<u64>,
+}
+
+/// Result of `MetadataView::select_state_snapshot_chain()`.
+pub struct StateSnapshotChain {
+    pub snapshot: StateSnapshotBackupMeta,
+    /// To be applied in order on top of `snapshot`.
+    pub incrementals: Vec<IncrementalStateSnapshotBackupMeta>,
+}
+
+/// Result of `MetadataView::plan_retention()`.
+pub(crate) struct RetentionPlan {
+    /// Metadata of all backups to be kept.
+    pub keep: Vec<Metadata>,
+    pub drop_state_snapshots: Vec<StateSnapshotBackupMeta>,
+    pub drop_incremental_state_snapshots: Vec<IncrementalStateSnapshotBackupMeta>,
+    pub drop_transactions: Vec<TransactionBackupMeta>,
 }
 
 impl From<Vec<Metadata>> for MetadataView {
     fn from(metadata_vec: Vec<Metadata>) -> Self {
         let mut epoch_ending_backups = Vec::new();
         let mut state_snapshot_backups = Vec::new();
+        let mut incremental_state_snapshot_backups = Vec::new();
         let mut transaction_backups = Vec::new();
 
         for meta in metadata_vec {
@@ -116,12 +421,27 @@ impl From<Vec<Metadata>> for MetadataView {
                 Metadata::EpochEndingBackup(e) => epoch_ending_backups.push(e),
                 Metadata::StateSnapshotBackup(s) => state_snapshot_backups.push(s),
                 Metadata::TransactionBackup(t) => transaction_backups.push(t),
+                Metadata::IncrementalStateSnapshotBackup(s) => {
+                    incremental_state_snapshot_backups.push(s)
+                }
             }
         }
 
+        // The same metadata can be loaded twice, if a garbage collection is interrupted between
+        // saving the compacted metadata and deleting the old metadata files.
+        epoch_ending_backups.sort();
+        epoch_ending_backups.dedup();
+        state_snapshot_backups.sort();
+        state_snapshot_backups.dedup();
+        incremental_state_snapshot_backups.sort();
+        incremental_state_snapshot_backups.dedup();
+        transaction_backups.sort();
+        transaction_backups.dedup();
+
         Self {
             epoch_ending_backups,
             state_snapshot_backups,
+            incremental_state_snapshot_backups,
             transaction_backups,
         }
     }
@@ -62,11 +62,25 @@ pub struct Commands {
     /// Command line to save a line of metadata
     /// input env vars:
     ///     $FILE_NAME
-    /// stdin will be fed with a line of text with a trailing newline.
+    /// stdin will be fed with lines of text, each with a trailing newline.
     pub save_metadata_line: String,
     /// Command line to list all existing metadata file handles.
     /// expected stdout to stream out lines of file handles.
     pub list_metadata_files: String,
+    /// Command line to delete a file, only needed by the garbage collection.
+    /// input env vars:
+    ///     $FILE_HANDLE
+    /// expected to succeed if the file doesn't exist.
+    #[serde(default)]
+    pub delete_file: Option<String>,
+    /// Command line to get the size of a file in bytes as stored, only needed to estimate the
+    /// download size of a restore.
+    /// input env vars:
+    ///     $FILE_HANDLE
+    /// expected output on stdout:
+    ///     number of bytes, surrounding whitespaces are trimmed
+    #[serde(default)]
+    pub get_file_size: Option<String>,
 }
 
 #[derive(Clone, Default, Deserialize)]
@@ -5,13 +5,13 @@ use super::*;
 use crate::storage::{
     command_adapter::config::Commands,
     test_util::{
-        arb_backups, arb_metadata_files, test_save_and_list_metadata_files_impl,
-        test_write_and_read_impl,
+        arb_backups, arb_metadata_files, test_delete_file_impl,
+        test_save_and_list_metadata_files_impl, test_write_and_read_impl,
     },
 };
 use diem_temppath::TempPath;
 use futures::Future;
-use proptest::prelude::*;
+use proptest::{collection::vec, prelude::*};
 use std::str::FromStr;
 use tokio::runtime::Runtime;
 
@@ -29,6 +29,8 @@ fn get_store(tmpdir: &TempPath) -> Box<dyn BackupStorage> {
                 open_for_read = 'cat "$FOLDER/$FILE_HANDLE"'
                 save_metadata_line= 'cd "$FOLDER" && mkdir -p metadata && cd metadata && cat > $FILE_NAME'
                 list_metadata_files = 'cd "$FOLDER" && (test -d metadata && cd metadata && ls -1 || exec) | while read f; do echo metadata/$f; done'
+                delete_file = 'rm -f "$FOLDER/$FILE_HANDLE"'
+                get_file_size = 'wc -c < "$FOLDER/$FILE_HANDLE"'
             "#, tmpdir.path().to_str().unwrap()),
     ).unwrap();
 
@@ -57,6 +59,15 @@ proptest! {
         let tmpdir = TempPath::new();
         block_on(test_save_and_list_metadata_files_impl(get_store(&tmpdir), input));
     }
+
+    #[test]
+    fn test_delete_file(
+        backups in arb_backups(),
+        metadata_lines in vec(any::<TextLine>(), 0..10),
+    ) {
+        let tmpdir = TempPath::new();
+        block_on(test_delete_file_impl(get_store(&tmpdir), backups, metadata_lines));
+    }
 }
 
 fn dummy_store(cmd: &str) -> CommandAdapter {
@@ -67,6 +78,8 @@ fn dummy_store(cmd: &str) -> CommandAdapter {
             open_for_read: cmd.to_string(),
             save_metadata_line: cmd.to_string(),
             list_metadata_files: cmd.to_string(),
+            delete_file: Some(cmd.to_string()),
+            get_file_size: Some(cmd.to_string()),
         },
         env_vars: Vec::new(),
     })
@@ -113,6 +126,12 @@ async fn assert_commands_error(cmd: &str) {
 
     // list_metadata_files
     assert!(store.list_metadata_files().await.is_err());
+
+    // delete_file
+    assert!(store.delete_file(handle).await.is_err());
+
+    // get_file_size
+    assert!(store.get_file_size(handle).await.is_err());
 }
 
 async fn assert_commands_okay(cmd: &str) {
@@ -148,7 +167,30 @@ async fn assert_commands_okay(cmd: &str) {
         .unwrap();
 
     // list_metadata_files
-    assert_eq!(store.list_metadata_files().await.unwrap(), vec!["okay"])
+    assert_eq!(store.list_metadata_files().await.unwrap(), vec!["okay"]);
+
+    // delete_file
+    store.delete_file(handle).await.unwrap();
+}
+
+#[test]
+fn test_delete_file_not_configured() {
+    let mut store = dummy_store("true");
+    store.config.commands.delete_file = None;
+    assert!(Runtime::new()
+        .unwrap()
+        .block_on(store.delete_file("handle"))
+        .is_err());
+}
+
+#[test]
+fn test_get_file_size_not_configured() {
+    let mut store = dummy_store("echo 1");
+    store.config.commands.get_file_size = None;
+    assert!(Runtime::new()
+        .unwrap()
+        .block_on(store.get_file_size("handle"))
+        .is_err());
 }
 
 #[test]
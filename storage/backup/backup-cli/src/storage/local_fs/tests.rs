@@ -3,11 +3,11 @@
 
 use super::*;
 use crate::storage::test_util::{
-    arb_backups, arb_metadata_files, test_save_and_list_metadata_files_impl,
+    arb_backups, arb_metadata_files, test_delete_file_impl, test_save_and_list_metadata_files_impl,
     test_write_and_read_impl,
 };
 use diem_temppath::TempPath;
-use proptest::prelude::*;
+use proptest::{collection::vec, prelude::*};
 use tokio::runtime::Runtime;
 
 proptest! {
@@ -36,4 +36,17 @@ proptest! {
         let rt = Runtime::new().unwrap();
         rt.block_on(test_save_and_list_metadata_files_impl(Box::new(store), input));
     }
+
+    #[test]
+    fn test_delete_file(
+        backups in arb_backups(),
+        metadata_lines in vec(any::<TextLine>(), 0..10),
+    ) {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let store = LocalFs::new(tmpdir.path().to_path_buf());
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_delete_file_impl(Box::new(store), backups, metadata_lines));
+    }
 }
@@ -160,6 +160,9 @@ pub trait BackupStorage: Send + Sync {
     /// is straightforward and acceptable.
     /// See `list_metadata_files`.
     async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()>;
+    /// Same as `save_metadata_line()`, but saves multiple entries in one file, used to compact
+    /// existing metadata files into one.
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()>;
     /// The backup system always asks for all metadata files and cache and build index on top of
     /// the content of them. This means:
     ///   1. The storage is free to reorganise the metadata files, like combining multiple ones to
@@ -167,6 +170,13 @@ pub trait BackupStorage: Send + Sync {
     ///   2. But the cache does expect the content stays the same for a file handle, so when
     /// reorganising metadata files, give them new unique names.
     async fn list_metadata_files(&self) -> Result<Vec<FileHandle>>;
+    /// Deletes a file, either one created by `create_for_write()` or a metadata file listed by
+    /// `list_metadata_files()`. Deleting a file that doesn't exist is not an error, so that an
+    /// interrupted garbage collection can be simply retried.
+    async fn delete_file(&self, file_handle: &FileHandleRef) -> Result<()>;
+    /// Returns the size in bytes of a file created by `create_for_write()`, as it's stored, i.e.
+    /// what `open_for_read()` needs to download. Only used to estimate the cost of a restore.
+    async fn get_file_size(&self, file_handle: &FileHandleRef) -> Result<u64>;
 }
 
 #[derive(StructOpt)]
@@ -11,7 +11,7 @@ use proptest::{
     collection::{hash_map, vec},
     prelude::*,
 };
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, path::Path, str::FromStr};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 fn to_file_name(backup_name: &str, file_name: &str) -> String {
@@ -43,10 +43,82 @@ pub async fn test_write_and_read_impl(
             let mut buf = Vec::new();
             file.read_to_end(&mut buf).await.unwrap();
             assert_eq!(content, &buf);
+            assert_eq!(
+                store.get_file_size(&handle).await.unwrap(),
+                content.len() as u64
+            );
         }
     }
 }
 
+pub async fn test_delete_file_impl(
+    store: Box<dyn BackupStorage>,
+    backups: HashMap<ShellSafeName, HashMap<ShellSafeName, Vec<u8>>>,
+    metadata_lines: Vec<TextLine>,
+) {
+    let mut file_handles = Vec::new();
+    for (backup_name, files) in &backups {
+        let backup_handle = store.create_backup(backup_name).await.unwrap();
+        for (name, content) in files {
+            let (handle, mut file) = store.create_for_write(&backup_handle, name).await.unwrap();
+            file.write_all(content).await.unwrap();
+            file.shutdown().await.unwrap();
+            file_handles.push(handle);
+        }
+    }
+    let metadata_name = ShellSafeName::from_str("compacted.meta").unwrap();
+    store
+        .save_metadata_lines(&metadata_name, &metadata_lines)
+        .await
+        .unwrap();
+    let metadata_files = store.list_metadata_files().await.unwrap();
+    assert_eq!(metadata_files.len(), 1);
+    let mut buf = String::new();
+    store
+        .open_for_read(&metadata_files[0])
+        .await
+        .unwrap()
+        .read_to_string(&mut buf)
+        .await
+        .unwrap();
+    assert_eq!(
+        buf,
+        metadata_lines.iter().map(AsRef::<str>::as_ref).join("")
+    );
+
+    let (deleted, kept) = file_handles.split_at(file_handles.len() / 2);
+    for handle in deleted.iter().chain(metadata_files.iter()) {
+        store.delete_file(handle).await.unwrap();
+        // deleting again is fine
+        store.delete_file(handle).await.unwrap();
+    }
+
+    for handle in deleted {
+        let mut buf = Vec::new();
+        assert!(async {
+            store
+                .open_for_read(handle)
+                .await?
+                .read_to_end(&mut buf)
+                .await?;
+            Result::<()>::Ok(())
+        }
+        .await
+        .is_err());
+    }
+    for handle in kept {
+        let mut buf = Vec::new();
+        store
+            .open_for_read(handle)
+            .await
+            .unwrap()
+            .read_to_end(&mut buf)
+            .await
+            .unwrap();
+    }
+    assert!(store.list_metadata_files().await.unwrap().is_empty());
+}
+
 pub fn arb_backups(
 ) -> impl Strategy<Value = HashMap<ShellSafeName, HashMap<ShellSafeName, Vec<u8>>>> {
     hash_map(
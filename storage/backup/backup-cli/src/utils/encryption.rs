@@ -0,0 +1,587 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Client side authenticated encryption of backup files, independent of the storage backend.
+//!
+//! A sealed file is laid out as:
+//!
+//! ```text
+//! | MAGIC | salt | segment | segment | ... | last segment |
+//! ```
+//!
+//! where each segment is:
+//!
+//! ```text
+//! | is_last (u8) | len(ciphertext) (u32 BE) | ciphertext, with the AES-256-GCM tag |
+//! ```
+//!
+//! Every file is encrypted with its own key, derived by HKDF-SHA3-256 from the backup encryption
+//! key and the random `salt` of the file. A segment holds at most `SEGMENT_SIZE` bytes of
+//! plaintext, and its nonce is made of its index in the file and the `is_last` flag, so
+//! reordering, dropping or truncating segments fails the authentication.
+//!
+//! Manifests are not sealed, instead they record an `EncryptionInfo` if the files they refer to
+//! are, so the restore side knows which key to open them with.
+
+use aes_gcm::{
+    aead::{generic_array::GenericArray, AeadInPlace, NewAead},
+    Aes256Gcm,
+};
+use anyhow::{anyhow, bail, ensure, Result};
+use diem_config::config::{PersistableConfig, SecureBackend};
+use diem_crypto::{compat::Sha3_256, hkdf::Hkdf, HashValue};
+use diem_secure_storage::{KVStorage, Storage};
+use rand::random;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::min,
+    io,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use structopt::StructOpt;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+pub const KEY_LEN: usize = 32;
+
+const MAGIC: [u8; 8] = *b"DIEMBKE1";
+const SALT_LEN: usize = 32;
+const SEGMENT_SIZE: usize = 64 * 1024;
+const SEGMENT_HEADER_LEN: usize = 1 + 4;
+const TAG_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_ID_DOMAIN: &[u8] = b"DIEM_BACKUP_ENCRYPTION_KEY_ID";
+const FILE_KEY_INFO: &[u8] = b"DIEM_BACKUP_FILE_KEY";
+
+/// Key the backup files are sealed with.
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+impl EncryptionKey {
+    pub fn new(key: [u8; KEY_LEN]) -> Self {
+        Self(key)
+    }
+
+    /// Parses a hex encoded key, ignoring surrounding whitespaces.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let bytes = hex::decode(hex.trim())?;
+        ensure!(
+            bytes.len() == KEY_LEN,
+            "Encryption key should be {} bytes, got {}.",
+            KEY_LEN,
+            bytes.len(),
+        );
+        let mut key = [0u8; KEY_LEN];
+        key.copy_from_slice(&bytes);
+        Ok(Self(key))
+    }
+
+    /// Identifies the key in the manifests without revealing it.
+    pub fn id(&self) -> HashValue {
+        HashValue::sha3_256_of(&[KEY_ID_DOMAIN, &self.0].concat())
+    }
+
+    fn file_cipher(&self, salt: &[u8]) -> io::Result<Aes256Gcm> {
+        let file_key = Hkdf::<Sha3_256>::extract_then_expand(
+            Some(salt),
+            &self.0,
+            Some(FILE_KEY_INFO),
+            KEY_LEN,
+        )
+        .map_err(|e| invalid_data(format!("Failed to derive file key: {:?}", e)))?;
+        Ok(Aes256Gcm::new(GenericArray::from_slice(&file_key)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionScheme {
+    /// See the module level doc.
+    Aes256GcmSegments,
+}
+
+/// Recorded in a manifest when the files it refers to are sealed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EncryptionInfo {
+    pub scheme: EncryptionScheme,
+    /// `EncryptionKey::id()` of the key the files are sealed with.
+    pub key_id: HashValue,
+}
+
+impl EncryptionInfo {
+    pub fn new(key: &EncryptionKey) -> Self {
+        Self {
+            scheme: EncryptionScheme::Aes256GcmSegments,
+            key_id: key.id(),
+        }
+    }
+
+    /// Returns the key to open the files of a manifest carrying `info`. Fails if the files are
+    /// sealed but `key` is missing or is not the one they are sealed with. Files not sealed are
+    /// read as is when no key is provided; when one is, they are rejected unless
+    /// `allow_plaintext`, so that unsealed files can't pass for the sealed backup.
+    pub fn key_for(
+        info: Option<&EncryptionInfo>,
+        key: Option<&Arc<EncryptionKey>>,
+        allow_plaintext: bool,
+    ) -> Result<Option<Arc<EncryptionKey>>> {
+        match (info, key) {
+            (None, None) => Ok(None),
+            (None, Some(key)) => {
+                ensure!(
+                    allow_plaintext,
+                    "Backup is not encrypted, but encryption key {} is provided. Pass \
+                     --allow-plaintext to read it anyway.",
+                    key.id(),
+                );
+                Ok(None)
+            }
+            (Some(info), None) => bail!(
+                "Backup is encrypted with key {}, but no encryption key is provided.",
+                info.key_id,
+            ),
+            (Some(info), Some(key)) => {
+                ensure!(
+                    info.key_id == key.id(),
+                    "Backup is encrypted with key {}, but the provided key is {}.",
+                    info.key_id,
+                    key.id(),
+                );
+                Ok(Some(Arc::clone(key)))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Default, StructOpt)]
+pub struct EncryptionOpt {
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "File holding the hex encoded 32 bytes key to encrypt new backups with, and to \
+        decrypt encrypted backups with. Backups are not encrypted if no key is provided."
+    )]
+    pub encryption_key_file: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with = "encryption-key-file",
+        requires = "encryption-key-name",
+        help = "YAML file configuring the secure storage to read the encryption key from, \
+        same as a `SecureBackend` in the node config."
+    )]
+    pub encryption_key_secure_backend: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        requires = "encryption-key-secure-backend",
+        help = "Name of the hex encoded encryption key in the secure storage."
+    )]
+    pub encryption_key_name: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Read backups that are not encrypted even though an encryption key is provided, \
+        which are rejected otherwise."
+    )]
+    pub allow_plaintext: bool,
+}
+
+impl EncryptionOpt {
+    pub fn load_key(&self) -> Result<Option<Arc<EncryptionKey>>> {
+        let hex = if let Some(path) = &self.encryption_key_file {
+            std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read encryption key file {:?}: {}", path, e))?
+        } else if let Some(path) = &self.encryption_key_secure_backend {
+            let name = self
+                .encryption_key_name
+                .as_ref()
+                .ok_or_else(|| anyhow!("Encryption key name not provided."))?;
+            let storage = Storage::from(&SecureBackend::load_config(path)?);
+            storage.get::<String>(name)?.value
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(Arc::new(EncryptionKey::from_hex(&hex)?)))
+    }
+}
+
+/// Returns a writer sealing everything written to it into `inner`. The last segment is sealed on
+/// `shutdown()`, without which the file can't be opened.
+pub fn seal(
+    key: &EncryptionKey,
+    inner: Box<dyn AsyncWrite + Send + Unpin>,
+) -> Result<Box<dyn AsyncWrite + Send + Unpin>> {
+    let salt: [u8; SALT_LEN] = random();
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&salt);
+    Ok(Box::new(SealingWriter {
+        inner,
+        cipher: key.file_cipher(&salt)?,
+        next_segment: 0,
+        plaintext: Vec::with_capacity(SEGMENT_SIZE),
+        pending: header,
+        pending_written: 0,
+        finished: false,
+    }))
+}
+
+/// Returns a reader of the content sealed in `inner`, failing with `InvalidData` on anything not
+/// authenticated.
+pub fn open(
+    key: Arc<EncryptionKey>,
+    inner: Box<dyn AsyncRead + Send + Unpin>,
+) -> Box<dyn AsyncRead + Send + Unpin> {
+    Box::new(OpeningReader {
+        inner,
+        key,
+        cipher: None,
+        next_segment: 0,
+        buf: vec![0; MAGIC.len() + SALT_LEN],
+        filled: 0,
+        plaintext: Vec::new(),
+        plaintext_read: 0,
+        state: OpeningState::Header,
+    })
+}
+
+fn segment_nonce(index: u64, is_last: bool) -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..8].copy_from_slice(&index.to_be_bytes());
+    nonce[NONCE_LEN - 1] = is_last as u8;
+    nonce
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+struct SealingWriter {
+    inner: Box<dyn AsyncWrite + Send + Unpin>,
+    cipher: Aes256Gcm,
+    next_segment: u64,
+    /// Plaintext of the segment being filled.
+    plaintext: Vec<u8>,
+    /// Sealed bytes not yet written to `inner`.
+    pending: Vec<u8>,
+    pending_written: usize,
+    finished: bool,
+}
+
+impl SealingWriter {
+    fn seal_segment(&mut self, is_last: bool) -> io::Result<()> {
+        let mut segment = std::mem::replace(&mut self.plaintext, Vec::with_capacity(SEGMENT_SIZE));
+        let nonce = segment_nonce(self.next_segment, is_last);
+        self.cipher
+            .encrypt_in_place(GenericArray::from_slice(&nonce), &[], &mut segment)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Failed to seal segment."))?;
+        self.pending.push(is_last as u8);
+        self.pending
+            .extend_from_slice(&(segment.len() as u32).to_be_bytes());
+        self.pending.extend_from_slice(&segment);
+        self.next_segment += 1;
+        Ok(())
+    }
+
+    fn poll_write_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.pending_written < self.pending.len() {
+            let n = futures::ready!(
+                Pin::new(&mut self.inner).poll_write(cx, &self.pending[self.pending_written..])
+            )?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pending_written += n;
+        }
+        self.pending.clear();
+        self.pending_written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for SealingWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.finished {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Write after shutdown.",
+            )));
+        }
+        futures::ready!(this.poll_write_pending(cx))?;
+        // A full segment is only sealed once more data comes, since the last one is marked.
+        if this.plaintext.len() == SEGMENT_SIZE {
+            this.seal_segment(false /* is_last */)?;
+            futures::ready!(this.poll_write_pending(cx))?;
+        }
+        let n = min(buf.len(), SEGMENT_SIZE - this.plaintext.len());
+        this.plaintext.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        futures::ready!(this.poll_write_pending(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if !this.finished {
+            this.seal_segment(true /* is_last */)?;
+            this.finished = true;
+        }
+        futures::ready!(this.poll_write_pending(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+enum OpeningState {
+    Header,
+    SegmentHeader,
+    Segment {
+        is_last: bool,
+    },
+    /// After the last segment, expecting the end of the file.
+    Trailer,
+    Done,
+}
+
+struct OpeningReader {
+    inner: Box<dyn AsyncRead + Send + Unpin>,
+    key: Arc<EncryptionKey>,
+    cipher: Option<Aes256Gcm>,
+    next_segment: u64,
+    /// Raw bytes expected by the current state.
+    buf: Vec<u8>,
+    filled: usize,
+    /// Plaintext of the last opened segment.
+    plaintext: Vec<u8>,
+    plaintext_read: usize,
+    state: OpeningState,
+}
+
+impl OpeningReader {
+    fn expect(&mut self, len: usize, state: OpeningState) {
+        self.buf = vec![0; len];
+        self.filled = 0;
+        self.state = state;
+    }
+
+    /// Reads into `buf` until it's filled, returns `false` if the file ends before that.
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<bool>> {
+        while self.filled < self.buf.len() {
+            let mut read_buf = ReadBuf::new(&mut self.buf[self.filled..]);
+            futures::ready!(Pin::new(&mut self.inner).poll_read(cx, &mut read_buf))?;
+            let n = read_buf.filled().len();
+            if n == 0 {
+                return Poll::Ready(Ok(false));
+            }
+            self.filled += n;
+        }
+        Poll::Ready(Ok(true))
+    }
+
+    fn open_header(&mut self) -> io::Result<()> {
+        let (magic, salt) = self.buf.split_at(MAGIC.len());
+        if magic != MAGIC {
+            return Err(invalid_data("Not an encrypted backup file.".to_string()));
+        }
+        self.cipher = Some(self.key.file_cipher(salt)?);
+        self.expect(SEGMENT_HEADER_LEN, OpeningState::SegmentHeader);
+        Ok(())
+    }
+
+    fn open_segment_header(&mut self) -> io::Result<()> {
+        let is_last = match self.buf[0] {
+            0 => false,
+            1 => true,
+            flag => return Err(invalid_data(format!("Bad segment flag {}.", flag))),
+        };
+        let mut len_bytes = [0u8; 4];
+        len_bytes.copy_from_slice(&self.buf[1..]);
+        let len = u32::from_be_bytes(len_bytes) as usize;
+        if len < TAG_LEN || len > SEGMENT_SIZE + TAG_LEN {
+            return Err(invalid_data(format!("Bad segment length {}.", len)));
+        }
+        self.expect(len, OpeningState::Segment { is_last });
+        Ok(())
+    }
+
+    fn open_segment(&mut self, is_last: bool) -> io::Result<()> {
+        let mut segment = std::mem::take(&mut self.buf);
+        let nonce = segment_nonce(self.next_segment, is_last);
+        self.cipher
+            .as_ref()
+            .expect("Cipher is set after the header.")
+            .decrypt_in_place(GenericArray::from_slice(&nonce), &[], &mut segment)
+            .map_err(|_| {
+                invalid_data(format!(
+                    "Segment {} failed authentication.",
+                    self.next_segment
+                ))
+            })?;
+        self.plaintext = segment;
+        self.plaintext_read = 0;
+        self.next_segment += 1;
+        if is_last {
+            self.expect(1, OpeningState::Trailer);
+        } else {
+            self.expect(SEGMENT_HEADER_LEN, OpeningState::SegmentHeader);
+        }
+        Ok(())
+    }
+}
+
+impl AsyncRead for OpeningReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        out: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.plaintext_read < this.plaintext.len() {
+                let n = min(out.remaining(), this.plaintext.len() - this.plaintext_read);
+                out.put_slice(&this.plaintext[this.plaintext_read..this.plaintext_read + n]);
+                this.plaintext_read += n;
+                return Poll::Ready(Ok(()));
+            }
+            if let OpeningState::Done = this.state {
+                return Poll::Ready(Ok(()));
+            }
+
+            let complete = futures::ready!(this.poll_fill(cx))?;
+            match this.state {
+                OpeningState::Trailer => {
+                    if complete {
+                        return Poll::Ready(Err(invalid_data(
+                            "Data found after the last segment.".to_string(),
+                        )));
+                    }
+                    this.state = OpeningState::Done;
+                }
+                _ if !complete => {
+                    return Poll::Ready(Err(invalid_data(
+                        "Encrypted backup file is truncated.".to_string(),
+                    )));
+                }
+                OpeningState::Header => this.open_header()?,
+                OpeningState::SegmentHeader => this.open_segment_header()?,
+                OpeningState::Segment { is_last } => this.open_segment(is_last)?,
+                OpeningState::Done => unreachable!(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::{collection::vec, prelude::*};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn key(byte: u8) -> Arc<EncryptionKey> {
+        Arc::new(EncryptionKey::new([byte; KEY_LEN]))
+    }
+
+    async fn seal_bytes(key: &EncryptionKey, plaintext: &[u8], write_size: usize) -> Vec<u8> {
+        let (writer, mut reader) = tokio::io::duplex(1024);
+        let read = tokio::spawn(async move {
+            let mut sealed = Vec::new();
+            reader.read_to_end(&mut sealed).await.unwrap();
+            sealed
+        });
+        let mut writer = seal(key, Box::new(writer)).unwrap();
+        for chunk in plaintext.chunks(write_size) {
+            writer.write_all(chunk).await.unwrap();
+        }
+        writer.shutdown().await.unwrap();
+        drop(writer);
+        read.await.unwrap()
+    }
+
+    async fn open_bytes(key: Arc<EncryptionKey>, sealed: Vec<u8>) -> io::Result<Vec<u8>> {
+        let mut reader = open(key, Box::new(std::io::Cursor::new(sealed)));
+        let mut plaintext = Vec::new();
+        reader.read_to_end(&mut plaintext).await?;
+        Ok(plaintext)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(10))]
+
+        #[test]
+        fn test_seal_open(
+            plaintext in vec(any::<u8>(), 0..3 * SEGMENT_SIZE),
+            write_size in 1..2 * SEGMENT_SIZE,
+        ) {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let key = key(1);
+            let sealed = rt.block_on(seal_bytes(&key, &plaintext, write_size));
+            prop_assert_ne!(&sealed[MAGIC.len() + SALT_LEN..], &plaintext[..]);
+            prop_assert_eq!(rt.block_on(open_bytes(key, sealed)).unwrap(), plaintext);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_tampered() {
+        let key = key(1);
+        let plaintext = vec![7u8; 2 * SEGMENT_SIZE + 10];
+        let sealed = seal_bytes(&key, &plaintext, 1000).await;
+        let segment_len = SEGMENT_HEADER_LEN + SEGMENT_SIZE + TAG_LEN;
+        let header_len = MAGIC.len() + SALT_LEN;
+
+        // wrong key
+        assert!(open_bytes(self::key(2), sealed.clone()).await.is_err());
+        // flipped bit
+        let mut flipped = sealed.clone();
+        flipped[header_len + SEGMENT_HEADER_LEN + 5] ^= 1;
+        assert!(open_bytes(Arc::clone(&key), flipped).await.is_err());
+        // truncated at a segment boundary
+        let truncated = sealed[..header_len + 2 * segment_len].to_vec();
+        assert!(open_bytes(Arc::clone(&key), truncated).await.is_err());
+        // segments reordered
+        let mut reordered = sealed[..header_len].to_vec();
+        reordered
+            .extend_from_slice(&sealed[header_len + segment_len..header_len + 2 * segment_len]);
+        reordered.extend_from_slice(&sealed[header_len..header_len + segment_len]);
+        reordered.extend_from_slice(&sealed[header_len + 2 * segment_len..]);
+        assert!(open_bytes(Arc::clone(&key), reordered).await.is_err());
+        // trailing data
+        let mut trailing = sealed.clone();
+        trailing.push(0);
+        assert!(open_bytes(Arc::clone(&key), trailing).await.is_err());
+
+        assert_eq!(open_bytes(key, sealed).await.unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_key_for() {
+        let key1 = key(1);
+        let info = EncryptionInfo::new(&key1);
+
+        assert!(EncryptionInfo::key_for(None, None, false)
+            .unwrap()
+            .is_none());
+        assert!(EncryptionInfo::key_for(None, Some(&key1), false).is_err());
+        assert!(EncryptionInfo::key_for(None, Some(&key1), true)
+            .unwrap()
+            .is_none());
+        assert!(EncryptionInfo::key_for(Some(&info), None, true).is_err());
+        assert!(EncryptionInfo::key_for(Some(&info), Some(&key(2)), true).is_err());
+        assert_eq!(
+            EncryptionInfo::key_for(Some(&info), Some(&key1), false)
+                .unwrap()
+                .unwrap()
+                .id(),
+            key1.id(),
+        );
+    }
+}
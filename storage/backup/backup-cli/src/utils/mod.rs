@@ -2,16 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod backup_service_client;
+pub mod encryption;
 pub(crate) mod error_notes;
 pub mod read_record_bytes;
+pub mod restore_journal;
 pub mod storage_ext;
 pub(crate) mod stream;
 
 #[cfg(test)]
 pub mod test_utils;
 
+use crate::utils::{
+    encryption::{EncryptionKey, EncryptionOpt},
+    restore_journal::RestoreJournal,
+};
 use anyhow::{anyhow, Result};
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_crypto::HashValue;
 use diem_infallible::duration_since_epoch;
 use diem_jellyfish_merkle::{restore::JellyfishMerkleRestore, NodeBatch, TreeWriter};
@@ -36,6 +42,9 @@ pub struct GlobalBackupOpt {
         help = "Maximum chunk file size in bytes."
     )]
     pub max_chunk_size: usize,
+
+    #[structopt(flatten)]
+    pub encryption: EncryptionOpt,
 }
 
 #[derive(Clone, StructOpt)]
@@ -92,6 +101,12 @@ pub struct GlobalRestoreOpt {
 
     #[structopt(flatten)]
     pub concurernt_downloads: ConcurrentDownloadsOpt,
+
+    #[structopt(flatten)]
+    pub max_chunks_in_flight: MaxChunksInFlightOpt,
+
+    #[structopt(flatten)]
+    pub encryption: EncryptionOpt,
 }
 
 pub enum RestoreRunMode {
@@ -122,14 +137,20 @@ impl RestoreRunMode {
         }
     }
 
+    /// If `resume` is set, the receiver continues from the accounts already restored to the DB.
     pub fn get_state_restore_receiver(
         &self,
         version: Version,
         expected_root_hash: HashValue,
+        resume: bool,
     ) -> Result<JellyfishMerkleRestore<AccountStateBlob>> {
         match self {
             Self::Restore { restore_handler } => {
-                restore_handler.get_state_restore_receiver(version, expected_root_hash)
+                if resume {
+                    restore_handler.get_resuming_state_restore_receiver(version, expected_root_hash)
+                } else {
+                    restore_handler.get_state_restore_receiver(version, expected_root_hash)
+                }
             }
             Self::Verify => JellyfishMerkleRestore::new_overwrite(
                 Arc::new(MockTreeWriter),
@@ -138,6 +159,27 @@ impl RestoreRunMode {
             ),
         }
     }
+
+    /// Applies an incremental state snapshot, see `RestoreHandler::save_account_state_changes()`.
+    /// Does nothing in verify mode, where the state tree at the base version is not available
+    /// for the resulting root hash to be checked.
+    pub fn save_account_state_changes(
+        &self,
+        base_version: Version,
+        version: Version,
+        blobs: Vec<(HashValue, AccountStateBlob)>,
+        expected_root_hash: HashValue,
+    ) -> Result<()> {
+        match self {
+            Self::Restore { restore_handler } => restore_handler.save_account_state_changes(
+                base_version,
+                version,
+                blobs,
+                expected_root_hash,
+            ),
+            Self::Verify => Ok(()),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -146,6 +188,10 @@ pub struct GlobalRestoreOptions {
     pub trusted_waypoints: Arc<HashMap<Version, Waypoint>>,
     pub run_mode: Arc<RestoreRunMode>,
     pub concurrent_downloads: usize,
+    pub max_chunks_in_flight: usize,
+    pub encryption_key: Option<Arc<EncryptionKey>>,
+    pub allow_plaintext: bool,
+    pub journal: Arc<RestoreJournal>,
 }
 
 impl TryFrom<GlobalRestoreOpt> for GlobalRestoreOptions {
@@ -154,12 +200,20 @@ impl TryFrom<GlobalRestoreOpt> for GlobalRestoreOptions {
     fn try_from(opt: GlobalRestoreOpt) -> Result<Self> {
         let target_version = opt.target_version.unwrap_or(Version::max_value());
         let concurrent_downloads = opt.concurernt_downloads.get();
+        let max_chunks_in_flight = opt.max_chunks_in_flight.get(concurrent_downloads);
+        let encryption_key = opt.encryption.load_key()?;
+        let journal = match &opt.db_dir {
+            Some(db_dir) => RestoreJournal::load(db_dir)?,
+            None => RestoreJournal::new_in_memory(),
+        };
         let run_mode = if let Some(db_dir) = &opt.db_dir {
             let restore_handler = Arc::new(DiemDB::open(
                 db_dir,
                 false, /* read_only */
                 None,  /* pruner */
+                None,  /* ledger_pruner */
                 opt.rocksdb_opt.into(),
+                SecondaryIndexesConfig::default(),
             )?)
             .get_restore_handler();
             RestoreRunMode::Restore { restore_handler }
@@ -171,6 +225,10 @@ impl TryFrom<GlobalRestoreOpt> for GlobalRestoreOptions {
             trusted_waypoints: Arc::new(opt.trusted_waypoints.verify()?),
             run_mode: Arc::new(run_mode),
             concurrent_downloads,
+            max_chunks_in_flight,
+            encryption_key,
+            allow_plaintext: opt.encryption.allow_plaintext,
+            journal: Arc::new(journal),
         })
     }
 }
@@ -222,6 +280,25 @@ impl ConcurrentDownloadsOpt {
     }
 }
 
+#[derive(Clone, Copy, Default, StructOpt)]
+pub struct MaxChunksInFlightOpt {
+    #[structopt(
+        long,
+        help = "[Defaults to twice the number of concurrent downloads] \
+        number of state snapshot chunks downloaded and prepared ahead of the one being added to \
+        the state tree, which bounds the memory they take up."
+    )]
+    max_chunks_in_flight: Option<usize>,
+}
+
+impl MaxChunksInFlightOpt {
+    pub fn get(&self, concurrent_downloads: usize) -> usize {
+        self.max_chunks_in_flight
+            .unwrap_or(concurrent_downloads * 2)
+            .max(1)
+    }
+}
+
 pub(crate) fn should_cut_chunk(chunk: &[u8], record: &[u8], max_chunk_size: usize) -> bool {
     !chunk.is_empty() && chunk.len() + record.len() + size_of::<u32>() > max_chunk_size
 }
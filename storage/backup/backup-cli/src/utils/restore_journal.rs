@@ -0,0 +1,138 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Records how far a restore has gone for each backup manifest, so that a restore interrupted
+//! (e.g. by a pod restart) resumes from where it was instead of starting over.
+//!
+//! The journal is a JSON file in the target DB directory, so it goes away together with the DB
+//! if the latter is wiped. It's rewritten as a whole (to a temporary file, then renamed) on every
+//! update, which is cheap since there's one small entry per manifest.
+
+use crate::storage::{FileHandle, FileHandleRef};
+use anyhow::{anyhow, Result};
+use diem_infallible::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+const JOURNAL_FILE_NAME: &str = "restore_journal.json";
+
+/// Progress of restoring a single manifest.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ManifestProgress {
+    /// Number of chunks, counting from the first one in the manifest, that have been applied.
+    pub num_chunks_applied: usize,
+    /// Whether everything in the manifest has been applied.
+    pub done: bool,
+}
+
+pub struct RestoreJournal {
+    /// `None` if the journal lives only in memory, e.g. when verifying backups.
+    path: Option<PathBuf>,
+    progress: Mutex<BTreeMap<FileHandle, ManifestProgress>>,
+}
+
+impl RestoreJournal {
+    /// Loads the journal in `db_dir`, or starts an empty one if there's none.
+    pub fn load(db_dir: &Path) -> Result<Self> {
+        let path = db_dir.join(JOURNAL_FILE_NAME);
+        let progress = if path.exists() {
+            serde_json::from_slice(&std::fs::read(&path)?)
+                .map_err(|e| anyhow!("Failed to parse restore journal {:?}: {}", path, e))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path: Some(path),
+            progress: Mutex::new(progress),
+        })
+    }
+
+    pub fn new_in_memory() -> Self {
+        Self {
+            path: None,
+            progress: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn get(&self, manifest: &FileHandleRef) -> ManifestProgress {
+        self.progress
+            .lock()
+            .get(manifest)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn record_chunks_applied(
+        &self,
+        manifest: &FileHandleRef,
+        num_chunks_applied: usize,
+    ) -> Result<()> {
+        self.update(manifest, |progress| {
+            progress.num_chunks_applied = num_chunks_applied
+        })
+    }
+
+    pub fn record_done(&self, manifest: &FileHandleRef) -> Result<()> {
+        self.update(manifest, |progress| progress.done = true)
+    }
+
+    fn update(
+        &self,
+        manifest: &FileHandleRef,
+        update: impl FnOnce(&mut ManifestProgress),
+    ) -> Result<()> {
+        let mut progress = self.progress.lock();
+        update(progress.entry(manifest.to_string()).or_default());
+
+        if let Some(path) = &self.path {
+            let tmp_path = path.with_extension("json.tmp");
+            std::fs::write(&tmp_path, serde_json::to_vec(&*progress)?)?;
+            std::fs::rename(&tmp_path, path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_temppath::TempPath;
+
+    #[test]
+    fn test_persisted() {
+        let db_dir = TempPath::new();
+        db_dir.create_as_dir().unwrap();
+
+        let journal = RestoreJournal::load(db_dir.path()).unwrap();
+        assert_eq!(journal.get("a"), ManifestProgress::default());
+        journal.record_chunks_applied("a", 2).unwrap();
+        journal.record_chunks_applied("b", 1).unwrap();
+        journal.record_done("b").unwrap();
+
+        let journal = RestoreJournal::load(db_dir.path()).unwrap();
+        assert_eq!(
+            journal.get("a"),
+            ManifestProgress {
+                num_chunks_applied: 2,
+                done: false
+            }
+        );
+        assert_eq!(
+            journal.get("b"),
+            ManifestProgress {
+                num_chunks_applied: 1,
+                done: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_in_memory() {
+        let journal = RestoreJournal::new_in_memory();
+        journal.record_chunks_applied("a", 2).unwrap();
+        assert_eq!(journal.get("a").num_chunks_applied, 2);
+    }
+}
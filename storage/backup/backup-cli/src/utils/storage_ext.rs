@@ -1,19 +1,44 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::storage::{BackupHandle, BackupStorage, FileHandleRef};
+use crate::{
+    storage::{
+        BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
+    },
+    utils::encryption::{self, EncryptionKey},
+};
 use anyhow::Result;
 use async_trait::async_trait;
 use rand::random;
 use serde::de::DeserializeOwned;
 use std::{convert::TryInto, sync::Arc};
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
 
 #[async_trait]
 pub trait BackupStorageExt {
     async fn read_all(&self, file_handle: &FileHandleRef) -> Result<Vec<u8>>;
     async fn load_json_file<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T>;
-    async fn load_bcs_file<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T>;
+    /// Loads a BCS file written by `create_for_write_sealed()` with the same `encryption_key`.
+    async fn load_bcs_file<T: DeserializeOwned>(
+        &self,
+        file_handle: &FileHandleRef,
+        encryption_key: Option<&Arc<EncryptionKey>>,
+    ) -> Result<T>;
+    /// Same as `create_for_write()`, but content written is sealed with `encryption_key` if
+    /// provided, in which case the writer must be shut down for the file to be complete.
+    async fn create_for_write_sealed(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+        encryption_key: Option<&Arc<EncryptionKey>>,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)>;
+    /// Same as `open_for_read()`, but content is opened with `encryption_key` if provided, failing
+    /// on anything not sealed with it.
+    async fn open_for_read_sealed(
+        &self,
+        file_handle: &FileHandleRef,
+        encryption_key: Option<&Arc<EncryptionKey>>,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>>;
     /// Adds a random suffix ".XXXX" to the backup name, so a retry won't pass a same backup name to
     /// the storage.
     async fn create_backup_with_random_suffix(&self, name: &str) -> Result<BackupHandle>;
@@ -28,8 +53,43 @@ impl BackupStorageExt for Arc<dyn BackupStorage> {
         Ok(bytes)
     }
 
-    async fn load_bcs_file<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T> {
-        Ok(bcs::from_bytes(&self.read_all(file_handle).await?)?)
+    async fn load_bcs_file<T: DeserializeOwned>(
+        &self,
+        file_handle: &FileHandleRef,
+        encryption_key: Option<&Arc<EncryptionKey>>,
+    ) -> Result<T> {
+        let mut file = self
+            .open_for_read_sealed(file_handle, encryption_key)
+            .await?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).await?;
+        Ok(bcs::from_bytes(&bytes)?)
+    }
+
+    async fn create_for_write_sealed(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+        encryption_key: Option<&Arc<EncryptionKey>>,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        let (file_handle, file) = self.create_for_write(backup_handle, name).await?;
+        let file = match encryption_key {
+            Some(key) => encryption::seal(key, file)?,
+            None => file,
+        };
+        Ok((file_handle, file))
+    }
+
+    async fn open_for_read_sealed(
+        &self,
+        file_handle: &FileHandleRef,
+        encryption_key: Option<&Arc<EncryptionKey>>,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        let file = self.open_for_read(file_handle).await?;
+        Ok(match encryption_key {
+            Some(key) => encryption::open(Arc::clone(key), file),
+            None => file,
+        })
     }
 
     async fn load_json_file<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T> {
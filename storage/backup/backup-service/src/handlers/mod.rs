@@ -7,20 +7,29 @@ use crate::handlers::utils::{
     handle_rejection, reply_with_async_channel_writer, reply_with_bcs_bytes,
     send_size_prefixed_bcs_bytes, unwrap_or_500, LATENCY_HISTOGRAM,
 };
+use anyhow::{ensure, Result};
 use diem_crypto::hash::HashValue;
 use diem_types::transaction::Version;
-use diemdb::backup::backup_handler::BackupHandler;
+use diemdb::DiemDB;
+use std::{path::PathBuf, sync::Arc};
 use warp::{filters::BoxedFilter, reply::Reply, Filter};
 
 static DB_STATE: &str = "db_state";
 static STATE_RANGE_PROOF: &str = "state_range_proof";
 static STATE_SNAPSHOT: &str = "state_snapshot";
+static STATE_SNAPSHOT_CHANGES: &str = "state_snapshot_changes";
 static STATE_ROOT_PROOF: &str = "state_root_proof";
 static EPOCH_ENDING_LEDGER_INFOS: &str = "epoch_ending_ledger_infos";
 static TRANSACTIONS: &str = "transactions";
 static TRANSACTION_RANGE_PROOF: &str = "transaction_range_proof";
+static CHECKPOINT: &str = "checkpoint";
+
+/// Checkpoints are made under this directory in the DB root directory.
+const CHECKPOINTS_DIR: &str = "checkpoints";
+
+pub(crate) fn get_routes(db: Arc<DiemDB>) -> BoxedFilter<(impl Reply,)> {
+    let backup_handler = db.get_backup_handler();
 
-pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Reply,)> {
     // GET db_state
     let bh = backup_handler.clone();
     let db_state = warp::path::end()
@@ -50,6 +59,19 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         })
         .recover(handle_rejection);
 
+    // GET state_snapshot_changes/<base_version>/<version>
+    let bh = backup_handler.clone();
+    let state_snapshot_changes = warp::path!(Version / Version)
+        .map(move |base_version, version| {
+            reply_with_async_channel_writer(&bh, STATE_SNAPSHOT_CHANGES, |bh, sender| {
+                send_size_prefixed_bcs_bytes(
+                    bh.get_account_changes_iter(base_version, version),
+                    sender,
+                )
+            })
+        })
+        .recover(handle_rejection);
+
     // GET state_root_proof/<version>
     let bh = backup_handler.clone();
     let state_root_proof = warp::path!(Version)
@@ -107,15 +129,23 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         .map(unwrap_or_500)
         .recover(handle_rejection);
 
+    // GET checkpoint/<name>
+    let checkpoint = warp::path!(String)
+        .map(move |name: String| reply_with_bcs_bytes(CHECKPOINT, &create_checkpoint(&db, &name)?))
+        .map(unwrap_or_500)
+        .recover(handle_rejection);
+
     // Route by endpoint name.
     let routes = warp::any()
         .and(warp::path(DB_STATE).and(db_state))
         .or(warp::path(STATE_RANGE_PROOF).and(state_range_proof))
         .or(warp::path(STATE_SNAPSHOT).and(state_snapshot))
+        .or(warp::path(STATE_SNAPSHOT_CHANGES).and(state_snapshot_changes))
         .or(warp::path(STATE_ROOT_PROOF).and(state_root_proof))
         .or(warp::path(EPOCH_ENDING_LEDGER_INFOS).and(epoch_ending_ledger_infos))
         .or(warp::path(TRANSACTIONS).and(transactions))
-        .or(warp::path(TRANSACTION_RANGE_PROOF).and(transaction_range_proof));
+        .or(warp::path(TRANSACTION_RANGE_PROOF).and(transaction_range_proof))
+        .or(warp::path(CHECKPOINT).and(checkpoint));
 
     // Serve all routes for GET only.
     warp::get()
@@ -128,3 +158,19 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         }))
         .boxed()
 }
+
+/// Makes a checkpoint named `name` in the checkpoints directory of the DB root, and returns the
+/// path it can be opened at.
+fn create_checkpoint(db: &DiemDB, name: &str) -> Result<String> {
+    ensure!(
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        "Invalid checkpoint name: {:?}",
+        name,
+    );
+    let checkpoint_root: PathBuf = db.db_root_path()?.join(CHECKPOINTS_DIR).join(name);
+    db.create_checkpoint(&checkpoint_root)?;
+    Ok(checkpoint_root.to_string_lossy().into_owned())
+}
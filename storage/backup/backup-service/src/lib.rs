@@ -10,8 +10,7 @@ use std::{net::SocketAddr, sync::Arc};
 use tokio::runtime::{Builder, Runtime};
 
 pub fn start_backup_service(address: SocketAddr, db: Arc<DiemDB>) -> Runtime {
-    let backup_handler = db.get_backup_handler();
-    let routes = get_routes(backup_handler);
+    let routes = get_routes(db);
 
     let runtime = Builder::new_multi_thread()
         .thread_name("backup")
@@ -40,7 +39,10 @@ mod tests {
     use diem_crypto::hash::HashValue;
     use diem_temppath::TempPath;
     use reqwest::blocking::get;
-    use std::net::{IpAddr, Ipv4Addr};
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        path::PathBuf,
+    };
 
     /// 404 - endpoint not found
     /// 400 - params not provided or failed parsing
@@ -49,8 +51,7 @@ mod tests {
     /// And failure on one endpoint doesn't result in warp::Rejection which makes it fallback to other matches.
     #[test]
     fn routing_and_error_codes() {
-        let tmpdir = TempPath::new();
-        let db = Arc::new(DiemDB::new_for_test(&tmpdir));
+        let db = Arc::new(DiemDB::new_for_test());
         let port = get_available_port();
         let _rt = start_backup_service(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port), db);
 
@@ -71,6 +72,8 @@ mod tests {
         assert_eq!(resp.status(), 400);
         let resp = get(&format!("http://127.0.0.1:{}/state_snapshot", port)).unwrap();
         assert_eq!(resp.status(), 400);
+        let resp = get(&format!("http://127.0.0.1:{}/checkpoint", port)).unwrap();
+        assert_eq!(resp.status(), 400);
 
         // Params fail to parse (HashValue)
         let resp = get(&format!("http://127.0.0.1:{}/state_range_proof/1/ff", port)).unwrap();
@@ -86,6 +89,8 @@ mod tests {
         assert_eq!(resp.status(), 500);
         let resp = get(&format!("http://127.0.0.1:{}/state_root_proof/0", port,)).unwrap();
         assert_eq!(resp.status(), 500);
+        let resp = get(&format!("http://127.0.0.1:{}/checkpoint/a.b", port,)).unwrap();
+        assert_eq!(resp.status(), 500);
 
         // an endpoint handled by `reply_with_async_channel_writer' always returns 200,
         // connection terminates prematurely when the channel writer errors.
@@ -94,4 +99,24 @@ mod tests {
         assert_eq!(resp.content_length(), None);
         assert!(resp.bytes().is_err());
     }
+    #[test]
+    fn checkpoint() {
+        let tmpdir = TempPath::new();
+        let db = Arc::new(DiemDB::new_for_test_on_disk(&tmpdir));
+        let port = get_available_port();
+        let _rt = start_backup_service(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port), db);
+
+        let resp = get(&format!("http://127.0.0.1:{}/checkpoint/cp1", port)).unwrap();
+        assert_eq!(resp.status(), 200);
+        let checkpoint_root: String = bcs::from_bytes(&resp.bytes().unwrap()).unwrap();
+        assert_eq!(
+            PathBuf::from(&checkpoint_root),
+            tmpdir.path().join("checkpoints").join("cp1")
+        );
+        DiemDB::new_for_test_on_disk(&checkpoint_root);
+
+        // Name already taken.
+        let resp = get(&format!("http://127.0.0.1:{}/checkpoint/cp1", port)).unwrap();
+        assert_eq!(resp.status(), 500);
+    }
 }
@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_config::{config::RocksdbConfig, utils::get_genesis_txn};
+use diem_config::{
+    config::{RocksdbConfig, SecondaryIndexesConfig},
+    utils::get_genesis_txn,
+};
 use diem_jellyfish_merkle::metrics::{
     DIEM_JELLYFISH_INTERNAL_ENCODED_BYTES, DIEM_JELLYFISH_LEAF_ENCODED_BYTES,
     DIEM_JELLYFISH_STORAGE_READS,
@@ -45,7 +48,9 @@ pub fn run_benchmark(
             &db_dir,
             false,        /* readonly */
             prune_window, /* pruner */
+            None,         /* ledger_pruner */
             RocksdbConfig::default(),
+            SecondaryIndexesConfig::default(),
         )
         .expect("DB should open."),
     );
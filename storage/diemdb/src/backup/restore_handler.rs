@@ -2,9 +2,16 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    change_set::ChangeSet, event_store::EventStore, ledger_store::LedgerStore,
-    schema::transaction_accumulator::TransactionAccumulatorSchema, state_store::StateStore,
-    transaction_store::TransactionStore, DiemDB,
+    change_set::ChangeSet,
+    event_store::EventStore,
+    ledger_store::LedgerStore,
+    schema::{
+        db_metadata::{DbMetadataKey, DbMetadataSchema, DbMetadataValue},
+        transaction_accumulator::TransactionAccumulatorSchema,
+    },
+    state_store::StateStore,
+    transaction_store::TransactionStore,
+    DiemDB,
 };
 use anyhow::{ensure, Result};
 use diem_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
@@ -13,12 +20,13 @@ use diem_types::{
     account_state_blob::AccountStateBlob,
     contract_event::ContractEvent,
     ledger_info::LedgerInfoWithSignatures,
-    proof::{definition::LeafCount, position::FrozenSubTreeIterator},
+    proof::{definition::LeafCount, position::FrozenSubTreeIterator, SparseMerkleRangeProof},
     transaction::{Transaction, TransactionInfo, Version, PRE_GENESIS_VERSION},
 };
+use itertools::zip_eq;
 use schemadb::DB;
 use std::sync::Arc;
-use storage_interface::{DbReader, TreeState};
+use storage_interface::{DbReader, StateSnapshotReceiver, TreeState};
 
 /// Provides functionalities for DiemDB data restore.
 #[derive(Clone)]
@@ -62,25 +70,49 @@ impl RestoreHandler {
         )
     }
 
-    pub fn save_ledger_infos(&self, ledger_infos: &[LedgerInfoWithSignatures]) -> Result<()> {
-        ensure!(!ledger_infos.is_empty(), "No LedgerInfos to save.");
+    /// Like `get_state_restore_receiver()`, but continues from the accounts restored to the DB by
+    /// a previous attempt that was interrupted.
+    pub fn get_resuming_state_restore_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<JellyfishMerkleRestore<AccountStateBlob>> {
+        JellyfishMerkleRestore::new(Arc::clone(&self.state_store), version, expected_root_hash)
+    }
 
+    /// Applies an incremental state snapshot to the state tree at `base_version`, making the tree
+    /// at `version`. Nothing is written unless the resulting root hash is `expected_root_hash`.
+    pub fn save_account_state_changes(
+        &self,
+        base_version: Version,
+        version: Version,
+        blobs: Vec<(HashValue, AccountStateBlob)>,
+        expected_root_hash: HashValue,
+    ) -> Result<()> {
         let mut cs = ChangeSet::new();
-        ledger_infos
-            .iter()
-            .map(|li| self.ledger_store.put_ledger_info(li, &mut cs))
-            .collect::<Result<Vec<_>>>()?;
-        self.db.write_schemas(cs.batch)?;
-
-        if let Some(li) = self.ledger_store.get_latest_ledger_info_option() {
-            if li.ledger_info().epoch() > ledger_infos.last().unwrap().ledger_info().epoch() {
-                // No need to update latest ledger info.
-                return Ok(());
-            }
-        }
+        let root_hash = self.state_store.put_account_state_blobs_on_base_version(
+            blobs,
+            base_version,
+            version,
+            &mut cs,
+        )?;
+        ensure!(
+            root_hash == expected_root_hash,
+            "Root hash mismatch after applying state changes from version {} to {}. \
+            Actual: {}, expected: {}",
+            base_version,
+            version,
+            root_hash,
+            expected_root_hash,
+        );
+        self.db.write_schemas(cs.batch)
+    }
 
-        self.ledger_store
-            .set_latest_ledger_info(ledger_infos.last().unwrap().clone());
+    pub fn save_ledger_infos(&self, ledger_infos: &[LedgerInfoWithSignatures]) -> Result<()> {
+        let mut cs = ChangeSet::new();
+        put_ledger_infos(&self.ledger_store, ledger_infos, &mut cs)?;
+        self.db.write_schemas(cs.batch)?;
+        update_latest_ledger_info(&self.ledger_store, ledger_infos);
         Ok(())
     }
 
@@ -90,32 +122,7 @@ impl RestoreHandler {
         frozen_subtrees: &[HashValue],
     ) -> Result<()> {
         let mut cs = ChangeSet::new();
-        let positions: Vec<_> = FrozenSubTreeIterator::new(num_leaves).collect();
-
-        ensure!(
-            positions.len() == frozen_subtrees.len(),
-            "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
-            positions.len(),
-            frozen_subtrees.len(),
-        );
-
-        positions
-            .iter()
-            .zip(frozen_subtrees.iter().rev())
-            .map(|(p, h)| {
-                if let Some(_h) = self.db.get::<TransactionAccumulatorSchema>(p)? {
-                    ensure!(
-                        h == &_h,
-                        "Frozen subtree root does not match that already in DB. Provided: {}, in db: {}.",
-                        h,
-                        _h,
-                    );
-                } else {
-                    cs.batch.put::<TransactionAccumulatorSchema>(p, h)?;
-                }
-                Ok(())
-            })
-            .collect::<Result<Vec<_>>>()?;
+        confirm_or_put_frozen_subtrees(&self.db, num_leaves, frozen_subtrees, &mut cs)?;
         self.db.write_schemas(cs.batch)
     }
 
@@ -127,15 +134,16 @@ impl RestoreHandler {
         events: &[Vec<ContractEvent>],
     ) -> Result<()> {
         let mut cs = ChangeSet::new();
-        for (idx, txn) in txns.iter().enumerate() {
-            self.transaction_store
-                .put_transaction(first_version + idx as Version, txn, &mut cs)?;
-        }
-        self.ledger_store
-            .put_transaction_infos(first_version, txn_infos, &mut cs)?;
-        self.event_store
-            .put_events_multiple_versions(first_version, events, &mut cs)?;
-
+        put_transactions(
+            &self.ledger_store,
+            &self.transaction_store,
+            &self.event_store,
+            first_version,
+            txns,
+            txn_infos,
+            events,
+            &mut cs,
+        )?;
         self.db.write_schemas(cs.batch)
     }
 
@@ -165,3 +173,125 @@ impl RestoreHandler {
             .map_or(0, |(ver, _txn_info)| ver + 1))
     }
 }
+
+/// Puts `ledger_infos` into `cs`. Call `update_latest_ledger_info()` once they are persisted.
+pub(crate) fn put_ledger_infos(
+    ledger_store: &LedgerStore,
+    ledger_infos: &[LedgerInfoWithSignatures],
+    cs: &mut ChangeSet,
+) -> Result<()> {
+    ensure!(!ledger_infos.is_empty(), "No LedgerInfos to save.");
+    ledger_infos
+        .iter()
+        .map(|li| ledger_store.put_ledger_info(li, cs))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(())
+}
+
+/// Makes the last of the persisted `ledger_infos` the latest ledger info, unless the latest one
+/// is in a later epoch.
+pub(crate) fn update_latest_ledger_info(
+    ledger_store: &LedgerStore,
+    ledger_infos: &[LedgerInfoWithSignatures],
+) {
+    if let Some(li) = ledger_store.get_latest_ledger_info_option() {
+        if li.ledger_info().epoch() > ledger_infos.last().unwrap().ledger_info().epoch() {
+            // No need to update latest ledger info.
+            return;
+        }
+    }
+
+    ledger_store.set_latest_ledger_info(ledger_infos.last().unwrap().clone());
+}
+
+/// Puts the roots of the frozen subtrees of an accumulator with `num_leaves` leaves into `cs`,
+/// checking those already in the DB match.
+pub(crate) fn confirm_or_put_frozen_subtrees(
+    db: &DB,
+    num_leaves: LeafCount,
+    frozen_subtrees: &[HashValue],
+    cs: &mut ChangeSet,
+) -> Result<()> {
+    let positions: Vec<_> = FrozenSubTreeIterator::new(num_leaves).collect();
+
+    ensure!(
+        positions.len() == frozen_subtrees.len(),
+        "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
+        positions.len(),
+        frozen_subtrees.len(),
+    );
+
+    positions
+        .iter()
+        .zip(frozen_subtrees.iter().rev())
+        .map(|(p, h)| {
+            if let Some(_h) = db.get::<TransactionAccumulatorSchema>(p)? {
+                ensure!(
+                    h == &_h,
+                    "Frozen subtree root does not match that already in DB. Provided: {}, in db: {}.",
+                    h,
+                    _h,
+                );
+            } else {
+                cs.batch.put::<TransactionAccumulatorSchema>(p, h)?;
+            }
+            Ok(())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(())
+}
+
+/// Puts the transactions starting at `first_version`, with their infos and events, into `cs`. If
+/// they don't follow the latest transaction in the DB, the ledger history is recorded to start
+/// from `first_version`, since the versions in between are missing.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn put_transactions(
+    ledger_store: &LedgerStore,
+    transaction_store: &TransactionStore,
+    event_store: &EventStore,
+    first_version: Version,
+    txns: &[Transaction],
+    txn_infos: &[TransactionInfo],
+    events: &[Vec<ContractEvent>],
+    cs: &mut ChangeSet,
+) -> Result<()> {
+    let next_version = ledger_store
+        .get_latest_transaction_info_option()?
+        .map_or(0, |(version, _txn_info)| version + 1);
+    if first_version > next_version {
+        cs.batch.put::<DbMetadataSchema>(
+            &DbMetadataKey::FirstAvailableLedgerVersion,
+            &DbMetadataValue::Version(first_version),
+        )?;
+    }
+
+    for (idx, (txn, txn_events)) in zip_eq(txns, events).enumerate() {
+        let version = first_version + idx as Version;
+        transaction_store.put_transaction(version, txn, cs)?;
+        transaction_store.put_secondary_indexes(version, txn, txn_events, cs)?;
+    }
+    ledger_store.put_transaction_infos(first_version, txn_infos, cs)?;
+    event_store.put_events_multiple_versions(first_version, events, cs)?;
+    Ok(())
+}
+
+/// Restores a state snapshot into the state store, see `DbWriter::get_state_snapshot_receiver()`.
+pub(crate) struct StateSnapshotRestore(pub JellyfishMerkleRestore<AccountStateBlob>);
+
+impl StateSnapshotReceiver for StateSnapshotRestore {
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        self.0.add_chunk(chunk, proof)
+    }
+
+    fn previous_key(&self) -> Option<HashValue> {
+        self.0.previous_key()
+    }
+
+    fn finish_box(self: Box<Self>) -> Result<()> {
+        self.0.finish()
+    }
+}
@@ -3,7 +3,6 @@
 
 use crate::{test_helper::arb_blocks_to_commit, DiemDB};
 use anyhow::Result;
-use diem_temppath::TempPath;
 use proptest::prelude::*;
 use storage_interface::DbWriter;
 
@@ -12,8 +11,7 @@ proptest! {
 
     #[test]
     fn test_get_transaction_iter(input in arb_blocks_to_commit()) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
 
         let mut cur_ver = 0;
         for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
@@ -16,6 +16,7 @@ use diem_types::transaction::Transaction;
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_config::AccountResource,
+    block_metadata::BlockMetadata,
     contract_event::ContractEvent,
     ledger_info::LedgerInfo,
     proof::SparseMerkleLeafNode,
@@ -78,8 +79,7 @@ fn verify_epochs(db: &DiemDB, ledger_infos_with_sigs: &[LedgerInfoWithSignatures
 }
 
 pub fn test_save_blocks_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
 
     let num_batches = input.len();
     let mut cur_ver = 0;
@@ -140,8 +140,7 @@ pub fn test_save_blocks_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWit
 }
 
 fn test_sync_transactions_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
 
     let num_batches = input.len();
     let mut cur_ver = 0;
@@ -496,6 +495,14 @@ fn verify_committed_transactions(
             .verify_user_txn(ledger_info, cur_ver, txn.sender(), txn.sequence_number())
             .unwrap();
 
+        let txn_with_proof = db
+            .get_transaction_by_hash(txn_to_commit.transaction().hash(), ledger_version, true)
+            .unwrap()
+            .expect("Should exist.");
+        txn_with_proof
+            .verify_user_txn(ledger_info, cur_ver, txn.sender(), txn.sequence_number())
+            .unwrap();
+
         let acct_txns_with_proof = db
             .get_account_transactions(txn.sender(), txn.sequence_number(), 1, true, ledger_version)
             .unwrap();
@@ -519,6 +526,16 @@ fn verify_committed_transactions(
             .unwrap();
         assert_eq!(txn_list_with_proof.transactions.len(), 1);
 
+        let txn_output_list_with_proof = db
+            .get_transaction_outputs(cur_ver, 1, ledger_version)
+            .unwrap();
+        txn_output_list_with_proof
+            .verify(ledger_info, Some(cur_ver))
+            .unwrap();
+        let (_, txn_output) = &txn_output_list_with_proof.transactions_and_outputs[0];
+        assert_eq!(txn_output.write_set(), txn_to_commit.write_set());
+        assert_eq!(txn_output.events(), txn_to_commit.events());
+
         // Fetch and verify account states.
         for (addr, expected_blob) in txn_to_commit.account_states() {
             let account_state_with_proof = db
@@ -557,6 +574,49 @@ proptest! {
     fn test_sync_transactions(input in arb_blocks_to_commit()) {
         test_sync_transactions_impl(input);
     }
+
+    #[test]
+    fn test_checkpoint(input in arb_blocks_to_commit()) {
+        test_checkpoint_impl(input);
+    }
+}
+
+fn test_checkpoint_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test_on_disk(&tmp_dir);
+    let checkpoint_dir = TempPath::new();
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    db.create_checkpoint(&checkpoint_dir).unwrap();
+    // The checkpoint can't be made twice at the same place.
+    assert!(db.create_checkpoint(&checkpoint_dir).is_err());
+
+    let (txns_to_commit, ledger_info_with_sigs) = input.last().unwrap();
+    let latest_ledger_info = db.get_latest_ledger_info().unwrap();
+    let latest_tree_state = db.get_latest_tree_state().unwrap();
+    drop(db);
+
+    let checkpoint = DiemDB::new_for_test_on_disk(&checkpoint_dir);
+    assert_eq!(
+        checkpoint.get_latest_ledger_info().unwrap(),
+        latest_ledger_info
+    );
+    assert_eq!(
+        checkpoint.get_latest_tree_state().unwrap(),
+        latest_tree_state
+    );
+    verify_committed_transactions(
+        &checkpoint,
+        txns_to_commit,
+        cur_ver - txns_to_commit.len() as u64,
+        ledger_info_with_sigs,
+        true, /* is_latest */
+    );
 }
 
 #[test]
@@ -594,16 +654,14 @@ fn test_get_first_seq_num_and_limit() {
 
 #[test]
 fn test_too_many_requested() {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
 
     assert!(db.get_transactions(0, 1001 /* limit */, 0, true).is_err());
 }
 
 #[test]
 fn test_get_latest_tree_state() {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
 
     // entirely emtpy db
     let empty = db.get_latest_tree_state().unwrap();
@@ -648,3 +706,61 @@ fn put_transaction_info(db: &DiemDB, version: Version, txn_info: &TransactionInf
         .unwrap();
     db.db.write_schemas(cs.batch).unwrap();
 }
+
+#[test]
+fn test_get_first_available_ledger_version() {
+    let db = DiemDB::new_for_test();
+
+    // history starting at genesis
+    put_block_metadata_transactions(&db, 0, 2);
+    assert_eq!(db.get_first_available_ledger_version().unwrap(), 0);
+
+    // history resuming after a gap, e.g. after genesis in a fast synced db
+    put_block_metadata_transactions(&db, 10, 2);
+    assert_eq!(db.get_first_available_ledger_version().unwrap(), 10);
+
+    // contiguous history
+    put_block_metadata_transactions(&db, 12, 1);
+    assert_eq!(db.get_first_available_ledger_version().unwrap(), 10);
+}
+
+fn put_block_metadata_transactions(db: &DiemDB, first_version: Version, num_txns: u64) {
+    let txns: Vec<_> = (0..num_txns)
+        .map(|round| {
+            Transaction::BlockMetadata(BlockMetadata::new(
+                HashValue::random(),
+                round,
+                0,
+                vec![],
+                AccountAddress::ZERO,
+            ))
+        })
+        .collect();
+    let txn_infos: Vec<_> = txns
+        .iter()
+        .map(|txn| {
+            TransactionInfo::new(
+                txn.hash(),
+                HashValue::random(),
+                HashValue::random(),
+                0,
+                KeptVMStatus::Executed,
+            )
+        })
+        .collect();
+    let events = vec![vec![]; txns.len()];
+
+    let mut cs = ChangeSet::new();
+    put_transactions(
+        &db.ledger_store,
+        &db.transaction_store,
+        &db.event_store,
+        first_version,
+        &txns,
+        &txn_infos,
+        &events,
+        &mut cs,
+    )
+    .unwrap();
+    db.db.write_schemas(cs.batch).unwrap();
+}
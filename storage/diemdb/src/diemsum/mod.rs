@@ -3,7 +3,7 @@
 
 use crate::{DiemDB, Order, MAX_LIMIT};
 use anyhow::{ensure, format_err, Result};
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_types::{
     account_address::AccountAddress,
     account_state_blob::AccountStateBlob,
@@ -24,7 +24,9 @@ impl Diemsum {
             db_root_path,
             true, /* read only */
             None, /* no prune_window */
+            None, /* no ledger_prune_window */
             RocksdbConfig::default(),
+            SecondaryIndexesConfig::default(),
         )?;
         Ok(Diemsum { db })
     }
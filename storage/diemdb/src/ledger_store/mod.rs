@@ -13,7 +13,7 @@ use crate::{
         transaction_info::TransactionInfoSchema,
     },
 };
-use accumulator::{HashReader, MerkleAccumulator};
+use accumulator::{CachedHashReader, HashReader, MerkleAccumulator};
 use anyhow::{ensure, format_err, Result};
 use arc_swap::ArcSwap;
 use diem_crypto::{
@@ -24,8 +24,9 @@ use diem_types::{
     epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
-        definition::LeafCount, position::Position, AccumulatorConsistencyProof,
-        TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
+        accumulator::InMemoryAccumulator, definition::LeafCount, position::Position,
+        AccumulatorConsistencyProof, TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
+        TransactionAccumulatorSummary,
     },
     transaction::{
         default_protocol::TransactionInfoWithProof, TransactionInfo, TransactionInfoTrait, Version,
@@ -44,8 +45,18 @@ pub(crate) struct LedgerStore {
     /// cache it in memory in order to avoid reading DB and deserializing the object frequently. It
     /// should be updated every time new ledger info and signatures are persisted.
     latest_ledger_info: ArcSwap<Option<LedgerInfoWithSignatures>>,
+
+    /// Reads frozen nodes of the transaction accumulator, keeping recently used ones and recently
+    /// requested frozen subtree summaries in memory. Serving many overlapping range and
+    /// consistency proofs (e.g. to syncing peers) mostly touches the same nodes.
+    accumulator_reader: CachedHashReader<TransactionAccumulatorReader>,
 }
 
+/// Max number of frozen accumulator nodes kept in memory. Each entry is a little over 40 bytes.
+const ACCUMULATOR_NODE_CACHE_SIZE: usize = 1 << 16;
+/// Max number of leaf counts whose frozen subtree root hashes are kept in memory.
+const ACCUMULATOR_SUMMARY_CACHE_SIZE: usize = 1024;
+
 impl LedgerStore {
     pub fn new(db: Arc<DB>) -> Self {
         // Upon restart, read the latest ledger info and signatures and cache them in memory.
@@ -61,6 +72,13 @@ impl LedgerStore {
         };
 
         Self {
+            accumulator_reader: CachedHashReader::new(
+                TransactionAccumulatorReader {
+                    db: Arc::clone(&db),
+                },
+                ACCUMULATOR_NODE_CACHE_SIZE,
+                ACCUMULATOR_SUMMARY_CACHE_SIZE,
+            ),
             db,
             latest_ledger_info: ArcSwap::from(Arc::new(ledger_info)),
         }
@@ -172,7 +190,7 @@ impl LedgerStore {
     }
 
     pub fn get_frozen_subtree_hashes(&self, num_transactions: LeafCount) -> Result<Vec<HashValue>> {
-        Accumulator::get_frozen_subtree_hashes(self, num_transactions)
+        Accumulator::get_frozen_subtree_hashes(&self.accumulator_reader, num_transactions)
     }
 
     pub fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
@@ -289,7 +307,11 @@ impl LedgerStore {
         version: Version,
         ledger_version: Version,
     ) -> Result<TransactionAccumulatorProof> {
-        Accumulator::get_proof(self, ledger_version + 1 /* num_leaves */, version)
+        Accumulator::get_proof(
+            &self.accumulator_reader,
+            ledger_version + 1, /* num_leaves */
+            version,
+        )
     }
 
     /// Get proof for `num_txns` consecutive transactions starting from `start_version` towards
@@ -301,7 +323,7 @@ impl LedgerStore {
         ledger_version: Version,
     ) -> Result<TransactionAccumulatorRangeProof> {
         Accumulator::get_range_proof(
-            self,
+            &self.accumulator_reader,
             ledger_version + 1, /* num_leaves */
             start_version,
             num_txns,
@@ -319,7 +341,29 @@ impl LedgerStore {
             .map(|v| v.saturating_add(1))
             .unwrap_or(0);
         let ledger_num_leaves = ledger_version.saturating_add(1);
-        Accumulator::get_consistency_proof(self, ledger_num_leaves, client_known_num_leaves)
+        Accumulator::get_consistency_proof(
+            &self.accumulator_reader,
+            ledger_num_leaves,
+            client_known_num_leaves,
+        )
+    }
+
+    /// Gets the summary (frozen subtree root hashes) of the transaction accumulator at
+    /// `ledger_version`.
+    pub fn get_accumulator_summary(
+        &self,
+        ledger_version: Version,
+    ) -> Result<TransactionAccumulatorSummary> {
+        let num_txns = ledger_version
+            .checked_add(1)
+            .ok_or_else(|| format_err!("ledger_version overflow: {}", ledger_version))?;
+        let frozen_subtree_hashes = self.get_frozen_subtree_hashes(num_txns)?;
+        TransactionAccumulatorSummary::new(
+            InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+                frozen_subtree_hashes,
+                num_txns,
+            )?,
+        )
     }
 
     /// Write `txn_infos` to `batch`. Assigned `first_version` to the the version number of the
@@ -340,7 +384,7 @@ impl LedgerStore {
         // write hash of txn_info into the accumulator
         let txn_hashes: Vec<HashValue> = txn_infos.iter().map(TransactionInfo::hash).collect();
         let (root_hash, writes) = Accumulator::append(
-            self,
+            &self.accumulator_reader,
             first_version, /* num_existing_leaves */
             &txn_hashes,
         )?;
@@ -368,13 +412,20 @@ impl LedgerStore {
     }
 
     pub fn get_root_hash(&self, version: Version) -> Result<HashValue> {
-        Accumulator::get_root_hash(self, version + 1)
+        Accumulator::get_root_hash(&self.accumulator_reader, version + 1)
     }
 }
 
-pub(crate) type Accumulator = MerkleAccumulator<LedgerStore, TransactionAccumulatorHasher>;
+pub(crate) type Accumulator =
+    MerkleAccumulator<CachedHashReader<TransactionAccumulatorReader>, TransactionAccumulatorHasher>;
+
+/// Reads frozen transaction accumulator nodes from `TransactionAccumulatorSchema`.
+#[derive(Debug)]
+pub(crate) struct TransactionAccumulatorReader {
+    db: Arc<DB>,
+}
 
-impl HashReader for LedgerStore {
+impl HashReader for TransactionAccumulatorReader {
     fn get(&self, position: Position) -> Result<HashValue> {
         self.db
             .get::<TransactionAccumulatorSchema>(&position)?
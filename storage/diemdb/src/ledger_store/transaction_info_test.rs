@@ -3,7 +3,6 @@
 
 use super::*;
 use crate::DiemDB;
-use diem_temppath::TempPath;
 use proptest::{collection::vec, prelude::*};
 
 fn verify(
@@ -52,8 +51,7 @@ proptest! {
         batch1 in vec(any::<TransactionInfo>(), 1..100),
         batch2 in vec(any::<TransactionInfo>(), 1..100),
     ) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.ledger_store;
 
         // insert two batches of transaction infos
@@ -83,8 +81,7 @@ proptest! {
                     (Just(infos), Just(start_version), 0..num_infos as usize * 2)
                 })
     ) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.ledger_store;
         save(store, 0, &infos);
 
@@ -100,4 +97,32 @@ proptest! {
             iter.collect::<Result<Vec<_>>>().unwrap()
         );
     }
+
+    #[test]
+    fn test_accumulator_summary(
+        batch1 in vec(any::<TransactionInfo>(), 1..100),
+        batch2 in vec(any::<TransactionInfo>(), 1..100),
+    ) {
+        let db = DiemDB::new_for_test();
+        let store = &db.ledger_store;
+
+        let root_hash1 = save(store, 0, &batch1);
+        let ledger_version1 = batch1.len() as u64 - 1;
+        let root_hash2 = save(store, batch1.len() as u64, &batch2);
+        let ledger_version2 = batch1.len() as u64 + batch2.len() as u64 - 1;
+
+        // Ask twice for each version, the second time is served by the cached summary.
+        for _ in 0..2 {
+            let summary1 = store.get_accumulator_summary(ledger_version1).unwrap();
+            prop_assert_eq!(summary1.version(), ledger_version1);
+            prop_assert_eq!(summary1.root_hash(), root_hash1);
+
+            let summary2 = store.get_accumulator_summary(ledger_version2).unwrap();
+            prop_assert_eq!(summary2.version(), ledger_version2);
+            prop_assert_eq!(summary2.root_hash(), root_hash2);
+        }
+
+        // Versions that are not committed yet can't be summarized.
+        prop_assert!(store.get_accumulator_summary(ledger_version2 + 1).is_err());
+    }
 }
@@ -37,7 +37,13 @@ mod diemdb_test;
 pub use diemdb_test::test_save_blocks_impl;
 
 use crate::{
-    backup::{backup_handler::BackupHandler, restore_handler::RestoreHandler},
+    backup::{
+        backup_handler::BackupHandler,
+        restore_handler::{
+            confirm_or_put_frozen_subtrees, put_ledger_infos, put_transactions,
+            update_latest_ledger_info, RestoreHandler, StateSnapshotRestore,
+        },
+    },
     change_set::{ChangeSet, SealedChangeSet},
     errors::DiemDbError,
     event_store::EventStore,
@@ -49,20 +55,27 @@ use crate::{
         DIEM_STORAGE_NEXT_BLOCK_EPOCH, DIEM_STORAGE_OTHER_TIMERS_SECONDS,
         DIEM_STORAGE_ROCKSDB_PROPERTIES,
     },
-    pruner::Pruner,
-    schema::*,
+    pruner::{ledger_pruner::LedgerPruner, Pruner},
+    schema::{
+        db_metadata::{DbMetadataKey, DbMetadataSchema, DbMetadataValue},
+        *,
+    },
     state_store::StateStore,
     system_store::SystemStore,
     transaction_store::TransactionStore,
 };
 use anyhow::{ensure, format_err, Result};
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_crypto::hash::{CryptoHash, HashValue, SPARSE_MERKLE_PLACEHOLDER_HASH};
+use diem_jellyfish_merkle::{iterator::JellyfishMerkleIterator, restore::JellyfishMerkleRestore};
 use diem_logger::prelude::*;
 use diem_types::{
     account_address::AccountAddress,
     account_state::AccountState,
-    account_state_blob::{default_protocol::AccountStateWithProof, AccountStateBlob},
+    account_state_blob::{
+        default_protocol::{AccountStateWithProof, AccountStatesWithMultiProof},
+        AccountStateBlob, AccountStatesRangeWithProof,
+    },
     contract_event::{
         default_protocol::{EventByVersionWithProof, EventWithProof},
         ContractEvent,
@@ -71,27 +84,30 @@ use diem_types::{
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
-        AccountStateProof, AccumulatorConsistencyProof, EventProof, SparseMerkleProof,
-        TransactionInfoListWithProof,
+        AccountStateProof, AccountStatesMultiProof, AccumulatorConsistencyProof, EventProof,
+        SparseMerkleProof, TransactionAccumulatorSummary, TransactionInfoListWithProof,
     },
     protocol_spec::DpnProto,
     state_proof::StateProof,
     transaction::{
         default_protocol::{
-            AccountTransactionsWithProof, TransactionListWithProof, TransactionWithProof,
+            AccountTransactionsWithProof, TransactionAndOutputListWithProof,
+            TransactionListWithProof, TransactionWithProof,
         },
-        TransactionInfo, TransactionInfoTrait, TransactionToCommit, Version, PRE_GENESIS_VERSION,
+        TransactionInfo, TransactionInfoTrait, TransactionOutput, TransactionStatus,
+        TransactionToCommit, Version, PRE_GENESIS_VERSION,
     },
 };
 use itertools::{izip, zip_eq};
 use move_core_types::{
-    language_storage::{ModuleId, StructTag},
+    identifier::IdentStr,
+    language_storage::{ModuleId, StructTag, TypeTag},
     resolver::{ModuleResolver, ResourceResolver},
 };
 use once_cell::sync::Lazy;
 use schemadb::{ColumnFamilyName, Options, DB, DEFAULT_CF_NAME};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
     iter::Iterator,
     path::Path,
@@ -99,7 +115,10 @@ use std::{
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
-use storage_interface::{DbReader, DbWriter, MoveDbReader, Order, StartupInfo, TreeState};
+use storage_interface::{
+    DbReader, DbWriter, LedgerHistoryPruned, MoveDbReader, Order, StartupInfo,
+    StateSnapshotReceiver, TreeState,
+};
 
 const MAX_LIMIT: u64 = 1000;
 
@@ -222,12 +241,15 @@ pub struct DiemDB {
     system_store: SystemStore,
     rocksdb_property_reporter: RocksdbPropertyReporter,
     pruner: Option<Pruner>,
+    ledger_pruner: Option<LedgerPruner>,
 }
 
 impl DiemDB {
-    fn column_families() -> Vec<ColumnFamilyName> {
+    /// Names of all column families in the DB.
+    pub fn column_families() -> Vec<ColumnFamilyName> {
         vec![
             /* LedgerInfo CF = */ DEFAULT_CF_NAME,
+            DB_METADATA_CF_NAME,
             EPOCH_BY_VERSION_CF_NAME,
             EVENT_ACCUMULATOR_CF_NAME,
             EVENT_BY_KEY_CF_NAME,
@@ -239,12 +261,20 @@ impl DiemDB {
             TRANSACTION_CF_NAME,
             TRANSACTION_ACCUMULATOR_CF_NAME,
             TRANSACTION_BY_ACCOUNT_CF_NAME,
+            TRANSACTION_BY_EVENT_TYPE_CF_NAME,
             TRANSACTION_BY_HASH_CF_NAME,
+            TRANSACTION_BY_SCRIPT_FUNCTION_CF_NAME,
             TRANSACTION_INFO_CF_NAME,
+            WRITE_SET_CF_NAME,
         ]
     }
 
-    fn new_with_db(db: DB, prune_window: Option<u64>) -> Self {
+    fn new_with_db(
+        db: DB,
+        prune_window: Option<u64>,
+        ledger_prune_window: Option<u64>,
+        secondary_indexes: SecondaryIndexesConfig,
+    ) -> Self {
         let db = Arc::new(db);
 
         DiemDB {
@@ -252,10 +282,11 @@ impl DiemDB {
             event_store: Arc::new(EventStore::new(Arc::clone(&db))),
             ledger_store: Arc::new(LedgerStore::new(Arc::clone(&db))),
             state_store: Arc::new(StateStore::new(Arc::clone(&db))),
-            transaction_store: Arc::new(TransactionStore::new(Arc::clone(&db))),
+            transaction_store: Arc::new(TransactionStore::new(Arc::clone(&db), secondary_indexes)),
             system_store: SystemStore::new(Arc::clone(&db)),
             rocksdb_property_reporter: RocksdbPropertyReporter::new(Arc::clone(&db)),
             pruner: prune_window.map(|n| Pruner::new(Arc::clone(&db), n)),
+            ledger_pruner: ledger_prune_window.map(|n| LedgerPruner::new(Arc::clone(&db), n)),
         }
     }
 
@@ -263,12 +294,18 @@ impl DiemDB {
         db_root_path: P,
         readonly: bool,
         prune_window: Option<u64>,
+        ledger_prune_window: Option<u64>,
         rocksdb_config: RocksdbConfig,
+        secondary_indexes: SecondaryIndexesConfig,
     ) -> Result<Self> {
         ensure!(
             prune_window.is_none() || !readonly,
             "Do not set prune_window when opening readonly.",
         );
+        ensure!(
+            ledger_prune_window.is_none() || !readonly,
+            "Do not set ledger_prune_window when opening readonly.",
+        );
 
         let path = db_root_path.as_ref().join("diemdb");
         let instant = Instant::now();
@@ -293,7 +330,7 @@ impl DiemDB {
             )?
         };
 
-        let ret = Self::new_with_db(db, prune_window);
+        let ret = Self::new_with_db(db, prune_window, ledger_prune_window, secondary_indexes);
         info!(
             path = path,
             time_ms = %instant.elapsed().as_millis(),
@@ -302,6 +339,21 @@ impl DiemDB {
         Ok(ret)
     }
 
+    /// Opens an empty DB which lives in memory only and is gone once dropped.
+    pub fn open_in_memory(
+        prune_window: Option<u64>,
+        ledger_prune_window: Option<u64>,
+        secondary_indexes: SecondaryIndexesConfig,
+    ) -> Result<Self> {
+        let db = DB::open_in_memory("diemdb_in_memory", Self::column_families())?;
+        Ok(Self::new_with_db(
+            db,
+            prune_window,
+            ledger_prune_window,
+            secondary_indexes,
+        ))
+    }
+
     pub fn open_as_secondary<P: AsRef<Path> + Clone>(
         db_root_path: P,
         secondary_path: P,
@@ -322,17 +374,36 @@ impl DiemDB {
                 &rocksdb_opts,
             )?,
             None, // prune_window
+            None, // ledger_prune_window
+            // Read only, allow querying whichever secondary indexes the primary maintains.
+            SecondaryIndexesConfig {
+                by_event_type: true,
+                by_script_function: true,
+            },
         ))
     }
 
-    /// This opens db in non-readonly mode, without the pruner.
+    /// This opens an in-memory db, without the pruner.
     #[cfg(any(test, feature = "fuzzing"))]
-    pub fn new_for_test<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
+    pub fn new_for_test() -> Self {
+        Self::open_in_memory(
+            None, /* pruner */
+            None, /* ledger_pruner */
+            SecondaryIndexesConfig::default(),
+        )
+        .expect("Unable to open DiemDB")
+    }
+
+    /// Like `new_for_test`, but on disk under `db_root_path`, for tests which need files.
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub fn new_for_test_on_disk<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
         Self::open(
             db_root_path,
             false, /* readonly */
             None,  /* pruner */
+            None,  /* ledger_pruner */
             RocksdbConfig::default(),
+            SecondaryIndexesConfig::default(),
         )
         .expect("Unable to open DiemDB")
     }
@@ -342,6 +413,48 @@ impl DiemDB {
         update_rocksdb_properties(&self.db)
     }
 
+    /// Reads an integer RocksDB property of a column family, e.g. "rocksdb.estimate-num-keys".
+    pub fn get_rocksdb_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
+        self.db.get_property(cf_name, property_name)
+    }
+
+    /// Returns the root path the DB was opened with, i.e. the parent of the RocksDB directory.
+    /// Fails if the DB lives in memory.
+    pub fn db_root_path(&self) -> Result<&Path> {
+        let path = self
+            .db
+            .path()
+            .ok_or_else(|| format_err!("DB lives in memory and has no path."))?;
+        path.parent()
+            .ok_or_else(|| format_err!("DB path {:?} has no parent.", path))
+    }
+
+    /// Creates a checkpoint of the DB under `checkpoint_root`, which can be opened with
+    /// `DiemDB::open(checkpoint_root, ..)` afterwards. The checkpoint is a consistent view of the
+    /// DB made mostly of hard links to the live SST files, so it takes seconds even for a big DB
+    /// and doesn't block writes.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, checkpoint_root: P) -> Result<()> {
+        let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+            .with_label_values(&["create_checkpoint"])
+            .start_timer();
+        let checkpoint_path = checkpoint_root.as_ref().join("diemdb");
+        ensure!(
+            !checkpoint_path.exists(),
+            "Checkpoint path {:?} already exists.",
+            checkpoint_path,
+        );
+        std::fs::create_dir_all(checkpoint_root.as_ref())?;
+
+        let instant = Instant::now();
+        self.db.create_checkpoint(&checkpoint_path)?;
+        info!(
+            path = checkpoint_path,
+            time_ms = %instant.elapsed().as_millis(),
+            "Made DiemDB checkpoint.",
+        );
+        Ok(())
+    }
+
     /// Returns ledger infos reflecting epoch bumps starting with the given epoch. If there are no
     /// more than `MAX_NUM_EPOCH_ENDING_LEDGER_INFO` results, this function returns all of them,
     /// otherwise the first `MAX_NUM_EPOCH_ENDING_LEDGER_INFO` results are returned and a flag
@@ -410,6 +523,7 @@ impl DiemDB {
         ledger_version: Version,
         fetch_events: bool,
     ) -> Result<TransactionWithProof> {
+        self.error_if_ledger_pruned(version)?;
         let proof = self
             .ledger_store
             .get_transaction_info_with_proof(version, ledger_version)?;
@@ -575,8 +689,19 @@ impl DiemDB {
         // Transaction updates. Gather transaction hashes.
         zip_eq(first_version..=last_version, txns_to_commit).try_for_each(
             |(ver, txn_to_commit)| {
+                self.transaction_store.put_transaction(
+                    ver,
+                    txn_to_commit.transaction(),
+                    &mut cs,
+                )?;
                 self.transaction_store
-                    .put_transaction(ver, txn_to_commit.transaction(), &mut cs)
+                    .put_write_set(ver, txn_to_commit.write_set(), &mut cs)?;
+                self.transaction_store.put_secondary_indexes(
+                    ver,
+                    txn_to_commit.transaction(),
+                    txn_to_commit.events(),
+                    &mut cs,
+                )
             },
         )?;
 
@@ -614,6 +739,62 @@ impl DiemDB {
         if let Some(pruner) = self.pruner.as_ref() {
             pruner.wake(latest_version)
         }
+        if let Some(ledger_pruner) = self.ledger_pruner.as_ref() {
+            // Proofs are served relative to the latest ledger info, so the history from its version
+            // on is never pruned, even if transactions beyond it were saved while syncing.
+            if let Some(ledger_info_with_sigs) = self.ledger_store.get_latest_ledger_info_option() {
+                ledger_pruner.wake(std::cmp::min(
+                    latest_version,
+                    ledger_info_with_sigs.ledger_info().version(),
+                ))
+            }
+        }
+    }
+
+    fn error_if_ledger_pruned(&self, version: Version) -> Result<()> {
+        let first_available_version = self.get_first_available_ledger_version()?;
+        if version < first_available_version {
+            return Err(LedgerHistoryPruned {
+                what: format!("Transaction at version {}", version),
+                first_available_version,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Fails if the account has sent transactions from `seq_num` on as of `ledger_version`, but
+    /// none of them is indexed, i.e. they have all been pruned.
+    fn error_if_account_transactions_pruned(
+        &self,
+        address: AccountAddress,
+        seq_num: u64,
+        ledger_version: Version,
+        first_available_version: Version,
+    ) -> Result<()> {
+        if first_available_version == 0 {
+            return Ok(());
+        }
+        let (blob, _proof) = self
+            .state_store
+            .get_account_state_with_proof_by_version(address, ledger_version)?;
+        let sequence_number = match blob {
+            Some(blob) => AccountState::try_from(&blob)?
+                .get_account_resource()?
+                .map_or(0, |resource| resource.sequence_number()),
+            None => 0,
+        };
+        if seq_num < sequence_number {
+            return Err(LedgerHistoryPruned {
+                what: format!(
+                    "Transaction of account {} with sequence number {}",
+                    address, seq_num
+                ),
+                first_available_version,
+            }
+            .into());
+        }
+        Ok(())
     }
 }
 
@@ -678,6 +859,7 @@ impl DbReader<DpnProto> for DiemDB {
         gauged_api("get_account_transactions", || {
             error_if_too_many_requested(limit, MAX_LIMIT)?;
 
+            let first_available_version = self.get_first_available_ledger_version()?;
             let txns_with_proofs = self
                 .transaction_store
                 .get_account_transaction_version_iter(
@@ -685,17 +867,68 @@ impl DbReader<DpnProto> for DiemDB {
                     start_seq_num,
                     limit,
                     ledger_version,
+                    first_available_version,
                 )?
                 .map(|result| {
                     let (_seq_num, txn_version) = result?;
                     self.get_transaction_with_proof(txn_version, ledger_version, include_events)
                 })
                 .collect::<Result<Vec<_>>>()?;
+            if txns_with_proofs.is_empty() && limit > 0 {
+                self.error_if_account_transactions_pruned(
+                    address,
+                    start_seq_num,
+                    ledger_version,
+                    first_available_version,
+                )?;
+            }
 
             Ok(AccountTransactionsWithProof::new(txns_with_proofs))
         })
     }
 
+    fn get_transaction_versions_by_event_type(
+        &self,
+        type_tag: &TypeTag,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<Version>> {
+        gauged_api("get_transaction_versions_by_event_type", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+            self.transaction_store
+                .get_transaction_versions_by_event_type(
+                    type_tag,
+                    start_version,
+                    limit,
+                    ledger_version,
+                )
+        })
+    }
+
+    fn get_transaction_versions_by_script_function(
+        &self,
+        module: &ModuleId,
+        function: &IdentStr,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<Version>> {
+        gauged_api("get_transaction_versions_by_script_function", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+            self.transaction_store
+                .get_transaction_versions_by_script_function(
+                    module,
+                    function,
+                    start_version,
+                    limit,
+                    ledger_version,
+                )
+        })
+    }
+
     // ======================= State Synchronizer Internal APIs ===================================
     /// Gets a batch of transactions for the purpose of synchronizing state to another node.
     ///
@@ -715,6 +948,7 @@ impl DbReader<DpnProto> for DiemDB {
             }
 
             let limit = std::cmp::min(limit, ledger_version - start_version + 1);
+            self.error_if_ledger_pruned(start_version)?;
 
             let txns = (start_version..start_version + limit)
                 .map(|version| self.transaction_store.get_transaction(version))
@@ -749,6 +983,89 @@ impl DbReader<DpnProto> for DiemDB {
         })
     }
 
+    /// Gets a batch of transactions along with their outputs, for the purpose of synchronizing
+    /// state to another node that applies the outputs instead of executing the transactions.
+    fn get_transaction_outputs(
+        &self,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<TransactionAndOutputListWithProof> {
+        gauged_api("get_transaction_outputs", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+            if start_version > ledger_version || limit == 0 {
+                return Ok(TransactionAndOutputListWithProof::new_empty());
+            }
+
+            let limit = std::cmp::min(limit, ledger_version - start_version + 1);
+            self.error_if_ledger_pruned(start_version)?;
+
+            let txn_infos = (start_version..start_version + limit)
+                .map(|version| self.ledger_store.get_transaction_info(version))
+                .collect::<Result<Vec<_>>>()?;
+            let transactions_and_outputs = zip_eq(start_version..start_version + limit, &txn_infos)
+                .map(|(version, txn_info)| {
+                    let txn = self.transaction_store.get_transaction(version)?;
+                    let write_set = self.transaction_store.get_write_set(version)?;
+                    let events = self.event_store.get_events_by_version(version)?;
+                    let txn_output = TransactionOutput::new(
+                        write_set,
+                        events,
+                        txn_info.gas_used(),
+                        TransactionStatus::Keep(txn_info.status().clone()),
+                    );
+                    Ok((txn, txn_output))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let proof = TransactionInfoListWithProof::new(
+                self.ledger_store.get_transaction_range_proof(
+                    Some(start_version),
+                    limit,
+                    ledger_version,
+                )?,
+                txn_infos,
+            );
+
+            Ok(TransactionAndOutputListWithProof::new(
+                transactions_and_outputs,
+                Some(start_version),
+                proof,
+            ))
+        })
+    }
+
+    fn get_transaction_by_hash(
+        &self,
+        hash: HashValue,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<Option<TransactionWithProof>> {
+        gauged_api("get_transaction_by_hash", || {
+            self.transaction_store
+                .get_transaction_version_by_hash(&hash, ledger_version)?
+                .map(|v| self.get_transaction_with_proof(v, ledger_version, fetch_events))
+                .transpose()
+        })
+    }
+
+    fn get_transaction_by_version(
+        &self,
+        version: Version,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<TransactionWithProof> {
+        gauged_api("get_transaction_by_version", || {
+            ensure!(
+                version <= ledger_version,
+                "Requested version {} is greater than ledger version {}.",
+                version,
+                ledger_version,
+            );
+            self.get_transaction_with_proof(version, ledger_version, fetch_events)
+        })
+    }
+
     fn get_events(
         &self,
         event_key: &EventKey,
@@ -889,6 +1206,49 @@ impl DbReader<DpnProto> for DiemDB {
         })
     }
 
+    fn get_account_states_with_multiproof(
+        &self,
+        addresses: Vec<AccountAddress>,
+        version: Version,
+        ledger_version: Version,
+    ) -> Result<AccountStatesWithMultiProof> {
+        gauged_api("get_account_states_with_multiproof", || {
+            error_if_too_many_requested(addresses.len() as u64, MAX_LIMIT)?;
+            ensure!(!addresses.is_empty(), "No accounts requested.");
+            ensure!(
+                addresses.iter().collect::<HashSet<_>>().len() == addresses.len(),
+                "Duplicate accounts requested.",
+            );
+            ensure!(
+                version <= ledger_version,
+                "The queried version {} should be equal to or older than ledger version {}.",
+                version,
+                ledger_version
+            );
+            {
+                let latest_version = self.get_latest_version()?;
+                ensure!(
+                    ledger_version <= latest_version,
+                    "ledger_version specified {} is greater than committed version {}.",
+                    ledger_version,
+                    latest_version
+                );
+            }
+
+            let txn_info_with_proof = self
+                .ledger_store
+                .get_transaction_info_with_proof(version, ledger_version)?;
+            let (account_state_blobs, sparse_merkle_multiproof) = self
+                .state_store
+                .get_account_states_with_multiproof_by_version(&addresses, version)?;
+            Ok(AccountStatesWithMultiProof::new(
+                version,
+                account_state_blobs,
+                AccountStatesMultiProof::new(txn_info_with_proof, sparse_merkle_multiproof),
+            ))
+        })
+    }
+
     fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
         gauged_api("get_startup_info", || self.ledger_store.get_startup_info())
     }
@@ -907,6 +1267,68 @@ impl DbReader<DpnProto> for DiemDB {
         })
     }
 
+    fn get_account_states_range(
+        &self,
+        version: Version,
+        start_key: HashValue,
+        limit: u64,
+    ) -> Result<AccountStatesRangeWithProof> {
+        gauged_api("get_account_states_range", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+            // One more than requested tells where the next page starts.
+            let mut account_blobs =
+                JellyfishMerkleIterator::new(Arc::clone(&self.state_store), version, start_key)?
+                    .take(limit as usize + 1)
+                    .collect::<Result<Vec<_>>>()?;
+            let next_key = if account_blobs.len() > limit as usize {
+                account_blobs.pop().map(|(key, _blob)| key)
+            } else {
+                None
+            };
+            let proof = account_blobs
+                .last()
+                .map(|(key, _blob)| {
+                    self.state_store
+                        .get_account_state_range_proof(*key, version)
+                })
+                .transpose()?;
+
+            Ok(AccountStatesRangeWithProof {
+                account_blobs,
+                proof,
+                next_key,
+            })
+        })
+    }
+
+    fn get_least_readable_state_version(&self) -> Result<Version> {
+        gauged_api("get_least_readable_state_version", || {
+            Ok(match self.pruner.as_ref() {
+                Some(pruner) => pruner.least_readable_version(self.get_latest_version()?),
+                None => 0,
+            })
+        })
+    }
+
+    fn get_first_available_ledger_version(&self) -> Result<Version> {
+        gauged_api("get_first_available_ledger_version", || {
+            // The history may start after genesis (e.g., if the DB was fast synced or restored
+            // from a later version), and the pruner may have deleted a prefix of it since.
+            let first_written_version = self
+                .db
+                .get::<DbMetadataSchema>(&DbMetadataKey::FirstAvailableLedgerVersion)?
+                .map_or(0, DbMetadataValue::expect_version);
+            Ok(match self.ledger_pruner.as_ref() {
+                Some(ledger_pruner) => std::cmp::max(
+                    first_written_version,
+                    ledger_pruner.first_available_version(self.get_latest_version()?),
+                ),
+                None => first_written_version,
+            })
+        })
+    }
+
     fn get_latest_state_root(&self) -> Result<(Version, HashValue)> {
         gauged_api("get_latest_state_root", || {
             let (version, txn_info) = self.ledger_store.get_latest_transaction_info()?;
@@ -1063,6 +1485,15 @@ impl DbReader<DpnProto> for DiemDB {
                 .get_consistency_proof(client_known_version, ledger_version)
         })
     }
+
+    fn get_accumulator_summary(
+        &self,
+        ledger_version: Version,
+    ) -> Result<TransactionAccumulatorSummary> {
+        gauged_api("get_accumulator_summary", || {
+            self.ledger_store.get_accumulator_summary(ledger_version)
+        })
+    }
 }
 
 impl ModuleResolver for DiemDB {
@@ -1183,6 +1614,97 @@ impl DbWriter<DpnProto> for DiemDB {
             Ok(())
         })
     }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        gauged_api("get_state_snapshot_receiver", || {
+            let receiver: Box<dyn StateSnapshotReceiver> =
+                Box::new(StateSnapshotRestore(JellyfishMerkleRestore::new(
+                    Arc::clone(&self.state_store),
+                    version,
+                    expected_root_hash,
+                )?));
+            Ok(receiver)
+        })
+    }
+
+    fn finalize_state_snapshot(
+        &self,
+        version: Version,
+        transaction_list_with_proof: TransactionListWithProof,
+        ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        gauged_api("finalize_state_snapshot", || {
+            let ledger_info = ledger_infos
+                .last()
+                .ok_or_else(|| format_err!("No ledger infos to save."))?
+                .ledger_info();
+            ensure!(
+                ledger_info.version() == version,
+                "The last ledger info is at version {}, not at the snapshot version {}.",
+                ledger_info.version(),
+                version,
+            );
+            ensure!(
+                transaction_list_with_proof.transactions.len() == 1,
+                "Expected exactly the transaction at the snapshot version, got {} transactions.",
+                transaction_list_with_proof.transactions.len(),
+            );
+            transaction_list_with_proof.verify(ledger_info, Some(version))?;
+            let TransactionListWithProof {
+                transactions,
+                events,
+                proof,
+                ..
+            } = transaction_list_with_proof;
+            let events = events.ok_or_else(|| format_err!("Events are missing."))?;
+
+            // The state snapshot must have been completely restored.
+            let state_root_hash = self.state_store.get_root_hash(version)?;
+            ensure!(
+                state_root_hash == proof.transaction_infos[0].state_root_hash(),
+                "State root hash at version {} doesn't match the transaction info. \
+                 Actual: {}, expected: {}",
+                version,
+                state_root_hash,
+                proof.transaction_infos[0].state_root_hash(),
+            );
+
+            // The frozen subtrees left of the transaction are read when appending its info to the
+            // transaction accumulator, so they are persisted first.
+            let mut cs = ChangeSet::new();
+            confirm_or_put_frozen_subtrees(
+                &self.db,
+                version,
+                proof.ledger_info_to_transaction_infos_proof.left_siblings(),
+                &mut cs,
+            )?;
+            self.db.write_schemas(cs.batch)?;
+
+            let mut cs = ChangeSet::new();
+            put_transactions(
+                &self.ledger_store,
+                &self.transaction_store,
+                &self.event_store,
+                version,
+                &transactions,
+                &proof.transaction_infos,
+                &events,
+                &mut cs,
+            )?;
+            put_ledger_infos(&self.ledger_store, ledger_infos, &mut cs)?;
+            self.db.write_schemas(cs.batch)?;
+            update_latest_ledger_info(&self.ledger_store, ledger_infos);
+
+            DIEM_STORAGE_LATEST_TXN_VERSION.set(version as i64);
+            DIEM_STORAGE_LEDGER_VERSION.set(version as i64);
+            DIEM_STORAGE_NEXT_BLOCK_EPOCH.set(ledger_info.next_block_epoch() as i64);
+            Ok(())
+        })
+    }
 }
 
 // Convert requested range and order to a range in ascending order.
@@ -63,6 +63,22 @@ pub static DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION: Lazy<IntGauge> = La
     .unwrap()
 });
 
+pub static DIEM_STORAGE_LEDGER_PRUNE_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_ledger_prune_window",
+        "Diem storage ledger prune window"
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_PRUNER_FIRST_AVAILABLE_LEDGER_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_pruner_first_available_ledger_version",
+        "Diem storage pruner first available ledger version"
+    )
+    .unwrap()
+});
+
 pub static DIEM_STORAGE_API_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         // metric name
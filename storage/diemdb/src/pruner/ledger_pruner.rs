@@ -0,0 +1,350 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides `LedgerPruner` which manages a thread pruning old ledger history in the
+//! background: transactions, transaction infos, write sets, events and the indexes on them. The
+//! transaction accumulator is kept, so proofs against later ledger infos can still be built.
+
+use super::Command;
+use crate::{
+    metrics::{
+        DIEM_STORAGE_LEDGER_PRUNE_WINDOW, DIEM_STORAGE_OTHER_TIMERS_SECONDS,
+        DIEM_STORAGE_PRUNER_FIRST_AVAILABLE_LEDGER_VERSION,
+    },
+    schema::{
+        event::EventSchema, event_accumulator::EventAccumulatorSchema,
+        event_by_key::EventByKeySchema, event_by_version::EventByVersionSchema,
+        transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema,
+        transaction_by_event_type::TransactionByEventTypeSchema,
+        transaction_by_hash::TransactionByHashSchema,
+        transaction_by_script_function::TransactionByScriptFunctionSchema,
+        transaction_info::TransactionInfoSchema, write_set::WriteSetSchema,
+    },
+    transaction_store::{script_function, script_function_key},
+};
+use anyhow::Result;
+use diem_crypto::hash::CryptoHash;
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::{
+    proof::position::Position,
+    transaction::{Transaction, Version},
+};
+use schemadb::{ReadOptions, SchemaBatch, DB};
+#[cfg(test)]
+use std::time::Instant;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread::{sleep, JoinHandle},
+    time::Duration,
+};
+
+/// The `LedgerPruner` is meant to be part of a `DiemDB` instance and runs in the background to
+/// prune ledger history, separately from the state `Pruner`.
+///
+/// It creates a worker thread on construction and joins it on destruction. When destructed, it
+/// quits the worker thread eagerly without waiting for all pending work to be done.
+#[derive(Debug)]
+pub(crate) struct LedgerPruner {
+    /// Other than the latest version, how many historical versions of the ledger history to keep.
+    /// For example, this being 0 means keep only the latest transaction.
+    ledger_versions_to_keep: u64,
+    /// The worker thread handle, created upon construction and joined upon destruction. It only
+    /// becomes `None` after joined in `drop()`.
+    worker_thread: Option<JoinHandle<()>>,
+    /// The sender side of the channel talking to the worker thread.
+    command_sender: Mutex<Sender<Command>>,
+    /// The first version whose ledger history has not been pruned, updated by the worker thread.
+    worker_progress: Arc<AtomicU64>,
+}
+
+impl LedgerPruner {
+    /// Creates a worker thread that waits on a channel for pruning commands.
+    pub fn new(db: Arc<DB>, ledger_versions_to_keep: u64) -> Self {
+        let (command_sender, command_receiver) = channel();
+
+        let worker_progress = Arc::new(AtomicU64::new(0));
+        let worker_progress_clone = Arc::clone(&worker_progress);
+
+        DIEM_STORAGE_LEDGER_PRUNE_WINDOW.set(ledger_versions_to_keep as i64);
+        let worker_thread = std::thread::Builder::new()
+            .name("diemdb_ledger_pruner".into())
+            .spawn(move || Worker::new(db, command_receiver, worker_progress_clone).work())
+            .expect("Creating ledger pruner thread should succeed.");
+
+        Self {
+            ledger_versions_to_keep,
+            worker_thread: Some(worker_thread),
+            command_sender: Mutex::new(command_sender),
+            worker_progress,
+        }
+    }
+
+    /// Returns the first version whose ledger history is available given `latest_version`.
+    /// History of versions before it is pruned or scheduled to be pruned.
+    pub fn first_available_version(&self, latest_version: Version) -> Version {
+        std::cmp::max(
+            latest_version.saturating_sub(self.ledger_versions_to_keep),
+            self.worker_progress.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Sends pruning command to the worker thread when necessary.
+    pub fn wake(&self, latest_version: Version) {
+        if latest_version > self.ledger_versions_to_keep {
+            self.command_sender
+                .lock()
+                .send(Command::Prune {
+                    least_readable_version: latest_version - self.ledger_versions_to_keep,
+                })
+                .expect("Receiver should not destruct prematurely.");
+        }
+    }
+
+    /// (For tests only.) Notifies the worker thread and waits for it to finish its job by polling
+    /// the worker progress.
+    #[cfg(test)]
+    pub fn wake_and_wait(&self, latest_version: Version) -> Result<()> {
+        self.wake(latest_version);
+
+        if latest_version > self.ledger_versions_to_keep {
+            let first_available_version = latest_version - self.ledger_versions_to_keep;
+            // Assuming no big pruning chunks will be issued by a test.
+            const TIMEOUT: Duration = Duration::from_secs(10);
+            let end = Instant::now() + TIMEOUT;
+
+            while Instant::now() < end {
+                if self.worker_progress.load(Ordering::Relaxed) >= first_available_version {
+                    return Ok(());
+                }
+                sleep(Duration::from_millis(1));
+            }
+            anyhow::bail!("Timeout waiting for ledger pruner worker.");
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LedgerPruner {
+    fn drop(&mut self) {
+        self.command_sender
+            .lock()
+            .send(Command::Quit)
+            .expect("Receiver should not destruct.");
+        self.worker_thread
+            .take()
+            .expect("Worker thread must exist.")
+            .join()
+            .expect("Worker thread should join peacefully.");
+    }
+}
+
+struct Worker {
+    db: Arc<DB>,
+    command_receiver: Receiver<Command>,
+    target_first_available_version: Version,
+    /// Keeps a record of the pruning progress. If this equals to version `V`, we know the ledger
+    /// history of versions smaller than `V` is no longer available.
+    first_available_version: Arc<AtomicU64>,
+    /// Indicates if there's NOT any pending work to do currently, to hint
+    /// `Self::receive_commands()` to `recv()` blocking-ly.
+    blocking_recv: bool,
+}
+
+impl Worker {
+    const MAX_VERSIONS_TO_PRUNE_PER_BATCH: u64 = 100;
+
+    fn new(
+        db: Arc<DB>,
+        command_receiver: Receiver<Command>,
+        first_available_version: Arc<AtomicU64>,
+    ) -> Self {
+        Self {
+            db,
+            command_receiver,
+            first_available_version,
+            target_first_available_version: 0,
+            blocking_recv: true,
+        }
+    }
+
+    fn work(mut self) {
+        self.initialize();
+
+        while self.receive_commands() {
+            // Process a reasonably small batch of work before trying to receive commands again,
+            // in case `Command::Quit` is received (that's when we should quit.)
+            let first_available_version = self.first_available_version.load(Ordering::Relaxed);
+            match prune_ledger(
+                &self.db,
+                first_available_version,
+                self.target_first_available_version,
+                Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+            ) {
+                Ok(new_first_available_version) => {
+                    self.record_progress(new_first_available_version);
+                    // Make next recv() blocking if nothing left to do.
+                    self.blocking_recv =
+                        new_first_available_version >= self.target_first_available_version;
+                }
+                Err(e) => {
+                    error!(
+                        error = ?e,
+                        "Error pruning ledger history.",
+                    );
+                    // On error, stop retrying vigorously by making next recv() blocking.
+                    self.blocking_recv = true;
+                }
+            }
+        }
+    }
+
+    /// Find out the first transaction not pruned yet.
+    fn initialize(&mut self) {
+        loop {
+            match self.get_first_available_version() {
+                Ok(first_available_version) => {
+                    info!(
+                        first_available_version = first_available_version,
+                        "[ledger pruner worker] initialized."
+                    );
+                    self.target_first_available_version = first_available_version;
+                    self.record_progress(first_available_version);
+                    return;
+                }
+                Err(e) => {
+                    error!(
+                        error = ?e,
+                        "[ledger pruner worker] Error on first seek. Retrying in 1 second.",
+                    );
+                    sleep(Duration::from_secs(1));
+                }
+            }
+        }
+    }
+
+    fn get_first_available_version(&self) -> Result<Version> {
+        let mut iter = self.db.iter::<TransactionSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        Ok(iter.next().transpose()?.map_or(0, |(version, _)| version))
+    }
+
+    fn record_progress(&mut self, first_available_version: Version) {
+        self.first_available_version
+            .store(first_available_version, Ordering::Relaxed);
+        DIEM_STORAGE_PRUNER_FIRST_AVAILABLE_LEDGER_VERSION.set(first_available_version as i64);
+    }
+
+    /// Tries to receive all pending commands, blocking waits for the next command if no work needs
+    /// to be done, otherwise quits with `true` to allow the outer loop to do some work before
+    /// getting back here.
+    ///
+    /// Returns `false` if `Command::Quit` is received, to break the outer loop and let `work()`
+    /// return.
+    fn receive_commands(&mut self) -> bool {
+        loop {
+            let command = if self.blocking_recv {
+                self.command_receiver
+                    .recv()
+                    .expect("Sender should not destruct prematurely.")
+            } else {
+                match self.command_receiver.try_recv() {
+                    Ok(command) => command,
+                    Err(_) => return true,
+                }
+            };
+
+            match command {
+                Command::Quit => return false,
+                Command::Prune {
+                    least_readable_version,
+                } => {
+                    if least_readable_version > self.target_first_available_version {
+                        self.target_first_available_version = least_readable_version;
+                        self.blocking_recv = false;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Deletes the ledger history of versions in `[first_available_version,
+/// target_first_available_version)`, at most `max_versions` of them, in one batch. Returns the new
+/// first available version.
+pub fn prune_ledger(
+    db: &DB,
+    first_available_version: Version,
+    target_first_available_version: Version,
+    max_versions: u64,
+) -> Result<Version> {
+    let end_version = std::cmp::min(
+        target_first_available_version,
+        first_available_version.saturating_add(max_versions),
+    );
+    if first_available_version >= end_version {
+        return Ok(first_available_version);
+    }
+
+    let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+        .with_label_values(&["ledger_pruner_commit"])
+        .start_timer();
+    let mut batch = SchemaBatch::new();
+
+    let mut iter = db.iter::<TransactionSchema>(ReadOptions::default())?;
+    iter.seek(&first_available_version)?;
+    for res in iter {
+        let (version, txn) = res?;
+        if version >= end_version {
+            break;
+        }
+        if let Transaction::UserTransaction(user_txn) = &txn {
+            batch.delete::<TransactionByAccountSchema>(&(
+                user_txn.sender(),
+                user_txn.sequence_number(),
+            ))?;
+        }
+        // The secondary indexes are deleted whether enabled or not, deleting nonexistent keys is
+        // harmless and covers the ones written while they were enabled.
+        if let Some(script_function) = script_function(&txn) {
+            batch.delete::<TransactionByScriptFunctionSchema>(&script_function_key(
+                script_function,
+                version,
+            ))?;
+        }
+        batch.delete::<TransactionByHashSchema>(&txn.hash())?;
+        batch.delete::<TransactionSchema>(&version)?;
+        batch.delete::<TransactionInfoSchema>(&version)?;
+        batch.delete::<WriteSetSchema>(&version)?;
+    }
+
+    let mut iter = db.iter::<EventSchema>(ReadOptions::default())?;
+    iter.seek(&first_available_version)?;
+    for res in iter {
+        let ((version, index), event) = res?;
+        if version >= end_version {
+            break;
+        }
+        batch.delete::<EventByKeySchema>(&(*event.key(), event.sequence_number()))?;
+        batch.delete::<EventByVersionSchema>(&(*event.key(), version, event.sequence_number()))?;
+        batch.delete::<TransactionByEventTypeSchema>(&(event.type_tag().clone(), version))?;
+        batch.delete::<EventSchema>(&(version, index))?;
+    }
+
+    let mut iter = db.iter::<EventAccumulatorSchema>(ReadOptions::default())?;
+    iter.seek(&(first_available_version, Position::from_inorder_index(0)))?;
+    for res in iter {
+        let ((version, position), _) = res?;
+        if version >= end_version {
+            break;
+        }
+        batch.delete::<EventAccumulatorSchema>(&(version, position))?;
+    }
+
+    db.write_schemas(batch)?;
+    Ok(end_version)
+}
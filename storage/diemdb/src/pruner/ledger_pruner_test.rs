@@ -0,0 +1,213 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::ledger_pruner::*;
+use crate::{
+    schema::{
+        event::EventSchema, transaction::TransactionSchema,
+        transaction_by_account::TransactionByAccountSchema,
+        transaction_by_hash::TransactionByHashSchema, transaction_info::TransactionInfoSchema,
+    },
+    test_helper::arb_blocks_to_commit,
+    DiemDB,
+};
+use diem_config::config::SecondaryIndexesConfig;
+use diem_crypto::hash::CryptoHash;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{TransactionToCommit, Version},
+};
+use proptest::prelude::*;
+use schemadb::{ReadOptions, DB};
+use std::sync::Arc;
+use storage_interface::{DbReader, DbWriter, LedgerHistoryPruned};
+
+fn save_blocks(db: &DiemDB, blocks: &[(Vec<TransactionToCommit>, LedgerInfoWithSignatures)]) {
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in blocks {
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver, /* first_version */
+            Some(ledger_info_with_sigs),
+        )
+        .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+}
+
+fn num_events_at(db: &DB, version: Version) -> usize {
+    let mut iter = db.iter::<EventSchema>(ReadOptions::default()).unwrap();
+    iter.seek(&version).unwrap();
+    iter.map(|res| res.unwrap())
+        .take_while(|((v, _), _)| *v == version)
+        .count()
+}
+
+fn verify_ledger_history(db: &DB, txns: &[TransactionToCommit], first_available_version: Version) {
+    for (version, txn_to_commit) in (0..).zip(txns.iter()) {
+        let available = version >= first_available_version;
+        let txn = txn_to_commit.transaction();
+        let user_txn = txn.as_signed_user_txn().unwrap();
+
+        assert_eq!(
+            db.get::<TransactionSchema>(&version).unwrap().is_some(),
+            available
+        );
+        assert_eq!(
+            db.get::<TransactionInfoSchema>(&version).unwrap().is_some(),
+            available
+        );
+        assert_eq!(
+            db.get::<TransactionByHashSchema>(&txn.hash())
+                .unwrap()
+                .is_some(),
+            available
+        );
+        assert_eq!(
+            db.get::<TransactionByAccountSchema>(&(user_txn.sender(), user_txn.sequence_number()))
+                .unwrap()
+                .is_some(),
+            available
+        );
+        assert_eq!(
+            num_events_at(db, version),
+            if available {
+                txn_to_commit.events().len()
+            } else {
+                0
+            }
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_ledger_pruner(
+        input in arb_blocks_to_commit(),
+        ledger_versions_to_keep in 0u64..10,
+    ) {
+        let db = DiemDB::new_for_test();
+        save_blocks(&db, &input);
+
+        let txns = input
+            .iter()
+            .flat_map(|(txns_to_commit, _)| txns_to_commit.iter().cloned())
+            .collect::<Vec<_>>();
+        let latest_version = db.get_latest_version().unwrap();
+
+        let pruner = LedgerPruner::new(Arc::clone(&db.db), ledger_versions_to_keep);
+        pruner.wake_and_wait(latest_version).unwrap();
+
+        let first_available_version = pruner.first_available_version(latest_version);
+        prop_assert_eq!(
+            first_available_version,
+            latest_version.saturating_sub(ledger_versions_to_keep)
+        );
+        verify_ledger_history(&db.db, &txns, first_available_version);
+    }
+
+    #[test]
+    fn test_ledger_pruner_keeps_latest_ledger_info_history(input in arb_blocks_to_commit()) {
+        prop_assume!(input.len() > 1);
+        let db = DiemDB::open_in_memory(
+            None,    /* prune_window */
+            Some(0), /* ledger_prune_window */
+            SecondaryIndexesConfig::default(),
+        )
+        .unwrap();
+        let (last_block, blocks) = input.split_last().unwrap();
+        save_blocks(&db, blocks);
+        let ledger_info_version = db.get_latest_version().unwrap();
+        // Saved while syncing, beyond the latest ledger info.
+        db.save_transactions(&last_block.0, ledger_info_version + 1, None)
+            .unwrap();
+
+        db.ledger_pruner
+            .as_ref()
+            .unwrap()
+            .wake_and_wait(ledger_info_version)
+            .unwrap();
+
+        let txns = input
+            .iter()
+            .flat_map(|(txns_to_commit, _)| txns_to_commit.iter().cloned())
+            .collect::<Vec<_>>();
+        prop_assert_eq!(
+            db.get_first_available_ledger_version().unwrap(),
+            ledger_info_version
+        );
+        verify_ledger_history(&db.db, &txns, ledger_info_version);
+    }
+
+    #[test]
+    fn test_get_account_transactions_after_prune(
+        input in arb_blocks_to_commit(),
+        ledger_versions_to_keep in 0u64..10,
+    ) {
+        let db = DiemDB::open_in_memory(
+            None, /* prune_window */
+            Some(ledger_versions_to_keep),
+            SecondaryIndexesConfig::default(),
+        )
+        .unwrap();
+        save_blocks(&db, &input);
+        let ledger_version = db.get_latest_version().unwrap();
+        db.ledger_pruner
+            .as_ref()
+            .unwrap()
+            .wake_and_wait(ledger_version)
+            .unwrap();
+        let first_available_version = db.get_first_available_ledger_version().unwrap();
+
+        let txns = input
+            .iter()
+            .flat_map(|(txns_to_commit, _)| txns_to_commit.iter())
+            .map(|txn_to_commit| txn_to_commit.transaction().as_signed_user_txn().unwrap());
+        for (version, txn) in (0..).zip(txns) {
+            let result = db.get_account_transactions(
+                txn.sender(),
+                txn.sequence_number(),
+                1,     /* limit */
+                false, /* include_events */
+                ledger_version,
+            );
+            if version < first_available_version {
+                let error = result.unwrap_err();
+                let pruned = error.downcast_ref::<LedgerHistoryPruned>().unwrap();
+                prop_assert_eq!(pruned.first_available_version, first_available_version);
+            } else {
+                let txns_with_proofs = result.unwrap().into_inner();
+                prop_assert_eq!(txns_with_proofs.len(), 1);
+                prop_assert_eq!(txns_with_proofs[0].version, version);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prune_ledger_in_batches(input in arb_blocks_to_commit()) {
+        let db = DiemDB::new_for_test();
+        save_blocks(&db, &input);
+
+        let txns = input
+            .iter()
+            .flat_map(|(txns_to_commit, _)| txns_to_commit.iter().cloned())
+            .collect::<Vec<_>>();
+        let target = txns.len() as Version / 2;
+
+        let mut first_available_version = 0;
+        while first_available_version < target {
+            let new_first_available_version =
+                prune_ledger(&db.db, first_available_version, target, 1 /* max_versions */)
+                    .unwrap();
+            prop_assert_eq!(new_first_available_version, first_available_version + 1);
+            first_available_version = new_first_available_version;
+            verify_ledger_history(&db.db, &txns, first_available_version);
+        }
+        prop_assert_eq!(
+            prune_ledger(&db.db, target, target, 1 /* max_versions */).unwrap(),
+            target
+        );
+    }
+}
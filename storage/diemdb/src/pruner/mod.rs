@@ -3,6 +3,11 @@
 
 //! This module provides `Pruner` which manages a thread pruning old data in the background and is
 //! meant to be triggered by other threads as they commit new data to the DB.
+//!
+//! Old states are pruned by `Pruner`, while old ledger history is pruned by `LedgerPruner`, each
+//! with its own prune window.
+
+pub(crate) mod ledger_pruner;
 
 use crate::{
     metrics::{
@@ -73,6 +78,12 @@ impl Pruner {
         }
     }
 
+    /// Returns the least version whose state is kept readable given `latest_version`. States
+    /// of versions before it are pruned or scheduled to be pruned.
+    pub fn least_readable_version(&self, latest_version: Version) -> Version {
+        latest_version.saturating_sub(self.historical_versions_to_keep)
+    }
+
     /// Sends pruning command to the worker thread when necessary.
     pub fn wake(&self, latest_version: Version) {
         if latest_version > self.historical_versions_to_keep {
@@ -408,5 +419,7 @@ pub fn prune_state(
     }
 }
 
+#[cfg(test)]
+mod ledger_pruner_test;
 #[cfg(test)]
 mod test;
@@ -4,7 +4,6 @@
 use super::*;
 use crate::{change_set::ChangeSet, state_store::StateStore, DiemDB};
 use diem_crypto::HashValue;
-use diem_temppath::TempPath;
 use diem_types::{account_address::AccountAddress, account_state_blob::AccountStateBlob};
 use std::collections::HashMap;
 
@@ -47,8 +46,7 @@ fn test_pruner() {
     let value1 = AccountStateBlob::from(vec![0x02]);
     let value2 = AccountStateBlob::from(vec![0x03]);
 
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir).db;
+    let db = DiemDB::new_for_test().db;
     let state_store = &StateStore::new(Arc::clone(&db));
     let pruner = Pruner::new(Arc::clone(&db), 0 /* historical_versions_to_keep */);
 
@@ -81,6 +79,7 @@ fn test_pruner() {
     // Prune till version=1.
     {
         pruner.wake_and_wait(1 /* latest_version */).unwrap();
+        assert_eq!(pruner.least_readable_version(1 /* latest_version */), 1);
         // root0 is gone.
         assert!(state_store
             .get_account_state_with_proof_by_version(address, 0)
@@ -108,8 +107,7 @@ fn test_worker_quit_eagerly() {
     let value1 = AccountStateBlob::from(vec![0x02]);
     let value2 = AccountStateBlob::from(vec![0x03]);
 
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir).db;
+    let db = DiemDB::new_for_test().db;
     let state_store = &StateStore::new(Arc::clone(&db));
 
     let _root0 = put_account_state_set(
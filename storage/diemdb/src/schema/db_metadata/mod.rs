@@ -0,0 +1,77 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for metadata of the DB itself, e.g. the lower
+//! bound of the ledger history it holds.
+//!
+//! Serialized metadata value identified by the serialized metadata key.
+//! ```text
+//! |<--key-->|<--value-->|
+//! | key tag | value     |
+//! ```
+
+use crate::schema::DB_METADATA_CF_NAME;
+use anyhow::Result;
+use diem_types::transaction::Version;
+#[cfg(test)]
+use proptest_derive::Arbitrary;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use serde::{Deserialize, Serialize};
+
+/// The kinds of DB metadata.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub(crate) enum DbMetadataKey {
+    /// The first version of the contiguous ledger history (transactions, transaction infos and
+    /// events) up to the latest version. Absent if the history starts at genesis. Versions before
+    /// it (except possibly genesis) were never written, e.g. because the DB was fast synced or
+    /// restored from a later version.
+    FirstAvailableLedgerVersion,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub(crate) enum DbMetadataValue {
+    Version(Version),
+}
+
+impl DbMetadataValue {
+    pub fn expect_version(self) -> Version {
+        match self {
+            Self::Version(version) => version,
+        }
+    }
+}
+
+define_schema!(
+    DbMetadataSchema,
+    DbMetadataKey,
+    DbMetadataValue,
+    DB_METADATA_CF_NAME
+);
+
+impl KeyCodec<DbMetadataSchema> for DbMetadataKey {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        bcs::to_bytes(self).map_err(Into::into)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        bcs::from_bytes(data).map_err(Into::into)
+    }
+}
+
+impl ValueCodec<DbMetadataSchema> for DbMetadataValue {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        bcs::to_bytes(self).map_err(Into::into)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        bcs::from_bytes(data).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,16 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use proptest::prelude::*;
+use schemadb::schema::assert_encode_decode;
+
+proptest! {
+    #[test]
+    fn test_encode_decode(
+        key in any::<DbMetadataKey>(),
+        value in any::<DbMetadataValue>(),
+    ) {
+        assert_encode_decode::<DbMetadataSchema>(&key, &value);
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for an optional transaction index via which the
+//! versions of the transactions that emitted events of a certain type can be found. With the
+//! version one can resort to `TransactionSchema` and `EventSchema` for the content.
+//!
+//! ```text
+//! |<-------key------->|<-value->|
+//! | type_tag | txn_ver |   ()    |
+//! ```
+//!
+//! `type_tag` is BCS encoded, which is prefix free, so the keys of a type tag are adjacent and
+//! ordered by version.
+
+use crate::schema::{ensure_slice_len_eq, ensure_slice_len_gt, TRANSACTION_BY_EVENT_TYPE_CF_NAME};
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use diem_types::transaction::Version;
+use move_core_types::language_storage::TypeTag;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    TransactionByEventTypeSchema,
+    Key,
+    (),
+    TRANSACTION_BY_EVENT_TYPE_CF_NAME
+);
+
+type Key = (TypeTag, Version);
+
+impl KeyCodec<TransactionByEventTypeSchema> for Key {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (ref type_tag, version) = *self;
+
+        let mut encoded = bcs::to_bytes(type_tag)?;
+        encoded.write_u64::<BigEndian>(version)?;
+
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        const VERSION_SIZE: usize = size_of::<Version>();
+
+        ensure_slice_len_gt(data, VERSION_SIZE)?;
+        let type_tag_len = data.len() - VERSION_SIZE;
+        let type_tag = bcs::from_bytes(&data[..type_tag_len])?;
+        let version = (&data[type_tag_len..]).read_u64::<BigEndian>()?;
+
+        Ok((type_tag, version))
+    }
+}
+
+impl ValueCodec<TransactionByEventTypeSchema> for () {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,16 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use proptest::prelude::*;
+use schemadb::schema::assert_encode_decode;
+
+proptest! {
+    #[test]
+    fn test_encode_decode(
+        type_tag in any::<TypeTag>(),
+        version in any::<Version>(),
+    ) {
+        assert_encode_decode::<TransactionByEventTypeSchema>(&(type_tag, version), &());
+    }
+}
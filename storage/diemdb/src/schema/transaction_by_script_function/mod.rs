@@ -0,0 +1,72 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for an optional transaction index via which the
+//! versions of the user transactions that called a certain script function can be found. With
+//! the version one can resort to `TransactionSchema` for the transaction content.
+//!
+//! ```text
+//! |<--------------key-------------->|<-value->|
+//! | module_id | function | txn_ver  |   ()    |
+//! ```
+//!
+//! `module_id` and `function` are BCS encoded, which is prefix free, so the keys of a script
+//! function are adjacent and ordered by version.
+
+use crate::schema::{
+    ensure_slice_len_eq, ensure_slice_len_gt, TRANSACTION_BY_SCRIPT_FUNCTION_CF_NAME,
+};
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use diem_types::transaction::Version;
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    TransactionByScriptFunctionSchema,
+    Key,
+    (),
+    TRANSACTION_BY_SCRIPT_FUNCTION_CF_NAME
+);
+
+type Key = (ModuleId, Identifier, Version);
+
+impl KeyCodec<TransactionByScriptFunctionSchema> for Key {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (ref module_id, ref function, version) = *self;
+
+        let mut encoded = bcs::to_bytes(&(module_id, function))?;
+        encoded.write_u64::<BigEndian>(version)?;
+
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        const VERSION_SIZE: usize = size_of::<Version>();
+
+        ensure_slice_len_gt(data, VERSION_SIZE)?;
+        let function_len = data.len() - VERSION_SIZE;
+        let (module_id, function) = bcs::from_bytes(&data[..function_len])?;
+        let version = (&data[function_len..]).read_u64::<BigEndian>()?;
+
+        Ok((module_id, function, version))
+    }
+}
+
+impl ValueCodec<TransactionByScriptFunctionSchema> for () {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,20 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use proptest::prelude::*;
+use schemadb::schema::assert_encode_decode;
+
+proptest! {
+    #[test]
+    fn test_encode_decode(
+        module_id in any::<ModuleId>(),
+        function in any::<Identifier>(),
+        version in any::<Version>(),
+    ) {
+        assert_encode_decode::<TransactionByScriptFunctionSchema>(
+            &(module_id, function, version),
+            &(),
+        );
+    }
+}
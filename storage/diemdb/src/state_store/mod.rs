@@ -20,7 +20,7 @@ use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state_blob::AccountStateBlob,
     nibble::{nibble_path::NibblePath, ROOT_NIBBLE_HEIGHT},
-    proof::{SparseMerkleProof, SparseMerkleRangeProof},
+    proof::{SparseMerkleMultiProof, SparseMerkleProof, SparseMerkleRangeProof},
     transaction::Version,
 };
 use schemadb::{SchemaBatch, DB};
@@ -52,6 +52,38 @@ impl StateStore {
         JellyfishMerkleTree::new(self).get_with_proof(address.hash(), version)
     }
 
+    /// Get the account state blobs of `addresses` with one proof for all of them, given the
+    /// version of the state Merkle tree. The blobs are in the order of `addresses`, which should
+    /// be distinct.
+    pub fn get_account_states_with_multiproof_by_version(
+        &self,
+        addresses: &[AccountAddress],
+        version: Version,
+    ) -> Result<(
+        Vec<Option<AccountStateBlob>>,
+        SparseMerkleMultiProof<AccountStateBlob>,
+    )> {
+        // The multiproof is over the hashed addresses in ascending order.
+        let mut keys_and_indices = addresses
+            .iter()
+            .enumerate()
+            .map(|(idx, address)| (address.hash(), idx))
+            .collect::<Vec<_>>();
+        keys_and_indices.sort_unstable();
+        let keys = keys_and_indices
+            .iter()
+            .map(|(key, _idx)| *key)
+            .collect::<Vec<_>>();
+        let (sorted_blobs, proof) =
+            JellyfishMerkleTree::new(self).get_with_multiproof(&keys, version)?;
+
+        let mut blobs = vec![None; addresses.len()];
+        for ((_key, idx), blob) in keys_and_indices.into_iter().zip(sorted_blobs) {
+            blobs[idx] = blob;
+        }
+        Ok((blobs, proof))
+    }
+
     /// Gets the proof that proves a range of accounts.
     pub fn get_account_state_range_proof(
         &self,
@@ -81,7 +113,7 @@ impl StateStore {
             .collect::<Vec<_>>();
 
         let (new_root_hash_vec, tree_update_batch) = JellyfishMerkleTree::new(self)
-            .batch_put_value_sets(blob_sets, node_hashes, first_version)?;
+            .batch_put_value_sets_parallel(blob_sets, node_hashes, first_version)?;
 
         let num_versions = new_root_hash_vec.len();
         assert_eq!(num_versions, tree_update_batch.node_stats.len());
@@ -108,6 +140,29 @@ impl StateStore {
         Ok(new_root_hash_vec)
     }
 
+    /// Put the tree nodes resulted from applying `blobs` to the tree at `base_version` to `cs`,
+    /// making the tree at `version`, and return its root hash. `blobs` is expected to have all
+    /// the account states changed in between.
+    pub fn put_account_state_blobs_on_base_version(
+        &self,
+        blobs: Vec<(HashValue, AccountStateBlob)>,
+        base_version: Version,
+        version: Version,
+        cs: &mut ChangeSet,
+    ) -> Result<HashValue> {
+        let (root_hash, tree_update_batch) = JellyfishMerkleTree::new(self)
+            .put_value_set_on_base_version(blobs, base_version, version)?;
+
+        add_node_batch(&mut cs.batch, &tree_update_batch.node_batch)?;
+        tree_update_batch
+            .stale_node_index_batch
+            .iter()
+            .map(|row| cs.batch.put::<StaleNodeIndexSchema>(row, &()))
+            .collect::<Result<Vec<()>>>()?;
+
+        Ok(root_hash)
+    }
+
     pub fn get_root_hash(&self, version: Version) -> Result<HashValue> {
         JellyfishMerkleTree::new(self).get_root_hash(version)
     }
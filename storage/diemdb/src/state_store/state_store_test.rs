@@ -4,12 +4,15 @@
 use super::*;
 use crate::{pruner, DiemDB};
 use diem_jellyfish_merkle::restore::JellyfishMerkleRestore;
-use diem_temppath::TempPath;
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state_blob::AccountStateBlob,
 };
-use proptest::{collection::hash_map, prelude::*};
+use proptest::{
+    collection::{hash_map, hash_set},
+    prelude::*,
+};
+use storage_interface::DbReader;
 
 fn put_account_state_set(
     store: &StateStore,
@@ -79,8 +82,7 @@ fn verify_state_in_store(
 
 #[test]
 fn test_empty_store() {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
     let store = &db.state_store;
     let address = AccountAddress::new([1u8; AccountAddress::LENGTH]);
     assert!(store
@@ -90,8 +92,7 @@ fn test_empty_store() {
 
 #[test]
 fn test_state_store_reader_writer() {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
     let store = &db.state_store;
     let address1 = AccountAddress::new([1u8; AccountAddress::LENGTH]);
     let address2 = AccountAddress::new([2u8; AccountAddress::LENGTH]);
@@ -144,8 +145,7 @@ fn test_retired_records() {
     let value3 = AccountStateBlob::from(vec![0x03]);
     let value3_update = AccountStateBlob::from(vec![0x13]);
 
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
     let store = &db.state_store;
 
     // Update.
@@ -237,8 +237,7 @@ proptest! {
         // Convert to a vector so iteration order becomes deterministic.
         let kvs: Vec<_> = input.into_iter().collect();
 
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.state_store;
         init_store(store, kvs.clone().into_iter());
 
@@ -267,16 +266,14 @@ proptest! {
                 (Just(input), 1..len)
             })
     ) {
-        let tmp_dir1 = TempPath::new();
-        let db1 = DiemDB::new_for_test(&tmp_dir1);
+        let db1 = DiemDB::new_for_test();
         let store1 = &db1.state_store;
         init_store(store1, input.clone().into_iter());
 
         let version = (input.len() - 1) as Version;
         let expected_root_hash = store1.get_root_hash(version).unwrap();
 
-        let tmp_dir2 = TempPath::new();
-        let db2 = DiemDB::new_for_test(&tmp_dir2);
+        let db2 = DiemDB::new_for_test();
         let store2 = &db2.state_store;
 
         let mut restore =
@@ -325,16 +322,14 @@ proptest! {
                 (Just(input), 1..len)
             })
     ) {
-        let tmp_dir1 = TempPath::new();
-        let db1 = DiemDB::new_for_test(&tmp_dir1);
+        let db1 = DiemDB::new_for_test();
         let store1 = &db1.state_store;
         init_store(store1, input.clone().into_iter());
 
         let version = (input.len() - 1) as Version;
         let expected_root_hash = store1.get_root_hash(version).unwrap();
 
-        let tmp_dir2 = TempPath::new();
-        let db2 = DiemDB::new_for_test(&tmp_dir2);
+        let db2 = DiemDB::new_for_test();
         let store2 = &db2.state_store;
 
         let mut restore =
@@ -356,11 +351,96 @@ proptest! {
             .unwrap();
 
         restore.add_chunk(batch1, proof_of_batch1).unwrap();
+        // Wait for the chunk to be written.
+        drop(restore);
 
         let expected = store2.get_rightmost_leaf_naive().unwrap();
         let actual = store2.get_rightmost_leaf().unwrap();
         prop_assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_get_account_states_range(
+        (input, page_size) in hash_map(any::<AccountAddress>(), any::<AccountStateBlob>(), 1..200)
+            .prop_flat_map(|input| {
+                let len = input.len();
+                (Just(input), 1..=len as u64)
+            })
+    ) {
+        let db1 = DiemDB::new_for_test();
+        init_store(&db1.state_store, input.clone().into_iter());
+
+        let version = (input.len() - 1) as Version;
+        let expected_root_hash = db1.state_store.get_root_hash(version).unwrap();
+
+        // Every page restores into another DB, which verifies the page against the root hash.
+        let db2 = DiemDB::new_for_test();
+        let mut restore =
+            JellyfishMerkleRestore::new(Arc::clone(&db2.state_store), version, expected_root_hash)
+                .unwrap();
+        let mut actual_values = vec![];
+        let mut start_key = Some(HashValue::zero());
+        while let Some(key) = start_key {
+            let page = db1.get_account_states_range(version, key, page_size).unwrap();
+            prop_assert!(page.account_blobs.len() as u64 <= page_size);
+            actual_values.extend(page.account_blobs.clone());
+            restore.add_chunk(page.account_blobs, page.proof.unwrap()).unwrap();
+            start_key = page.next_key;
+        }
+        restore.finish().unwrap();
+
+        let mut expected_values: Vec<_> = input
+            .into_iter()
+            .map(|(addr, value)| (addr.hash(), value))
+            .collect();
+        expected_values.sort_unstable_by_key(|(key, _value)| *key);
+        prop_assert_eq!(actual_values, expected_values);
+        prop_assert_eq!(db2.state_store.get_root_hash(version).unwrap(), expected_root_hash);
+    }
+
+    #[test]
+    fn test_get_account_states_with_multiproof_by_version(
+        (input, nonexistent_addresses) in hash_map(any::<AccountAddress>(), any::<AccountStateBlob>(), 1..200)
+            .prop_flat_map(|input| {
+                let input_clone = input.clone();
+                (
+                    Just(input),
+                    hash_set(
+                        any::<AccountAddress>().prop_filter("Must not exist.", move |addr| {
+                            !input_clone.contains_key(addr)
+                        }),
+                        0..20,
+                    ),
+                )
+            })
+    ) {
+        let db = DiemDB::new_for_test();
+        let store = &db.state_store;
+        init_store(store, input.clone().into_iter());
+        let version = (input.len() - 1) as Version;
+        let root_hash = store.get_root_hash(version).unwrap();
+
+        let addresses: Vec<_> = input
+            .keys()
+            .chain(nonexistent_addresses.iter())
+            .cloned()
+            .collect();
+        let (blobs, proof) = store
+            .get_account_states_with_multiproof_by_version(&addresses, version)
+            .unwrap();
+        prop_assert_eq!(
+            &blobs,
+            &addresses.iter().map(|addr| input.get(addr).cloned()).collect::<Vec<_>>()
+        );
+
+        let mut elements: Vec<_> = addresses
+            .iter()
+            .map(|addr| addr.hash())
+            .zip(blobs.iter().map(Option::as_ref))
+            .collect();
+        elements.sort_unstable_by_key(|(key, _blob)| *key);
+        prop_assert!(proof.verify(root_hash, &elements).is_ok());
+    }
 }
 
 // Initializes the state store by inserting one key at each version.
@@ -5,7 +5,6 @@
 
 use super::*;
 use diem_crypto::hash::CryptoHash;
-use diem_temppath::TempPath;
 use diem_types::{
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
@@ -19,8 +18,7 @@ fn to_blocks_to_commit(
 ) -> Result<Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>> {
     // Use temporary DiemDB and STORE LEVEL APIs to calculate hashes on a per transaction basis.
     // Result is used to test the batch PUBLIC API for saving everything, i.e. `save_transactions()`
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
 
     let mut cur_ver = 0;
     let mut cur_txn_accu_hash = HashValue::zero();
@@ -6,25 +6,44 @@
 use crate::{
     change_set::ChangeSet,
     errors::DiemDbError,
-    schema::{transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema},
+    schema::{
+        transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema,
+        transaction_by_event_type::TransactionByEventTypeSchema,
+        transaction_by_hash::TransactionByHashSchema,
+        transaction_by_script_function::TransactionByScriptFunctionSchema,
+        write_set::WriteSetSchema,
+    },
 };
 use anyhow::{ensure, format_err, Result};
+use diem_config::config::SecondaryIndexesConfig;
+use diem_crypto::{hash::CryptoHash, HashValue};
 use diem_types::{
     account_address::AccountAddress,
     block_metadata::BlockMetadata,
-    transaction::{Transaction, Version},
+    contract_event::ContractEvent,
+    transaction::{ScriptFunction, Transaction, TransactionPayload, Version},
+    write_set::WriteSet,
+};
+use move_core_types::{
+    identifier::{IdentStr, Identifier},
+    language_storage::{ModuleId, TypeTag},
 };
 use schemadb::{ReadOptions, SchemaIterator, DB};
-use std::sync::Arc;
+use std::{collections::BTreeSet, sync::Arc};
+use storage_interface::LedgerHistoryPruned;
 
 #[derive(Debug)]
 pub(crate) struct TransactionStore {
     db: Arc<DB>,
+    secondary_indexes: SecondaryIndexesConfig,
 }
 
 impl TransactionStore {
-    pub fn new(db: Arc<DB>) -> Self {
-        Self { db }
+    pub fn new(db: Arc<DB>, secondary_indexes: SecondaryIndexesConfig) -> Self {
+        Self {
+            db,
+            secondary_indexes,
+        }
     }
 
     /// Gets the version of a transaction by the sender `address` and `sequence_number`.
@@ -46,6 +65,18 @@ impl TransactionStore {
         Ok(None)
     }
 
+    /// Gets the version of a transaction by its hash.
+    pub fn get_transaction_version_by_hash(
+        &self,
+        hash: &HashValue,
+        ledger_version: Version,
+    ) -> Result<Option<Version>> {
+        Ok(match self.db.get::<TransactionByHashSchema>(hash)? {
+            Some(version) if version <= ledger_version => Some(version),
+            _ => None,
+        })
+    }
+
     /// Gets an iterator that yields `(sequence_number, version)` for each
     /// transaction sent by an account, starting at `start_seq_num`, and returning
     /// at most `num_versions` results with `version <= ledger_version`.
@@ -58,6 +89,7 @@ impl TransactionStore {
         start_seq_num: u64,
         num_versions: u64,
         ledger_version: Version,
+        first_available_version: Version,
     ) -> Result<AccountTransactionVersionIter> {
         let mut iter = self
             .db
@@ -72,9 +104,67 @@ impl TransactionStore {
                 .ok_or_else(|| format_err!("too many transactions requested"))?,
             prev_version: None,
             ledger_version,
+            first_available_version,
         })
     }
 
+    /// Gets the versions of at most `limit` transactions which emitted events of `type_tag`,
+    /// starting from `start_version`, with `version <= ledger_version`.
+    pub fn get_transaction_versions_by_event_type(
+        &self,
+        type_tag: &TypeTag,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<Version>> {
+        ensure!(
+            self.secondary_indexes.by_event_type,
+            "Transaction index by event type is not enabled.",
+        );
+        let mut iter = self
+            .db
+            .iter::<TransactionByEventTypeSchema>(ReadOptions::default())?;
+        iter.seek(&(type_tag.clone(), start_version))?;
+        iter.map(|res| res.map(|(key, ())| key))
+            .take_while(|res| match res {
+                Ok((tag, version)) => tag == type_tag && *version <= ledger_version,
+                Err(_) => true,
+            })
+            .take(limit as usize)
+            .map(|res| res.map(|(_tag, version)| version))
+            .collect()
+    }
+
+    /// Gets the versions of at most `limit` user transactions which called the script function
+    /// `module::function`, starting from `start_version`, with `version <= ledger_version`.
+    pub fn get_transaction_versions_by_script_function(
+        &self,
+        module: &ModuleId,
+        function: &IdentStr,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<Version>> {
+        ensure!(
+            self.secondary_indexes.by_script_function,
+            "Transaction index by script function is not enabled.",
+        );
+        let mut iter = self
+            .db
+            .iter::<TransactionByScriptFunctionSchema>(ReadOptions::default())?;
+        iter.seek(&(module.clone(), function.to_owned(), start_version))?;
+        iter.map(|res| res.map(|(key, ())| key))
+            .take_while(|res| match res {
+                Ok((m, f, version)) => {
+                    m == module && f.as_ident_str() == function && *version <= ledger_version
+                }
+                Err(_) => true,
+            })
+            .take(limit as usize)
+            .map(|res| res.map(|(_m, _f, version)| version))
+            .collect()
+    }
+
     /// Get signed transaction given `version`
     pub fn get_transaction(&self, version: Version) -> Result<Transaction> {
         self.db
@@ -82,6 +172,13 @@ impl TransactionStore {
             .ok_or_else(|| DiemDbError::NotFound(format!("Txn {}", version)).into())
     }
 
+    /// Get the write set of the transaction at `version`.
+    pub fn get_write_set(&self, version: Version) -> Result<WriteSet> {
+        self.db
+            .get::<WriteSetSchema>(&version)?
+            .ok_or_else(|| DiemDbError::NotFound(format!("WriteSet at version {}", version)).into())
+    }
+
     /// Gets an iterator that yields `num_transactions` transactions starting from `start_version`.
     pub fn get_transaction_iter(
         &self,
@@ -137,10 +234,71 @@ impl TransactionStore {
                 &version,
             )?;
         }
+        cs.batch
+            .put::<TransactionByHashSchema>(&transaction.hash(), &version)?;
         cs.batch.put::<TransactionSchema>(&version, transaction)?;
 
         Ok(())
     }
+
+    /// Save the write set of the transaction at `version`.
+    pub fn put_write_set(
+        &self,
+        version: Version,
+        write_set: &WriteSet,
+        cs: &mut ChangeSet,
+    ) -> Result<()> {
+        cs.batch.put::<WriteSetSchema>(&version, write_set)
+    }
+
+    /// Save the enabled secondary indexes of the transaction at `version`, which emitted `events`.
+    pub fn put_secondary_indexes(
+        &self,
+        version: Version,
+        transaction: &Transaction,
+        events: &[ContractEvent],
+        cs: &mut ChangeSet,
+    ) -> Result<()> {
+        if self.secondary_indexes.by_event_type {
+            let type_tags: BTreeSet<_> = events.iter().map(ContractEvent::type_tag).collect();
+            for type_tag in type_tags {
+                cs.batch
+                    .put::<TransactionByEventTypeSchema>(&(type_tag.clone(), version), &())?;
+            }
+        }
+        if self.secondary_indexes.by_script_function {
+            if let Some(script_function) = script_function(transaction) {
+                cs.batch.put::<TransactionByScriptFunctionSchema>(
+                    &script_function_key(script_function, version),
+                    &(),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the script function called by `transaction`, if it's a user transaction calling one.
+pub(crate) fn script_function(transaction: &Transaction) -> Option<&ScriptFunction> {
+    match transaction {
+        Transaction::UserTransaction(txn) => match txn.payload() {
+            TransactionPayload::ScriptFunction(script_function) => Some(script_function),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub(crate) fn script_function_key(
+    script_function: &ScriptFunction,
+    version: Version,
+) -> (ModuleId, Identifier, Version) {
+    (
+        script_function.module().clone(),
+        script_function.function().to_owned(),
+        version,
+    )
 }
 
 pub struct TransactionIter<'a> {
@@ -201,6 +359,7 @@ pub struct AccountTransactionVersionIter<'a> {
     end_seq_num: u64,
     prev_version: Option<Version>,
     ledger_version: Version,
+    first_available_version: Version,
 }
 
 impl<'a> AccountTransactionVersionIter<'a> {
@@ -216,6 +375,23 @@ impl<'a> AccountTransactionVersionIter<'a> {
                     return Ok(None);
                 }
 
+                // Pruning the ledger history deletes the index entries of the earliest transactions
+                // of the account, the first entry found is then past the requested one.
+                if (self.prev_version.is_none()
+                    && seq_num > self.expected_next_seq_num
+                    && self.first_available_version > 0)
+                    || version < self.first_available_version
+                {
+                    return Err(LedgerHistoryPruned {
+                        what: format!(
+                            "Transaction of account {} with sequence number {}",
+                            self.address, self.expected_next_seq_num,
+                        ),
+                        first_available_version: self.first_available_version,
+                    }
+                    .into());
+                }
+
                 // Ensure seq_num_{i+1} == seq_num_{i} + 1
                 ensure!(
                     seq_num == self.expected_next_seq_num,
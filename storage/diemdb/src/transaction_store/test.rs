@@ -3,13 +3,16 @@
 
 use super::*;
 use crate::DiemDB;
+use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
 use diem_proptest_helpers::Index;
-use diem_temppath::TempPath;
 use diem_types::{
     block_metadata::BlockMetadata,
+    event::EventKey,
     proptest_types::{AccountInfoUniverse, SignatureCheckedTransactionGen},
+    test_helpers::transaction_test_helpers::get_test_signed_txn,
     transaction::{SignedTransaction, Transaction},
 };
+use move_core_types::{ident_str, language_storage::CORE_CODE_ADDRESS};
 use proptest::{collection::vec, prelude::*};
 use std::collections::BTreeMap;
 
@@ -24,8 +27,7 @@ proptest! {
             1..10
         ),
     ) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.transaction_store;
         let txns = init_store(universe, gens, store);
 
@@ -45,6 +47,12 @@ proptest! {
                     .unwrap(),
                 Some(ver as Version)
             );
+            prop_assert_eq!(
+                store
+                    .get_transaction_version_by_hash(&txn.hash(), ledger_version)
+                    .unwrap(),
+                Some(ver as Version)
+            );
         }
 
         prop_assert!(store.get_transaction(ledger_version + 1).is_err());
@@ -58,8 +66,7 @@ proptest! {
             1..10
         ),
     ) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.transaction_store;
         let txns = init_store(universe, gens, store);
 
@@ -114,8 +121,7 @@ proptest! {
             1..100,
         )
     ) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.transaction_store;
 
         let mut cs = ChangeSet::new();
@@ -163,8 +169,7 @@ proptest! {
         ledger_version in 0_u64..50,
         num_versions in 0_u64..=50,
     ) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.transaction_store;
         let txns = init_store(universe, gens, store);
 
@@ -182,6 +187,7 @@ proptest! {
                 txn.sequence_number(),
                 1, /* num_versions */
                 ledger_version,
+                0, /* first_available_version */
             ).unwrap();
 
             if *version <= ledger_version {
@@ -224,6 +230,7 @@ proptest! {
                         seq_num_offset,
                         num_versions,
                         ledger_version,
+                        0, /* first_available_version */
                     )
                     .unwrap()
                     .collect::<Result<Vec<_>>>()
@@ -236,6 +243,99 @@ proptest! {
     }
 }
 
+#[test]
+fn test_secondary_indexes() {
+    let db = DiemDB::new_for_test();
+    let store = TransactionStore::new(
+        Arc::clone(&db.db),
+        SecondaryIndexesConfig {
+            by_event_type: true,
+            by_script_function: true,
+        },
+    );
+
+    let module = ModuleId::new(CORE_CODE_ADDRESS, ident_str!("M").to_owned());
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let txn = |seq_num: u64, function: Option<&str>| {
+        let payload = function.map(|f| {
+            TransactionPayload::ScriptFunction(ScriptFunction::new(
+                module.clone(),
+                Identifier::new(f).unwrap(),
+                vec![],
+                vec![],
+            ))
+        });
+        Transaction::UserTransaction(get_test_signed_txn(
+            AccountAddress::random(),
+            seq_num,
+            &private_key,
+            private_key.public_key(),
+            payload,
+        ))
+    };
+    let events = |type_tags: &[TypeTag]| {
+        type_tags
+            .iter()
+            .map(|type_tag| ContractEvent::new(EventKey::random(), 0, type_tag.clone(), vec![]))
+            .collect::<Vec<_>>()
+    };
+    let txns = vec![
+        (txn(0, Some("f")), events(&[TypeTag::U64])),
+        (
+            txn(0, None),
+            events(&[TypeTag::Bool, TypeTag::U64, TypeTag::U64]),
+        ),
+        (txn(0, Some("f")), events(&[])),
+        (txn(0, Some("g")), events(&[TypeTag::Bool])),
+    ];
+
+    let mut cs = ChangeSet::new();
+    for (ver, (txn, events)) in txns.iter().enumerate() {
+        store
+            .put_secondary_indexes(ver as Version, txn, events, &mut cs)
+            .unwrap();
+    }
+    store.db.write_schemas(cs.batch).unwrap();
+
+    let by_event_type = |type_tag: &TypeTag, start_version, limit, ledger_version| {
+        store
+            .get_transaction_versions_by_event_type(type_tag, start_version, limit, ledger_version)
+            .unwrap()
+    };
+    assert_eq!(by_event_type(&TypeTag::U64, 0, 10, 3), vec![0, 1]);
+    assert_eq!(by_event_type(&TypeTag::U64, 1, 10, 3), vec![1]);
+    assert_eq!(by_event_type(&TypeTag::U64, 0, 1, 3), vec![0]);
+    assert_eq!(by_event_type(&TypeTag::Bool, 0, 10, 3), vec![1, 3]);
+    assert_eq!(by_event_type(&TypeTag::Bool, 0, 10, 2), vec![1]);
+    assert!(by_event_type(&TypeTag::Address, 0, 10, 3).is_empty());
+
+    let by_script_function = |function: &str, start_version, limit, ledger_version| {
+        store
+            .get_transaction_versions_by_script_function(
+                &module,
+                IdentStr::new(function).unwrap(),
+                start_version,
+                limit,
+                ledger_version,
+            )
+            .unwrap()
+    };
+    assert_eq!(by_script_function("f", 0, 10, 3), vec![0, 2]);
+    assert_eq!(by_script_function("f", 1, 10, 3), vec![2]);
+    assert_eq!(by_script_function("f", 0, 10, 1), vec![0]);
+    assert_eq!(by_script_function("g", 0, 10, 3), vec![3]);
+    assert!(by_script_function("h", 0, 10, 3).is_empty());
+
+    // Disabled indexes can't be queried.
+    let store = TransactionStore::new(Arc::clone(&db.db), SecondaryIndexesConfig::default());
+    assert!(store
+        .get_transaction_versions_by_event_type(&TypeTag::U64, 0, 10, 3)
+        .is_err());
+    assert!(store
+        .get_transaction_versions_by_script_function(&module, ident_str!("f"), 0, 10, 3)
+        .is_err());
+}
+
 fn init_store(
     mut universe: AccountInfoUniverse,
     gens: Vec<(Index, SignatureCheckedTransactionGen)>,
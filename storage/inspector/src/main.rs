@@ -3,16 +3,28 @@
 
 #![forbid(unsafe_code)]
 
-use anyhow::Result;
-use diem_config::config::RocksdbConfig;
+use anyhow::{ensure, Result};
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
+use diem_crypto::{
+    hash::{CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher},
+    HashValue,
+};
 use diem_framework_releases::name_for_script;
 use diem_logger::info;
 use diemdb::DiemDB;
+use serde::Serialize;
 use std::path::PathBuf;
-use storage_interface::DbReader;
+use storage_interface::{DbReader, Order};
 
 use diem_types::{
-    account_address::AccountAddress, account_config::AccountResource, account_state::AccountState,
+    account_address::AccountAddress,
+    account_config::AccountResource,
+    account_state::AccountState,
+    contract_event::ContractEvent,
+    event::EventKey,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{Transaction, TransactionInfo, TransactionInfoTrait, Version},
 };
 use std::convert::TryFrom;
 use structopt::StructOpt;
@@ -39,6 +51,175 @@ enum Command {
     },
     #[structopt(name = "list-accounts")]
     ListAccounts,
+    /// Makes a checkpoint of the DB, which can be opened as a DB by itself. The DB can't be in use
+    /// by a running node, for which the backup service provides the same.
+    #[structopt(name = "db-checkpoint")]
+    DbCheckpoint {
+        #[structopt(long, parse(from_os_str))]
+        output_dir: PathBuf,
+    },
+    /// Prints events of an event stream as JSON.
+    #[structopt(name = "events")]
+    Events {
+        #[structopt(long)]
+        key: EventKey,
+        /// Sequence number of the first event, or the last one if `--descending`.
+        #[structopt(long, default_value = "0")]
+        start: u64,
+        #[structopt(long, default_value = "100")]
+        limit: u64,
+        #[structopt(long)]
+        descending: bool,
+    },
+    /// Prints a transaction looked up by its hash as JSON.
+    #[structopt(name = "txn-by-hash")]
+    TxnByHash {
+        hash: HashValue,
+        #[structopt(long)]
+        with_events: bool,
+    },
+    /// Prints the state of an account at a version (the latest by default) as JSON.
+    #[structopt(name = "account")]
+    Account {
+        #[structopt(parse(try_from_str))]
+        address: AccountAddress,
+        #[structopt(long)]
+        version: Option<Version>,
+    },
+    /// Prints the latest ledger info as JSON.
+    #[structopt(name = "ledger-info")]
+    LedgerInfo,
+    /// Prints the epoch ending ledger infos of epochs in [start_epoch, end_epoch) as JSON. By
+    /// default, all of them.
+    #[structopt(name = "epoch-history")]
+    EpochHistory {
+        #[structopt(long, default_value = "0")]
+        start_epoch: u64,
+        #[structopt(long)]
+        end_epoch: Option<u64>,
+    },
+    /// Prints the state root hash and the transaction accumulator root hash at a version as JSON.
+    #[structopt(name = "state-root")]
+    StateRoot { version: Version },
+    /// Prints the estimated size and number of keys of each column family as JSON.
+    #[structopt(name = "cf-stats")]
+    CfStats,
+    /// Recomputes the transaction accumulator root hash after each version in
+    /// [start_version, end_version) from the transactions, events and transaction infos stored,
+    /// and compares them with the accumulator the DB serves proofs from. Prints the mismatches
+    /// found as JSON and exits with a failure if there are any.
+    #[structopt(name = "check-accumulator")]
+    CheckAccumulator {
+        #[structopt(long, default_value = "0")]
+        start_version: Version,
+        /// The latest version plus one by default.
+        #[structopt(long)]
+        end_version: Option<Version>,
+    },
+}
+
+#[derive(Serialize)]
+struct EventView {
+    key: EventKey,
+    sequence_number: u64,
+    type_tag: String,
+    data: String,
+}
+
+impl From<&ContractEvent> for EventView {
+    fn from(event: &ContractEvent) -> Self {
+        Self {
+            key: *event.key(),
+            sequence_number: event.sequence_number(),
+            type_tag: event.type_tag().to_string(),
+            data: hex::encode(event.event_data()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VersionedEventView {
+    version: Version,
+    #[serde(flatten)]
+    event: EventView,
+}
+
+#[derive(Serialize)]
+struct TransactionView {
+    version: Version,
+    hash: HashValue,
+    transaction: Transaction,
+    info: TransactionInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    events: Option<Vec<EventView>>,
+}
+
+#[derive(Serialize)]
+struct ResourceView {
+    type_tag: String,
+    data: String,
+}
+
+#[derive(Serialize)]
+struct AccountView {
+    address: AccountAddress,
+    version: Version,
+    state_hash: Option<HashValue>,
+    account_resource: Option<AccountResource>,
+    resources: Vec<ResourceView>,
+    num_modules: usize,
+}
+
+#[derive(Serialize)]
+struct LedgerInfoView {
+    ledger_info: LedgerInfo,
+    signers: Vec<AccountAddress>,
+}
+
+impl From<&LedgerInfoWithSignatures> for LedgerInfoView {
+    fn from(li: &LedgerInfoWithSignatures) -> Self {
+        Self {
+            ledger_info: li.ledger_info().clone(),
+            signers: li.signatures().keys().cloned().collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StateRootView {
+    version: Version,
+    state_root_hash: HashValue,
+    transaction_accumulator_root_hash: HashValue,
+}
+
+#[derive(Serialize)]
+struct ColumnFamilyView {
+    name: &'static str,
+    estimated_num_keys: u64,
+    estimated_live_data_size_bytes: u64,
+    total_sst_files_size_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct AccumulatorMismatch {
+    version: Version,
+    /// What is inconsistent: "transaction_hash", "event_root_hash" or "accumulator_root_hash".
+    field: &'static str,
+    expected: HashValue,
+    actual: HashValue,
+}
+
+#[derive(Serialize)]
+struct AccumulatorCheckView {
+    start_version: Version,
+    end_version: Version,
+    root_hash: HashValue,
+    mismatches: Vec<AccumulatorMismatch>,
+}
+
+fn print_json<T: Serialize>(data: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(data)?);
+    Ok(())
 }
 
 /// Print out latest information stored in the DB.
@@ -152,6 +333,196 @@ fn list_accounts(db: &DiemDB) {
     info!("Total Accounts: {}", num_account);
 }
 
+fn print_events(
+    db: &DiemDB,
+    key: &EventKey,
+    start: u64,
+    limit: u64,
+    descending: bool,
+) -> Result<()> {
+    let order = if descending {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+    let events = db
+        .get_events(key, start, order, limit)?
+        .iter()
+        .map(|(version, event)| VersionedEventView {
+            version: *version,
+            event: event.into(),
+        })
+        .collect::<Vec<_>>();
+    print_json(&events)
+}
+
+fn print_txn_by_hash(db: &DiemDB, hash: HashValue, with_events: bool) -> Result<()> {
+    let txn = db
+        .get_transaction_by_hash(hash, db.get_latest_version()?, with_events)?
+        .map(|txn| TransactionView {
+            version: txn.version,
+            hash,
+            info: txn.proof.transaction_info().clone(),
+            events: txn
+                .events
+                .as_ref()
+                .map(|events| events.iter().map(Into::into).collect()),
+            transaction: txn.transaction,
+        });
+    print_json(&txn)
+}
+
+fn print_account_at_version(
+    db: &DiemDB,
+    address: AccountAddress,
+    version: Option<Version>,
+) -> Result<()> {
+    let version = match version {
+        Some(version) => version,
+        None => db.get_latest_version()?,
+    };
+    let (maybe_blob, _proof) = db.get_account_state_with_proof_by_version(address, version)?;
+    let account = match maybe_blob {
+        Some(blob) => {
+            let state = AccountState::try_from(&blob)?;
+            Some(AccountView {
+                address,
+                version,
+                state_hash: Some(blob.hash()),
+                account_resource: state.get_account_resource()?,
+                resources: state
+                    .get_resources()
+                    .map(|(type_tag, data)| ResourceView {
+                        type_tag: type_tag.to_string(),
+                        data: hex::encode(data),
+                    })
+                    .collect(),
+                num_modules: state.get_modules().count(),
+            })
+        }
+        None => None,
+    };
+    print_json(&account)
+}
+
+fn print_epoch_history(db: &DiemDB, start_epoch: u64, end_epoch: Option<u64>) -> Result<()> {
+    let end_epoch = match end_epoch {
+        Some(end_epoch) => end_epoch,
+        None => db
+            .get_latest_ledger_info()?
+            .ledger_info()
+            .next_block_epoch(),
+    };
+    let ledger_infos = db
+        .get_backup_handler()
+        .get_epoch_ending_ledger_info_iter(start_epoch, end_epoch)?
+        .map(|li_res| li_res.map(|li| LedgerInfoView::from(&li)))
+        .collect::<Result<Vec<_>>>()?;
+    print_json(&ledger_infos)
+}
+
+fn print_state_root(db: &DiemDB, version: Version) -> Result<()> {
+    let (txn_info_with_proof, _ledger_info) =
+        db.get_backup_handler().get_state_root_proof(version)?;
+    print_json(&StateRootView {
+        version,
+        state_root_hash: txn_info_with_proof.transaction_info().state_root_hash(),
+        transaction_accumulator_root_hash: db.get_accumulator_root_hash(version)?,
+    })
+}
+
+fn print_cf_stats(db: &DiemDB) -> Result<()> {
+    let stats = DiemDB::column_families()
+        .into_iter()
+        .map(|name| {
+            Ok(ColumnFamilyView {
+                name,
+                estimated_num_keys: db.get_rocksdb_property(name, "rocksdb.estimate-num-keys")?,
+                estimated_live_data_size_bytes: db
+                    .get_rocksdb_property(name, "rocksdb.estimate-live-data-size")?,
+                total_sst_files_size_bytes: db
+                    .get_rocksdb_property(name, "rocksdb.total-sst-files-size")?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    print_json(&stats)
+}
+
+/// Returns whether the accumulator is consistent over the range.
+fn check_accumulator(
+    db: &DiemDB,
+    start_version: Version,
+    end_version: Option<Version>,
+) -> Result<bool> {
+    let end_version = match end_version {
+        Some(end_version) => end_version,
+        None => db.get_latest_version()? + 1,
+    };
+    ensure!(
+        start_version < end_version,
+        "Bad version range [{}, {})",
+        start_version,
+        end_version,
+    );
+
+    let mut accumulator = if start_version == 0 {
+        InMemoryAccumulator::<TransactionAccumulatorHasher>::default()
+    } else {
+        InMemoryAccumulator::new(
+            db.get_accumulator_consistency_proof(None, start_version - 1)?
+                .into_subtrees(),
+            start_version,
+        )?
+    };
+
+    let mut mismatches = Vec::new();
+    let mut check = |version, field, expected, actual| {
+        if expected != actual {
+            mismatches.push(AccumulatorMismatch {
+                version,
+                field,
+                expected,
+                actual,
+            });
+        }
+    };
+    let backup = db.get_backup_handler();
+    let txn_iter =
+        backup.get_transaction_iter(start_version, (end_version - start_version) as usize)?;
+    for (version, res) in (start_version..).zip(txn_iter) {
+        let (txn, txn_info, events) = res?;
+        check(
+            version,
+            "transaction_hash",
+            txn_info.transaction_hash(),
+            txn.hash(),
+        );
+        let event_hashes = events.iter().map(CryptoHash::hash).collect::<Vec<_>>();
+        check(
+            version,
+            "event_root_hash",
+            txn_info.event_root_hash(),
+            InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes).root_hash(),
+        );
+        accumulator = accumulator.append(&[txn_info.hash()]);
+        check(
+            version,
+            "accumulator_root_hash",
+            db.get_accumulator_root_hash(version)?,
+            accumulator.root_hash(),
+        );
+    }
+
+    let consistent = mismatches.is_empty();
+    print_json(&AccumulatorCheckView {
+        start_version,
+        end_version,
+        root_hash: accumulator.root_hash(),
+        mismatches,
+    })?;
+    Ok(consistent)
+}
+
 fn main() {
     ::diem_logger::DiemLogger::builder().build();
 
@@ -167,11 +538,15 @@ fn main() {
     let log_dir = tempfile::tempdir().expect("Unable to get temp dir");
     info!("Opening DB at: {:?}, log at {:?}", p, log_dir.path());
 
+    // RocksDB checkpoints can't be made on a DB opened readonly.
+    let readonly = !matches!(opt.cmd, Some(Command::DbCheckpoint { .. }));
     let db = DiemDB::open(
         p,
-        true, /* readonly */
+        readonly,
         None, /* pruner */
+        None, /* ledger_pruner */
         RocksdbConfig::default(),
+        SecondaryIndexesConfig::default(),
     )
     .expect("Unable to open DiemDB");
     info!("DB opened successfully.");
@@ -190,6 +565,54 @@ fn main() {
             Command::ListAccounts => {
                 list_accounts(&db);
             }
+            Command::DbCheckpoint { output_dir } => {
+                db.create_checkpoint(&output_dir)
+                    .expect("Unable to make DB checkpoint");
+                info!("Checkpoint made at {:?}.", output_dir);
+            }
+            Command::Events {
+                key,
+                start,
+                limit,
+                descending,
+            } => {
+                print_events(&db, &key, start, limit, descending).expect("Unable to read events");
+            }
+            Command::TxnByHash { hash, with_events } => {
+                print_txn_by_hash(&db, hash, with_events).expect("Unable to read transaction");
+            }
+            Command::Account { address, version } => {
+                print_account_at_version(&db, address, version).expect("Unable to read account");
+            }
+            Command::LedgerInfo => {
+                let li = db
+                    .get_latest_ledger_info()
+                    .expect("Unable to read latest ledger info");
+                print_json(&LedgerInfoView::from(&li)).expect("Unable to print ledger info");
+            }
+            Command::EpochHistory {
+                start_epoch,
+                end_epoch,
+            } => {
+                print_epoch_history(&db, start_epoch, end_epoch)
+                    .expect("Unable to read epoch ending ledger infos");
+            }
+            Command::StateRoot { version } => {
+                print_state_root(&db, version).expect("Unable to read state root");
+            }
+            Command::CfStats => {
+                print_cf_stats(&db).expect("Unable to read column family properties");
+            }
+            Command::CheckAccumulator {
+                start_version,
+                end_version,
+            } => {
+                if !check_accumulator(&db, start_version, end_version)
+                    .expect("Unable to check accumulator")
+                {
+                    std::process::exit(1);
+                }
+            }
         }
     } else {
         print_head(&db).expect("Unable to read information from DB");
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    iterator::JellyfishMerkleIterator,
+    iterator::{JellyfishMerkleChangesIterator, JellyfishMerkleIterator},
     mock_tree_store::MockTreeStore,
     test_helper::{plus_one, ValueBlob},
     JellyfishMerkleTree,
@@ -10,7 +10,7 @@ use crate::{
 use anyhow::Result;
 use diem_crypto::HashValue;
 use diem_types::transaction::Version;
-use rand::{rngs::StdRng, SeedableRng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{collections::BTreeMap, sync::Arc};
 
 #[test]
@@ -25,6 +25,49 @@ fn test_iterator_multiple_versions() {
     test_n_leaves_multiple_versions(50);
 }
 
+#[test]
+fn test_changes_iterator() {
+    let db = Arc::new(MockTreeStore::default());
+    let tree = JellyfishMerkleTree::new(&*db);
+    let mut rng = StdRng::from_seed([1; 32]);
+
+    let base_kvs = (0..100u64)
+        .map(|i| {
+            (
+                HashValue::random_with_rng(&mut rng),
+                ValueBlob::from(i.to_be_bytes().to_vec()),
+            )
+        })
+        .collect::<Vec<_>>();
+    let (_root_hash, batch) = tree.put_value_set(base_kvs.clone(), 0).unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+
+    let mut changes = BTreeMap::new();
+    for version in 1..10 {
+        let kvs = (0..5)
+            .map(|_| {
+                let key = if rng.gen_bool(0.5) {
+                    base_kvs[rng.gen_range(0..base_kvs.len())].0
+                } else {
+                    HashValue::random_with_rng(&mut rng)
+                };
+                (key, ValueBlob::from(version.to_be_bytes().to_vec()))
+            })
+            .collect::<Vec<_>>();
+        changes.extend(kvs.clone());
+        let (_root_hash, batch) = tree.put_value_set(kvs, version).unwrap();
+        db.write_tree_update_batch(batch).unwrap();
+
+        let iter = JellyfishMerkleChangesIterator::new(Arc::clone(&db), 0, version).unwrap();
+        assert_eq!(
+            iter.collect::<Result<Vec<_>>>().unwrap(),
+            changes.clone().into_iter().collect::<Vec<_>>(),
+        );
+    }
+
+    assert!(JellyfishMerkleChangesIterator::new(Arc::clone(&db), 5, 5).is_err());
+}
+
 fn test_n_leaves_same_version(n: usize) {
     let db = Arc::new(MockTreeStore::default());
     let tree = JellyfishMerkleTree::new(&*db);
@@ -4,7 +4,8 @@
 //! This module implements `JellyfishMerkleIterator`. Initialized with a version and a key, the
 //! iterator generates all the key-value pairs in this version of the tree, starting from the
 //! smallest key that is greater or equal to the given key, by performing a depth first traversal
-//! on the tree.
+//! on the tree. It also implements `JellyfishMerkleChangesIterator`, which generates only the
+//! key-value pairs written after a base version.
 
 #[cfg(test)]
 mod iterator_test;
@@ -13,7 +14,7 @@ use crate::{
     node_type::{InternalNode, Node, NodeKey},
     TreeReader,
 };
-use anyhow::{format_err, Result};
+use anyhow::{ensure, format_err, Result};
 use diem_crypto::HashValue;
 use diem_types::{
     nibble::{nibble_path::NibblePath, Nibble},
@@ -265,3 +266,74 @@ where
         }
     }
 }
+
+/// The `JellyfishMerkleChangesIterator` yields, in key order, the key-value pairs in the tree at
+/// `version` that were written after `base_version`. Subtrees not updated after `base_version`
+/// are skipped as a whole, judging by the versions of the children of each internal node. A
+/// value rewritten to what it was at `base_version` is still yielded.
+pub struct JellyfishMerkleChangesIterator<R, V> {
+    /// The storage engine from which we can read nodes using node keys.
+    reader: Arc<R>,
+
+    /// Changes no newer than this version are skipped.
+    base_version: Version,
+
+    /// The nodes to visit in a depth first traversal, the next one being on the top.
+    stack: Vec<NodeKey>,
+
+    phantom_value: PhantomData<V>,
+}
+
+impl<R, V> JellyfishMerkleChangesIterator<R, V>
+where
+    R: TreeReader<V>,
+    V: crate::Value,
+{
+    pub fn new(reader: Arc<R>, base_version: Version, version: Version) -> Result<Self> {
+        ensure!(
+            base_version < version,
+            "Base version {} must be older than version {}.",
+            base_version,
+            version,
+        );
+        Ok(Self {
+            reader,
+            base_version,
+            stack: vec![NodeKey::new_empty_path(version)],
+            phantom_value: PhantomData,
+        })
+    }
+}
+
+impl<R, V> Iterator for JellyfishMerkleChangesIterator<R, V>
+where
+    R: TreeReader<V>,
+    V: crate::Value,
+{
+    type Item = Result<(HashValue, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node_key) = self.stack.pop() {
+            match self.reader.get_node(&node_key) {
+                Ok(Node::Internal(internal_node)) => {
+                    // Pushed from right to left so the leftmost child is visited first.
+                    for i in (0..16u8).rev() {
+                        let nibble = Nibble::from(i);
+                        if let Some(child) = internal_node.child(nibble) {
+                            if child.version > self.base_version {
+                                self.stack
+                                    .push(node_key.gen_child_node_key(child.version, nibble));
+                            }
+                        }
+                    }
+                }
+                Ok(Node::Leaf(leaf_node)) => {
+                    return Some(Ok((leaf_node.account_key(), leaf_node.value().clone())));
+                }
+                Ok(Node::Null) => {}
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        None
+    }
+}
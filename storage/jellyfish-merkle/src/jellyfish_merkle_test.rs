@@ -4,13 +4,16 @@
 use super::*;
 use crate::test_helper::{
     arb_existent_kvs_and_nonexistent_keys, arb_kv_pair_with_distinct_last_nibble,
-    arb_tree_with_index, test_get_range_proof, test_get_with_proof,
+    arb_tree_with_index, test_get_range_proof, test_get_with_multiproof, test_get_with_proof,
     test_get_with_proof_with_distinct_last_nibble, ValueBlob,
 };
 use diem_crypto::HashValue;
 use diem_types::{nibble::Nibble, transaction::PRE_GENESIS_VERSION};
 use mock_tree_store::MockTreeStore;
-use proptest::prelude::*;
+use proptest::{
+    collection::{hash_map, vec},
+    prelude::*,
+};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::collections::HashMap;
 
@@ -339,9 +342,14 @@ fn test_batch_insertion() {
         let db = MockTreeStore::default();
         let tree = JellyfishMerkleTree::new(&db);
 
-        let (_roots, batch) = tree
-            .batch_put_value_sets(batches, None, 0 /* first_version */)
+        let (roots, batch) = tree
+            .batch_put_value_sets(batches.clone(), None, 0 /* first_version */)
             .unwrap();
+        assert_eq!(
+            tree.batch_put_value_sets_parallel(batches, None, 0 /* first_version */)
+                .unwrap(),
+            (roots, batch.clone())
+        );
         db.write_tree_update_batch(batch).unwrap();
         verify_fn(&tree, 6);
 
@@ -563,13 +571,75 @@ fn test_put_value_sets() {
             value_sets.push(keyed_value_set);
         }
         let (root_hashes, batch) = tree
-            .batch_put_value_sets(value_sets, None, 0 /* version */)
+            .batch_put_value_sets(value_sets.clone(), None, 0 /* version */)
+            .unwrap();
+        assert_eq!(root_hashes, root_hashes_one_by_one);
+        assert_eq!(batch, batch_one_by_one);
+
+        let (root_hashes, batch) = tree
+            .batch_put_value_sets_parallel(value_sets, None, 0 /* version */)
             .unwrap();
         assert_eq!(root_hashes, root_hashes_one_by_one);
         assert_eq!(batch, batch_one_by_one);
     }
 }
 
+#[test]
+fn test_put_value_set_on_base_version() {
+    let base_kvs = (0..50)
+        .map(|_| {
+            (
+                HashValue::random(),
+                ValueBlob::from(HashValue::random().to_vec()),
+            )
+        })
+        .collect::<Vec<_>>();
+    let db = MockTreeStore::default();
+    let tree = JellyfishMerkleTree::new(&db);
+    let (_root, batch) = tree.put_value_set(base_kvs.clone(), 0).unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+
+    // Only the base version and the latest version of the tree are in `restored_db`.
+    let restored_db = MockTreeStore::default();
+    let restored_tree = JellyfishMerkleTree::new(&restored_db);
+    let (_root, batch) = restored_tree.put_value_set(base_kvs.clone(), 0).unwrap();
+    restored_db.write_tree_update_batch(batch).unwrap();
+
+    let mut changes = HashMap::new();
+    for version in 1..10 {
+        let kvs = vec![
+            (
+                base_kvs[version as usize].0,
+                ValueBlob::from(vec![version as u8]),
+            ),
+            (HashValue::random(), ValueBlob::from(vec![version as u8])),
+        ];
+        changes.extend(kvs.clone());
+        let (root, batch) = tree.put_value_set(kvs, version).unwrap();
+        db.write_tree_update_batch(batch).unwrap();
+
+        if version % 3 == 0 {
+            let (restored_root, batch) = restored_tree
+                .put_value_set_on_base_version(
+                    changes.drain().collect(),
+                    version - 3, /* base_version */
+                    version,
+                )
+                .unwrap();
+            restored_db.write_tree_update_batch(batch).unwrap();
+            assert_eq!(restored_root, root);
+            assert_eq!(restored_tree.get_root_hash(version).unwrap(), root);
+        }
+    }
+
+    assert!(restored_tree
+        .put_value_set_on_base_version(vec![], 9, 10)
+        .is_err());
+    assert!(restored_tree
+        .put_value_set_on_base_version(base_kvs, 9, 9)
+        .is_err());
+}
+
 fn many_keys_get_proof_and_verify_tree_root(seed: &[u8], num_keys: usize) {
     assert!(seed.len() < 32);
     let mut actual_seed = [0u8; 32];
@@ -670,6 +740,11 @@ proptest! {
         test_get_with_proof((existent_kvs, nonexistent_keys))
     }
 
+    #[test]
+    fn proptest_get_with_multiproof((existent_kvs, nonexistent_keys) in arb_existent_kvs_and_nonexistent_keys::<ValueBlob>(1000, 100)) {
+        test_get_with_multiproof((existent_kvs, nonexistent_keys))
+    }
+
     #[test]
     fn proptest_get_with_proof_with_distinct_last_nibble((kv1, kv2) in arb_kv_pair_with_distinct_last_nibble::<ValueBlob>()) {
         test_get_with_proof_with_distinct_last_nibble((kv1, kv2))
@@ -679,4 +754,42 @@ proptest! {
     fn proptest_get_range_proof((btree, n) in arb_tree_with_index::<ValueBlob>(1000)) {
         test_get_range_proof((btree, n))
     }
+
+    #[test]
+    fn proptest_batch_put_value_sets_parallel(
+        value_sets in vec(hash_map(any::<HashValue>(), any::<ValueBlob>(), 1..200), 1..10)
+    ) {
+        let value_sets = value_sets
+            .into_iter()
+            .map(|value_set| value_set.into_iter().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let db = MockTreeStore::default();
+        let tree = JellyfishMerkleTree::new(&db);
+
+        // Apply the first value set to the empty tree, then the rest on top of it.
+        let (base_value_set, value_sets) = value_sets.split_first().unwrap();
+        let base = tree
+            .batch_put_value_sets(vec![base_value_set.clone()], None, 0 /* first_version */)
+            .unwrap();
+        prop_assert_eq!(
+            &tree
+                .batch_put_value_sets_parallel(
+                    vec![base_value_set.clone()],
+                    None,
+                    0, /* first_version */
+                )
+                .unwrap(),
+            &base
+        );
+        db.write_tree_update_batch(base.1).unwrap();
+
+        if !value_sets.is_empty() {
+            prop_assert_eq!(
+                tree.batch_put_value_sets_parallel(value_sets.to_vec(), None, 1 /* first_version */)
+                    .unwrap(),
+                tree.batch_put_value_sets(value_sets.to_vec(), None, 1 /* first_version */)
+                    .unwrap()
+            );
+        }
+    }
 }
@@ -87,7 +87,7 @@ use diem_types::{
         nibble_path::{skip_common_prefix, NibbleIterator, NibblePath},
         Nibble, ROOT_NIBBLE_HEIGHT,
     },
-    proof::{SparseMerkleProof, SparseMerkleRangeProof},
+    proof::{SparseMerkleMultiProof, SparseMerkleProof, SparseMerkleRangeProof},
     transaction::Version,
 };
 use node_type::{Child, Children, InternalNode, LeafNode, Node, NodeKey};
@@ -95,6 +95,7 @@ use node_type::{Child, Children, InternalNode, LeafNode, Node, NodeKey};
 use proptest::arbitrary::Arbitrary;
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
+use rayon::prelude::*;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
@@ -138,7 +139,10 @@ pub trait Value: Clone + CryptoHash + Serialize + DeserializeOwned {}
 /// `TestValue` defines the types of data that can be stored in a Jellyfish Merkle tree and used in
 /// tests.
 #[cfg(any(test, feature = "fuzzing"))]
-pub trait TestValue: Value + Arbitrary + std::fmt::Debug + Eq + PartialEq + 'static {}
+pub trait TestValue:
+    Value + Arbitrary + std::fmt::Debug + Eq + PartialEq + Send + Sync + 'static
+{
+}
 
 // This crate still depends on types for a few things, therefore we implement `Value` and
 // `TestValue` for `AccountStateBlob` here. Ideally the module that defines the specific value like
@@ -269,6 +273,33 @@ where
         node_hashes: Option<Vec<&HashMap<NibblePath, HashValue>>>,
         first_version: Version,
     ) -> Result<(Vec<HashValue>, TreeUpdateBatch<V>)> {
+        self.batch_put_value_sets_impl(
+            value_sets,
+            node_hashes,
+            first_version,
+            |root_node_key, version, kvs, hash_cache, tree_cache| {
+                Self::batch_insert_at(root_node_key, version, kvs, 0, hash_cache, tree_cache)
+                    .map(|(node_key, _)| node_key)
+            },
+        )
+    }
+
+    fn batch_put_value_sets_impl<F>(
+        &self,
+        value_sets: Vec<Vec<(HashValue, V)>>,
+        node_hashes: Option<Vec<&HashMap<NibblePath, HashValue>>>,
+        first_version: Version,
+        mut insert_at_root: F,
+    ) -> Result<(Vec<HashValue>, TreeUpdateBatch<V>)>
+    where
+        F: FnMut(
+            NodeKey,
+            Version,
+            &[(HashValue, V)],
+            &Option<&HashMap<NibblePath, HashValue>>,
+            &mut TreeCache<R, V>,
+        ) -> Result<NodeKey>,
+    {
         let mut tree_cache = TreeCache::new(self.reader, first_version)?;
         let hash_sets: Vec<_> = match node_hashes {
             Some(hashes) => hashes.into_iter().map(Some).collect(),
@@ -289,11 +320,10 @@ where
                 .into_iter()
                 .collect::<Vec<_>>();
             let root_node_key = tree_cache.get_root_node_key().clone();
-            let (new_root_node_key, _) = Self::batch_insert_at(
+            let new_root_node_key = insert_at_root(
                 root_node_key,
                 version,
                 deduped_and_sorted_kvs.as_slice(),
-                0,
                 &hash_set,
                 &mut tree_cache,
             )?;
@@ -306,6 +336,39 @@ where
         Ok(tree_cache.into())
     }
 
+    /// Like [`batch_put_value_sets`](struct.JellyfishMerkleTree.html#method.batch_put_value_sets)
+    /// with a single `value_set`, but applies it to the tree at `base_version` rather than that at
+    /// `version - 1`. `value_set` is expected to carry all values changed in between, for example,
+    /// an incremental state snapshot.
+    pub fn put_value_set_on_base_version(
+        &self,
+        value_set: Vec<(HashValue, V)>,
+        base_version: Version,
+        version: Version,
+    ) -> Result<(HashValue, TreeUpdateBatch<V>)> {
+        ensure!(!value_set.is_empty(), "Empty value set.");
+        let mut tree_cache = TreeCache::new_with_base_version(self.reader, base_version, version)?;
+        let deduped_and_sorted_kvs = value_set
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let root_node_key = tree_cache.get_root_node_key().clone();
+        let (new_root_node_key, _) = Self::batch_insert_at(
+            root_node_key,
+            version,
+            deduped_and_sorted_kvs.as_slice(),
+            0,
+            &None,
+            &mut tree_cache,
+        )?;
+        tree_cache.set_root_node_key(new_root_node_key);
+        tree_cache.freeze();
+
+        let (root_hashes, tree_update_batch): (Vec<_>, _) = tree_cache.into();
+        Ok((root_hashes[0], tree_update_batch))
+    }
+
     fn batch_insert_at(
         mut node_key: NodeKey,
         version: Version,
@@ -921,6 +984,22 @@ where
         bail!("Jellyfish Merkle tree has cyclic graph inside.");
     }
 
+    /// Returns the values (if applicable) of `keys`, which are in strictly ascending order, and
+    /// one merkle proof for all of them.
+    pub fn get_with_multiproof(
+        &self,
+        keys: &[HashValue],
+        version: Version,
+    ) -> Result<(Vec<Option<V>>, SparseMerkleMultiProof<V>)> {
+        let (values, proofs): (Vec<_>, Vec<_>) = keys
+            .iter()
+            .map(|key| self.get_with_proof(*key, version))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        Ok((values, SparseMerkleMultiProof::from_proofs(keys, &proofs)?))
+    }
+
     /// Gets the proof that shows a list of keys up to `rightmost_key_to_prove` exist at `version`.
     pub fn get_range_proof(
         &self,
@@ -967,6 +1046,102 @@ where
     }
 }
 
+impl<'a, R, V> JellyfishMerkleTree<'a, R, V>
+where
+    R: 'a + TreeReader<V> + Sync,
+    V: Value + Send + Sync,
+{
+    /// Same as [`batch_put_value_sets`](struct.JellyfishMerkleTree.html#method.batch_put_value_sets),
+    /// but for each version, splits the updates by the first nibble of the keys and updates the
+    /// (up to 16) subtrees under the root concurrently before merging them at the root. The
+    /// result is identical to that of the serial version.
+    pub fn batch_put_value_sets_parallel(
+        &self,
+        value_sets: Vec<Vec<(HashValue, V)>>,
+        node_hashes: Option<Vec<&HashMap<NibblePath, HashValue>>>,
+        first_version: Version,
+    ) -> Result<(Vec<HashValue>, TreeUpdateBatch<V>)> {
+        self.batch_put_value_sets_impl(
+            value_sets,
+            node_hashes,
+            first_version,
+            Self::batch_insert_at_root_parallel,
+        )
+    }
+
+    /// The root level of `batch_insert_at`, which processes the children of the root node in
+    /// parallel if it's an internal node, each in a `TreeCache` of its own reading the frozen
+    /// nodes in `tree_cache`.
+    fn batch_insert_at_root_parallel(
+        mut node_key: NodeKey,
+        version: Version,
+        kvs: &[(HashValue, V)],
+        hash_cache: &Option<&HashMap<NibblePath, HashValue>>,
+        tree_cache: &mut TreeCache<R, V>,
+    ) -> Result<NodeKey> {
+        assert!(!kvs.is_empty());
+
+        let internal_node = match tree_cache.get_node(&node_key)? {
+            Node::Internal(internal_node) if !tree_cache.has_pending_updates() => internal_node,
+            // Nothing to parallelize when the tree is too small to have an internal root.
+            _ => {
+                return Self::batch_insert_at(node_key, version, kvs, 0, hash_cache, tree_cache)
+                    .map(|(node_key, _)| node_key)
+            }
+        };
+
+        let ranges = NibbleRangeIterator::new(kvs, 0).collect::<Vec<_>>();
+        let subtree_results = {
+            let frozen_reader = tree_cache.frozen_reader();
+            ranges
+                .into_par_iter()
+                .map(|(left, right)| {
+                    let child_index = kvs[left].0.get_nibble(0);
+                    let mut subtree_cache = TreeCache::new_for_subtree(&frozen_reader, version);
+                    let (new_child_node_key, new_child_node) =
+                        match internal_node.child(child_index) {
+                            Some(child) => JellyfishMerkleTree::<_, V>::batch_insert_at(
+                                node_key.gen_child_node_key(child.version, child_index),
+                                version,
+                                &kvs[left..=right],
+                                1,
+                                hash_cache,
+                                &mut subtree_cache,
+                            )?,
+                            None => JellyfishMerkleTree::<_, V>::batch_create_subtree(
+                                node_key.gen_child_node_key(version, child_index),
+                                version,
+                                &kvs[left..=right],
+                                1,
+                                hash_cache,
+                                &mut subtree_cache,
+                            )?,
+                        };
+                    let new_child = Child::new(
+                        Self::get_hash(&new_child_node_key, &new_child_node, hash_cache),
+                        version,
+                        new_child_node.is_leaf(),
+                    );
+                    Ok((child_index, new_child, subtree_cache.into_subtree_updates()))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        // Same as `batch_insert_at` from here on, with the updates to the subtrees merged.
+        tree_cache.delete_node(&node_key, false /* is_leaf */);
+        let mut children: Children = internal_node.into();
+        for (child_index, new_child, subtree_updates) in subtree_results {
+            children.insert(child_index, new_child);
+            tree_cache.merge_subtree_updates(subtree_updates)?;
+        }
+        let new_internal_node = InternalNode::new(children);
+
+        node_key.set_version(version);
+        tree_cache.put_node(node_key.clone(), new_internal_node.into())?;
+        Ok(node_key)
+    }
+}
+
 trait NibbleExt {
     fn get_nibble(&self, index: usize) -> Nibble;
     fn common_prefix_nibbles_len(&self, other: HashValue) -> usize;
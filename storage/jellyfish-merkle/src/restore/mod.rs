@@ -27,7 +27,11 @@ use diem_types::{
     transaction::Version,
 };
 use mirai_annotations::*;
-use std::sync::Arc;
+use rayon::prelude::*;
+use std::{
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum ChildInfo<V> {
@@ -105,9 +109,15 @@ where
     }
 }
 
+/// A chunk of accounts whose leaves are hashed and whose keys are checked to be increasing, see
+/// `JellyfishMerkleRestore::prepare_chunk`.
+pub struct PreparedChunk<V> {
+    leaves: Vec<LeafNode<V>>,
+}
+
 pub struct JellyfishMerkleRestore<V> {
     /// The underlying storage.
-    store: Arc<dyn TreeWriter<V>>,
+    store: Arc<dyn TreeWriter<V> + Send + Sync>,
 
     /// The version of the tree we are restoring.
     version: Version,
@@ -147,6 +157,11 @@ pub struct JellyfishMerkleRestore<V> {
     /// The nodes that have been fully restored and are ready to be written to storage.
     frozen_nodes: NodeBatch<V>,
 
+    /// The thread writing the frozen nodes of the previous chunk, which is done in the background
+    /// while the next chunk is being added. It's not a task of the rayon pool, so that waiting
+    /// for it doesn't depend on a free worker of the pool the caller may be running on.
+    pending_write: Option<JoinHandle<Result<()>>>,
+
     /// The most recently added leaf. This is used to ensure the keys come in increasing order and
     /// do proof verification.
     previous_leaf: Option<LeafNode<V>>,
@@ -160,16 +175,27 @@ pub struct JellyfishMerkleRestore<V> {
 
 impl<V> JellyfishMerkleRestore<V>
 where
-    V: crate::Value,
+    V: crate::Value + Send + 'static,
 {
-    pub fn new<D: 'static + TreeReader<V> + TreeWriter<V>>(
+    pub fn new<D: 'static + TreeReader<V> + TreeWriter<V> + Send + Sync>(
         store: Arc<D>,
         version: Version,
         expected_root_hash: HashValue,
     ) -> Result<Self> {
         let tree_reader = Arc::clone(&store);
+        let root_node_key = NodeKey::new_empty_path(version);
         let (partial_nodes, previous_leaf) =
-            if let Some((node_key, leaf_node)) = tree_reader.get_rightmost_leaf()? {
+            if let Some(root_node) = tree_reader.get_node_option(&root_node_key)? {
+                // The root is written last, so the previous restoration attempt has completed and
+                // there is nothing left to do. `finish` becomes a no-op with no partial nodes.
+                let previous_leaf = match root_node {
+                    Node::Leaf(leaf_node) => Some(leaf_node),
+                    _ => tree_reader
+                        .get_rightmost_leaf()?
+                        .map(|(_node_key, leaf_node)| leaf_node),
+                };
+                (vec![], previous_leaf)
+            } else if let Some((node_key, leaf_node)) = tree_reader.get_rightmost_leaf()? {
                 // TODO: confirm rightmost leaf is at the desired version
                 // If the system crashed in the middle of the previous restoration attempt, we need
                 // to recover the partial nodes to the state right before the crash.
@@ -178,10 +204,7 @@ where
                     Some(leaf_node),
                 )
             } else {
-                (
-                    vec![InternalInfo::new_empty(NodeKey::new_empty_path(version))],
-                    None,
-                )
+                (vec![InternalInfo::new_empty(root_node_key)], None)
             };
 
         Ok(Self {
@@ -189,13 +212,14 @@ where
             version,
             partial_nodes,
             frozen_nodes: NodeBatch::new(),
+            pending_write: None,
             previous_leaf,
             num_keys_received: 0,
             expected_root_hash,
         })
     }
 
-    pub fn new_overwrite<D: 'static + TreeWriter<V>>(
+    pub fn new_overwrite<D: 'static + TreeWriter<V> + Send + Sync>(
         store: Arc<D>,
         version: Version,
         expected_root_hash: HashValue,
@@ -205,12 +229,19 @@ where
             version,
             partial_nodes: vec![InternalInfo::new_empty(NodeKey::new_empty_path(version))],
             frozen_nodes: NodeBatch::new(),
+            pending_write: None,
             previous_leaf: None,
             num_keys_received: 0,
             expected_root_hash,
         })
     }
 
+    /// Returns the key of the last account restored so far, including those recovered from
+    /// storage if this is resuming a previous restoration.
+    pub fn previous_key(&self) -> Option<HashValue> {
+        self.previous_leaf.as_ref().map(|leaf| leaf.account_key())
+    }
+
     /// Recovers partial nodes from storage. We do this by looking at all the ancestors of the
     /// rightmost leaf. The ones do not exist in storage are the partial nodes.
     fn recover_partial_nodes(
@@ -278,41 +309,91 @@ where
         Ok(partial_nodes)
     }
 
+    /// Hashes the values of `chunk` in parallel and checks that its keys are increasing. This
+    /// doesn't depend on the chunks added before, so chunks can be prepared concurrently, ahead
+    /// of being added in order by `add_prepared_chunk`.
+    pub fn prepare_chunk(chunk: Vec<(HashValue, V)>) -> Result<PreparedChunk<V>> {
+        ensure!(!chunk.is_empty(), "Should not add empty chunks.");
+        ensure!(
+            chunk.windows(2).all(|w| w[0].0 < w[1].0),
+            "Account keys must come in increasing order.",
+        );
+
+        let leaves = chunk
+            .into_par_iter()
+            .map(|(key, value)| LeafNode::new(key, value))
+            .collect();
+        Ok(PreparedChunk { leaves })
+    }
+
     /// Restores a chunk of accounts. This function will verify that the given chunk is correct
     /// using the proof and root hash, then write things to storage. If the chunk is invalid, an
     /// error will be returned and nothing will be written to storage.
+    ///
+    /// The values are hashed in parallel (see `prepare_chunk`), and the nodes are written in the
+    /// background while the next chunk is added. A failure to write them is returned by the next
+    /// call to `add_chunk` or `finish`.
     pub fn add_chunk(
         &mut self,
         chunk: Vec<(HashValue, V)>,
         proof: SparseMerkleRangeProof,
     ) -> Result<()> {
-        ensure!(!chunk.is_empty(), "Should not add empty chunks.");
+        self.add_prepared_chunk(Self::prepare_chunk(chunk)?, proof)
+    }
 
-        for (key, value) in chunk {
-            if let Some(ref prev_leaf) = self.previous_leaf {
-                ensure!(
-                    key > prev_leaf.account_key(),
-                    "Account keys must come in increasing order.",
-                )
-            }
-            self.add_one(key, value.clone());
-            self.previous_leaf.replace(LeafNode::new(key, value));
+    /// Like `add_chunk`, for a chunk prepared by `prepare_chunk`. The proof is verified against
+    /// all the accounts added so far, so chunks are added one at a time, in key order.
+    pub fn add_prepared_chunk(
+        &mut self,
+        chunk: PreparedChunk<V>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        ensure!(
+            !self.partial_nodes.is_empty(),
+            "Restoration has already completed.",
+        );
+        if let (Some(prev_leaf), Some(first_leaf)) = (&self.previous_leaf, chunk.leaves.first()) {
+            ensure!(
+                first_leaf.account_key() > prev_leaf.account_key(),
+                "Account keys must come in increasing order.",
+            );
+        }
+
+        for leaf in chunk.leaves {
+            self.add_one(leaf.clone());
+            self.previous_leaf.replace(leaf);
             self.num_keys_received += 1;
         }
 
         // Verify what we have added so far is all correct.
         self.verify(proof)?;
 
-        // Write the frozen nodes to storage.
-        self.store.write_node_batch(&self.frozen_nodes)?;
-        self.frozen_nodes.clear();
+        // Write the frozen nodes to storage, after the ones of the previous chunk so that the
+        // rightmost leaf in storage is always the last one of a fully written chunk.
+        self.wait_for_pending_write()?;
+        let store = Arc::clone(&self.store);
+        let frozen_nodes = std::mem::take(&mut self.frozen_nodes);
+        let pending_write = thread::Builder::new()
+            .name("jmt-restore-write".into())
+            .spawn(move || store.write_node_batch(&frozen_nodes))?;
+        self.pending_write = Some(pending_write);
 
         Ok(())
     }
 
+    /// Waits for the frozen nodes of the previous chunk to be written, if they aren't yet.
+    fn wait_for_pending_write(&mut self) -> Result<()> {
+        match self.pending_write.take() {
+            Some(pending_write) => pending_write
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+            None => Ok(()),
+        }
+    }
+
     /// Restores one account.
-    fn add_one(&mut self, new_key: HashValue, new_value: V) {
-        let nibble_path = NibblePath::new(new_key.to_vec());
+    fn add_one(&mut self, new_leaf: LeafNode<V>) {
+        let nibble_path = NibblePath::new(new_leaf.account_key().to_vec());
         let mut nibbles = nibble_path.nibbles();
 
         for i in 0..ROOT_NIBBLE_HEIGHT {
@@ -332,13 +413,7 @@ where
                         );
 
                         let existing_leaf = node.clone();
-                        self.insert_at_leaf(
-                            child_index,
-                            existing_leaf,
-                            new_key,
-                            new_value,
-                            nibbles,
-                        );
+                        self.insert_at_leaf(child_index, existing_leaf, new_leaf, nibbles);
                         break;
                     }
                 }
@@ -348,12 +423,8 @@ where
                     self.freeze(i + 1);
 
                     // Mark this position as a leaf child.
-                    self.partial_nodes[i].set_child(
-                        child_index,
-                        ChildInfo::Leaf {
-                            node: LeafNode::new(new_key, new_value),
-                        },
-                    );
+                    self.partial_nodes[i]
+                        .set_child(child_index, ChildInfo::Leaf { node: new_leaf });
 
                     // We do not add this leaf node to self.frozen_nodes because we don't know its
                     // node key yet. We will know its node key when the next account comes.
@@ -370,10 +441,10 @@ where
         &mut self,
         child_index: usize,
         existing_leaf: LeafNode<V>,
-        new_key: HashValue,
-        new_value: V,
+        new_leaf: LeafNode<V>,
         mut remaining_nibbles: NibbleIterator,
     ) {
+        let new_key = new_leaf.account_key();
         let num_existing_partial_nodes = self.partial_nodes.len();
 
         // The node at this position becomes an internal node. Since we may insert more nodes at
@@ -430,9 +501,7 @@ where
             .expect("This node must exist.")
             .set_child(
                 u8::from(new_child_index) as usize,
-                ChildInfo::Leaf {
-                    node: LeafNode::new(new_key, new_value),
-                },
+                ChildInfo::Leaf { node: new_leaf },
             );
     }
 
@@ -623,6 +692,8 @@ where
     /// Finishes the restoration process. This tells the code that there is no more account,
     /// otherwise we can not freeze the rightmost leaf and its ancestors.
     pub fn finish(mut self) -> Result<()> {
+        self.wait_for_pending_write()?;
+
         // Deal with the special case when the entire tree has a single leaf.
         if self.partial_nodes.len() == 1 {
             let mut num_children = 0;
@@ -651,3 +722,12 @@ where
         self.store.write_node_batch(&self.frozen_nodes)
     }
 }
+
+impl<V> Drop for JellyfishMerkleRestore<V> {
+    fn drop(&mut self) {
+        // Don't leave a write behind, a new restore may resume from what's in storage right away.
+        if let Some(pending_write) = self.pending_write.take() {
+            let _ = pending_write.join();
+        }
+    }
+}
@@ -10,6 +10,7 @@ use crate::{
 use diem_crypto::HashValue;
 use diem_types::transaction::Version;
 use proptest::{collection::btree_map, prelude::*};
+use rayon::prelude::*;
 use std::{collections::BTreeMap, sync::Arc};
 
 proptest! {
@@ -78,6 +79,85 @@ proptest! {
         assert_success(&restore_db, expected_root_hash, &all, version);
     }
 
+    #[test]
+    fn test_restore_after_completion(
+        btree in btree_map(any::<HashValue>(), any::<ValueBlob>(), 1..1000),
+        target_version in 0u64..2000,
+    ) {
+        let restore_db = Arc::new(MockTreeStore::default());
+        restore_without_interruption(&btree, target_version, &restore_db, true);
+
+        // Say the process crashed right after `finish`, before the caller recorded the
+        // completion. Resuming should be a no-op.
+        let expected_root_hash = JellyfishMerkleTree::new(restore_db.as_ref())
+            .get_root_hash(target_version)
+            .unwrap();
+        let restore =
+            JellyfishMerkleRestore::new(Arc::clone(&restore_db), target_version, expected_root_hash)
+                .unwrap();
+        prop_assert_eq!(restore.previous_key(), btree.keys().last().copied());
+        restore.finish().unwrap();
+
+        assert_success(&restore_db, expected_root_hash, &btree, target_version);
+    }
+
+    #[test]
+    fn test_restore_prepared_chunks(
+        btree in btree_map(any::<HashValue>(), any::<ValueBlob>(), 1..1000),
+        target_version in 0u64..2000,
+    ) {
+        let (db, source_version) = init_mock_db(&btree.clone().into_iter().collect());
+        let tree = JellyfishMerkleTree::new(&db);
+        let expected_root_hash = tree.get_root_hash(source_version).unwrap();
+        let restore_db = Arc::new(MockTreeStore::default());
+        let mut restore =
+            JellyfishMerkleRestore::new(Arc::clone(&restore_db), target_version, expected_root_hash)
+                .unwrap();
+
+        // Prepare the chunks concurrently, then add them in order.
+        let chunks: Vec<Vec<_>> = btree
+            .clone()
+            .into_iter()
+            .collect::<Vec<_>>()
+            .chunks(10)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        let prepared_chunks: Vec<_> = chunks
+            .into_par_iter()
+            .map(|chunk| {
+                let last_key = chunk.last().unwrap().0;
+                (JellyfishMerkleRestore::prepare_chunk(chunk).unwrap(), last_key)
+            })
+            .collect();
+        for (chunk, last_key) in prepared_chunks {
+            let proof = tree.get_range_proof(last_key, source_version).unwrap();
+            restore.add_prepared_chunk(chunk, proof).unwrap();
+        }
+        restore.finish().unwrap();
+
+        assert_success(&restore_db, expected_root_hash, &btree, target_version);
+    }
+
+    #[test]
+    fn test_restore_on_single_thread_rayon_pool(
+        btree in btree_map(any::<HashValue>(), any::<ValueBlob>(), 1..100),
+        target_version in 0u64..2000,
+    ) {
+        // Waiting for the background write must not need a free worker of the caller's pool.
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let restore_db = Arc::new(MockTreeStore::default());
+        pool.install(|| restore_without_interruption(&btree, target_version, &restore_db, true));
+    }
+
+    #[test]
+    fn test_prepare_chunk_with_unordered_keys(
+        btree in btree_map(any::<HashValue>(), any::<ValueBlob>(), 2..100),
+    ) {
+        let mut chunk: Vec<_> = btree.into_iter().collect();
+        chunk.reverse();
+        prop_assert!(JellyfishMerkleRestore::prepare_chunk(chunk).is_err());
+    }
+
     #[test]
     fn test_overwrite(
         btree1 in btree_map(any::<HashValue>(), any::<ValueBlob>(), 1..1000),
@@ -61,7 +61,8 @@ pub fn plus_one(key: HashValue) -> HashValue {
     HashValue::from_slice(&buf).unwrap()
 }
 
-/// Initializes a DB with a set of key-value pairs by inserting one key at each version.
+/// Initializes a DB with a set of key-value pairs by inserting one key at each version, checking
+/// that the parallel update path yields exactly the same updates along the way.
 pub fn init_mock_db<V>(kvs: &HashMap<HashValue, V>) -> (MockTreeStore<V>, Version)
 where
     V: crate::TestValue,
@@ -72,9 +73,14 @@ where
     let tree = JellyfishMerkleTree::new(&db);
 
     for (i, (key, value)) in kvs.iter().enumerate() {
-        let (_root_hash, write_batch) = tree
+        let (root_hash, write_batch) = tree
             .put_value_set(vec![(*key, value.clone())], i as Version)
             .unwrap();
+        let (parallel_root_hashes, parallel_write_batch) = tree
+            .batch_put_value_sets_parallel(vec![vec![(*key, value.clone())]], None, i as Version)
+            .unwrap();
+        assert_eq!(parallel_root_hashes, vec![root_hash]);
+        assert_eq!(parallel_write_batch, write_batch);
         db.write_tree_update_batch(write_batch).unwrap();
     }
 
@@ -110,6 +116,42 @@ pub fn test_get_with_proof<V: crate::TestValue>(
     test_nonexistent_keys_impl(&tree, version, &nonexistent_keys);
 }
 
+pub fn test_get_with_multiproof<V: crate::TestValue>(
+    (existent_kvs, nonexistent_keys): (HashMap<HashValue, V>, Vec<HashValue>),
+) {
+    let (db, version) = init_mock_db(&existent_kvs);
+    let tree = JellyfishMerkleTree::new(&db);
+    let root_hash = tree.get_root_hash(version).unwrap();
+
+    let expected: BTreeMap<_, _> = existent_kvs
+        .iter()
+        .map(|(key, value)| (*key, Some(value.clone())))
+        .chain(nonexistent_keys.iter().map(|key| (*key, None)))
+        .collect();
+    let keys = expected.keys().cloned().collect::<Vec<_>>();
+    let (values, proof) = tree.get_with_multiproof(&keys, version).unwrap();
+    assert_eq!(values, expected.values().cloned().collect::<Vec<_>>());
+    let elements = keys
+        .iter()
+        .cloned()
+        .zip(values.iter().map(Option::as_ref))
+        .collect::<Vec<_>>();
+    assert!(proof.verify(root_hash, &elements).is_ok());
+
+    // No sibling is sent twice or more.
+    let num_siblings_in_single_proofs: usize = keys
+        .iter()
+        .map(|key| {
+            tree.get_with_proof(*key, version)
+                .unwrap()
+                .1
+                .siblings()
+                .len()
+        })
+        .sum();
+    assert!(proof.siblings().len() <= num_siblings_in_single_proofs);
+}
+
 pub fn arb_kv_pair_with_distinct_last_nibble<V: crate::TestValue>(
 ) -> impl Strategy<Value = ((HashValue, V), (HashValue, V))> {
     (
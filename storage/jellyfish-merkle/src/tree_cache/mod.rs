@@ -71,10 +71,10 @@ mod tree_cache_test;
 
 use crate::{
     metrics::DIEM_JELLYFISH_STORAGE_READS,
-    node_type::{Node, NodeKey},
+    node_type::{LeafNode, Node, NodeKey},
     NodeBatch, NodeStats, StaleNodeIndex, StaleNodeIndexBatch, TreeReader, TreeUpdateBatch,
 };
-use anyhow::{bail, Result};
+use anyhow::{bail, ensure, Result};
 use diem_crypto::HashValue;
 use diem_types::transaction::{Version, PRE_GENESIS_VERSION};
 use std::collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet};
@@ -133,6 +133,10 @@ pub struct TreeCache<'a, R, V> {
 
     /// The underlying persistent storage.
     reader: &'a R,
+
+    /// Whether reads from `reader` are counted as storage reads, which is not the case when
+    /// `reader` is a `FrozenTreeCacheReader`, that counts the reads reaching the storage itself.
+    count_storage_reads: bool,
 }
 
 impl<'a, R, V> TreeCache<'a, R, V>
@@ -165,7 +169,46 @@ where
         } else {
             NodeKey::new_empty_path(next_version - 1)
         };
-        Ok(Self {
+        Ok(Self::new_impl(
+            reader,
+            root_node_key,
+            node_cache,
+            next_version,
+            true,
+        ))
+    }
+
+    /// Constructs a new `TreeCache` instance whose upcoming `put`s are based on the tree at
+    /// `base_version` instead of that at `next_version - 1`, which is not necessarily in the DB
+    /// (e.g. when the DB is restored from a state snapshot plus incremental ones).
+    pub fn new_with_base_version(
+        reader: &'a R,
+        base_version: Version,
+        next_version: Version,
+    ) -> Result<Self> {
+        ensure!(
+            base_version < next_version,
+            "Base version {} must be older than the next version {}.",
+            base_version,
+            next_version,
+        );
+        Ok(Self::new_impl(
+            reader,
+            NodeKey::new_empty_path(base_version),
+            HashMap::new(),
+            next_version,
+            true,
+        ))
+    }
+
+    fn new_impl(
+        reader: &'a R,
+        root_node_key: NodeKey,
+        node_cache: HashMap<NodeKey, Node<V>>,
+        next_version: Version,
+        count_storage_reads: bool,
+    ) -> Self {
+        Self {
             node_cache,
             stale_node_index_cache: HashSet::new(),
             frozen_cache: FrozenTreeCache::new(),
@@ -174,7 +217,21 @@ where
             reader,
             num_stale_leaves: 0,
             num_new_leaves: 0,
-        })
+            count_storage_reads,
+        }
+    }
+
+    /// Constructs an empty `TreeCache` for updating a subtree in parallel with its siblings,
+    /// reading the nodes it doesn't have from a `FrozenTreeCacheReader`, which counts the storage
+    /// reads. See `into_subtree_updates()`.
+    pub fn new_for_subtree(reader: &'a R, next_version: Version) -> Self {
+        Self::new_impl(
+            reader,
+            NodeKey::new_empty_path(next_version),
+            HashMap::new(),
+            next_version,
+            false,
+        )
     }
 
     /// Gets a node with given node key. If it doesn't exist in node cache, read from `reader`.
@@ -184,7 +241,9 @@ where
         } else if let Some(node) = self.frozen_cache.node_cache.get(node_key) {
             node.clone()
         } else {
-            DIEM_JELLYFISH_STORAGE_READS.inc();
+            if self.count_storage_reads {
+                DIEM_JELLYFISH_STORAGE_READS.inc();
+            }
             self.reader.get_node(node_key)?
         })
     }
@@ -228,6 +287,52 @@ where
         }
     }
 
+    /// Whether anything has been put or deleted since the last `freeze()`.
+    pub fn has_pending_updates(&self) -> bool {
+        !self.node_cache.is_empty() || !self.stale_node_index_cache.is_empty()
+    }
+
+    /// A reader of the frozen contents of this cache and the underlying storage, which is all
+    /// there is when `has_pending_updates()` is false.
+    pub fn frozen_reader(&self) -> FrozenTreeCacheReader<'_, R, V> {
+        FrozenTreeCacheReader {
+            node_cache: &self.frozen_cache.node_cache,
+            reader: self.reader,
+        }
+    }
+
+    /// Takes the updates made to a `TreeCache` created by `new_for_subtree()`.
+    pub fn into_subtree_updates(self) -> SubtreeUpdates<V> {
+        SubtreeUpdates {
+            node_cache: self.node_cache,
+            num_new_leaves: self.num_new_leaves,
+            stale_node_index_cache: self.stale_node_index_cache,
+            num_stale_leaves: self.num_stale_leaves,
+        }
+    }
+
+    /// Merges the updates made to a subtree in parallel into this cache, as if they were made to
+    /// this cache directly.
+    pub fn merge_subtree_updates(&mut self, updates: SubtreeUpdates<V>) -> Result<()> {
+        for (node_key, node) in updates.node_cache {
+            match self.node_cache.entry(node_key) {
+                Entry::Vacant(o) => {
+                    o.insert(node);
+                }
+                Entry::Occupied(o) => {
+                    bail!("Node with key {:?} already exists in NodeBatch", o.key())
+                }
+            }
+        }
+        self.num_new_leaves += updates.num_new_leaves;
+        for node_key in updates.stale_node_index_cache {
+            let is_new_entry = self.stale_node_index_cache.insert(node_key);
+            assert!(is_new_entry, "Node gets stale twice unexpectedly.");
+        }
+        self.num_stale_leaves += updates.num_stale_leaves;
+        Ok(())
+    }
+
     /// Freezes all the contents in cache to be immutable and clear `node_cache`.
     pub fn freeze(&mut self) {
         let root_node_key = self.get_root_node_key();
@@ -265,6 +370,41 @@ where
     }
 }
 
+/// Reads nodes from the frozen part of a `TreeCache` and then its underlying storage. See
+/// `TreeCache::frozen_reader()`.
+pub struct FrozenTreeCacheReader<'a, R, V> {
+    node_cache: &'a NodeBatch<V>,
+    reader: &'a R,
+}
+
+impl<'a, R, V> TreeReader<V> for FrozenTreeCacheReader<'a, R, V>
+where
+    R: 'a + TreeReader<V>,
+    V: crate::Value,
+{
+    fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node<V>>> {
+        match self.node_cache.get(node_key) {
+            Some(node) => Ok(Some(node.clone())),
+            None => {
+                DIEM_JELLYFISH_STORAGE_READS.inc();
+                self.reader.get_node_option(node_key)
+            }
+        }
+    }
+
+    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode<V>)>> {
+        bail!("Not supported by FrozenTreeCacheReader.")
+    }
+}
+
+/// Updates made to a `TreeCache` created by `TreeCache::new_for_subtree()`.
+pub struct SubtreeUpdates<V> {
+    node_cache: HashMap<NodeKey, Node<V>>,
+    num_new_leaves: usize,
+    stale_node_index_cache: HashSet<NodeKey>,
+    num_stale_leaves: usize,
+}
+
 impl<'a, R, V> From<TreeCache<'a, R, V>> for (Vec<HashValue>, TreeUpdateBatch<V>)
 where
     R: 'a + TreeReader<V>,
@@ -0,0 +1,207 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backend::{KeyValueDB, RawIterator},
+    ColumnFamilyName, ReadOptions, SchemaBatch, WriteOp,
+};
+use anyhow::{bail, format_err, Result};
+use diem_infallible::RwLock;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Bound::{Excluded, Included, Unbounded},
+    path::Path,
+    sync::Arc,
+};
+
+/// A column family is shared with the iterators created on it, and copied on write only if there
+/// are any, so that iterators see a consistent view of it like RocksDB iterators do.
+type ColumnFamily = Arc<BTreeMap<Vec<u8>, Vec<u8>>>;
+
+/// [`KeyValueDB`] keeping everything in ordered maps in memory, for tests and benchmarks that don't
+/// care about durability.
+#[derive(Debug)]
+pub(crate) struct InMemoryDB {
+    column_families: RwLock<HashMap<ColumnFamilyName, ColumnFamily>>,
+}
+
+impl InMemoryDB {
+    pub fn new(column_families: &[ColumnFamilyName]) -> Self {
+        Self {
+            column_families: RwLock::new(
+                column_families
+                    .iter()
+                    .map(|cf_name| (*cf_name, ColumnFamily::default()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn get_cf(&self, cf_name: &str) -> Result<ColumnFamily> {
+        self.column_families
+            .read()
+            .get(cf_name)
+            .cloned()
+            .ok_or_else(|| cf_not_found(cf_name))
+    }
+}
+
+impl KeyValueDB for InMemoryDB {
+    fn get(&self, cf_name: ColumnFamilyName, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.get_cf(cf_name)?.get(key).cloned())
+    }
+
+    fn write(&self, batch: &SchemaBatch) -> Result<usize> {
+        let mut column_families = self.column_families.write();
+        if let Some(cf_name) = batch
+            .rows
+            .keys()
+            .find(|cf_name| !column_families.contains_key(*cf_name))
+        {
+            return Err(cf_not_found(cf_name));
+        }
+
+        let mut size = 0;
+        for (cf_name, rows) in &batch.rows {
+            let cf = Arc::make_mut(
+                column_families
+                    .get_mut(cf_name)
+                    .expect("Column family checked above."),
+            );
+            for (key, write_op) in rows {
+                size += key.len();
+                match write_op {
+                    WriteOp::Value(value) => {
+                        size += value.len();
+                        cf.insert(key.clone(), value.clone());
+                    }
+                    WriteOp::Deletion => {
+                        cf.remove(key);
+                    }
+                }
+            }
+        }
+        Ok(size)
+    }
+
+    fn range_delete(&self, cf_name: ColumnFamilyName, begin: &[u8], end: &[u8]) -> Result<()> {
+        let mut column_families = self.column_families.write();
+        let cf = Arc::make_mut(
+            column_families
+                .get_mut(cf_name)
+                .ok_or_else(|| cf_not_found(cf_name))?,
+        );
+        let mut deleted = cf.split_off(begin);
+        let mut kept = deleted.split_off(end);
+        cf.append(&mut kept);
+        Ok(())
+    }
+
+    fn raw_iter(
+        &self,
+        cf_name: ColumnFamilyName,
+        _opts: ReadOptions,
+    ) -> Result<Box<dyn RawIterator + '_>> {
+        Ok(Box::new(InMemoryIterator {
+            cf: self.get_cf(cf_name)?,
+            position: None,
+        }))
+    }
+
+    fn flush_all(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// There are no memtables, SST files or compactions in memory, so all RocksDB properties are
+    /// reported as 0.
+    fn get_property(&self, cf_name: &str, _property_name: &str) -> Result<u64> {
+        self.get_cf(cf_name)?;
+        Ok(0)
+    }
+
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+
+    fn create_checkpoint(&self, _path: &Path) -> Result<()> {
+        bail!("Checkpoints are not supported by the in-memory DB.")
+    }
+}
+
+struct InMemoryIterator {
+    cf: ColumnFamily,
+    /// The key the iterator is positioned at.
+    position: Option<Vec<u8>>,
+}
+
+impl RawIterator for InMemoryIterator {
+    fn seek_to_first(&mut self) {
+        self.position = self.cf.keys().next().cloned();
+    }
+
+    fn seek_to_last(&mut self) {
+        self.position = self.cf.keys().next_back().cloned();
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        self.position = self
+            .cf
+            .range::<[u8], _>((Included(key), Unbounded))
+            .next()
+            .map(|(k, _)| k.clone());
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) {
+        self.position = self
+            .cf
+            .range::<[u8], _>((Unbounded, Included(key)))
+            .next_back()
+            .map(|(k, _)| k.clone());
+    }
+
+    fn valid(&self) -> bool {
+        self.position.is_some()
+    }
+
+    fn key(&self) -> Option<&[u8]> {
+        self.position.as_deref()
+    }
+
+    fn value(&self) -> Option<&[u8]> {
+        self.position
+            .as_ref()
+            .and_then(|key| self.cf.get(key))
+            .map(Vec::as_slice)
+    }
+
+    fn next(&mut self) {
+        if let Some(key) = self.position.take() {
+            self.position = self
+                .cf
+                .range::<[u8], _>((Excluded(key.as_slice()), Unbounded))
+                .next()
+                .map(|(k, _)| k.clone());
+        }
+    }
+
+    fn prev(&mut self) {
+        if let Some(key) = self.position.take() {
+            self.position = self
+                .cf
+                .range::<[u8], _>((Unbounded, Excluded(key.as_slice())))
+                .next_back()
+                .map(|(k, _)| k.clone());
+        }
+    }
+
+    fn status(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn cf_not_found(cf_name: &str) -> anyhow::Error {
+    format_err!(
+        "DB::cf_handle not found for column family name: {}",
+        cf_name
+    )
+}
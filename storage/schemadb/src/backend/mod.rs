@@ -0,0 +1,72 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines the `KeyValueDB` trait, the raw key-value store a [`DB`](crate::DB) is built
+//! on, with a RocksDB implementation and an in-memory one.
+
+pub(crate) mod in_memory;
+pub(crate) mod rocks_db;
+
+use crate::{ColumnFamilyName, ReadOptions, SchemaBatch};
+use anyhow::Result;
+use std::{fmt::Debug, path::Path};
+
+/// A key-value store of raw bytes, with keys in separate column families ordered bytewise.
+pub(crate) trait KeyValueDB: Debug + Send + Sync {
+    /// Reads the value of `key` in the column family.
+    fn get(&self, cf_name: ColumnFamilyName, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Applies all the updates in `batch` atomically. Returns the size of the batch in bytes.
+    fn write(&self, batch: &SchemaBatch) -> Result<usize>;
+
+    /// Deletes all keys in range [begin, end) in the column family.
+    fn range_delete(&self, cf_name: ColumnFamilyName, begin: &[u8], end: &[u8]) -> Result<()>;
+
+    /// Returns an unpositioned iterator on the column family. The iterator sees a consistent view
+    /// of the column family as of its creation.
+    fn raw_iter(
+        &self,
+        cf_name: ColumnFamilyName,
+        opts: ReadOptions,
+    ) -> Result<Box<dyn RawIterator + '_>>;
+
+    /// Flushes all in-memory data to disk, if there's a disk.
+    fn flush_all(&self) -> Result<()>;
+
+    /// Reads a RocksDB property of the column family.
+    fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64>;
+
+    /// Returns the path of the directory the DB lives in, or `None` if it lives in memory.
+    fn path(&self) -> Option<&Path>;
+
+    /// Creates a physical checkpoint of the DB in directory `path`.
+    fn create_checkpoint(&self, path: &Path) -> Result<()>;
+}
+
+/// A bidirectional cursor over the raw keys and values of a column family, mirroring
+/// `rocksdb::DBRawIterator`.
+pub(crate) trait RawIterator {
+    fn seek_to_first(&mut self);
+
+    fn seek_to_last(&mut self);
+
+    /// Positions at the first key equal to or greater than `key`.
+    fn seek(&mut self, key: &[u8]);
+
+    /// Positions at the last key less than or equal to `key`.
+    fn seek_for_prev(&mut self, key: &[u8]);
+
+    /// Returns whether the iterator is positioned at a key.
+    fn valid(&self) -> bool;
+
+    fn key(&self) -> Option<&[u8]>;
+
+    fn value(&self) -> Option<&[u8]>;
+
+    fn next(&mut self);
+
+    fn prev(&mut self);
+
+    /// Returns the error hit by the iterator, if any.
+    fn status(&self) -> Result<()>;
+}
@@ -0,0 +1,151 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backend::{KeyValueDB, RawIterator},
+    ColumnFamilyName, ReadOptions, SchemaBatch, WriteOp,
+};
+use anyhow::{format_err, Result};
+use std::path::Path;
+
+/// [`KeyValueDB`] on a RocksDB instance.
+#[derive(Debug)]
+pub(crate) struct RocksDB {
+    inner: rocksdb::DB,
+    column_families: Vec<ColumnFamilyName>,
+}
+
+impl RocksDB {
+    pub fn new(inner: rocksdb::DB, column_families: Vec<ColumnFamilyName>) -> Self {
+        Self {
+            inner,
+            column_families,
+        }
+    }
+
+    fn get_cf_handle(&self, cf_name: &str) -> Result<&rocksdb::ColumnFamily> {
+        self.inner.cf_handle(cf_name).ok_or_else(|| {
+            format_err!(
+                "DB::cf_handle not found for column family name: {}",
+                cf_name
+            )
+        })
+    }
+}
+
+impl KeyValueDB for RocksDB {
+    fn get(&self, cf_name: ColumnFamilyName, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.inner.get_cf(self.get_cf_handle(cf_name)?, key)?)
+    }
+
+    fn write(&self, batch: &SchemaBatch) -> Result<usize> {
+        let mut db_batch = rocksdb::WriteBatch::default();
+        for (cf_name, rows) in &batch.rows {
+            let cf_handle = self.get_cf_handle(cf_name)?;
+            for (key, write_op) in rows {
+                match write_op {
+                    WriteOp::Value(value) => db_batch.put_cf(cf_handle, key, value),
+                    WriteOp::Deletion => db_batch.delete_cf(cf_handle, key),
+                }
+            }
+        }
+        let serialized_size = db_batch.size_in_bytes();
+
+        self.inner.write_opt(db_batch, &default_write_options())?;
+        Ok(serialized_size)
+    }
+
+    fn range_delete(&self, cf_name: ColumnFamilyName, begin: &[u8], end: &[u8]) -> Result<()> {
+        self.inner
+            .delete_range_cf(self.get_cf_handle(cf_name)?, begin, end)?;
+        Ok(())
+    }
+
+    fn raw_iter(
+        &self,
+        cf_name: ColumnFamilyName,
+        opts: ReadOptions,
+    ) -> Result<Box<dyn RawIterator + '_>> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        Ok(Box::new(self.inner.raw_iterator_cf_opt(cf_handle, opts)))
+    }
+
+    fn flush_all(&self) -> Result<()> {
+        for cf_name in &self.column_families {
+            self.inner.flush_cf(self.get_cf_handle(cf_name)?)?;
+        }
+        Ok(())
+    }
+
+    fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
+        self.inner
+            .property_int_value_cf(self.get_cf_handle(cf_name)?, property_name)?
+            .ok_or_else(|| {
+                format_err!(
+                    "Unable to get property \"{}\" of  column family \"{}\".",
+                    property_name,
+                    cf_name,
+                )
+            })
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(self.inner.path())
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        rocksdb::checkpoint::Checkpoint::new(&self.inner)?.create_checkpoint(path)?;
+        Ok(())
+    }
+}
+
+impl<'a> RawIterator for rocksdb::DBRawIterator<'a> {
+    fn seek_to_first(&mut self) {
+        rocksdb::DBRawIterator::seek_to_first(self)
+    }
+
+    fn seek_to_last(&mut self) {
+        rocksdb::DBRawIterator::seek_to_last(self)
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        rocksdb::DBRawIterator::seek(self, key)
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) {
+        rocksdb::DBRawIterator::seek_for_prev(self, key)
+    }
+
+    fn valid(&self) -> bool {
+        rocksdb::DBRawIterator::valid(self)
+    }
+
+    fn key(&self) -> Option<&[u8]> {
+        rocksdb::DBRawIterator::key(self)
+    }
+
+    fn value(&self) -> Option<&[u8]> {
+        rocksdb::DBRawIterator::value(self)
+    }
+
+    fn next(&mut self) {
+        rocksdb::DBRawIterator::next(self)
+    }
+
+    fn prev(&mut self) {
+        rocksdb::DBRawIterator::prev(self)
+    }
+
+    fn status(&self) -> Result<()> {
+        Ok(rocksdb::DBRawIterator::status(self)?)
+    }
+}
+
+/// For now we always use synchronous writes. This makes sure that once the operation returns
+/// `Ok(())` the data is persisted even if the machine crashes. In the future we might consider
+/// selectively turning this off for some non-critical writes to improve performance.
+fn default_write_options() -> rocksdb::WriteOptions {
+    let mut opts = rocksdb::WriteOptions::default();
+    opts.set_sync(true);
+    opts
+}
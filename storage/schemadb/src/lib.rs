@@ -3,21 +3,23 @@
 
 #![forbid(unsafe_code)]
 
-//! This library implements a schematized DB on top of [RocksDB](https://rocksdb.org/). It makes
-//! sure all data passed in and out are structured according to predefined schemas and prevents
-//! access to raw keys and values. This library also enforces a set of Diem specific DB options,
-//! like custom comparators and schema-to-column-family mapping.
+//! This library implements a schematized DB on top of [RocksDB](https://rocksdb.org/), or of an
+//! in-memory store for tests. It makes sure all data passed in and out are structured according to
+//! predefined schemas and prevents access to raw keys and values. This library also enforces a set
+//! of Diem specific DB options, like custom comparators and schema-to-column-family mapping.
 //!
 //! It requires that different kinds of key-value pairs be stored in separate column
 //! families.  To use this library to store a kind of key-value pairs, the user needs to use the
 //! [`define_schema!`] macro to define the schema name, the types of key and value, and name of the
 //! column family.
 
+mod backend;
 mod metrics;
 #[macro_use]
 pub mod schema;
 
 use crate::{
+    backend::{in_memory::InMemoryDB, rocks_db::RocksDB, KeyValueDB, RawIterator},
     metrics::{
         DIEM_SCHEMADB_BATCH_COMMIT_BYTES, DIEM_SCHEMADB_BATCH_COMMIT_LATENCY_SECONDS,
         DIEM_SCHEMADB_DELETES, DIEM_SCHEMADB_GET_BYTES, DIEM_SCHEMADB_GET_LATENCY_SECONDS,
@@ -25,7 +27,7 @@ use crate::{
     },
     schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec},
 };
-use anyhow::{ensure, format_err, Result};
+use anyhow::{ensure, Result};
 use diem_logger::prelude::*;
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
@@ -98,7 +100,7 @@ pub enum ScanDirection {
 /// DB Iterator parameterized on [`Schema`] that seeks with [`Schema::Key`] and yields
 /// [`Schema::Key`] and [`Schema::Value`]
 pub struct SchemaIterator<'a, S> {
-    db_iter: rocksdb::DBRawIterator<'a>,
+    db_iter: Box<dyn RawIterator + 'a>,
     direction: ScanDirection,
     phantom: PhantomData<S>,
 }
@@ -107,7 +109,7 @@ impl<'a, S> SchemaIterator<'a, S>
 where
     S: Schema,
 {
-    fn new(db_iter: rocksdb::DBRawIterator<'a>, direction: ScanDirection) -> Self {
+    fn new(db_iter: Box<dyn RawIterator + 'a>, direction: ScanDirection) -> Self {
         SchemaIterator {
             db_iter,
             direction,
@@ -189,12 +191,11 @@ where
 }
 
 /// This DB is a schematized RocksDB wrapper where all data passed in and out are typed according to
-/// [`Schema`]s.
+/// [`Schema`]s. It can also keep everything in memory instead, see [`DB::open_in_memory`].
 #[derive(Debug)]
 pub struct DB {
     name: &'static str, // for logging
-    inner: rocksdb::DB,
-    column_families: Vec<ColumnFamilyName>,
+    inner: Box<dyn KeyValueDB>,
 }
 
 impl DB {
@@ -206,22 +207,39 @@ impl DB {
         column_families: Vec<ColumnFamilyName>,
         db_opts: &rocksdb::Options,
     ) -> Result<Self> {
-        {
-            let cfs_set: HashSet<_> = column_families.iter().collect();
-            ensure!(
-                cfs_set.contains(&DEFAULT_CF_NAME),
-                "No \"default\" column family name is provided.",
-            );
-            ensure!(
-                cfs_set.len() == column_families.len(),
-                "Duplicate column family name found.",
-            );
-        }
+        Self::check_column_families(&column_families)?;
 
         let db = DB::open_cf(db_opts, path, name, column_families)?;
         Ok(db)
     }
 
+    /// Create an empty db with all the column families provided, which lives in memory only and is
+    /// gone when dropped.
+    pub fn open_in_memory(
+        name: &'static str,
+        column_families: Vec<ColumnFamilyName>,
+    ) -> Result<Self> {
+        Self::check_column_families(&column_families)?;
+
+        Ok(Self::log_construct(
+            name,
+            Box::new(InMemoryDB::new(&column_families)),
+        ))
+    }
+
+    fn check_column_families(column_families: &[ColumnFamilyName]) -> Result<()> {
+        let cfs_set: HashSet<_> = column_families.iter().collect();
+        ensure!(
+            cfs_set.contains(&DEFAULT_CF_NAME),
+            "No \"default\" column family name is provided.",
+        );
+        ensure!(
+            cfs_set.len() == column_families.len(),
+            "Duplicate column family name found.",
+        );
+        Ok(())
+    }
+
     /// Open db in readonly mode
     /// Note that this still assumes there's only one process that opens the same DB.
     /// See `open_as_secondary`
@@ -263,7 +281,10 @@ impl DB {
                 rocksdb::ColumnFamilyDescriptor::new((*cf_name).to_string(), cf_opts)
             }),
         )?;
-        Ok(Self::log_construct(name, column_families, inner))
+        Ok(Self::log_construct(
+            name,
+            Box::new(RocksDB::new(inner, column_families)),
+        ))
     }
 
     fn open_cf_readonly(
@@ -280,7 +301,10 @@ impl DB {
             error_if_log_file_exists,
         )?;
 
-        Ok(Self::log_construct(name, column_families, inner))
+        Ok(Self::log_construct(
+            name,
+            Box::new(RocksDB::new(inner, column_families)),
+        ))
     }
 
     fn open_cf_as_secondary<P: AsRef<Path>>(
@@ -297,20 +321,19 @@ impl DB {
             &column_families,
         )?;
 
-        Ok(Self::log_construct(name, column_families, inner))
+        Ok(Self::log_construct(
+            name,
+            Box::new(RocksDB::new(inner, column_families)),
+        ))
     }
 
-    fn log_construct(
-        name: &'static str,
-        column_families: Vec<&'static str>,
-        inner: rocksdb::DB,
-    ) -> DB {
-        info!(rocksdb_name = name, "Opened RocksDB.");
-        DB {
-            name,
-            inner,
-            column_families,
-        }
+    fn log_construct(name: &'static str, inner: Box<dyn KeyValueDB>) -> DB {
+        info!(
+            rocksdb_name = name,
+            in_memory = inner.path().is_none(),
+            "Opened RocksDB."
+        );
+        DB { name, inner }
     }
 
     /// Reads single record by key.
@@ -320,9 +343,7 @@ impl DB {
             .start_timer();
 
         let k = <S::Key as KeyCodec<S>>::encode_key(schema_key)?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
-
-        let result = self.inner.get_cf(cf_handle, &k)?;
+        let result = self.inner.get(S::COLUMN_FAMILY_NAME, &k)?;
         DIEM_SCHEMADB_GET_BYTES
             .with_label_values(&[S::COLUMN_FAMILY_NAME])
             .observe(result.as_ref().map_or(0.0, |v| v.len() as f64));
@@ -352,11 +373,9 @@ impl DB {
     {
         let raw_begin = begin.encode_seek_key()?;
         let raw_end = end.encode_seek_key()?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
 
         self.inner
-            .delete_range_cf(cf_handle, &raw_begin, &raw_end)?;
-        Ok(())
+            .range_delete(S::COLUMN_FAMILY_NAME, &raw_begin, &raw_end)
     }
 
     fn iter_with_direction<S: Schema>(
@@ -364,9 +383,8 @@ impl DB {
         opts: ReadOptions,
         direction: ScanDirection,
     ) -> Result<SchemaIterator<S>> {
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
         Ok(SchemaIterator::new(
-            self.inner.raw_iterator_cf_opt(cf_handle, opts),
+            self.inner.raw_iter(S::COLUMN_FAMILY_NAME, opts)?,
             direction,
         ))
     }
@@ -387,19 +405,7 @@ impl DB {
             .with_label_values(&[self.name])
             .start_timer();
 
-        let mut db_batch = rocksdb::WriteBatch::default();
-        for (cf_name, rows) in &batch.rows {
-            let cf_handle = self.get_cf_handle(cf_name)?;
-            for (key, write_op) in rows {
-                match write_op {
-                    WriteOp::Value(value) => db_batch.put_cf(cf_handle, key, value),
-                    WriteOp::Deletion => db_batch.delete_cf(cf_handle, key),
-                }
-            }
-        }
-        let serialized_size = db_batch.size_in_bytes();
-
-        self.inner.write_opt(db_batch, &default_write_options())?;
+        let serialized_size = self.inner.write(&batch)?;
 
         // Bump counters only after DB write succeeds.
         for (cf_name, rows) in &batch.rows {
@@ -423,49 +429,23 @@ impl DB {
         Ok(())
     }
 
-    fn get_cf_handle(&self, cf_name: &str) -> Result<&rocksdb::ColumnFamily> {
-        self.inner.cf_handle(cf_name).ok_or_else(|| {
-            format_err!(
-                "DB::cf_handle not found for column family name: {}",
-                cf_name
-            )
-        })
-    }
-
     /// Flushes all memtable data. This is only used for testing `get_approximate_sizes_cf` in unit
     /// tests.
     pub fn flush_all(&self) -> Result<()> {
-        for cf_name in &self.column_families {
-            let cf_handle = self.get_cf_handle(cf_name)?;
-            self.inner.flush_cf(cf_handle)?;
-        }
-        Ok(())
+        self.inner.flush_all()
     }
 
     pub fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
-        self.inner
-            .property_int_value_cf(self.get_cf_handle(cf_name)?, property_name)?
-            .ok_or_else(|| {
-                format_err!(
-                    "Unable to get property \"{}\" of  column family \"{}\".",
-                    property_name,
-                    cf_name,
-                )
-            })
+        self.inner.get_property(cf_name, property_name)
+    }
+
+    /// Returns the path of the directory the DB was opened at, or `None` if it's in memory.
+    pub fn path(&self) -> Option<&Path> {
+        self.inner.path()
     }
 
     /// Creates new physical DB checkpoint in directory specified by `path`.
     pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        rocksdb::checkpoint::Checkpoint::new(&self.inner)?.create_checkpoint(path)?;
-        Ok(())
+        self.inner.create_checkpoint(path.as_ref())
     }
 }
-
-/// For now we always use synchronous writes. This makes sure that once the operation returns
-/// `Ok(())` the data is persisted even if the machine crashes. In the future we might consider
-/// selectively turning this off for some non-critical writes to improve performance.
-fn default_write_options() -> rocksdb::WriteOptions {
-    let mut opts = rocksdb::WriteOptions::default();
-    opts.set_sync(true);
-    opts
-}
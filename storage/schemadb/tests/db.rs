@@ -414,3 +414,50 @@ fn test_checkpoint() {
         assert_eq!(db.get::<TestSchema1>(&TestField(1)).unwrap(), None);
     }
 }
+
+#[test]
+fn test_in_memory() {
+    let db = DB::open_in_memory("test", get_column_families()).unwrap();
+    assert!(db.path().is_none());
+
+    let mut db_batch = SchemaBatch::new();
+    for i in 0..10 {
+        db_batch
+            .put::<TestSchema1>(&TestField(i), &TestField(i))
+            .unwrap();
+    }
+    db_batch
+        .put::<TestSchema2>(&TestField(0), &TestField(0))
+        .unwrap();
+    db.write_schemas(db_batch).unwrap();
+
+    // Iterators see the column family as of their creation.
+    let mut iter = db.iter::<TestSchema1>(Default::default()).unwrap();
+    iter.seek_to_first();
+    db.range_delete::<TestSchema1, TestField>(&TestField(2), &TestField(8))
+        .unwrap();
+    let mut db_batch = SchemaBatch::new();
+    db_batch.delete::<TestSchema2>(&TestField(0)).unwrap();
+    db.write_schemas(db_batch).unwrap();
+    assert_eq!(iter.count(), 10);
+
+    let mut iter = db.iter::<TestSchema1>(Default::default()).unwrap();
+    iter.seek_to_first();
+    assert_eq!(
+        iter.collect::<Result<Vec<_>>>().unwrap(),
+        gen_expected_values(&[(0, 0), (1, 1), (8, 8), (9, 9)]),
+    );
+    assert_eq!(db.get::<TestSchema2>(&TestField(0)).unwrap(), None);
+
+    assert_eq!(
+        db.get_property("TestCF1", "rocksdb.estimate-live-data-size")
+            .unwrap(),
+        0
+    );
+    assert!(db
+        .get_property("NoSuchCF", "rocksdb.estimate-live-data-size")
+        .is_err());
+    assert!(db
+        .create_checkpoint(diem_temppath::TempPath::new().path())
+        .is_err());
+}
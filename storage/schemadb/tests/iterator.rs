@@ -3,10 +3,11 @@
 
 use anyhow::Result;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use proptest::{collection::vec, prelude::*};
 use schemadb::{
     define_schema,
     schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec},
-    SchemaIterator, DB, DEFAULT_CF_NAME,
+    ColumnFamilyName, SchemaIterator, DB, DEFAULT_CF_NAME,
 };
 
 define_schema!(TestSchema, TestKey, TestValue, "TestCF");
@@ -66,24 +67,37 @@ impl SeekKeyCodec<TestSchema> for KeyPrefix2 {
     }
 }
 
+fn get_column_families() -> Vec<ColumnFamilyName> {
+    vec![DEFAULT_CF_NAME, TestSchema::COLUMN_FAMILY_NAME]
+}
+
 fn collect_values(iter: SchemaIterator<TestSchema>) -> Vec<u32> {
     iter.map(|row| (row.unwrap().1).0).collect()
 }
 
 struct TestDB {
-    _tmpdir: diem_temppath::TempPath,
+    _tmpdir: Option<diem_temppath::TempPath>,
     db: DB,
 }
 
 impl TestDB {
     fn new() -> Self {
         let tmpdir = diem_temppath::TempPath::new();
-        let column_families = vec![DEFAULT_CF_NAME, TestSchema::COLUMN_FAMILY_NAME];
         let mut db_opts = rocksdb::Options::default();
         db_opts.create_if_missing(true);
         db_opts.create_missing_column_families(true);
-        let db = DB::open(&tmpdir.path(), "test", column_families, &db_opts).unwrap();
+        let db = DB::open(&tmpdir.path(), "test", get_column_families(), &db_opts).unwrap();
+
+        Self::populate(db, Some(tmpdir))
+    }
+
+    fn new_in_memory() -> Self {
+        let db = DB::open_in_memory("test", get_column_families()).unwrap();
 
+        Self::populate(db, None)
+    }
+
+    fn populate(db: DB, tmpdir: Option<diem_temppath::TempPath>) -> Self {
         db.put::<TestSchema>(&TestKey(1, 0, 0), &TestValue(100))
             .unwrap();
         db.put::<TestSchema>(&TestKey(1, 0, 2), &TestValue(102))
@@ -265,3 +279,51 @@ fn test_seek_for_prev_by_2prefix() {
     iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
     assert_eq!(collect_values(iter), [114, 112, 110, 104, 102, 100]);
 }
+
+#[derive(Debug)]
+enum SeekOp {
+    ToFirst,
+    ToLast,
+    Key(u32, u32, u32),
+    ForPrevKey(u32, u32, u32),
+    Prefix1(u32),
+    ForPrevPrefix2(u32, u32),
+}
+
+fn arb_seek_op() -> impl Strategy<Value = SeekOp> {
+    prop_oneof![
+        Just(SeekOp::ToFirst),
+        Just(SeekOp::ToLast),
+        (0..4u32, 0..3u32, 0..6u32).prop_map(|(a, b, c)| SeekOp::Key(a, b, c)),
+        (0..4u32, 0..3u32, 0..6u32).prop_map(|(a, b, c)| SeekOp::ForPrevKey(a, b, c)),
+        (0..4u32).prop_map(SeekOp::Prefix1),
+        (0..4u32, 0..3u32).prop_map(|(a, b)| SeekOp::ForPrevPrefix2(a, b)),
+    ]
+}
+
+fn seek(mut iter: SchemaIterator<TestSchema>, op: &SeekOp) -> Vec<u32> {
+    match op {
+        SeekOp::ToFirst => iter.seek_to_first(),
+        SeekOp::ToLast => iter.seek_to_last(),
+        SeekOp::Key(a, b, c) => iter.seek(&TestKey(*a, *b, *c)).unwrap(),
+        SeekOp::ForPrevKey(a, b, c) => iter.seek_for_prev(&TestKey(*a, *b, *c)).unwrap(),
+        SeekOp::Prefix1(a) => iter.seek(&KeyPrefix1(*a)).unwrap(),
+        SeekOp::ForPrevPrefix2(a, b) => iter.seek_for_prev(&KeyPrefix2(*a, *b)).unwrap(),
+    }
+    collect_values(iter)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_in_memory_iterators_match_rocksdb(ops in vec(arb_seek_op(), 1..20)) {
+        let db = TestDB::new();
+        let in_memory_db = TestDB::new_in_memory();
+
+        for op in &ops {
+            prop_assert_eq!(seek(db.iter(), op), seek(in_memory_db.iter(), op));
+            prop_assert_eq!(seek(db.rev_iter(), op), seek(in_memory_db.rev_iter(), op));
+        }
+    }
+}
@@ -3,7 +3,7 @@
 
 use anyhow::Result;
 use byteorder::{BigEndian, WriteBytesExt};
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{RocksdbConfig, SecondaryIndexesConfig};
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state_blob::AccountStateBlob,
@@ -31,12 +31,20 @@ fn gen_random_blob<R: Rng>(size: usize, rng: &mut R) -> AccountStateBlob {
     AccountStateBlob::from(v)
 }
 
-pub fn run_benchmark(num_updates: usize, max_accounts: u64, blob_size: usize, db_dir: PathBuf) {
+pub fn run_benchmark(
+    num_updates: usize,
+    max_accounts: u64,
+    blob_size: usize,
+    db_dir: PathBuf,
+    max_in_mem_nodes: Option<usize>,
+) {
     let db = DiemDB::open(
         &db_dir,
         false, /* readonly */
         None,  /* pruner */
+        None,  /* ledger_pruner */
         RocksdbConfig::default(),
+        SecondaryIndexesConfig::default(),
     )
     .expect("DB should open.");
 
@@ -72,17 +80,32 @@ pub fn run_benchmark(num_updates: usize, max_accounts: u64, blob_size: usize, db
     let root = db.get_latest_state_root().unwrap().1;
     let smt = SparseMerkleTree::new(root);
     let start = std::time::Instant::now();
-    smt.batch_update(
-        updates
-            .iter()
-            .map(|(k, v)| (k.hash(), v))
-            .collect::<Vec<_>>(),
-        &proof_reader,
-    )
-    .unwrap();
+    let updated = smt
+        .batch_update(
+            updates
+                .iter()
+                .map(|(k, v)| (k.hash(), v))
+                .collect::<Vec<_>>(),
+            &proof_reader,
+        )
+        .unwrap();
     println!(
-        "Sparse Merkle Tree batch update {} updates: {}ms",
+        "Sparse Merkle Tree batch update {} updates: {}ms, {} nodes in memory",
         num_updates,
-        start.elapsed().as_millis()
+        start.elapsed().as_millis(),
+        updated.num_in_mem_nodes(),
     );
+
+    if let Some(max_in_mem_nodes) = max_in_mem_nodes {
+        // Treat the updated tree as committed, which is when eviction is allowed.
+        let start = std::time::Instant::now();
+        let num_evicted = updated.evict_idle_subtrees(max_in_mem_nodes);
+        println!(
+            "Evicted {} nodes to fit {} nodes in memory: {}ms, {} nodes in memory",
+            num_evicted,
+            max_in_mem_nodes,
+            start.elapsed().as_millis(),
+            updated.num_in_mem_nodes(),
+        );
+    }
 }
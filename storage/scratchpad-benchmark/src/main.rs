@@ -17,6 +17,10 @@ struct Opt {
 
     #[structopt(long, parse(from_os_str))]
     db_dir: PathBuf,
+
+    /// If set, evicts nodes of the updated tree until at most this many are held in memory.
+    #[structopt(long)]
+    max_in_mem_nodes: Option<usize>,
 }
 
 fn main() {
@@ -31,5 +35,6 @@ fn main() {
         opt.num_accounts,
         opt.blob_size,
         opt.db_dir,
+        opt.max_in_mem_nodes,
     );
 }
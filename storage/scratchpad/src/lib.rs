@@ -3,6 +3,7 @@
 
 //! This crate provides in-memory representation of Diem core data structures used by the executor.
 
+pub mod metrics;
 mod sparse_merkle;
 
 pub use crate::sparse_merkle::{AccountStatus, ProofRead, SparseMerkleTree};
@@ -0,0 +1,21 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_metrics::{register_int_counter, register_int_gauge, IntCounter, IntGauge};
+use once_cell::sync::Lazy;
+
+pub static DIEM_SCRATCHPAD_IN_MEM_NODES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_scratchpad_in_mem_nodes",
+        "Number of sparse merkle tree nodes held in memory by the scratchpad"
+    )
+    .unwrap()
+});
+
+pub static DIEM_SCRATCHPAD_EVICTED_NODES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_scratchpad_evicted_nodes",
+        "Number of sparse merkle tree nodes evicted from memory by the scratchpad"
+    )
+    .unwrap()
+});
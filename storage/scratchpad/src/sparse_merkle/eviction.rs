@@ -0,0 +1,217 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module implements the selection and collapsing of idle subtrees, used to bound the number
+//! of nodes a family of sparse merkle trees holds in memory.
+//!
+//! A subtree is "owned" by a tree if the tree holds a strong reference to its root node, which is
+//! only the case in the tree that created the node. Evicting a subtree replaces it by an unknown
+//! subtree carrying the same hash in the owning tree, which drops its nodes since trees derived
+//! from the owning tree only hold weak references to them.
+//!
+//! The effective last access of an owned subtree is the latest generation that touched any of its
+//! owned nodes. It never decreases from a subtree to one of its ancestors, so evicting in the
+//! order of effective last access (shallower first on ties) evicts whole cold regions at once.
+
+use crate::sparse_merkle::node::{Node, SubTree};
+use diem_crypto::hash::CryptoHash;
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+    sync::Arc,
+};
+
+/// Identifies a node by its address, which is stable while a strong reference to it is held.
+type NodeId = usize;
+
+fn node_id<V>(node: &Arc<Node<V>>) -> NodeId {
+    Arc::as_ptr(node) as NodeId
+}
+
+/// Returns the number of nodes owned by `subtree`.
+pub(crate) fn num_owned_nodes<V: CryptoHash>(subtree: &SubTree<V>) -> usize {
+    let mut num_nodes = 0;
+    let mut stack = vec![subtree];
+    while let Some(subtree) = stack.pop() {
+        if let Some(node) = subtree.get_owned_node() {
+            num_nodes += 1;
+            if let Node::Internal(internal_node) = node.borrow() {
+                stack.push(&internal_node.left);
+                stack.push(&internal_node.right);
+            }
+        }
+    }
+    num_nodes
+}
+
+/// An owned subtree which may be chosen, ordered by effective last access, shallower first on ties
+/// (and by id to make the order total).
+struct Candidate {
+    last_access: u64,
+    depth: usize,
+    id: NodeId,
+    /// Owned nodes on the path from the root to this subtree, root first.
+    ancestors: Vec<NodeId>,
+    num_nodes: usize,
+}
+
+impl Candidate {
+    fn key(&self) -> (u64, usize, NodeId) {
+        (self.last_access, self.depth, self.id)
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// The owned subtrees of a tree chosen to be evicted.
+pub(crate) struct IdleSubtrees {
+    /// Roots of the subtrees to be collapsed.
+    roots: HashSet<NodeId>,
+    /// Owned nodes that have a chosen subtree below them, which need to be rebuilt.
+    ancestors: HashSet<NodeId>,
+    num_nodes: usize,
+}
+
+impl IdleSubtrees {
+    /// Chooses the least recently accessed owned subtrees of `root`, until they contain at least
+    /// `num_nodes_to_evict` nodes or all owned nodes are chosen.
+    ///
+    /// Every candidate considered before the last one chosen is a distinct node evicted by then, so
+    /// only the `num_nodes_to_evict` first candidates in order are ever considered. Only those are
+    /// kept while walking the tree, in a max-heap, instead of sorting all owned nodes.
+    pub fn select<V: CryptoHash>(root: &SubTree<V>, num_nodes_to_evict: usize) -> Self {
+        let mut candidates = BinaryHeap::new();
+        Self::collect_candidates(root, &mut Vec::new(), num_nodes_to_evict, &mut candidates);
+
+        let mut selected = Self {
+            roots: HashSet::new(),
+            ancestors: HashSet::new(),
+            num_nodes: 0,
+        };
+        // Number of nodes already chosen below each owned node.
+        let mut num_chosen_below: HashMap<NodeId, usize> = HashMap::new();
+        for candidate in candidates.into_sorted_vec() {
+            if selected.num_nodes >= num_nodes_to_evict {
+                break;
+            }
+            if candidate
+                .ancestors
+                .iter()
+                .any(|id| selected.roots.contains(id))
+            {
+                continue;
+            }
+
+            let num_new = candidate.num_nodes
+                - num_chosen_below
+                    .get(&candidate.id)
+                    .copied()
+                    .unwrap_or_default();
+            for id in &candidate.ancestors {
+                *num_chosen_below.entry(*id).or_default() += num_new;
+                selected.ancestors.insert(*id);
+            }
+            selected.roots.insert(candidate.id);
+            selected.num_nodes += num_new;
+        }
+        selected
+    }
+
+    /// Returns `(num_nodes, last_access)` of the owned part of `subtree`, keeping a candidate for
+    /// each owned node in `candidates` as long as it is among the `max_candidates` first ones.
+    fn collect_candidates<V: CryptoHash>(
+        subtree: &SubTree<V>,
+        ancestors: &mut Vec<NodeId>,
+        max_candidates: usize,
+        candidates: &mut BinaryHeap<Candidate>,
+    ) -> (usize, u64) {
+        let node = match subtree.get_owned_node() {
+            Some(node) => node,
+            None => return (0, 0),
+        };
+        let id = node_id(node);
+        let (mut num_nodes, mut last_access) = (1, node.last_access());
+        if let Node::Internal(internal_node) = node.borrow() {
+            ancestors.push(id);
+            for child in &[&internal_node.left, &internal_node.right] {
+                let (n, l) = Self::collect_candidates(child, ancestors, max_candidates, candidates);
+                num_nodes += n;
+                last_access = last_access.max(l);
+            }
+            ancestors.pop();
+        }
+
+        let depth = ancestors.len();
+        let is_candidate = candidates.len() < max_candidates
+            || candidates
+                .peek()
+                .map_or(false, |last| (last_access, depth, id) < last.key());
+        if is_candidate {
+            if candidates.len() == max_candidates {
+                candidates.pop();
+            }
+            candidates.push(Candidate {
+                last_access,
+                depth,
+                id,
+                ancestors: ancestors.clone(),
+                num_nodes,
+            });
+        }
+        (num_nodes, last_access)
+    }
+
+    /// Number of nodes to be evicted.
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    /// Returns `subtree` with the chosen subtrees replaced by their root hashes. Nodes on the way
+    /// to them are rebuilt and keep their last access, while other subtrees are reused as they
+    /// are, so weak references to them held by descendant trees stay valid.
+    pub fn collapse<V: CryptoHash>(&self, subtree: &SubTree<V>) -> SubTree<V> {
+        let node = match subtree.get_owned_node() {
+            Some(node) => node,
+            None => return subtree.clone(),
+        };
+        let id = node_id(node);
+        if self.roots.contains(&id) {
+            return SubTree::new_unknown(subtree.hash());
+        }
+        if !self.ancestors.contains(&id) {
+            return subtree.clone();
+        }
+        match node.borrow() {
+            Node::Internal(internal_node) => {
+                let collapsed = SubTree::new_internal(
+                    self.collapse(&internal_node.left),
+                    self.collapse(&internal_node.right),
+                );
+                if let Some(new_node) = collapsed.get_owned_node() {
+                    new_node.touch(node.last_access());
+                }
+                collapsed
+            }
+            Node::Leaf(_) => unreachable!("A leaf can't have a chosen subtree below it."),
+        }
+    }
+}
@@ -50,6 +50,10 @@
 //! on it which replaces the strong references to its root and its base tree with weak references.
 //!     3. We can hold strong references to recently accessed nodes that have already been persisted
 //! in an LRU flavor cache for less DB reads.
+//!     4. Nodes of a persisted tree can be dropped selectively to bound memory usage, which we
+//! express by calling "evict_idle_subtrees()" on it. Subtrees accessed least recently (by
+//! generation of the accessing tree, see `SparseMerkleTree::generation`) are replaced by their
+//! root hashes, and trees depending on them will read them from DB via proofs again if needed.
 //!
 //! This Sparse Merkle Tree serves a dual purpose. First, to support a leader based consensus
 //! algorithm, we need to build a tree of transactions like the following:
@@ -69,6 +73,7 @@
 // See https://play.rust-lang.org/?version=stable&mode=debug&edition=2018&gist=795cd4f459f1d4a0005a99650726834b
 #![allow(clippy::while_let_loop)]
 
+mod eviction;
 mod node;
 mod updater;
 mod utils;
@@ -78,11 +83,15 @@ mod sparse_merkle_test;
 #[cfg(any(test, feature = "bench", feature = "fuzzing"))]
 pub mod test_utils;
 
-use crate::sparse_merkle::{
-    node::{Node, SubTree},
-    updater::SubTreeUpdater,
-    utils::{partition, swap_if},
+use crate::{
+    metrics::DIEM_SCRATCHPAD_EVICTED_NODES,
+    sparse_merkle::{
+        node::{Node, SubTree},
+        updater::SubTreeUpdater,
+        utils::{partition, swap_if},
+    },
 };
+use arc_swap::ArcSwap;
 use diem_crypto::{
     hash::{CryptoHash, SPARSE_MERKLE_PLACEHOLDER_HASH},
     HashValue,
@@ -96,7 +105,10 @@ use std::{
     borrow::Borrow,
     cmp,
     collections::{BTreeMap, BTreeSet, HashMap},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 /// `AccountStatus` describes the result of querying an account from this SparseMerkleTree.
@@ -125,8 +137,14 @@ pub enum AccountStatus<V> {
 /// INNER of it can still live if referenced by a previous version.
 #[derive(Debug)]
 struct Inner<V> {
-    root: SubTree<V>,
+    /// Only replaced (by a tree with the same root hash) when nodes are evicted from memory.
+    root: ArcSwap<SubTree<V>>,
+    /// Number of nodes `root` holds strong references to, kept up to date on eviction so that
+    /// the number of nodes in memory is known without traversing the tree.
+    num_owned_nodes: AtomicUsize,
     children: Mutex<Vec<Arc<Inner<V>>>>,
+    /// Number of updates between the tree this family is rooted at and this tree.
+    generation: u64,
 }
 
 impl<V> Drop for Inner<V> {
@@ -145,16 +163,24 @@ impl<V> Drop for Inner<V> {
     }
 }
 
-impl<V> Inner<V> {
+impl<V: CryptoHash> Inner<V> {
     fn new(root: SubTree<V>) -> Arc<Self> {
+        Self::new_with_generation(root, 0)
+    }
+
+    /// Only the nodes created for `root` are owned by it, so counting them costs no more than
+    /// creating them.
+    fn new_with_generation(root: SubTree<V>, generation: u64) -> Arc<Self> {
         Arc::new(Self {
-            root,
+            num_owned_nodes: AtomicUsize::new(eviction::num_owned_nodes(&root)),
+            root: ArcSwap::from_pointee(root),
             children: Mutex::new(Vec::new()),
+            generation,
         })
     }
 
     fn spawn(&self, child_root: SubTree<V>) -> Arc<Self> {
-        let child = Self::new(child_root);
+        let child = Self::new_with_generation(child_root, self.generation + 1);
         self.children.lock().push(child.clone());
         child
     }
@@ -206,7 +232,56 @@ where
     }
 
     fn root_weak(&self) -> SubTree<V> {
-        self.inner.root.weak()
+        self.inner.root.load().weak()
+    }
+
+    /// Returns the number of updates between the tree this one is derived from via `new` and this
+    /// tree. Nodes record the latest generation of a tree that accessed them.
+    pub fn generation(&self) -> u64 {
+        self.inner.generation
+    }
+
+    /// Returns the number of nodes held in memory by this tree and all trees derived from it.
+    pub fn num_in_mem_nodes(&self) -> usize {
+        let mut num_nodes = 0;
+        let mut trees = vec![self.inner.clone()];
+        while let Some(inner) = trees.pop() {
+            num_nodes += inner.num_owned_nodes.load(Ordering::Relaxed);
+            trees.extend(inner.children.lock().iter().cloned());
+        }
+        num_nodes
+    }
+
+    /// Collapses the subtrees of this tree that are accessed least recently into their root hashes,
+    /// until at most `max_in_mem_nodes` nodes are held in memory by this tree and all trees derived
+    /// from it, or nothing of this tree is left to evict. Returns the number of nodes evicted.
+    ///
+    /// Only nodes created by this tree are evicted. Collapsed subtrees are reloaded on demand from
+    /// the `ProofRead` given to later updates, and queries into them return
+    /// `AccountStatus::Unknown`, so this must only be called on a tree whose state is persisted,
+    /// typically the latest committed one. Nodes of speculative trees derived from it count
+    /// against the budget but are never evicted. Nodes on the paths to evicted subtrees are
+    /// replaced, so derived trees read those paths via proofs as well after an eviction.
+    pub fn evict_idle_subtrees(&self, max_in_mem_nodes: usize) -> usize {
+        let num_nodes = self.num_in_mem_nodes();
+        if num_nodes <= max_in_mem_nodes {
+            return 0;
+        }
+
+        let root = self.inner.root.load_full();
+        let idle_subtrees = eviction::IdleSubtrees::select(&root, num_nodes - max_in_mem_nodes);
+        if idle_subtrees.num_nodes() == 0 {
+            return 0;
+        }
+        self.inner
+            .root
+            .store(Arc::new(idle_subtrees.collapse(&root)));
+        self.inner
+            .num_owned_nodes
+            .fetch_sub(idle_subtrees.num_nodes(), Ordering::Relaxed);
+
+        DIEM_SCRATCHPAD_EVICTED_NODES.inc_by(idle_subtrees.num_nodes() as u64);
+        idle_subtrees.num_nodes()
     }
 
     /// Constructs a new Sparse Merkle Tree as if we are updating the existing tree multiple
@@ -361,6 +436,7 @@ where
             root_weak,
             /* subtree_depth = */ 0,
             &updates[..],
+            self.generation() + 1,
             proof_reader,
         )?;
         // Convert txn_hashes to the output format, i.e. a Vec<HashValue> holding a hash value
@@ -391,6 +467,7 @@ where
         subtree: SubTree<V>,
         subtree_depth: usize,
         updates: &[(HashValue, (usize, &V))],
+        generation: u64,
         proof_reader: &impl ProofRead<V>,
     ) -> Result<(SubTree<V>, IntermediateHashes), UpdateError> {
         if updates.is_empty() {
@@ -398,7 +475,11 @@ where
         }
 
         if let SubTree::NonEmpty { root, .. } = &subtree {
-            match root.get_if_in_mem() {
+            let node = root.get_if_in_mem();
+            if let Some(arc_node) = &node {
+                arc_node.touch(generation);
+            }
+            match node {
                 Some(arc_node) => match arc_node.borrow() {
                     Node::Internal(internal_node) => {
                         let pivot = partition(updates, subtree_depth);
@@ -411,12 +492,14 @@ where
                             left_weak,
                             subtree_depth + 1,
                             &updates[..pivot],
+                            generation,
                             proof_reader,
                         )?;
                         let (right_tree, right_hashes) = Self::batches_update_subtree(
                             right_weak,
                             subtree_depth + 1,
                             &updates[pivot..],
+                            generation,
                             proof_reader,
                         )?;
 
@@ -718,11 +801,13 @@ where
 
     /// Queries a `key` in this `SparseMerkleTree`.
     pub fn get(&self, key: HashValue) -> AccountStatus<V> {
+        let generation = self.generation();
         let mut cur = self.root_weak();
         let mut bits = key.iter_bits();
 
         loop {
             if let Some(node) = cur.get_node_if_in_mem() {
+                node.touch(generation);
                 if let Node::Internal(internal_node) = node.borrow() {
                     match bits.next() {
                         Some(bit) => {
@@ -788,14 +873,19 @@ where
         if kvs.is_empty() {
             Ok(self.clone())
         } else {
-            let root = SubTreeUpdater::update(current_root, &kvs[..], proof_reader)?;
+            let root = SubTreeUpdater::update(
+                current_root,
+                &kvs[..],
+                self.generation() + 1,
+                proof_reader,
+            )?;
             Ok(self.spawn(root))
         }
     }
 
     /// Returns the root hash of this tree.
     pub fn root_hash(&self) -> HashValue {
-        self.inner.root.hash()
+        self.inner.root.load().hash()
     }
 }
 
@@ -20,18 +20,37 @@
 //! key which is the hash of the account address as well as a value hash which is the hash of the
 //! corresponding account content. The difference is that a `LeafNode` does not always have the
 //! value, in the case when the leaf was loaded into memory as part of a non-inclusion proof.
+//!
+//! Each node records the latest generation (see `SparseMerkleTree::generation`) of a tree that
+//! accessed it, so that subtrees untouched for a while can be told apart when evicting nodes from
+//! memory.
 
+use crate::metrics::DIEM_SCRATCHPAD_IN_MEM_NODES;
 use diem_crypto::{
     hash::{CryptoHash, SPARSE_MERKLE_PLACEHOLDER_HASH},
     HashValue,
 };
 use diem_types::proof::{SparseMerkleInternalNode, SparseMerkleLeafNode};
-use std::sync::{Arc, Weak};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Weak,
+};
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub(crate) struct InternalNode<V> {
     pub left: SubTree<V>,
     pub right: SubTree<V>,
+    last_access: AtomicU64,
+}
+
+impl<V> InternalNode<V> {
+    pub fn new(left: SubTree<V>, right: SubTree<V>) -> Self {
+        Self {
+            left,
+            right,
+            last_access: AtomicU64::new(0),
+        }
+    }
 }
 
 impl<V: CryptoHash> InternalNode<V> {
@@ -40,15 +59,30 @@ impl<V: CryptoHash> InternalNode<V> {
     }
 }
 
-#[derive(Clone, Debug)]
+impl<V: Clone> Clone for InternalNode<V> {
+    fn clone(&self) -> Self {
+        Self {
+            left: self.left.clone(),
+            right: self.right.clone(),
+            last_access: AtomicU64::new(self.last_access.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub(crate) struct LeafNode<V> {
     pub key: HashValue,
     pub value: LeafValue<V>,
+    last_access: AtomicU64,
 }
 
 impl<V: CryptoHash> LeafNode<V> {
     pub fn new(key: HashValue, value: LeafValue<V>) -> Self {
-        Self { key, value }
+        Self {
+            key,
+            value,
+            last_access: AtomicU64::new(0),
+        }
     }
 
     pub fn calc_hash(&self) -> HashValue {
@@ -56,9 +90,16 @@ impl<V: CryptoHash> LeafNode<V> {
     }
 
     pub fn clone_with_weak_value(&self) -> Self {
+        Self::new(self.key, self.value.weak())
+    }
+}
+
+impl<V: Clone> Clone for LeafNode<V> {
+    fn clone(&self) -> Self {
         Self {
             key: self.key,
-            value: self.value.weak(),
+            value: self.value.clone(),
+            last_access: AtomicU64::new(self.last_access.load(Ordering::Relaxed)),
         }
     }
 }
@@ -68,10 +109,10 @@ where
     V: CryptoHash,
 {
     fn from(leaf_node: &SparseMerkleLeafNode) -> Self {
-        Self {
-            key: leaf_node.key(),
-            value: LeafValue::new_with_value_hash(leaf_node.value_hash()),
-        }
+        Self::new(
+            leaf_node.key(),
+            LeafValue::new_with_value_hash(leaf_node.value_hash()),
+        )
     }
 }
 
@@ -83,11 +124,11 @@ pub(crate) enum Node<V> {
 
 impl<V: CryptoHash> Node<V> {
     pub fn new_leaf(key: HashValue, value: LeafValue<V>) -> Self {
-        Self::Leaf(LeafNode::new(key, value))
+        Self::from(LeafNode::new(key, value))
     }
 
     pub fn new_internal(left: SubTree<V>, right: SubTree<V>) -> Self {
-        Self::Internal(InternalNode { left, right })
+        Self::from(InternalNode::new(left, right))
     }
 
     pub fn calc_hash(&self) -> HashValue {
@@ -98,6 +139,46 @@ impl<V: CryptoHash> Node<V> {
     }
 }
 
+impl<V> Node<V> {
+    /// Records that a tree of `generation` accessed this node.
+    pub fn touch(&self, generation: u64) {
+        self.last_access_atomic()
+            .fetch_max(generation, Ordering::Relaxed);
+    }
+
+    /// The latest generation of a tree that accessed this node.
+    pub fn last_access(&self) -> u64 {
+        self.last_access_atomic().load(Ordering::Relaxed)
+    }
+
+    fn last_access_atomic(&self) -> &AtomicU64 {
+        match self {
+            Self::Internal(internal_node) => &internal_node.last_access,
+            Self::Leaf(leaf_node) => &leaf_node.last_access,
+        }
+    }
+}
+
+impl<V> From<InternalNode<V>> for Node<V> {
+    fn from(internal_node: InternalNode<V>) -> Self {
+        DIEM_SCRATCHPAD_IN_MEM_NODES.inc();
+        Self::Internal(internal_node)
+    }
+}
+
+impl<V> From<LeafNode<V>> for Node<V> {
+    fn from(leaf_node: LeafNode<V>) -> Self {
+        DIEM_SCRATCHPAD_IN_MEM_NODES.inc();
+        Self::Leaf(leaf_node)
+    }
+}
+
+impl<V> Drop for Node<V> {
+    fn drop(&mut self) {
+        DIEM_SCRATCHPAD_IN_MEM_NODES.dec();
+    }
+}
+
 #[derive(Debug)]
 pub enum Ref<R> {
     Shared(Arc<R>),
@@ -210,6 +291,18 @@ impl<V: CryptoHash> SubTree<V> {
         }
     }
 
+    /// Returns the root node if this subtree holds a strong reference to it, which is the case
+    /// only in the tree that created the node.
+    pub fn get_owned_node(&self) -> Option<&Arc<Node<V>>> {
+        match self {
+            Self::NonEmpty {
+                root: NodeHandle::Shared(arc),
+                ..
+            } => Some(arc),
+            _ => None,
+        }
+    }
+
     #[cfg(test)]
     pub fn is_unknown(&self) -> bool {
         matches!(
@@ -396,6 +396,100 @@ fn test_drop() {
     drop(root_smt)
 }
 
+#[test]
+fn test_evict_idle_subtrees_to_zero() {
+    //             root
+    //            /    \
+    //           x      key3
+    //          / \
+    //      key1   key2
+    let key1 = HashValue::new([0x00; HashValue::LENGTH]);
+    let key2 = update_byte(&key1, 0, 0b0100_0000);
+    let key3 = HashValue::new([0xff; HashValue::LENGTH]);
+    let value1 = AccountStateBlob::from(b"value1".to_vec());
+    let value2 = AccountStateBlob::from(b"value2".to_vec());
+    let value3 = AccountStateBlob::from(b"value3".to_vec());
+
+    let smt = SparseMerkleTree::new(*SPARSE_MERKLE_PLACEHOLDER_HASH)
+        .batch_update(
+            vec![(key1, &value1), (key2, &value2), (key3, &value3)],
+            &ProofReader::default(),
+        )
+        .unwrap();
+    let root_hash = smt.root_hash();
+    assert_eq!(smt.num_in_mem_nodes(), 5);
+
+    assert_eq!(smt.evict_idle_subtrees(0), 5);
+    assert_eq!(smt.num_in_mem_nodes(), 0);
+    assert_eq!(smt.root_hash(), root_hash);
+    assert_eq!(smt.get(key1), AccountStatus::Unknown);
+    assert_eq!(smt.evict_idle_subtrees(0), 0);
+
+    // Updating the evicted tree reads the affected part back via proofs.
+    let leaf1 = SparseMerkleLeafNode::new(key1, value1.hash());
+    let leaf2_hash = hash_leaf(key2, value2.hash());
+    let leaf3_hash = hash_leaf(key3, value3.hash());
+    let proof = SparseMerkleProof::new(Some(leaf1), vec![leaf2_hash, leaf3_hash]);
+    assert!(proof.verify(root_hash, key1, Some(&value1)).is_ok());
+
+    let new_value1 = AccountStateBlob::from(b"value11111".to_vec());
+    let proof_reader = ProofReader::new(vec![(key1, proof)]);
+    let updated = smt
+        .batch_update(vec![(key1, &new_value1)], &proof_reader)
+        .unwrap();
+    let x_hash = hash_internal(hash_leaf(key1, new_value1.hash()), leaf2_hash);
+    assert_eq!(updated.root_hash(), hash_internal(x_hash, leaf3_hash));
+    assert_eq!(
+        updated.get(key1),
+        AccountStatus::ExistsInScratchPad(new_value1)
+    );
+    assert_eq!(updated.get(key2), AccountStatus::Unknown);
+}
+
+#[test]
+fn test_evict_idle_subtrees_least_recently_accessed_first() {
+    let key1 = HashValue::new([0x00; HashValue::LENGTH]);
+    let key2 = update_byte(&key1, 0, 0b0100_0000);
+    let key3 = HashValue::new([0xff; HashValue::LENGTH]);
+    let value1 = AccountStateBlob::from(b"value1".to_vec());
+    let value2 = AccountStateBlob::from(b"value2".to_vec());
+    let value3 = AccountStateBlob::from(b"value3".to_vec());
+    let proof_reader = ProofReader::default();
+
+    let smt1 = SparseMerkleTree::new(*SPARSE_MERKLE_PLACEHOLDER_HASH)
+        .batch_update(
+            vec![(key1, &value1), (key2, &value2), (key3, &value3)],
+            &proof_reader,
+        )
+        .unwrap();
+    // smt2 owns a new root and a new leaf for key3, and refers to x in smt1.
+    let new_value3 = AccountStateBlob::from(b"value33333".to_vec());
+    let smt2 = smt1
+        .batch_update(vec![(key3, &new_value3)], &proof_reader)
+        .unwrap();
+    assert_eq!(smt2.generation(), smt1.generation() + 1);
+    assert_eq!(smt1.num_in_mem_nodes(), 7);
+    assert_eq!(smt2.num_in_mem_nodes(), 2);
+
+    // Reading key1 from smt2 makes it the most recently accessed leaf in smt1.
+    assert_eq!(
+        smt2.get(key1),
+        AccountStatus::ExistsInScratchPad(value1.clone())
+    );
+
+    let root_hash = smt1.root_hash();
+    assert_eq!(smt1.evict_idle_subtrees(5), 2);
+    assert_eq!(smt1.num_in_mem_nodes(), 5);
+    assert_eq!(smt1.root_hash(), root_hash);
+    assert_eq!(smt1.get(key1), AccountStatus::ExistsInScratchPad(value1));
+    assert_eq!(smt1.get(key2), AccountStatus::Unknown);
+    assert_eq!(smt1.get(key3), AccountStatus::Unknown);
+    assert_eq!(
+        smt2.get(key3),
+        AccountStatus::ExistsInScratchPad(new_value3)
+    );
+}
+
 proptest! {
     #[test]
     fn test_correctness( input in arb_smt_correctness_case() ) {
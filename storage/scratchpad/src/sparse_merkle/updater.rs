@@ -55,13 +55,10 @@ impl<V: Clone + CryptoHash> InMemSubTreeInfo<V> {
     }
 
     fn create_internal(left: Self, right: Self) -> Self {
-        let node = InternalNode {
-            left: left.into_subtree(),
-            right: right.into_subtree(),
-        };
+        let node = InternalNode::new(left.into_subtree(), right.into_subtree());
         let subtree = InMemSubTree::NonEmpty {
             hash: node.calc_hash(),
-            root: NodeHandle::new_shared(Node::Internal(node.clone())),
+            root: NodeHandle::new_shared(Node::from(node.clone())),
         };
 
         Self::Internal { subtree, node }
@@ -173,7 +170,7 @@ impl<'a, V: Clone + CryptoHash> SubTreeInfo<'a, V> {
                         // that case will reveal its information (since the position didn't change.)
                         // The waste can be counteracted by making from_in_mem() lazy, as commented
                         // in `into_children`
-                        let node = Node::Leaf(leaf_node.clone_with_weak_value());
+                        let node = Node::from(leaf_node.clone_with_weak_value());
                         let subtree = InMemSubTree::NonEmpty {
                             hash: subtree.hash(),
                             root: NodeHandle::new_shared(node),
@@ -266,18 +263,23 @@ impl<'a, V: Clone + CryptoHash> SubTreeInfo<'a, V> {
 
 pub struct SubTreeUpdater<'a, V> {
     depth: usize,
+    generation: u64,
     info: SubTreeInfo<'a, V>,
     updates: &'a [(HashValue, &'a V)],
 }
 
 impl<'a, V: Send + Sync + Clone + CryptoHash> SubTreeUpdater<'a, V> {
+    /// Applies `updates` to `root`, recording accesses to existing nodes as made by a tree of
+    /// `generation`.
     pub(crate) fn update(
         root: InMemSubTree<V>,
         updates: &'a [(HashValue, &'a V)],
+        generation: u64,
         proof_reader: &'a impl ProofRead<V>,
     ) -> Result<InMemSubTree<V>> {
         let updater = Self {
             depth: 0,
+            generation,
             info: SubTreeInfo::from_in_mem(&root),
             updates,
         };
@@ -294,6 +296,7 @@ impl<'a, V: Send + Sync + Clone + CryptoHash> SubTreeUpdater<'a, V> {
         match self.maybe_end_recursion() {
             Either::A(ended) => Ok(ended),
             Either::B(myself) => {
+                myself.touch();
                 let (left, right) = myself.into_children(proof_reader)?;
                 let (left_ret, right_ret) = if depth <= MAX_PARALLELIZABLE_DEPTH
                     && left.updates.len() >= MIN_PARALLELIZABLE_SIZE
@@ -335,6 +338,16 @@ impl<'a, V: Send + Sync + Clone + CryptoHash> SubTreeUpdater<'a, V> {
         }
     }
 
+    /// Records the access to the in-mem internal node the updates are being applied to, if any.
+    /// Siblings of the updated paths are not touched, so untouched regions can be told apart.
+    fn touch(&self) {
+        if let SubTreeInfo::InMem(InMemSubTreeInfo::Internal { subtree, .. }) = &self.info {
+            if let Some(arc_node) = subtree.get_node_if_in_mem() {
+                arc_node.touch(self.generation);
+            }
+        }
+    }
+
     fn into_children(self, proof_reader: &'a impl ProofRead<V>) -> Result<(Self, Self)> {
         let pivot = partition(self.updates, self.depth);
         let (left_updates, right_updates) = self.updates.split_at(pivot);
@@ -345,11 +358,13 @@ impl<'a, V: Send + Sync + Clone + CryptoHash> SubTreeUpdater<'a, V> {
         Ok((
             Self {
                 depth: self.depth + 1,
+                generation: self.generation,
                 info: left_info,
                 updates: left_updates,
             },
             Self {
                 depth: self.depth + 1,
+                generation: self.generation,
                 info: right_info,
                 updates: right_updates,
             },
@@ -7,7 +7,10 @@ use diem_types::{
     access_path::AccessPath,
     account_address::AccountAddress,
     account_state::AccountState,
-    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    account_state_blob::{
+        AccountStateBlob, AccountStateWithProof, AccountStatesRangeWithProof,
+        AccountStatesWithMultiProof,
+    },
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     epoch_change::EpochChangeProof,
     epoch_state::EpochState,
@@ -16,17 +19,21 @@ use diem_types::{
     move_resource::MoveStorage,
     proof::{
         definition::LeafCount, AccumulatorConsistencyProof, SparseMerkleProof,
-        TransactionAccumulatorSummary,
+        SparseMerkleRangeProof, TransactionAccumulatorSummary,
     },
     protocol_spec::ProtocolSpec,
     state_proof::StateProof,
     transaction::{
-        AccountTransactionsWithProof, TransactionInfo, TransactionListWithProof,
-        TransactionToCommit, TransactionWithProof, Version,
+        AccountTransactionsWithProof, TransactionAndOutputListWithProof, TransactionInfo,
+        TransactionListWithProof, TransactionToCommit, TransactionWithProof, Version,
     },
 };
 use itertools::Itertools;
-use move_core_types::resolver::{ModuleResolver, ResourceResolver};
+use move_core_types::{
+    identifier::IdentStr,
+    language_storage::{ModuleId, TypeTag},
+    resolver::{ModuleResolver, ResourceResolver},
+};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
@@ -161,6 +168,16 @@ impl From<diem_secure_net::Error> for Error {
     }
 }
 
+/// Error of the `DbReader` methods reading ledger history which has been pruned, it is told apart
+/// from other errors with `anyhow::Error::downcast_ref`.
+#[derive(Debug, Error)]
+#[error("{what} has been pruned, first available version is {first_available_version}.")]
+pub struct LedgerHistoryPruned {
+    /// The pruned history which was requested, e.g. "Transaction at version 1".
+    pub what: String,
+    pub first_available_version: Version,
+}
+
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub enum Order {
     Ascending,
@@ -191,6 +208,40 @@ pub trait DbReader<PS: ProtocolSpec>: Send + Sync {
         fetch_events: bool,
     ) -> Result<TransactionListWithProof<PS::TransactionInfo>>;
 
+    /// See [`DiemDB::get_transaction_outputs`].
+    ///
+    /// [`DiemDB::get_transaction_outputs`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_transaction_outputs
+    fn get_transaction_outputs(
+        &self,
+        _start_version: Version,
+        _batch_size: u64,
+        _ledger_version: Version,
+    ) -> Result<TransactionAndOutputListWithProof<PS::TransactionInfo>> {
+        unimplemented!()
+    }
+
+    /// Returns the transaction with the given `hash` along with its proof against
+    /// `ledger_version`, or `None` if no such transaction exists in that view of the ledger.
+    fn get_transaction_by_hash(
+        &self,
+        _hash: HashValue,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<Option<TransactionWithProof<PS::TransactionInfo>>> {
+        unimplemented!()
+    }
+
+    /// Returns the transaction at `version` along with its proof against `ledger_version`.
+    fn get_transaction_by_version(
+        &self,
+        _version: Version,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<TransactionWithProof<PS::TransactionInfo>> {
+        unimplemented!()
+    }
+
     /// Returns events by given event key
     fn get_events(
         &self,
@@ -288,6 +339,33 @@ pub trait DbReader<PS: ProtocolSpec>: Send + Sync {
         ledger_version: Version,
     ) -> Result<AccountTransactionsWithProof<PS::TransactionInfo>>;
 
+    /// Returns the versions of at most `limit` transactions emitting events of `type_tag`, in
+    /// ascending order starting from `start_version`. Will ignore transactions with
+    /// `txn.version > ledger_version`. Fails if the index is not enabled.
+    fn get_transaction_versions_by_event_type(
+        &self,
+        _type_tag: &TypeTag,
+        _start_version: Version,
+        _limit: u64,
+        _ledger_version: Version,
+    ) -> Result<Vec<Version>> {
+        unimplemented!()
+    }
+
+    /// Returns the versions of at most `limit` user transactions calling the script function
+    /// `module::function`, in ascending order starting from `start_version`. Will ignore
+    /// transactions with `txn.version > ledger_version`. Fails if the index is not enabled.
+    fn get_transaction_versions_by_script_function(
+        &self,
+        _module: &ModuleId,
+        _function: &IdentStr,
+        _start_version: Version,
+        _limit: u64,
+        _ledger_version: Version,
+    ) -> Result<Vec<Version>> {
+        unimplemented!()
+    }
+
     /// Returns proof of new state for a given ledger info with signatures relative to version known
     /// to client
     fn get_state_proof_with_ledger_info(
@@ -308,6 +386,17 @@ pub trait DbReader<PS: ProtocolSpec>: Send + Sync {
         ledger_version: Version,
     ) -> Result<AccountStateWithProof<PS::TransactionInfo>>;
 
+    /// Like `get_account_state_with_proof`, but returns the states of multiple distinct accounts,
+    /// in the order of `addresses`, with one proof for all of them.
+    fn get_account_states_with_multiproof(
+        &self,
+        _addresses: Vec<AccountAddress>,
+        _version: Version,
+        _ledger_version: Version,
+    ) -> Result<AccountStatesWithMultiProof<PS::TransactionInfo>> {
+        unimplemented!()
+    }
+
     // Gets an account state by account address, out of the ledger state indicated by the state
     // Merkle tree root with a sparse merkle proof proving state tree root.
     // See [`DiemDB::get_account_state_with_proof_by_version`].
@@ -325,6 +414,29 @@ pub trait DbReader<PS: ProtocolSpec>: Send + Sync {
         SparseMerkleProof<AccountStateBlob>,
     )>;
 
+    /// Returns at most `limit` account states at `version` in ascending order of the account keys,
+    /// starting from `start_key`, with the proof of the range ending with the last one.
+    fn get_account_states_range(
+        &self,
+        _version: Version,
+        _start_key: HashValue,
+        _limit: u64,
+    ) -> Result<AccountStatesRangeWithProof> {
+        unimplemented!()
+    }
+
+    /// Returns the least version whose account states can still be read. States of versions before
+    /// it have been pruned, or are about to be pruned.
+    fn get_least_readable_state_version(&self) -> Result<Version> {
+        unimplemented!()
+    }
+
+    /// Returns the first version whose transaction, transaction info and events can still be read.
+    /// Ledger history of versions before it has been pruned, or is about to be pruned.
+    fn get_first_available_ledger_version(&self) -> Result<Version> {
+        unimplemented!()
+    }
+
     /// See [`DiemDB::get_latest_state_root`].
     ///
     /// [`DiemDB::get_latest_state_root`]:
@@ -465,6 +577,50 @@ pub trait DbWriter<PS: ProtocolSpec>: Send + Sync {
         first_version: Version,
         ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
     ) -> Result<()>;
+
+    /// Returns a receiver restoring the account states at `version` chunk by chunk, as done when
+    /// bootstrapping from a state snapshot. It continues from the accounts already restored by a
+    /// previous receiver for the same version, if any.
+    fn get_state_snapshot_receiver(
+        &self,
+        _version: Version,
+        _expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        unimplemented!()
+    }
+
+    /// Makes the DB start from the state snapshot restored at `version`, without any of the ledger
+    /// history before it. `transaction_list_with_proof` holds the transaction at `version` (with
+    /// its events) and proves it against the last of `ledger_infos`, which is at `version`. The
+    /// other ledger infos are the epoch ending ones before it.
+    fn finalize_state_snapshot(
+        &self,
+        _version: Version,
+        _transaction_list_with_proof: TransactionListWithProof<PS::TransactionInfo>,
+        _ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        unimplemented!()
+    }
+}
+
+/// Restores the account states of a state snapshot, see [`DbWriter::get_state_snapshot_receiver`].
+pub trait StateSnapshotReceiver: Send {
+    /// Adds a chunk of account states, which must follow the previously added ones in the order of
+    /// the account keys. Fails, without persisting anything, if `proof` doesn't prove the accounts
+    /// added so far against the expected root hash.
+    ///
+    /// A receiver that failed to add a chunk must not be used anymore.
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()>;
+
+    /// Returns the key of the last account restored so far.
+    fn previous_key(&self) -> Option<HashValue>;
+
+    /// Persists the rest of the tree. Must be called after all account states are added.
+    fn finish_box(self: Box<Self>) -> Result<()>;
 }
 
 pub trait MoveDbReader<PS: ProtocolSpec>:
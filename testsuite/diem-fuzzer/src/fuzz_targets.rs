@@ -40,6 +40,7 @@ static ALL_TARGETS: Lazy<BTreeMap<&'static str, Box<dyn FuzzTargetImpl>>> = Lazy
         Box::new(json_rpc_service::JsonRpcGetCurrenciesRequest::default()),
         Box::new(json_rpc_service::JsonRpcGetStateProofRequest::default()),
         Box::new(json_rpc_service::JsonRpcGetAccountStateWithProofRequest::default()),
+        Box::new(json_rpc_service::JsonRpcGetAccountStatesWithMultiproofRequest::default()),
         Box::new(json_rpc_service::JsonRpcGetNetworkStatusRequest::default()),
         // Mempool
         Box::new(mempool::MempoolIncomingTransactions::default()),
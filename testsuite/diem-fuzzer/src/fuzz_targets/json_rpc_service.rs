@@ -184,6 +184,23 @@ impl FuzzTargetImpl for JsonRpcGetAccountStateWithProofRequest {
     }
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct JsonRpcGetAccountStatesWithMultiproofRequest;
+
+impl FuzzTargetImpl for JsonRpcGetAccountStatesWithMultiproofRequest {
+    fn description(&self) -> &'static str {
+        "JSON RPC get_account_states_with_multiproof request"
+    }
+
+    fn generate(&self, _idx: usize, _gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(gen_request_params!([[ADDRESS], 0, 1]))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        method_fuzzer(data, "get_account_states_with_multiproof");
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct JsonRpcGetNetworkStatusRequest;
 
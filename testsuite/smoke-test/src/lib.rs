@@ -31,6 +31,8 @@ mod release_flow;
 mod state_sync;
 #[cfg(test)]
 mod storage;
+#[cfg(test)]
+mod storage_service;
 
 #[cfg(test)]
 mod smoke_test_environment;
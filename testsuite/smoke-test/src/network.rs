@@ -261,7 +261,7 @@ fn check_endpoint(
     result.is_ok()
 }
 
-fn network_address(node_config: &NodeConfig, network_id: &NetworkId) -> NetworkAddress {
+pub(crate) fn network_address(node_config: &NodeConfig, network_id: &NetworkId) -> NetworkAddress {
     let network = network(node_config, network_id);
 
     let port = network
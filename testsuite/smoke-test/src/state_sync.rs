@@ -8,6 +8,7 @@ use crate::{
         assert_balance, create_and_fund_account, diem_swarm_utils::insert_waypoint, transfer_coins,
     },
 };
+use diem_config::config::{BootstrappingMode, NodeConfig};
 use diem_types::{
     account_address::AccountAddress,
     epoch_change::EpochChangeProof,
@@ -21,6 +22,72 @@ use std::{
     time::{Duration, Instant},
 };
 
+#[test]
+fn test_full_node_fast_sync() {
+    // Start a validator and commit a few transactions
+    let mut swarm = new_local_swarm(1);
+    let validator_peer_id = swarm.validators().next().unwrap().peer_id();
+    let transaction_factory = swarm.chain_info().transaction_factory();
+    let mut account_0 = create_and_fund_account(&mut swarm, 100);
+    let account_1 = create_and_fund_account(&mut swarm, 10);
+    let validator_client = swarm
+        .validator(validator_peer_id)
+        .unwrap()
+        .json_rpc_client();
+    transfer_coins(
+        &validator_client,
+        &transaction_factory,
+        &mut account_0,
+        &account_1,
+        10,
+    );
+
+    // Start a full node that bootstraps by downloading the latest account states
+    let version = swarm.versions().max().unwrap();
+    let mut vfn_config = NodeConfig::default_for_validator_full_node();
+    vfn_config.state_sync.bootstrapping_mode = BootstrappingMode::DownloadLatestAccountStates;
+    let vfn_peer_id = swarm
+        .add_validator_fullnode(&version, vfn_config, validator_peer_id)
+        .unwrap();
+    swarm
+        .validator_mut(validator_peer_id)
+        .unwrap()
+        .wait_until_healthy(Instant::now() + Duration::from_secs(10))
+        .unwrap();
+    swarm
+        .fullnode_mut(vfn_peer_id)
+        .unwrap()
+        .wait_until_healthy(Instant::now() + Duration::from_secs(60))
+        .unwrap();
+    swarm
+        .wait_for_all_nodes_to_catchup(Instant::now() + Duration::from_secs(30))
+        .unwrap();
+
+    // Verify the full node holds the latest state, but none of the history before it
+    let vfn_client = swarm.fullnode(vfn_peer_id).unwrap().json_rpc_client();
+    assert_balance(&vfn_client, &account_0, 90);
+    assert_balance(&vfn_client, &account_1, 20);
+    assert!(vfn_client.get_transactions(1, 1, false).is_err());
+
+    // Verify the full node keeps syncing new transactions
+    let validator_client = swarm
+        .validator(validator_peer_id)
+        .unwrap()
+        .json_rpc_client();
+    transfer_coins(
+        &validator_client,
+        &transaction_factory,
+        &mut account_0,
+        &account_1,
+        1,
+    );
+    swarm
+        .wait_for_all_nodes_to_catchup(Instant::now() + Duration::from_secs(30))
+        .unwrap();
+    assert_balance(&vfn_client, &account_0, 89);
+    assert_balance(&vfn_client, &account_1, 21);
+}
+
 #[test]
 fn test_basic_state_synchronization() {
     // - Start a swarm of 4 nodes (3 nodes forming a QC).
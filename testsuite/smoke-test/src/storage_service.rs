@@ -0,0 +1,154 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    network::network_address, smoke_test_environment::new_local_swarm,
+    test_utils::create_and_fund_account,
+};
+use diem_config::{
+    config::{DiscoveryMethod, NodeConfig, Peer, PeerRole},
+    network_id::NetworkId,
+};
+use diem_time_service::TimeService;
+use diem_types::{chain_id::ChainId, network_address::NetworkAddress};
+use forge::{NodeExt, Swarm};
+use futures::StreamExt;
+use network::{application::storage::PeerMetadataStorage, protocols::network::Event};
+use network_builder::builder::NetworkBuilder;
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use storage_service_server::network::{
+    network_endpoint_config, StorageServiceNetworkEvents, StorageServiceNetworkSender,
+};
+use storage_service_types::{
+    StorageServiceRequest, StorageServiceResponse, TransactionsWithProofRequest,
+};
+use tokio::runtime::Runtime;
+
+const CHUNK_SIZE: u64 = 3;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn test_fetch_chunks_from_swarm() {
+    // Start a validator with a full node, and commit a few transactions
+    let mut swarm = new_local_swarm(1);
+    let version = swarm.versions().max().unwrap();
+    let validator_peer_id = swarm.validators().next().unwrap().peer_id();
+    let vfn_peer_id = swarm
+        .add_validator_fullnode(
+            &version,
+            NodeConfig::default_for_validator_full_node(),
+            validator_peer_id,
+        )
+        .unwrap();
+    for _ in 0..3 {
+        create_and_fund_account(&mut swarm, 10);
+    }
+    swarm
+        .fullnode_mut(vfn_peer_id)
+        .unwrap()
+        .wait_until_healthy(Instant::now() + Duration::from_secs(10))
+        .unwrap();
+
+    // Connect to the public network of the full node
+    let runtime = Runtime::new().unwrap();
+    let chain_id = swarm.chain_info().chain_id();
+    let vfn_config = swarm.fullnode(vfn_peer_id).unwrap().config().clone();
+    let (mut sender, mut events) = start_client_network(&runtime, chain_id, &vfn_config);
+
+    runtime.block_on(async move {
+        let vfn = loop {
+            if let Some(Event::NewPeer(metadata)) = events.next().await {
+                break metadata.remote_peer_id;
+            }
+        };
+
+        // Fetch the storage server summary
+        let summary = match sender
+            .send_request(
+                vfn,
+                StorageServiceRequest::GetStorageServerSummary,
+                REQUEST_TIMEOUT,
+            )
+            .await
+            .unwrap()
+        {
+            StorageServiceResponse::StorageServerSummary(summary) => summary,
+            response => panic!("Expected a storage server summary but got: {:?}", response),
+        };
+        let ledger_info = summary
+            .data_summary
+            .synced_ledger_info
+            .ledger_info()
+            .clone();
+        assert!(ledger_info.version() > 0);
+
+        // Fetch all transactions chunk by chunk, verifying each chunk
+        let mut start_version = 0;
+        while start_version <= ledger_info.version() {
+            let request =
+                StorageServiceRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+                    proof_version: ledger_info.version(),
+                    start_version,
+                    expected_num_transactions: CHUNK_SIZE,
+                    include_events: true,
+                });
+            let transaction_list = match sender
+                .send_request(vfn, request, REQUEST_TIMEOUT)
+                .await
+                .unwrap()
+            {
+                StorageServiceResponse::TransactionsWithProof(transaction_list) => transaction_list,
+                response => panic!("Expected transactions with proof but got: {:?}", response),
+            };
+            assert!(!transaction_list.transactions.is_empty());
+            transaction_list
+                .verify(&ledger_info, Some(start_version))
+                .unwrap();
+            start_version += transaction_list.transactions.len() as u64;
+        }
+    });
+}
+
+/// Starts a public full node network (without a node) that only speaks the
+/// storage service protocol and connects to the public network of `vfn_config`.
+fn start_client_network(
+    runtime: &Runtime,
+    chain_id: ChainId,
+    vfn_config: &NodeConfig,
+) -> (StorageServiceNetworkSender, StorageServiceNetworkEvents) {
+    let vfn_network = vfn_config
+        .full_node_networks
+        .iter()
+        .find(|network| network.network_id == NetworkId::Public)
+        .unwrap();
+    let vfn_peer = Peer::from_addrs(
+        PeerRole::Upstream,
+        vec![network_address(vfn_config, &NetworkId::Public)],
+    );
+
+    let config = NodeConfig::default_for_public_full_node();
+    let mut network_config = config.full_node_networks[0].clone();
+    network_config.listen_address = NetworkAddress::from_str("/ip4/127.0.0.1/tcp/0").unwrap();
+    network_config.discovery_method = DiscoveryMethod::None;
+    network_config.seeds = vec![(vfn_network.peer_id(), vfn_peer)]
+        .into_iter()
+        .collect();
+
+    let _enter = runtime.enter();
+    let mut network_builder = NetworkBuilder::create(
+        chain_id,
+        config.base.role,
+        &network_config,
+        TimeService::real(),
+        None,
+        PeerMetadataStorage::new(&[network_config.network_id]),
+    );
+    let network_handles = network_builder
+        .add_protocol_handler(network_endpoint_config(config.state_sync.storage_service));
+    network_builder.build(runtime.handle().clone());
+    network_builder.start();
+    network_handles
+}
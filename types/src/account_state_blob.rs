@@ -6,7 +6,7 @@ use crate::{
     account_config::{AccountResource, BalanceResource},
     account_state::AccountState,
     ledger_info::LedgerInfo,
-    proof::{AccountStateProof, SparseMerkleRangeProof},
+    proof::{AccountStateProof, AccountStatesMultiProof, SparseMerkleRangeProof},
     transaction::{TransactionInfoTrait, Version},
 };
 use anyhow::{anyhow, ensure, Error, Result};
@@ -188,10 +188,69 @@ impl<T: TransactionInfoTrait> AccountStateWithProof<T> {
     }
 }
 
+/// The states of multiple accounts seen at a version, with one proof for all of them.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct AccountStatesWithMultiProof<T> {
+    /// The transaction version at which these account states are seen.
+    pub version: Version,
+    /// Blob values representing the account states, in the order of the queried accounts. `None`
+    /// means the account does not exist.
+    pub blobs: Vec<Option<AccountStateBlob>>,
+    /// The proof the client can use to authenticate the values.
+    pub proof: AccountStatesMultiProof<T>,
+}
+
+impl<T: TransactionInfoTrait> AccountStatesWithMultiProof<T> {
+    /// Constructor.
+    pub fn new(
+        version: Version,
+        blobs: Vec<Option<AccountStateBlob>>,
+        proof: AccountStatesMultiProof<T>,
+    ) -> Self {
+        Self {
+            version,
+            blobs,
+            proof,
+        }
+    }
+
+    /// Verifies the account state blobs with the proof, both carried by `self`, as the states of
+    /// the distinct accounts `addresses` in that order. See `AccountStateWithProof::verify`.
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        version: Version,
+        addresses: &[AccountAddress],
+    ) -> Result<()> {
+        ensure!(
+            self.version == version,
+            "State version ({}) is not expected ({}).",
+            self.version,
+            version,
+        );
+        ensure!(
+            self.blobs.len() == addresses.len(),
+            "Number of account states ({}) is not expected ({}).",
+            self.blobs.len(),
+            addresses.len(),
+        );
+
+        let mut account_states = addresses
+            .iter()
+            .map(|address| address.hash())
+            .zip(self.blobs.iter().map(Option::as_ref))
+            .collect::<Vec<_>>();
+        account_states.sort_by_key(|(key, _blob)| *key);
+        self.proof.verify(ledger_info, version, &account_states)
+    }
+}
+
 pub mod default_protocol {
     use crate::transaction::TransactionInfo;
 
     pub type AccountStateWithProof = super::AccountStateWithProof<TransactionInfo>;
+    pub type AccountStatesWithMultiProof = super::AccountStatesWithMultiProof<TransactionInfo>;
 }
 
 /// TODO(joshlind): add a proof implementation (e.g., verify()) and unit tests
@@ -200,23 +259,34 @@ pub mod default_protocol {
 /// A single chunk of all account states at a specific version.
 /// Note: this is similar to `StateSnapshotChunk` but all data is included
 /// in the struct itself and not behind pointers/handles to file locations.
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// Chunks are keyed by account key (not by the position of the accounts in
+/// the tree), as storage doesn't index accounts by position.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct AccountStatesChunkWithProof {
-    pub first_index: u64,
-    // The first account index in chunk
-    pub last_index: u64,
-    // The last account index in chunk
-    pub first_key: HashValue,
-    // The first account key in chunk
-    pub last_key: HashValue,
-    // The last account key in chunk
+    pub start_key: HashValue,
+    // The account key the chunk starts from (inclusive)
     pub account_blobs: Vec<(HashValue, AccountStateBlob)>,
-    // The account blobs in the chunk
-    pub proof: SparseMerkleRangeProof, // The proof to ensure the chunk is in the account states
+    // The account blobs in the chunk, in ascending order of their keys
+    pub proof: Option<SparseMerkleRangeProof>, // The proof of the chunk (None iff it is empty)
+}
+
+/// A page of the account states at a version, in ascending order of the account keys (the hashes
+/// of the account addresses), starting from a given key.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AccountStatesRangeWithProof {
+    pub account_blobs: Vec<(HashValue, AccountStateBlob)>,
+    /// Proves all the account states up to the last one in `account_blobs`, i.e. this page and the
+    /// ones before it, against the state root hash of the version. `None` if the page is empty.
+    pub proof: Option<SparseMerkleRangeProof>,
+    /// The key the next page starts from, `None` if this is the last page.
+    pub next_key: Option<HashValue>,
 }
 #[cfg(test)]
 mod tests {
-    use super::{default_protocol::AccountStateWithProof, *};
+    use super::{
+        default_protocol::{AccountStateWithProof, AccountStatesWithMultiProof},
+        *,
+    };
     use bcs::test_helpers::assert_canonical_encode_decode;
     use proptest::collection::vec;
 
@@ -241,6 +311,11 @@ mod tests {
         fn account_state_with_proof_bcs_roundtrip(account_state_with_proof in any::<AccountStateWithProof>()) {
             assert_canonical_encode_decode(account_state_with_proof);
         }
+
+        #[test]
+        fn account_states_with_multiproof_bcs_roundtrip(account_states_with_multiproof in any::<AccountStatesWithMultiProof>()) {
+            assert_canonical_encode_decode(account_states_with_multiproof);
+        }
     }
 
     #[test]
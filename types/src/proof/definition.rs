@@ -265,6 +265,258 @@ where
     }
 }
 
+/// A proof that can be used to authenticate multiple elements in a Sparse Merkle Tree given a
+/// trusted root hash at once. Compared to a `SparseMerkleProof` for each element, siblings on the
+/// path of another element are left out because they can be computed, and siblings shared by
+/// multiple elements are included only once.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SparseMerkleMultiProof<V> {
+    /// For each of the keys in ascending order, the leaf found on its path like
+    /// `SparseMerkleProof::leaf`, and the depth of it (or the empty subtree if `None`) in the tree.
+    leaves: Vec<(Option<SparseMerkleLeafNode>, u16)>,
+
+    /// The siblings that can not be computed from the leaves, ordered from the left to the right
+    /// in the tree.
+    siblings: Vec<HashValue>,
+
+    phantom: PhantomData<V>,
+}
+
+impl<V> SparseMerkleMultiProof<V>
+where
+    V: CryptoHash,
+{
+    /// Constructs a new `SparseMerkleMultiProof` using leaves and a list of siblings.
+    pub fn new(leaves: Vec<(Option<SparseMerkleLeafNode>, u16)>, siblings: Vec<HashValue>) -> Self {
+        Self {
+            leaves,
+            siblings,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Combines the `SparseMerkleProof`s of `keys` in ascending order, all out of the same tree,
+    /// into one `SparseMerkleMultiProof`.
+    pub fn from_proofs(keys: &[HashValue], proofs: &[SparseMerkleProof<V>]) -> Result<Self> {
+        ensure!(!keys.is_empty(), "No keys to prove.");
+        ensure!(
+            keys.len() == proofs.len(),
+            "Number of keys ({}) and number of proofs ({}) do not match.",
+            keys.len(),
+            proofs.len(),
+        );
+        ensure!(
+            keys.windows(2).all(|w| w[0] < w[1]),
+            "Keys are not in strictly ascending order.",
+        );
+        ensure!(
+            proofs
+                .iter()
+                .all(|proof| proof.siblings.len() <= HashValue::LENGTH_IN_BITS),
+            "Sparse Merkle Tree proof has more than {} siblings.",
+            HashValue::LENGTH_IN_BITS,
+        );
+
+        let mut siblings = Vec::new();
+        Self::collect_siblings(keys, proofs, 0, &mut siblings)?;
+        let leaves = proofs
+            .iter()
+            .map(|proof| (proof.leaf, proof.siblings.len() as u16))
+            .collect();
+        Ok(Self::new(leaves, siblings))
+    }
+
+    /// Collects the siblings needed to compute the hash of the subtree at `depth`, which all of
+    /// `keys` fall in, from their proofs.
+    fn collect_siblings(
+        keys: &[HashValue],
+        proofs: &[SparseMerkleProof<V>],
+        depth: usize,
+        siblings: &mut Vec<HashValue>,
+    ) -> Result<()> {
+        if proofs[0].siblings.len() == depth {
+            ensure!(
+                proofs
+                    .iter()
+                    .all(|proof| proof.siblings.len() == depth && proof.leaf == proofs[0].leaf),
+                "Proofs disagree on the subtree at depth {} containing key {:x}.",
+                depth,
+                keys[0],
+            );
+            return Ok(());
+        }
+        ensure!(
+            proofs.iter().all(|proof| proof.siblings.len() > depth),
+            "Proofs disagree on the subtree at depth {} containing key {:x}.",
+            depth,
+            keys[0],
+        );
+
+        // Siblings in a `SparseMerkleProof` are ordered from the bottom level to the root level.
+        let sibling_at_depth =
+            |proof: &SparseMerkleProof<V>| proof.siblings[proof.siblings.len() - 1 - depth];
+        let num_left = keys.iter().take_while(|key| !key.bit(depth)).count();
+        if num_left == 0 {
+            siblings.push(sibling_at_depth(&proofs[0]));
+        } else {
+            Self::collect_siblings(&keys[..num_left], &proofs[..num_left], depth + 1, siblings)?;
+        }
+        if num_left == keys.len() {
+            siblings.push(sibling_at_depth(&proofs[0]));
+        } else {
+            Self::collect_siblings(&keys[num_left..], &proofs[num_left..], depth + 1, siblings)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the leaves and their depths in this proof.
+    pub fn leaves(&self) -> &[(Option<SparseMerkleLeafNode>, u16)] {
+        &self.leaves
+    }
+
+    /// Returns the list of siblings in this proof.
+    pub fn siblings(&self) -> &[HashValue] {
+        &self.siblings
+    }
+
+    /// Verifies the elements in `elements`, in ascending order of the keys, against the Sparse
+    /// Merkle Tree with root hash `expected_root_hash`. Like `SparseMerkleProof::verify`, an
+    /// element with a value is verified to exist in the tree, and one without to not exist.
+    pub fn verify(
+        &self,
+        expected_root_hash: HashValue,
+        elements: &[(HashValue, Option<&V>)],
+    ) -> Result<()> {
+        ensure!(!elements.is_empty(), "No elements to verify.");
+        ensure!(
+            elements.len() == self.leaves.len(),
+            "Number of elements ({}) and number of leaves in proof ({}) do not match.",
+            elements.len(),
+            self.leaves.len(),
+        );
+        ensure!(
+            elements.windows(2).all(|w| w[0].0 < w[1].0),
+            "Keys are not in strictly ascending order.",
+        );
+
+        for ((element_key, element_value), (leaf, depth)) in elements.iter().zip(&self.leaves) {
+            ensure!(
+                *depth as usize <= HashValue::LENGTH_IN_BITS,
+                "Leaf of key {:x} is deeper than {}.",
+                element_key,
+                HashValue::LENGTH_IN_BITS,
+            );
+            match (element_value, leaf) {
+                (Some(value), Some(leaf)) => {
+                    // Inclusion, see `SparseMerkleProof::verify`.
+                    ensure!(
+                        *element_key == leaf.key,
+                        "Keys do not match. Key in proof: {:x}. Expected key: {:x}.",
+                        leaf.key,
+                        element_key
+                    );
+                    let hash = value.hash();
+                    ensure!(
+                        hash == leaf.value_hash,
+                        "Value hashes do not match. Value hash in proof: {:x}. \
+                         Expected value hash: {:x}",
+                        leaf.value_hash,
+                        hash,
+                    );
+                }
+                (Some(_value), None) => bail!(
+                    "Expected inclusion proof of key {:x}. Found non-inclusion proof.",
+                    element_key,
+                ),
+                (None, Some(leaf)) => {
+                    // Non-inclusion with the key falling in the subtree of another single key,
+                    // see `SparseMerkleProof::verify`.
+                    ensure!(
+                        *element_key != leaf.key,
+                        "Expected non-inclusion proof, but key {:x} exists in proof.",
+                        element_key,
+                    );
+                    ensure!(
+                        element_key.common_prefix_bits_len(leaf.key) >= *depth as usize,
+                        "Key {:x} would not have ended up in the subtree where the provided key in \
+                         proof is the only existing key, if it existed. So this is not a valid \
+                         non-inclusion proof.",
+                        element_key,
+                    );
+                }
+                (None, None) => {
+                    // Non-inclusion with the key falling in an empty subtree.
+                }
+            }
+        }
+
+        let keys = elements.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+        let mut siblings = self.siblings.iter();
+        let actual_root_hash = Self::compute_subtree_hash(&keys, &self.leaves, 0, &mut siblings)?;
+        ensure!(
+            siblings.next().is_none(),
+            "{} siblings in proof, more than needed.",
+            self.siblings.len(),
+        );
+        ensure!(
+            actual_root_hash == expected_root_hash,
+            "Root hashes do not match. Actual root hash: {:x}. Expected root hash: {:x}.",
+            actual_root_hash,
+            expected_root_hash,
+        );
+
+        Ok(())
+    }
+
+    /// Computes the hash of the subtree at `depth`, which all of `keys` fall in. Consumes siblings
+    /// in the same order as `collect_siblings` produces them.
+    fn compute_subtree_hash<'a>(
+        keys: &[HashValue],
+        leaves: &[(Option<SparseMerkleLeafNode>, u16)],
+        depth: usize,
+        siblings: &mut impl Iterator<Item = &'a HashValue>,
+    ) -> Result<HashValue> {
+        if leaves[0].1 as usize == depth {
+            ensure!(
+                leaves.iter().all(|leaf| *leaf == leaves[0]),
+                "Proof disagrees on the subtree at depth {} containing key {:x}.",
+                depth,
+                keys[0],
+            );
+            return Ok(leaves[0]
+                .0
+                .map_or(*SPARSE_MERKLE_PLACEHOLDER_HASH, |leaf| leaf.hash()));
+        }
+        ensure!(
+            leaves
+                .iter()
+                .all(|(_, leaf_depth)| *leaf_depth as usize > depth),
+            "Proof disagrees on the subtree at depth {} containing key {:x}.",
+            depth,
+            keys[0],
+        );
+
+        let num_left = keys.iter().take_while(|key| !key.bit(depth)).count();
+        let left_hash = if num_left == 0 {
+            siblings
+                .next()
+                .copied()
+                .ok_or_else(|| format_err!("Not enough siblings in proof."))?
+        } else {
+            Self::compute_subtree_hash(&keys[..num_left], &leaves[..num_left], depth + 1, siblings)?
+        };
+        let right_hash = if num_left == keys.len() {
+            siblings
+                .next()
+                .copied()
+                .ok_or_else(|| format_err!("Not enough siblings in proof."))?
+        } else {
+            Self::compute_subtree_hash(&keys[num_left..], &leaves[num_left..], depth + 1, siblings)?
+        };
+        Ok(SparseMerkleInternalNode::new(left_hash, right_hash).hash())
+    }
+}
+
 /// An in-memory accumulator for storing a summary of the core transaction info
 /// accumulator. It is a summary in the sense that it only stores maximally
 /// frozen subtree nodes rather than storing all leaves and internal nodes.
@@ -767,6 +1019,65 @@ impl<T: TransactionInfoTrait> AccountStateProof<T> {
     }
 }
 
+/// Like `AccountStateProof`, but authenticates the states of multiple accounts with a
+/// `SparseMerkleMultiProof` from the state root.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct AccountStatesMultiProof<T> {
+    transaction_info_with_proof: TransactionInfoWithProof<T>,
+
+    /// The sparse merkle multiproof from state root to the account states.
+    transaction_info_to_accounts_multiproof: SparseMerkleMultiProof<AccountStateBlob>,
+}
+
+impl<T: TransactionInfoTrait> AccountStatesMultiProof<T> {
+    /// Constructs a new `AccountStatesMultiProof` using given `transaction_info_with_proof` and
+    /// `transaction_info_to_accounts_multiproof`.
+    pub fn new(
+        transaction_info_with_proof: TransactionInfoWithProof<T>,
+        transaction_info_to_accounts_multiproof: SparseMerkleMultiProof<AccountStateBlob>,
+    ) -> Self {
+        AccountStatesMultiProof {
+            transaction_info_with_proof,
+            transaction_info_to_accounts_multiproof,
+        }
+    }
+
+    /// Returns the `transaction_info_with_proof` object in this proof.
+    pub fn transaction_info_with_proof(&self) -> &TransactionInfoWithProof<T> {
+        &self.transaction_info_with_proof
+    }
+
+    /// Returns the `transaction_info_to_accounts_multiproof` object in this proof.
+    pub fn transaction_info_to_accounts_multiproof(
+        &self,
+    ) -> &SparseMerkleMultiProof<AccountStateBlob> {
+        &self.transaction_info_to_accounts_multiproof
+    }
+
+    /// Verifies that the states of accounts at version `state_version` are correct using the
+    /// provided proof. `account_states` are pairs of account address hash and account state blob,
+    /// in ascending order of the former. See `AccountStateProof::verify`.
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        state_version: Version,
+        account_states: &[(HashValue, Option<&AccountStateBlob>)],
+    ) -> Result<()> {
+        self.transaction_info_to_accounts_multiproof.verify(
+            self.transaction_info_with_proof
+                .transaction_info
+                .state_root_hash(),
+            account_states,
+        )?;
+
+        self.transaction_info_with_proof
+            .verify(ledger_info, state_version)?;
+
+        Ok(())
+    }
+}
+
 /// The complete proof used to authenticate a contract event. This structure consists of the
 /// `AccumulatorProof` from `LedgerInfo` to `TransactionInfo`, the `TransactionInfo` object and the
 /// `AccumulatorProof` from event accumulator root to the event.
@@ -911,12 +1222,13 @@ pub mod default_protocol {
 
     pub use super::{
         AccumulatorConsistencyProof, AccumulatorExtensionProof, AccumulatorProof,
-        AccumulatorRangeProof, EventAccumulatorProof, SparseMerkleProof, SparseMerkleRangeProof,
-        TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
+        AccumulatorRangeProof, EventAccumulatorProof, SparseMerkleMultiProof, SparseMerkleProof,
+        SparseMerkleRangeProof, TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
         TransactionAccumulatorSummary,
     };
 
     pub type AccountStateProof = super::AccountStateProof<TransactionInfo>;
+    pub type AccountStatesMultiProof = super::AccountStatesMultiProof<TransactionInfo>;
     pub type EventProof = super::EventProof<TransactionInfo>;
     pub type TransactionInfoListWithProof = super::TransactionInfoListWithProof<TransactionInfo>;
     pub type TransactionInfoWithProof = super::TransactionInfoWithProof<TransactionInfo>;
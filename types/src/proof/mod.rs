@@ -29,10 +29,11 @@ use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
 pub use self::definition::{
-    AccountStateProof, AccumulatorConsistencyProof, AccumulatorExtensionProof, AccumulatorProof,
-    AccumulatorRangeProof, EventAccumulatorProof, EventProof, SparseMerkleProof,
-    SparseMerkleRangeProof, TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
-    TransactionAccumulatorSummary, TransactionInfoListWithProof, TransactionInfoWithProof,
+    AccountStateProof, AccountStatesMultiProof, AccumulatorConsistencyProof,
+    AccumulatorExtensionProof, AccumulatorProof, AccumulatorRangeProof, EventAccumulatorProof,
+    EventProof, SparseMerkleMultiProof, SparseMerkleProof, SparseMerkleRangeProof,
+    TransactionAccumulatorProof, TransactionAccumulatorRangeProof, TransactionAccumulatorSummary,
+    TransactionInfoListWithProof, TransactionInfoWithProof,
 };
 
 #[cfg(any(test, feature = "fuzzing"))]
@@ -128,10 +129,10 @@ impl CryptoHash for SparseMerkleLeafNode {
 
 pub mod default_protocol {
     pub use super::definition::default_protocol::{
-        AccountStateProof, AccumulatorConsistencyProof, AccumulatorExtensionProof,
-        AccumulatorProof, AccumulatorRangeProof, EventAccumulatorProof, EventProof,
-        SparseMerkleProof, SparseMerkleRangeProof, TransactionAccumulatorProof,
-        TransactionAccumulatorRangeProof, TransactionAccumulatorSummary,
-        TransactionInfoListWithProof, TransactionInfoWithProof,
+        AccountStateProof, AccountStatesMultiProof, AccumulatorConsistencyProof,
+        AccumulatorExtensionProof, AccumulatorProof, AccumulatorRangeProof, EventAccumulatorProof,
+        EventProof, SparseMerkleMultiProof, SparseMerkleProof, SparseMerkleRangeProof,
+        TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
+        TransactionAccumulatorSummary, TransactionInfoListWithProof, TransactionInfoWithProof,
     };
 }
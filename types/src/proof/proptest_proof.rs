@@ -6,8 +6,8 @@
 
 use crate::proof::{
     definition::MAX_ACCUMULATOR_PROOF_DEPTH, AccumulatorConsistencyProof, AccumulatorProof,
-    AccumulatorRangeProof, SparseMerkleLeafNode, SparseMerkleProof, SparseMerkleRangeProof,
-    TransactionAccumulatorSummary,
+    AccumulatorRangeProof, SparseMerkleLeafNode, SparseMerkleMultiProof, SparseMerkleProof,
+    SparseMerkleRangeProof, TransactionAccumulatorSummary,
 };
 use diem_crypto::{
     hash::{
@@ -103,6 +103,29 @@ where
     }
 }
 
+impl<V> Arbitrary for SparseMerkleMultiProof<V>
+where
+    V: std::fmt::Debug + CryptoHash,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        (
+            vec(
+                (
+                    any::<Option<SparseMerkleLeafNode>>(),
+                    0..=HashValue::LENGTH_IN_BITS as u16,
+                ),
+                1..10,
+            ),
+            vec(arb_sparse_merkle_sibling(), 0..100),
+        )
+            .prop_map(|(leaves, siblings)| SparseMerkleMultiProof::new(leaves, siblings))
+            .boxed()
+    }
+}
+
 impl Arbitrary for AccumulatorConsistencyProof {
     type Parameters = ();
     type Strategy = BoxedStrategy<Self>;
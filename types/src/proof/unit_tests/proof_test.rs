@@ -23,9 +23,9 @@ use crate::{
         TransactionAccumulatorProof, TransactionInfoWithProof,
     },
     transaction::{
-        RawTransaction, Script, Transaction, TransactionInfo, TransactionInfoTrait,
-        TransactionListWithProof, TransactionOutput, TransactionOutputListWithProof,
-        TransactionStatus,
+        RawTransaction, Script, Transaction, TransactionAndOutputListWithProof, TransactionInfo,
+        TransactionInfoTrait, TransactionListWithProof, TransactionOutput,
+        TransactionOutputListWithProof, TransactionStatus,
     },
     vm_status::KeptVMStatus,
     write_set::WriteSet,
@@ -41,6 +41,7 @@ use diem_crypto::{
 use move_core_types::language_storage::TypeTag;
 
 type SparseMerkleProof = crate::proof::SparseMerkleProof<AccountStateBlob>;
+type SparseMerkleMultiProof = crate::proof::SparseMerkleMultiProof<AccountStateBlob>;
 
 #[test]
 fn test_verify_empty_accumulator() {
@@ -261,6 +262,123 @@ fn test_verify_three_element_sparse_merkle() {
     }
 }
 
+#[test]
+fn test_verify_sparse_merkle_multiproof() {
+    // Same tree as in `test_verify_three_element_sparse_merkle`.
+    //            root
+    //           /    \
+    //          a      default
+    //         / \
+    //     key1   b
+    //           / \
+    //       key2   key3
+    let key1 = b"hello".test_only_hash();
+    let key2 = b"world".test_only_hash();
+    let key3 = b"!".test_only_hash();
+    let non_existing_key = b"def".test_only_hash();
+    assert_eq!(non_existing_key[0], 0b1000_1110);
+
+    let blob1 = AccountStateBlob::from(b"1".to_vec());
+    let blob2 = AccountStateBlob::from(b"2".to_vec());
+    let blob3 = AccountStateBlob::from(b"3".to_vec());
+
+    let leaf1 = SparseMerkleLeafNode::new(key1, blob1.hash());
+    let leaf1_hash = leaf1.hash();
+    let leaf2_hash = SparseMerkleLeafNode::new(key2, blob2.hash()).hash();
+    let leaf3 = SparseMerkleLeafNode::new(key3, blob3.hash());
+    let leaf3_hash = leaf3.hash();
+    let internal_b_hash = SparseMerkleInternalNode::new(leaf2_hash, leaf3_hash).hash();
+    let internal_a_hash = SparseMerkleInternalNode::new(leaf1_hash, internal_b_hash).hash();
+    let root_hash =
+        SparseMerkleInternalNode::new(internal_a_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH).hash();
+
+    let keys = [key1, key3, non_existing_key];
+    let proofs = [
+        SparseMerkleProof::new(
+            Some(leaf1),
+            vec![internal_b_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH],
+        ),
+        SparseMerkleProof::new(
+            Some(leaf3),
+            vec![leaf2_hash, leaf1_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH],
+        ),
+        SparseMerkleProof::new(None, vec![internal_a_hash]),
+    ];
+    let multiproof = SparseMerkleMultiProof::from_proofs(&keys, &proofs).unwrap();
+    // Only `key2` is needed, everything else is computed.
+    assert_eq!(
+        multiproof,
+        SparseMerkleMultiProof::new(
+            vec![(Some(leaf1), 2), (Some(leaf3), 3), (None, 1)],
+            vec![leaf2_hash],
+        )
+    );
+
+    let elements = [
+        (key1, Some(&blob1)),
+        (key3, Some(&blob3)),
+        (non_existing_key, None),
+    ];
+    assert!(multiproof.verify(root_hash, &elements).is_ok());
+
+    // Wrong values.
+    assert!(multiproof
+        .verify(
+            root_hash,
+            &[
+                (key1, Some(&blob1)),
+                (key3, Some(&blob2)),
+                (non_existing_key, None)
+            ]
+        )
+        .is_err());
+    assert!(multiproof
+        .verify(
+            root_hash,
+            &[(key1, None), (key3, Some(&blob3)), (non_existing_key, None)]
+        )
+        .is_err());
+    // Keys out of order.
+    assert!(multiproof
+        .verify(
+            root_hash,
+            &[
+                (key3, Some(&blob3)),
+                (key1, Some(&blob1)),
+                (non_existing_key, None)
+            ]
+        )
+        .is_err());
+    // Fewer elements than proven.
+    assert!(multiproof.verify(root_hash, &elements[..2]).is_err());
+    // Wrong root hash.
+    assert!(multiproof.verify(internal_a_hash, &elements).is_err());
+    // Too few or too many siblings.
+    assert!(
+        SparseMerkleMultiProof::new(multiproof.leaves().to_vec(), vec![])
+            .verify(root_hash, &elements)
+            .is_err()
+    );
+    assert!(SparseMerkleMultiProof::new(
+        multiproof.leaves().to_vec(),
+        vec![leaf2_hash, leaf2_hash]
+    )
+    .verify(root_hash, &elements)
+    .is_err());
+    // Leaf deeper than where it is.
+    assert!(SparseMerkleMultiProof::new(
+        vec![(Some(leaf1), 3), (Some(leaf3), 3), (None, 1)],
+        vec![leaf2_hash],
+    )
+    .verify(root_hash, &elements)
+    .is_err());
+
+    // Single key, same as the `SparseMerkleProof`.
+    let multiproof = SparseMerkleMultiProof::from_proofs(&keys[..1], &proofs[..1]).unwrap();
+    assert_eq!(multiproof.siblings(), proofs[0].siblings());
+    assert!(multiproof.verify(root_hash, &elements[..1]).is_ok());
+}
+
 #[test]
 fn test_verify_transaction() {
     //            root
@@ -669,6 +787,80 @@ fn test_transaction_output_list_with_proof() {
         .unwrap();
 }
 
+#[test]
+fn test_transaction_and_output_list_with_proof() {
+    // Create test event, transaction and transaction output
+    let event = create_event();
+    let transaction = Transaction::BlockMetadata(BlockMetadata::new(
+        HashValue::random(),
+        0,
+        0,
+        vec![],
+        AccountAddress::random(),
+    ));
+    let transaction_output = TransactionOutput::new(
+        WriteSet::default(),
+        vec![event.clone()],
+        0,
+        TransactionStatus::Retry,
+    );
+
+    // Create transaction output list with proof
+    let transaction_info_list_proof = create_single_transaction_info_proof(None, None);
+    let transaction_output_list_proof = TransactionAndOutputListWithProof::new(
+        vec![(transaction.clone(), transaction_output.clone())],
+        Some(1),
+        transaction_info_list_proof.clone(),
+    );
+
+    // Verify first transaction version must match the proof
+    let empty_ledger_info = LedgerInfo::new(BlockInfo::empty(), HashValue::zero());
+    transaction_output_list_proof
+        .verify(&empty_ledger_info, None)
+        .unwrap_err();
+
+    // Verify mismatch between hash of transaction and hash stored in transaction info
+    let expected_info_hash = transaction_info_list_proof.transaction_infos[0].hash();
+    let block_info = BlockInfo::new(0, 0, HashValue::random(), expected_info_hash, 0, 0, None);
+    let ledger_info = LedgerInfo::new(block_info, HashValue::zero());
+    transaction_output_list_proof
+        .verify(&ledger_info, Some(1))
+        .unwrap_err();
+
+    // Verify correct transaction and info hashes but event verification now fails (event hash
+    // mismatch)
+    let transaction_info_list_proof =
+        create_single_transaction_info_proof(Some(transaction.hash()), None);
+    let transaction_output_list_proof = TransactionAndOutputListWithProof::new(
+        vec![(transaction.clone(), transaction_output.clone())],
+        Some(1),
+        transaction_info_list_proof.clone(),
+    );
+    let expected_info_hash = transaction_info_list_proof.transaction_infos[0].hash();
+    let block_info = BlockInfo::new(0, 0, HashValue::random(), expected_info_hash, 0, 0, None);
+    let ledger_info = LedgerInfo::new(block_info, HashValue::zero());
+    transaction_output_list_proof
+        .verify(&ledger_info, Some(1))
+        .unwrap_err();
+
+    // Construct a new transaction output list proof where the transaction info and event hashes match
+    let transaction_info_list_proof =
+        create_single_transaction_info_proof(Some(transaction.hash()), Some(event.hash()));
+    let expected_info_hash = transaction_info_list_proof.transaction_infos[0].hash();
+    let transaction_output_list_proof = TransactionAndOutputListWithProof::new(
+        vec![(transaction, transaction_output)],
+        Some(1),
+        transaction_info_list_proof,
+    );
+
+    // Ensure ledger verification now passes
+    let block_info = BlockInfo::new(0, 0, HashValue::random(), expected_info_hash, 0, 0, None);
+    let ledger_info = LedgerInfo::new(block_info, HashValue::zero());
+    transaction_output_list_proof
+        .verify(&ledger_info, Some(1))
+        .unwrap();
+}
+
 fn create_single_transaction_info_proof(
     transaction_hash: Option<HashValue>,
     event_root_hash: Option<HashValue>,
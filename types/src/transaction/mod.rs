@@ -1018,6 +1018,7 @@ pub struct TransactionToCommit {
     transaction: Transaction,
     account_states: HashMap<AccountAddress, AccountStateBlob>,
     jf_node_hashes: Option<HashMap<NibblePath, HashValue>>,
+    write_set: WriteSet,
     events: Vec<ContractEvent>,
     gas_used: u64,
     status: KeptVMStatus,
@@ -1028,6 +1029,7 @@ impl TransactionToCommit {
         transaction: Transaction,
         account_states: HashMap<AccountAddress, AccountStateBlob>,
         jf_node_hashes: Option<HashMap<NibblePath, HashValue>>,
+        write_set: WriteSet,
         events: Vec<ContractEvent>,
         gas_used: u64,
         status: KeptVMStatus,
@@ -1036,6 +1038,7 @@ impl TransactionToCommit {
             transaction,
             account_states,
             jf_node_hashes,
+            write_set,
             events,
             gas_used,
             status,
@@ -1054,6 +1057,10 @@ impl TransactionToCommit {
         self.jf_node_hashes.as_ref()
     }
 
+    pub fn write_set(&self) -> &WriteSet {
+        &self.write_set
+    }
+
     pub fn events(&self) -> &[ContractEvent] {
         &self.events
     }
@@ -1235,6 +1242,108 @@ impl<T: TransactionInfoTrait> TransactionOutputListWithProof<T> {
     }
 }
 
+/// This differs from TransactionOutputListWithProof in that each
+/// TransactionOutput is stored along with its transaction, so the outputs can
+/// be applied (and committed) without executing the transactions. Events are
+/// stored inside each TransactionOutput.
+///
+/// Note: the proof cannot verify the TransactionOutputs themselves. This
+/// requires speculative execution of each TransactionOutput to verify that the
+/// resulting state matches the expected state in the proof (for each version).
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TransactionAndOutputListWithProof<T> {
+    pub transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
+    pub first_transaction_version: Option<Version>,
+    pub proof: TransactionInfoListWithProof<T>,
+}
+
+impl<T: TransactionInfoTrait> TransactionAndOutputListWithProof<T> {
+    pub fn new(
+        transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
+        first_transaction_version: Option<Version>,
+        proof: TransactionInfoListWithProof<T>,
+    ) -> Self {
+        Self {
+            transactions_and_outputs,
+            first_transaction_version,
+            proof,
+        }
+    }
+
+    /// A convenience function to create an empty proof. Mostly used for tests.
+    pub fn new_empty() -> Self {
+        Self::new(vec![], None, TransactionInfoListWithProof::new_empty())
+    }
+
+    /// Verifies the transaction and output list with proof using the given `ledger_info`.
+    /// This method will ensure:
+    /// 1. All transaction infos exist on the given `ledger_info`.
+    /// 2. If `first_transaction_version` is None, the list is empty.
+    ///    Otherwise, the list starts at `first_transaction_version`.
+    /// 3. The transactions match the transaction hashes in the proof.
+    /// 4. Events in each transaction output match the expected event root hashes in the proof.
+    ///
+    /// Note: the proof cannot verify the TransactionOutputs themselves. This
+    /// requires speculative execution of each TransactionOutput to verify that the
+    /// resulting state matches the expected state in the proof (for each version).
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        first_transaction_version: Option<Version>,
+    ) -> Result<()> {
+        // Verify the first transaction versions match
+        ensure!(
+            self.first_transaction_version == first_transaction_version,
+            "First transaction version ({:?}) doesn't match given version ({:?}).",
+            self.first_transaction_version,
+            first_transaction_version,
+        );
+
+        // Verify the lengths of the transaction outputs and transaction infos match
+        ensure!(
+            self.proof.transaction_infos.len() == self.transactions_and_outputs.len(),
+            "The number of TransactionInfo objects ({}) does not match the number of \
+             transaction outputs ({}).",
+            self.proof.transaction_infos.len(),
+            self.transactions_and_outputs.len(),
+        );
+
+        // Verify the transaction hashes match those of the transaction infos
+        itertools::zip_eq(
+            &self.transactions_and_outputs,
+            &self.proof.transaction_infos,
+        )
+        .map(|((txn, _), txn_info)| {
+            let txn_hash = txn.hash();
+            ensure!(
+                txn_hash == txn_info.transaction_hash(),
+                "The hash of transaction does not match the transaction info in proof. \
+                     Transaction hash: {:x}. Transaction hash in txn_info: {:x}.",
+                txn_hash,
+                txn_info.transaction_hash(),
+            );
+            Ok(())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+        // Verify the transaction infos are proven by the ledger info.
+        self.proof
+            .verify(ledger_info, self.first_transaction_version)?;
+
+        // Verify the events
+        itertools::zip_eq(
+            &self.transactions_and_outputs,
+            &self.proof.transaction_infos,
+        )
+        .map(|((_, txn_output), txn_info)| {
+            verify_events_against_root_hash(&txn_output.events, txn_info)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+        Ok(())
+    }
+}
+
 /// Verifies a list of events against an expected event root hash. This is done
 /// by calculating the hash of the events using an event accumulator hasher.
 fn verify_events_against_root_hash<T: TransactionInfoTrait>(
@@ -1399,5 +1508,7 @@ pub mod default_protocol {
     pub type TransactionListWithProof = super::TransactionListWithProof<TransactionInfo>;
     pub type TransactionOutputListWithProof =
         super::TransactionOutputListWithProof<TransactionInfo>;
+    pub type TransactionAndOutputListWithProof =
+        super::TransactionAndOutputListWithProof<TransactionInfo>;
     pub type TransactionWithProof = super::TransactionWithProof<TransactionInfo>;
 }